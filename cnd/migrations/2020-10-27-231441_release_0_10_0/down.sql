@@ -1,5 +1,23 @@
 -- This file should undo anything in `up.sql`
 
+DROP TABLE remote_orders;
+
+DROP TABLE failed_swaps;
+
+CREATE TABLE orders_without_valid_until
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    order_id UNIQUE NOT NULL,
+    position        NOT NULL,
+    created_at      NOT NULL
+);
+
+INSERT INTO orders_without_valid_until (id, order_id, position, created_at)
+SELECT id, order_id, position, created_at FROM orders;
+
+DROP TABLE orders;
+ALTER TABLE orders_without_valid_until RENAME TO orders;
+
 DROP VIEW swap_contexts;
 CREATE VIEW swap_contexts AS
 SELECT local_swap_id as id,
@@ -19,3 +19,32 @@ FROM swaps;
 DROP TABLE halbits;
 
 DROP TABLE shared_swap_ids;
+
+-- Orders can expire
+ALTER TABLE orders ADD COLUMN valid_until BIGINT;
+
+-- Swaps can fail
+CREATE TABLE failed_swaps
+(
+    id INTEGER     NOT NULL PRIMARY KEY,
+    swap_id UNIQUE NOT NULL,
+    failed_on      NOT NULL,
+    FOREIGN KEY (swap_id) REFERENCES swaps (id)
+);
+
+-- Orders of other makers, restored into the orderbook on startup
+CREATE TABLE remote_orders
+(
+    id INTEGER           NOT NULL PRIMARY KEY,
+    order_id UNIQUE      NOT NULL,
+    maker                NOT NULL,
+    position             NOT NULL,
+    quantity             NOT NULL,
+    price                NOT NULL,
+    hbit_side            NOT NULL,
+    hbit_expiry_offset   NOT NULL,
+    herc20_expiry_offset NOT NULL,
+    created_at           NOT NULL,
+    valid_until,
+    last_seen            NOT NULL
+);
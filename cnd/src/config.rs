@@ -4,21 +4,25 @@ mod validation;
 
 use crate::{ethereum, ethereum::ChainId, fs};
 use anyhow::{Context, Result};
-use comit::ledger;
+use comit::{btsieve::PollingPolicy, ledger};
 use conquer_once::Lazy;
 use libp2p::Multiaddr;
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
-use std::{fmt::Debug, path::PathBuf, str::FromStr};
+use std::{fmt::Debug, net::SocketAddr, path::PathBuf, str::FromStr, time::Duration};
 
 pub use self::{
     file::File,
-    settings::{AllowedOrigins, Bitcoin, BitcoinFees, Settings},
+    settings::{
+        AllowedOrigins, Bitcoin, BitcoinFees, Cors, OrderTooSmall, Orders, Reconnect, Settings,
+    },
     validation::validate_connection_to_network,
 };
 
 static BITCOIND_RPC_MAINNET: Lazy<Url> = Lazy::new(|| parse_unchecked("http://localhost:8332"));
 static BITCOIND_RPC_TESTNET: Lazy<Url> = Lazy::new(|| parse_unchecked("http://localhost:18332"));
+static BITCOIND_RPC_TESTNET4: Lazy<Url> = Lazy::new(|| parse_unchecked("http://localhost:48332"));
+static BITCOIND_RPC_SIGNET: Lazy<Url> = Lazy::new(|| parse_unchecked("http://localhost:38332"));
 static BITCOIND_RPC_REGTEST: Lazy<Url> = Lazy::new(|| parse_unchecked("http://localhost:18443"));
 
 static WEB3_URL: Lazy<Url> = Lazy::new(|| parse_unchecked("http://localhost:8545"));
@@ -41,6 +45,12 @@ static DAI_KOVAN: Lazy<ethereum::Address> =
 static DAI_ROPSTEN: Lazy<ethereum::Address> =
     Lazy::new(|| parse_unchecked("0x31F42841c2db5173425b5223809CF3A38FEde360"));
 
+/// The USDC token contract on Ethereum mainnet.
+///
+/// Source: https://www.centre.io/usdc-transparency
+static USDC_MAINNET: Lazy<ethereum::Address> =
+    Lazy::new(|| parse_unchecked("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"));
+
 static COMIT_SOCKET: Lazy<Multiaddr> = Lazy::new(|| parse_unchecked("/ip4/0.0.0.0/tcp/9939"));
 
 // Low value that would allow inclusion in ~6 blocks:
@@ -77,6 +87,12 @@ impl Data {
 #[serde(deny_unknown_fields)]
 pub struct Bitcoind {
     pub node_url: Url,
+    /// Where bitcoind publishes new blocks, as configured with
+    /// `-zmqpubrawblock`. The latest block is polled if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zmq_raw_block: Option<SocketAddr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polling: Option<Polling>,
 }
 
 impl Bitcoind {
@@ -84,10 +100,16 @@ impl Bitcoind {
         let node_url = match network {
             ledger::Bitcoin::Mainnet => BITCOIND_RPC_MAINNET.clone(),
             ledger::Bitcoin::Testnet => BITCOIND_RPC_TESTNET.clone(),
+            ledger::Bitcoin::Testnet4 => BITCOIND_RPC_TESTNET4.clone(),
+            ledger::Bitcoin::Signet => BITCOIND_RPC_SIGNET.clone(),
             ledger::Bitcoin::Regtest => BITCOIND_RPC_REGTEST.clone(),
         };
 
-        Bitcoind { node_url }
+        Bitcoind {
+            node_url,
+            zmq_raw_block: None,
+            polling: None,
+        }
     }
 }
 
@@ -149,6 +171,7 @@ impl From<Tokens> for file::Tokens {
     fn from(tokens: Tokens) -> Self {
         file::Tokens {
             dai: Some(tokens.dai),
+            usdc: tokens.usdc,
         }
     }
 }
@@ -157,12 +180,46 @@ impl From<Tokens> for file::Tokens {
 #[serde(deny_unknown_fields)]
 pub struct Geth {
     pub node_url: Url,
+    /// The most blocks whose logs are requested at once, for nodes that
+    /// refuse large ranges. By default the whole range is requested and only
+    /// narrowed if the node returns too many logs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_block_range: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polling: Option<Polling>,
 }
 
 impl Geth {
     fn new() -> Self {
         Self {
             node_url: WEB3_URL.clone(),
+            max_block_range: None,
+            polling: None,
+        }
+    }
+}
+
+/// How often a node is polled for new blocks, overriding the default for the
+/// network it is connected to.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Polling {
+    pub interval_secs: u64,
+    /// The longest interval polling backs off to while no new block arrives,
+    /// eight times `interval_secs` if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_interval_secs: Option<u64>,
+}
+
+impl From<Polling> for PollingPolicy {
+    fn from(polling: Polling) -> Self {
+        let default = PollingPolicy::new(Duration::from_secs(polling.interval_secs));
+
+        PollingPolicy {
+            max: polling
+                .max_interval_secs
+                .map_or(default.max, Duration::from_secs),
+            ..default
         }
     }
 }
@@ -170,22 +227,48 @@ impl Geth {
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Tokens {
     pub dai: ethereum::Address,
+    pub usdc: Option<ethereum::Address>,
 }
 
 impl Tokens {
     fn new(chain_id: ChainId) -> Result<Self> {
         let dai = dai_address_from_chain_id(chain_id)?;
+        let usdc = usdc_address_from_chain_id(chain_id);
 
-        Ok(Self { dai })
+        Ok(Self { dai, usdc })
     }
 
     fn from_file(file: file::Tokens, id: ChainId) -> Result<Self> {
         let dai = file.dai.map_or_else(|| dai_address_from_chain_id(id), Ok)?;
+        let usdc = file.usdc.or_else(|| usdc_address_from_chain_id(id));
+
+        Ok(Self { dai, usdc })
+    }
+
+    /// All tokens cnd knows the symbol and decimals of.
+    pub fn list(&self) -> Vec<Token> {
+        let dai = Token {
+            symbol: "DAI",
+            address: self.dai,
+            decimals: 18,
+        };
+        let usdc = self.usdc.map(|address| Token {
+            symbol: "USDC",
+            address,
+            decimals: 6,
+        });
 
-        Ok(Self { dai })
+        std::iter::once(dai).chain(usdc).collect()
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Token {
+    pub symbol: &'static str,
+    pub address: ethereum::Address,
+    pub decimals: u8,
+}
+
 fn dai_address_from_chain_id(id: ChainId) -> Result<ethereum::Address> {
     Ok(match id {
         ChainId::MAINNET => *DAI_MAINNET,
@@ -198,6 +281,13 @@ fn dai_address_from_chain_id(id: ChainId) -> Result<ethereum::Address> {
     })
 }
 
+fn usdc_address_from_chain_id(id: ChainId) -> Option<ethereum::Address> {
+    match id {
+        ChainId::MAINNET => Some(*USDC_MAINNET),
+        _ => None,
+    }
+}
+
 fn parse_unchecked<T>(str: &'static str) -> T
 where
     T: FromStr + Debug,
@@ -2,8 +2,9 @@ use crate::{
     config::{settings, Bitcoind, Data, Geth, Settings},
     ethereum,
     ethereum::ChainId,
+    trace::LogFormat,
 };
-use comit::ledger;
+use comit::{asset::Erc20Quantity, ledger};
 use libp2p::core::Multiaddr;
 use log::LevelFilter;
 use reqwest::Url;
@@ -24,6 +25,9 @@ pub struct File {
     pub logging: Option<Logging>,
     pub bitcoin: Option<Bitcoin>,
     pub ethereum: Option<Ethereum>,
+    pub expiries: Option<Expiries>,
+    pub swaps: Option<Swaps>,
+    pub orders: Option<Orders>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -31,6 +35,14 @@ pub struct File {
 pub struct Network {
     pub listen: Vec<Multiaddr>,
     pub peer_addresses: Option<Vec<Multiaddr>>,
+    pub reconnect: Option<Reconnect>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Reconnect {
+    pub initial_backoff_secs: Option<u64>,
+    pub max_backoff_secs: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -83,6 +95,8 @@ pub struct Ethereum {
 #[serde(deny_unknown_fields)]
 pub struct Tokens {
     pub dai: Option<ethereum::Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usdc: Option<ethereum::Address>,
 }
 
 impl File {
@@ -94,6 +108,9 @@ impl File {
             logging: Option::None,
             bitcoin: Option::None,
             ethereum: Option::None,
+            expiries: Option::None,
+            swaps: Option::None,
+            orders: Option::None,
         }
     }
 
@@ -109,10 +126,58 @@ impl File {
     }
 }
 
+/// The confirmation targets the expiry offsets of orders are derived from.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Expiries {
+    pub bitcoin_confirmation_target: Option<u8>,
+    pub ethereum_confirmation_target: Option<u8>,
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Swaps {
+    pub max_communication_wait_secs: Option<u64>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Orders {
+    pub min_quantity_sats: Option<u64>,
+    /// The minimum value of an order in the smallest unit of DAI.
+    pub min_value_wei: Option<Erc20Quantity>,
+}
+
 #[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Logging {
     pub level: Option<Level>,
+    pub format: Option<Format>,
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Pretty,
+    Json,
+}
+
+impl From<LogFormat> for Format {
+    fn from(format: LogFormat) -> Self {
+        match format {
+            LogFormat::Pretty => Format::Pretty,
+            LogFormat::Json => Format::Json,
+        }
+    }
+}
+
+impl From<Format> for LogFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Pretty => LogFormat::Pretty,
+            Format::Json => LogFormat::Json,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
@@ -154,6 +219,7 @@ impl From<Level> for LevelFilter {
 #[serde(deny_unknown_fields)]
 pub struct HttpApi {
     pub socket: SocketAddr,
+    pub auth_token: Option<String>,
     pub cors: Option<Cors>,
 }
 
@@ -161,6 +227,8 @@ pub struct HttpApi {
 #[serde(deny_unknown_fields)]
 pub struct Cors {
     pub allowed_origins: AllowedOrigins,
+    pub allowed_methods: Option<Vec<String>>,
+    pub allowed_headers: Option<Vec<String>>,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
@@ -187,17 +255,29 @@ impl From<Settings> for File {
     fn from(settings: Settings) -> Self {
         let Settings {
             network,
-            http_api: settings::HttpApi { socket, cors },
+            http_api:
+                settings::HttpApi {
+                    socket,
+                    cors,
+                    auth_token,
+                },
             data,
-            logging: settings::Logging { level },
+            logging: settings::Logging { level, format },
             bitcoin,
             ethereum,
+            expiries,
+            swaps,
+            orders,
         } = settings;
 
         File {
             network: Some(Network {
                 listen: network.listen,
                 peer_addresses: Some(network.peer_addresses),
+                reconnect: Some(Reconnect {
+                    initial_backoff_secs: Some(network.reconnect.initial_backoff.as_secs()),
+                    max_backoff_secs: Some(network.reconnect.max_backoff.as_secs()),
+                }),
             }),
             http_api: Some(HttpApi {
                 socket,
@@ -207,14 +287,39 @@ impl From<Settings> for File {
                         settings::AllowedOrigins::None => AllowedOrigins::None(None::None),
                         settings::AllowedOrigins::Some(origins) => AllowedOrigins::Some(origins),
                     },
+                    allowed_methods: Some(
+                        cors.allowed_methods
+                            .iter()
+                            .map(|method| method.as_str().to_owned())
+                            .collect(),
+                    ),
+                    allowed_headers: Some(
+                        cors.allowed_headers
+                            .iter()
+                            .map(|header| header.as_str().to_owned())
+                            .collect(),
+                    ),
                 }),
+                auth_token,
             }),
             data: Some(data),
             logging: Some(Logging {
                 level: Some(level.into()),
+                format: Some(format.into()),
             }),
             bitcoin: Some(bitcoin.into()),
             ethereum: Some(ethereum.into()),
+            expiries: Some(Expiries {
+                bitcoin_confirmation_target: Some(expiries.bitcoin_confirmation_target),
+                ethereum_confirmation_target: Some(expiries.ethereum_confirmation_target),
+            }),
+            swaps: Some(Swaps {
+                max_communication_wait_secs: Some(swaps.max_communication_wait.as_secs()),
+            }),
+            orders: Some(Orders {
+                min_quantity_sats: orders.min_quantity.map(|quantity| quantity.as_sat()),
+                min_value_wei: orders.min_value,
+            }),
         }
     }
 }
@@ -251,7 +356,8 @@ impl From<settings::BitcoinFees> for BitcoinFees {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{Bitcoind, Geth, Settings};
+    use crate::config::{Bitcoind, Geth, Polling, Settings};
+    use comit::asset::ethereum::FromWei;
     use reqwest::Url;
     use spectral::prelude::*;
     use std::{
@@ -284,6 +390,7 @@ mod tests {
             Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
                 peer_addresses: Some(vec!["/ip4/1.1.1.1/tcp/9939".parse().unwrap()]),
+                reconnect: None,
             },
             Network {
                 listen: (vec![
@@ -294,6 +401,7 @@ mod tests {
                     "/ip4/1.1.1.1/tcp/9939".parse().unwrap(),
                     "/ip4/2.2.2.2/tcp/3456".parse().unwrap(),
                 ]),
+                reconnect: None,
             },
         ];
 
@@ -318,20 +426,39 @@ mod tests {
             r#"
              allowed_origins = ["http://localhost:8000", "https://192.168.1.55:3000"]
             "#,
+            r#"
+             allowed_origins = ["http://localhost:8000"]
+             allowed_methods = ["GET"]
+             allowed_headers = ["content-type", "authorization"]
+            "#,
         ];
 
         let expected = vec![
             Cors {
                 allowed_origins: AllowedOrigins::All(All::All),
+                allowed_methods: None,
+                allowed_headers: None,
             },
             Cors {
                 allowed_origins: AllowedOrigins::None(None::None),
+                allowed_methods: None,
+                allowed_headers: None,
             },
             Cors {
                 allowed_origins: AllowedOrigins::Some(vec![
                     String::from("http://localhost:8000"),
                     String::from("https://192.168.1.55:3000"),
                 ]),
+                allowed_methods: None,
+                allowed_headers: None,
+            },
+            Cors {
+                allowed_origins: AllowedOrigins::Some(vec![String::from("http://localhost:8000")]),
+                allowed_methods: Some(vec![String::from("GET")]),
+                allowed_headers: Some(vec![
+                    String::from("content-type"),
+                    String::from("authorization"),
+                ]),
             },
         ];
 
@@ -350,8 +477,12 @@ mod tests {
 [network]
 listen = ["/ip4/0.0.0.0/tcp/9939"]
 
+[network.reconnect]
+max_backoff_secs = 60
+
 [http_api]
 socket = "127.0.0.1:8000"
+auth_token = "s3cr3t"
 
 [http_api.cors]
 allowed_origins = "all"
@@ -361,6 +492,7 @@ dir = "/tmp/comit/"
 
 [logging]
 level = "debug"
+format = "json"
 
 [bitcoin]
 network = "regtest"
@@ -382,28 +514,48 @@ node_url = "http://localhost:8545/"
 
 [ethereum.tokens]
 dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
+
+[expiries]
+bitcoin_confirmation_target = 3
+
+[swaps]
+max_communication_wait_secs = 600
+
+[orders]
+min_quantity_sats = 10000
+min_value_wei = "1000000000000000000"
 "#;
         let file = File {
             network: Some(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
                 peer_addresses: None,
+                reconnect: Some(Reconnect {
+                    initial_backoff_secs: None,
+                    max_backoff_secs: Some(60),
+                }),
             }),
             http_api: Some(HttpApi {
                 socket: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8000),
                 cors: Some(Cors {
                     allowed_origins: AllowedOrigins::All(All::All),
+                    allowed_methods: None,
+                    allowed_headers: None,
                 }),
+                auth_token: Some("s3cr3t".to_owned()),
             }),
             data: Some(Data {
                 dir: PathBuf::from("/tmp/comit/"),
             }),
             logging: Some(Logging {
                 level: Some(Level::Debug),
+                format: Some(Format::Json),
             }),
             bitcoin: Some(Bitcoin {
                 network: ledger::Bitcoin::Regtest,
                 bitcoind: Some(Bitcoind {
                     node_url: "http://localhost:18443".parse().unwrap(),
+                    zmq_raw_block: None,
+                    polling: None,
                 }),
                 fees: Some(BitcoinFees {
                     strategy: BitcoinFeesStrategy::Static,
@@ -417,6 +569,8 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                 chain_id: ChainId::GETH_DEV,
                 geth: Some(Geth {
                     node_url: "http://localhost:8545".parse().unwrap(),
+                    max_block_range: None,
+                    polling: None,
                 }),
                 tokens: Some(Tokens {
                     dai: Some(
@@ -424,8 +578,20 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                             .parse()
                             .unwrap(),
                     ),
+                    usdc: None,
                 }),
             }),
+            expiries: Some(Expiries {
+                bitcoin_confirmation_target: Some(3),
+                ethereum_confirmation_target: None,
+            }),
+            swaps: Some(Swaps {
+                max_communication_wait_secs: Some(600),
+            }),
+            orders: Some(Orders {
+                min_quantity_sats: Some(10_000),
+                min_value_wei: Some(Erc20Quantity::from_wei(1_000_000_000_000_000_000u64)),
+            }),
         };
 
         let config = toml::from_str::<File>(contents);
@@ -475,6 +641,9 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
             network = "regtest"
             [bitcoind]
             node_url = "http://example.com:18443"
+            zmq_raw_block = "127.0.0.1:28332"
+            [bitcoind.polling]
+            interval_secs = 5
             "#,
         ];
 
@@ -483,6 +652,8 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                 network: ledger::Bitcoin::Mainnet,
                 bitcoind: Some(Bitcoind {
                     node_url: Url::parse("http://example.com:8332").unwrap(),
+                    zmq_raw_block: None,
+                    polling: None,
                 }),
                 fees: Some(BitcoinFees {
                     strategy: BitcoinFeesStrategy::Static,
@@ -496,6 +667,8 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                 network: ledger::Bitcoin::Testnet,
                 bitcoind: Some(Bitcoind {
                     node_url: Url::parse("http://example.com:18332").unwrap(),
+                    zmq_raw_block: None,
+                    polling: None,
                 }),
                 fees: Some(BitcoinFees {
                     strategy: BitcoinFeesStrategy::CypherBlock,
@@ -509,6 +682,11 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                 network: ledger::Bitcoin::Regtest,
                 bitcoind: Some(Bitcoind {
                     node_url: Url::parse("http://example.com:18443").unwrap(),
+                    zmq_raw_block: Some("127.0.0.1:28332".parse().unwrap()),
+                    polling: Some(Polling {
+                        interval_secs: 5,
+                        max_interval_secs: None,
+                    }),
                 }),
                 fees: None,
             },
@@ -544,8 +722,13 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
             chain_id = 1
             [geth]
             node_url = "http://example.com:8545"
+            max_block_range = 5000
+            [geth.polling]
+            interval_secs = 15
+            max_interval_secs = 60
             [tokens]
             dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
+            usdc = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
             "#,
         ];
 
@@ -554,6 +737,8 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                 chain_id: ChainId::KOVAN,
                 geth: Some(Geth {
                     node_url: Url::parse("http://example.com:8545").unwrap(),
+                    max_block_range: None,
+                    polling: None,
                 }),
                 tokens: Some(Tokens {
                     dai: Some(
@@ -561,12 +746,15 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                             .parse()
                             .unwrap(),
                     ),
+                    usdc: None,
                 }),
             },
             Ethereum {
                 chain_id: ChainId::ROPSTEN,
                 geth: Some(Geth {
                     node_url: Url::parse("http://example.com:8545").unwrap(),
+                    max_block_range: None,
+                    polling: None,
                 }),
                 tokens: Some(Tokens {
                     dai: Some(
@@ -574,12 +762,18 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                             .parse()
                             .unwrap(),
                     ),
+                    usdc: None,
                 }),
             },
             Ethereum {
                 chain_id: ChainId::MAINNET,
                 geth: Some(Geth {
                     node_url: Url::parse("http://example.com:8545").unwrap(),
+                    max_block_range: Some(5000),
+                    polling: Some(Polling {
+                        interval_secs: 15,
+                        max_interval_secs: Some(60),
+                    }),
                 }),
                 tokens: Some(Tokens {
                     dai: Some(
@@ -587,6 +781,11 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                             .parse()
                             .unwrap(),
                     ),
+                    usdc: Some(
+                        "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+                            .parse()
+                            .unwrap(),
+                    ),
                 }),
             },
         ];
@@ -1,12 +1,25 @@
-use crate::config::{
-    file, Bitcoind, Data, Ethereum, File, COMIT_SOCKET, CYPHERBLOCK_MAINNET_URL,
-    CYPHERBLOCK_TESTNET_URL, FEERATE_SAT_PER_VBYTE,
+use crate::{
+    config::{
+        file, Bitcoind, Data, Ethereum, File, COMIT_SOCKET, CYPHERBLOCK_MAINNET_URL,
+        CYPHERBLOCK_TESTNET_URL, FEERATE_SAT_PER_VBYTE,
+    },
+    trace::LogFormat,
+};
+use anyhow::{Context, Result};
+use comit::{
+    asset::{self, Erc20Quantity},
+    expiries::ExpiryConfig,
+    ledger,
+    orderpool::MinimumMatch,
+    BtcDaiOrder,
 };
-use anyhow::Result;
-use comit::ledger;
 use libp2p::core::Multiaddr;
 use log::LevelFilter;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+use warp::http::{header, header::HeaderName, Method};
 
 /// This structs represents the settings as they are used through out the code.
 ///
@@ -22,12 +35,16 @@ pub struct Settings {
     pub logging: Logging,
     pub bitcoin: Bitcoin,
     pub ethereum: Ethereum,
+    pub expiries: ExpiryConfig,
+    pub swaps: Swaps,
+    pub orders: Orders,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Network {
     pub listen: Vec<Multiaddr>,
     pub peer_addresses: Vec<Multiaddr>,
+    pub reconnect: Reconnect,
 }
 
 impl Default for Network {
@@ -35,6 +52,7 @@ impl Default for Network {
         Self {
             listen: vec![COMIT_SOCKET.clone()],
             peer_addresses: vec![],
+            reconnect: Reconnect::default(),
         }
     }
 }
@@ -43,18 +61,153 @@ impl From<file::Network> for Network {
     fn from(network: file::Network) -> Self {
         let listen = network.listen;
         let peer_addresses = network.peer_addresses.unwrap_or_default();
+        let reconnect = network.reconnect.map(Reconnect::from).unwrap_or_default();
 
         Self {
             listen,
             peer_addresses,
+            reconnect,
         }
     }
 }
 
+/// How we redial makers we lost the connection to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reconnect {
+    /// How long we wait before the second attempt, every further attempt
+    /// waits twice as long as the previous one.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for Reconnect {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl From<file::Reconnect> for Reconnect {
+    fn from(reconnect: file::Reconnect) -> Self {
+        let default = Reconnect::default();
+
+        Reconnect {
+            initial_backoff: reconnect
+                .initial_backoff_secs
+                .map_or(default.initial_backoff, Duration::from_secs),
+            max_backoff: reconnect
+                .max_backoff_secs
+                .map_or(default.max_backoff, Duration::from_secs),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Swaps {
+    /// How long we wait for a swap to be funded after it was set up before we
+    /// abandon it.
+    pub max_communication_wait: Duration,
+}
+
+impl Default for Swaps {
+    fn default() -> Self {
+        Self {
+            max_communication_wait: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl From<file::Swaps> for Swaps {
+    fn from(swaps: file::Swaps) -> Self {
+        let default = Swaps::default();
+
+        Swaps {
+            max_communication_wait: swaps
+                .max_communication_wait_secs
+                .map_or(default.max_communication_wait, Duration::from_secs),
+        }
+    }
+}
+
+/// The smallest orders we make and show in the market.
+///
+/// Orders that are smaller are not worth the fees of the swap.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Orders {
+    pub min_quantity: Option<asset::Bitcoin>,
+    pub min_value: Option<Erc20Quantity>,
+}
+
+impl Orders {
+    pub fn ensure_big_enough(&self, order: &BtcDaiOrder) -> Result<(), OrderTooSmall> {
+        let quantity = order.quantity.to_inner();
+        if let Some(min_quantity) = self.min_quantity {
+            if quantity < min_quantity {
+                return Err(OrderTooSmall::Quantity {
+                    quantity,
+                    min_quantity,
+                });
+            }
+        }
+
+        if let Some(min_value) = &self.min_value {
+            let value = order.quote();
+            if &value < min_value {
+                return Err(OrderTooSmall::Value {
+                    value,
+                    min_value: min_value.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_big_enough(&self, order: &BtcDaiOrder) -> bool {
+        self.ensure_big_enough(order).is_ok()
+    }
+}
+
+impl From<Orders> for MinimumMatch {
+    fn from(orders: Orders) -> Self {
+        MinimumMatch {
+            quantity: orders.min_quantity,
+            value: orders.min_value,
+        }
+    }
+}
+
+impl From<file::Orders> for Orders {
+    fn from(orders: file::Orders) -> Self {
+        Orders {
+            min_quantity: orders.min_quantity_sats.map(asset::Bitcoin::from_sat),
+            min_value: orders.min_value_wei,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum OrderTooSmall {
+    #[error("order quantity {quantity} is less than the minimum of {min_quantity}")]
+    Quantity {
+        quantity: asset::Bitcoin,
+        min_quantity: asset::Bitcoin,
+    },
+    #[error("order value of {value} wei is less than the minimum of {min_value} wei")]
+    Value {
+        value: Erc20Quantity,
+        min_value: Erc20Quantity,
+    },
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct HttpApi {
     pub socket: SocketAddr,
     pub cors: Cors,
+    /// The bearer token requests that change state need to present, if set.
+    pub auth_token: Option<String>,
 }
 
 impl Default for HttpApi {
@@ -62,41 +215,82 @@ impl Default for HttpApi {
         Self {
             socket: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8000),
             cors: Cors::default(),
+            auth_token: None,
         }
     }
 }
 
-impl From<file::HttpApi> for HttpApi {
-    fn from(http_api: file::HttpApi) -> Self {
+impl HttpApi {
+    fn from_file(http_api: file::HttpApi) -> Result<Self> {
         let socket = http_api.socket;
-        let cors = http_api.cors.map_or_else(Cors::default, Cors::from);
-
-        HttpApi { socket, cors }
+        let cors = http_api
+            .cors
+            .map_or_else(|| Ok(Cors::default()), Cors::from_file)?;
+
+        Ok(HttpApi {
+            socket,
+            cors,
+            auth_token: http_api.auth_token,
+        })
     }
 }
 
+/// Which browser-based clients may call the HTTP API.
+///
+/// By default, no origin is allowed, i.e. only clients served from the same
+/// origin as the API can call it.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Cors {
     pub allowed_origins: AllowedOrigins,
+    pub allowed_methods: Vec<Method>,
+    pub allowed_headers: Vec<HeaderName>,
 }
 
 impl Default for Cors {
     fn default() -> Self {
         Self {
             allowed_origins: AllowedOrigins::None,
+            allowed_methods: vec![Method::GET, Method::POST, Method::DELETE],
+            allowed_headers: vec![header::CONTENT_TYPE, header::AUTHORIZATION],
         }
     }
 }
 
-impl From<file::Cors> for Cors {
-    fn from(cors: file::Cors) -> Self {
+impl Cors {
+    fn from_file(cors: file::Cors) -> Result<Self> {
+        let default = Cors::default();
+
         let allowed_origins = match cors.allowed_origins {
             file::AllowedOrigins::All(_) => AllowedOrigins::All,
             file::AllowedOrigins::None(_) => AllowedOrigins::None,
             file::AllowedOrigins::Some(origins) => AllowedOrigins::Some(origins),
         };
+        let allowed_methods = match cors.allowed_methods {
+            Some(methods) => methods
+                .iter()
+                .map(|method| {
+                    Method::from_bytes(method.as_bytes())
+                        .with_context(|| format!("invalid HTTP method {}", method))
+                })
+                .collect::<Result<_>>()?,
+            None => default.allowed_methods,
+        };
+        let allowed_headers = match cors.allowed_headers {
+            Some(headers) => headers
+                .iter()
+                .map(|header| {
+                    HeaderName::from_bytes(header.as_bytes())
+                        .with_context(|| format!("invalid HTTP header {}", header))
+                })
+                .collect::<Result<_>>()?,
+            None => default.allowed_headers,
+        };
 
-        Cors { allowed_origins }
+        Ok(Cors {
+            allowed_origins,
+            allowed_methods,
+            allowed_headers,
+        })
     }
 }
 
@@ -112,15 +306,17 @@ pub enum AllowedOrigins {
 pub struct Logging {
     #[derivative(Default(value = "LevelFilter::Info"))]
     pub level: LevelFilter,
+    #[derivative(Default(value = "LogFormat::Pretty"))]
+    pub format: LogFormat,
 }
 
 impl From<file::Logging> for Logging {
     fn from(logging: file::Logging) -> Self {
-        match logging {
-            file::Logging { level: None } => Logging::default(),
-            file::Logging { level: Some(level) } => Logging {
-                level: level.into(),
-            },
+        let default = Logging::default();
+
+        Logging {
+            level: logging.level.map_or(default.level, LevelFilter::from),
+            format: logging.format.map_or(default.format, LogFormat::from),
         }
     }
 }
@@ -147,7 +343,7 @@ impl Bitcoin {
     ) -> Result<Self> {
         if let Some(comit_network) = comit_network {
             let inferred = ledger::Bitcoin::from(comit_network);
-            if inferred != bitcoin.network {
+            if comit::Network::from(bitcoin.network) != comit_network {
                 anyhow::bail!(
                     "inferred Bitcoin network {} from CLI argument {} but config file says {}",
                     inferred,
@@ -185,7 +381,8 @@ impl BitcoinFees {
         match network {
             Mainnet => BitcoinFees::CypherBlock(CYPHERBLOCK_MAINNET_URL.clone()),
             Testnet => BitcoinFees::CypherBlock(CYPHERBLOCK_TESTNET_URL.clone()),
-            Regtest => BitcoinFees::StaticSatPerVbyte(*FEERATE_SAT_PER_VBYTE),
+            // cypherblock does not serve fee estimates for these networks
+            Testnet4 | Signet | Regtest => BitcoinFees::StaticSatPerVbyte(*FEERATE_SAT_PER_VBYTE),
         }
     }
 
@@ -215,6 +412,37 @@ impl BitcoinFees {
     }
 }
 
+/// Fills in the confirmation targets absent from the config file with the
+/// defaults of the network.
+fn expiries_from_file(
+    expiries: Option<file::Expiries>,
+    comit_network: Option<comit::Network>,
+) -> Result<ExpiryConfig> {
+    let default = ExpiryConfig::from_network(comit_network.unwrap_or_default());
+    let file::Expiries {
+        bitcoin_confirmation_target,
+        ethereum_confirmation_target,
+    } = match expiries {
+        Some(expiries) => expiries,
+        None => return Ok(default),
+    };
+
+    let expiry_config = ExpiryConfig {
+        bitcoin_confirmation_target: bitcoin_confirmation_target
+            .unwrap_or(default.bitcoin_confirmation_target),
+        ethereum_confirmation_target: ethereum_confirmation_target
+            .unwrap_or(default.ethereum_confirmation_target),
+    };
+
+    if expiry_config.bitcoin_confirmation_target == 0
+        || expiry_config.ethereum_confirmation_target == 0
+    {
+        anyhow::bail!("expiries confirmation targets must be at least 1")
+    }
+
+    Ok(expiry_config)
+}
+
 impl Settings {
     pub fn from_config_file_and_defaults(
         config_file: File,
@@ -227,11 +455,14 @@ impl Settings {
             logging,
             bitcoin,
             ethereum,
+            expiries,
+            swaps,
+            orders,
         } = config_file;
 
         Ok(Self {
             network: network.map_or_else(Network::default, Network::from),
-            http_api: http_api.map_or_else(HttpApi::default, HttpApi::from),
+            http_api: http_api.map_or_else(|| Ok(HttpApi::default()), HttpApi::from_file)?,
             data: data.map_or_else(Data::default, Ok)?,
             logging: logging.map_or_else(Logging::default, Logging::from),
 
@@ -247,6 +478,9 @@ impl Settings {
                 || Ethereum::new(comit_network.unwrap_or_default().into()),
                 |file| Ethereum::from_file(file, comit_network),
             )?,
+            expiries: expiries_from_file(expiries, comit_network)?,
+            swaps: swaps.map_or_else(Swaps::default, Swaps::from),
+            orders: orders.map_or_else(Orders::default, Orders::from),
         })
     }
 }
@@ -255,7 +489,7 @@ impl Settings {
 mod tests {
     use super::*;
     use crate::{
-        config::{file, Bitcoind, Geth, Tokens, DAI_MAINNET},
+        config::{file, Bitcoind, Geth, Tokens, DAI_MAINNET, USDC_MAINNET},
         ethereum::ChainId,
     };
     use comit::ledger;
@@ -276,6 +510,28 @@ mod tests {
             .map(|settings| &settings.logging)
             .is_equal_to(Logging {
                 level: LevelFilter::Info,
+                format: LogFormat::Pretty,
+            })
+    }
+
+    #[test]
+    fn logging_format_can_be_set_to_json() {
+        let config_file = File {
+            logging: Some(file::Logging {
+                level: None,
+                format: Some(file::Format::Json),
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file, None);
+
+        assert_that(&settings)
+            .is_ok()
+            .map(|settings| &settings.logging)
+            .is_equal_to(Logging {
+                level: LevelFilter::Info,
+                format: LogFormat::Json,
             })
     }
 
@@ -285,6 +541,7 @@ mod tests {
             http_api: Some(file::HttpApi {
                 socket: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8000),
                 cors: None,
+                auth_token: None,
             }),
             ..File::default()
         };
@@ -296,9 +553,31 @@ mod tests {
             .map(|settings| &settings.http_api.cors)
             .is_equal_to(Cors {
                 allowed_origins: AllowedOrigins::None,
+                allowed_methods: vec![Method::GET, Method::POST, Method::DELETE],
+                allowed_headers: vec![header::CONTENT_TYPE, header::AUTHORIZATION],
             })
     }
 
+    #[test]
+    fn cors_with_invalid_method_is_rejected() {
+        let config_file = File {
+            http_api: Some(file::HttpApi {
+                socket: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8000),
+                cors: Some(file::Cors {
+                    allowed_origins: file::AllowedOrigins::All(file::All::All),
+                    allowed_methods: Some(vec!["GET POST".to_owned()]),
+                    allowed_headers: None,
+                }),
+                auth_token: None,
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file, None);
+
+        assert_that(&settings).is_err();
+    }
+
     #[test]
     fn http_api_section_defaults() {
         let config_file = File {
@@ -315,7 +594,10 @@ mod tests {
                 socket: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 8000),
                 cors: Cors {
                     allowed_origins: AllowedOrigins::None,
+                    allowed_methods: vec![Method::GET, Method::POST, Method::DELETE],
+                    allowed_headers: vec![header::CONTENT_TYPE, header::AUTHORIZATION],
                 },
+                auth_token: None,
             })
     }
 
@@ -334,6 +616,7 @@ mod tests {
             .is_equal_to(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
                 peer_addresses: vec![],
+                reconnect: Reconnect::default(),
             })
     }
 
@@ -350,6 +633,8 @@ mod tests {
                 network: ledger::Bitcoin::Mainnet,
                 bitcoind: Bitcoind {
                     node_url: "http://localhost:8332".parse().unwrap(),
+                    zmq_raw_block: None,
+                    polling: None,
                 },
                 fees: BitcoinFees::CypherBlock(CYPHERBLOCK_MAINNET_URL.clone()),
             })
@@ -396,6 +681,8 @@ mod tests {
                     network,
                     bitcoind: Bitcoind {
                         node_url: url.parse().unwrap(),
+                        zmq_raw_block: None,
+                        polling: None,
                     },
                     fees,
                 })
@@ -415,11 +702,65 @@ mod tests {
                 chain_id: ChainId::MAINNET,
                 geth: Geth {
                     node_url: "http://localhost:8545".parse().unwrap(),
+                    max_block_range: None,
+                    polling: None,
                 },
-                tokens: Tokens { dai: *DAI_MAINNET },
+                tokens: Tokens {
+                    dai: *DAI_MAINNET,
+                    usdc: Some(*USDC_MAINNET),
+                },
+            })
+    }
+
+    #[test]
+    fn expiries_default_to_the_confirmation_targets_of_the_network() {
+        let config_file = File { ..File::default() };
+
+        let settings =
+            Settings::from_config_file_and_defaults(config_file, Some(comit::Network::Test));
+
+        assert_that(&settings)
+            .is_ok()
+            .map(|settings| &settings.expiries)
+            .is_equal_to(ExpiryConfig::from_network(comit::Network::Test))
+    }
+
+    #[test]
+    fn expiries_take_the_configured_confirmation_targets() {
+        let config_file = File {
+            expiries: Some(file::Expiries {
+                bitcoin_confirmation_target: Some(2),
+                ethereum_confirmation_target: None,
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file, None);
+
+        assert_that(&settings)
+            .is_ok()
+            .map(|settings| &settings.expiries)
+            .is_equal_to(ExpiryConfig {
+                bitcoin_confirmation_target: 2,
+                ..ExpiryConfig::from_network(comit::Network::Main)
             })
     }
 
+    #[test]
+    fn given_zero_confirmation_target_then_error() {
+        let config_file = File {
+            expiries: Some(file::Expiries {
+                bitcoin_confirmation_target: None,
+                ethereum_confirmation_target: Some(0),
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file, None);
+
+        assert_that(&settings).is_err();
+    }
+
     #[test]
     fn given_network_on_cli_when_config_disagrees_then_error() {
         let comit_network = comit::Network::Main;
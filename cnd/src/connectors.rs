@@ -3,7 +3,7 @@ use crate::{
     btsieve::{
         bitcoin::BitcoindConnector,
         ethereum::{GetLogs, TransactionByHash, Web3Connector},
-        ConnectedNetwork, LatestBlock,
+        ConnectedNetwork, LatestBlock, MempoolTransactions,
     },
     ethereum,
 };
@@ -40,7 +40,8 @@ impl Connectors {
     ) -> Arc<
         impl LatestBlock<Block = bitcoin::Block>
             + BlockByHash<Block = bitcoin::Block, BlockHash = bitcoin::BlockHash>
-            + ConnectedNetwork<Network = ledger::Bitcoin>,
+            + ConnectedNetwork<Network = ledger::Bitcoin>
+            + MempoolTransactions<Transaction = bitcoin::Transaction>,
     > {
         self.bitcoin.clone()
     }
@@ -57,8 +58,35 @@ impl Connectors {
             + ReceiptByHash
             + TransactionByHash
             + ConnectedNetwork<Network = ethereum::ChainId>
-            + GetLogs,
+            + GetLogs
+            + MempoolTransactions<Transaction = ethereum::Transaction>,
     > {
         self.ethereum.clone()
     }
+
+    /// The Bitcoin node behind the cache, for probing whether it responds.
+    pub fn bitcoin_node(
+        &self,
+    ) -> &(impl LatestBlock<Block = bitcoin::Block> + ConnectedNetwork<Network = ledger::Bitcoin>)
+    {
+        &self.bitcoin.connector
+    }
+
+    /// The Ethereum node behind the cache, for probing whether it responds.
+    pub fn ethereum_node(
+        &self,
+    ) -> &(impl LatestBlock<Block = ethereum::Block> + ConnectedNetwork<Network = ethereum::ChainId>)
+    {
+        &self.ethereum.connector
+    }
+
+    /// Puts the blocks bitcoind publishes into the block cache until the
+    /// subscription ends.
+    pub async fn follow_bitcoin_blocks(&self) -> anyhow::Result<()> {
+        self.bitcoin.follow_subscription().await
+    }
+
+    pub async fn bitcoin_block_cache_stats(&self) -> btsieve::bitcoin::CacheStats {
+        self.bitcoin.stats().await
+    }
 }
@@ -0,0 +1,157 @@
+use crate::{
+    network::Swarm,
+    storage::{
+        commands,
+        commands::{delete_remote_orders_not_seen_since, NotOpen},
+        queries::all_open_btc_dai_orders,
+        Sqlite, Storage,
+    },
+};
+use anyhow::Result;
+use comit::{clock::Clock, OrderId};
+use std::time::Duration;
+use time::OffsetDateTime;
+
+/// How often we check for orders that are no longer valid.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long we keep the orders of another maker that we no longer hear from.
+///
+/// Makers we are connected to are asked for their orders every few seconds,
+/// this mostly applies to orders restored after a restart.
+pub const REMOTE_ORDERS_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Cancels our open orders once their `valid_until` passed according to
+/// `clock` and forgets the orders of makers not seen within
+/// `REMOTE_ORDERS_TTL`, so that they disappear from the market.
+pub async fn expire_orders<C>(storage: Storage, swarm: Swarm, clock: C)
+where
+    C: Clock,
+{
+    loop {
+        let now = clock.now();
+
+        match cancel_expired_orders(&storage.db, now).await {
+            Ok(expired) => {
+                for order_id in expired {
+                    tracing::info!("order {} expired", order_id);
+                    swarm.cancel_order(order_id).await;
+                }
+            }
+            Err(e) => tracing::warn!("failed to cancel expired orders: {:#}", e),
+        }
+
+        let cutoff = now - REMOTE_ORDERS_TTL;
+        for maker in swarm.remove_orders_not_seen_since(cutoff).await {
+            tracing::info!("forgot the orders of {}, not seen for too long", maker);
+        }
+        if let Err(e) = storage
+            .db
+            .do_in_transaction(|conn| delete_remote_orders_not_seen_since(conn, cutoff))
+            .await
+        {
+            tracing::warn!("failed to delete stale remote orders: {:#}", e);
+        }
+
+        tokio::time::delay_for(POLL_INTERVAL).await;
+    }
+}
+
+/// Cancels the open orders whose `valid_until` passed at `now`.
+async fn cancel_expired_orders(db: &Sqlite, now: OffsetDateTime) -> Result<Vec<OrderId>> {
+    let expired_orders = db
+        .do_in_transaction(|conn| all_open_btc_dai_orders(conn))
+        .await?
+        .into_iter()
+        .filter(|(order, _)| {
+            order
+                .valid_until
+                .map_or(false, |valid_until| now.timestamp() >= valid_until)
+        })
+        .map(|(order, _)| order.order_id);
+
+    let mut cancelled = Vec::new();
+
+    for order_id in expired_orders {
+        match db
+            .do_in_transaction(|conn| commands::update_btc_dai_order_to_cancelled(conn, order_id))
+            .await
+        {
+            Ok(()) => cancelled.push(order_id),
+            // only settling, nothing left to cancel
+            Err(e) if e.is::<NotOpen>() => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(cancelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{InsertableBtcDaiOrder, InsertableOrder};
+    use comit::{
+        asset::{ethereum::FromWei, Bitcoin, Erc20Quantity},
+        Position, Quantity,
+    };
+
+    async fn insert_order(db: &Sqlite, created_at: i64, valid_until: Option<i64>) -> OrderId {
+        let order_id = OrderId::random();
+
+        db.do_in_transaction(|conn| {
+            let order_fk = InsertableOrder::new(
+                order_id,
+                Position::Sell,
+                OffsetDateTime::from_unix_timestamp(created_at),
+                valid_until.map(OffsetDateTime::from_unix_timestamp),
+            )
+            .insert(conn)?;
+            InsertableBtcDaiOrder::new(
+                order_fk,
+                Bitcoin::from_sat(1_000_000),
+                Erc20Quantity::from_wei(9_000_000_000_000u64),
+            )
+            .insert(conn)
+        })
+        .await
+        .unwrap();
+
+        order_id
+    }
+
+    /// The orders that are shown as open, like `GET /orders` does.
+    async fn open_orders(db: &Sqlite) -> Vec<OrderId> {
+        db.do_in_transaction(|conn| all_open_btc_dai_orders(conn))
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|(_, btc_dai_order)| btc_dai_order.open != Quantity::new(Bitcoin::ZERO))
+            .map(|(order, _)| order.order_id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn order_disappears_once_clock_passes_valid_until() {
+        let db = Sqlite::test();
+        let expiring = insert_order(&db, 1_000, Some(1_060)).await;
+        let forever = insert_order(&db, 1_000, None).await;
+
+        let cancelled = cancel_expired_orders(&db, OffsetDateTime::from_unix_timestamp(1_059))
+            .await
+            .unwrap();
+        assert!(cancelled.is_empty());
+        assert_eq!(open_orders(&db).await.len(), 2);
+
+        let cancelled = cancel_expired_orders(&db, OffsetDateTime::from_unix_timestamp(1_060))
+            .await
+            .unwrap();
+        assert_eq!(cancelled, vec![expiring]);
+        assert_eq!(open_orders(&db).await, vec![forever]);
+
+        let cancelled = cancel_expired_orders(&db, OffsetDateTime::from_unix_timestamp(2_000))
+            .await
+            .unwrap();
+        assert!(cancelled.is_empty());
+    }
+}
@@ -1,7 +1,8 @@
 pub use comit::{hbit::*, identity};
 
 use crate::{
-    btsieve::{BlockByHash, ConnectedNetwork, LatestBlock},
+    btsieve::{BlockByHash, ConnectedNetwork, InMemoryCheckpoints, LatestBlock},
+    http_api::SwapEvent,
     ledger,
     storage::Storage,
     LocalSwapId,
@@ -37,9 +38,16 @@ where
         params: &Params,
         start_of_swap: OffsetDateTime,
     ) -> Result<Funded, IncorrectlyFunded> {
+        // swap events are not persisted, hence we only resume scanning when retrying
+        let checkpoints = InMemoryCheckpoints::default();
         let operation = || {
-            comit::hbit::watch_for_funded(self.connector.as_ref(), &params.shared, start_of_swap)
-                .map_err(backoff::Error::Transient)
+            comit::hbit::watch_for_funded(
+                self.connector.as_ref(),
+                &checkpoints,
+                &params.shared,
+                start_of_swap,
+            )
+            .map_err(backoff::Error::Transient)
         };
 
         let funded = operation
@@ -56,6 +64,10 @@ where
             .entry(self.swap_id)
             .or_default()
             .fund = Some(funded);
+        self.storage
+            .publish_swap_event(self.swap_id, SwapEvent::HbitFunded {
+                tx: funded.location.txid,
+            });
 
         Ok(funded)
     }
@@ -74,9 +86,11 @@ where
         fund_event: Funded,
         start_of_swap: OffsetDateTime,
     ) -> Redeemed {
+        let checkpoints = InMemoryCheckpoints::default();
         let operation = || {
             watch_for_redeemed(
                 self.connector.as_ref(),
+                &checkpoints,
                 &params.shared,
                 fund_event.location,
                 start_of_swap,
@@ -98,6 +112,10 @@ where
             .entry(self.swap_id)
             .or_default()
             .redeem = Some(redeemed);
+        self.storage
+            .publish_swap_event(self.swap_id, SwapEvent::HbitRedeemed {
+                tx: redeemed.transaction,
+            });
 
         redeemed
     }
@@ -3,9 +3,10 @@ pub use comit::herc20::*;
 use crate::{
     btsieve::{
         ethereum::{GetLogs, ReceiptByHash, TransactionByHash},
-        BlockByHash, ConnectedNetwork, LatestBlock,
+        BlockByHash, ConnectedNetwork, InMemoryCheckpoints, LatestBlock,
     },
     ethereum::{Block, ChainId, Hash},
+    http_api::SwapEvent,
     storage::Storage,
     LocalSwapId,
 };
@@ -42,9 +43,16 @@ where
         params: Params,
         utc_start_of_swap: OffsetDateTime,
     ) -> Deployed {
+        // swap events are not persisted, hence we only resume scanning when retrying
+        let checkpoints = InMemoryCheckpoints::default();
         let operation = || {
-            watch_for_deployed(self.connector.as_ref(), params.clone(), utc_start_of_swap)
-                .map_err(backoff::Error::Transient)
+            watch_for_deployed(
+                self.connector.as_ref(),
+                &checkpoints,
+                params.clone(),
+                utc_start_of_swap,
+            )
+            .map_err(backoff::Error::Transient)
         };
 
         let deployed = operation
@@ -64,6 +72,10 @@ where
             .entry(self.swap_id)
             .or_default()
             .deploy = Some(deployed);
+        self.storage
+            .publish_swap_event(self.swap_id, SwapEvent::Herc20Deployed {
+                tx: deployed.transaction,
+            });
 
         deployed
     }
@@ -109,6 +121,10 @@ where
             .entry(self.swap_id)
             .or_default()
             .fund = Some(funded);
+        self.storage
+            .publish_swap_event(self.swap_id, SwapEvent::Herc20Funded {
+                tx: funded.transaction,
+            });
 
         Ok(funded)
     }
@@ -149,6 +165,10 @@ where
             .entry(self.swap_id)
             .or_default()
             .redeem = Some(redeemed);
+        self.storage
+            .publish_swap_event(self.swap_id, SwapEvent::Herc20Redeemed {
+                tx: redeemed.transaction,
+            });
 
         redeemed
     }
@@ -1,14 +1,22 @@
 mod action;
+mod auth;
+mod cors;
 mod dial_addr;
+mod encoding;
+mod health;
 mod info;
 mod markets;
+mod metrics;
 mod orders;
+mod page;
 mod peers;
 mod problem;
 mod route_factory;
+mod rpc;
 mod serde_peer_id;
 mod swaps;
 mod tokens;
+mod version;
 
 pub use self::{problem::*, route_factory::create as create_routes, swaps::SwapResource};
 
@@ -17,12 +25,15 @@ pub const PATH: &str = "swaps";
 use crate::{
     asset,
     asset::Erc20Quantity,
+    config::Token,
     ethereum,
     storage::{BtcDaiOrder, Order},
 };
 use anyhow::Result;
-use comit::{swap::Action, OrderId, Position, Price, Quantity};
-use serde::Serialize;
+use comit::{swap::Action, LockProtocol, OrderId, Position, Price, Quantity};
+use libp2p::PeerId;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use strum::IntoEnumIterator;
 use warp::http::Method;
 
 /// The struct representing the properties within the siren document in our
@@ -34,6 +45,18 @@ struct OrderProperties {
     price: Amount,
     quantity: Amount,
     state: State,
+    #[serde(with = "serde_peer_id::option")]
+    maker: Option<PeerId>,
+}
+
+impl OrderProperties {
+    /// Orders in our database are always made by us.
+    fn made_by(self, local_peer_id: PeerId) -> Self {
+        Self {
+            maker: Some(local_peer_id),
+            ..self
+        }
+    }
 }
 
 impl From<(Order, BtcDaiOrder)> for OrderProperties {
@@ -52,21 +75,55 @@ impl From<(Order, BtcDaiOrder)> for OrderProperties {
                 failed: btc_dai_order.failed.to_inner(),
                 cancelled: btc_dai_order.cancelled.to_inner(),
             },
+            maker: None,
         }
     }
 }
 
-#[derive(Clone, Debug, Serialize, PartialEq)]
-#[serde(tag = "currency")]
+/// The currency of amounts of tokens we do not know the symbol of.
+const UNKNOWN_ERC20: &str = "ERC20";
+
+/// An amount of an asset, serialized with the symbol of the asset as
+/// `currency`.
+#[derive(Clone, Debug, PartialEq)]
 pub enum Amount {
-    #[serde(rename = "BTC")]
     Bitcoin {
-        #[serde(with = "asset::bitcoin::sats_as_string")]
         value: asset::Bitcoin,
         decimals: u8,
     },
-    #[serde(rename = "DAI")]
-    Dai { value: Erc20Quantity, decimals: u8 },
+    Erc20 {
+        value: Erc20Quantity,
+        decimals: u8,
+        symbol: String,
+    },
+}
+
+impl Serialize for Amount {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Amount", 3)?;
+
+        match self {
+            Amount::Bitcoin { value, decimals } => {
+                state.serialize_field("currency", "BTC")?;
+                state.serialize_field("value", &value.as_sat().to_string())?;
+                state.serialize_field("decimals", decimals)?;
+            }
+            Amount::Erc20 {
+                value,
+                decimals,
+                symbol,
+            } => {
+                state.serialize_field("currency", symbol)?;
+                state.serialize_field("value", value)?;
+                state.serialize_field("decimals", decimals)?;
+            }
+        }
+
+        state.end()
+    }
 }
 
 impl From<Quantity<asset::Bitcoin>> for Amount {
@@ -82,18 +139,111 @@ impl From<Price<asset::Bitcoin, Erc20Quantity>> for Amount {
 }
 
 impl Amount {
+    /// Adds two amounts of the same currency.
+    pub fn add(&self, other: &Amount) -> Result<Amount, AmountError> {
+        self.combine(
+            other,
+            |left, right| left.checked_add(right),
+            |left, right| left.checked_add(right),
+            AmountError::Overflow,
+        )
+    }
+
+    /// Subtracts an amount of the same currency.
+    #[cfg(test)]
+    pub fn sub(&self, other: &Amount) -> Result<Amount, AmountError> {
+        self.combine(
+            other,
+            |left, right| left.checked_sub(right),
+            |left, right| left.checked_sub(right),
+            AmountError::Underflow,
+        )
+    }
+
+    /// Applies the checked operation matching the currency of both amounts,
+    /// `error` is returned if it fails.
+    fn combine(
+        &self,
+        other: &Amount,
+        bitcoin: impl FnOnce(asset::Bitcoin, asset::Bitcoin) -> Option<asset::Bitcoin>,
+        erc20: impl FnOnce(Erc20Quantity, Erc20Quantity) -> Option<Erc20Quantity>,
+        error: AmountError,
+    ) -> Result<Amount, AmountError> {
+        if self.currency() != other.currency() || self.decimals() != other.decimals() {
+            return Err(self.mismatch(other));
+        }
+
+        match (self, other) {
+            (Amount::Bitcoin { value, decimals }, Amount::Bitcoin { value: other, .. }) => {
+                Ok(Amount::Bitcoin {
+                    value: bitcoin(*value, *other).ok_or(error)?,
+                    decimals: *decimals,
+                })
+            }
+            (
+                Amount::Erc20 {
+                    value,
+                    decimals,
+                    symbol,
+                },
+                Amount::Erc20 { value: other, .. },
+            ) => Ok(Amount::Erc20 {
+                value: erc20(value.clone(), other.clone()).ok_or(error)?,
+                decimals: *decimals,
+                symbol: symbol.clone(),
+            }),
+            _ => Err(self.mismatch(other)),
+        }
+    }
+
+    fn currency(&self) -> &str {
+        match self {
+            Amount::Bitcoin { .. } => "BTC",
+            Amount::Erc20 { symbol, .. } => symbol,
+        }
+    }
+
+    fn decimals(&self) -> u8 {
+        match self {
+            Amount::Bitcoin { decimals, .. } | Amount::Erc20 { decimals, .. } => *decimals,
+        }
+    }
+
+    fn mismatch(&self, other: &Amount) -> AmountError {
+        AmountError::CurrencyMismatch {
+            left: format!("{} ({} decimals)", self.currency(), self.decimals()),
+            right: format!("{} ({} decimals)", other.currency(), other.decimals()),
+        }
+    }
+
     fn btc(value: asset::Bitcoin) -> Self {
         Amount::Bitcoin { value, decimals: 8 }
     }
 
     fn dai(value: Erc20Quantity) -> Self {
-        Amount::Dai {
+        Amount::erc20(value, 18, "DAI")
+    }
+
+    fn erc20(value: Erc20Quantity, decimals: u8, symbol: &str) -> Self {
+        Amount::Erc20 {
             value,
-            decimals: 18,
+            decimals,
+            symbol: symbol.to_owned(),
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum AmountError {
+    #[error("cannot combine amounts of {left} and {right}")]
+    CurrencyMismatch { left: String, right: String },
+    #[error("amount overflowed")]
+    Overflow,
+    #[cfg(test)]
+    #[error("amount underflowed")]
+    Underflow,
+}
+
 #[derive(Serialize)]
 struct State {
     #[serde(with = "asset::bitcoin::sats_as_string")]
@@ -143,8 +293,13 @@ fn cancel_action(order: &OrderProperties) -> Option<siren::Action> {
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "lowercase", tag = "protocol")]
 pub enum Protocol {
-    Hbit { asset: Amount },
-    Herc20 { asset: Amount },
+    Hbit {
+        asset: Amount,
+    },
+    Herc20 {
+        asset: Amount,
+        token_contract: ethereum::Address,
+    },
 }
 
 impl Protocol {
@@ -154,13 +309,62 @@ impl Protocol {
         }
     }
 
-    pub fn herc20_dai(dai: Erc20Quantity) -> Self {
+    /// The amount is described in the smallest unit of the token, as
+    /// `UNKNOWN_ERC20`, if we do not know the symbol and decimals of the token.
+    pub fn herc20(
+        quantity: Erc20Quantity,
+        token_contract: ethereum::Address,
+        token: Option<&Token>,
+    ) -> Self {
+        let asset = match token {
+            Some(token) => Amount::erc20(quantity, token.decimals, token.symbol),
+            None => Amount::erc20(quantity, 0, UNKNOWN_ERC20),
+        };
+
         Protocol::Herc20 {
-            asset: Amount::dai(dai),
+            asset,
+            token_contract,
+        }
+    }
+}
+
+/// The names of the protocols, as used in the `protocol` tag of `Protocol`.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum ProtocolName {
+    Hbit,
+    Herc20,
+}
+
+impl From<LockProtocol> for ProtocolName {
+    fn from(protocol: LockProtocol) -> Self {
+        match protocol {
+            LockProtocol::Hbit => ProtocolName::Hbit,
+            LockProtocol::Herc20 => ProtocolName::Herc20,
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("unknown protocol '{0}', expected one of: {}", protocol_names())]
+pub struct UnknownProtocol(pub String);
+
+fn protocol_names() -> String {
+    ProtocolName::iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum ActionName {
@@ -181,7 +385,7 @@ impl From<Action> for ActionName {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[serde(tag = "name", rename_all = "snake_case")]
 pub enum SwapEvent {
     HbitFunded { tx: bitcoin::Txid },
@@ -218,6 +422,7 @@ mod tests {
                 failed: Bitcoin::from_sat(6000000),
                 cancelled: Bitcoin::from_sat(0),
             },
+            maker: None,
         };
 
         let result = serde_json::to_string_pretty(&properties).unwrap();
@@ -243,11 +448,133 @@ mod tests {
     "settling": "0",
     "failed": "6000000",
     "cancelled": "0"
-  }
+  },
+  "maker": null
 }"#
         );
     }
 
+    #[test]
+    fn order_properties_in_cbor_match_json() {
+        let properties = OrderProperties {
+            id: OrderId::from(Uuid::from_u128(0)),
+            position: Position::Sell,
+            price: Amount::dai(Erc20Quantity::from_wei_dec_str("9100000000000000000000").unwrap()),
+            quantity: Amount::btc(Bitcoin::from_sat(10000000)),
+            state: State {
+                open: Bitcoin::from_sat(3000000),
+                closed: Bitcoin::from_sat(1000000),
+                settling: Bitcoin::from_sat(0),
+                failed: Bitcoin::from_sat(6000000),
+                cancelled: Bitcoin::from_sat(0),
+            },
+            maker: None,
+        };
+
+        let cbor = encoding::to_cbor(&properties).unwrap();
+        let from_cbor = serde_cbor::from_slice::<serde_json::Value>(&cbor).unwrap();
+        let from_json = serde_json::to_value(&properties).unwrap();
+
+        assert_eq!(from_cbor, from_json);
+        assert_eq!(from_cbor["state"]["open"], serde_json::json!("3000000"));
+    }
+
+    #[test]
+    fn partially_filled_order_can_be_cancelled_while_anything_is_open() {
+        let order = |open, settling| OrderProperties {
+            id: OrderId::from(Uuid::from_u128(0)),
+            position: Position::Sell,
+            price: Amount::dai(Erc20Quantity::from_wei(9_100u64)),
+            quantity: Amount::btc(Bitcoin::from_sat(10_000)),
+            state: State {
+                open: Bitcoin::from_sat(open),
+                closed: Bitcoin::ZERO,
+                settling: Bitcoin::from_sat(settling),
+                failed: Bitcoin::ZERO,
+                cancelled: Bitcoin::ZERO,
+            },
+            maker: None,
+        };
+
+        assert!(cancel_action(&order(10_000, 0)).is_some());
+        assert!(cancel_action(&order(7_000, 3_000)).is_some());
+        assert!(cancel_action(&order(0, 10_000)).is_none());
+    }
+
+    #[test]
+    fn maker_of_order_round_trips() {
+        let maker: PeerId = "QmfUfpC2frwFvcDzpspnfZitHt5wct6n4kpG5jzgRdsxkY"
+            .parse()
+            .unwrap();
+        let properties = OrderProperties {
+            id: OrderId::from(Uuid::from_u128(0)),
+            position: Position::Buy,
+            price: Amount::dai(Erc20Quantity::from_wei(9_100u64)),
+            quantity: Amount::btc(Bitcoin::from_sat(10_000)),
+            state: State {
+                open: Bitcoin::from_sat(10_000),
+                closed: Bitcoin::ZERO,
+                settling: Bitcoin::ZERO,
+                failed: Bitcoin::ZERO,
+                cancelled: Bitcoin::ZERO,
+            },
+            maker: None,
+        }
+        .made_by(maker.clone());
+
+        let json = serde_json::to_value(&properties).unwrap();
+        let serialized_maker = &json["maker"];
+
+        assert_eq!(
+            serialized_maker,
+            &serde_json::json!("QmfUfpC2frwFvcDzpspnfZitHt5wct6n4kpG5jzgRdsxkY")
+        );
+        let round_tripped: PeerId = serialized_maker.as_str().unwrap().parse().unwrap();
+        assert_eq!(round_tripped, maker);
+    }
+
+    #[test]
+    fn amounts_of_same_currency_add_up() {
+        let btc = Amount::btc(Bitcoin::from_sat(1_000))
+            .add(&Amount::btc(Bitcoin::from_sat(500)))
+            .unwrap();
+        let dai = Amount::dai(Erc20Quantity::from_wei(1_000u64))
+            .add(&Amount::dai(Erc20Quantity::from_wei(500u64)))
+            .unwrap();
+
+        assert_eq!(btc, Amount::btc(Bitcoin::from_sat(1_500)));
+        assert_eq!(dai, Amount::dai(Erc20Quantity::from_wei(1_500u64)));
+    }
+
+    #[test]
+    fn amounts_of_different_currencies_do_not_mix() {
+        let btc = Amount::btc(Bitcoin::from_sat(1_000));
+        let dai = Amount::dai(Erc20Quantity::from_wei(1_000u64));
+        let usdc = Amount::erc20(Erc20Quantity::from_wei(1_000u64), 6, "USDC");
+
+        assert!(matches!(
+            btc.add(&dai),
+            Err(AmountError::CurrencyMismatch { .. })
+        ));
+        assert!(matches!(
+            dai.sub(&btc),
+            Err(AmountError::CurrencyMismatch { .. })
+        ));
+        assert!(matches!(
+            dai.add(&usdc),
+            Err(AmountError::CurrencyMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn subtracting_more_sats_than_available_underflows() {
+        let small = Amount::btc(Bitcoin::from_sat(500));
+        let large = Amount::btc(Bitcoin::from_sat(1_000));
+
+        assert_eq!(large.sub(&small), Ok(Amount::btc(Bitcoin::from_sat(500))));
+        assert_eq!(small.sub(&large), Err(AmountError::Underflow));
+    }
+
     #[test]
     fn btc_amount_serializes_properly() {
         let amount = Amount::btc(asset::Bitcoin::from_sat(100000000));
@@ -273,6 +600,32 @@ mod tests {
         )
     }
 
+    #[test]
+    fn usdc_amount_serializes_properly() {
+        let amount = Amount::erc20(Erc20Quantity::from_wei(9_000_000_000u64), 6, "USDC");
+
+        let string = serde_json::to_string(&amount).unwrap();
+
+        assert_eq!(
+            string,
+            r#"{"currency":"USDC","value":"9000000000","decimals":6}"#
+        )
+    }
+
+    #[test]
+    fn protocol_names_round_trip() {
+        for name in ProtocolName::iter() {
+            assert_eq!(name.to_string().parse::<ProtocolName>().unwrap(), name);
+        }
+
+        assert_eq!("hbit".parse::<ProtocolName>().unwrap(), ProtocolName::Hbit);
+        assert!("halight".parse::<ProtocolName>().is_err());
+        assert_eq!(
+            UnknownProtocol(String::from("halight")).to_string(),
+            "unknown protocol 'halight', expected one of: hbit, herc20"
+        );
+    }
+
     #[test]
     fn hbit_protocol_serializes_correctly() {
         let protocol = Protocol::hbit(asset::Bitcoin::from_sat(10_000));
@@ -294,7 +647,16 @@ mod tests {
 
     #[test]
     fn herc20_protocol_serializes_correctly() {
-        let protocol = Protocol::herc20_dai(Erc20Quantity::from_wei(1_000_000_000_000_000u64));
+        let dai = Token {
+            symbol: "DAI",
+            address: ethereum::Address::from([0x6b; 20]),
+            decimals: 18,
+        };
+        let protocol = Protocol::herc20(
+            Erc20Quantity::from_wei(1_000_000_000_000_000u64),
+            dai.address,
+            Some(&dai),
+        );
 
         let result = serde_json::to_string_pretty(&protocol).unwrap();
 
@@ -306,7 +668,61 @@ mod tests {
     "currency": "DAI",
     "value": "1000000000000000",
     "decimals": 18
-  }
+  },
+  "token_contract": "0x6b6b6b6b6b6b6b6b6b6b6b6b6b6b6b6b6b6b6b6b"
+}"#
+        )
+    }
+
+    #[test]
+    fn usdc_herc20_protocol_serializes_correctly() {
+        let usdc = Token {
+            symbol: "USDC",
+            address: ethereum::Address::from([0xa0; 20]),
+            decimals: 6,
+        };
+        let protocol = Protocol::herc20(
+            Erc20Quantity::from_wei(25_000_000u64),
+            usdc.address,
+            Some(&usdc),
+        );
+
+        let result = serde_json::to_string_pretty(&protocol).unwrap();
+
+        assert_eq!(
+            result,
+            r#"{
+  "protocol": "herc20",
+  "asset": {
+    "currency": "USDC",
+    "value": "25000000",
+    "decimals": 6
+  },
+  "token_contract": "0xa0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0"
+}"#
+        )
+    }
+
+    #[test]
+    fn herc20_protocol_of_unknown_token_is_not_labelled_as_a_known_token() {
+        let protocol = Protocol::herc20(
+            Erc20Quantity::from_wei(25_000_000u64),
+            ethereum::Address::from([0x11; 20]),
+            None,
+        );
+
+        let result = serde_json::to_string_pretty(&protocol).unwrap();
+
+        assert_eq!(
+            result,
+            r#"{
+  "protocol": "herc20",
+  "asset": {
+    "currency": "ERC20",
+    "value": "25000000",
+    "decimals": 0
+  },
+  "token_contract": "0x1111111111111111111111111111111111111111"
 }"#
         )
     }
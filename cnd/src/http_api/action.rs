@@ -28,6 +28,10 @@ pub enum ActionResponseBody {
         amount: asset::Ether,
         gas_limit: crate::ethereum::U256,
         chain_id: ChainId,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_fee_per_gas: Option<asset::Ether>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_priority_fee_per_gas: Option<asset::Ether>,
     },
     EthereumCallContract {
         contract_address: identity::Ethereum,
@@ -35,6 +39,10 @@ pub enum ActionResponseBody {
         data: Option<crate::ethereum::UnformattedData>,
         gas_limit: crate::ethereum::U256,
         chain_id: ChainId,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_fee_per_gas: Option<asset::Ether>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_priority_fee_per_gas: Option<asset::Ether>,
     },
 }
 
@@ -60,6 +68,8 @@ impl From<DeployContract> for ActionResponseBody {
             chain_id,
             gas_limit,
             data,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
         } = action;
 
         ActionResponseBody::EthereumDeployContract {
@@ -67,6 +77,8 @@ impl From<DeployContract> for ActionResponseBody {
             amount,
             gas_limit: gas_limit.into(),
             chain_id,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
         }
     }
 }
@@ -78,6 +90,8 @@ impl From<CallContract> for ActionResponseBody {
             data,
             gas_limit,
             chain_id,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
         } = action;
 
         ActionResponseBody::EthereumCallContract {
@@ -85,6 +99,8 @@ impl From<CallContract> for ActionResponseBody {
             data: data.map(UnformattedData),
             gas_limit: gas_limit.into(),
             chain_id,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
         }
     }
 }
@@ -138,6 +154,8 @@ mod test {
             data: None,
             gas_limit: U256::from(1),
             chain_id,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
         };
         let serialized = serde_json::to_string(&contract).unwrap();
         assert_eq!(
@@ -153,6 +171,8 @@ mod test {
             amount: asset::Ether::from_wei(10000u32),
             gas_limit: U256::from(1),
             chain_id: ChainId::from(3),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
         };
 
         let serialized = serde_json::to_string(&response_body).unwrap();
@@ -163,6 +183,46 @@ mod test {
         );
     }
 
+    #[test]
+    fn call_contract_with_eip1559_fees_serializes_correctly_to_json() {
+        let addr =
+            identity::Ethereum::from_str("0A81e8be41b21f651a71aaB1A85c6813b8bBcCf8").unwrap();
+        let contract = ActionResponseBody::EthereumCallContract {
+            contract_address: addr,
+            data: None,
+            gas_limit: U256::from(1),
+            chain_id: ChainId::MAINNET,
+            max_fee_per_gas: Some(asset::Ether::from_wei(100u32)),
+            max_priority_fee_per_gas: Some(asset::Ether::from_wei(2u32)),
+        };
+
+        let serialized = serde_json::to_string(&contract).unwrap();
+
+        assert_eq!(
+            serialized,
+            r#"{"type":"ethereum-call-contract","payload":{"contract_address":"0x0a81e8be41b21f651a71aab1a85c6813b8bbccf8","gas_limit":"0x1","chain_id":1,"max_fee_per_gas":"100","max_priority_fee_per_gas":"2"}}"#
+        );
+    }
+
+    #[test]
+    fn deploy_contract_with_eip1559_fees_serializes_correctly_to_json() {
+        let response_body = ActionResponseBody::EthereumDeployContract {
+            data: UnformattedData(vec![0x1, 0x2]),
+            amount: asset::Ether::from_wei(10000u32),
+            gas_limit: U256::from(1),
+            chain_id: ChainId::MAINNET,
+            max_fee_per_gas: Some(asset::Ether::from_wei(100u32)),
+            max_priority_fee_per_gas: Some(asset::Ether::from_wei(2u32)),
+        };
+
+        let serialized = serde_json::to_string(&response_body).unwrap();
+
+        assert_eq!(
+            serialized,
+            r#"{"type":"ethereum-deploy-contract","payload":{"data":"0x0102","amount":"10000","gas_limit":"0x1","chain_id":1,"max_fee_per_gas":"100","max_priority_fee_per_gas":"2"}}"#
+        );
+    }
+
     #[test]
     fn bitcoin_send_amount_to_address_serializes_correctly_to_json() {
         let to = BitcoinAddress::from_str("2N3pk6v15FrDiRNKYVuxnnugn1Yg7wfQRL9").unwrap();
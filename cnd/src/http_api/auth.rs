@@ -0,0 +1,128 @@
+use crate::http_api::problem;
+use warp::{http::Method, Filter, Rejection};
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("missing or invalid bearer token")]
+pub struct Unauthorized;
+
+/// Requires requests that change state to present the configured bearer token
+/// in their `Authorization` header.
+///
+/// Only `GET`, `HEAD` and `OPTIONS` requests are considered read-only, every
+/// other method needs the token. Without a configured token, every request is
+/// let through.
+pub fn bearer_token(token: Option<String>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::method()
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(move |method: Method, authorization: Option<String>| {
+            let result = check(token.as_deref(), &method, authorization.as_deref());
+
+            async move {
+                result
+                    .map_err(anyhow::Error::from)
+                    .map_err(problem::from_anyhow)
+                    .map_err(warp::reject::custom)
+            }
+        })
+        .untuple_one()
+}
+
+fn check(
+    token: Option<&str>,
+    method: &Method,
+    authorization: Option<&str>,
+) -> Result<(), Unauthorized> {
+    let token = match token {
+        Some(token) => token,
+        None => return Ok(()),
+    };
+
+    if *method == Method::GET || *method == Method::HEAD || *method == Method::OPTIONS {
+        return Ok(());
+    }
+
+    let presented = authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(Unauthorized)?;
+
+    if constant_time_eq(presented.as_bytes(), token.as_bytes()) {
+        Ok(())
+    } else {
+        Err(Unauthorized)
+    }
+}
+
+/// Compares without short-circuiting so that the time it takes does not tell
+/// how much of the token was guessed right.
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right.iter())
+            .fold(0u8, |acc, (l, r)| acc | (l ^ r))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_api::unpack_problem;
+    use warp::{http::StatusCode, Reply};
+
+    fn route(
+        token: Option<&str>,
+    ) -> impl Filter<Extract = impl Reply, Error = std::convert::Infallible> + Clone {
+        bearer_token(token.map(str::to_owned))
+            .and(warp::any().map(warp::reply))
+            .recover(unpack_problem)
+            .recover(|_| async { Ok::<_, std::convert::Infallible>(StatusCode::NOT_FOUND) })
+    }
+
+    async fn delete_order(token: Option<&str>, authorization: Option<&str>) -> StatusCode {
+        let mut request = warp::test::request().method("DELETE").path("/orders/1");
+        if let Some(authorization) = authorization {
+            request = request.header("authorization", authorization);
+        }
+
+        request.reply(&route(token)).await.status()
+    }
+
+    #[tokio::test]
+    async fn valid_token_passes() {
+        assert_eq!(
+            delete_order(Some("s3cr3t"), Some("Bearer s3cr3t")).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_or_invalid_token_is_unauthorized() {
+        assert_eq!(
+            delete_order(Some("s3cr3t"), None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            delete_order(Some("s3cr3t"), Some("Bearer guess")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            delete_order(Some("s3cr3t"), Some("s3cr3t")).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn reading_needs_no_token() {
+        let response = warp::test::request()
+            .path("/markets/BTC-DAI")
+            .reply(&route(Some("s3cr3t")))
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn without_configured_token_everything_passes() {
+        assert_eq!(delete_order(None, None).await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::config::{AllowedOrigins, Cors};
+use warp::cors::Builder;
+
+/// The CORS policy of the HTTP API as configured by the operator.
+///
+/// Preflight requests are answered by the returned filter itself, the routes
+/// it wraps never see them.
+pub fn policy(cors: &Cors) -> Builder {
+    let policy = warp::cors()
+        .allow_methods(cors.allowed_methods.clone())
+        .allow_headers(cors.allowed_headers.clone());
+
+    match &cors.allowed_origins {
+        AllowedOrigins::None => policy.allow_origins(Vec::<&str>::new()),
+        AllowedOrigins::All => policy.allow_any_origin(),
+        AllowedOrigins::Some(hosts) => {
+            policy.allow_origins::<Vec<&str>>(hosts.iter().map(|host| host.as_str()).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::{
+        http::{header, Method, StatusCode},
+        Filter,
+    };
+
+    fn dashboard_only() -> Cors {
+        Cors {
+            allowed_origins: AllowedOrigins::Some(vec!["http://dashboard.local".to_owned()]),
+            ..Cors::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn allowed_origin_gets_cors_headers() {
+        let route = warp::any().map(warp::reply).with(policy(&dashboard_only()));
+
+        let response = warp::test::request()
+            .path("/markets/BTC-DAI")
+            .header("origin", "http://dashboard.local")
+            .reply(&route)
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "http://dashboard.local"
+        );
+    }
+
+    #[tokio::test]
+    async fn disallowed_origin_gets_no_cors_headers() {
+        let route = warp::any().map(warp::reply).with(policy(&dashboard_only()));
+
+        let response = warp::test::request()
+            .path("/markets/BTC-DAI")
+            .header("origin", "http://evil.local")
+            .reply(&route)
+            .await;
+
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn preflight_request_is_answered() {
+        let route = warp::any().map(warp::reply).with(policy(&dashboard_only()));
+
+        let response = warp::test::request()
+            .method("OPTIONS")
+            .path("/orders/BTC-DAI")
+            .header("origin", "http://dashboard.local")
+            .header("access-control-request-method", Method::DELETE.as_str())
+            .header("access-control-request-headers", "content-type")
+            .reply(&route)
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "http://dashboard.local"
+        );
+        assert!(response.headers()[header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap()
+            .contains("DELETE"));
+    }
+}
@@ -0,0 +1,171 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use warp::{
+    http::{header, HeaderValue},
+    reply::Response,
+    Filter, Rejection, Reply,
+};
+
+const APPLICATION_CBOR: &str = "application/cbor";
+const APPLICATION_JSON: &str = "application/json";
+
+/// The encoding of a response body, negotiated through the `Accept` header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Json,
+    Cbor,
+}
+
+impl Encoding {
+    /// Responds with CBOR only if the client explicitly accepts it with a
+    /// non-zero quality that JSON does not beat, anything else falls back to
+    /// JSON.
+    pub fn from_accept(accept: Option<&str>) -> Self {
+        let media_ranges = accept
+            .into_iter()
+            .flat_map(|accept| accept.split(','))
+            .map(MediaRange::parse)
+            .collect::<Vec<_>>();
+
+        let cbor = quality(&media_ranges, &[APPLICATION_CBOR]);
+        let json = quality(&media_ranges, &[APPLICATION_JSON, "application/*", "*/*"]);
+
+        match (cbor, json) {
+            (Some(cbor), Some(json)) if cbor > 0.0 && cbor >= json => Encoding::Cbor,
+            (Some(cbor), None) if cbor > 0.0 => Encoding::Cbor,
+            _ => Encoding::Json,
+        }
+    }
+}
+
+/// A media range of an `Accept` header with its quality, `q` defaults to 1.
+#[derive(Debug)]
+struct MediaRange<'a> {
+    media_type: &'a str,
+    quality: f32,
+}
+
+impl<'a> MediaRange<'a> {
+    fn parse(media_range: &'a str) -> Self {
+        let mut parts = media_range.split(';').map(str::trim);
+        let media_type = parts.next().unwrap_or_default();
+        let quality = parts
+            .filter_map(|parameter| parameter.strip_prefix("q="))
+            .find_map(|q| q.parse().ok())
+            .unwrap_or(1.0);
+
+        Self {
+            media_type,
+            quality,
+        }
+    }
+}
+
+/// The quality of the first of `media_types` listed in the header, the most
+/// specific media type has to come first.
+fn quality(media_ranges: &[MediaRange<'_>], media_types: &[&str]) -> Option<f32> {
+    media_types.iter().find_map(|media_type| {
+        media_ranges
+            .iter()
+            .find(|range| range.media_type.eq_ignore_ascii_case(media_type))
+            .map(|range| range.quality)
+    })
+}
+
+/// The warp filter extracting the encoding the client asks for.
+pub fn accept() -> impl Filter<Extract = (Encoding,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("accept")
+        .map(|accept: Option<String>| Encoding::from_accept(accept.as_deref()))
+}
+
+/// Serializes `body` with the given encoding.
+///
+/// The response depends on the `Accept` header, caches are told so through
+/// `Vary`.
+pub fn reply<T>(encoding: Encoding, body: &T) -> Result<Response>
+where
+    T: Serialize,
+{
+    let mut response = match encoding {
+        Encoding::Json => warp::reply::json(body).into_response(),
+        Encoding::Cbor => {
+            let bytes = to_cbor(body)?;
+
+            warp::reply::with_header(bytes, header::CONTENT_TYPE, APPLICATION_CBOR).into_response()
+        }
+    };
+    response
+        .headers_mut()
+        .insert(header::VARY, HeaderValue::from_static("accept"));
+
+    Ok(response)
+}
+
+/// Serializes `body` to CBOR holding the same values as its JSON form.
+///
+/// Some types, e.g. order ids, serialize to bytes instead of strings in
+/// non-human-readable formats, hence we go through a JSON value.
+pub fn to_cbor<T>(body: &T) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    let value = serde_json::to_value(body).context("failed to serialize body")?;
+
+    serde_cbor::to_vec(&value).context("failed to serialize body to CBOR")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cbor_only_if_accepted() {
+        assert_eq!(Encoding::from_accept(None), Encoding::Json);
+        assert_eq!(Encoding::from_accept(Some("*/*")), Encoding::Json);
+        assert_eq!(
+            Encoding::from_accept(Some("application/json")),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::from_accept(Some("application/cbor")),
+            Encoding::Cbor
+        );
+        assert_eq!(
+            Encoding::from_accept(Some("application/json;q=0.5, application/cbor")),
+            Encoding::Cbor
+        );
+        assert_eq!(
+            Encoding::from_accept(Some("application/cbor, */*")),
+            Encoding::Cbor
+        );
+    }
+
+    #[test]
+    fn cbor_refused_or_less_preferred_is_json() {
+        assert_eq!(
+            Encoding::from_accept(Some("application/cbor;q=0")),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::from_accept(Some("application/cbor; q=0, */*")),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::from_accept(Some("application/cbor;q=0.5, application/json")),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::from_accept(Some("application/cbor;q=0.2, application/*;q=0.8")),
+            Encoding::Json
+        );
+    }
+
+    #[test]
+    fn responses_vary_on_accept() {
+        for encoding in &[Encoding::Json, Encoding::Cbor] {
+            let response = reply(*encoding, &vec![1u8, 2, 3]).unwrap();
+
+            assert_eq!(response.headers()[header::VARY], "accept");
+        }
+    }
+}
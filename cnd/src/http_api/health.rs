@@ -0,0 +1,219 @@
+use crate::{
+    btsieve::{ConnectedNetwork, LatestBlock},
+    connectors::Connectors,
+    ethereum::ChainId,
+};
+use comit::ledger;
+use serde::Serialize;
+use std::{fmt::Display, time::Duration};
+use warp::{http::StatusCode, reply, Filter, Rejection, Reply};
+
+/// How long we wait for a node before reporting it as unreachable, the probe
+/// itself must never hang.
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The warp filter for checking that the nodes we depend on are reachable and
+/// connected to the expected networks.
+pub fn route(
+    connectors: Connectors,
+    bitcoin: ledger::Bitcoin,
+    ethereum: ChainId,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("health"))
+        .and_then(move || handler(connectors.clone(), bitcoin, ethereum))
+}
+
+async fn handler(
+    connectors: Connectors,
+    bitcoin: ledger::Bitcoin,
+    ethereum: ChainId,
+) -> Result<impl Reply, Rejection> {
+    let health = health(
+        connectors.bitcoin_node(),
+        bitcoin,
+        connectors.ethereum_node(),
+        ethereum,
+    )
+    .await;
+
+    Ok(reply::with_status(
+        reply::json(&health),
+        health.status_code(),
+    ))
+}
+
+#[derive(Debug, Serialize)]
+struct Health {
+    bitcoin: Status,
+    ethereum: Status,
+}
+
+impl Health {
+    fn status_code(&self) -> StatusCode {
+        match (&self.bitcoin, &self.ethereum) {
+            (Status::Healthy, Status::Healthy) => StatusCode::OK,
+            _ => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum Status {
+    Healthy,
+    WrongNetwork { expected: String, actual: String },
+    Unreachable { reason: String },
+}
+
+async fn health<B, E>(
+    bitcoin: &B,
+    expected_bitcoin: ledger::Bitcoin,
+    ethereum: &E,
+    expected_ethereum: ChainId,
+) -> Health
+where
+    B: ConnectedNetwork<Network = ledger::Bitcoin> + LatestBlock,
+    E: ConnectedNetwork<Network = ChainId> + LatestBlock,
+{
+    let (bitcoin, ethereum) = futures::future::join(
+        check(bitcoin, expected_bitcoin),
+        check(ethereum, expected_ethereum),
+    )
+    .await;
+
+    Health { bitcoin, ethereum }
+}
+
+/// Asks the node itself, the cached connectors would answer from memory even
+/// if the node went away.
+async fn check<C, N>(connector: &C, expected: N) -> Status
+where
+    C: ConnectedNetwork<Network = N> + LatestBlock,
+    N: PartialEq + Display,
+{
+    let probe = async {
+        connector.latest_block().await?;
+        connector.connected_network().await
+    };
+
+    match tokio::time::timeout(TIMEOUT, probe).await {
+        Ok(Ok(actual)) if actual == expected => Status::Healthy,
+        Ok(Ok(actual)) => Status::WrongNetwork {
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        },
+        Ok(Err(e)) => {
+            tracing::warn!("health check failed: {:#}", e);
+            Status::Unreachable {
+                reason: format!("{:#}", e),
+            }
+        }
+        Err(_) => Status::Unreachable {
+            reason: format!("no response within {:?}", TIMEOUT),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use async_trait::async_trait;
+
+    struct Reachable<N>(N);
+
+    #[async_trait]
+    impl<N> ConnectedNetwork for Reachable<N>
+    where
+        N: Clone + Send + Sync + 'static,
+    {
+        type Network = N;
+
+        async fn connected_network(&self) -> Result<N> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[async_trait]
+    impl<N> LatestBlock for Reachable<N>
+    where
+        N: Send + Sync + 'static,
+    {
+        type Block = ();
+
+        async fn latest_block(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Still knows its network, as a cache would, but no block comes through.
+    struct Down;
+
+    #[async_trait]
+    impl ConnectedNetwork for Down {
+        type Network = ChainId;
+
+        async fn connected_network(&self) -> Result<ChainId> {
+            Ok(ChainId::GETH_DEV)
+        }
+    }
+
+    #[async_trait]
+    impl LatestBlock for Down {
+        type Block = ();
+
+        async fn latest_block(&self) -> Result<()> {
+            anyhow::bail!("connection refused")
+        }
+    }
+
+    #[tokio::test]
+    async fn all_nodes_healthy() {
+        let health = health(
+            &Reachable(ledger::Bitcoin::Regtest),
+            ledger::Bitcoin::Regtest,
+            &Reachable(ChainId::GETH_DEV),
+            ChainId::GETH_DEV,
+        )
+        .await;
+
+        assert_eq!(health.bitcoin, Status::Healthy);
+        assert_eq!(health.ethereum, Status::Healthy);
+        assert_eq!(health.status_code(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn one_node_down_is_unavailable() {
+        let health = health(
+            &Reachable(ledger::Bitcoin::Regtest),
+            ledger::Bitcoin::Regtest,
+            &Down,
+            ChainId::GETH_DEV,
+        )
+        .await;
+
+        assert_eq!(health.bitcoin, Status::Healthy);
+        assert_eq!(health.ethereum, Status::Unreachable {
+            reason: "connection refused".to_owned()
+        });
+        assert_eq!(health.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn node_on_wrong_network_is_unavailable() {
+        let health = health(
+            &Reachable(ledger::Bitcoin::Testnet),
+            ledger::Bitcoin::Regtest,
+            &Reachable(ChainId::GETH_DEV),
+            ChainId::GETH_DEV,
+        )
+        .await;
+
+        assert_eq!(health.bitcoin, Status::WrongNetwork {
+            expected: "regtest".to_owned(),
+            actual: "testnet".to_owned(),
+        });
+        assert_eq!(health.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -1,3 +1,7 @@
+mod btc_dai_feed;
 mod get_btc_dai;
+mod get_btc_dai_depth;
 
-pub use get_btc_dai::route as get_btc_dai;
+pub use btc_dai_feed::{route as get_btc_dai_feed, viable_orders, BtcDaiMarket};
+pub use get_btc_dai::{route as get_btc_dai, InvalidPosition, MarketItem};
+pub use get_btc_dai_depth::route as get_btc_dai_depth;
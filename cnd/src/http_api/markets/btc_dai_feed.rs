@@ -0,0 +1,409 @@
+use crate::{
+    config::Orders,
+    http_api::{
+        markets::get_btc_dai::{has_viable_expiries, MarketItem},
+        Amount,
+    },
+    network::Swarm,
+};
+use anyhow::{anyhow, Result};
+use comit::{expiries::ExpiryConfig, BtcDaiOrder, OrderId};
+use futures::{stream::SplitSink, SinkExt, StreamExt};
+use libp2p::PeerId;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+use time::OffsetDateTime;
+use tokio::sync::watch;
+use warp::{
+    ws::{Message, WebSocket, Ws},
+    Filter, Rejection, Reply,
+};
+
+/// How often we poll the market on behalf of all connected clients.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A client that does not accept a message within this time is dropped
+/// instead of buffering messages for it.
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The source of the orders shown in the feed.
+#[async_trait::async_trait]
+pub trait BtcDaiMarket: Clone + Send + Sync + 'static {
+    async fn btc_dai_market(&self) -> Vec<(PeerId, BtcDaiOrder)>;
+    fn local_peer_id(&self) -> PeerId;
+}
+
+#[async_trait::async_trait]
+impl BtcDaiMarket for Swarm {
+    async fn btc_dai_market(&self) -> Vec<(PeerId, BtcDaiOrder)> {
+        Swarm::btc_dai_market(self).await
+    }
+
+    fn local_peer_id(&self) -> PeerId {
+        Swarm::local_peer_id(self)
+    }
+}
+
+/// The warp filter for following the BTC/DAI market over a WebSocket.
+///
+/// The market is polled by a single task that publishes the viable orders to
+/// every connected client.
+pub fn route<M>(
+    market: M,
+    network: comit::Network,
+    expiry_config: ExpiryConfig,
+    order_limits: Orders,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
+where
+    M: BtcDaiMarket,
+{
+    let (sender, orders) = watch::channel(BTreeMap::new());
+    // Every client holds a clone, the poller idles while the route holds the
+    // only one and stops once the route is gone.
+    let clients = Arc::new(());
+
+    tokio::spawn(poll(
+        market.clone(),
+        network,
+        expiry_config,
+        order_limits.clone(),
+        Arc::downgrade(&clients),
+        sender,
+    ));
+
+    warp::get()
+        .and(warp::path!("markets" / "BTC-DAI" / "ws"))
+        .and(warp::ws())
+        .map(move |ws: Ws| {
+            let market = market.clone();
+            let order_limits = order_limits.clone();
+            let orders = orders.clone();
+            let client = clients.clone();
+            ws.on_upgrade(move |socket| async move {
+                let snapshot = viable_orders(&market, network, expiry_config, &order_limits).await;
+                feed(socket, snapshot, orders).await;
+                drop(client);
+            })
+        })
+}
+
+/// Publishes the viable orders whenever they change while clients are
+/// connected.
+async fn poll<M>(
+    market: M,
+    network: comit::Network,
+    expiry_config: ExpiryConfig,
+    order_limits: Orders,
+    clients: Weak<()>,
+    sender: watch::Sender<BTreeMap<OrderId, MarketItem>>,
+) where
+    M: BtcDaiMarket,
+{
+    let mut published = BTreeMap::new();
+    let mut ticks = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        ticks.tick().await;
+
+        match Weak::strong_count(&clients) {
+            0 => return,
+            1 => continue,
+            _ => {}
+        }
+
+        let current = viable_orders(&market, network, expiry_config, &order_limits).await;
+        if current == published {
+            continue;
+        }
+        if sender.broadcast(current.clone()).is_err() {
+            return;
+        }
+
+        published = current;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FeedMessage {
+    /// All viable orders, sent once the client connected.
+    Snapshot {
+        orders: Vec<MarketItem>,
+    },
+    Add {
+        order: MarketItem,
+    },
+    Update(OrderUpdate),
+    Remove {
+        id: OrderId,
+    },
+}
+
+/// The properties of an order that changed, the others never do.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct OrderUpdate {
+    id: OrderId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quantity: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price: Option<Amount>,
+}
+
+impl OrderUpdate {
+    /// `None` if none of the properties the update carries changed.
+    fn between(old: &MarketItem, new: &MarketItem) -> Option<Self> {
+        let changed = |old: &Amount, new: &Amount| Some(new.clone()).filter(|_| old != new);
+
+        let update = Self {
+            id: new.id,
+            quantity: changed(&old.quantity, &new.quantity),
+            price: changed(&old.price, &new.price),
+        };
+
+        if update.quantity.is_none() && update.price.is_none() {
+            return None;
+        }
+
+        Some(update)
+    }
+}
+
+/// Sends `known` as a snapshot of the market and then every published change
+/// to it until the client disconnects or falls behind.
+async fn feed(
+    socket: WebSocket,
+    mut known: BTreeMap<OrderId, MarketItem>,
+    mut orders: watch::Receiver<BTreeMap<OrderId, MarketItem>>,
+) {
+    let (mut sink, mut incoming) = socket.split();
+
+    let snapshot = FeedMessage::Snapshot {
+        orders: known.values().cloned().collect(),
+    };
+    if let Err(e) = send(&mut sink, &snapshot).await {
+        tracing::info!("dropping market feed client: {:#}", e);
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            message = incoming.next() => match message {
+                // we do not expect anything but control frames from the client
+                Some(Ok(message)) if !message.is_close() => {}
+                _ => return,
+            },
+            current = orders.recv() => {
+                let current = match current {
+                    Some(current) => current,
+                    None => return,
+                };
+
+                for message in diff(&known, &current) {
+                    if let Err(e) = send(&mut sink, &message).await {
+                        tracing::info!("dropping market feed client: {:#}", e);
+                        return;
+                    }
+                }
+
+                known = current;
+            }
+        }
+    }
+}
+
+async fn send(sink: &mut SplitSink<WebSocket, Message>, message: &FeedMessage) -> Result<()> {
+    let text = serde_json::to_string(message)?;
+
+    tokio::time::timeout(SEND_TIMEOUT, sink.send(Message::text(text)))
+        .await
+        .map_err(|_| anyhow!("client did not accept a message within {:?}", SEND_TIMEOUT))??;
+
+    Ok(())
+}
+
+/// The orders shown in the market view, keyed by their id.
+pub async fn viable_orders<M>(
+    market: &M,
+    network: comit::Network,
+    expiry_config: ExpiryConfig,
+    order_limits: &Orders,
+) -> BTreeMap<OrderId, MarketItem>
+where
+    M: BtcDaiMarket,
+{
+    let local_peer_id = market.local_peer_id();
+    let now = OffsetDateTime::now_utc();
+
+    market
+        .btc_dai_market()
+        .await
+        .into_iter()
+        .filter(|(_, order)| !order.is_expired(now))
+        .filter(|(_, order)| has_viable_expiries(order, network, expiry_config))
+        .filter(|(_, order)| order_limits.is_big_enough(order))
+        .map(|(maker, order)| {
+            let ours = maker == local_peer_id;
+            (order.id, MarketItem::new(order, maker, ours))
+        })
+        .collect()
+}
+
+/// The messages that bring a client that knows `old` up to date with `new`.
+fn diff(
+    old: &BTreeMap<OrderId, MarketItem>,
+    new: &BTreeMap<OrderId, MarketItem>,
+) -> Vec<FeedMessage> {
+    let mut messages = Vec::new();
+
+    for (id, item) in new {
+        match old.get(id) {
+            None => messages.push(FeedMessage::Add {
+                order: item.clone(),
+            }),
+            Some(previous) => {
+                if let Some(update) = OrderUpdate::between(previous, item) {
+                    messages.push(FeedMessage::Update(update))
+                }
+            }
+        }
+    }
+
+    for id in old.keys().filter(|id| !new.contains_key(id)) {
+        messages.push(FeedMessage::Remove { id: *id });
+    }
+
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use comit::{asset, order::SwapProtocol, Position, Price, Quantity, Role};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone)]
+    struct TestMarket {
+        orders: Arc<Mutex<Vec<(PeerId, BtcDaiOrder)>>>,
+        local_peer_id: PeerId,
+    }
+
+    #[async_trait::async_trait]
+    impl BtcDaiMarket for TestMarket {
+        async fn btc_dai_market(&self) -> Vec<(PeerId, BtcDaiOrder)> {
+            self.orders.lock().unwrap().clone()
+        }
+
+        fn local_peer_id(&self) -> PeerId {
+            self.local_peer_id.clone()
+        }
+    }
+
+    fn sell_order(sats: u64) -> BtcDaiOrder {
+        BtcDaiOrder::sell(
+            Quantity::new(asset::Bitcoin::from_sat(sats)),
+            Price::from_wei_per_sat(asset::Erc20Quantity::zero()),
+            SwapProtocol::new(Role::Alice, Position::Sell, comit::Network::Dev),
+        )
+    }
+
+    fn json(message: warp::ws::Message) -> serde_json::Value {
+        serde_json::from_str(message.to_str().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn sends_snapshot_then_added_order() {
+        let maker = PeerId::random();
+        let existing = sell_order(1_000_000);
+        let market = TestMarket {
+            orders: Arc::new(Mutex::new(vec![(maker.clone(), existing.clone())])),
+            local_peer_id: PeerId::random(),
+        };
+        let filter = route(
+            market.clone(),
+            comit::Network::Dev,
+            ExpiryConfig::from_network(comit::Network::Dev),
+            Orders::default(),
+        );
+
+        let mut client = warp::test::ws()
+            .path("/markets/BTC-DAI/ws")
+            .handshake(filter)
+            .await
+            .unwrap();
+
+        let snapshot = json(client.recv().await.unwrap());
+        assert_eq!(snapshot["type"], "snapshot");
+        assert_eq!(snapshot["orders"].as_array().unwrap().len(), 1);
+        assert_eq!(snapshot["orders"][0]["id"], existing.id.to_string());
+
+        let added = sell_order(2_000_000);
+        market.orders.lock().unwrap().push((maker, added.clone()));
+
+        let increment = json(client.recv().await.unwrap());
+        assert_eq!(increment["type"], "add");
+        assert_eq!(increment["order"]["id"], added.id.to_string());
+    }
+
+    #[test]
+    fn diff_carries_only_changed_fields() {
+        let maker = PeerId::random();
+        let order = sell_order(1_000_000);
+        let removed = sell_order(3_000_000);
+        let old = vec![
+            (
+                order.id,
+                MarketItem::new(order.clone(), maker.clone(), false),
+            ),
+            (
+                removed.id,
+                MarketItem::new(removed.clone(), maker.clone(), false),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let partially_taken = BtcDaiOrder {
+            quantity: Quantity::new(asset::Bitcoin::from_sat(400_000)),
+            ..order.clone()
+        };
+        let new = vec![(
+            order.id,
+            MarketItem::new(partially_taken.clone(), maker, false),
+        )]
+        .into_iter()
+        .collect();
+
+        let messages = diff(&old, &new);
+
+        assert_eq!(messages, vec![
+            FeedMessage::Update(OrderUpdate {
+                id: order.id,
+                quantity: Some(Amount::from(partially_taken.quantity)),
+                price: None,
+            }),
+            FeedMessage::Remove { id: removed.id },
+        ]);
+        let json = serde_json::to_value(&messages[0]).unwrap();
+        assert_eq!(json["type"], "update");
+        assert!(json.get("price").is_none());
+    }
+
+    #[test]
+    fn no_update_if_only_unsent_fields_changed() {
+        let order = sell_order(1_000_000);
+        let old = vec![(
+            order.id,
+            MarketItem::new(order.clone(), PeerId::random(), false),
+        )]
+        .into_iter()
+        .collect();
+        let new = vec![(order.id, MarketItem::new(order, PeerId::random(), true))]
+            .into_iter()
+            .collect();
+
+        assert_eq!(diff(&old, &new), vec![]);
+    }
+}
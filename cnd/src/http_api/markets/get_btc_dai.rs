@@ -1,94 +1,244 @@
 use crate::{
-    http_api::{problem, serde_peer_id, Amount},
+    config::Orders,
+    http_api::{encoding, encoding::Encoding, page::Page, problem, serde_peer_id, Amount},
     network::Swarm,
 };
 use anyhow::{Context, Result};
-use comit::{expiries, order::SwapProtocol, BtcDaiOrder, OrderId, Position};
+use comit::{
+    expiries::{self, ExpiryConfig},
+    order::SwapProtocol,
+    BtcDaiOrder, OrderId, Position,
+};
 use futures::TryFutureExt;
 use libp2p::PeerId;
-use serde::Serialize;
-use warp::{reply, Filter, Rejection, Reply};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use warp::{Filter, Rejection, Reply};
+
+const PATH: &str = "/markets/BTC-DAI";
 
 /// The warp filter for getting the BTC/DAI market view.
 pub fn route(
     swarm: Swarm,
     network: comit::Network,
+    expiry_config: ExpiryConfig,
+    order_limits: Orders,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::get()
         .and(warp::path!("markets" / "BTC-DAI"))
-        .and_then(move || {
-            handler(swarm.clone(), network)
-                .map_err(problem::from_anyhow)
-                .map_err(warp::reject::custom)
+        .and(warp::query::<Query>())
+        .and(encoding::accept())
+        .and_then(move |query, encoding| {
+            handler(
+                swarm.clone(),
+                network,
+                expiry_config,
+                order_limits.clone(),
+                query,
+                encoding,
+            )
+            .map_err(problem::from_anyhow)
+            .map_err(warp::reject::custom)
         })
 }
 
 /// Retrieves viable orders: orders that have expiries that match the safe
-/// expiries determined by the expiries module.
-async fn handler(swarm: Swarm, network: comit::Network) -> Result<impl Reply> {
-    let mut orders = siren::Entity::default();
+/// expiries determined by the expiries module for the configured confirmation
+/// targets and that are not smaller than the configured minimum.
+///
+/// Orders are sorted by price and then by id so that pages are stable
+/// across requests. The whole market is returned unless a `limit` is given.
+async fn handler(
+    swarm: Swarm,
+    network: comit::Network,
+    expiry_config: ExpiryConfig,
+    order_limits: Orders,
+    query: Query,
+    encoding: Encoding,
+) -> Result<impl Reply> {
+    let filters = query.filters()?;
     let local_peer_id = swarm.local_peer_id();
+    let now = OffsetDateTime::now_utc();
 
-    let viable_orders = swarm
+    let mut viable_orders = swarm
         .btc_dai_market()
         .await
         .into_iter()
-        .filter(|(_, order)| has_viable_expiries(order, network));
+        .filter(|(_, order)| !order.is_expired(now))
+        .filter(|(_, order)| has_viable_expiries(order, network, expiry_config))
+        .filter(|(_, order)| order_limits.is_big_enough(order))
+        .filter(|(maker, order)| filters.matches(maker == &local_peer_id, order))
+        .collect::<Vec<_>>();
+    viable_orders.sort_by(|(_, left), (_, right)| {
+        left.price
+            .cmp(&right.price)
+            .then_with(|| left.id.cmp(&right.id))
+    });
+
+    let mut orders = siren::Entity::default();
+    if let Some(page) = query.page() {
+        let total = viable_orders.len();
+
+        if let Some(prev) = page.prev(total) {
+            orders = orders.with_link(siren::NavigationalLink::new(&["prev"], filters.href(&prev)));
+        }
+        if let Some(next) = page.next(total) {
+            orders = orders.with_link(siren::NavigationalLink::new(&["next"], filters.href(&next)));
+        }
+
+        viable_orders = page.slice(viable_orders).collect();
+    }
 
     for (maker, order) in viable_orders {
         let market_item = siren::Entity::default()
-            .with_properties(MarketItem {
-                id: order.id,
-                quantity: Amount::from(order.quantity),
-                price: Amount::from(order.price),
-                ours: maker == local_peer_id,
-                maker,
-                position: order.position,
-            })
+            .with_properties(MarketItem::new(
+                order,
+                maker.clone(),
+                maker == local_peer_id,
+            ))
             .context("failed to serialize market item sub entity")?;
 
         orders.push_sub_entity(siren::SubEntity::from_entity(market_item, &["item"]))
     }
 
-    Ok(reply::json(&orders))
+    encoding::reply(encoding, &orders)
 }
 
-pub fn has_viable_expiries(order: &BtcDaiOrder, network: comit::Network) -> bool {
+pub fn has_viable_expiries(
+    order: &BtcDaiOrder,
+    network: comit::Network,
+    expiry_config: ExpiryConfig,
+) -> bool {
     match order.swap_protocol {
         SwapProtocol::HbitHerc20 {
             hbit_expiry_offset,
             herc20_expiry_offset,
         } => {
             (hbit_expiry_offset, herc20_expiry_offset)
-                == expiries::expiry_offsets_hbit_herc20(network)
+                == expiries::expiry_offsets_hbit_herc20_with_config(network, expiry_config)
         }
         SwapProtocol::Herc20Hbit {
             herc20_expiry_offset,
             hbit_expiry_offset,
         } => {
             (herc20_expiry_offset, hbit_expiry_offset)
-                == expiries::expiry_offsets_herc20_hbit(network)
+                == expiries::expiry_offsets_herc20_hbit_with_config(network, expiry_config)
         }
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
-struct MarketItem {
-    id: OrderId,
+#[derive(Clone, Debug, Default, Deserialize)]
+struct Query {
+    limit: Option<usize>,
+    offset: Option<usize>,
+    ours: Option<bool>,
+    position: Option<String>,
+}
+
+impl Query {
+    fn filters(&self) -> Result<Filters, InvalidPosition> {
+        let position = match &self.position {
+            Some(position) => Some(
+                position
+                    .parse()
+                    .map_err(|_| InvalidPosition(position.clone()))?,
+            ),
+            None => None,
+        };
+
+        Ok(Filters {
+            ours: self.ours,
+            position,
+        })
+    }
+
+    /// The `offset` only applies together with a `limit`, without one the
+    /// whole market is requested.
+    fn page(&self) -> Option<Page> {
+        self.limit.map(|limit| Page::new(self.offset, Some(limit)))
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone)]
+#[error("invalid position '{0}', expected 'buy' or 'sell'")]
+pub struct InvalidPosition(pub String);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Filters {
+    ours: Option<bool>,
+    position: Option<Position>,
+}
+
+impl Filters {
+    fn matches(&self, ours: bool, order: &BtcDaiOrder) -> bool {
+        self.ours.map_or(true, |expected| expected == ours)
+            && self
+                .position
+                .map_or(true, |position| position == order.position)
+    }
+
+    /// Link to `page` of the market view, keeping these filters.
+    fn href(&self, page: &Page) -> String {
+        let mut href = format!("{}?{}", PATH, page.query());
+
+        if let Some(ours) = self.ours {
+            href.push_str(&format!("&ours={}", ours));
+        }
+        if let Some(position) = self.position {
+            href.push_str(&format!("&position={}", position));
+        }
+
+        href
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MarketItem {
+    pub(super) id: OrderId,
     #[serde(with = "serde_peer_id")]
     maker: PeerId,
     ours: bool,
     position: Position,
-    quantity: Amount,
-    price: Amount,
+    pub(super) quantity: Amount,
+    pub(super) price: Amount,
+    /// Seconds after the start of the swap at which the hbit HTLC expires.
+    hbit_expiry_offset: i64,
+    /// Seconds after the start of the swap at which the herc20 HTLC expires.
+    herc20_expiry_offset: i64,
+}
+
+impl MarketItem {
+    pub(super) fn new(order: BtcDaiOrder, maker: PeerId, ours: bool) -> Self {
+        Self {
+            id: order.id,
+            maker,
+            ours,
+            position: order.position,
+            quantity: Amount::from(order.quantity),
+            price: Amount::from(order.price),
+            hbit_expiry_offset: order.swap_protocol.hbit_expiry_offset().whole_seconds(),
+            herc20_expiry_offset: order.swap_protocol.herc20_expiry_offset().whole_seconds(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::http_api::markets::get_btc_dai::has_viable_expiries;
-    use comit::{asset, order::SwapProtocol, BtcDaiOrder, Position, Price, Quantity, Role};
+    use crate::http_api::{
+        markets::get_btc_dai::{has_viable_expiries, Filters, MarketItem, Query},
+        page::{Page, MAX_PAGE_SIZE},
+        problem,
+    };
+    use comit::{
+        asset,
+        expiries::{self, ExpiryConfig},
+        order::SwapProtocol,
+        BtcDaiOrder, Position, Price, Quantity, Role,
+    };
+    use libp2p::PeerId;
     use spectral::{assert_that, prelude::MappingIterAssertions};
     use time::Duration;
+    use warp::http::StatusCode;
 
     #[test]
     fn filter_out_orders_with_unviable_expiries() {
@@ -100,7 +250,13 @@ mod tests {
 
         let filtered_orders = unfiltered_orders
             .into_iter()
-            .filter(|order| has_viable_expiries(order, comit::Network::Dev))
+            .filter(|order| {
+                has_viable_expiries(
+                    order,
+                    comit::Network::Dev,
+                    ExpiryConfig::from_network(comit::Network::Dev),
+                )
+            })
             .collect::<Vec<BtcDaiOrder>>();
 
         assert_eq!(filtered_orders.len(), 1);
@@ -108,6 +264,222 @@ mod tests {
             .matching_contains(|order| order_with_viable_expiries.id == order.id);
     }
 
+    #[test]
+    fn orders_are_validated_against_the_configured_expiries() {
+        let network = comit::Network::Dev;
+        let custom = ExpiryConfig {
+            bitcoin_confirmation_target: 10,
+            ethereum_confirmation_target: 10,
+        };
+        let (hbit_expiry_offset, herc20_expiry_offset) =
+            expiries::expiry_offsets_hbit_herc20_with_config(network, custom);
+        let order_with_custom_expiries = BtcDaiOrder::sell(
+            Quantity::new(asset::Bitcoin::ZERO),
+            Price::from_wei_per_sat(asset::Erc20Quantity::zero()),
+            SwapProtocol::HbitHerc20 {
+                hbit_expiry_offset,
+                herc20_expiry_offset,
+            },
+        );
+
+        assert!(has_viable_expiries(
+            &order_with_custom_expiries,
+            network,
+            custom
+        ));
+        assert!(!has_viable_expiries(
+            &order_with_custom_expiries,
+            network,
+            ExpiryConfig::from_network(network)
+        ));
+        assert!(!has_viable_expiries(
+            &order_with_viable_expiries(),
+            network,
+            custom
+        ));
+    }
+
+    #[test]
+    fn query_without_limit_yields_the_whole_market() {
+        let only_offset = Query {
+            offset: Some(10),
+            ..Query::default()
+        };
+
+        assert_eq!(Query::default().page(), None);
+        assert_eq!(only_offset.page(), None);
+    }
+
+    #[test]
+    fn query_with_limit_yields_a_page() {
+        let query = Query {
+            limit: Some(10),
+            offset: Some(20),
+            ..Query::default()
+        };
+
+        assert_eq!(
+            query.page(),
+            Some(Page {
+                offset: 20,
+                limit: 10
+            })
+        );
+    }
+
+    #[test]
+    fn query_limit_is_clamped() {
+        let too_small = Query {
+            limit: Some(0),
+            ..Query::default()
+        };
+        let too_large = Query {
+            limit: Some(MAX_PAGE_SIZE + 1),
+            ..Query::default()
+        };
+
+        assert_eq!(too_small.page().unwrap().limit, 1);
+        assert_eq!(too_large.page().unwrap().limit, MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn first_page_links_only_to_next() {
+        let page = Page {
+            offset: 0,
+            limit: 4,
+        };
+
+        assert_eq!(page.prev(10), None);
+        assert_eq!(
+            page.next(10).map(|page| Filters::default().href(&page)),
+            Some("/markets/BTC-DAI?offset=4&limit=4".to_owned())
+        );
+    }
+
+    #[test]
+    fn last_page_links_only_to_prev() {
+        let page = Page {
+            offset: 8,
+            limit: 4,
+        };
+
+        assert_eq!(
+            page.prev(10).map(|page| Filters::default().href(&page)),
+            Some("/markets/BTC-DAI?offset=4&limit=4".to_owned())
+        );
+        assert_eq!(page.next(10), None);
+    }
+
+    #[test]
+    fn links_preserve_ours_filter() {
+        let page = Page {
+            offset: 4,
+            limit: 4,
+        };
+
+        assert_eq!(
+            page.next(10).map(|page| Filters {
+                ours: Some(true),
+                position: Some(Position::Sell)
+            }
+            .href(&page)),
+            Some("/markets/BTC-DAI?offset=8&limit=4&ours=true&position=sell".to_owned())
+        );
+    }
+
+    #[test]
+    fn filter_orders_by_position() {
+        let buy = BtcDaiOrder::buy(
+            Quantity::new(asset::Bitcoin::ZERO),
+            Price::from_wei_per_sat(asset::Erc20Quantity::zero()),
+            SwapProtocol::new(Role::Alice, Position::Buy, comit::Network::Dev),
+        );
+        let sell = order_with_viable_expiries();
+        let orders = vec![buy.clone(), sell.clone(), buy.clone()];
+
+        let only = |position: &str| {
+            let filters = Query {
+                position: Some(position.to_owned()),
+                ..Query::default()
+            }
+            .filters()
+            .unwrap();
+
+            orders
+                .iter()
+                .filter(|order| filters.matches(false, order))
+                .map(|order| order.id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(only("buy"), vec![buy.id, buy.id]);
+        assert_eq!(only("sell"), vec![sell.id]);
+    }
+
+    #[test]
+    fn omitted_position_does_not_filter() {
+        let filters = Query::default().filters().unwrap();
+
+        assert_eq!(filters, Filters::default());
+        assert!(filters.matches(false, &order_with_viable_expiries()));
+        assert!(filters.matches(true, &order_with_viable_expiries()));
+    }
+
+    #[test]
+    fn garbage_position_is_a_bad_request() {
+        let query = Query {
+            position: Some("sideways".to_owned()),
+            ..Query::default()
+        };
+
+        let error = query.filters().unwrap_err();
+        let problem = problem::from_anyhow(error.into());
+
+        assert_eq!(problem.status, Some(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn market_item_exposes_expiry_offsets_of_hbit_herc20() {
+        let order = BtcDaiOrder::sell(
+            Quantity::new(asset::Bitcoin::ZERO),
+            Price::from_wei_per_sat(asset::Erc20Quantity::zero()),
+            SwapProtocol::new(Role::Alice, Position::Sell, comit::Network::Dev),
+        );
+        let (hbit, herc20) = expiries::expiry_offsets_hbit_herc20(comit::Network::Dev);
+
+        let json = serde_json::to_value(MarketItem::new(order, PeerId::random(), true)).unwrap();
+
+        assert_eq!(
+            json["hbit_expiry_offset"],
+            Duration::from(hbit).whole_seconds()
+        );
+        assert_eq!(
+            json["herc20_expiry_offset"],
+            Duration::from(herc20).whole_seconds()
+        );
+    }
+
+    #[test]
+    fn market_item_exposes_expiry_offsets_of_herc20_hbit() {
+        let order = BtcDaiOrder::buy(
+            Quantity::new(asset::Bitcoin::ZERO),
+            Price::from_wei_per_sat(asset::Erc20Quantity::zero()),
+            SwapProtocol::new(Role::Alice, Position::Buy, comit::Network::Dev),
+        );
+        let (herc20, hbit) = expiries::expiry_offsets_herc20_hbit(comit::Network::Dev);
+
+        let json = serde_json::to_value(MarketItem::new(order, PeerId::random(), false)).unwrap();
+
+        assert_eq!(
+            json["hbit_expiry_offset"],
+            Duration::from(hbit).whole_seconds()
+        );
+        assert_eq!(
+            json["herc20_expiry_offset"],
+            Duration::from(herc20).whole_seconds()
+        );
+    }
+
     fn order_with_viable_expiries() -> BtcDaiOrder {
         BtcDaiOrder::sell(
             Quantity::new(asset::Bitcoin::ZERO),
@@ -0,0 +1,179 @@
+use crate::{
+    config::Orders,
+    http_api::{
+        encoding, encoding::Encoding, markets::get_btc_dai::has_viable_expiries, problem, Amount,
+    },
+    network::Swarm,
+};
+use anyhow::{Context, Result};
+use comit::{asset, expiries::ExpiryConfig, BtcDaiOrder, Position, Price, Quantity};
+use futures::TryFutureExt;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+use warp::{Filter, Rejection, Reply};
+
+/// The warp filter for getting the aggregated depth of the BTC/DAI market.
+pub fn route(
+    swarm: Swarm,
+    network: comit::Network,
+    expiry_config: ExpiryConfig,
+    order_limits: Orders,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("markets" / "BTC-DAI" / "depth"))
+        .and(encoding::accept())
+        .and_then(move |encoding| {
+            handler(
+                swarm.clone(),
+                network,
+                expiry_config,
+                order_limits.clone(),
+                encoding,
+            )
+            .map_err(problem::from_anyhow)
+            .map_err(warp::reject::custom)
+        })
+}
+
+/// Aggregates the viable orders of the market into price levels.
+async fn handler(
+    swarm: Swarm,
+    network: comit::Network,
+    expiry_config: ExpiryConfig,
+    order_limits: Orders,
+    encoding: Encoding,
+) -> Result<impl Reply> {
+    let now = OffsetDateTime::now_utc();
+    let viable_orders = swarm
+        .btc_dai_market()
+        .await
+        .into_iter()
+        .map(|(_, order)| order)
+        .filter(|order| !order.is_expired(now))
+        .filter(|order| has_viable_expiries(order, network, expiry_config))
+        .filter(|order| order_limits.is_big_enough(order));
+
+    let depth = siren::Entity::default()
+        .with_properties(Depth::aggregate(viable_orders)?)
+        .context("failed to serialize market depth")?;
+
+    encoding::reply(encoding, &depth)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct Depth {
+    /// Buy orders, best (highest) price first.
+    bids: Vec<PriceLevel>,
+    /// Sell orders, best (lowest) price first.
+    asks: Vec<PriceLevel>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct PriceLevel {
+    price: Amount,
+    quantity: Amount,
+    /// The sum of the quantities of this and all better price levels.
+    cumulative_quantity: Amount,
+}
+
+type Levels = BTreeMap<Price<asset::Bitcoin, asset::Erc20Quantity>, asset::Bitcoin>;
+
+impl Depth {
+    fn aggregate(orders: impl IntoIterator<Item = BtcDaiOrder>) -> Result<Self> {
+        let mut bids = Levels::new();
+        let mut asks = Levels::new();
+
+        for order in orders {
+            let levels = match order.position {
+                Position::Buy => &mut bids,
+                Position::Sell => &mut asks,
+            };
+
+            let quantity = levels.entry(order.price).or_insert(asset::Bitcoin::ZERO);
+            *quantity = quantity
+                .checked_add(order.quantity.to_inner())
+                .context("quantity of price level overflowed")?;
+        }
+
+        Ok(Depth {
+            bids: accumulate(bids.into_iter().rev())?,
+            asks: accumulate(asks.into_iter())?,
+        })
+    }
+}
+
+fn accumulate(
+    levels: impl Iterator<Item = (Price<asset::Bitcoin, asset::Erc20Quantity>, asset::Bitcoin)>,
+) -> Result<Vec<PriceLevel>> {
+    let mut cumulative_quantity = Amount::from(Quantity::new(asset::Bitcoin::ZERO));
+
+    levels
+        .map(|(price, quantity)| {
+            let quantity = Amount::from(Quantity::new(quantity));
+            cumulative_quantity = cumulative_quantity
+                .add(&quantity)
+                .context("failed to add up cumulative quantity")?;
+
+            Ok(PriceLevel {
+                price: Amount::from(price),
+                quantity,
+                cumulative_quantity: cumulative_quantity.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use comit::{asset::ethereum::FromWei, order::SwapProtocol, Role};
+
+    #[test]
+    fn aggregates_orders_by_position_and_price_level() {
+        let orders = vec![
+            order(Position::Buy, 100, 1_000),
+            order(Position::Buy, 200, 1_000),
+            order(Position::Buy, 50, 1_100),
+            order(Position::Sell, 300, 1_200),
+            order(Position::Sell, 400, 1_300),
+            order(Position::Sell, 25, 1_200),
+        ];
+
+        let depth = Depth::aggregate(orders).unwrap();
+
+        assert_eq!(depth, Depth {
+            bids: vec![level(1_100, 50, 50), level(1_000, 300, 350)],
+            asks: vec![level(1_200, 325, 325), level(1_300, 400, 725)],
+        });
+    }
+
+    #[test]
+    fn empty_market_has_no_levels() {
+        let depth = Depth::aggregate(vec![]).unwrap();
+
+        assert!(depth.bids.is_empty());
+        assert!(depth.asks.is_empty());
+    }
+
+    fn order(position: Position, sats: u64, wei_per_sat: u64) -> BtcDaiOrder {
+        BtcDaiOrder::new(
+            position,
+            Quantity::new(asset::Bitcoin::from_sat(sats)),
+            Price::from_wei_per_sat(asset::Erc20Quantity::from_wei(wei_per_sat)),
+            SwapProtocol::new(Role::Alice, position, comit::Network::Dev),
+        )
+    }
+
+    fn level(wei_per_sat: u64, sats: u64, cumulative_sats: u64) -> PriceLevel {
+        PriceLevel {
+            price: Amount::from(Price::from_wei_per_sat(asset::Erc20Quantity::from_wei(
+                wei_per_sat,
+            ))),
+            quantity: Amount::from(Quantity::new(asset::Bitcoin::from_sat(sats))),
+            cumulative_quantity: Amount::from(Quantity::new(asset::Bitcoin::from_sat(
+                cumulative_sats,
+            ))),
+        }
+    }
+}
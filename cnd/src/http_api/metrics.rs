@@ -0,0 +1,209 @@
+use crate::{
+    btsieve::bitcoin::CacheStats,
+    connectors::Connectors,
+    http_api::{
+        problem,
+        swaps::{swap_phases, SwapPhase},
+    },
+    network::Swarm,
+    storage::{queries::count_open_and_cancelled_btc_dai_orders, Storage},
+};
+use anyhow::Result;
+use futures::TryFutureExt;
+use std::convert::TryFrom;
+use strum::IntoEnumIterator;
+use warp::{http::header::CONTENT_TYPE, reply, Filter, Rejection, Reply};
+
+const CONTENT_TYPE_PROMETHEUS: &str = "text/plain; version=0.0.4";
+
+/// The warp filter for scraping metrics in the Prometheus text format.
+pub fn route(
+    storage: Storage,
+    swarm: Swarm,
+    connectors: Connectors,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get().and(warp::path!("metrics")).and_then(move || {
+        handler(storage.clone(), swarm.clone(), connectors.clone())
+            .map_err(problem::from_anyhow)
+            .map_err(warp::reject::custom)
+    })
+}
+
+async fn handler(storage: Storage, swarm: Swarm, connectors: Connectors) -> Result<impl Reply> {
+    let (open_orders, cancelled_orders) = storage
+        .db
+        .do_in_transaction(count_open_and_cancelled_btc_dai_orders)
+        .await?;
+    let phases = swap_phases(&storage).await?;
+    let bitcoin_block_cache = connectors.bitcoin_block_cache_stats().await;
+    let connected_peers = swarm.connected_peers().await.count();
+
+    let snapshot = Snapshot {
+        swaps: SwapPhase::iter()
+            .map(|phase| {
+                let count = phases.iter().filter(|(_, p)| *p == phase).count();
+                (phase, u64::try_from(count).unwrap_or(u64::MAX))
+            })
+            .collect(),
+        open_orders,
+        cancelled_orders,
+        bitcoin_block_cache,
+        connected_peers: u64::try_from(connected_peers).unwrap_or(u64::MAX),
+    };
+
+    Ok(reply::with_header(
+        snapshot.render(),
+        CONTENT_TYPE,
+        CONTENT_TYPE_PROMETHEUS,
+    ))
+}
+
+#[derive(Debug)]
+struct Snapshot {
+    swaps: Vec<(SwapPhase, u64)>,
+    open_orders: u64,
+    cancelled_orders: u64,
+    bitcoin_block_cache: CacheStats,
+    connected_peers: u64,
+}
+
+impl Snapshot {
+    fn render(&self) -> String {
+        let cache = self.bitcoin_block_cache;
+        let mut registry = Registry::default();
+
+        registry.family("cnd_swaps", "gauge", "Number of swaps by state.");
+        for (phase, count) in &self.swaps {
+            registry.sample("cnd_swaps", Some(("state", &phase.to_string())), *count);
+        }
+
+        registry.family("cnd_orders", "gauge", "Number of BTC/DAI orders by state.");
+        registry.sample("cnd_orders", Some(("state", "open")), self.open_orders);
+        registry.sample(
+            "cnd_orders",
+            Some(("state", "cancelled")),
+            self.cancelled_orders,
+        );
+
+        registry.family(
+            "cnd_bitcoin_block_cache_hits_total",
+            "counter",
+            "Blocks served from the bitcoin block cache.",
+        );
+        registry.sample("cnd_bitcoin_block_cache_hits_total", None, cache.hits);
+        registry.family(
+            "cnd_bitcoin_block_cache_misses_total",
+            "counter",
+            "Blocks fetched from bitcoind because they were not cached.",
+        );
+        registry.sample("cnd_bitcoin_block_cache_misses_total", None, cache.misses);
+        registry.family(
+            "cnd_bitcoin_block_cache_evictions_total",
+            "counter",
+            "Blocks evicted from the bitcoin block cache.",
+        );
+        registry.sample(
+            "cnd_bitcoin_block_cache_evictions_total",
+            None,
+            cache.evictions,
+        );
+
+        registry.family(
+            "cnd_bitcoin_block_cache_blocks",
+            "gauge",
+            "Number of blocks currently held in the bitcoin block cache.",
+        );
+        registry.sample(
+            "cnd_bitcoin_block_cache_blocks",
+            None,
+            u64::try_from(cache.len).unwrap_or(u64::MAX),
+        );
+
+        registry.family("cnd_connected_peers", "gauge", "Number of connected peers.");
+        registry.sample("cnd_connected_peers", None, self.connected_peers);
+
+        registry.output
+    }
+}
+
+/// Just enough of the Prometheus text exposition format for our needs.
+#[derive(Debug, Default)]
+struct Registry {
+    output: String,
+}
+
+impl Registry {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        self.output.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} {}\n",
+            name, help, name, kind
+        ));
+    }
+
+    fn sample(&mut self, name: &str, label: Option<(&str, &str)>, value: u64) {
+        match label {
+            Some((key, label)) => self
+                .output
+                .push_str(&format!("{}{{{}=\"{}\"}} {}\n", name, key, label, value)),
+            None => self.output.push_str(&format!("{} {}\n", name, value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendered_metrics_are_well_formed() {
+        let snapshot = Snapshot {
+            swaps: SwapPhase::iter().map(|phase| (phase, 1)).collect(),
+            open_orders: 2,
+            cancelled_orders: 3,
+            bitcoin_block_cache: CacheStats {
+                hits: 4,
+                misses: 5,
+                evictions: 6,
+                len: 7,
+            },
+            connected_peers: 8,
+        };
+
+        let output = snapshot.render();
+
+        let names = output
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let mut parts = line.split(' ');
+                let series = parts.next().unwrap();
+                let value = parts.next().unwrap();
+                assert!(parts.next().is_none(), "unexpected token in {}", line);
+                assert!(value.parse::<f64>().is_ok(), "invalid value in {}", line);
+
+                let name = series.split('{').next().unwrap();
+                assert!(
+                    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+                    "invalid metric name in {}",
+                    line
+                );
+
+                name.to_owned()
+            })
+            .collect::<Vec<_>>();
+
+        for expected in &[
+            "cnd_swaps",
+            "cnd_orders",
+            "cnd_bitcoin_block_cache_hits_total",
+            "cnd_bitcoin_block_cache_misses_total",
+            "cnd_bitcoin_block_cache_evictions_total",
+            "cnd_bitcoin_block_cache_blocks",
+            "cnd_connected_peers",
+        ] {
+            assert!(names.iter().any(|name| name == expected), "{}", expected);
+        }
+        assert!(output.contains("cnd_swaps{state=\"settling\"} 1\n"));
+        assert!(output.contains("cnd_orders{state=\"cancelled\"} 3\n"));
+    }
+}
@@ -1,9 +1,11 @@
 mod cancel;
+mod cancel_all;
 mod get_single;
 mod list_open;
 mod make_btc_dai;
 
-pub use cancel::route as cancel;
+pub use cancel::{cancel_order, route as cancel};
+pub use cancel_all::route as cancel_all;
 pub use get_single::route as get_single;
 pub use list_open::route as list_open;
 pub use make_btc_dai::route as make_btc_dai;
@@ -23,11 +23,18 @@ pub fn route(
 }
 
 async fn handler(order_id: OrderId, storage: Storage, swarm: Swarm) -> Result<impl Reply> {
-    let db = &storage.db;
+    cancel_order(order_id, &storage, &swarm).await?;
 
-    db.do_in_transaction(|conn| commands::update_btc_dai_order_to_cancelled(conn, order_id))
+    Ok(warp::reply())
+}
+
+/// Cancels the order in our database and withdraws it from the network.
+pub async fn cancel_order(order_id: OrderId, storage: &Storage, swarm: &Swarm) -> Result<()> {
+    storage
+        .db
+        .do_in_transaction(|conn| commands::update_btc_dai_order_to_cancelled(conn, order_id))
         .await?;
     swarm.cancel_order(order_id).await;
 
-    Ok(warp::reply())
+    Ok(())
 }
@@ -0,0 +1,179 @@
+use crate::{
+    http_api::{problem, OrderProperties},
+    network::Swarm,
+    storage::{commands, commands::NotOpen, queries::all_open_btc_dai_orders, Sqlite, Storage},
+};
+use anyhow::Result;
+use comit::OrderId;
+use futures::TryFutureExt;
+use serde::Serialize;
+use warp::{reply, Filter, Rejection, Reply};
+
+/// The warp filter for cancelling all open orders.
+pub fn route(
+    storage: Storage,
+    swarm: Swarm,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::delete().and(warp::path!("orders")).and_then(move || {
+        handler(storage.clone(), swarm.clone())
+            .map_err(problem::from_anyhow)
+            .map_err(warp::reject::custom)
+    })
+}
+
+async fn handler(storage: Storage, swarm: Swarm) -> Result<impl Reply> {
+    let cancellation = cancel_open_orders(&storage.db).await?;
+
+    for order_id in &cancellation.cancelled {
+        swarm.cancel_order(*order_id).await;
+    }
+
+    Ok(reply::json(&Summary::from(cancellation)))
+}
+
+#[derive(Debug)]
+struct Cancellation {
+    cancelled: Vec<OrderId>,
+    failed: Vec<Failure>,
+}
+
+#[derive(Debug, Serialize)]
+struct Failure {
+    id: OrderId,
+    reason: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Summary {
+    cancelled: usize,
+    failed: Vec<Failure>,
+}
+
+impl From<Cancellation> for Summary {
+    fn from(cancellation: Cancellation) -> Self {
+        Self {
+            cancelled: cancellation.cancelled.len(),
+            failed: cancellation.failed,
+        }
+    }
+}
+
+/// Cancels every order that is still open in the database.
+///
+/// Orders that are no longer open by the time we get to them are skipped,
+/// which makes cancelling all orders idempotent.
+async fn cancel_open_orders(db: &Sqlite) -> Result<Cancellation> {
+    let open_orders = db
+        .do_in_transaction(|conn| all_open_btc_dai_orders(conn))
+        .await?
+        .into_iter()
+        .map(OrderProperties::from)
+        .filter(|order| order.state.is_open())
+        .map(|order| order.id);
+
+    let mut cancelled = Vec::new();
+    let mut failed = Vec::new();
+
+    for order_id in open_orders {
+        match db
+            .do_in_transaction(|conn| commands::update_btc_dai_order_to_cancelled(conn, order_id))
+            .await
+        {
+            Ok(()) => cancelled.push(order_id),
+            Err(e) if e.is::<NotOpen>() => {}
+            Err(e) => {
+                tracing::warn!("failed to cancel order {}: {:#}", order_id, e);
+                failed.push(Failure {
+                    id: order_id,
+                    reason: format!("{:#}", e),
+                })
+            }
+        }
+    }
+
+    Ok(Cancellation { cancelled, failed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{BtcDaiOrder, InsertableBtcDaiOrder, InsertableOrder, Order};
+    use comit::{
+        asset::{ethereum::FromWei, Bitcoin, Erc20Quantity},
+        Position,
+    };
+    use time::OffsetDateTime;
+
+    async fn insert_order(db: &Sqlite) -> OrderId {
+        let order_id = OrderId::random();
+
+        db.do_in_transaction(|conn| {
+            let order_fk =
+                InsertableOrder::new(order_id, Position::Sell, OffsetDateTime::now_utc(), None)
+                    .insert(conn)?;
+            InsertableBtcDaiOrder::new(
+                order_fk,
+                Bitcoin::from_sat(1_000_000),
+                Erc20Quantity::from_wei(9_000_000_000_000u64),
+            )
+            .insert(conn)
+        })
+        .await
+        .unwrap();
+
+        order_id
+    }
+
+    async fn load(db: &Sqlite, order_id: OrderId) -> BtcDaiOrder {
+        db.do_in_transaction(|conn| {
+            let order = Order::by_order_id(conn, order_id)?;
+            BtcDaiOrder::by_order(conn, &order)
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn only_open_orders_are_cancelled() {
+        let db = Sqlite::test();
+        let first_open = insert_order(&db).await;
+        let second_open = insert_order(&db).await;
+        let settling = insert_order(&db).await;
+        let cancelled = insert_order(&db).await;
+        db.do_in_transaction(|conn| {
+            commands::update_btc_dai_order_to_settling(
+                conn,
+                settling,
+                Bitcoin::from_sat(1_000_000),
+            )?;
+            commands::update_btc_dai_order_to_cancelled(conn, cancelled)
+        })
+        .await
+        .unwrap();
+
+        let cancellation = cancel_open_orders(&db).await.unwrap();
+
+        let mut expected = vec![first_open, second_open];
+        let mut actual = cancellation.cancelled;
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected);
+        assert!(cancellation.failed.is_empty());
+
+        let settling = load(&db, settling).await;
+        assert_eq!(settling.settling, settling.quantity);
+        assert_eq!(settling.cancelled.to_inner(), Bitcoin::ZERO);
+    }
+
+    #[tokio::test]
+    async fn cancelling_twice_is_a_no_op() {
+        let db = Sqlite::test();
+        let _ = insert_order(&db).await;
+        let _ = cancel_open_orders(&db).await.unwrap();
+
+        let cancellation = cancel_open_orders(&db).await.unwrap();
+
+        assert!(cancellation.cancelled.is_empty());
+        assert!(cancellation.failed.is_empty());
+    }
+}
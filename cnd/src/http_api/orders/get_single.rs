@@ -1,5 +1,6 @@
 use crate::{
-    http_api::{make_order_entity, problem},
+    http_api::{encoding, encoding::Encoding, make_order_entity, problem, OrderProperties},
+    network::Swarm,
     storage::Storage,
 };
 use anyhow::Result;
@@ -8,19 +9,28 @@ use futures::TryFutureExt;
 use warp::{Filter, Rejection, Reply};
 
 /// The warp filter for getting a single order.
-pub fn route(storage: Storage) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+pub fn route(
+    storage: Storage,
+    swarm: Swarm,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::get()
         .and(warp::path!("orders" / OrderId))
-        .and_then(move |order_id| {
-            handler(order_id, storage.clone())
+        .and(encoding::accept())
+        .and_then(move |order_id, encoding| {
+            handler(order_id, storage.clone(), swarm.clone(), encoding)
                 .map_err(problem::from_anyhow)
                 .map_err(warp::reject::custom)
         })
 }
 
-async fn handler(order_id: OrderId, storage: Storage) -> Result<impl Reply> {
+async fn handler(
+    order_id: OrderId,
+    storage: Storage,
+    swarm: Swarm,
+    encoding: Encoding,
+) -> Result<impl Reply> {
     let db = &storage.db;
-    let properties = db
+    let properties: OrderProperties = db
         .do_in_transaction(|conn| {
             use crate::storage::{BtcDaiOrder, Order};
 
@@ -32,5 +42,7 @@ async fn handler(order_id: OrderId, storage: Storage) -> Result<impl Reply> {
         .await?
         .into();
 
-    Ok(warp::reply::json(&make_order_entity(properties)?))
+    let entity = make_order_entity(properties.made_by(swarm.local_peer_id()))?;
+
+    encoding::reply(encoding, &entity)
 }
@@ -1,5 +1,6 @@
 use crate::{
-    http_api::{make_order_entity, problem, OrderProperties},
+    http_api::{encoding, encoding::Encoding, make_order_entity, problem, OrderProperties},
+    network::Swarm,
     storage::{queries::all_open_btc_dai_orders, Storage},
 };
 use anyhow::Result;
@@ -7,15 +8,21 @@ use futures::TryFutureExt;
 use warp::{Filter, Rejection, Reply};
 
 /// The warp filter for listing all open orders.
-pub fn route(storage: Storage) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
-    warp::get().and(warp::path!("orders")).and_then(move || {
-        handler(storage.clone())
-            .map_err(problem::from_anyhow)
-            .map_err(warp::reject::custom)
-    })
+pub fn route(
+    storage: Storage,
+    swarm: Swarm,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("orders"))
+        .and(encoding::accept())
+        .and_then(move |encoding| {
+            handler(storage.clone(), swarm.clone(), encoding)
+                .map_err(problem::from_anyhow)
+                .map_err(warp::reject::custom)
+        })
 }
 
-async fn handler(storage: Storage) -> Result<impl Reply> {
+async fn handler(storage: Storage, swarm: Swarm, encoding: Encoding) -> Result<impl Reply> {
     let db = &storage.db;
     let orders = db
         .do_in_transaction(|conn| all_open_btc_dai_orders(conn))
@@ -26,10 +33,11 @@ async fn handler(storage: Storage) -> Result<impl Reply> {
     for entity in orders
         .into_iter()
         .map(OrderProperties::from)
+        .map(|properties| properties.made_by(swarm.local_peer_id()))
         .map(make_order_entity)
     {
         open_orders.push_sub_entity(siren::SubEntity::from_entity(entity?, &["item"]));
     }
 
-    Ok(warp::reply::json(&open_orders))
+    encoding::reply(encoding, &open_orders)
 }
@@ -24,6 +24,7 @@ use comit::{order::SwapProtocol, BtcDaiOrder, Position, Price, Quantity, Side};
 use diesel::SqliteConnection;
 use futures::TryFutureExt;
 use serde::Deserialize;
+use time::OffsetDateTime;
 use warp::{http::StatusCode, Filter, Rejection, Reply};
 
 /// The warp filter for making a new BTC/DAI order.
@@ -58,13 +59,22 @@ async fn handler(
 ) -> Result<impl Reply> {
     let db = storage.db;
 
-    let order = BtcDaiOrder::new(
-        body.position,
-        Quantity::new(body.quantity),
-        Price::from_wei_per_sat(body.price),
-        SwapProtocol::new(body.swap.role, body.position, network),
-    );
+    let order = BtcDaiOrder {
+        valid_until: body.valid_until.map(OffsetDateTime::from_unix_timestamp),
+        ..BtcDaiOrder::new(
+            body.position,
+            Quantity::new(body.quantity),
+            Price::from_wei_per_sat(body.price),
+            SwapProtocol::with_expiry_config(
+                body.swap.role,
+                body.position,
+                network,
+                settings.expiries,
+            ),
+        )
+    };
     let order_id = order.id;
+    settings.orders.ensure_big_enough(&order)?;
 
     db.do_in_transaction(save_order(order.clone(), body.swap, settings))
         .await?;
@@ -83,6 +93,9 @@ struct Body {
     #[serde(with = "asset::bitcoin::sats_as_string")]
     quantity: asset::Bitcoin,
     price: Erc20Quantity,
+    /// Unix timestamp after which the order is cancelled automatically.
+    #[serde(default)]
+    valid_until: Option<i64>,
     swap: SwapParams,
 }
 
@@ -103,7 +116,12 @@ fn save_order(
     swap: SwapParams,
     settings: Settings,
 ) -> impl FnOnce(&SqliteConnection) -> Result<()> {
-    let insertable_order = InsertableOrder::new(order.id, order.position, order.created_at);
+    let insertable_order = InsertableOrder::new(
+        order.id,
+        order.position,
+        order.created_at,
+        order.valid_until,
+    );
 
     let insertable_btc_dai_order = {
         let quantity = order.quantity.to_inner();
@@ -162,3 +180,240 @@ fn save_order(
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset::{self, ethereum::FromWei},
+        btsieve::{self, bitcoin::BitcoindConnector, ethereum::Web3Connector},
+        config::{File, Orders},
+        connectors::Connectors,
+        http_api::{problem, unpack_problem},
+        proptest::*,
+        republish::restore_orderbook,
+        storage::{commands, queries::get_orders_to_republish, RootSeed, Sqlite},
+    };
+    use ::libp2p::PeerId;
+    use tokio::runtime::{Handle, Runtime};
+
+    fn settings() -> Settings {
+        let mut settings = Settings::from_config_file_and_defaults(File::default(), None).unwrap();
+        settings.network.listen = vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()];
+
+        settings
+    }
+
+    async fn swarm(settings: &Settings, storage: Storage) -> Swarm {
+        // the nodes are never contacted by these tests
+        let connectors = Connectors::new(
+            btsieve::bitcoin::Cache::new(
+                BitcoindConnector::new("http://localhost:18443".parse().unwrap()).unwrap(),
+                1,
+            ),
+            btsieve::ethereum::Cache::new(
+                Web3Connector::new("http://localhost:8545".parse().unwrap()),
+                1,
+                1,
+            ),
+        );
+
+        Swarm::new(
+            settings,
+            RootSeed::new_random(&mut rand::thread_rng()).unwrap(),
+            Handle::current(),
+            storage,
+            connectors,
+        )
+        .await
+        .unwrap()
+    }
+
+    /// The creation time is truncated to seconds as the database does not
+    /// store more precision.
+    fn order(sats: u64, wei_per_sat: u64) -> BtcDaiOrder {
+        let order = BtcDaiOrder::sell(
+            Quantity::new(asset::Bitcoin::from_sat(sats)),
+            Price::from_wei_per_sat(Erc20Quantity::from_wei(wei_per_sat)),
+            SwapProtocol::new(Role::Alice, Position::Sell, comit::Network::Dev),
+        );
+
+        BtcDaiOrder {
+            created_at: OffsetDateTime::from_unix_timestamp(order.created_at.timestamp()),
+            ..order
+        }
+    }
+
+    #[test]
+    fn order_below_minimum_size_is_a_bad_request() {
+        let order_limits = Orders {
+            min_quantity: Some(asset::Bitcoin::from_sat(10_000)),
+            min_value: Some(Erc20Quantity::from_wei(100_000_000u64)),
+        };
+        let too_few_sats = order_limits
+            .ensure_big_enough(&order(9_999, 20_000))
+            .unwrap_err();
+        assert_eq!(
+            problem::from_anyhow(too_few_sats.into()).status,
+            Some(StatusCode::BAD_REQUEST)
+        );
+        assert!(order_limits
+            .ensure_big_enough(&order(10_001, 20_000))
+            .is_ok());
+
+        let worth_too_little = order_limits
+            .ensure_big_enough(&order(10_001, 9_999))
+            .unwrap_err();
+        assert_eq!(
+            problem::from_anyhow(worth_too_little.into()).status,
+            Some(StatusCode::BAD_REQUEST)
+        );
+        assert!(order_limits
+            .ensure_big_enough(&order(10_001, 10_000))
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn making_an_order_below_minimum_size_responds_with_bad_request() {
+        let mut settings = settings();
+        settings.orders = Orders {
+            min_quantity: Some(asset::Bitcoin::from_sat(10_000)),
+            min_value: None,
+        };
+        let storage = Storage::test();
+        let swarm = swarm(&settings, storage.clone()).await;
+        let route = route(storage, swarm, settings, comit::Network::Dev).recover(unpack_problem);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/orders/BTC-DAI")
+            .json(&serde_json::json!({
+                "position": "sell",
+                "quantity": "9999",
+                "price": "20000",
+                "swap": {
+                    "bitcoin_address": "bcrt1qq7pflkfujg6dq25n73n66yjkvppq6h9caklrhz",
+                    "ethereum_address": "0x00a329c0648769a73afac7f9381e08fb43dbea72"
+                }
+            }))
+            .reply(&route)
+            .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    proptest! {
+        #[test]
+        fn saved_order_is_loaded_unchanged(
+            order in order::btc_dai(),
+            bitcoin_address in bitcoin::address(),
+            ethereum_address in identity::ethereum(),
+        ) {
+            let settings = Settings::from_config_file_and_defaults(File::default(), None).unwrap();
+            let swap = SwapParams {
+                role: Role::Alice,
+                bitcoin_address,
+                ethereum_address,
+            };
+            let db = Sqlite::test();
+            let mut runtime = Runtime::new().unwrap();
+
+            let loaded = runtime.block_on(async {
+                db.do_in_transaction(save_order(order.clone(), swap, settings)).await.unwrap();
+                db.do_in_transaction(|conn| get_orders_to_republish(conn)).await.unwrap()
+            });
+
+            assert_eq!(loaded, vec![order]);
+        }
+    }
+
+    #[tokio::test]
+    async fn saved_orders_reappear_in_market_after_restart() {
+        let settings = settings();
+        let dir = tempfile::tempdir().unwrap();
+        let seed = RootSeed::new_random(&mut rand::thread_rng()).unwrap();
+        let now = OffsetDateTime::now_utc();
+        let our_order = order(20_000, 20_000);
+        let swap = SwapParams {
+            role: Role::Alice,
+            bitcoin_address: "bcrt1qq7pflkfujg6dq25n73n66yjkvppq6h9caklrhz"
+                .parse()
+                .unwrap(),
+            ethereum_address: "0x00a329c0648769a73afac7f9381e08fb43dbea72"
+                .parse()
+                .unwrap(),
+        };
+        let recent_maker = PeerId::random();
+        let recent_order = order(30_000, 21_000);
+        let stale_maker = PeerId::random();
+        let stale_order = order(40_000, 22_000);
+
+        {
+            let db = Sqlite::new_in_dir(dir.path()).unwrap();
+            db.do_in_transaction(save_order(our_order.clone(), swap, settings.clone()))
+                .await
+                .unwrap();
+            db.do_in_transaction(|conn| {
+                commands::save_remote_orders(
+                    conn,
+                    recent_maker.clone(),
+                    &[recent_order.clone()],
+                    now - time::Duration::minutes(1),
+                )?;
+                commands::save_remote_orders(
+                    conn,
+                    stale_maker,
+                    &[stale_order],
+                    now - time::Duration::hours(1),
+                )
+            })
+            .await
+            .unwrap();
+        }
+
+        let storage = Storage::new(Sqlite::new_in_dir(dir.path()).unwrap(), seed);
+        let swarm = swarm(&settings, storage.clone()).await;
+        restore_orderbook(storage, swarm.clone(), now).await;
+
+        let mut market = swarm.btc_dai_market().await;
+        market.sort_by_key(|(_, order)| order.quantity.to_inner());
+        assert_eq!(market, vec![
+            (swarm.local_peer_id(), our_order),
+            (recent_maker, recent_order),
+        ]);
+    }
+
+    proptest! {
+        #[test]
+        fn partially_filled_order_is_republished_with_open_quantity(
+            order in order::btc_dai(),
+            bitcoin_address in bitcoin::address(),
+            ethereum_address in identity::ethereum(),
+        ) {
+            let settings = Settings::from_config_file_and_defaults(File::default(), None).unwrap();
+            let swap = SwapParams {
+                role: Role::Alice,
+                bitcoin_address,
+                ethereum_address,
+            };
+            let db = Sqlite::test();
+            let mut runtime = Runtime::new().unwrap();
+            let filled = asset::Bitcoin::from_sat(order.quantity.to_inner().as_sat() / 2);
+
+            let loaded = runtime.block_on(async {
+                db.do_in_transaction(save_order(order.clone(), swap, settings)).await.unwrap();
+                db.do_in_transaction(|conn| {
+                    commands::update_btc_dai_order_to_settling(conn, order.id, filled)
+                })
+                .await
+                .unwrap();
+                db.do_in_transaction(|conn| get_orders_to_republish(conn)).await.unwrap()
+            });
+
+            assert_eq!(loaded, vec![BtcDaiOrder {
+                quantity: Quantity::new(order.quantity.to_inner() - filled),
+                ..order
+            }]);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Offset based pagination of the collections served by the HTTP API.
+
+/// The number of items returned if the request does not specify a limit.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// The largest page size we are willing to serve, regardless of the
+/// requested limit.
+pub const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Page {
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Page {
+    /// Builds the page requested by the `offset` and `limit` query
+    /// parameters, clamping the limit to what we are willing to serve.
+    pub fn new(offset: Option<usize>, limit: Option<usize>) -> Self {
+        Self {
+            offset: offset.unwrap_or(0),
+            limit: limit.unwrap_or(DEFAULT_PAGE_SIZE).max(1).min(MAX_PAGE_SIZE),
+        }
+    }
+
+    pub fn slice<T>(&self, items: Vec<T>) -> impl Iterator<Item = T> {
+        items.into_iter().skip(self.offset).take(self.limit)
+    }
+
+    pub fn prev(&self, total: usize) -> Option<Page> {
+        if self.offset == 0 || total == 0 {
+            return None;
+        }
+
+        let offset = self.offset.min(total).saturating_sub(self.limit);
+
+        Some(Page {
+            offset,
+            limit: self.limit,
+        })
+    }
+
+    pub fn next(&self, total: usize) -> Option<Page> {
+        let offset = self.offset.saturating_add(self.limit);
+
+        if offset >= total {
+            return None;
+        }
+
+        Some(Page {
+            offset,
+            limit: self.limit,
+        })
+    }
+
+    /// The query string selecting this page.
+    pub fn query(&self) -> String {
+        format!("offset={}&limit={}", self.offset, self.limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_parameters_yield_default_page() {
+        assert_eq!(Page::new(None, None), Page {
+            offset: 0,
+            limit: DEFAULT_PAGE_SIZE
+        });
+    }
+
+    #[test]
+    fn limit_is_clamped() {
+        assert_eq!(Page::new(None, Some(0)).limit, 1);
+        assert_eq!(
+            Page::new(None, Some(MAX_PAGE_SIZE + 1)).limit,
+            MAX_PAGE_SIZE
+        );
+    }
+
+    #[test]
+    fn slice_respects_page_boundaries() {
+        let items = (0..10).collect::<Vec<_>>();
+
+        let first = Page {
+            offset: 0,
+            limit: 4,
+        };
+        let last = Page {
+            offset: 8,
+            limit: 4,
+        };
+        let beyond = Page {
+            offset: 12,
+            limit: 4,
+        };
+
+        assert_eq!(first.slice(items.clone()).collect::<Vec<_>>(), vec![
+            0, 1, 2, 3
+        ]);
+        assert_eq!(last.slice(items.clone()).collect::<Vec<_>>(), vec![8, 9]);
+        assert!(beyond.slice(items).next().is_none());
+    }
+
+    #[test]
+    fn single_page_has_no_neighbours() {
+        let page = Page {
+            offset: 0,
+            limit: 10,
+        };
+
+        assert_eq!(page.prev(10), None);
+        assert_eq!(page.next(10), None);
+    }
+
+    #[test]
+    fn prev_of_unaligned_offset_starts_at_zero() {
+        let page = Page {
+            offset: 2,
+            limit: 4,
+        };
+
+        assert_eq!(
+            page.prev(10),
+            Some(Page {
+                offset: 0,
+                limit: 4
+            })
+        );
+    }
+
+    #[test]
+    fn prev_of_page_beyond_the_end_is_last_page() {
+        let page = Page {
+            offset: 20,
+            limit: 4,
+        };
+
+        assert_eq!(
+            page.prev(10),
+            Some(Page {
+                offset: 6,
+                limit: 4
+            })
+        );
+        assert_eq!(page.next(10), None);
+    }
+}
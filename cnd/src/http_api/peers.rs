@@ -1,20 +1,69 @@
-use crate::{http_api::serde_peer_id, network::Swarm};
+use crate::{
+    http_api::serde_peer_id,
+    network::{Connection, ConnectionState, Direction, Swarm},
+};
 use libp2p::{Multiaddr, PeerId};
 use serde::Serialize;
+use std::time::Instant;
 use warp::{Rejection, Reply};
 
 #[allow(clippy::needless_pass_by_value)]
 pub async fn get_peers(swarm: Swarm) -> Result<impl Reply, Rejection> {
-    let peers = swarm
-        .connected_peers()
-        .await
+    let dialed = swarm.connected_peers().await.collect();
+    let connections = swarm.connections().await;
+    let peers = peers(dialed, connections, Instant::now());
+
+    Ok(warp::reply::json(&PeersResource { peers }))
+}
+
+/// Lists the peers we dialed followed by the ones only connected to us, each
+/// with their open connections as of `now`.
+fn peers(
+    dialed: Vec<(PeerId, Vec<Multiaddr>)>,
+    connections: Vec<(PeerId, Connection)>,
+    now: Instant,
+) -> Vec<Peer> {
+    let mut peers = dialed
+        .into_iter()
         .map(|(peer, addresses)| Peer {
             id: peer,
             endpoints: addresses,
+            connections: vec![],
         })
+        .collect::<Vec<_>>();
+
+    for (id, connection) in connections {
+        let connection = PeerConnectionDetails {
+            direction: connection.direction,
+            address: connection.address,
+            uptime_secs: now
+                .saturating_duration_since(connection.established)
+                .as_secs(),
+        };
+
+        match peers.iter_mut().find(|peer| peer.id == id) {
+            Some(peer) => peer.connections.push(connection),
+            None => peers.push(Peer {
+                id,
+                endpoints: vec![],
+                connections: vec![connection],
+            }),
+        }
+    }
+
+    peers
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub async fn get_peer_connections(swarm: Swarm) -> Result<impl Reply, Rejection> {
+    let connections = swarm
+        .connection_states()
+        .await
+        .into_iter()
+        .map(|(peer, state)| PeerConnection { id: peer, state })
         .collect();
 
-    Ok(warp::reply::json(&PeersResource { peers }))
+    Ok(warp::reply::json(&PeerConnectionsResource { connections }))
 }
 
 #[derive(Serialize, Debug)]
@@ -27,4 +76,95 @@ pub struct Peer {
     #[serde(with = "serde_peer_id")]
     id: PeerId,
     endpoints: Vec<Multiaddr>,
+    connections: Vec<PeerConnectionDetails>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PeerConnectionDetails {
+    direction: Direction,
+    address: Multiaddr,
+    uptime_secs: u64,
+}
+
+/// The makers we redial if we lose the connection to them.
+#[derive(Serialize, Debug)]
+pub struct PeerConnectionsResource {
+    connections: Vec<PeerConnection>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PeerConnection {
+    #[serde(with = "serde_peer_id")]
+    id: PeerId,
+    #[serde(flatten)]
+    state: ConnectionState,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn connection(direction: Direction, address: &Multiaddr, established: Instant) -> Connection {
+        Connection {
+            direction,
+            address: address.clone(),
+            established,
+        }
+    }
+
+    #[test]
+    fn peers_carry_direction_and_uptime_of_their_connections() {
+        let now = Instant::now();
+        let maker = PeerId::random();
+        let maker_address = "/ip4/127.0.0.1/tcp/9939".parse::<Multiaddr>().unwrap();
+        let taker = PeerId::random();
+        let taker_address = "/ip4/10.0.0.2/tcp/51234".parse::<Multiaddr>().unwrap();
+
+        let peers = peers(
+            vec![(maker.clone(), vec![maker_address.clone()])],
+            vec![
+                (
+                    taker.clone(),
+                    connection(Direction::Inbound, &taker_address, now),
+                ),
+                (
+                    maker.clone(),
+                    connection(
+                        Direction::Outbound,
+                        &maker_address,
+                        now - Duration::from_secs(90),
+                    ),
+                ),
+            ],
+            now,
+        );
+
+        let json = serde_json::to_value(PeersResource { peers }).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "peers": [
+                    {
+                        "id": maker.to_string(),
+                        "endpoints": [maker_address],
+                        "connections": [{
+                            "direction": "outbound",
+                            "address": maker_address,
+                            "uptime_secs": 90
+                        }]
+                    },
+                    {
+                        "id": taker.to_string(),
+                        "endpoints": [],
+                        "connections": [{
+                            "direction": "inbound",
+                            "address": taker_address,
+                            "uptime_secs": 0
+                        }]
+                    }
+                ]
+            })
+        );
+    }
 }
@@ -1,5 +1,8 @@
 use crate::{
-    http_api::ActionNotFound,
+    config::OrderTooSmall,
+    http_api::{
+        auth::Unauthorized, markets::InvalidPosition, swaps::InvalidSwapsQuery, ActionNotFound,
+    },
     storage::{commands::NotOpen, NoOrderExists, NoSwapExists},
 };
 use http_api_problem::HttpApiProblem;
@@ -26,9 +29,21 @@ pub fn from_anyhow(e: anyhow::Error) -> HttpApiProblem {
         }
         e if e.is::<NotOpen>() => HttpApiProblem::new("Order can no longer be cancelled.")
             .set_status(StatusCode::BAD_REQUEST),
+        e if e.is::<Unauthorized>() => {
+            HttpApiProblem::new("Unauthorized.").set_status(StatusCode::UNAUTHORIZED)
+        }
         e if e.is::<ActionNotFound>() => {
             HttpApiProblem::new("Action not found.").set_status(StatusCode::NOT_FOUND)
         }
+        e if e.is::<InvalidPosition>() => HttpApiProblem::new("Invalid position.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail(format!("{}", e)),
+        e if e.is::<OrderTooSmall>() => HttpApiProblem::new("Order too small.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail(format!("{}", e)),
+        e if e.is::<InvalidSwapsQuery>() => HttpApiProblem::new("Invalid query.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail(format!("{}", e)),
         e => {
             tracing::error!("unhandled error: {:#}", e);
 
@@ -1,8 +1,12 @@
 use crate::{
     bitcoin_fees::BitcoinFees,
-    config::{AllowedOrigins, Settings},
+    config::Settings,
+    connectors::Connectors,
     http_api,
-    http_api::{dial_addr, info, markets, orders, peers, swaps, tokens},
+    http_api::{
+        auth, cors, dial_addr, health, info, markets, metrics, orders, peers, rpc, swaps, tokens,
+        version,
+    },
     network::Swarm,
     storage::Storage,
     LocalSwapId,
@@ -19,6 +23,7 @@ pub fn create(
     settings: &Settings,
     bitcoin_fees: BitcoinFees,
     network: comit::Network,
+    connectors: Connectors,
 ) -> BoxedFilter<(impl Reply,)> {
     let swaps = warp::path(http_api::PATH);
     let swarm_filter = warp::any().map({
@@ -32,16 +37,7 @@ pub fn create(
     let bitcoin_fees = warp::any().map(move || bitcoin_fees.clone());
     let preflight_cors_route = warp::options().map(warp::reply);
 
-    let cors = warp::cors()
-        .allow_methods(vec!["GET", "POST"])
-        .allow_header("content-type");
-    let cors = match &settings.http_api.cors.allowed_origins {
-        AllowedOrigins::None => cors.allow_origins(Vec::<&str>::new()),
-        AllowedOrigins::All => cors.allow_any_origin(),
-        AllowedOrigins::Some(hosts) => {
-            cors.allow_origins::<Vec<&str>>(hosts.iter().map(|host| host.as_str()).collect())
-        }
-    };
+    let cors = cors::policy(&settings.http_api.cors);
 
     let get_info = warp::get()
         .and(warp::path::end())
@@ -60,6 +56,11 @@ pub fn create(
         .and(swarm_filter.clone())
         .and_then(peers::get_peers);
 
+    let get_peer_connections = warp::get()
+        .and(warp::path!("peers" / "connections"))
+        .and(swarm_filter.clone())
+        .and_then(peers::get_peer_connections);
+
     let get_swap = swaps
         .and(warp::get())
         .and(warp::path::param())
@@ -67,9 +68,26 @@ pub fn create(
         .and(storage_filter.clone())
         .and_then(swaps::get_swap);
 
+    let get_swap_events = swaps
+        .and(warp::get())
+        .and(warp::path::param())
+        .and(warp::path("events"))
+        .and(warp::path::end())
+        .and(storage_filter.clone())
+        .and_then(swaps::get_swap_events);
+
+    let get_swap_actions = swaps
+        .and(warp::get())
+        .and(warp::path::param())
+        .and(warp::path("actions"))
+        .and(warp::path::end())
+        .and(storage_filter.clone())
+        .and_then(swaps::get_swap_actions);
+
     let get_swaps = warp::get()
         .and(swaps)
         .and(warp::path::end())
+        .and(warp::query::raw().or(warp::any().map(String::new)).unify())
         .and(storage_filter.clone())
         .and_then(swaps::get_swaps);
 
@@ -94,12 +112,16 @@ pub fn create(
         .and(swarm_filter)
         .and_then(dial_addr::post_dial_addr);
 
-    preflight_cors_route
+    let routes = preflight_cors_route
         .or(get_peers)
+        .or(get_peer_connections)
         .or(get_info_siren)
         .or(get_info)
+        .or(version::route())
         .or(get_swap)
         .or(get_swaps)
+        .or(get_swap_events)
+        .or(get_swap_actions)
         .or(action)
         .or(orders::make_btc_dai(
             storage.clone(),
@@ -107,12 +129,46 @@ pub fn create(
             settings.clone(),
             network,
         ))
-        .or(orders::get_single(storage.clone()))
-        .or(orders::list_open(storage.clone()))
-        .or(orders::cancel(storage, swarm.clone()))
+        .or(orders::get_single(storage.clone(), swarm.clone()))
+        .or(orders::list_open(storage.clone(), swarm.clone()))
+        .or(orders::cancel(storage.clone(), swarm.clone()))
+        .or(orders::cancel_all(storage.clone(), swarm.clone()))
         .or(tokens::list(settings.clone()))
-        .or(markets::get_btc_dai(swarm, network))
+        .or(markets::get_btc_dai(
+            swarm.clone(),
+            network,
+            settings.expiries,
+            settings.orders.clone(),
+        ))
+        .or(markets::get_btc_dai_depth(
+            swarm.clone(),
+            network,
+            settings.expiries,
+            settings.orders.clone(),
+        ))
+        .or(markets::get_btc_dai_feed(
+            swarm.clone(),
+            network,
+            settings.expiries,
+            settings.orders.clone(),
+        ))
         .or(post_dial_addr)
+        .or(rpc::route(rpc::Node {
+            swarm: swarm.clone(),
+            storage: storage.clone(),
+            network,
+            expiry_config: settings.expiries,
+            order_limits: settings.orders.clone(),
+        }))
+        .or(metrics::route(storage, swarm, connectors.clone()))
+        .or(health::route(
+            connectors,
+            settings.bitcoin.network,
+            settings.ethereum.chain_id,
+        ));
+
+    auth::bearer_token(settings.http_api.auth_token.clone())
+        .and(routes)
         .recover(http_api::unpack_problem)
         .with(warp::trace(|info| {
             tracing::error_span!(
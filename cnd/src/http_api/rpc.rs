@@ -0,0 +1,323 @@
+//! A JSON-RPC 2.0 interface to the same operations the REST API offers.
+//!
+//! Every request is answered over `POST /rpc`, batches included. Errors of the
+//! REST API are mapped to JSON-RPC errors based on the status code of their
+//! problem.
+
+use crate::{
+    config::Orders,
+    http_api::{markets, orders, problem},
+    network::Swarm,
+    storage::Storage,
+};
+use anyhow::Result;
+use comit::{expiries::ExpiryConfig, OrderId};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use warp::{
+    http::StatusCode,
+    reply::{self, Response},
+    Filter, Rejection, Reply,
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// The resource the request refers to does not exist.
+const NOT_FOUND: i64 = -32001;
+/// The request is well-formed but cannot be fulfilled in the current state.
+const REFUSED: i64 = -32002;
+
+/// The operations that can be called over JSON-RPC.
+#[async_trait::async_trait]
+pub trait Methods: Send + Sync {
+    async fn get_btc_dai_market(&self) -> Result<Vec<markets::MarketItem>>;
+    async fn cancel_order(&self, order_id: OrderId) -> Result<()>;
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub swarm: Swarm,
+    pub storage: Storage,
+    pub network: comit::Network,
+    pub expiry_config: ExpiryConfig,
+    pub order_limits: Orders,
+}
+
+#[async_trait::async_trait]
+impl Methods for Node {
+    async fn get_btc_dai_market(&self) -> Result<Vec<markets::MarketItem>> {
+        let orders = markets::viable_orders(
+            &self.swarm,
+            self.network,
+            self.expiry_config,
+            &self.order_limits,
+        )
+        .await;
+
+        Ok(orders.into_iter().map(|(_, item)| item).collect())
+    }
+
+    async fn cancel_order(&self, order_id: OrderId) -> Result<()> {
+        orders::cancel_order(order_id, &self.storage, &self.swarm).await
+    }
+}
+
+/// The warp filter for calling methods over JSON-RPC.
+pub fn route<M>(methods: M) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
+where
+    M: Methods + Clone + 'static,
+{
+    warp::post()
+        .and(warp::path!("rpc"))
+        .and(warp::body::bytes())
+        .and_then(move |body: bytes::Bytes| {
+            let methods = methods.clone();
+            async move { Ok::<_, Rejection>(to_reply(handle(&methods, &body).await)) }
+        })
+}
+
+fn to_reply(response: Option<Value>) -> Response {
+    match response {
+        Some(response) => reply::json(&response).into_response(),
+        // a request only made of notifications is answered with nothing
+        None => StatusCode::NO_CONTENT.into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    /// Notifications come without an id and are not answered.
+    id: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct Error {
+    code: i64,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<String>,
+}
+
+impl Error {
+    fn new(code: i64, message: &str) -> Self {
+        Self {
+            code,
+            message: message.to_owned(),
+            data: None,
+        }
+    }
+
+    fn from_anyhow(e: anyhow::Error) -> Self {
+        let problem = problem::from_anyhow(e);
+        let code = match problem.status {
+            Some(StatusCode::NOT_FOUND) => NOT_FOUND,
+            Some(StatusCode::BAD_REQUEST) => REFUSED,
+            _ => INTERNAL_ERROR,
+        };
+
+        Self {
+            code,
+            message: problem.title,
+            data: problem.detail,
+        }
+    }
+}
+
+/// Answers a single request or a batch of them.
+pub async fn handle<M>(methods: &M, body: &[u8]) -> Option<Value>
+where
+    M: Methods,
+{
+    let body = match serde_json::from_slice::<Value>(body) {
+        Ok(body) => body,
+        Err(_) => {
+            return Some(error_response(
+                Value::Null,
+                Error::new(PARSE_ERROR, "Parse error"),
+            ))
+        }
+    };
+
+    match body {
+        Value::Array(batch) if batch.is_empty() => Some(error_response(
+            Value::Null,
+            Error::new(INVALID_REQUEST, "Invalid Request"),
+        )),
+        Value::Array(batch) => {
+            let mut responses = Vec::new();
+            for request in batch {
+                responses.extend(handle_single(methods, request).await);
+            }
+
+            if responses.is_empty() {
+                None
+            } else {
+                Some(Value::Array(responses))
+            }
+        }
+        request => handle_single(methods, request).await,
+    }
+}
+
+async fn handle_single<M>(methods: &M, request: Value) -> Option<Value>
+where
+    M: Methods,
+{
+    let request = match serde_json::from_value::<Request>(request) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        _ => {
+            return Some(error_response(
+                Value::Null,
+                Error::new(INVALID_REQUEST, "Invalid Request"),
+            ))
+        }
+    };
+
+    let result = call(methods, &request.method, request.params).await;
+    let id = request.id?;
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+        Err(error) => error_response(id, error),
+    })
+}
+
+async fn call<M>(methods: &M, method: &str, params: Value) -> Result<Value, Error>
+where
+    M: Methods,
+{
+    match method {
+        "get_btc_dai_market" => {
+            let market = methods
+                .get_btc_dai_market()
+                .await
+                .map_err(Error::from_anyhow)?;
+
+            serde_json::to_value(market).map_err(|e| Error::from_anyhow(anyhow::Error::new(e)))
+        }
+        "cancel_order" => {
+            #[derive(Deserialize)]
+            struct Params {
+                order_id: OrderId,
+            }
+
+            let Params { order_id } = serde_json::from_value(params)
+                .map_err(|_| Error::new(INVALID_PARAMS, "Invalid params"))?;
+
+            methods
+                .cancel_order(order_id)
+                .await
+                .map_err(Error::from_anyhow)?;
+
+            Ok(Value::Null)
+        }
+        _ => Err(Error::new(METHOD_NOT_FOUND, "Method not found")),
+    }
+}
+
+fn error_response(id: Value, error: Error) -> Value {
+    json!({ "jsonrpc": "2.0", "error": error, "id": id })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::NoOrderExists;
+
+    /// Knows a single order that can be cancelled.
+    struct MockMethods {
+        order_id: OrderId,
+    }
+
+    #[async_trait::async_trait]
+    impl Methods for MockMethods {
+        async fn get_btc_dai_market(&self) -> Result<Vec<markets::MarketItem>> {
+            Ok(vec![])
+        }
+
+        async fn cancel_order(&self, order_id: OrderId) -> Result<()> {
+            if order_id != self.order_id {
+                anyhow::bail!(NoOrderExists(order_id))
+            }
+
+            Ok(())
+        }
+    }
+
+    fn mock_methods() -> MockMethods {
+        MockMethods {
+            order_id: OrderId::random(),
+        }
+    }
+
+    #[tokio::test]
+    async fn calls_method() {
+        let methods = mock_methods();
+        let body = json!({
+            "jsonrpc": "2.0",
+            "method": "cancel_order",
+            "params": { "order_id": methods.order_id },
+            "id": 1
+        });
+
+        let response = handle(&methods, body.to_string().as_bytes()).await;
+
+        assert_eq!(
+            response,
+            Some(json!({ "jsonrpc": "2.0", "result": null, "id": 1 }))
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_method_is_not_found() {
+        let body = json!({ "jsonrpc": "2.0", "method": "post_swap", "id": "a" });
+
+        let response = handle(&mock_methods(), body.to_string().as_bytes())
+            .await
+            .unwrap();
+
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(response["id"], "a");
+    }
+
+    #[tokio::test]
+    async fn batch_answers_every_request() {
+        let methods = mock_methods();
+        let body = json!([
+            { "jsonrpc": "2.0", "method": "get_btc_dai_market", "id": 1 },
+            {
+                "jsonrpc": "2.0",
+                "method": "cancel_order",
+                "params": { "order_id": OrderId::random() },
+                "id": 2
+            },
+            // a notification is executed but not answered
+            {
+                "jsonrpc": "2.0",
+                "method": "cancel_order",
+                "params": { "order_id": methods.order_id }
+            }
+        ]);
+
+        let response = handle(&methods, body.to_string().as_bytes()).await.unwrap();
+
+        assert_eq!(
+            response,
+            json!([
+                { "jsonrpc": "2.0", "result": [], "id": 1 },
+                {
+                    "jsonrpc": "2.0",
+                    "error": { "code": NOT_FOUND, "message": "Order not found." },
+                    "id": 2
+                }
+            ])
+        );
+    }
+}
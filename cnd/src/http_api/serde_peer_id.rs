@@ -23,6 +23,22 @@ where
     Ok(peer_id)
 }
 
+/// Serializes optional PeerIds, `None` becomes `null`.
+pub mod option {
+    use super::*;
+    use serde::Serialize;
+
+    pub fn serialize<S>(peer_id: &Option<PeerId>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        peer_id
+            .as_ref()
+            .map(PeerId::to_string)
+            .serialize(serializer)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
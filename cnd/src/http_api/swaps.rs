@@ -8,19 +8,37 @@
 //!
 //! 3. To fetch swap actions: GET requests can fetch an appropriate swap
 //!    action on the action endpoint e.g., "/swaps/:swap_id/fund"
+//!
+//! 4. To follow a swap: GET requests on "/swaps/:swap_id/events" return a
+//!    stream of server-sent events, one for each swap event
+//!
+//! 5. To list swaps: GET requests on "/swaps", optionally filtered by one or
+//!    more `state` and `protocol` query parameters and paginated with
+//!    `offset` and `limit`
+//!
+//! 6. To list the available actions: GET requests on
+//!    "/swaps/:swap_id/actions" return the names of the actions the swap can
+//!    currently perform
 
 use crate::{
     bitcoin_fees::BitcoinFees,
+    hbit, herc20, http_api,
     http_api::{
-        action::ActionResponseBody, problem, route_factory, ActionName, ActionNotFound, Protocol,
-        SwapEvent,
+        action::ActionResponseBody, page::Page, problem, route_factory, ActionName, ActionNotFound,
+        Protocol, ProtocolName, SwapEvent, UnknownProtocol,
+    },
+    storage::{
+        queries::{get_all_swap_contexts, get_swap_outcome, SwapOutcome},
+        Load, Storage, SwapContext,
     },
-    storage::{queries::get_active_swap_contexts, Load, Storage},
     LocalSwapId, Role,
 };
 use comit::swap::Action;
+use futures::{stream, Stream, StreamExt};
 use serde::Serialize;
-use warp::{http, Rejection, Reply};
+use std::{collections::VecDeque, convert::Infallible};
+use tokio::sync::broadcast::{self, RecvError};
+use warp::{http, sse, Rejection, Reply};
 
 #[allow(clippy::needless_pass_by_value)]
 pub async fn get_swap(id: LocalSwapId, storage: Storage) -> Result<impl Reply, Rejection> {
@@ -31,31 +49,335 @@ pub async fn get_swap(id: LocalSwapId, storage: Storage) -> Result<impl Reply, R
         .map_err(warp::reject::custom)
 }
 
-pub async fn get_swaps(storage: Storage) -> Result<impl Reply, Rejection> {
-    let swaps = async {
-        let mut swaps = siren::Entity::default().with_class_member("swaps");
-
-        for context in storage
-            .db
-            .do_in_transaction(get_active_swap_contexts)
-            .await?
-        {
-            swaps.push_sub_entity(siren::SubEntity::from_link(siren::EntityLink {
-                class: vec![],
-                title: None,
-                rel: vec![String::from("item")],
-                href: format!("/swaps/{}", context.id),
-                _type: None,
-            }));
+pub async fn get_swaps(query: String, storage: Storage) -> Result<impl Reply, Rejection> {
+    handle_get_swaps(query, storage)
+        .await
+        .map(|swaps| warp::reply::json(&swaps))
+        .map_err(problem::from_anyhow)
+        .map_err(warp::reject::custom)
+}
+
+async fn handle_get_swaps(query: String, storage: Storage) -> anyhow::Result<siren::Entity> {
+    let query = SwapsQuery::parse(&query)?;
+    let matching = query.select(swap_phases(&storage).await?);
+
+    Ok(swaps_entity(&query, matching))
+}
+
+/// Lists the `matching` swaps, only the requested page of them if the query
+/// asks for one.
+fn swaps_entity(query: &SwapsQuery, matching: Vec<LocalSwapId>) -> siren::Entity {
+    let mut swaps = siren::Entity::default().with_class_member("swaps");
+
+    let matching = match query.page {
+        Some(page) => {
+            let total = matching.len();
+
+            if let Some(prev) = page.prev(total) {
+                swaps = swaps.with_link(siren::NavigationalLink::new(&["prev"], query.href(&prev)));
+            }
+            if let Some(next) = page.next(total) {
+                swaps = swaps.with_link(siren::NavigationalLink::new(&["next"], query.href(&next)));
+            }
+
+            page.slice(matching).collect()
+        }
+        None => matching,
+    };
+
+    for id in matching {
+        swaps.push_sub_entity(siren::SubEntity::from_link(siren::EntityLink {
+            class: vec![],
+            title: None,
+            rel: vec![String::from("item")],
+            href: route_factory::swap_path(id),
+            _type: None,
+        }));
+    }
+
+    swaps
+}
+
+/// Determines the phase of every swap we know about.
+pub async fn swap_phases(storage: &Storage) -> anyhow::Result<Vec<(SwapContext, SwapPhase)>> {
+    let contexts = storage.db.do_in_transaction(get_all_swap_contexts).await?;
+
+    // Funding events are only kept in memory, looking them up is enough to
+    // tell open from settling swaps without loading every swap.
+    let hbit_events = storage.hbit_events.lock().await;
+    let herc20_events = storage.herc20_events.lock().await;
+
+    let phases = contexts
+        .into_iter()
+        .map(|(context, outcome)| {
+            let phase = match outcome {
+                Some(SwapOutcome::Completed) => SwapPhase::Closed,
+                Some(SwapOutcome::Failed) => SwapPhase::Failed,
+                None => SwapPhase::in_progress(
+                    hbit_events.get(&context.id),
+                    herc20_events.get(&context.id),
+                ),
+            };
+
+            (context, phase)
+        })
+        .collect();
+
+    Ok(phases)
+}
+
+/// The phase a swap is currently in, as far as the HTTP API is concerned.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum SwapPhase {
+    /// Nothing has been locked up yet.
+    Open,
+    /// At least one of the parties funded their HTLC.
+    Settling,
+    Closed,
+    Failed,
+}
+
+impl SwapPhase {
+    /// The phase of a swap that has neither completed nor failed.
+    fn in_progress(hbit: Option<&hbit::Events>, herc20: Option<&herc20::Events>) -> Self {
+        let funded = hbit.map_or(false, |events| events.fund.is_some())
+            || herc20.map_or(false, |events| events.fund.is_some());
+
+        if funded {
+            SwapPhase::Settling
+        } else {
+            SwapPhase::Open
+        }
+    }
+}
+
+/// Swaps that are still in progress, returned if no state is requested.
+const ACTIVE_PHASES: [SwapPhase; 3] = [SwapPhase::Open, SwapPhase::Settling, SwapPhase::Failed];
+
+#[derive(Clone, Debug, PartialEq)]
+struct SwapsQuery {
+    states: Vec<SwapPhase>,
+    protocols: Vec<ProtocolName>,
+    /// All matching swaps are listed if neither `offset` nor `limit` is given.
+    page: Option<Page>,
+}
+
+impl SwapsQuery {
+    /// Parses the query string of the swaps route, `state` and `protocol` may
+    /// be given more than once to select swaps in any of the given states or
+    /// using any of the given protocols.
+    fn parse(query: &str) -> Result<Self, InvalidSwapsQuery> {
+        let mut states = Vec::new();
+        let mut protocols = Vec::new();
+        let mut offset = None;
+        let mut limit = None;
+
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            match key.as_ref() {
+                "state" => states.push(
+                    value
+                        .parse()
+                        .map_err(|_| InvalidSwapsQuery::State(value.to_string()))?,
+                ),
+                "protocol" => protocols.push(
+                    value
+                        .parse()
+                        .map_err(|_| UnknownProtocol(value.to_string()))?,
+                ),
+                "offset" => offset = Some(parse_number("offset", &value)?),
+                "limit" => limit = Some(parse_number("limit", &value)?),
+                _ => {}
+            }
+        }
+
+        if states.is_empty() {
+            states = ACTIVE_PHASES.to_vec();
+        }
+
+        Ok(Self {
+            states,
+            protocols,
+            page: if offset.is_some() || limit.is_some() {
+                Some(Page::new(offset, limit))
+            } else {
+                None
+            },
+        })
+    }
+
+    fn select(&self, swaps: Vec<(SwapContext, SwapPhase)>) -> Vec<LocalSwapId> {
+        swaps
+            .into_iter()
+            .filter(|(_, phase)| self.states.contains(phase))
+            .filter(|(context, _)| self.uses_requested_protocol(context))
+            .map(|(context, _)| context.id)
+            .collect()
+    }
+
+    /// Swaps match if either of their legs uses one of the requested
+    /// protocols, any swap matches if no protocol was requested.
+    fn uses_requested_protocol(&self, context: &SwapContext) -> bool {
+        self.protocols.is_empty()
+            || self.protocols.contains(&context.alpha.into())
+            || self.protocols.contains(&context.beta.into())
+    }
+
+    /// Link to `page` of the swaps, keeping the requested filters.
+    fn href(&self, page: &Page) -> String {
+        let mut href = format!("/{}?{}", http_api::PATH, page.query());
+
+        for state in &self.states {
+            href.push_str(&format!("&state={}", state));
+        }
+        for protocol in &self.protocols {
+            href.push_str(&format!("&protocol={}", protocol));
         }
 
-        Ok(swaps)
+        href
     }
-    .await
-    .map_err(problem::from_anyhow)
-    .map_err(warp::reject::custom)?;
+}
 
-    Ok(warp::reply::json(&swaps))
+fn parse_number(name: &'static str, value: &str) -> Result<usize, InvalidSwapsQuery> {
+    value
+        .parse()
+        .map_err(|_| InvalidSwapsQuery::Number(name, value.to_owned()))
+}
+
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum InvalidSwapsQuery {
+    #[error("invalid state '{0}', expected 'open', 'settling', 'closed' or 'failed'")]
+    State(String),
+    #[error("invalid {0} '{1}', expected a non-negative number")]
+    Number(&'static str, String),
+    #[error(transparent)]
+    Protocol(#[from] UnknownProtocol),
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub async fn get_swap_events(id: LocalSwapId, storage: Storage) -> Result<impl Reply, Rejection> {
+    // Subscribe before loading the known events, otherwise an event persisted
+    // in between would be neither replayed nor pushed.
+    let live = storage.subscribe_swap_events();
+    let resource: SwapResource = storage
+        .load(id)
+        .await
+        .map_err(problem::from_anyhow)
+        .map_err(warp::reject::custom)?;
+
+    let events = swap_events(id, resource.events, live, storage)
+        .map(|event| Ok::<_, Infallible>(sse::json(event)));
+
+    Ok(sse::reply(sse::keep_alive().stream(events)))
+}
+
+struct SwapEvents {
+    id: LocalSwapId,
+    live: broadcast::Receiver<(LocalSwapId, SwapEvent)>,
+    storage: Storage,
+    /// Events that have been emitted or are about to be.
+    seen: Vec<SwapEvent>,
+    pending: VecDeque<SwapEvent>,
+}
+
+impl SwapEvents {
+    fn enqueue(&mut self, events: impl IntoIterator<Item = SwapEvent>) {
+        for event in events {
+            if !self.seen.contains(&event) {
+                self.seen.push(event);
+                self.pending.push_back(event);
+            }
+        }
+    }
+}
+
+/// Replays the known events of a swap and then pushes new ones as they are
+/// persisted.
+///
+/// The stream owns its subscription, dropping it (e.g. because the client
+/// disconnected) unsubscribes.
+fn swap_events(
+    id: LocalSwapId,
+    known: Vec<SwapEvent>,
+    live: broadcast::Receiver<(LocalSwapId, SwapEvent)>,
+    storage: Storage,
+) -> impl Stream<Item = SwapEvent> {
+    let mut state = SwapEvents {
+        id,
+        live,
+        storage,
+        seen: Vec::new(),
+        pending: VecDeque::new(),
+    };
+    state.enqueue(known);
+
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(event) = state.pending.pop_front() {
+                return Some((event, state));
+            }
+
+            match state.live.recv().await {
+                Ok((id, event)) if id == state.id => state.enqueue(Some(event)),
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        "swap event subscriber of {} lagged behind by {} events, reloading",
+                        state.id,
+                        skipped
+                    );
+
+                    let reloaded: anyhow::Result<SwapResource> = state.storage.load(state.id).await;
+
+                    match reloaded {
+                        Ok(resource) => state.enqueue(resource.events),
+                        Err(e) => {
+                            tracing::error!("failed to reload swap events: {:#}", e);
+                            return None;
+                        }
+                    }
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub async fn get_swap_actions(id: LocalSwapId, storage: Storage) -> Result<impl Reply, Rejection> {
+    handle_get_swap_actions(id, storage)
+        .await
+        .map(|actions| warp::reply::json(&actions))
+        .map_err(problem::from_anyhow)
+        .map_err(warp::reject::custom)
+}
+
+/// The swap executor stores the action it is waiting for in `next_action`,
+/// the same action that is advertised on the swap entity. Swaps that ended
+/// have nothing left to do even if an action is still recorded.
+async fn handle_get_swap_actions(
+    id: LocalSwapId,
+    storage: Storage,
+) -> anyhow::Result<Vec<ActionName>> {
+    let outcome = storage
+        .db
+        .do_in_transaction(|conn| get_swap_outcome(conn, id))
+        .await?;
+    if outcome.is_some() {
+        return Ok(Vec::new());
+    }
+
+    let next_action = storage.next_action.lock().await.get(&id).cloned();
+
+    Ok(next_action.into_iter().map(ActionName::from).collect())
 }
 
 async fn handle_get_swap(id: LocalSwapId, storage: Storage) -> anyhow::Result<siren::Entity> {
@@ -150,3 +472,260 @@ async fn handle_action(
 
     ActionResponseBody::from_action(action, bitcoin_fees).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset,
+        ethereum::Hash,
+        http_api::page::DEFAULT_PAGE_SIZE,
+        proptest::*,
+        storage::{proptest::tables, NoSwapExists},
+        LockProtocol,
+    };
+    use comit::{swap::actions::SendToAddress, Side};
+    use tokio::runtime::Runtime;
+    use warp::http::StatusCode;
+
+    // FK given by sqlite deterministically start from 1, we can thus anticipate
+    // which FK will be used if we have a fresh test database for every test.
+    const SWAP_FK: i32 = 1;
+
+    #[test]
+    fn swaps_without_state_are_active_ones() {
+        let query = SwapsQuery::parse("").unwrap();
+
+        assert_eq!(query.states, ACTIVE_PHASES.to_vec());
+        assert_eq!(query.page, None);
+    }
+
+    #[test]
+    fn repeated_states_are_combined() {
+        let query = SwapsQuery::parse("state=open&state=closed&limit=10").unwrap();
+
+        assert_eq!(query.states, vec![SwapPhase::Open, SwapPhase::Closed]);
+        assert_eq!(
+            query.page,
+            Some(Page {
+                offset: 0,
+                limit: 10
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_state_is_a_bad_request() {
+        let error = SwapsQuery::parse("state=pending").unwrap_err();
+        let problem = problem::from_anyhow(error.into());
+
+        assert_eq!(problem.status, Some(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn unknown_protocol_is_a_bad_request() {
+        let error = SwapsQuery::parse("protocol=halight").unwrap_err();
+        let problem = problem::from_anyhow(error.into());
+
+        assert_eq!(problem.status, Some(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn garbage_offset_is_a_bad_request() {
+        let error = SwapsQuery::parse("offset=-1").unwrap_err();
+        let problem = problem::from_anyhow(error.into());
+
+        assert_eq!(problem.status, Some(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn phase_of_unfinished_swap_depends_on_funding() {
+        let deployed = herc20::Events {
+            deploy: Some(comit::herc20::Deployed {
+                transaction: Hash::from([1u8; 32]),
+                location: Default::default(),
+            }),
+            ..Default::default()
+        };
+        let mut funded = deployed.clone();
+        funded.fund = Some(comit::herc20::Funded {
+            transaction: Hash::from([2u8; 32]),
+        });
+
+        assert_eq!(SwapPhase::in_progress(None, None), SwapPhase::Open);
+        assert_eq!(
+            SwapPhase::in_progress(None, Some(&deployed)),
+            SwapPhase::Open
+        );
+        assert_eq!(
+            SwapPhase::in_progress(None, Some(&funded)),
+            SwapPhase::Settling
+        );
+    }
+
+    fn send_to_address() -> Action {
+        Action::HbitFund(SendToAddress {
+            to: "bcrt1qq7pflkfujg6dq25n73n66yjkvppq6h9caklrhz"
+                .parse()
+                .unwrap(),
+            amount: asset::Bitcoin::from_sat(1_000_000),
+            network: comit::ledger::Bitcoin::Regtest,
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn actions_are_the_next_action_of_a_running_swap(
+            swap in tables::insertable_swap(),
+            hbit in tables::insertable_hbit(SWAP_FK, Side::Alpha),
+            herc20 in tables::insertable_herc20(SWAP_FK, Side::Beta),
+            completed_swap in tables::insertable_completed_swap(SWAP_FK),
+        ) {
+            let storage = Storage::test();
+            let id = swap.local_swap_id.0;
+            let mut runtime = Runtime::new().unwrap();
+
+            let (idle, running, completed) = runtime.block_on(async {
+                storage.db.do_in_transaction(|conn| {
+                    swap.insert(conn)?;
+                    hbit.insert(conn)?;
+                    herc20.insert(conn)
+                }).await.unwrap();
+
+                let idle = handle_get_swap_actions(id, storage.clone()).await.unwrap();
+
+                storage.next_action.lock().await.insert(id, send_to_address());
+                let running = handle_get_swap_actions(id, storage.clone()).await.unwrap();
+
+                storage.db.do_in_transaction(|conn| completed_swap.insert(conn)).await.unwrap();
+                let completed = handle_get_swap_actions(id, storage.clone()).await.unwrap();
+
+                (idle, running, completed)
+            });
+
+            prop_assert_eq!(idle, vec![]);
+            prop_assert_eq!(running, vec![ActionName::Fund]);
+            prop_assert_eq!(completed, vec![]);
+        }
+    }
+
+    #[tokio::test]
+    async fn actions_of_unknown_swap_are_not_found() {
+        let storage = Storage::test();
+
+        let error = handle_get_swap_actions(LocalSwapId::random(), storage)
+            .await
+            .unwrap_err();
+
+        assert!(error.is::<NoSwapExists>());
+    }
+
+    #[test]
+    fn links_keep_the_requested_filters() {
+        let query =
+            SwapsQuery::parse("state=settling&state=failed&protocol=hbit&offset=0").unwrap();
+
+        let next = query.page.unwrap().next(DEFAULT_PAGE_SIZE + 1).unwrap();
+
+        assert_eq!(
+            query.href(&next),
+            format!(
+                "/swaps?offset={}&limit={}&state=settling&state=failed&protocol=hbit",
+                DEFAULT_PAGE_SIZE, DEFAULT_PAGE_SIZE
+            )
+        );
+    }
+
+    #[test]
+    fn swaps_are_not_paginated_without_offset_or_limit() {
+        let ids = (0..DEFAULT_PAGE_SIZE + 10)
+            .map(|_| LocalSwapId::random())
+            .collect::<Vec<_>>();
+
+        let all = swaps_entity(&SwapsQuery::parse("").unwrap(), ids.clone());
+        let page = swaps_entity(&SwapsQuery::parse("limit=10").unwrap(), ids);
+
+        assert_eq!(all.entities.len(), DEFAULT_PAGE_SIZE + 10);
+        assert!(all.links.is_empty());
+        assert_eq!(page.entities.len(), 10);
+    }
+
+    fn hbit_herc20_swap() -> SwapContext {
+        SwapContext {
+            id: LocalSwapId::random(),
+            role: Role::Alice,
+            alpha: LockProtocol::Hbit,
+            beta: LockProtocol::Herc20,
+        }
+    }
+
+    #[test]
+    fn filter_selects_swaps_in_the_requested_states() {
+        let open = hbit_herc20_swap();
+        let settling = hbit_herc20_swap();
+        let closed = hbit_herc20_swap();
+        let failed = hbit_herc20_swap();
+        let swaps = vec![
+            (open, SwapPhase::Open),
+            (settling, SwapPhase::Settling),
+            (closed, SwapPhase::Closed),
+            (failed, SwapPhase::Failed),
+        ];
+        let (open, settling, closed, failed) = (open.id, settling.id, closed.id, failed.id);
+
+        let select = |query: &str| SwapsQuery::parse(query).unwrap().select(swaps.clone());
+
+        assert_eq!(select("state=open"), vec![open]);
+        assert_eq!(select("state=settling"), vec![settling]);
+        assert_eq!(select("state=closed"), vec![closed]);
+        assert_eq!(select("state=failed"), vec![failed]);
+        assert_eq!(select("state=closed&state=open"), vec![open, closed]);
+        assert_eq!(select(""), vec![open, settling, failed]);
+    }
+
+    #[test]
+    fn filter_selects_swaps_using_the_requested_protocols() {
+        let hbit_herc20 = hbit_herc20_swap();
+        let herc20_herc20 = SwapContext {
+            alpha: LockProtocol::Herc20,
+            ..hbit_herc20_swap()
+        };
+        let swaps = vec![
+            (hbit_herc20, SwapPhase::Open),
+            (herc20_herc20, SwapPhase::Open),
+        ];
+
+        let select = |query: &str| SwapsQuery::parse(query).unwrap().select(swaps.clone());
+
+        assert_eq!(select("protocol=hbit"), vec![hbit_herc20.id]);
+        assert_eq!(select("protocol=herc20"), vec![
+            hbit_herc20.id,
+            herc20_herc20.id
+        ]);
+        assert_eq!(select(""), vec![hbit_herc20.id, herc20_herc20.id]);
+    }
+
+    #[tokio::test]
+    async fn replays_known_events_then_pushes_persisted_ones() {
+        let storage = Storage::test();
+        let id = LocalSwapId::random();
+        let deployed = SwapEvent::Herc20Deployed {
+            tx: Hash::from([1u8; 32]),
+        };
+        let funded = SwapEvent::Herc20Funded {
+            tx: Hash::from([2u8; 32]),
+        };
+
+        let live = storage.subscribe_swap_events();
+        let mut events = Box::pin(swap_events(id, vec![deployed], live, storage.clone()));
+
+        storage.publish_swap_event(id, deployed);
+        storage.publish_swap_event(LocalSwapId::random(), SwapEvent::Herc20Redeemed {
+            tx: Hash::from([3u8; 32]),
+        });
+        storage.publish_swap_event(id, funded);
+
+        assert_eq!(events.next().await, Some(deployed));
+        assert_eq!(events.next().await, Some(funded));
+    }
+}
@@ -1,7 +1,6 @@
-use crate::{config::Settings, ethereum, http_api::problem};
+use crate::{config::Settings, http_api::problem};
 use anyhow::Result;
 use futures::TryFutureExt;
-use serde::Serialize;
 use warp::{reply, Filter, Rejection, Reply};
 
 /// The warp filter for listing all token contract addresses as used by cnd.
@@ -14,18 +13,5 @@ pub fn route(settings: Settings) -> impl Filter<Extract = impl Reply, Error = Re
 }
 
 async fn handler(settings: Settings) -> Result<impl Reply> {
-    let dai = Token {
-        symbol: "DAI".to_owned(),
-        address: settings.ethereum.tokens.dai,
-        decimals: 18,
-    };
-
-    Ok(reply::json(&vec![dai]))
-}
-
-#[derive(Clone, Debug, Serialize)]
-struct Token {
-    symbol: String,
-    address: ethereum::Address,
-    decimals: u8,
+    Ok(reply::json(&settings.ethereum.tokens.list()))
 }
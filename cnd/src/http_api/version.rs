@@ -0,0 +1,64 @@
+use comit::network::setup_swap::{HbitHerc20Protocol, Herc20HbitProtocol};
+use libp2p::core::ProtocolName;
+use serde::Serialize;
+use warp::{reply, Filter, Rejection, Reply};
+
+/// The warp filter for telling which build of cnd is running.
+pub fn route() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get()
+        .and(warp::path!("info" / "version"))
+        .map(|| reply::json(&Version::current()))
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Version {
+    version: &'static str,
+    commit: &'static str,
+    swap_protocols: Vec<String>,
+}
+
+impl Version {
+    fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: env!("GIT_HASH").trim(),
+            swap_protocols: vec![
+                protocol_name(HbitHerc20Protocol),
+                protocol_name(Herc20HbitProtocol),
+            ],
+        }
+    }
+}
+
+fn protocol_name<P>(protocol: P) -> String
+where
+    P: ProtocolName,
+{
+    String::from_utf8_lossy(protocol.protocol_name()).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn reports_version_of_crate() {
+        let response = warp::test::request()
+            .path("/info/version")
+            .reply(&route())
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = serde_json::from_slice::<serde_json::Value>(response.body()).unwrap();
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert!(json["commit"].is_string());
+        assert_eq!(
+            json["swap_protocols"],
+            serde_json::json!([
+                "/comit/setup-swap/hbit-herc20/1.0.0",
+                "/comit/setup-swap/herc20-hbit/1.0.0"
+            ])
+        );
+    }
+}
@@ -30,6 +30,7 @@ mod bitcoin_fees;
 mod cli;
 mod config;
 mod connectors;
+mod expire_orders;
 mod file_lock;
 mod fs;
 mod hbit;
@@ -66,13 +67,14 @@ mod btsieve {
 
 use self::{
     bitcoin_fees::BitcoinFees,
-    btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector},
+    btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector, PollingPolicy},
     config::{validate_connection_to_network, Settings},
     connectors::Connectors,
+    expire_orders::expire_orders,
     file_lock::TryLockExclusive,
     local_swap_id::LocalSwapId,
     network::{Swarm, SwarmWorker},
-    republish::republish_open_orders,
+    republish::restore_orderbook,
     respawn::respawn,
     spawn::*,
     storage::{RootSeed, Sqlite, Storage},
@@ -83,12 +85,13 @@ use crate::{
 };
 use ::bitcoin::secp256k1::{All, Secp256k1};
 use anyhow::{Context, Result};
-use comit::{ledger, LockProtocol, Never, Role, Secret, Side};
+use comit::{clock::SystemClock, ledger, LockProtocol, Never, Role, Secret, Side};
 use conquer_once::Lazy;
 use futures::future;
 use rand::rngs::OsRng;
-use std::{env, process};
+use std::{env, process, time::Duration};
 use structopt::StructOpt;
+use time::OffsetDateTime;
 use tokio::{net::TcpListener, runtime::Handle};
 
 pub static SECP: Lazy<Secp256k1<All>> = Lazy::new(Secp256k1::new);
@@ -110,7 +113,7 @@ async fn main() -> Result<()> {
         process::exit(0);
     }
 
-    crate::trace::init_tracing(settings.logging.level)?;
+    crate::trace::init_tracing(settings.logging.level, settings.logging.format)?;
     std::panic::set_hook(Box::new(|panic_info| {
         tracing::error!(
             "thread panicked at {}: {}",
@@ -124,7 +127,7 @@ async fn main() -> Result<()> {
 
     let database = Sqlite::new_in_dir(&settings.data.dir)?;
     let seed = RootSeed::from_dir_or_generate(&settings.data.dir, OsRng)?;
-    let storage = Storage::new(database, seed);
+    let storage = Storage::new(database, seed).with_tokens(settings.ethereum.tokens.list());
 
     let _locked_datadir = &settings.data.dir.try_lock_exclusive()?;
 
@@ -151,7 +154,11 @@ async fn main() -> Result<()> {
             network,
             fees: _,
         } = &settings.bitcoin;
-        let connector = BitcoindConnector::new(bitcoind.node_url.clone())?;
+        let mut connector = BitcoindConnector::new(bitcoind.node_url.clone())?
+            .with_polling_policy(bitcoind.polling.map(PollingPolicy::from));
+        if let Some(address) = bitcoind.zmq_raw_block {
+            connector = connector.with_zmq_raw_block(address);
+        }
 
         match validate_connection_to_network(&connector, *network).await {
             Ok(inner) => inner?,
@@ -165,7 +172,9 @@ async fn main() -> Result<()> {
 
     let ethereum_connector = {
         let config::Ethereum { geth, chain_id, .. } = &settings.ethereum;
-        let connector = Web3Connector::new(geth.node_url.clone());
+        let connector = Web3Connector::new(geth.node_url.clone())
+            .with_max_block_range(geth.max_block_range)
+            .with_polling_policy(geth.polling.map(PollingPolicy::from));
 
         match validate_connection_to_network(&connector, *chain_id).await {
             Ok(inner) => inner?,
@@ -183,6 +192,10 @@ async fn main() -> Result<()> {
     };
 
     let connectors = Connectors::new(bitcoin_connector, ethereum_connector);
+    tokio::spawn(log_bitcoin_block_cache_stats(connectors.clone()));
+    if settings.bitcoin.bitcoind.zmq_raw_block.is_some() {
+        tokio::spawn(follow_bitcoin_blocks(connectors.clone()));
+    }
 
     let swarm = Swarm::new(
         &settings,
@@ -194,14 +207,19 @@ async fn main() -> Result<()> {
     .await?;
 
     let http_api_listener = bind_http_api_socket(&settings).await?;
-    match respawn(storage.clone(), connectors.clone(), Handle::current()).await {
+    match respawn(
+        storage.clone(),
+        connectors.clone(),
+        Handle::current(),
+        settings.swaps.max_communication_wait,
+    )
+    .await
+    {
         Ok(()) => {}
         Err(e) => tracing::warn!("failed to respawn swaps: {:#}", e),
     };
-    match republish_open_orders(storage.clone(), swarm.clone()).await {
-        Ok(()) => {}
-        Err(e) => tracing::warn!("failed to republish orders: {:#}", e),
-    };
+    restore_orderbook(storage.clone(), swarm.clone(), OffsetDateTime::now_utc()).await;
+    tokio::spawn(expire_orders(storage.clone(), swarm.clone(), SystemClock));
 
     tokio::spawn(make_http_api_worker(
         settings,
@@ -209,6 +227,7 @@ async fn main() -> Result<()> {
         options.network.unwrap_or_default(),
         swarm.clone(),
         storage,
+        connectors,
         http_api_listener,
     ));
     tokio::spawn(make_network_api_worker(swarm));
@@ -229,6 +248,39 @@ fn version() {
     println!("{} {} ({})", name, version, short);
 }
 
+/// Periodically logs the statistics of the bitcoin block cache so operators
+/// can tell whether its capacity is adequate.
+/// Follows the blocks bitcoind publishes, subscribing again a while after the
+/// subscription failed or ended. Meanwhile the latest block is polled.
+async fn follow_bitcoin_blocks(connectors: Connectors) {
+    const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(60);
+
+    loop {
+        if let Err(e) = connectors.follow_bitcoin_blocks().await {
+            tracing::warn!("failed to subscribe to bitcoin blocks: {:#}", e);
+        }
+
+        tokio::time::delay_for(RESUBSCRIBE_DELAY).await;
+    }
+}
+
+async fn log_bitcoin_block_cache_stats(connectors: Connectors) {
+    const INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+    loop {
+        tokio::time::delay_for(INTERVAL).await;
+
+        let stats = connectors.bitcoin_block_cache_stats().await;
+        tracing::info!(
+            "bitcoin block cache: {} hits, {} misses, {} evictions, {} blocks cached",
+            stats.hits,
+            stats.misses,
+            stats.evictions,
+            stats.len
+        );
+    }
+}
+
 /// Binds to the socket for the HTTP API specified in the settings
 ///
 /// Fails if we cannot bind to the socket.
@@ -248,9 +300,11 @@ async fn make_http_api_worker(
     network: comit::Network,
     swarm: Swarm,
     storage: Storage,
+    connectors: Connectors,
     incoming_requests: tokio::net::TcpListener,
 ) {
-    let routes = http_api::create_routes(swarm, storage, &settings, bitcoin_fees, network);
+    let routes =
+        http_api::create_routes(swarm, storage, &settings, bitcoin_fees, network, connectors);
 
     match incoming_requests.local_addr() {
         Ok(socket) => {
@@ -1,9 +1,12 @@
 mod comit_node;
 mod peer_tracker;
+mod reconnect;
 mod swarm;
 mod transport;
 
 // Export comit network types while maintaining the module abstraction.
 pub use ::comit::{asset, ledger, network::*};
+pub use peer_tracker::{Connection, Direction};
+pub use reconnect::ConnectionState;
 pub use swarm::{Swarm, SwarmWorker};
 pub use transport::ComitTransport;
@@ -14,8 +14,10 @@ use comit::{
 };
 use futures::{channel::mpsc, SinkExt, TryFutureExt};
 use libp2p::{identity::Keypair, NetworkBehaviour, PeerId};
+use std::time::Duration;
 use time::OffsetDateTime;
 use tokio::runtime::Handle;
+use tracing_futures::Instrument;
 
 /// A `NetworkBehaviour` that represents a COMIT node.
 #[derive(NetworkBehaviour)]
@@ -33,6 +35,8 @@ pub struct ComitNode {
     connectors: Connectors,
     #[behaviour(ignore)]
     matches_sender: mpsc::Sender<orderpool::Match>,
+    #[behaviour(ignore)]
+    max_communication_wait: Duration,
 }
 
 impl ComitNode {
@@ -43,6 +47,7 @@ impl ComitNode {
         peer_id: PeerId,
         key: Keypair,
         matches_sender: mpsc::Sender<orderpool::Match>,
+        max_communication_wait: Duration,
     ) -> Self {
         Self {
             setup_swap: Default::default(),
@@ -52,6 +57,15 @@ impl ComitNode {
             storage,
             connectors,
             matches_sender,
+            max_communication_wait,
+        }
+    }
+
+    /// Only match orders for at least `minimum`.
+    pub fn with_minimum_match(self, minimum: orderpool::MinimumMatch) -> Self {
+        Self {
+            orderbook: self.orderbook.with_minimum_match(minimum),
+            ..self
         }
     }
 }
@@ -90,6 +104,33 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<orderbook::BehaviourOutEvent> f
                     }
                 });
             }
+            orderbook::BehaviourOutEvent::RetrievedOrders { maker, orders } => {
+                let db = self.storage.db.clone();
+                let seen_at = OffsetDateTime::now_utc();
+
+                self.task_executor.spawn(async move {
+                    if let Err(e) = db
+                        .do_in_transaction(|conn| {
+                            commands::save_remote_orders(conn, maker, &orders, seen_at)
+                        })
+                        .await
+                    {
+                        tracing::warn!("failed to save remote orders: {:#}", e);
+                    }
+                });
+            }
+            orderbook::BehaviourOutEvent::MakerIsGone { maker } => {
+                let db = self.storage.db.clone();
+
+                self.task_executor.spawn(async move {
+                    if let Err(e) = db
+                        .do_in_transaction(|conn| commands::delete_remote_orders(conn, maker))
+                        .await
+                    {
+                        tracing::warn!("failed to delete remote orders: {:#}", e);
+                    }
+                });
+            }
         }
     }
 }
@@ -116,6 +157,7 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<setup_swap::BehaviourOutEvent<S
                     InsertableSwap::new(swap_id, exec_swap.peer_id, role, start_of_swap);
 
                 let hbit_params = exec_swap.hbit;
+                let quantity = hbit_params.asset;
                 let insertable_hbit = move |swap_fk, our_final_address| {
                     InsertableHbit::new(
                         swap_fk,
@@ -158,6 +200,7 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<setup_swap::BehaviourOutEvent<S
                     let connectors = self.connectors.clone();
                     let storage = self.storage.clone();
                     let handle = self.task_executor.clone();
+                    let max_communication_wait = self.max_communication_wait;
 
                     async move {
                         storage
@@ -168,7 +211,9 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<setup_swap::BehaviourOutEvent<S
                                 insertable_secret_hash(swap_pk).insert(conn)?;
                                 insertable_herc20(swap_pk).insert(conn)?;
 
-                                commands::update_btc_dai_order_to_settling(conn, order_id)?;
+                                commands::update_btc_dai_order_to_settling(
+                                    conn, order_id, quantity,
+                                )?;
 
                                 let order = Order::by_order_id(conn, order_id)?;
                                 InsertableOrderSwap::new(swap_pk, order.id).insert(conn)?;
@@ -179,27 +224,36 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<setup_swap::BehaviourOutEvent<S
                                 Ok(())
                             })
                             .await?;
-                        spawn::spawn(connectors, storage, handle, SwapContext {
-                            id: swap_id,
-                            role,
-                            alpha: match protocol {
-                                Herc20Hbit => LockProtocol::Herc20,
-                                HbitHerc20 => LockProtocol::Hbit,
+                        spawn::spawn(
+                            connectors,
+                            storage,
+                            handle,
+                            SwapContext {
+                                id: swap_id,
+                                role,
+                                alpha: match protocol {
+                                    Herc20Hbit => LockProtocol::Herc20,
+                                    HbitHerc20 => LockProtocol::Hbit,
+                                },
+                                beta: match protocol {
+                                    Herc20Hbit => LockProtocol::Hbit,
+                                    HbitHerc20 => LockProtocol::Herc20,
+                                },
                             },
-                            beta: match protocol {
-                                Herc20Hbit => LockProtocol::Hbit,
-                                HbitHerc20 => LockProtocol::Herc20,
-                            },
-                        })
+                            max_communication_wait,
+                        )
                         .await?;
 
                         Ok(())
                     }
                 };
-                self.task_executor
-                    .spawn(save_data_and_start_swap.map_err(|e: anyhow::Error| {
-                        tracing::error!("{}", e);
-                    }));
+                self.task_executor.spawn(
+                    save_data_and_start_swap
+                        .map_err(|e: anyhow::Error| {
+                            tracing::error!("{}", e);
+                        })
+                        .instrument(spawn::swap_span(swap_id)),
+                );
                 if let Err(e) = self
                     .orderbook
                     .orderpool_mut()
@@ -215,6 +269,9 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<setup_swap::BehaviourOutEvent<S
                 "Already have role dependent parameters from this peer: {}",
                 peer
             ),
+            setup_swap::BehaviourOutEvent::Declined { peer, reason } => {
+                tracing::info!("Declined swap proposed by {}: {}", peer, reason)
+            }
         }
     }
 }
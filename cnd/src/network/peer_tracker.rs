@@ -8,9 +8,11 @@ use libp2p::{
     },
     Multiaddr, PeerId,
 };
+use serde::Serialize;
 use std::{
     collections::{hash_map::Entry, HashMap, VecDeque},
     task::Poll,
+    time::Instant,
 };
 
 /// A NetworkBehaviour that tracks connections to other peers.
@@ -18,12 +20,34 @@ use std::{
 pub struct PeerTracker {
     connected_peers: HashMap<PeerId, Vec<Multiaddr>>,
     address_hints: HashMap<PeerId, VecDeque<Multiaddr>>,
+    connections: HashMap<ConnectionId, (PeerId, Connection)>,
+}
+
+/// Who opened a connection.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// A connection to another peer, dialed by us or by them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Connection {
+    pub direction: Direction,
+    pub address: Multiaddr,
+    pub established: Instant,
 }
 
 impl PeerTracker {
     pub fn connected_peers(&self) -> impl Iterator<Item = (PeerId, Vec<Multiaddr>)> {
         self.connected_peers.clone().into_iter()
     }
+
+    /// All open connections, inbound ones included.
+    pub fn connections(&self) -> Vec<(PeerId, Connection)> {
+        self.connections.values().cloned().collect()
+    }
 }
 
 impl NetworkBehaviour for PeerTracker {
@@ -65,9 +89,22 @@ impl NetworkBehaviour for PeerTracker {
     fn inject_connection_established(
         &mut self,
         peer: &PeerId,
-        _: &ConnectionId,
+        id: &ConnectionId,
         point: &ConnectedPoint,
     ) {
+        let (direction, address) = match point {
+            ConnectedPoint::Dialer { address } => (Direction::Outbound, address),
+            ConnectedPoint::Listener { send_back_addr, .. } => (Direction::Inbound, send_back_addr),
+        };
+        self.connections.insert(
+            *id,
+            (peer.clone(), Connection {
+                direction,
+                address: address.clone(),
+                established: Instant::now(),
+            }),
+        );
+
         if let ConnectedPoint::Dialer { address } = point {
             self.connected_peers
                 .entry(peer.clone())
@@ -79,9 +116,11 @@ impl NetworkBehaviour for PeerTracker {
     fn inject_connection_closed(
         &mut self,
         peer: &PeerId,
-        _: &ConnectionId,
+        id: &ConnectionId,
         point: &ConnectedPoint,
     ) {
+        self.connections.remove(id);
+
         if let ConnectedPoint::Dialer { address } = point {
             match self.connected_peers.entry(peer.clone()) {
                 Entry::Vacant(_) => {}
@@ -121,6 +160,10 @@ mod tests {
         assert!(bob_swarm.connected_peers.is_empty());
 
         connect(&mut alice_swarm, &mut bob_swarm).await;
+        // `connect` returns once either side established the connection
+        while bob_swarm.connections().is_empty() {
+            let _ = bob_swarm.next_event().await;
+        }
 
         assert_eq!(
             alice_swarm.connected_peers.get(&bob_id),
@@ -130,5 +173,18 @@ mod tests {
             bob_swarm.connected_peers.is_empty(),
             "we only track dialed connections"
         );
+
+        let directions = |connections: Vec<(PeerId, Connection)>| {
+            connections
+                .into_iter()
+                .map(|(peer, connection)| (peer, connection.direction))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(directions(alice_swarm.connections()), vec![(
+            bob_id,
+            Direction::Outbound
+        )]);
+        assert_eq!(directions(bob_swarm.connections()).len(), 1);
+        assert_eq!(directions(bob_swarm.connections())[0].1, Direction::Inbound);
     }
 }
@@ -0,0 +1,270 @@
+//! Redial makers we lost the connection to.
+//!
+//! The orders of a maker are removed from our order pool as soon as the
+//! connection to them closes. Redialing brings their orders back once they are
+//! reachable again, hence a brief outage does not make their orders vanish for
+//! good.
+
+use crate::config::Reconnect;
+use anyhow::Result;
+use libp2p::{Multiaddr, PeerId};
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+/// How often we check whether a maker needs to be redialed.
+pub const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[async_trait::async_trait]
+pub trait Peers: Send + Sync {
+    /// The peers we dialed, with the addresses their open connections use.
+    async fn dialed_peers(&self) -> Vec<(PeerId, Vec<Multiaddr>)>;
+
+    /// The peers whose orders are in our order pool.
+    async fn makers(&self) -> HashSet<PeerId>;
+
+    async fn redial(&self, address: Multiaddr) -> Result<()>;
+}
+
+/// The state of our connection to a maker.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connected,
+    Reconnecting { attempts: u32 },
+}
+
+#[derive(Debug)]
+struct Tracked {
+    addresses: Vec<Multiaddr>,
+    state: ConnectionState,
+    next_attempt: Instant,
+}
+
+/// Keeps track of the makers we dialed and redials them with an exponential
+/// backoff once the connection is lost.
+#[derive(Debug)]
+pub struct Reconnector {
+    policy: Reconnect,
+    peers: HashMap<PeerId, Tracked>,
+}
+
+impl Reconnector {
+    pub fn new(policy: Reconnect) -> Self {
+        Self {
+            policy,
+            peers: HashMap::new(),
+        }
+    }
+
+    pub fn connection_states(&self) -> Vec<(PeerId, ConnectionState)> {
+        self.peers
+            .iter()
+            .map(|(peer, tracked)| (peer.clone(), tracked.state))
+            .collect()
+    }
+
+    /// Starts tracking the makers we are connected to and redials the ones
+    /// whose backoff elapsed at `now`.
+    pub async fn tick<P>(&mut self, peers: &P, now: Instant)
+    where
+        P: Peers,
+    {
+        let makers = peers.makers().await;
+        let connected = peers
+            .dialed_peers()
+            .await
+            .into_iter()
+            .filter(|(_, addresses)| !addresses.is_empty())
+            .collect::<HashMap<_, _>>();
+
+        // Makers that cancelled all their orders are no longer worth redialing.
+        self.peers
+            .retain(|peer, _| makers.contains(peer) || !connected.contains_key(peer));
+
+        for (peer, addresses) in connected.iter() {
+            if !makers.contains(peer) && !self.peers.contains_key(peer) {
+                continue;
+            }
+
+            self.peers.insert(peer.clone(), Tracked {
+                addresses: addresses.clone(),
+                state: ConnectionState::Connected,
+                next_attempt: now,
+            });
+        }
+
+        for (peer, tracked) in self.peers.iter_mut() {
+            if connected.contains_key(peer) {
+                continue;
+            }
+
+            let attempts = match tracked.state {
+                ConnectionState::Connected => {
+                    tracing::info!("lost connection to maker {}, redialing", peer);
+                    0
+                }
+                ConnectionState::Reconnecting { .. } if now < tracked.next_attempt => continue,
+                ConnectionState::Reconnecting { attempts } => attempts,
+            };
+
+            for address in tracked.addresses.iter() {
+                if let Err(e) = peers.redial(address.clone()).await {
+                    tracing::warn!("failed to redial maker {} on {}: {:#}", peer, address, e);
+                }
+            }
+
+            let attempts = attempts.saturating_add(1);
+            tracked.state = ConnectionState::Reconnecting { attempts };
+            tracked.next_attempt = now + backoff(self.policy, attempts);
+        }
+    }
+}
+
+/// How long we wait after the given number of attempts before dialing again.
+fn backoff(policy: Reconnect, attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+
+    policy
+        .initial_backoff
+        .checked_mul(factor)
+        .map_or(policy.max_backoff, |backoff| {
+            std::cmp::min(backoff, policy.max_backoff)
+        })
+}
+
+/// Redials lost makers until the process exits.
+pub async fn watch<P>(peers: P, reconnector: std::sync::Arc<tokio::sync::Mutex<Reconnector>>)
+where
+    P: Peers,
+{
+    loop {
+        reconnector.lock().await.tick(&peers, Instant::now()).await;
+
+        tokio::time::delay_for(TICK_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// A transport that refuses the first dial and connects on every later
+    /// one.
+    #[derive(Default)]
+    struct FlakyTransport {
+        connected: Mutex<HashMap<PeerId, Multiaddr>>,
+        addresses: Mutex<HashMap<Multiaddr, PeerId>>,
+        dials: Mutex<u32>,
+    }
+
+    impl FlakyTransport {
+        fn connect(&self, peer: &PeerId, address: &Multiaddr) {
+            self.addresses
+                .lock()
+                .unwrap()
+                .insert(address.clone(), peer.clone());
+            self.connected
+                .lock()
+                .unwrap()
+                .insert(peer.clone(), address.clone());
+        }
+
+        fn disconnect(&self, peer: &PeerId) {
+            self.connected.lock().unwrap().remove(peer);
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Peers for FlakyTransport {
+        async fn dialed_peers(&self) -> Vec<(PeerId, Vec<Multiaddr>)> {
+            self.connected
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(peer, address)| (peer.clone(), vec![address.clone()]))
+                .collect()
+        }
+
+        async fn makers(&self) -> HashSet<PeerId> {
+            self.connected.lock().unwrap().keys().cloned().collect()
+        }
+
+        async fn redial(&self, address: Multiaddr) -> Result<()> {
+            let mut dials = self.dials.lock().unwrap();
+            *dials += 1;
+
+            if *dials == 1 {
+                anyhow::bail!("connection refused")
+            }
+
+            let peer = self.addresses.lock().unwrap()[&address].clone();
+            self.connected.lock().unwrap().insert(peer, address);
+
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn redials_maker_after_failed_first_attempt() {
+        let policy = Reconnect {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        };
+        let transport = FlakyTransport::default();
+        let maker = PeerId::random();
+        let address = "/memory/1".parse::<Multiaddr>().unwrap();
+        let mut reconnector = Reconnector::new(policy);
+        let start = Instant::now();
+
+        transport.connect(&maker, &address);
+        reconnector.tick(&transport, start).await;
+        assert_eq!(reconnector.connection_states(), vec![(
+            maker.clone(),
+            ConnectionState::Connected
+        )]);
+
+        transport.disconnect(&maker);
+        reconnector.tick(&transport, start).await;
+        assert_eq!(reconnector.connection_states(), vec![(
+            maker.clone(),
+            ConnectionState::Reconnecting { attempts: 1 }
+        )]);
+
+        // still backing off, we must not dial again
+        reconnector
+            .tick(&transport, start + Duration::from_millis(500))
+            .await;
+        assert_eq!(*transport.dials.lock().unwrap(), 1);
+
+        reconnector
+            .tick(&transport, start + Duration::from_secs(1))
+            .await;
+        assert_eq!(*transport.dials.lock().unwrap(), 2);
+
+        reconnector
+            .tick(&transport, start + Duration::from_secs(2))
+            .await;
+        assert_eq!(reconnector.connection_states(), vec![(
+            maker,
+            ConnectionState::Connected
+        )]);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let policy = Reconnect {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+        };
+
+        let backoffs = (1..=5)
+            .map(|attempts| backoff(policy, attempts).as_secs())
+            .collect::<Vec<_>>();
+
+        assert_eq!(backoffs, vec![1, 2, 4, 5, 5]);
+    }
+}
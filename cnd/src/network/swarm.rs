@@ -5,6 +5,8 @@ use crate::{
     local_swap_id::LocalSwapId,
     network::{
         comit_node::{ComitNode, SetupSwapContext},
+        peer_tracker::Connection,
+        reconnect::{self, ConnectionState, Peers, Reconnector},
         setup_swap,
         setup_swap::{AliceParams, BobParams},
         transport,
@@ -27,11 +29,13 @@ use libp2p::{
     PeerId,
 };
 use std::{
+    collections::HashSet,
     future::Future,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
 };
+use time::OffsetDateTime;
 use tokio::sync::Mutex;
 
 #[derive(Clone, derivative::Derivative)]
@@ -40,6 +44,7 @@ pub struct Swarm {
     #[derivative(Debug = "ignore")]
     inner: Arc<Mutex<libp2p::Swarm<ComitNode>>>,
     local_peer_id: PeerId,
+    reconnector: Arc<Mutex<Reconnector>>,
 }
 
 impl Swarm {
@@ -65,7 +70,9 @@ impl Swarm {
             local_peer_id.clone(),
             local_key_pair,
             sender,
-        );
+            settings.swaps.max_communication_wait,
+        )
+        .with_minimum_match(settings.orders.clone().into());
 
         let mut swarm = SwarmBuilder::new(transport, behaviour, local_peer_id.clone())
             .executor(Box::new(TokioExecutor {
@@ -89,10 +96,15 @@ impl Swarm {
 
         task_executor.spawn(new_match_worker(swarm.clone(), receiver, storage, seed));
 
-        Ok(Self {
+        let swarm = Self {
             inner: swarm,
             local_peer_id,
-        })
+            reconnector: Arc::new(Mutex::new(Reconnector::new(settings.network.reconnect))),
+        };
+
+        task_executor.spawn(reconnect::watch(swarm.clone(), swarm.reconnector.clone()));
+
+        Ok(swarm)
     }
 
     pub async fn publish_order(&self, order: BtcDaiOrder) {
@@ -114,6 +126,31 @@ impl Swarm {
         self.inner.lock().await.orderbook.cancel(order_id);
     }
 
+    /// Adds orders of another maker that we saw at `seen_at`.
+    pub async fn receive_orders_seen_at(
+        &self,
+        maker: PeerId,
+        orders: Vec<BtcDaiOrder>,
+        seen_at: OffsetDateTime,
+    ) {
+        self.inner
+            .lock()
+            .await
+            .orderbook
+            .orderpool_mut()
+            .receive_seen_at(maker, orders, seen_at);
+    }
+
+    /// Removes the orders of all makers we did not hear from since `cutoff`.
+    pub async fn remove_orders_not_seen_since(&self, cutoff: OffsetDateTime) -> Vec<PeerId> {
+        self.inner
+            .lock()
+            .await
+            .orderbook
+            .orderpool_mut()
+            .remove_not_seen_since(cutoff)
+    }
+
     pub async fn dial_addr(&self, addr: Multiaddr) -> anyhow::Result<()> {
         let mut guard = self.inner.lock().await;
         let _ = libp2p::Swarm::dial_addr(&mut *guard, addr)?;
@@ -138,6 +175,17 @@ impl Swarm {
         Box::new(swarm.peer_tracker.connected_peers())
     }
 
+    /// Every open connection, including the ones other peers opened to us.
+    pub async fn connections(&self) -> Vec<(PeerId, Connection)> {
+        let swarm = self.inner.lock().await;
+        swarm.peer_tracker.connections()
+    }
+
+    /// The state of our connections to the makers we dialed.
+    pub async fn connection_states(&self) -> Vec<(PeerId, ConnectionState)> {
+        self.reconnector.lock().await.connection_states()
+    }
+
     pub async fn listen_addresses(&self) -> Vec<Multiaddr> {
         let swarm = self.inner.lock().await;
 
@@ -148,6 +196,25 @@ impl Swarm {
     }
 }
 
+#[async_trait::async_trait]
+impl Peers for Swarm {
+    async fn dialed_peers(&self) -> Vec<(PeerId, Vec<Multiaddr>)> {
+        self.connected_peers().await.collect()
+    }
+
+    async fn makers(&self) -> HashSet<PeerId> {
+        self.btc_dai_market()
+            .await
+            .into_iter()
+            .map(|(maker, _)| maker)
+            .collect()
+    }
+
+    async fn redial(&self, address: Multiaddr) -> Result<()> {
+        self.dial_addr(address).await
+    }
+}
+
 struct TokioExecutor {
     handle: tokio::runtime::Handle,
 }
@@ -115,6 +115,8 @@ pub mod ledger {
         prop_oneof![
             Just(ledger::Bitcoin::Mainnet),
             Just(ledger::Bitcoin::Testnet),
+            Just(ledger::Bitcoin::Testnet4),
+            Just(ledger::Bitcoin::Signet),
             Just(ledger::Bitcoin::Regtest)
         ]
     }
@@ -145,3 +147,101 @@ pub mod asset {
         }
     }
 }
+
+pub mod order {
+    use super::*;
+    use comit::{
+        expiries::{AlphaOffset, BetaOffset},
+        order::SwapProtocol,
+        BtcDaiOrder, OrderId, Position, Price, Quantity,
+    };
+
+    pub fn position() -> impl Strategy<Value = Position> {
+        prop_oneof![Just(Position::Buy), Just(Position::Sell)]
+    }
+
+    /// A non-zero quantity, orders for nothing are never open.
+    pub fn quantity() -> impl Strategy<Value = Quantity<comit::asset::Bitcoin>> {
+        (1..=u64::MAX).prop_map(|sats| Quantity::new(comit::asset::Bitcoin::from_sat(sats)))
+    }
+
+    pub fn price(
+    ) -> impl Strategy<Value = Price<comit::asset::Bitcoin, comit::asset::Erc20Quantity>> {
+        ethereum::erc20_quantity().prop_map(Price::from_wei_per_sat)
+    }
+
+    prop_compose! {
+        fn expiry_offsets()(
+            alpha in any::<u32>(),
+            beta in any::<u32>(),
+        ) -> (AlphaOffset, BetaOffset) {
+            (
+                AlphaOffset::from(time::Duration::seconds(alpha.into())),
+                BetaOffset::from(time::Duration::seconds(beta.into())),
+            )
+        }
+    }
+
+    pub fn swap_protocol() -> impl Strategy<Value = SwapProtocol> {
+        prop_oneof![
+            expiry_offsets().prop_map(|(hbit_expiry_offset, herc20_expiry_offset)| {
+                SwapProtocol::HbitHerc20 {
+                    hbit_expiry_offset,
+                    herc20_expiry_offset,
+                }
+            }),
+            expiry_offsets().prop_map(|(herc20_expiry_offset, hbit_expiry_offset)| {
+                SwapProtocol::Herc20Hbit {
+                    herc20_expiry_offset,
+                    hbit_expiry_offset,
+                }
+            }),
+        ]
+    }
+
+    prop_compose! {
+        pub fn btc_dai()(
+            id in prop::num::u128::ANY,
+            position in position(),
+            swap_protocol in swap_protocol(),
+            created_at in timestamp(),
+            valid_until in prop::option::of(timestamp()),
+            quantity in quantity(),
+            price in price(),
+        ) -> BtcDaiOrder {
+            BtcDaiOrder {
+                id: OrderId::from(Uuid::from_u128(id)),
+                position,
+                swap_protocol,
+                created_at,
+                valid_until,
+                quantity,
+                price,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use comit::{order::SwapProtocol, BtcDaiOrder};
+
+    proptest! {
+        #[test]
+        fn btc_dai_order_roundtrips_through_serde(order in order::btc_dai()) {
+            let json = serde_json::to_string(&order).unwrap();
+            let deserialized = serde_json::from_str::<BtcDaiOrder>(&json).unwrap();
+
+            prop_assert_eq!(deserialized, order);
+        }
+
+        #[test]
+        fn swap_protocol_roundtrips_through_serde(swap_protocol in order::swap_protocol()) {
+            let json = serde_json::to_string(&swap_protocol).unwrap();
+            let deserialized = serde_json::from_str::<SwapProtocol>(&json).unwrap();
+
+            prop_assert_eq!(deserialized, swap_protocol);
+        }
+    }
+}
@@ -1,19 +1,65 @@
 use crate::{
+    expire_orders::REMOTE_ORDERS_TTL,
     network::Swarm,
-    storage::{queries::get_orders_to_republish, Storage},
+    storage::{
+        queries::{get_orders_to_republish, get_remote_orders_seen_since},
+        Storage,
+    },
 };
 use anyhow::Result;
+use std::collections::HashMap;
+use time::OffsetDateTime;
 
-/// Republish all open orders to the orderbook.
-pub async fn republish_open_orders(storage: Storage, swarm: Swarm) -> Result<()> {
+/// Fills the orderbook of a freshly started node with what we saved before
+/// the restart, so the market does not start out empty.
+pub async fn restore_orderbook(storage: Storage, swarm: Swarm, now: OffsetDateTime) {
+    match republish_open_orders(storage.clone(), swarm.clone(), now).await {
+        Ok(()) => {}
+        Err(e) => tracing::warn!("failed to republish orders: {:#}", e),
+    };
+    match restore_remote_orders(storage, swarm, now).await {
+        Ok(()) => {}
+        Err(e) => tracing::warn!("failed to restore remote orders: {:#}", e),
+    };
+}
+
+/// Republish all open orders that did not expire to the orderbook.
+async fn republish_open_orders(storage: Storage, swarm: Swarm, now: OffsetDateTime) -> Result<()> {
     let open_btc_dai_orders = storage
         .db
         .do_in_transaction(get_orders_to_republish)
         .await?;
 
-    for order in open_btc_dai_orders {
+    for order in open_btc_dai_orders
+        .into_iter()
+        .filter(|order| !order.is_expired(now))
+    {
         swarm.publish_order(order).await;
     }
 
     Ok(())
 }
+
+/// Puts the orders of other makers we saw recently back into the orderbook,
+/// they are removed again unless the makers are seen within
+/// `REMOTE_ORDERS_TTL`.
+async fn restore_remote_orders(storage: Storage, swarm: Swarm, now: OffsetDateTime) -> Result<()> {
+    let remote_orders = storage
+        .db
+        .do_in_transaction(|conn| get_remote_orders_seen_since(conn, now - REMOTE_ORDERS_TTL))
+        .await?;
+
+    let mut by_maker = HashMap::new();
+    for (maker, order, seen_at) in remote_orders {
+        let (orders, _) = by_maker
+            .entry(maker)
+            .or_insert_with(|| (Vec::new(), seen_at));
+        orders.push(order);
+    }
+
+    for (maker, (orders, seen_at)) in by_maker {
+        swarm.receive_orders_seen_at(maker, orders, seen_at).await;
+    }
+
+    Ok(())
+}
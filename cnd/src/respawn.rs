@@ -7,8 +7,9 @@
 use crate::{
     connectors::Connectors,
     spawn::spawn,
-    storage::{commands, queries::get_active_swap_contexts, Storage},
+    storage::{queries::get_active_swap_contexts, Storage},
 };
+use std::time::Duration;
 use tokio::runtime::Handle;
 
 /// Respawn the protocols for all swaps that are not yet done.
@@ -16,6 +17,7 @@ pub async fn respawn(
     storage: Storage,
     connectors: Connectors,
     handle: Handle,
+    max_communication_wait: Duration,
 ) -> anyhow::Result<()> {
     let swaps = storage
         .db
@@ -24,17 +26,16 @@ pub async fn respawn(
 
     for swap in swaps {
         let id = swap.id;
-        if let Err(e) = spawn(connectors.clone(), storage.clone(), handle.clone(), swap).await {
-            tracing::warn!(swap_id = %id, "failed to spawn swap {:#}", e);
-            continue;
-        };
-
-        if let Err(e) = storage
-            .db
-            .do_in_transaction(|conn| commands::update_order_of_swap_to_settling(conn, id))
-            .await
+        if let Err(e) = spawn(
+            connectors.clone(),
+            storage.clone(),
+            handle.clone(),
+            swap,
+            max_communication_wait,
+        )
+        .await
         {
-            tracing::warn!(swap_id = %id, "failed to update order state for swap {:#}", e);
+            tracing::warn!(swap_id = %id, "failed to spawn swap {:#}", e);
         }
     }
 
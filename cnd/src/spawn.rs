@@ -6,11 +6,17 @@ use crate::{
     Role, Side, Storage,
 };
 use anyhow::Result;
-use comit::swap::{hbit, Action};
+use comit::{
+    btsieve::MempoolTransactions,
+    clock::{Clock, SystemClock},
+    swap::{hbit, Action},
+};
 use diesel::SqliteConnection;
-use futures::prelude::*;
+use futures::{future::Either, prelude::*};
+use std::time::Duration;
 use time::OffsetDateTime;
 use tokio::runtime::Handle;
+use tracing_futures::Instrument;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Swap<A, B> {
@@ -28,27 +34,188 @@ struct MetaData {
     start: OffsetDateTime,
 }
 
+/// The span every log line of a swap is recorded in, from its creation until
+/// it is settled.
+///
+/// It has no parent so that the swap is not attributed to whichever span
+/// happens to spawn it.
+pub fn swap_span(swap_id: LocalSwapId) -> tracing::Span {
+    tracing::info_span!(parent: None, "swap", swap_id = %swap_id)
+}
+
+/// How often we check whether a swap that nobody funded yet is to be
+/// abandoned.
+const ABANDON_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("swap was not funded within {0:?}, abandoning it")]
+pub struct Abandoned(Duration);
+
 pub async fn spawn(
     connectors: Connectors,
     storage: Storage,
     handle: Handle,
     swap_context: SwapContext,
+    max_communication_wait: Duration,
 ) -> anyhow::Result<()> {
     within_swap_context!(swap_context, {
         let swap = Load::<Swap<AlphaParams, BetaParams>>::load(&storage, swap_context.id).await?;
+        // Funding events are only kept in memory, a respawned swap has to
+        // rediscover them first, hence the window never starts before now.
+        let window_start = std::cmp::max(swap.start_of_swap, SystemClock.now());
 
-        handle.spawn(async move {
-            let swap_result = swap
-                .execute(swap_context.id, connectors.clone(), storage.clone())
-                .await;
+        let alpha = swap.alpha.clone();
+        let beta = swap.beta.clone();
+        let mempool_connectors = connectors.clone();
+        let seen_in_mempool = move || {
+            let alpha = alpha.clone();
+            let beta = beta.clone();
+            let connectors = mempool_connectors.clone();
 
-            handle_swap_result(swap_result, storage, swap_context.id).await;
-        });
+            async move {
+                Ok(alpha.seen_in_mempool(&connectors).await?
+                    || beta.seen_in_mempool(&connectors).await?)
+            }
+        };
+
+        let execution = swap.execute(swap_context.id, connectors, storage.clone());
+        let abandonment = abandon_unless_committed(
+            storage.clone(),
+            swap_context.id,
+            window_start,
+            max_communication_wait,
+            SystemClock,
+            ABANDON_POLL_INTERVAL,
+            seen_in_mempool,
+        );
+
+        handle.spawn(
+            supervise(execution, abandonment, storage, swap_context.id)
+                .instrument(swap_span(swap_context.id)),
+        );
     });
 
     Ok(())
 }
 
+/// Drives the execution of a swap until it finishes or is abandoned and
+/// records the outcome.
+async fn supervise(
+    execution: impl Future<Output = Result<()>>,
+    abandonment: impl Future<Output = Abandoned>,
+    storage: Storage,
+    swap_id: LocalSwapId,
+) {
+    futures::pin_mut!(execution, abandonment);
+
+    let swap_result = match future::select(execution, abandonment).await {
+        Either::Left((swap_result, _)) => swap_result,
+        Either::Right((abandoned, _)) => Err(abandoned.into()),
+    };
+
+    handle_swap_result(swap_result, storage, swap_id).await;
+}
+
+/// Resolves once nothing was committed to the swap `max_communication_wait`
+/// after `window_start`, never resolves once funds may have been committed.
+///
+/// Funds may have been committed as soon as we handed out an action that
+/// locks them up, a lock transaction was mined or one is waiting in the
+/// mempool of either ledger.
+async fn abandon_unless_committed<F, Fut>(
+    storage: Storage,
+    swap_id: LocalSwapId,
+    window_start: OffsetDateTime,
+    max_communication_wait: Duration,
+    clock: impl Clock,
+    poll_interval: Duration,
+    seen_in_mempool: F,
+) -> Abandoned
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    loop {
+        if is_committed(&storage, swap_id).await {
+            return future::pending().await;
+        }
+
+        if clock.now() >= window_start + max_communication_wait {
+            match seen_in_mempool().await {
+                Ok(true) => {
+                    tracing::info!("lock transaction is waiting to be mined, not abandoning swap");
+                    return future::pending().await;
+                }
+                Ok(false) => return Abandoned(max_communication_wait),
+                Err(e) => tracing::warn!(
+                    "failed to look for lock transactions in the mempool: {:#}",
+                    e
+                ),
+            }
+        }
+
+        tokio::time::delay_for(poll_interval).await;
+    }
+}
+
+async fn is_committed(storage: &Storage, swap_id: LocalSwapId) -> bool {
+    let action_issued = storage
+        .funding_actions_issued
+        .lock()
+        .await
+        .contains(&swap_id);
+    let hbit_funded = storage
+        .hbit_events
+        .lock()
+        .await
+        .get(&swap_id)
+        .map_or(false, |events| events.fund.is_some());
+    let herc20_locked = storage
+        .herc20_events
+        .lock()
+        .await
+        .get(&swap_id)
+        .map_or(false, |events| {
+            events.deploy.is_some() || events.fund.is_some()
+        });
+
+    action_issued || hbit_funded || herc20_locked
+}
+
+/// Looks for a transaction of the protocol that locks up the asset and is not
+/// mined yet.
+#[async_trait::async_trait]
+trait SeenInMempool {
+    async fn seen_in_mempool(&self, connectors: &Connectors) -> Result<bool>;
+}
+
+#[async_trait::async_trait]
+impl SeenInMempool for hbit::Params {
+    async fn seen_in_mempool(&self, connectors: &Connectors) -> Result<bool> {
+        let script_pubkey = self.shared.compute_address().script_pubkey();
+        let transactions = connectors.bitcoin().mempool_transactions().await?;
+
+        Ok(transactions.iter().any(|transaction| {
+            transaction
+                .output
+                .iter()
+                .any(|output| output.script_pubkey == script_pubkey)
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl SeenInMempool for herc20::Params {
+    async fn seen_in_mempool(&self, connectors: &Connectors) -> Result<bool> {
+        let bytecode = self.bytecode();
+        let transactions = connectors.ethereum().mempool_transactions().await?;
+
+        Ok(transactions
+            .iter()
+            .any(|transaction| transaction.to.is_none() && transaction.input.0 == bytecode))
+    }
+}
+
 async fn handle_swap_result(swap_result: Result<()>, storage: Storage, swap_id: LocalSwapId) {
     let db_update: Box<dyn Fn(&SqliteConnection) -> Result<()> + Send> = match swap_result {
         Ok(()) => {
@@ -61,11 +228,24 @@ async fn handle_swap_result(swap_result: Result<()>, storage: Storage, swap_id:
                 Ok(())
             })
         }
+        Err(e) if e.is::<Abandoned>() => {
+            tracing::warn!(swap = %swap_id, "{:#}", e);
+
+            Box::new(move |conn| {
+                commands::update_order_of_swap_to_failed(conn, swap_id)?;
+                commands::mark_swap_as_failed(conn, swap_id, OffsetDateTime::now_utc())?;
+                // the counterparty may still fund late, hence the swap is not
+                // marked as completed so that a restart respawns it
+
+                Ok(())
+            })
+        }
         Err(e) => {
             tracing::error!(swap = %swap_id, "failed to complete swap: {:#}", e);
 
             Box::new(move |conn| {
                 commands::update_order_of_swap_to_failed(conn, swap_id)?;
+                commands::mark_swap_as_failed(conn, swap_id, OffsetDateTime::now_utc())?;
                 // we don't mark a swap as completed in case of failure so that a
                 // restart of the node will respawn the swap
 
@@ -194,8 +374,196 @@ where
     E: std::error::Error + Send + Sync + 'static,
 {
     while let Some(action) = swap.try_next().await? {
+        if let Action::Herc20Deploy(_) | Action::Herc20Fund(_) | Action::HbitFund(_) = action {
+            storage.funding_actions_issued.lock().await.insert(swap_id);
+        }
         storage.next_action.lock().await.insert(swap_id, action);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset,
+        proptest::*,
+        storage::{
+            commands::update_btc_dai_order_to_settling, proptest::tables, queries, BtcDaiOrder,
+            InsertableBtcDaiOrder, InsertableHbit, InsertableHerc20, InsertableOrder,
+            InsertableOrderSwap, InsertableSwap, Order, Sqlite,
+        },
+    };
+    use comit::{
+        asset::{ethereum::FromWei, Erc20Quantity},
+        clock::TestClock,
+        OrderId, Position,
+    };
+    use std::convert::TryFrom;
+    use tokio::runtime::Runtime;
+
+    // FK given by sqlite deterministically start from 1, we can thus anticipate
+    // which FK will be used if we have a fresh test database for every test.
+    const SWAP_FK: i32 = 1;
+    const MAX_COMMUNICATION_WAIT: Duration = Duration::from_secs(600);
+    const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+    /// Inserts a swap that fills the whole of a sell order.
+    async fn insert_settling_swap(
+        db: &Sqlite,
+        swap: InsertableSwap,
+        hbit: InsertableHbit,
+        herc20: InsertableHerc20,
+    ) -> OrderId {
+        let order_id = OrderId::random();
+        let quantity = asset::Bitcoin::from(hbit.amount);
+
+        db.do_in_transaction(|conn| {
+            let order_fk =
+                InsertableOrder::new(order_id, Position::Sell, OffsetDateTime::now_utc(), None)
+                    .insert(conn)?;
+            InsertableBtcDaiOrder::new(
+                order_fk,
+                quantity,
+                Erc20Quantity::from_wei(9_000_000_000_000u64),
+            )
+            .insert(conn)?;
+            update_btc_dai_order_to_settling(conn, order_id, quantity)?;
+
+            let swap_fk = swap.insert(conn)?;
+            hbit.insert(conn)?;
+            herc20.insert(conn)?;
+            InsertableOrderSwap::new(swap_fk, order_fk).insert(conn)
+        })
+        .await
+        .unwrap();
+
+        order_id
+    }
+
+    async fn load_order(db: &Sqlite, order_id: OrderId) -> BtcDaiOrder {
+        db.do_in_transaction(|conn| {
+            let order = Order::by_order_id(conn, order_id)?;
+            BtcDaiOrder::by_order(conn, &order)
+        })
+        .await
+        .unwrap()
+    }
+
+    async fn is_active(db: &Sqlite, swap_id: LocalSwapId) -> bool {
+        db.do_in_transaction(queries::get_active_swap_contexts)
+            .await
+            .unwrap()
+            .iter()
+            .any(|context| context.id == swap_id)
+    }
+
+    /// Supervises a swap whose execution never finishes once its
+    /// communication window is over.
+    async fn supervise_after_window(
+        storage: &Storage,
+        swap_id: LocalSwapId,
+        seen_in_mempool: bool,
+    ) {
+        let window_start = OffsetDateTime::from_unix_timestamp(1_000);
+        let clock = TestClock::new(window_start);
+        clock.advance(time::Duration::try_from(MAX_COMMUNICATION_WAIT).unwrap());
+
+        let abandonment = abandon_unless_committed(
+            storage.clone(),
+            swap_id,
+            window_start,
+            MAX_COMMUNICATION_WAIT,
+            clock,
+            POLL_INTERVAL,
+            move || future::ready(Ok(seen_in_mempool)),
+        );
+
+        supervise(future::pending(), abandonment, storage.clone(), swap_id).await
+    }
+
+    proptest! {
+        #[test]
+        fn unfunded_swap_is_abandoned_but_not_completed(
+            swap in tables::insertable_swap(),
+            hbit in tables::insertable_hbit(SWAP_FK, Side::Alpha),
+            herc20 in tables::insertable_herc20(SWAP_FK, Side::Beta),
+        ) {
+            let storage = Storage::test();
+            let swap_id = swap.local_swap_id.0;
+            let mut runtime = Runtime::new().unwrap();
+
+            let (order, active) = runtime.block_on(async {
+                let order_id = insert_settling_swap(&storage.db, swap, hbit, herc20).await;
+
+                supervise_after_window(&storage, swap_id, false).await;
+
+                let order = load_order(&storage.db, order_id).await;
+                let active = is_active(&storage.db, swap_id).await;
+
+                (order, active)
+            });
+
+            prop_assert_eq!(order.failed, order.quantity);
+            prop_assert!(active);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn swap_is_not_abandoned_once_a_funding_action_was_issued(
+            swap in tables::insertable_swap(),
+            hbit in tables::insertable_hbit(SWAP_FK, Side::Alpha),
+            herc20 in tables::insertable_herc20(SWAP_FK, Side::Beta),
+        ) {
+            let storage = Storage::test();
+            let swap_id = swap.local_swap_id.0;
+            let mut runtime = Runtime::new().unwrap();
+
+            let (supervised, order) = runtime.block_on(async {
+                let order_id = insert_settling_swap(&storage.db, swap, hbit, herc20).await;
+                storage.funding_actions_issued.lock().await.insert(swap_id);
+
+                let supervised = tokio::time::timeout(
+                    POLL_INTERVAL * 10,
+                    supervise_after_window(&storage, swap_id, false),
+                )
+                .await;
+
+                (supervised, load_order(&storage.db, order_id).await)
+            });
+
+            prop_assert!(supervised.is_err());
+            prop_assert_eq!(order.settling, order.quantity);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn swap_is_not_abandoned_while_lock_transaction_is_in_mempool(
+            swap in tables::insertable_swap(),
+            hbit in tables::insertable_hbit(SWAP_FK, Side::Alpha),
+            herc20 in tables::insertable_herc20(SWAP_FK, Side::Beta),
+        ) {
+            let storage = Storage::test();
+            let swap_id = swap.local_swap_id.0;
+            let mut runtime = Runtime::new().unwrap();
+
+            let (supervised, order) = runtime.block_on(async {
+                let order_id = insert_settling_swap(&storage.db, swap, hbit, herc20).await;
+
+                let supervised = tokio::time::timeout(
+                    POLL_INTERVAL * 10,
+                    supervise_after_window(&storage, swap_id, true),
+                )
+                .await;
+
+                (supervised, load_order(&storage.db, order_id).await)
+            });
+
+            prop_assert!(supervised.is_err());
+            prop_assert_eq!(order.settling, order.quantity);
+        }
+    }
+}
@@ -3,12 +3,15 @@ mod http_api;
 mod seed;
 
 use crate::{
-    asset, hbit, herc20, identity, spawn, storage::db::queries::get_swap_context_by_id,
-    LocalSwapId, Role, Side,
+    asset, config::Token, ethereum, hbit, herc20, http_api::SwapEvent, identity, spawn,
+    storage::db::queries::get_swap_context_by_id, LocalSwapId, Role, Side,
 };
 use async_trait::async_trait;
-use std::{collections::HashMap, sync::Arc};
-use tokio::sync::Mutex;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use tokio::sync::{broadcast, Mutex};
 
 use comit::swap::Action;
 pub use db::*;
@@ -20,6 +23,9 @@ pub trait Load<T>: Send + Sync + 'static {
     async fn load(&self, swap_id: LocalSwapId) -> anyhow::Result<T>;
 }
 
+/// How many swap events are buffered for subscribers that fall behind.
+const SWAP_EVENTS_CAPACITY: usize = 64;
+
 /// A facade for the storage layer.
 #[derive(Debug, Clone)]
 pub struct Storage {
@@ -29,19 +35,53 @@ pub struct Storage {
     pub next_action: Arc<Mutex<HashMap<LocalSwapId, Action>>>,
     pub hbit_events: Arc<Mutex<HashMap<LocalSwapId, hbit::Events>>>,
     pub herc20_events: Arc<Mutex<HashMap<LocalSwapId, herc20::Events>>>,
+    /// Swaps for which we handed out an action that locks up funds.
+    pub funding_actions_issued: Arc<Mutex<HashSet<LocalSwapId>>>,
+    swap_events: broadcast::Sender<(LocalSwapId, SwapEvent)>,
+    /// The tokens we know the symbol and decimals of, used to describe the
+    /// ERC20 amounts of swaps.
+    tokens: Vec<Token>,
 }
 
 impl Storage {
     pub fn new(db: Sqlite, seed: RootSeed) -> Self {
+        let (swap_events, _) = broadcast::channel(SWAP_EVENTS_CAPACITY);
+
         Self {
             db,
             seed,
             next_action: Arc::new(Default::default()),
             hbit_events: Arc::new(Default::default()),
             herc20_events: Arc::new(Default::default()),
+            funding_actions_issued: Arc::new(Default::default()),
+            swap_events,
+            tokens: Vec::new(),
         }
     }
 
+    pub fn with_tokens(self, tokens: Vec<Token>) -> Self {
+        Self { tokens, ..self }
+    }
+
+    /// The known token deployed at `address`, if any.
+    pub fn token(&self, address: ethereum::Address) -> Option<&Token> {
+        self.tokens.iter().find(|token| token.address == address)
+    }
+
+    /// Notify subscribers about an event that has just been persisted.
+    ///
+    /// Must be called after the event has been saved so that subscribers
+    /// which replay the known events of a swap don't miss it.
+    pub fn publish_swap_event(&self, swap_id: LocalSwapId, event: SwapEvent) {
+        // An error only means that nobody is listening right now.
+        let _ = self.swap_events.send((swap_id, event));
+    }
+
+    /// Subscribe to the events of all swaps that are persisted from now on.
+    pub fn subscribe_swap_events(&self) -> broadcast::Receiver<(LocalSwapId, SwapEvent)> {
+        self.swap_events.subscribe()
+    }
+
     /// Transient identity used by the hbit HTLC.
     pub fn derive_transient_identity(
         &self,
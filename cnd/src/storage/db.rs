@@ -9,7 +9,7 @@ embed_migrations!("./migrations");
 #[cfg(test)]
 mod integration_tests;
 #[cfg(test)]
-mod proptest;
+pub mod proptest;
 
 pub use self::{
     tables::*,
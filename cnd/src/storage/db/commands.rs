@@ -8,32 +8,39 @@ use crate::{
     local_swap_id::LocalSwapId,
     storage::{
         db::{schema::*, wrapper_types::Satoshis},
-        BtcDaiOrder, InsertableCompletedSwap, Order, Text,
+        BtcDaiOrder, Hbit, InsertableCompletedSwap, InsertableFailedSwap, InsertableRemoteOrder,
+        Order, Swap, Text,
     },
 };
 use anyhow::{Context, Result};
 use comit::{OrderId, Quantity};
 use diesel::prelude::*;
+use libp2p::PeerId;
 use time::OffsetDateTime;
 
-/// Move the amount that is settling from open to settling.
+/// Move `quantity` of an order from open to settling.
 ///
-/// Whilst we don't have partial order matching, this simply means updating
-/// `settling` to the quantity of the order and all other state fields to `0`.
-///
-/// Once we implement partial order matching, this will need to get more
-/// sophisticated.
-pub fn update_btc_dai_order_to_settling(conn: &SqliteConnection, order_id: OrderId) -> Result<()> {
+/// A swap may only fill part of an order, the remaining open quantity of the
+/// order can still be matched.
+pub fn update_btc_dai_order_to_settling(
+    conn: &SqliteConnection,
+    order_id: OrderId,
+    quantity: asset::Bitcoin,
+) -> Result<()> {
     let order = Order::by_order_id(conn, order_id)?;
     let btc_dai_order = BtcDaiOrder::by_order(conn, &order)?;
 
+    let open = btc_dai_order
+        .open
+        .to_inner()
+        .checked_sub(quantity)
+        .ok_or(NotEnoughOpen(order_id))?;
+    let settling = btc_dai_order.settling.to_inner() + quantity;
+
     let affected_rows = diesel::update(&btc_dai_order)
         .set((
-            btc_dai_orders::settling.eq(Text::<Satoshis>(btc_dai_order.quantity.to_inner().into())),
-            btc_dai_orders::open.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::closed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::failed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::cancelled.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
+            btc_dai_orders::open.eq(Text::<Satoshis>(open.into())),
+            btc_dai_orders::settling.eq(Text::<Satoshis>(settling.into())),
         ))
         .execute(conn)?;
 
@@ -44,6 +51,9 @@ pub fn update_btc_dai_order_to_settling(conn: &SqliteConnection, order_id: Order
     Ok(())
 }
 
+/// Move whatever is still open of an order to cancelled.
+///
+/// Quantities that are already settling are not affected.
 pub fn update_btc_dai_order_to_cancelled(conn: &SqliteConnection, order_id: OrderId) -> Result<()> {
     let order = Order::by_order_id(conn, order_id)?;
     let btc_dai_order = BtcDaiOrder::by_order(conn, &order)?;
@@ -52,14 +62,12 @@ pub fn update_btc_dai_order_to_cancelled(conn: &SqliteConnection, order_id: Orde
         anyhow::bail!(NotOpen(order_id))
     }
 
+    let cancelled = btc_dai_order.cancelled.to_inner() + btc_dai_order.open.to_inner();
+
     let affected_rows = diesel::update(&btc_dai_order)
         .set((
-            btc_dai_orders::cancelled
-                .eq(Text::<Satoshis>(btc_dai_order.quantity.to_inner().into())),
-            btc_dai_orders::settling.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
+            btc_dai_orders::cancelled.eq(Text::<Satoshis>(cancelled.into())),
             btc_dai_orders::open.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::closed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::failed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
         ))
         .execute(conn)?;
 
@@ -70,26 +78,21 @@ pub fn update_btc_dai_order_to_cancelled(conn: &SqliteConnection, order_id: Orde
     Ok(())
 }
 
+/// Move the quantity of the swap from settling to closed.
 pub fn update_order_of_swap_to_closed(conn: &SqliteConnection, swap_id: LocalSwapId) -> Result<()> {
-    let (order, btc_dai_order) = orders::table
-        .inner_join(order_swaps::table.inner_join(swaps::table))
-        .inner_join(btc_dai_orders::table)
-        .filter(swaps::local_swap_id.eq(Text(swap_id)))
-        .select((orders::all_columns, btc_dai_orders::all_columns))
-        .first::<(Order, BtcDaiOrder)>(conn)
-        .with_context(|| NoOrderForSwap(swap_id))?;
+    let (order, btc_dai_order, quantity) = order_of_swap(conn, swap_id)?;
 
-    if btc_dai_order.settling == Quantity::new(asset::Bitcoin::ZERO) {
-        anyhow::bail!(NotSettling(order.order_id))
-    }
+    let settling = btc_dai_order
+        .settling
+        .to_inner()
+        .checked_sub(quantity)
+        .ok_or(NotSettling(order.order_id))?;
+    let closed = btc_dai_order.closed.to_inner() + quantity;
 
     let affected_rows = diesel::update(&btc_dai_order)
         .set((
-            btc_dai_orders::closed.eq(Text::<Satoshis>(btc_dai_order.quantity.to_inner().into())),
-            btc_dai_orders::settling.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::open.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::cancelled.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::failed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
+            btc_dai_orders::settling.eq(Text::<Satoshis>(settling.into())),
+            btc_dai_orders::closed.eq(Text::<Satoshis>(closed.into())),
         ))
         .execute(conn)?;
 
@@ -100,26 +103,21 @@ pub fn update_order_of_swap_to_closed(conn: &SqliteConnection, swap_id: LocalSwa
     Ok(())
 }
 
+/// Move the quantity of the swap from settling to failed.
 pub fn update_order_of_swap_to_failed(conn: &SqliteConnection, swap_id: LocalSwapId) -> Result<()> {
-    let (order, btc_dai_order) = orders::table
-        .inner_join(order_swaps::table.inner_join(swaps::table))
-        .inner_join(btc_dai_orders::table)
-        .filter(swaps::local_swap_id.eq(Text(swap_id)))
-        .select((orders::all_columns, btc_dai_orders::all_columns))
-        .first::<(Order, BtcDaiOrder)>(conn)
-        .with_context(|| NoOrderForSwap(swap_id))?;
+    let (order, btc_dai_order, quantity) = order_of_swap(conn, swap_id)?;
 
-    if btc_dai_order.settling == Quantity::new(asset::Bitcoin::ZERO) {
-        anyhow::bail!(NotSettling(order.order_id))
-    }
+    let settling = btc_dai_order
+        .settling
+        .to_inner()
+        .checked_sub(quantity)
+        .ok_or(NotSettling(order.order_id))?;
+    let failed = btc_dai_order.failed.to_inner() + quantity;
 
     let affected_rows = diesel::update(&btc_dai_order)
         .set((
-            btc_dai_orders::failed.eq(Text::<Satoshis>(btc_dai_order.quantity.to_inner().into())),
-            btc_dai_orders::settling.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::open.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::cancelled.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::closed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
+            btc_dai_orders::settling.eq(Text::<Satoshis>(settling.into())),
+            btc_dai_orders::failed.eq(Text::<Satoshis>(failed.into())),
         ))
         .execute(conn)?;
 
@@ -130,33 +128,26 @@ pub fn update_order_of_swap_to_failed(conn: &SqliteConnection, swap_id: LocalSwa
     Ok(())
 }
 
-pub fn update_order_of_swap_to_settling(
+/// Loads the order a swap was created from together with the quantity of the
+/// order the swap fills.
+fn order_of_swap(
     conn: &SqliteConnection,
     swap_id: LocalSwapId,
-) -> Result<()> {
-    let (order, btc_dai_order) = orders::table
+) -> Result<(Order, BtcDaiOrder, asset::Bitcoin)> {
+    let (order, btc_dai_order, swap) = orders::table
         .inner_join(order_swaps::table.inner_join(swaps::table))
         .inner_join(btc_dai_orders::table)
         .filter(swaps::local_swap_id.eq(Text(swap_id)))
-        .select((orders::all_columns, btc_dai_orders::all_columns))
-        .first::<(Order, BtcDaiOrder)>(conn)
-        .with_context(|| NoOrderForSwap(swap_id))?;
-
-    let affected_rows = diesel::update(&btc_dai_order)
-        .set((
-            btc_dai_orders::settling.eq(Text::<Satoshis>(btc_dai_order.quantity.to_inner().into())),
-            btc_dai_orders::open.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::closed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::failed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::cancelled.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
+        .select((
+            orders::all_columns,
+            btc_dai_orders::all_columns,
+            swaps::all_columns,
         ))
-        .execute(conn)?;
-
-    if affected_rows == 0 {
-        anyhow::bail!("failed to mark order {} as settling", order.order_id)
-    }
+        .first::<(Order, BtcDaiOrder, Swap)>(conn)
+        .with_context(|| NoOrderForSwap(swap_id))?;
+    let hbit = Hbit::belonging_to(&swap).first::<Hbit>(conn)?;
 
-    Ok(())
+    Ok((order, btc_dai_order, hbit.amount))
 }
 
 pub fn mark_swap_as_completed(
@@ -170,6 +161,54 @@ pub fn mark_swap_as_completed(
     Ok(())
 }
 
+/// Records that a swap failed.
+///
+/// A failed swap is respawned on restart and may still complete, completion
+/// takes precedence over a recorded failure.
+pub fn mark_swap_as_failed(
+    conn: &SqliteConnection,
+    swap_id: LocalSwapId,
+    failed_at: OffsetDateTime,
+) -> Result<()> {
+    let swap_fk = swap_id_fk!(swap_id).first(conn)?;
+    InsertableFailedSwap::new(swap_fk, failed_at).insert(conn)?;
+
+    Ok(())
+}
+
+/// Replaces the saved orders of `maker` with the ones we just saw.
+pub fn save_remote_orders(
+    conn: &SqliteConnection,
+    maker: PeerId,
+    orders: &[comit::BtcDaiOrder],
+    seen_at: OffsetDateTime,
+) -> Result<()> {
+    delete_remote_orders(conn, maker.clone())?;
+
+    for order in orders {
+        InsertableRemoteOrder::new(maker.clone(), order, seen_at).insert(conn)?;
+    }
+
+    Ok(())
+}
+
+pub fn delete_remote_orders(conn: &SqliteConnection, maker: PeerId) -> Result<()> {
+    diesel::delete(remote_orders::table.filter(remote_orders::maker.eq(Text(maker))))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn delete_remote_orders_not_seen_since(
+    conn: &SqliteConnection,
+    cutoff: OffsetDateTime,
+) -> Result<()> {
+    diesel::delete(remote_orders::table.filter(remote_orders::last_seen.lt(cutoff.timestamp())))
+        .execute(conn)?;
+
+    Ok(())
+}
+
 #[derive(thiserror::Error, Debug, Clone, Copy)]
 #[error("no order found in the database for swap id {0}")]
 pub struct NoOrderForSwap(pub LocalSwapId);
@@ -178,6 +217,110 @@ pub struct NoOrderForSwap(pub LocalSwapId);
 #[error("Order {0} is no longer open and can therefore not be cancelled")]
 pub struct NotOpen(pub OrderId);
 
+#[derive(thiserror::Error, Debug, Clone, Copy)]
+#[error("Order {0} does not have enough open quantity left for this swap")]
+pub struct NotEnoughOpen(pub OrderId);
+
 #[derive(thiserror::Error, Debug, Clone, Copy)]
 #[error("Order {0} is not settling and can therefore not be closed")]
 pub struct NotSettling(pub OrderId);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{InsertableBtcDaiOrder, InsertableOrder, Sqlite};
+    use comit::{
+        asset::{ethereum::FromWei, Erc20Quantity},
+        Position,
+    };
+
+    async fn insert_order(db: &Sqlite) -> OrderId {
+        let order_id = OrderId::random();
+
+        db.do_in_transaction(|conn| {
+            let order_fk =
+                InsertableOrder::new(order_id, Position::Sell, OffsetDateTime::now_utc(), None)
+                    .insert(conn)?;
+            InsertableBtcDaiOrder::new(
+                order_fk,
+                asset::Bitcoin::from_sat(1_000_000),
+                Erc20Quantity::from_wei(9_000_000_000_000u64),
+            )
+            .insert(conn)
+        })
+        .await
+        .unwrap();
+
+        order_id
+    }
+
+    async fn settle(db: &Sqlite, order_id: OrderId, sats: u64) -> Result<()> {
+        db.do_in_transaction(|conn| {
+            update_btc_dai_order_to_settling(conn, order_id, asset::Bitcoin::from_sat(sats))
+        })
+        .await
+    }
+
+    async fn load(db: &Sqlite, order_id: OrderId) -> BtcDaiOrder {
+        db.do_in_transaction(|conn| {
+            let order = Order::by_order_id(conn, order_id)?;
+            BtcDaiOrder::by_order(conn, &order)
+        })
+        .await
+        .unwrap()
+    }
+
+    fn sats(order: &BtcDaiOrder) -> [u64; 4] {
+        [
+            order.open.to_inner().as_sat(),
+            order.settling.to_inner().as_sat(),
+            order.cancelled.to_inner().as_sat(),
+            order.quantity.to_inner().as_sat(),
+        ]
+    }
+
+    #[tokio::test]
+    async fn order_is_filled_in_two_partial_steps() {
+        let db = Sqlite::test();
+        let order_id = insert_order(&db).await;
+
+        settle(&db, order_id, 300_000).await.unwrap();
+        assert_eq!(sats(&load(&db, order_id).await), [
+            700_000, 300_000, 0, 1_000_000
+        ]);
+
+        settle(&db, order_id, 700_000).await.unwrap();
+        assert_eq!(sats(&load(&db, order_id).await), [
+            0, 1_000_000, 0, 1_000_000
+        ]);
+    }
+
+    #[tokio::test]
+    async fn cannot_settle_more_than_is_open() {
+        let db = Sqlite::test();
+        let order_id = insert_order(&db).await;
+        settle(&db, order_id, 600_000).await.unwrap();
+
+        let result = settle(&db, order_id, 600_000).await;
+
+        assert!(result.unwrap_err().is::<NotEnoughOpen>());
+        assert_eq!(sats(&load(&db, order_id).await), [
+            400_000, 600_000, 0, 1_000_000
+        ]);
+    }
+
+    #[tokio::test]
+    async fn cancelling_partially_filled_order_only_cancels_what_is_open() {
+        let db = Sqlite::test();
+        let order_id = insert_order(&db).await;
+        settle(&db, order_id, 300_000).await.unwrap();
+
+        db.do_in_transaction(|conn| update_btc_dai_order_to_cancelled(conn, order_id))
+            .await
+            .unwrap();
+
+        assert_eq!(sats(&load(&db, order_id).await), [
+            0, 300_000, 700_000, 1_000_000
+        ]);
+    }
+}
@@ -47,4 +47,12 @@ pub mod tables {
             tables::InsertableCompletedSwap::new(swap_id, completed_at)
         }
     }
+
+    prop_compose! {
+        pub fn insertable_failed_swap(swap_id: i32)(
+            failed_at in timestamp(),
+        ) -> tables::InsertableFailedSwap {
+            tables::InsertableFailedSwap::new(swap_id, failed_at)
+        }
+    }
 }
@@ -8,13 +8,14 @@ use crate::{
     storage::{
         db::{schema::*, wrapper_types::Satoshis},
         BtcDaiOrder, NoSwapExists, Order, OrderHbitParams, OrderHerc20Params, ParamsTuple,
-        SwapContext, Text,
+        RemoteOrder, SwapContext, Text,
     },
     LocalSwapId,
 };
 use anyhow::{Context, Result};
 use comit::order::SwapProtocol;
 use diesel::{prelude::*, SqliteConnection};
+use libp2p::PeerId;
 use std::convert::TryFrom;
 use time::OffsetDateTime;
 
@@ -40,6 +41,71 @@ pub fn get_active_swap_contexts(conn: &SqliteConnection) -> Result<Vec<SwapConte
     Ok(contexts)
 }
 
+/// How a swap ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SwapOutcome {
+    Completed,
+    Failed,
+}
+
+impl SwapOutcome {
+    /// A failed swap is respawned on restart and may still complete, hence
+    /// completion is checked first.
+    fn from_timestamps(completed_on: Option<i64>, failed_on: Option<i64>) -> Option<Self> {
+        match (completed_on, failed_on) {
+            (Some(_), _) => Some(SwapOutcome::Completed),
+            (None, Some(_)) => Some(SwapOutcome::Failed),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Returns the context of every swap together with how it ended, `None` if
+/// the swap is still running.
+pub fn get_all_swap_contexts(
+    conn: &SqliteConnection,
+) -> Result<Vec<(SwapContext, Option<SwapOutcome>)>> {
+    let query = swaps::table
+        .inner_join(swap_contexts::table.on(swap_contexts::id.eq(swaps::local_swap_id)))
+        .left_join(completed_swaps::table)
+        .left_join(failed_swaps::table)
+        .select((
+            swap_contexts::all_columns,
+            completed_swaps::completed_on.nullable(),
+            failed_swaps::failed_on.nullable(),
+        ));
+
+    let contexts = query
+        .load::<(SwapContext, Option<i64>, Option<i64>)>(conn)?
+        .into_iter()
+        .map(|(context, completed_on, failed_on)| {
+            (
+                context,
+                SwapOutcome::from_timestamps(completed_on, failed_on),
+            )
+        })
+        .collect();
+
+    Ok(contexts)
+}
+
+/// Returns how the given swap ended, `None` if it is still running.
+pub fn get_swap_outcome(conn: &SqliteConnection, id: LocalSwapId) -> Result<Option<SwapOutcome>> {
+    let (completed_on, failed_on) = swaps::table
+        .left_join(completed_swaps::table)
+        .left_join(failed_swaps::table)
+        .filter(swaps::local_swap_id.eq(Text(id)))
+        .select((
+            completed_swaps::completed_on.nullable(),
+            failed_swaps::failed_on.nullable(),
+        ))
+        .get_result::<(Option<i64>, Option<i64>)>(conn)
+        .optional()?
+        .ok_or(NoSwapExists(id))?;
+
+    Ok(SwapOutcome::from_timestamps(completed_on, failed_on))
+}
+
 pub fn all_open_btc_dai_orders(conn: &SqliteConnection) -> Result<Vec<(Order, BtcDaiOrder)>> {
     let orders = orders::table
         .inner_join(btc_dai_orders::table)
@@ -50,6 +116,27 @@ pub fn all_open_btc_dai_orders(conn: &SqliteConnection) -> Result<Vec<(Order, Bt
     Ok(orders)
 }
 
+/// Counts the BTC/DAI orders that are still open and those that were
+/// cancelled.
+pub fn count_open_and_cancelled_btc_dai_orders(conn: &SqliteConnection) -> Result<(u64, u64)> {
+    let zero = Text::<Satoshis>(asset::Bitcoin::ZERO.into());
+
+    let open = btc_dai_orders::table
+        .filter(btc_dai_orders::open.ne(zero))
+        .count()
+        .get_result::<i64>(conn)?;
+    let cancelled = btc_dai_orders::table
+        .filter(btc_dai_orders::cancelled.ne(zero))
+        .count()
+        .get_result::<i64>(conn)?;
+
+    Ok((u64::try_from(open)?, u64::try_from(cancelled)?))
+}
+
+/// Loads our orders that are still open to publish them again, e.g. after a
+/// restart.
+///
+/// Partially filled orders are published with the quantity that is still open.
 pub fn get_orders_to_republish(conn: &SqliteConnection) -> Result<Vec<comit::BtcDaiOrder>> {
     let orders = orders::table
         .inner_join(btc_dai_orders::table)
@@ -76,7 +163,8 @@ pub fn get_orders_to_republish(conn: &SqliteConnection) -> Result<Vec<comit::Btc
                     position: order.position,
                     swap_protocol,
                     created_at: OffsetDateTime::from_unix_timestamp(order.created_at),
-                    quantity: btc_dai_order.quantity,
+                    valid_until: order.valid_until.map(OffsetDateTime::from_unix_timestamp),
+                    quantity: btc_dai_order.open,
                     price: btc_dai_order.price,
                 })
             },
@@ -86,12 +174,33 @@ pub fn get_orders_to_republish(conn: &SqliteConnection) -> Result<Vec<comit::Btc
     Ok(orders)
 }
 
+/// Loads the orders of other makers we saw since `cutoff`, together with when
+/// we last saw them.
+pub fn get_remote_orders_seen_since(
+    conn: &SqliteConnection,
+    cutoff: OffsetDateTime,
+) -> Result<Vec<(PeerId, comit::BtcDaiOrder, OffsetDateTime)>> {
+    let orders = remote_orders::table
+        .filter(remote_orders::last_seen.ge(cutoff.timestamp()))
+        .load::<RemoteOrder>(conn)?
+        .into_iter()
+        .map(|order| {
+            let maker = order.maker.clone();
+            let last_seen = OffsetDateTime::from_unix_timestamp(order.last_seen);
+
+            (maker, order.into(), last_seen)
+        })
+        .collect();
+
+    Ok(orders)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
         proptest::*,
-        storage::{db, db::Sqlite},
+        storage::{commands, db, db::Sqlite},
     };
     use comit::{LockProtocol, Side};
     use tokio::runtime::Runtime;
@@ -100,6 +209,7 @@ mod tests {
     // which FK will be used if we have a fresh test database for every test.
     const FIRST_SWAP_FK: i32 = 1;
     const SECOND_SWAP_FK: i32 = 2;
+    const THIRD_SWAP_FK: i32 = 3;
 
     proptest! {
         #[test]
@@ -151,6 +261,90 @@ mod tests {
         }
     }
 
+    proptest! {
+        #[test]
+        fn get_all_swap_contexts_returns_how_swaps_ended(
+            first_insertable_swap in db::proptest::tables::insertable_swap(),
+            first_insertable_hbit in db::proptest::tables::insertable_hbit(FIRST_SWAP_FK, Side::Alpha),
+            first_insertable_herc20 in db::proptest::tables::insertable_herc20(FIRST_SWAP_FK, Side::Beta),
+            insertable_completed_swap in db::proptest::tables::insertable_completed_swap(FIRST_SWAP_FK),
+            second_insertable_swap in db::proptest::tables::insertable_swap(),
+            second_insertable_herc20 in db::proptest::tables::insertable_herc20(SECOND_SWAP_FK, Side::Alpha),
+            second_insertable_hbit in db::proptest::tables::insertable_hbit(SECOND_SWAP_FK, Side::Beta),
+            third_insertable_swap in db::proptest::tables::insertable_swap(),
+            third_insertable_hbit in db::proptest::tables::insertable_hbit(THIRD_SWAP_FK, Side::Alpha),
+            third_insertable_herc20 in db::proptest::tables::insertable_herc20(THIRD_SWAP_FK, Side::Beta),
+            insertable_failed_swap in db::proptest::tables::insertable_failed_swap(THIRD_SWAP_FK),
+        ) {
+            let db = Sqlite::test();
+            let mut runtime = Runtime::new().unwrap();
+
+            let completed_swap_id = first_insertable_swap.local_swap_id.0;
+            let active_swap_id = second_insertable_swap.local_swap_id.0;
+            let failed_swap_id = third_insertable_swap.local_swap_id.0;
+
+            let mut swap_contexts = runtime.block_on(async {
+                db.do_in_transaction(|conn| {
+                    first_insertable_swap.insert(conn)?;
+                    first_insertable_hbit.insert(conn)?;
+                    first_insertable_herc20.insert(conn)?;
+                    insertable_completed_swap.insert(conn)?;
+
+                    second_insertable_swap.insert(conn)?;
+                    second_insertable_hbit.insert(conn)?;
+                    second_insertable_herc20.insert(conn)?;
+
+                    third_insertable_swap.insert(conn)?;
+                    third_insertable_hbit.insert(conn)?;
+                    third_insertable_herc20.insert(conn)?;
+                    insertable_failed_swap.insert(conn)?;
+
+                    get_all_swap_contexts(conn)
+                }).await.unwrap()
+            })
+            .into_iter()
+            .map(|(context, outcome)| (context.id, outcome))
+            .collect::<Vec<_>>();
+            swap_contexts.sort_by_key(|(_, outcome)| outcome.map(|outcome| outcome == SwapOutcome::Failed));
+
+            assert_eq!(swap_contexts, vec![
+                (active_swap_id, None),
+                (completed_swap_id, Some(SwapOutcome::Completed)),
+                (failed_swap_id, Some(SwapOutcome::Failed)),
+            ])
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn swap_that_completed_after_failing_is_completed(
+            insertable_swap in db::proptest::tables::insertable_swap(),
+            insertable_hbit in db::proptest::tables::insertable_hbit(FIRST_SWAP_FK, Side::Alpha),
+            insertable_herc20 in db::proptest::tables::insertable_herc20(FIRST_SWAP_FK, Side::Beta),
+            insertable_failed_swap in db::proptest::tables::insertable_failed_swap(FIRST_SWAP_FK),
+            insertable_completed_swap in db::proptest::tables::insertable_completed_swap(FIRST_SWAP_FK),
+        ) {
+            let db = Sqlite::test();
+            let mut runtime = Runtime::new().unwrap();
+
+            let swap_id = insertable_swap.local_swap_id.0;
+
+            let outcome = runtime.block_on(async {
+                db.do_in_transaction(|conn| {
+                    insertable_swap.insert(conn)?;
+                    insertable_hbit.insert(conn)?;
+                    insertable_herc20.insert(conn)?;
+                    insertable_failed_swap.insert(conn)?;
+                    insertable_completed_swap.insert(conn)?;
+
+                    get_swap_outcome(conn, swap_id)
+                }).await.unwrap()
+            });
+
+            assert_eq!(outcome, Some(SwapOutcome::Completed))
+        }
+    }
+
     proptest! {
         #[test]
         fn get_swap_context_by_id_returns_correct_swap(
@@ -191,4 +385,33 @@ mod tests {
             assert_eq!(second_swap_context.beta, LockProtocol::Hbit);
         }
     }
+
+    proptest! {
+        #[test]
+        fn remote_orders_are_loaded_until_they_were_not_seen_for_too_long(
+            maker in libp2p::peer_id(),
+            first_order in order::btc_dai(),
+            second_order in order::btc_dai(),
+        ) {
+            let db = Sqlite::test();
+            let mut runtime = Runtime::new().unwrap();
+            let seen_at = OffsetDateTime::from_unix_timestamp(1_000);
+
+            let (loaded, expired) = runtime.block_on(async {
+                db.do_in_transaction(|conn| {
+                    commands::save_remote_orders(conn, maker.clone(), &[first_order.clone()], seen_at)?;
+                    // seeing the maker again replaces what they offered before
+                    commands::save_remote_orders(conn, maker.clone(), &[second_order.clone()], seen_at)?;
+
+                    let loaded = get_remote_orders_seen_since(conn, seen_at)?;
+                    let expired = get_remote_orders_seen_since(conn, seen_at + time::Duration::seconds(1))?;
+
+                    Ok((loaded, expired))
+                }).await.unwrap()
+            });
+
+            assert_eq!(loaded, vec![(maker, second_order, seen_at)]);
+            assert!(expired.is_empty());
+        }
+    }
 }
@@ -61,6 +61,7 @@ table! {
         order_id -> Text,
         position -> Text,
         created_at -> BigInt,
+        valid_until -> Nullable<BigInt>,
     }
 }
 
@@ -117,6 +118,31 @@ table! {
     }
 }
 
+table! {
+    failed_swaps {
+        id -> Integer,
+        swap_id -> Integer,
+        failed_on -> BigInt,
+    }
+}
+
+table! {
+    remote_orders {
+        id -> Integer,
+        order_id -> Text,
+        maker -> Text,
+        position -> Text,
+        quantity -> Text,
+        price -> Text,
+        hbit_side -> Text,
+        hbit_expiry_offset -> BigInt,
+        herc20_expiry_offset -> BigInt,
+        created_at -> BigInt,
+        valid_until -> Nullable<BigInt>,
+        last_seen -> BigInt,
+    }
+}
+
 allow_tables_to_appear_in_same_query!(swaps, herc20s);
 allow_tables_to_appear_in_same_query!(swaps, hbits);
 allow_tables_to_appear_in_same_query!(hbits, herc20s);
@@ -134,10 +160,14 @@ allow_tables_to_appear_in_same_query!(btc_dai_orders, order_swaps);
 allow_tables_to_appear_in_same_query!(swap_contexts, swaps);
 allow_tables_to_appear_in_same_query!(completed_swaps, swaps);
 allow_tables_to_appear_in_same_query!(completed_swaps, swap_contexts);
+allow_tables_to_appear_in_same_query!(failed_swaps, swaps);
+allow_tables_to_appear_in_same_query!(failed_swaps, swap_contexts);
+allow_tables_to_appear_in_same_query!(failed_swaps, completed_swaps);
 joinable!(btc_dai_orders -> orders (order_id));
 joinable!(order_hbit_params -> orders (order_id));
 joinable!(order_herc20_params -> orders (order_id));
 joinable!(order_swaps -> orders (order_id));
 joinable!(order_swaps -> swaps (swap_id));
 joinable!(completed_swaps -> swaps (swap_id));
+joinable!(failed_swaps -> swaps (swap_id));
 joinable!(hbits -> swaps (swap_id));
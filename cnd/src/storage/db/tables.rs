@@ -20,12 +20,14 @@ macro_rules! swap_id_fk {
 
 mod btc_dai_orders;
 mod completed_swaps;
+mod failed_swaps;
 mod hbits;
 mod herc20s;
 mod order_hbit_params;
 mod order_herc20_params;
 mod order_swaps;
 mod orders;
+mod remote_orders;
 mod secret_hashes;
 mod swap_contexts;
 mod swaps;
@@ -33,12 +35,14 @@ mod swaps;
 pub use btc_dai_orders::{BtcDaiOrder, InsertableBtcDaiOrder};
 use comit::order::SwapProtocol;
 pub use completed_swaps::{CompletedSwap, InsertableCompletedSwap};
+pub use failed_swaps::{FailedSwap, InsertableFailedSwap};
 pub use hbits::{Hbit, InsertableHbit};
 pub use herc20s::{Herc20, InsertableHerc20};
 pub use order_hbit_params::{InsertableOrderHbitParams, OrderHbitParams};
 pub use order_herc20_params::{InsertableOrderHerc20Params, OrderHerc20Params};
 pub use order_swaps::{InsertableOrderSwap, OrderSwap};
 pub use orders::{InsertableOrder, NoOrderExists, Order};
+pub use remote_orders::{InsertableRemoteOrder, RemoteOrder};
 pub use secret_hashes::{InsertableSecretHash, SecretHash};
 use std::convert::TryFrom;
 pub use swap_contexts::SwapContext;
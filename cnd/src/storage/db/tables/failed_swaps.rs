@@ -0,0 +1,42 @@
+use crate::storage::{
+    db::{schema::failed_swaps, Swap},
+    Timestamp,
+};
+use anyhow::Result;
+use diesel::{prelude::*, sqlite::SqliteConnection};
+use time::OffsetDateTime;
+
+#[derive(Associations, Clone, Copy, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "failed_swaps"]
+pub struct FailedSwap {
+    id: i32,
+    pub swap_id: i32,
+    #[diesel(deserialize_as = "Timestamp")]
+    pub failed_on: OffsetDateTime,
+}
+
+#[derive(Insertable, Clone, Copy, Debug)]
+#[table_name = "failed_swaps"]
+pub struct InsertableFailedSwap {
+    pub swap_id: i32,
+    pub failed_on: Timestamp,
+}
+
+impl InsertableFailedSwap {
+    pub fn new(swap_id: i32, failed_on: OffsetDateTime) -> Self {
+        Self {
+            swap_id,
+            failed_on: Timestamp(failed_on),
+        }
+    }
+
+    /// A respawned swap may fail again, only its first failure is kept.
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_or_ignore_into(failed_swaps::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
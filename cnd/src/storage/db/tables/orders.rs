@@ -13,6 +13,7 @@ pub struct Order {
     #[diesel(deserialize_as = "Text<Position>")]
     pub position: Position,
     pub created_at: i64,
+    pub valid_until: Option<i64>,
 }
 
 impl Order {
@@ -36,14 +37,21 @@ pub struct InsertableOrder {
     pub order_id: Text<OrderId>,
     pub position: Text<Position>,
     pub created_at: i64,
+    pub valid_until: Option<i64>,
 }
 
 impl InsertableOrder {
-    pub fn new(order_id: OrderId, position: Position, created_at: OffsetDateTime) -> Self {
+    pub fn new(
+        order_id: OrderId,
+        position: Position,
+        created_at: OffsetDateTime,
+        valid_until: Option<OffsetDateTime>,
+    ) -> Self {
         Self {
             order_id: Text(order_id),
             position: Text(position),
             created_at: created_at.timestamp(),
+            valid_until: valid_until.map(|valid_until| valid_until.timestamp()),
         }
     }
 
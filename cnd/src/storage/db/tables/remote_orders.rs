@@ -0,0 +1,113 @@
+use crate::storage::{
+    db::{
+        schema::remote_orders,
+        wrapper_types::{Erc20Amount, Satoshis, WeiPerSat},
+    },
+    Text,
+};
+use anyhow::Result;
+use comit::{
+    asset::{bitcoin, ethereum::Erc20Quantity},
+    expiries::{AlphaOffset, BetaOffset},
+    order::{Quantity, SwapProtocol},
+    BtcDaiOrder, OrderId, Position, Price, Side,
+};
+use diesel::{prelude::*, SqliteConnection};
+use libp2p::PeerId;
+use time::{Duration, OffsetDateTime};
+
+/// An order of another maker as we last saw it.
+#[derive(Clone, Debug, Identifiable, Queryable, PartialEq)]
+#[table_name = "remote_orders"]
+pub struct RemoteOrder {
+    id: i32,
+    #[diesel(deserialize_as = "Text<OrderId>")]
+    pub order_id: OrderId,
+    #[diesel(deserialize_as = "Text<PeerId>")]
+    pub maker: PeerId,
+    #[diesel(deserialize_as = "Text<Position>")]
+    pub position: Position,
+    #[diesel(deserialize_as = "Text<Satoshis>")]
+    pub quantity: Quantity<bitcoin::Bitcoin>,
+    #[diesel(deserialize_as = "Text<WeiPerSat>")]
+    pub price: Price<bitcoin::Bitcoin, Erc20Quantity>,
+    #[diesel(deserialize_as = "Text<Side>")]
+    pub hbit_side: Side,
+    pub hbit_expiry_offset: i64,
+    pub herc20_expiry_offset: i64,
+    pub created_at: i64,
+    pub valid_until: Option<i64>,
+    pub last_seen: i64,
+}
+
+impl From<RemoteOrder> for BtcDaiOrder {
+    fn from(order: RemoteOrder) -> Self {
+        let hbit_expiry_offset = Duration::seconds(order.hbit_expiry_offset);
+        let herc20_expiry_offset = Duration::seconds(order.herc20_expiry_offset);
+        let swap_protocol = match order.hbit_side {
+            Side::Alpha => SwapProtocol::HbitHerc20 {
+                hbit_expiry_offset: AlphaOffset::from(hbit_expiry_offset),
+                herc20_expiry_offset: BetaOffset::from(herc20_expiry_offset),
+            },
+            Side::Beta => SwapProtocol::Herc20Hbit {
+                herc20_expiry_offset: AlphaOffset::from(herc20_expiry_offset),
+                hbit_expiry_offset: BetaOffset::from(hbit_expiry_offset),
+            },
+        };
+
+        BtcDaiOrder {
+            id: order.order_id,
+            position: order.position,
+            swap_protocol,
+            created_at: OffsetDateTime::from_unix_timestamp(order.created_at),
+            valid_until: order.valid_until.map(OffsetDateTime::from_unix_timestamp),
+            quantity: order.quantity,
+            price: order.price,
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "remote_orders"]
+pub struct InsertableRemoteOrder {
+    pub order_id: Text<OrderId>,
+    pub maker: Text<PeerId>,
+    pub position: Text<Position>,
+    pub quantity: Text<Satoshis>,
+    pub price: Text<Erc20Amount>,
+    pub hbit_side: Text<Side>,
+    pub hbit_expiry_offset: i64,
+    pub herc20_expiry_offset: i64,
+    pub created_at: i64,
+    pub valid_until: Option<i64>,
+    pub last_seen: i64,
+}
+
+impl InsertableRemoteOrder {
+    pub fn new(maker: PeerId, order: &BtcDaiOrder, last_seen: OffsetDateTime) -> Self {
+        Self {
+            order_id: Text(order.id),
+            maker: Text(maker),
+            position: Text(order.position),
+            quantity: Text(order.quantity.to_inner().into()),
+            price: Text(order.price.wei_per_sat().into()),
+            hbit_side: Text(match order.swap_protocol {
+                SwapProtocol::HbitHerc20 { .. } => Side::Alpha,
+                SwapProtocol::Herc20Hbit { .. } => Side::Beta,
+            }),
+            hbit_expiry_offset: order.swap_protocol.hbit_expiry_offset().whole_seconds(),
+            herc20_expiry_offset: order.swap_protocol.herc20_expiry_offset().whole_seconds(),
+            created_at: order.created_at.timestamp(),
+            valid_until: order.valid_until.map(|valid_until| valid_until.timestamp()),
+            last_seen: last_seen.timestamp(),
+        }
+    }
+
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_into(remote_orders::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
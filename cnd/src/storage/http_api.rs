@@ -23,7 +23,11 @@ impl Load<SwapResource> for Storage {
 
                 (
                     Protocol::hbit(tab.alpha.into()),
-                    Protocol::herc20_dai(tab.beta.amount),
+                    Protocol::herc20(
+                        tab.beta.amount,
+                        tab.beta.token_contract,
+                        self.token(tab.beta.token_contract),
+                    ),
                 )
             }
             SwapContext {
@@ -34,7 +38,11 @@ impl Load<SwapResource> for Storage {
                 let tab: Tables<Herc20, Hbit> = self.db.load_tables(swap_id).await?;
 
                 (
-                    Protocol::herc20_dai(tab.alpha.amount),
+                    Protocol::herc20(
+                        tab.alpha.amount,
+                        tab.alpha.token_contract,
+                        self.token(tab.alpha.token_contract),
+                    ),
                     Protocol::hbit(tab.beta.into()),
                 )
             }
@@ -1,10 +1,20 @@
 use atty::{self, Stream};
 use log::LevelFilter;
-use tracing::{info, subscriber};
+use std::io;
+use tracing::{info, subscriber, Subscriber};
 use tracing_log::LogTracer;
-use tracing_subscriber::FmtSubscriber;
+use tracing_subscriber::{fmt::MakeWriter, FmtSubscriber};
 
-pub fn init_tracing(level: log::LevelFilter) -> anyhow::Result<()> {
+/// The format in which log lines are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    /// Human readable, colored if written to a terminal.
+    Pretty,
+    /// One JSON object per line, including the fields of the enclosing spans.
+    Json,
+}
+
+pub fn init_tracing(level: log::LevelFilter, format: LogFormat) -> anyhow::Result<()> {
     if level == LevelFilter::Off {
         return Ok(());
     }
@@ -12,14 +22,148 @@ pub fn init_tracing(level: log::LevelFilter) -> anyhow::Result<()> {
     // We want upstream library log messages, just only at Info level.
     LogTracer::init_with_filter(LevelFilter::Info)?;
 
-    let is_terminal = atty::is(Stream::Stderr);
-    let subscriber = FmtSubscriber::builder()
-        .with_env_filter(format!("cnd={},comit={}", level, level))
-        .with_ansi(is_terminal)
-        .finish();
-
-    subscriber::set_global_default(subscriber)?;
+    match format {
+        LogFormat::Pretty => {
+            let is_terminal = atty::is(Stream::Stderr);
+            subscriber::set_global_default(pretty(level, is_terminal, io::stdout))?
+        }
+        LogFormat::Json => subscriber::set_global_default(json(level, io::stdout))?,
+    }
     info!("Initialized tracing with level: {}", level);
 
     Ok(())
 }
+
+fn pretty<W>(level: LevelFilter, ansi: bool, make_writer: W) -> impl Subscriber + Send + Sync
+where
+    W: MakeWriter + Send + Sync + 'static,
+{
+    FmtSubscriber::builder()
+        .with_env_filter(env_filter(level))
+        .with_ansi(ansi)
+        .with_writer(make_writer)
+        .finish()
+}
+
+fn json<W>(level: LevelFilter, make_writer: W) -> impl Subscriber + Send + Sync
+where
+    W: MakeWriter + Send + Sync + 'static,
+{
+    FmtSubscriber::builder()
+        .json()
+        .with_current_span(true)
+        .with_span_list(true)
+        .with_env_filter(env_filter(level))
+        .with_ansi(false)
+        .with_writer(make_writer)
+        .finish()
+}
+
+fn env_filter(level: LevelFilter) -> String {
+    format!("cnd={},comit={}", level, level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Buffer {
+        fn lines(&self) -> Vec<String> {
+            let bytes = self.0.lock().unwrap();
+            String::from_utf8(bytes.clone())
+                .unwrap()
+                .lines()
+                .map(String::from)
+                .collect()
+        }
+    }
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl MakeWriter for Buffer {
+        type Writer = Self;
+
+        fn make_writer(&self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn json_format_writes_one_json_object_per_line() {
+        let buffer = Buffer::default();
+
+        subscriber::with_default(json(LevelFilter::Info, buffer.clone()), || {
+            let span = tracing::info_span!("swap", id = 42);
+            let _enter = span.enter();
+            info!(role = "alice", "swap started");
+        });
+
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 1);
+        let line = serde_json::from_str::<serde_json::Value>(&lines[0]).unwrap();
+        assert_eq!(line["fields"]["message"], "swap started");
+        assert_eq!(line["fields"]["role"], "alice");
+        assert_eq!(line["span"]["name"], "swap");
+        assert_eq!(line["span"]["id"], 42);
+    }
+
+    #[tokio::test]
+    async fn every_line_of_a_swap_carries_its_id() {
+        use crate::{spawn::swap_span, LocalSwapId};
+        use std::time::Duration;
+        use tracing_futures::Instrument;
+
+        let buffer = Buffer::default();
+        let _guard = subscriber::set_default(json(LevelFilter::Info, buffer.clone()));
+        let swap_id = LocalSwapId::random();
+
+        let swap = async {
+            info!("swap started");
+            tokio::time::delay_for(Duration::from_millis(1)).await;
+            tokio::spawn(async { info!("watching for funding") }.in_current_span())
+                .await
+                .unwrap();
+            info!("swap completed");
+        }
+        .instrument(swap_span(swap_id));
+        tokio::spawn(swap).await.unwrap();
+        info!("unrelated to the swap");
+
+        let lines = buffer
+            .lines()
+            .iter()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        for line in &lines[..3] {
+            assert_eq!(line["span"]["swap_id"], swap_id.to_string());
+        }
+        assert!(lines[3]["span"].is_null());
+    }
+
+    #[test]
+    fn pretty_format_is_not_json() {
+        let buffer = Buffer::default();
+
+        subscriber::with_default(pretty(LevelFilter::Info, false, buffer.clone()), || {
+            info!("swap started");
+        });
+
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 1);
+        assert!(serde_json::from_str::<serde_json::Value>(&lines[0]).is_err());
+    }
+}
@@ -39,6 +39,24 @@ impl Erc20Quantity {
         self.0.to_bytes_le()
     }
 
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let result = Self(self.0 + other.0);
+
+        if result > Self::max_value() {
+            return None;
+        }
+
+        Some(result)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        if other > self {
+            return None;
+        }
+
+        Some(Self(self.0 - other.0))
+    }
+
     pub fn checked_mul(self, factor: u64) -> Option<Self> {
         let result = Self(self.0 * factor);
 
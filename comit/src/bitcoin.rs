@@ -6,7 +6,10 @@
 //!       libraries
 //!     - Common functionality that is not (yet) available upstream
 
+mod multisig;
+
 pub use ::bitcoin::Address;
+pub use multisig::{MismatchedSignatures, Multisig2Of2};
 
 use crate::{
     btsieve::{BlockByHash, LatestBlock},
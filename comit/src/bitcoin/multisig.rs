@@ -0,0 +1,170 @@
+use crate::bitcoin::{Address, PublicKey};
+use bitcoin::{
+    blockdata::{opcodes::all::*, script::Builder},
+    secp256k1::Signature,
+    Network, Script, SigHashType,
+};
+
+/// A 2-of-2 multisig output that needs the signatures of both parties to be
+/// spent, e.g. to close cooperatively.
+///
+/// The keys are sorted lexicographically by their serialization so that both
+/// parties derive the same script no matter in which order they pass the keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Multisig2Of2 {
+    first: PublicKey,
+    second: PublicKey,
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("signatures do not match the public keys of the multisig")]
+pub struct MismatchedSignatures;
+
+impl Multisig2Of2 {
+    pub fn new(a: PublicKey, b: PublicKey) -> Self {
+        if a.to_bytes() <= b.to_bytes() {
+            Self {
+                first: a,
+                second: b,
+            }
+        } else {
+            Self {
+                first: b,
+                second: a,
+            }
+        }
+    }
+
+    pub fn witness_script(&self) -> Script {
+        Builder::new()
+            .push_opcode(OP_PUSHNUM_2)
+            .push_key(&self.first.into())
+            .push_key(&self.second.into())
+            .push_opcode(OP_PUSHNUM_2)
+            .push_opcode(OP_CHECKMULTISIG)
+            .into_script()
+    }
+
+    pub fn address(&self, network: Network) -> Address {
+        Address::p2wsh(&self.witness_script(), network)
+    }
+
+    /// Builds the witness that spends the multisig output given the
+    /// `SIGHASH_ALL` signatures of both parties, in any order.
+    pub fn witness(
+        &self,
+        signatures: [(PublicKey, Signature); 2],
+    ) -> Result<Vec<Vec<u8>>, MismatchedSignatures> {
+        let [(key_a, sig_a), (key_b, sig_b)] = signatures;
+
+        let (first, second) = if (key_a, key_b) == (self.first, self.second) {
+            (sig_a, sig_b)
+        } else if (key_a, key_b) == (self.second, self.first) {
+            (sig_b, sig_a)
+        } else {
+            return Err(MismatchedSignatures);
+        };
+
+        Ok(vec![
+            // OP_CHECKMULTISIG pops one element more than it uses
+            vec![],
+            with_sighash_all(first),
+            with_sighash_all(second),
+            self.witness_script().into_bytes(),
+        ])
+    }
+}
+
+fn with_sighash_all(signature: Signature) -> Vec<u8> {
+    let mut bytes = signature.serialize_der().to_vec();
+    // The sighash type is a single byte in a signature, ALL is 0x01.
+    #[allow(clippy::cast_possible_truncation)]
+    let sighash_all = SigHashType::All.as_u32() as u8;
+    bytes.push(sighash_all);
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::{self, Message, Secp256k1, SecretKey};
+    use std::str::FromStr;
+
+    fn public_key(hex: &str) -> PublicKey {
+        PublicKey::from(secp256k1::PublicKey::from_str(hex).unwrap())
+    }
+
+    #[test]
+    fn address_matches_known_vector() {
+        let multisig = Multisig2Of2::new(
+            public_key("02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275"),
+            public_key("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"),
+        );
+
+        assert_eq!(
+            hex::encode(multisig.witness_script().as_bytes()),
+            "52210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f817982102c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b527552ae"
+        );
+        assert_eq!(
+            multisig.address(Network::Bitcoin).to_string(),
+            "bc1qcyursgjw5sv7vth7233sj4aw363m3y8scxd5qvcvrk4cq7hvystqul6m4d"
+        );
+    }
+
+    #[test]
+    fn address_does_not_depend_on_key_order() {
+        let secp = Secp256k1::signing_only();
+        let alice = PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[1u8; 32]).unwrap());
+        let bob = PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[2u8; 32]).unwrap());
+
+        assert_eq!(
+            Multisig2Of2::new(alice, bob).address(Network::Regtest),
+            Multisig2Of2::new(bob, alice).address(Network::Regtest)
+        );
+    }
+
+    #[test]
+    fn witness_does_not_depend_on_signature_order() {
+        let secp = Secp256k1::signing_only();
+        let alice_sk = SecretKey::from_slice(&[1u8; 32]).unwrap();
+        let bob_sk = SecretKey::from_slice(&[2u8; 32]).unwrap();
+        let alice = PublicKey::from_secret_key(&secp, &alice_sk);
+        let bob = PublicKey::from_secret_key(&secp, &bob_sk);
+        let message = Message::from_slice(&[0xab; 32]).unwrap();
+        let alice_sig = secp.sign(&message, &alice_sk);
+        let bob_sig = secp.sign(&message, &bob_sk);
+        let multisig = Multisig2Of2::new(alice, bob);
+
+        let witness = multisig
+            .witness([(alice, alice_sig), (bob, bob_sig)])
+            .unwrap();
+        let swapped = multisig
+            .witness([(bob, bob_sig), (alice, alice_sig)])
+            .unwrap();
+
+        assert_eq!(witness, swapped);
+        assert_eq!(witness.len(), 4);
+        assert!(witness[0].is_empty());
+        assert_eq!(witness[3], multisig.witness_script().into_bytes());
+    }
+
+    #[test]
+    fn witness_rejects_signature_of_foreign_key() {
+        let secp = Secp256k1::signing_only();
+        let alice_sk = SecretKey::from_slice(&[1u8; 32]).unwrap();
+        let mallory_sk = SecretKey::from_slice(&[3u8; 32]).unwrap();
+        let alice = PublicKey::from_secret_key(&secp, &alice_sk);
+        let bob = PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[2u8; 32]).unwrap());
+        let mallory = PublicKey::from_secret_key(&secp, &mallory_sk);
+        let message = Message::from_slice(&[0xab; 32]).unwrap();
+        let multisig = Multisig2Of2::new(alice, bob);
+
+        let result = multisig.witness([
+            (alice, secp.sign(&message, &alice_sk)),
+            (mallory, secp.sign(&message, &mallory_sk)),
+        ]);
+
+        assert!(result.is_err());
+    }
+}
@@ -1,12 +1,23 @@
 pub mod bitcoin;
 pub mod ethereum;
 mod jsonrpc;
+mod polling;
 
-use crate::Never;
+pub use polling::{Backoff, PollingPolicy};
+
+use crate::{clock::Clock, Never};
 use anyhow::Result;
 use async_trait::async_trait;
+use futures::Stream;
 use genawaiter::sync::{Co, Gen};
-use std::{collections::HashSet, future::Future, hash::Hash, time::Duration};
+use std::{
+    collections::HashSet,
+    future::Future,
+    hash::Hash,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use time::OffsetDateTime;
 
 #[async_trait]
@@ -16,6 +27,20 @@ pub trait LatestBlock: Send + Sync + 'static {
     async fn latest_block(&self) -> Result<Self::Block>;
 }
 
+/// The blocks a node pushes to its subscribers as it learns about them.
+pub type BlockStream<B> = Pin<Box<dyn Stream<Item = B> + Send>>;
+
+/// Abstracts over nodes that push new blocks, sparing us the round-trips of
+/// polling `LatestBlock`.
+#[async_trait]
+pub trait BlockSubscription: Send + Sync + 'static {
+    type Block;
+
+    /// Fails if the node does not offer a subscription, in which case the
+    /// latest block has to be polled.
+    async fn subscribe(&self) -> Result<BlockStream<Self::Block>>;
+}
+
 #[async_trait]
 pub trait BlockByHash: Send + Sync + 'static {
     type Block;
@@ -29,6 +54,20 @@ pub trait ConnectedNetwork: Send + Sync + 'static {
     type Network;
 
     async fn connected_network(&self) -> Result<Self::Network>;
+
+    /// How to poll this node, `None` falls back to the default policy for
+    /// the network it is connected to.
+    fn polling_policy(&self) -> Option<PollingPolicy> {
+        None
+    }
+}
+
+/// The transactions a node knows about that are not mined yet.
+#[async_trait]
+pub trait MempoolTransactions: Send + Sync + 'static {
+    type Transaction;
+
+    async fn mempool_transactions(&self) -> Result<Vec<Self::Transaction>>;
 }
 
 /// Checks if a given block predates a certain timestamp.
@@ -50,6 +89,76 @@ pub trait PreviousBlockHash {
     fn previous_block_hash(&self) -> Self::BlockHash;
 }
 
+/// Remembers up to which block the blockchain was scanned, so that scanning
+/// can resume from there after a restart instead of going back to the start
+/// of the swap.
+///
+/// A checkpoint only makes sense for one particular scan, implementations
+/// must not share checkpoints between scans looking for different things.
+#[async_trait]
+pub trait Checkpoints<H>: Send + Sync {
+    /// The last block up to which all blocks were scanned.
+    async fn load_checkpoint(&self) -> Result<Option<H>>;
+
+    async fn save_checkpoint(&self, block_hash: H) -> Result<()>;
+}
+
+/// Scan from the start of the swap every time.
+#[derive(Debug, Clone, Copy)]
+pub struct NoCheckpoints;
+
+#[async_trait]
+impl<H> Checkpoints<H> for NoCheckpoints
+where
+    H: Send + 'static,
+{
+    async fn load_checkpoint(&self) -> Result<Option<H>> {
+        Ok(None)
+    }
+
+    async fn save_checkpoint(&self, _: H) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Keeps the checkpoint in memory, hence only useful within one process.
+#[derive(Debug)]
+pub struct InMemoryCheckpoints<H> {
+    checkpoint: Arc<Mutex<Option<H>>>,
+}
+
+impl<H> Default for InMemoryCheckpoints<H> {
+    fn default() -> Self {
+        Self {
+            checkpoint: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl<H> Clone for InMemoryCheckpoints<H> {
+    fn clone(&self) -> Self {
+        Self {
+            checkpoint: Arc::clone(&self.checkpoint),
+        }
+    }
+}
+
+#[async_trait]
+impl<H> Checkpoints<H> for InMemoryCheckpoints<H>
+where
+    H: Copy + Send + 'static,
+{
+    async fn load_checkpoint(&self) -> Result<Option<H>> {
+        Ok(*self.checkpoint.lock().expect("mutex is not poisoned"))
+    }
+
+    async fn save_checkpoint(&self, block_hash: H) -> Result<()> {
+        *self.checkpoint.lock().expect("mutex is not poisoned") = Some(block_hash);
+
+        Ok(())
+    }
+}
+
 /// Fetch blocks from a given timestamp on.
 ///
 /// To do this reliably, we start with the current latest block and walk the
@@ -59,33 +168,68 @@ pub trait PreviousBlockHash {
 /// we continuously check if we've seen a block's parent before. If we don't we
 /// walk back the ancestor chain again until we've seen a parent or we are past
 /// the given timestamp again.
-pub fn fetch_blocks_since<'a, C, B, H>(
+///
+/// Every time all blocks up to the latest one have been processed, the latest
+/// block is saved as checkpoint. If a checkpoint was saved by a previous run,
+/// we only walk back until the checkpoint instead of the given timestamp. In
+/// case the checkpoint was orphaned by a reorg we never come across it, hence
+/// we rewind all the way back to the given timestamp.
+pub fn fetch_blocks_since<'a, C, K, B, H>(
     connector: &'a C,
+    checkpoints: &'a K,
     start_of_swap: OffsetDateTime,
-    poll_interval: Duration,
+    polling_policy: PollingPolicy,
 ) -> Gen<B, (), impl Future<Output = Result<Never>> + 'a>
 where
     C: LatestBlock<Block = B> + BlockByHash<Block = B, BlockHash = H>,
+    K: Checkpoints<H>,
     B: Predates + BlockHash<BlockHash = H> + PreviousBlockHash<BlockHash = H> + Clone + 'a,
-    H: Eq + Hash + Copy,
+    H: Eq + Hash + Copy + 'a,
+{
+    fetch_blocks_since_with_backoff(
+        connector,
+        checkpoints,
+        start_of_swap,
+        Backoff::new(polling_policy),
+    )
+}
+
+fn fetch_blocks_since_with_backoff<'a, C, K, B, H, T>(
+    connector: &'a C,
+    checkpoints: &'a K,
+    start_of_swap: OffsetDateTime,
+    mut backoff: Backoff<T>,
+) -> Gen<B, (), impl Future<Output = Result<Never>> + 'a>
+where
+    C: LatestBlock<Block = B> + BlockByHash<Block = B, BlockHash = H>,
+    K: Checkpoints<H>,
+    B: Predates + BlockHash<BlockHash = H> + PreviousBlockHash<BlockHash = H> + Clone + 'a,
+    H: Eq + Hash + Copy + 'a,
+    T: Clock + 'a,
 {
     Gen::new(|co| async move {
+        let poll_interval = backoff.policy().initial;
+        let checkpoint = checkpoints.load_checkpoint().await?;
         let block = connector.latest_block().await?;
+        let mut last_checkpoint = block.block_hash();
 
-        // Look back in time until we get a block that predates start_of_swap.
+        // Look back in time until we get to the checkpoint or a block that predates
+        // start_of_swap.
         let mut seen_blocks = walk_back_until(
-            predates_start_of_swap(start_of_swap),
+            checkpoint_or_predates_start_of_swap(checkpoint, start_of_swap),
             block,
-            |_| true, // initially, yield all blocks because we haven't seen any of them
+            |hash| Some(*hash) != checkpoint, // the checkpoint was processed by a previous run
             connector,
             poll_interval,
             &co,
         )
         .await?;
+        checkpoints.save_checkpoint(last_checkpoint).await?;
 
         // Look forward in time, but keep going back for missed blocks
         loop {
             let block = connector.latest_block().await?;
+            let latest_block_hash = block.block_hash();
 
             let missed_blocks = walk_back_until(
                 seen_block_or_predates_start_of_swap(&seen_blocks, start_of_swap),
@@ -99,7 +243,13 @@ where
 
             seen_blocks.extend(missed_blocks);
 
-            tokio::time::delay_for(poll_interval).await;
+            let new_block = latest_block_hash != last_checkpoint;
+            if new_block {
+                checkpoints.save_checkpoint(latest_block_hash).await?;
+                last_checkpoint = latest_block_hash;
+            }
+
+            backoff.wait(new_block).await;
         }
     })
 }
@@ -172,6 +322,24 @@ where
     move |block| block.predates(start_of_swap)
 }
 
+/// Constructs a predicate that returns `true` if the given block is the
+/// checkpoint or predates the start_of_swap timestamp.
+fn checkpoint_or_predates_start_of_swap<B, H>(
+    checkpoint: Option<H>,
+    start_of_swap: OffsetDateTime,
+) -> impl Fn(&B) -> bool
+where
+    B: Predates + BlockHash<BlockHash = H>,
+    H: Eq,
+{
+    move |block: &B| {
+        let is_checkpoint = checkpoint.as_ref() == Some(&block.block_hash());
+        let predates_start_of_swap = predates_start_of_swap(start_of_swap)(block);
+
+        is_checkpoint || predates_start_of_swap
+    }
+}
+
 /// Constructs a predicate that returns `true` if we have seen the given block
 /// or the block predates the start_of_swap timestamp.
 fn seen_block_or_predates_start_of_swap<'sb, B, H>(
@@ -193,6 +361,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::TestClock;
     use futures::{future, Stream, TryStreamExt};
     use genawaiter::GeneratorState;
     use std::{collections::hash_map::RandomState, iter::FromIterator};
@@ -216,7 +385,12 @@ mod tests {
         let connector =
             FakeConnector::new(blocks, 50, INFURA_LATENCY, ETHEREUM_MAINNET_MINING_SPEED);
 
-        let gen = fetch_blocks_since(&connector, start_of_swap, ZERO_POLL_INTERVAL);
+        let gen = fetch_blocks_since(
+            &connector,
+            &NoCheckpoints,
+            start_of_swap,
+            PollingPolicy::new(ZERO_POLL_INTERVAL),
+        );
         let yielded_blocks = fallible_generator_to_try_stream(gen)
             .map_ok(|b| b.number)
             .try_take_while(|n| future::ready(Ok(*n != 52)))
@@ -239,7 +413,12 @@ mod tests {
         let start_of_swap = blocks[20].timestamp;
         let connector = FakeConnector::new(blocks, 50, Duration::from_secs(0), mining_speed);
 
-        let gen = fetch_blocks_since(&connector, start_of_swap, ZERO_POLL_INTERVAL);
+        let gen = fetch_blocks_since(
+            &connector,
+            &NoCheckpoints,
+            start_of_swap,
+            PollingPolicy::new(ZERO_POLL_INTERVAL),
+        );
         let yielded_blocks = fallible_generator_to_try_stream(gen)
             .map_ok(|b| b.number)
             .try_take_while(|n| future::ready(Ok(*n != 65)))
@@ -253,6 +432,109 @@ mod tests {
         assert_eq!(number_of_yielded_blocks, unique_blocks.len())
     }
 
+    #[tokio::test]
+    async fn scanning_resumes_from_the_checkpoint_after_a_restart() {
+        let mining_speed = Duration::from_secs(60);
+        let blocks = make_blockchain(100, mining_speed);
+        let start_of_swap = blocks[20].timestamp;
+        let checkpoints = InMemoryCheckpoints::default();
+
+        // the first run scans everything since the start of the swap
+        let connector =
+            FakeConnector::new(blocks.clone(), 50, Duration::from_secs(0), mining_speed);
+        let first_run = first_yielded_blocks(&connector, &checkpoints, start_of_swap, 32).await;
+        // block 19 is the first block that predates the start of the swap
+        assert_eq!(first_run.iter().min(), Some(&19));
+        assert_eq!(checkpoints.load_checkpoint().await.unwrap(), Some(50));
+
+        // by the time we restart, a few more blocks have been mined
+        let connector = FakeConnector::new(blocks, 60, Duration::from_secs(0), mining_speed);
+        let second_run = first_yielded_blocks(&connector, &checkpoints, start_of_swap, 10).await;
+
+        assert_eq!(second_run, (51..=60).rev().collect::<Vec<_>>());
+        assert_eq!(checkpoints.load_checkpoint().await.unwrap(), Some(60));
+    }
+
+    #[tokio::test]
+    async fn scanning_rewinds_if_the_checkpoint_was_orphaned() {
+        let mining_speed = Duration::from_secs(60);
+        let blocks = make_blockchain(100, mining_speed);
+        let start_of_swap = blocks[20].timestamp;
+        let checkpoints = InMemoryCheckpoints::default();
+        // a block that is not part of the chain (anymore)
+        checkpoints.save_checkpoint(1_000).await.unwrap();
+
+        let connector = FakeConnector::new(blocks, 50, Duration::from_secs(0), mining_speed);
+        let yielded_blocks =
+            first_yielded_blocks(&connector, &checkpoints, start_of_swap, 32).await;
+
+        assert_eq!(yielded_blocks.iter().min(), Some(&19));
+    }
+
+    #[tokio::test]
+    async fn polling_slows_down_while_no_new_blocks_arrive() {
+        // no block is mined during the test
+        let mining_speed = Duration::from_secs(3600);
+        let blocks = make_blockchain(100, mining_speed);
+        let start_of_swap = blocks[50].timestamp;
+        let connector = FakeConnector::new(blocks, 50, Duration::from_secs(0), mining_speed);
+        let policy = PollingPolicy {
+            initial: Duration::from_millis(10),
+            max: Duration::from_secs(1),
+            multiplier: 2,
+            jitter: 0.0,
+        };
+
+        let mut gen = fetch_blocks_since_with_backoff(
+            &connector,
+            &NoCheckpoints,
+            start_of_swap,
+            Backoff::with_clock(policy, connector.clock.clone()),
+        );
+        while connector.latest_block_calls.lock().await.len() < 10 {
+            let _ = tokio::time::timeout(Duration::from_millis(10), gen.async_resume()).await;
+        }
+
+        let calls = connector.latest_block_calls.lock().await.clone();
+        let intervals = calls[..10]
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).whole_milliseconds())
+            .collect::<Vec<_>>();
+
+        // the first two polls are the initial scan and the first look forward
+        assert_eq!(intervals, vec![0, 10, 20, 40, 80, 160, 320, 640, 1000]);
+    }
+
+    /// Collects the numbers of the first `n` blocks yielded by
+    /// `fetch_blocks_since`.
+    async fn first_yielded_blocks(
+        connector: &FakeConnector,
+        checkpoints: &InMemoryCheckpoints<usize>,
+        start_of_swap: OffsetDateTime,
+        n: usize,
+    ) -> Vec<usize> {
+        let mut gen = fetch_blocks_since(
+            connector,
+            checkpoints,
+            start_of_swap,
+            PollingPolicy::new(Duration::from_millis(10)),
+        );
+
+        let mut blocks = Vec::new();
+        while blocks.len() < n {
+            match gen.async_resume().await {
+                GeneratorState::Yielded(block) => blocks.push(block.number),
+                GeneratorState::Complete(Ok(never)) => match never {},
+                GeneratorState::Complete(Err(e)) => panic!("block processing failed: {:#}", e),
+            }
+        }
+
+        // let the generator save the checkpoint once the last block was processed
+        let _ = tokio::time::timeout(Duration::from_millis(100), gen.async_resume()).await;
+
+        blocks
+    }
+
     fn fallible_generator_to_try_stream<I, E, F: Future<Output = Result<Never, E>>>(
         gen: Gen<I, (), F>,
     ) -> impl Stream<Item = Result<I, E>> {
@@ -271,6 +553,8 @@ mod tests {
     /// point. Additionally, we have an artificial latency whilst fetching
     /// blocks as well as a mining interval that moves the pointer to the
     /// current latest block forward.
+    ///
+    /// Calls to `latest_block` are recorded with the time of `clock`.
     #[derive(Debug)]
     struct FakeConnector {
         blocks: Vec<FakeBlock>,
@@ -278,6 +562,8 @@ mod tests {
         current_block: Mutex<usize>,
         mining_speed: Duration,
         time_until_next_block: Mutex<Delay>,
+        clock: TestClock,
+        latest_block_calls: Mutex<Vec<OffsetDateTime>>,
     }
 
     impl FakeConnector {
@@ -293,6 +579,8 @@ mod tests {
                 current_block: Mutex::new(current_block),
                 mining_speed,
                 time_until_next_block: Mutex::new(delay_for(mining_speed)),
+                clock: TestClock::new(OffsetDateTime::from_unix_timestamp(0)),
+                latest_block_calls: Mutex::new(Vec::new()),
             }
         }
     }
@@ -342,6 +630,7 @@ mod tests {
         type Block = FakeBlock;
 
         async fn latest_block(&self) -> Result<Self::Block> {
+            self.latest_block_calls.lock().await.push(self.clock.now());
             tokio::time::delay_for(self.io_latency).await;
 
             let mut delay = self.time_until_next_block.lock().await;
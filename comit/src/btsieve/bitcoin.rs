@@ -1,14 +1,15 @@
 mod bitcoind_connector;
 mod cache;
+mod zmq;
 
 pub use self::{
     bitcoind_connector::{BitcoindConnector, ChainInfo},
-    cache::Cache,
+    cache::{BlockNotFound, Cache, CacheStats},
 };
 use crate::{
     btsieve::{
-        fetch_blocks_since, BlockByHash, BlockHash, ConnectedNetwork, LatestBlock, Predates,
-        PreviousBlockHash,
+        fetch_blocks_since, BlockByHash, BlockHash, Checkpoints, ConnectedNetwork, LatestBlock,
+        PollingPolicy, Predates, PreviousBlockHash,
     },
     identity, ledger,
 };
@@ -37,9 +38,10 @@ impl PreviousBlockHash for Block {
     }
 }
 
-#[tracing::instrument(level = "debug", skip(blockchain_connector, start_of_swap, identity), fields(%outpoint))]
-pub async fn watch_for_spent_outpoint<C>(
+#[tracing::instrument(level = "debug", skip(blockchain_connector, checkpoints, start_of_swap, identity), fields(%outpoint))]
+pub async fn watch_for_spent_outpoint<C, K>(
     blockchain_connector: &C,
+    checkpoints: &K,
     start_of_swap: OffsetDateTime,
     outpoint: OutPoint,
     identity: identity::Bitcoin,
@@ -48,23 +50,33 @@ where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
         + ConnectedNetwork<Network = ledger::Bitcoin>,
+    K: Checkpoints<Hash>,
 {
-    let (transaction, txin) = watch(blockchain_connector, start_of_swap, |transaction| {
-        transaction
-            .input
-            .iter()
-            .filter(|txin| txin.previous_output == outpoint)
-            .find(|txin| txin.witness.contains(&identity.to_bytes()))
-            .cloned()
-    })
+    let (transaction, txin) = watch(
+        blockchain_connector,
+        checkpoints,
+        start_of_swap,
+        |transaction| {
+            transaction
+                .input
+                .iter()
+                .filter(|txin| txin.previous_output == outpoint)
+                .find(|txin| txin.witness.contains(&identity.to_bytes()))
+                .cloned()
+        },
+    )
     .await?;
 
     Ok((transaction, txin))
 }
 
-#[tracing::instrument(level = "debug", skip(blockchain_connector, start_of_swap))]
-pub async fn watch_for_created_outpoint<C>(
+#[tracing::instrument(
+    level = "debug",
+    skip(blockchain_connector, checkpoints, start_of_swap)
+)]
+pub async fn watch_for_created_outpoint<C, K>(
     blockchain_connector: &C,
+    checkpoints: &K,
     start_of_swap: OffsetDateTime,
     address: bitcoin::Address,
 ) -> Result<(bitcoin::Transaction, bitcoin::OutPoint)>
@@ -72,30 +84,37 @@ where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
         + ConnectedNetwork<Network = ledger::Bitcoin>,
+    K: Checkpoints<Hash>,
 {
-    let (transaction, out_point) = watch(blockchain_connector, start_of_swap, |transaction| {
-        let txid = transaction.txid();
-        transaction
-            .output
-            .iter()
-            .enumerate()
-            .map(|(index, txout)| {
-                // Casting a usize to u32 can lead to truncation on 64bit platforms
-                // However, bitcoin limits the number of inputs to u32 anyway, so this
-                // is not a problem for us.
-                #[allow(clippy::cast_possible_truncation)]
-                (index as u32, txout)
-            })
-            .find(|(_, txout)| txout.script_pubkey == address.script_pubkey())
-            .map(|(vout, _txout)| OutPoint { txid, vout })
-    })
+    let (transaction, out_point) = watch(
+        blockchain_connector,
+        checkpoints,
+        start_of_swap,
+        |transaction| {
+            let txid = transaction.txid();
+            transaction
+                .output
+                .iter()
+                .enumerate()
+                .map(|(index, txout)| {
+                    // Casting a usize to u32 can lead to truncation on 64bit platforms
+                    // However, bitcoin limits the number of inputs to u32 anyway, so this
+                    // is not a problem for us.
+                    #[allow(clippy::cast_possible_truncation)]
+                    (index as u32, txout)
+                })
+                .find(|(_, txout)| txout.script_pubkey == address.script_pubkey())
+                .map(|(vout, _txout)| OutPoint { txid, vout })
+        },
+    )
     .await?;
 
     Ok((transaction, out_point))
 }
 
-async fn watch<C, S, M>(
+async fn watch<C, K, S, M>(
     connector: &C,
+    checkpoints: &K,
     start_of_swap: OffsetDateTime,
     sieve: S,
 ) -> Result<(bitcoin::Transaction, M)>
@@ -103,10 +122,12 @@ where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
         + ConnectedNetwork<Network = ledger::Bitcoin>,
+    K: Checkpoints<Hash>,
     S: Fn(&bitcoin::Transaction) -> Option<M>,
 {
-    let poll_interval = poll_interval(connector).await?;
-    let mut block_generator = fetch_blocks_since(connector, start_of_swap, poll_interval);
+    let polling_policy = polling_policy(connector).await?;
+    let mut block_generator =
+        fetch_blocks_since(connector, checkpoints, start_of_swap, polling_policy);
 
     loop {
         match block_generator.async_resume().await {
@@ -144,17 +165,21 @@ impl Predates for Block {
     }
 }
 
-async fn poll_interval<C>(connector: &C) -> Result<Duration>
+async fn polling_policy<C>(connector: &C) -> Result<PollingPolicy>
 where
     C: ConnectedNetwork<Network = ledger::Bitcoin>,
 {
     use ledger::Bitcoin::*;
 
+    if let Some(policy) = connector.polling_policy() {
+        return Ok(policy);
+    }
+
     let network = connector.connected_network().await?;
     let seconds = match network {
-        Mainnet | Testnet => 30,
+        Mainnet | Testnet | Testnet4 | Signet => 30,
         Regtest => 1,
     };
 
-    Ok(Duration::from_secs(seconds))
+    Ok(PollingPolicy::new(Duration::from_secs(seconds)))
 }
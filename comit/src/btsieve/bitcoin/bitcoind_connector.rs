@@ -1,12 +1,16 @@
 use crate::{
-    btsieve::{BlockByHash, ConnectedNetwork, LatestBlock},
+    btsieve::{
+        bitcoin::zmq::RawBlockSubscriber, BlockByHash, BlockStream, BlockSubscription,
+        ConnectedNetwork, LatestBlock, MempoolTransactions, PollingPolicy,
+    },
     ledger,
 };
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use bitcoin::{consensus::deserialize, BlockHash};
+use bitcoin::{consensus::deserialize, hashes::hex::FromHex, BlockHash, Transaction, Txid};
 use reqwest::{Client, Url};
-use serde::Deserialize;
+use serde::{de::IgnoredAny, Deserialize};
+use std::{collections::HashMap, net::SocketAddr};
 
 #[derive(Copy, Clone, Debug, Deserialize)]
 pub struct ChainInfo {
@@ -19,7 +23,11 @@ pub struct ChainInfo {
 pub struct BitcoindConnector {
     chaininfo_url: Url,
     raw_block_by_hash_url: Url,
+    mempool_contents_url: Url,
+    raw_transaction_by_id_url: Url,
     client: Client,
+    zmq_raw_block: Option<SocketAddr>,
+    polling_policy: Option<PollingPolicy>,
 }
 
 impl BitcoindConnector {
@@ -27,16 +35,65 @@ impl BitcoindConnector {
         Ok(Self {
             chaininfo_url: base_url.join("rest/chaininfo.json")?,
             raw_block_by_hash_url: base_url.join("rest/block/")?,
+            mempool_contents_url: base_url.join("rest/mempool/contents.json")?,
+            raw_transaction_by_id_url: base_url.join("rest/tx/")?,
             client: Client::new(),
+            zmq_raw_block: None,
+            polling_policy: None,
         })
     }
 
+    /// Subscribe to the blocks bitcoind publishes on `address`, as configured
+    /// with `-zmqpubrawblock`.
+    pub fn with_zmq_raw_block(self, address: SocketAddr) -> Self {
+        Self {
+            zmq_raw_block: Some(address),
+            ..self
+        }
+    }
+
+    /// Poll bitcoind according to `polling_policy` instead of the default for
+    /// the network it is connected to.
+    pub fn with_polling_policy(self, polling_policy: Option<PollingPolicy>) -> Self {
+        Self {
+            polling_policy,
+            ..self
+        }
+    }
+
     fn raw_block_by_hash_url(&self, block_hash: &BlockHash) -> Url {
         self.raw_block_by_hash_url
             .join(&format!("{}.hex", block_hash))
             .expect("building url should work")
     }
 
+    fn raw_transaction_by_id_url(&self, txid: &Txid) -> Url {
+        self.raw_transaction_by_id_url
+            .join(&format!("{}.hex", txid))
+            .expect("building url should work")
+    }
+
+    async fn transaction_by_id(&self, txid: &Txid) -> Result<Transaction> {
+        let url = self.raw_transaction_by_id_url(txid);
+        let response = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .with_context(|| GetRequestFailed(url.clone()))?
+            .error_for_status()
+            .with_context(|| GetRequestFailed(url.clone()))?
+            .text()
+            .await
+            .with_context(|| format!("failed to read response body for GET {}", url))?;
+
+        let bytes = hex::decode(response.trim()).context("failed to decode hex")?;
+        let transaction =
+            deserialize(bytes.as_slice()).context("failed to deserialize bytes as transaction")?;
+
+        Ok(transaction)
+    }
+
     pub async fn chain_info(&self) -> Result<ChainInfo> {
         let url = &self.chaininfo_url;
         let chain_info = self
@@ -88,6 +145,61 @@ impl BlockByHash for BitcoindConnector {
     }
 }
 
+#[async_trait]
+impl BlockSubscription for BitcoindConnector {
+    type Block = bitcoin::Block;
+
+    async fn subscribe(&self) -> Result<BlockStream<Self::Block>> {
+        let address = self
+            .zmq_raw_block
+            .context("no ZMQ endpoint to subscribe to blocks configured")?;
+        let subscriber = RawBlockSubscriber::connect(address).await?;
+
+        let blocks = futures::stream::unfold(subscriber, |mut subscriber| async move {
+            match subscriber.next_block().await {
+                Ok(block) => Some((block, subscriber)),
+                Err(e) => {
+                    tracing::warn!("block subscription ended: {:#}", e);
+                    None
+                }
+            }
+        });
+
+        Ok(Box::pin(blocks))
+    }
+}
+
+#[async_trait]
+impl MempoolTransactions for BitcoindConnector {
+    type Transaction = Transaction;
+
+    async fn mempool_transactions(&self) -> Result<Vec<Self::Transaction>> {
+        let url = &self.mempool_contents_url;
+        let contents = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .with_context(|| GetRequestFailed(url.clone()))?
+            .json::<HashMap<String, IgnoredAny>>()
+            .await
+            .context("failed to deserialize JSON response as mempool contents")?;
+
+        let mut transactions = Vec::with_capacity(contents.len());
+        for txid in contents.keys() {
+            let txid = Txid::from_hex(txid).context("failed to parse txid of mempool entry")?;
+
+            // the transaction may have been mined or evicted since we listed it
+            match self.transaction_by_id(&txid).await {
+                Ok(transaction) => transactions.push(transaction),
+                Err(e) => tracing::debug!("mempool transaction {} is gone: {:#}", txid, e),
+            }
+        }
+
+        Ok(transactions)
+    }
+}
+
 #[async_trait]
 impl ConnectedNetwork for BitcoindConnector {
     type Network = ledger::Bitcoin;
@@ -97,6 +209,10 @@ impl ConnectedNetwork for BitcoindConnector {
 
         Ok(chain)
     }
+
+    fn polling_policy(&self) -> Option<PollingPolicy> {
+        self.polling_policy
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -1,15 +1,39 @@
 use crate::{
-    btsieve::{BlockByHash, ConnectedNetwork, LatestBlock},
+    btsieve::{
+        BlockByHash, BlockSubscription, ConnectedNetwork, LatestBlock, MempoolTransactions,
+        PollingPolicy,
+    },
     ledger,
 };
 use anyhow::Result;
 use async_trait::async_trait;
 use bitcoin::{Block, BlockHash as Hash, BlockHash};
 use derivative::Derivative;
+use futures::StreamExt;
 use lru::LruCache;
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 use tokio::sync::Mutex;
 
+/// For how long a block that could not be fetched from the connector is
+/// remembered as missing before we ask the connector again.
+pub const DEFAULT_MISSING_BLOCK_TTL: Duration = Duration::from_secs(2);
+
+/// For how long the latest block pushed by the connector is trusted to be the
+/// tip. Blocks are rarely further apart, if none arrived in the meantime the
+/// subscription may have stalled and the connector is polled instead.
+pub const DEFAULT_SUBSCRIPTION_STALENESS: Duration = Duration::from_secs(20 * 60);
+
+/// How often `latest_block` cross-checks the cached network with the
+/// connector. Pointing the node to a different network is rare, asking on
+/// every poll is not worth the extra request.
+pub const DEFAULT_NETWORK_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
 #[derive(Derivative, Clone)]
 #[derivative(Debug)]
 pub struct Cache<C> {
@@ -17,42 +41,350 @@ pub struct Cache<C> {
     #[derivative(Debug = "ignore")]
     pub block_cache: Arc<Mutex<LruCache<BlockHash, Block>>>,
     #[derivative(Debug = "ignore")]
+    pub missing_block_cache: Arc<Mutex<LruCache<BlockHash, Instant>>>,
+    pub missing_block_ttl: Duration,
+    #[derivative(Debug = "ignore")]
     pub connected_network_cache: Arc<Mutex<Option<ledger::Bitcoin>>>,
+    /// When the connector was last asked for its network.
+    #[derivative(Debug = "ignore")]
+    last_network_check: Arc<Mutex<Option<Instant>>>,
+    pub network_check_interval: Duration,
+    #[derivative(Debug = "ignore")]
+    counters: Arc<Counters>,
+    #[derivative(Debug = "ignore")]
+    tip: Arc<Mutex<Option<BlockHash>>>,
+    /// The latest block pushed by the connector and when it arrived, as long
+    /// as the subscription is alive.
+    #[derivative(Debug = "ignore")]
+    subscribed_tip: Arc<Mutex<Option<(BlockHash, Instant)>>>,
+    pub subscription_staleness: Duration,
+    #[derivative(Debug = "ignore")]
+    max_reorg_depth: Arc<AtomicUsize>,
+    auto_resize_ceiling: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+/// Statistics about the block cache, useful to assess whether its capacity is
+/// adequate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub len: usize,
 }
 
 impl<C> Cache<C> {
     pub fn new(connector: C, capacity: usize) -> Cache<C> {
         let block_cache = Arc::new(Mutex::new(LruCache::new(capacity)));
+        let missing_block_cache = Arc::new(Mutex::new(LruCache::new(capacity)));
         let connected_network_cache = Arc::new(Mutex::new(None));
 
         Cache {
             connector,
             block_cache,
+            missing_block_cache,
+            missing_block_ttl: DEFAULT_MISSING_BLOCK_TTL,
             connected_network_cache,
+            last_network_check: Arc::new(Mutex::new(None)),
+            network_check_interval: DEFAULT_NETWORK_CHECK_INTERVAL,
+            counters: Arc::new(Counters::default()),
+            tip: Arc::new(Mutex::new(None)),
+            subscribed_tip: Arc::new(Mutex::new(None)),
+            subscription_staleness: DEFAULT_SUBSCRIPTION_STALENESS,
+            max_reorg_depth: Arc::new(AtomicUsize::new(0)),
+            auto_resize_ceiling: None,
+        }
+    }
+
+    /// Grow the block cache, up to `ceiling` blocks, whenever a reorg is
+    /// observed that the current capacity cannot handle.
+    pub fn with_auto_resize(self, ceiling: usize) -> Cache<C> {
+        Cache {
+            auto_resize_ceiling: Some(ceiling),
+            ..self
+        }
+    }
+
+    /// Forget the network of the connector, the next call to
+    /// `connected_network` asks the connector again.
+    pub async fn invalidate_network(&self) {
+        let _ = self.connected_network_cache.lock().await.take();
+    }
+
+    /// The deepest reorg observed so far, in number of blocks that were
+    /// removed from the chain.
+    pub fn max_reorg_depth(&self) -> usize {
+        self.max_reorg_depth.load(Ordering::Relaxed)
+    }
+
+    /// The minimum capacity needed to handle the deepest reorg observed so
+    /// far: both branches of the reorg and their common ancestor must fit in
+    /// the cache.
+    pub fn suggested_capacity(&self) -> usize {
+        2 * self.max_reorg_depth() + 1
+    }
+
+    /// Set for how long a block that could not be fetched is remembered as
+    /// missing. A zero duration disables the caching of missing blocks.
+    pub fn with_missing_block_ttl(self, missing_block_ttl: Duration) -> Cache<C> {
+        Cache {
+            missing_block_ttl,
+            ..self
         }
     }
+
+    /// Set for how long the latest pushed block is served as the tip before
+    /// the connector is polled again.
+    pub fn with_subscription_staleness(self, subscription_staleness: Duration) -> Cache<C> {
+        Cache {
+            subscription_staleness,
+            ..self
+        }
+    }
+
+    /// Set how often `latest_block` cross-checks the cached network with the
+    /// connector.
+    pub fn with_network_check_interval(self, network_check_interval: Duration) -> Cache<C> {
+        Cache {
+            network_check_interval,
+            ..self
+        }
+    }
+
+    /// Whether `latest_block` should cross-check the cached network, which
+    /// only happens once per network check interval and only if there is a
+    /// cached network to check.
+    async fn network_check_due(&self) -> bool {
+        if self.connected_network_cache.lock().await.is_none() {
+            return false;
+        }
+
+        let mut last_network_check = self.last_network_check.lock().await;
+        match *last_network_check {
+            Some(checked) if checked.elapsed() < self.network_check_interval => false,
+            _ => {
+                let _ = last_network_check.replace(Instant::now());
+                true
+            }
+        }
+    }
+
+    pub async fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            evictions: self.counters.evictions.load(Ordering::Relaxed),
+            len: self.block_cache.lock().await.len(),
+        }
+    }
+
+    fn record_hit(&self) {
+        self.counters.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_miss(&self) {
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Inserts the block in the cache, recording the eviction of the least
+    /// recently used block if the cache is full.
+    fn put_block(
+        &self,
+        cache: &mut LruCache<BlockHash, Block>,
+        block_hash: BlockHash,
+        block: Block,
+    ) {
+        if cache.len() == cache.cap() && !cache.contains(&block_hash) {
+            self.counters.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+
+        cache.put(block_hash, block);
+    }
 }
 
 #[async_trait]
 impl<C> LatestBlock for Cache<C>
 where
-    C: LatestBlock<Block = Block>,
+    C: LatestBlock<Block = Block>
+        + BlockByHash<Block = Block, BlockHash = Hash>
+        + ConnectedNetwork<Network = ledger::Bitcoin>,
 {
     type Block = Block;
 
     async fn latest_block(&self) -> Result<Self::Block> {
+        if let Some((tip, received)) = *self.subscribed_tip.lock().await {
+            if received.elapsed() < self.subscription_staleness {
+                if let Some(block) = self.block_cache.lock().await.get(&tip) {
+                    self.record_hit();
+                    return Ok(block.clone());
+                }
+            }
+        }
+
         let block = self.connector.latest_block().await?;
 
-        let block_hash = block.block_hash();
-        let mut guard = self.block_cache.lock().await;
-        if !guard.contains(&block_hash) {
-            guard.put(block_hash, block.clone());
+        // The node may have been pointed to a different network since we cached it,
+        // check every now and then. We never hold the lock while talking to the
+        // connector.
+        if self.network_check_due().await {
+            match self.connector.connected_network().await {
+                Ok(observed) => {
+                    let mut guard = self.connected_network_cache.lock().await;
+                    if let Some(cached) = *guard {
+                        if cached != observed {
+                            tracing::warn!(
+                                "connected network changed from {} to {}, invalidating cache",
+                                cached,
+                                observed
+                            );
+                            let _ = guard.take();
+                        }
+                    }
+                }
+                Err(e) => tracing::debug!("failed to cross-check connected network: {:#}", e),
+            }
+        }
+
+        if self.block_cache.lock().await.contains(&block.block_hash()) {
+            self.record_hit();
+        } else {
+            self.record_miss();
         }
+        self.observe_tip(&block).await;
 
         Ok(block)
     }
 }
 
+impl<C> Cache<C>
+where
+    C: BlockSubscription<Block = Block> + BlockByHash<Block = Block, BlockHash = Hash>,
+{
+    /// Puts the blocks pushed by the connector into the cache until the
+    /// subscription ends. Meanwhile, `latest_block` is answered from the cache
+    /// instead of polling the connector, unless no block was pushed for
+    /// longer than the subscription staleness.
+    ///
+    /// Fails right away if the connector does not offer a subscription, in
+    /// which case `latest_block` keeps polling.
+    pub async fn follow_subscription(&self) -> Result<()> {
+        let mut blocks = self.connector.subscribe().await?;
+
+        while let Some(block) = blocks.next().await {
+            self.observe_tip(&block).await;
+            let _ = self
+                .subscribed_tip
+                .lock()
+                .await
+                .replace((block.block_hash(), Instant::now()));
+        }
+
+        tracing::info!("block subscription ended, polling for the latest block");
+        let _ = self.subscribed_tip.lock().await.take();
+
+        Ok(())
+    }
+}
+
+impl<C> Cache<C>
+where
+    C: BlockByHash<Block = Block, BlockHash = Hash>,
+{
+    /// Caches the latest block of the chain and tracks reorgs.
+    async fn observe_tip(&self, block: &Block) {
+        let block_hash = block.block_hash();
+        {
+            let mut guard = self.block_cache.lock().await;
+            if !guard.contains(&block_hash) {
+                self.put_block(&mut guard, block_hash, block.clone());
+            }
+        }
+
+        // The node knows about this block now, no need to wait for the TTL to expire.
+        let _ = self.missing_block_cache.lock().await.pop(&block_hash);
+
+        let previous_tip = self.tip.lock().await.replace(block_hash);
+        match previous_tip {
+            Some(previous_tip)
+                if previous_tip != block_hash && block.header.prev_blockhash != previous_tip =>
+            {
+                self.track_reorg(previous_tip, block).await;
+            }
+            _ => {}
+        }
+    }
+
+    /// Measures how many blocks of the chain ending at `previous_tip` are not
+    /// part of the chain ending at `new_tip`.
+    ///
+    /// Several blocks may have been mined since we last saw the tip, in which
+    /// case `previous_tip` is an ancestor of `new_tip` and the depth is 0.
+    async fn track_reorg(&self, previous_tip: BlockHash, new_tip: &Block) {
+        let previous_chain = {
+            let guard = self.block_cache.lock().await;
+
+            let mut chain = vec![previous_tip];
+            while let Some(block) = chain.last().and_then(|hash| guard.peek(hash)) {
+                chain.push(block.header.prev_blockhash);
+            }
+
+            chain
+        };
+        let max_steps = self.block_cache.lock().await.cap();
+
+        let mut current = new_tip.header.prev_blockhash;
+        let mut depth = None;
+        for _ in 0..max_steps {
+            if let Some(position) = previous_chain.iter().position(|hash| *hash == current) {
+                depth = Some(position);
+                break;
+            }
+
+            match self.block_by_hash(current).await {
+                Ok(block) => current = block.header.prev_blockhash,
+                Err(e) => {
+                    tracing::debug!("could not walk back new chain to measure reorg: {:#}", e);
+                    break;
+                }
+            }
+        }
+        // If we could not find the common ancestor, the reorg is at least as deep as
+        // the part of the previous chain we know about.
+        let depth = depth.unwrap_or_else(|| previous_chain.len());
+
+        if depth == 0 {
+            return;
+        }
+
+        tracing::info!("observed a reorg of depth {}", depth);
+        let max_reorg_depth = self
+            .max_reorg_depth
+            .fetch_max(depth, Ordering::Relaxed)
+            .max(depth);
+
+        if let Some(ceiling) = self.auto_resize_ceiling {
+            let mut guard = self.block_cache.lock().await;
+            let needed = (2 * max_reorg_depth + 1).min(ceiling);
+
+            if needed > guard.cap() {
+                tracing::info!(
+                    "growing block cache from {} to {} blocks",
+                    guard.cap(),
+                    needed
+                );
+                guard.resize(needed);
+            }
+        }
+    }
+}
+
 #[async_trait]
 impl<C> BlockByHash for Cache<C>
 where
@@ -63,20 +395,42 @@ where
 
     async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block> {
         if let Some(block) = self.block_cache.lock().await.get(&block_hash) {
+            self.record_hit();
             return Ok(block.clone());
         }
+        self.record_miss();
+
+        if let Some(missing_since) = self.missing_block_cache.lock().await.get(&block_hash) {
+            if missing_since.elapsed() < self.missing_block_ttl {
+                return Err(BlockNotFound(block_hash).into());
+            }
+        }
 
-        let block = self.connector.block_by_hash(block_hash).await?;
+        let block = match self.connector.block_by_hash(block_hash).await {
+            Ok(block) => block,
+            Err(e) => {
+                self.missing_block_cache
+                    .lock()
+                    .await
+                    .put(block_hash, Instant::now());
+                return Err(e);
+            }
+        };
+        let _ = self.missing_block_cache.lock().await.pop(&block_hash);
 
         // We dropped the lock so at this stage the block may have been inserted by
         // another thread, no worries, inserting the same block twice does not hurt.
         let mut guard = self.block_cache.lock().await;
-        guard.put(block_hash, block.clone());
+        self.put_block(&mut guard, block_hash, block.clone());
 
         Ok(block)
     }
 }
 
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("block {0} was recently not found by the connector")]
+pub struct BlockNotFound(pub BlockHash);
+
 #[async_trait]
 impl<C> ConnectedNetwork for Cache<C>
 where
@@ -91,7 +445,443 @@ where
 
         let network = self.connector.connected_network().await?;
         let _ = self.connected_network_cache.lock().await.replace(network);
+        let _ = self.last_network_check.lock().await.replace(Instant::now());
 
         Ok(network)
     }
+
+    fn polling_policy(&self) -> Option<PollingPolicy> {
+        self.connector.polling_policy()
+    }
+}
+
+#[async_trait]
+impl<C> MempoolTransactions for Cache<C>
+where
+    C: MempoolTransactions<Transaction = bitcoin::Transaction>,
+{
+    type Transaction = bitcoin::Transaction;
+
+    async fn mempool_transactions(&self) -> Result<Vec<Self::Transaction>> {
+        self.connector.mempool_transactions().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btsieve::BlockStream;
+    use bitcoin::{blockdata::constants::genesis_block, Network};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const TTL: Duration = Duration::from_millis(200);
+
+    /// Connector that only knows the blocks it was given and counts how often
+    /// it is asked for a block.
+    #[derive(Debug, Clone)]
+    struct CountingConnector {
+        blocks: Arc<Mutex<Vec<Block>>>,
+        block_by_hash_calls: Arc<AtomicUsize>,
+        network: Arc<Mutex<ledger::Bitcoin>>,
+        connected_network_calls: Arc<AtomicUsize>,
+        /// The blocks pushed to subscribers, `None` if subscriptions are not
+        /// supported.
+        subscription: Arc<Mutex<Option<Vec<Block>>>>,
+    }
+
+    impl Default for CountingConnector {
+        fn default() -> Self {
+            CountingConnector {
+                blocks: Arc::new(Mutex::new(Vec::new())),
+                block_by_hash_calls: Arc::new(AtomicUsize::new(0)),
+                network: Arc::new(Mutex::new(ledger::Bitcoin::Regtest)),
+                connected_network_calls: Arc::new(AtomicUsize::new(0)),
+                subscription: Arc::new(Mutex::new(None)),
+            }
+        }
+    }
+
+    impl CountingConnector {
+        async fn switch_network(&self, network: ledger::Bitcoin) {
+            *self.network.lock().await = network;
+        }
+
+        fn connected_network_calls(&self) -> usize {
+            self.connected_network_calls.load(Ordering::SeqCst)
+        }
+
+        async fn mine(&self, block: Block) {
+            self.blocks.lock().await.push(block);
+        }
+
+        fn block_by_hash_calls(&self) -> usize {
+            self.block_by_hash_calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl LatestBlock for CountingConnector {
+        type Block = Block;
+
+        async fn latest_block(&self) -> Result<Self::Block> {
+            self.blocks
+                .lock()
+                .await
+                .last()
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("no blocks"))
+        }
+    }
+
+    #[async_trait]
+    impl BlockByHash for CountingConnector {
+        type Block = Block;
+        type BlockHash = BlockHash;
+
+        async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block> {
+            self.block_by_hash_calls.fetch_add(1, Ordering::SeqCst);
+
+            self.blocks
+                .lock()
+                .await
+                .iter()
+                .find(|block| block.block_hash() == block_hash)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("block {} not found", block_hash))
+        }
+    }
+
+    #[async_trait]
+    impl BlockSubscription for CountingConnector {
+        type Block = Block;
+
+        async fn subscribe(&self) -> Result<BlockStream<Self::Block>> {
+            let blocks = self
+                .subscription
+                .lock()
+                .await
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("subscriptions not supported"))?;
+
+            // The subscription stays open after pushing the blocks
+            Ok(Box::pin(
+                futures::stream::iter(blocks).chain(futures::stream::pending()),
+            ))
+        }
+    }
+
+    #[async_trait]
+    impl ConnectedNetwork for CountingConnector {
+        type Network = ledger::Bitcoin;
+
+        async fn connected_network(&self) -> Result<Self::Network> {
+            self.connected_network_calls.fetch_add(1, Ordering::SeqCst);
+
+            Ok(*self.network.lock().await)
+        }
+    }
+
+    fn block(nonce: u32) -> Block {
+        let mut block = genesis_block(Network::Regtest);
+        block.header.nonce = nonce;
+
+        block
+    }
+
+    #[tokio::test]
+    async fn missing_block_is_not_fetched_again_within_ttl() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10).with_missing_block_ttl(TTL);
+        let hash = block(1).block_hash();
+
+        for _ in 0..5 {
+            let result = cache.block_by_hash(hash).await;
+            assert!(result.is_err());
+        }
+
+        assert_eq!(connector.block_by_hash_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn missing_block_is_fetched_again_after_ttl() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10).with_missing_block_ttl(TTL);
+        let block = block(1);
+        let hash = block.block_hash();
+
+        assert!(cache.block_by_hash(hash).await.is_err());
+
+        connector.mine(block).await;
+        tokio::time::delay_for(TTL).await;
+
+        assert!(cache.block_by_hash(hash).await.is_ok());
+        assert_eq!(connector.block_by_hash_calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn missing_block_is_forgotten_once_seen_as_latest_block() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10).with_missing_block_ttl(TTL);
+        let block = block(1);
+        let hash = block.block_hash();
+
+        assert!(cache.block_by_hash(hash).await.is_err());
+
+        connector.mine(block).await;
+        let latest_block = cache.latest_block().await.unwrap();
+        assert_eq!(latest_block.block_hash(), hash);
+
+        assert!(cache.missing_block_cache.lock().await.peek(&hash).is_none());
+        assert!(cache.block_by_hash(hash).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn zero_ttl_disables_caching_of_missing_blocks() {
+        let connector = CountingConnector::default();
+        let cache =
+            Cache::new(connector.clone(), 10).with_missing_block_ttl(Duration::from_secs(0));
+        let hash = block(1).block_hash();
+
+        for _ in 0..3 {
+            assert!(cache.block_by_hash(hash).await.is_err());
+        }
+
+        assert_eq!(connector.block_by_hash_calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn stats_count_hits_misses_and_evictions() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 2);
+        let (block1, block2, block3) = (block(1), block(2), block(3));
+
+        connector.mine(block1.clone()).await;
+        cache.latest_block().await.unwrap(); // miss
+        cache.block_by_hash(block1.block_hash()).await.unwrap(); // hit
+        cache.latest_block().await.unwrap(); // hit
+
+        connector.mine(block2).await;
+        cache.latest_block().await.unwrap(); // miss
+
+        connector.mine(block3).await;
+        cache.latest_block().await.unwrap(); // miss, evicts block1
+
+        cache.block_by_hash(block1.block_hash()).await.unwrap(); // miss, evicts block2
+
+        assert_eq!(cache.stats().await, CacheStats {
+            hits: 2,
+            misses: 4,
+            evictions: 2,
+            len: 2
+        });
+    }
+
+    fn child(parent: &Block, nonce: u32) -> Block {
+        let mut block = block(nonce);
+        block.header.prev_blockhash = parent.block_hash();
+
+        block
+    }
+
+    /// Mines `length` blocks on top of `parent`, calling `latest_block` after
+    /// each of them, and returns the tip.
+    async fn mine_chain(
+        connector: &CountingConnector,
+        cache: &Cache<CountingConnector>,
+        parent: &Block,
+        length: u32,
+        nonce_offset: u32,
+    ) -> Block {
+        let mut tip = parent.clone();
+        for nonce in 0..length {
+            tip = child(&tip, nonce_offset + nonce);
+            connector.mine(tip.clone()).await;
+            cache.latest_block().await.unwrap();
+        }
+
+        tip
+    }
+
+    #[tokio::test]
+    async fn linear_chain_does_not_count_as_reorg() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 20);
+        let genesis = block(0);
+        connector.mine(genesis.clone()).await;
+
+        mine_chain(&connector, &cache, &genesis, 10, 100).await;
+
+        assert_eq!(cache.max_reorg_depth(), 0);
+        assert_eq!(cache.suggested_capacity(), 1);
+    }
+
+    #[tokio::test]
+    async fn five_block_reorg_is_reflected_in_suggested_capacity() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 20);
+        let genesis = block(0);
+        connector.mine(genesis.clone()).await;
+
+        let fork_point = mine_chain(&connector, &cache, &genesis, 5, 100).await;
+        mine_chain(&connector, &cache, &fork_point, 5, 200).await;
+
+        // The competing branch is mined without us polling, only its tip is seen
+        let mut tip = fork_point;
+        for nonce in 300..306 {
+            tip = child(&tip, nonce);
+            connector.mine(tip.clone()).await;
+        }
+        cache.latest_block().await.unwrap();
+
+        assert_eq!(cache.max_reorg_depth(), 5);
+        assert_eq!(cache.suggested_capacity(), 11);
+    }
+
+    #[tokio::test]
+    async fn auto_resize_grows_cache_up_to_ceiling() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 8).with_auto_resize(10);
+        let genesis = block(0);
+        connector.mine(genesis.clone()).await;
+
+        let fork_point = mine_chain(&connector, &cache, &genesis, 2, 100).await;
+        mine_chain(&connector, &cache, &fork_point, 5, 200).await;
+
+        let mut tip = fork_point;
+        for nonce in 300..306 {
+            tip = child(&tip, nonce);
+            connector.mine(tip.clone()).await;
+        }
+        cache.latest_block().await.unwrap();
+
+        assert_eq!(cache.max_reorg_depth(), 5);
+        assert_eq!(cache.block_cache.lock().await.cap(), 10);
+    }
+
+    #[tokio::test]
+    async fn invalidated_network_is_fetched_again() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10);
+
+        assert_eq!(
+            cache.connected_network().await.unwrap(),
+            ledger::Bitcoin::Regtest
+        );
+        assert_eq!(
+            cache.connected_network().await.unwrap(),
+            ledger::Bitcoin::Regtest
+        );
+        assert_eq!(connector.connected_network_calls(), 1);
+
+        cache.invalidate_network().await;
+
+        assert_eq!(
+            cache.connected_network().await.unwrap(),
+            ledger::Bitcoin::Regtest
+        );
+        assert_eq!(connector.connected_network_calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn latest_block_invalidates_network_on_mismatch() {
+        let connector = CountingConnector::default();
+        let cache =
+            Cache::new(connector.clone(), 10).with_network_check_interval(Duration::from_secs(0));
+        connector.mine(block(0)).await;
+
+        assert_eq!(
+            cache.connected_network().await.unwrap(),
+            ledger::Bitcoin::Regtest
+        );
+
+        connector.switch_network(ledger::Bitcoin::Testnet).await;
+        cache.latest_block().await.unwrap();
+
+        assert_eq!(
+            cache.connected_network().await.unwrap(),
+            ledger::Bitcoin::Testnet
+        );
+    }
+
+    #[tokio::test]
+    async fn latest_block_cross_checks_network_once_per_interval() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10).with_network_check_interval(TTL);
+        connector.mine(block(0)).await;
+
+        cache.latest_block().await.unwrap();
+        assert_eq!(
+            connector.connected_network_calls(),
+            0,
+            "nothing cached, nothing to cross-check"
+        );
+
+        cache.connected_network().await.unwrap();
+        cache.latest_block().await.unwrap();
+        cache.latest_block().await.unwrap();
+        assert_eq!(connector.connected_network_calls(), 1);
+
+        tokio::time::delay_for(TTL).await;
+        cache.latest_block().await.unwrap();
+        cache.latest_block().await.unwrap();
+        assert_eq!(connector.connected_network_calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn cache_is_populated_from_subscription() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10);
+        let genesis = block(0);
+        let tip = child(&genesis, 1);
+        *connector.subscription.lock().await = Some(vec![genesis.clone(), tip.clone()]);
+
+        let _ = tokio::spawn({
+            let cache = cache.clone();
+            async move { cache.follow_subscription().await }
+        });
+        tokio::time::delay_for(Duration::from_millis(50)).await;
+
+        // The connector was never polled, it does not even know the blocks
+        assert_eq!(cache.latest_block().await.unwrap(), tip);
+        assert_eq!(
+            cache.block_by_hash(genesis.block_hash()).await.unwrap(),
+            genesis
+        );
+        assert_eq!(connector.block_by_hash_calls(), 0);
+        assert_eq!(cache.stats().await.len, 2);
+    }
+
+    #[tokio::test]
+    async fn latest_block_is_polled_once_subscription_is_stale() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10)
+            .with_subscription_staleness(Duration::from_millis(100));
+        let genesis = block(0);
+        let tip = child(&genesis, 1);
+        *connector.subscription.lock().await = Some(vec![genesis.clone()]);
+        connector.mine(genesis.clone()).await;
+
+        let _ = tokio::spawn({
+            let cache = cache.clone();
+            async move { cache.follow_subscription().await }
+        });
+        tokio::time::delay_for(Duration::from_millis(50)).await;
+
+        // the subscription stalls while the node mines another block
+        connector.mine(tip.clone()).await;
+        assert_eq!(cache.latest_block().await.unwrap(), genesis);
+
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+        assert_eq!(cache.latest_block().await.unwrap(), tip);
+    }
+
+    #[tokio::test]
+    async fn latest_block_is_polled_without_subscription() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10);
+        connector.mine(block(0)).await;
+
+        assert!(cache.follow_subscription().await.is_err());
+        assert_eq!(cache.latest_block().await.unwrap(), block(0));
+    }
 }
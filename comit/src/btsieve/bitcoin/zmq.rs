@@ -0,0 +1,205 @@
+//! A minimal ZeroMQ subscriber for the blocks bitcoind publishes.
+//!
+//! bitcoind publishes every block it connects if started with
+//! `-zmqpubrawblock=tcp://<address>`. Only what is needed to receive these
+//! notifications is implemented: a SUB socket speaking ZMTP 3.0 with the NULL
+//! security mechanism.
+
+use anyhow::{Context, Result};
+use bitcoin::{consensus::deserialize, Block};
+use std::{convert::TryFrom, net::SocketAddr};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+};
+
+const RAW_BLOCK_TOPIC: &[u8] = b"rawblock";
+
+const FLAG_MORE: u8 = 0x01;
+const FLAG_LONG: u8 = 0x02;
+const FLAG_COMMAND: u8 = 0x04;
+
+/// Blocks are at most 4 MB, anything bigger is not sent by bitcoind.
+const MAX_FRAME_SIZE: u64 = 8 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct RawBlockSubscriber {
+    stream: TcpStream,
+}
+
+impl RawBlockSubscriber {
+    pub async fn connect(address: SocketAddr) -> Result<Self> {
+        let mut stream = TcpStream::connect(address)
+            .await
+            .with_context(|| format!("failed to connect to ZMQ publisher at {}", address))?;
+
+        stream.write_all(&greeting()).await?;
+        let mut peer_greeting = [0u8; 64];
+        stream.read_exact(&mut peer_greeting).await?;
+        if peer_greeting[0] != 0xFF || peer_greeting[9] != 0x7F || peer_greeting[10] < 3 {
+            anyhow::bail!("ZMQ publisher at {} does not speak ZMTP 3", address)
+        }
+
+        write_frame(&mut stream, FLAG_COMMAND, &ready_command()).await?;
+        let (flags, _) = read_frame(&mut stream).await?;
+        if flags & FLAG_COMMAND == 0 {
+            anyhow::bail!("expected READY command from ZMQ publisher at {}", address)
+        }
+
+        // In ZMTP 3.0, subscribing is a message starting with 0x01 followed by the
+        // topic.
+        let mut subscription = vec![0x01];
+        subscription.extend_from_slice(RAW_BLOCK_TOPIC);
+        write_frame(&mut stream, 0, &subscription).await?;
+
+        Ok(Self { stream })
+    }
+
+    /// Waits for the next block bitcoind publishes.
+    pub async fn next_block(&mut self) -> Result<Block> {
+        loop {
+            // bitcoind sends the topic, the block and a sequence number
+            if let [topic, body, ..] = self.read_message().await?.as_slice() {
+                if topic.as_slice() == RAW_BLOCK_TOPIC {
+                    return deserialize(body).context("failed to deserialize published block");
+                }
+            }
+        }
+    }
+
+    /// Reads all frames of the next message, skipping commands.
+    async fn read_message(&mut self) -> Result<Vec<Vec<u8>>> {
+        let mut frames = Vec::new();
+
+        loop {
+            let (flags, body) = read_frame(&mut self.stream).await?;
+            if flags & FLAG_COMMAND != 0 {
+                continue;
+            }
+
+            frames.push(body);
+
+            if flags & FLAG_MORE == 0 {
+                return Ok(frames);
+            }
+        }
+    }
+}
+
+fn greeting() -> [u8; 64] {
+    let mut greeting = [0u8; 64];
+    // signature
+    greeting[0] = 0xFF;
+    greeting[9] = 0x7F;
+    // version 3.0
+    greeting[10] = 3;
+    // mechanism, zero padded, followed by as-server = 0 and filler
+    greeting[12..16].copy_from_slice(b"NULL");
+
+    greeting
+}
+
+fn ready_command() -> Vec<u8> {
+    let mut command = vec![5];
+    command.extend_from_slice(b"READY");
+    command.push(11);
+    command.extend_from_slice(b"Socket-Type");
+    command.extend_from_slice(&3u32.to_be_bytes());
+    command.extend_from_slice(b"SUB");
+
+    command
+}
+
+async fn write_frame<W>(writer: &mut W, flags: u8, body: &[u8]) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    match u8::try_from(body.len()) {
+        Ok(size) => writer.write_all(&[flags, size]).await?,
+        Err(_) => {
+            writer.write_all(&[flags | FLAG_LONG]).await?;
+            writer
+                .write_all(&u64::try_from(body.len())?.to_be_bytes())
+                .await?;
+        }
+    }
+    writer.write_all(body).await?;
+
+    Ok(())
+}
+
+async fn read_frame<R>(reader: &mut R) -> Result<(u8, Vec<u8>)>
+where
+    R: AsyncRead + Unpin,
+{
+    let flags = reader.read_u8().await?;
+    let size = if flags & FLAG_LONG != 0 {
+        reader.read_u64().await?
+    } else {
+        u64::from(reader.read_u8().await?)
+    };
+
+    if size > MAX_FRAME_SIZE {
+        anyhow::bail!("ZMQ frame of {} bytes exceeds the maximum size", size)
+    }
+
+    let mut body = vec![0u8; usize::try_from(size)?];
+    reader.read_exact(&mut body).await?;
+
+    Ok((flags, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{blockdata::constants::genesis_block, consensus::serialize, Network};
+    use tokio::net::TcpListener;
+
+    /// Plays the part of bitcoind: completes the handshake, expects the
+    /// subscription and publishes `block`.
+    async fn publish(mut listener: TcpListener, block: Block) {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        stream.write_all(&greeting()).await.unwrap();
+        let mut peer_greeting = [0u8; 64];
+        stream.read_exact(&mut peer_greeting).await.unwrap();
+        assert_eq!(&peer_greeting[12..16], b"NULL");
+
+        write_frame(&mut stream, FLAG_COMMAND, &ready_command())
+            .await
+            .unwrap();
+        let (flags, _) = read_frame(&mut stream).await.unwrap();
+        assert_eq!(flags, FLAG_COMMAND);
+        let (_, subscription) = read_frame(&mut stream).await.unwrap();
+        assert_eq!(&subscription[1..], RAW_BLOCK_TOPIC);
+
+        write_frame(&mut stream, FLAG_MORE, b"hashblock")
+            .await
+            .unwrap();
+        write_frame(&mut stream, 0, &[0u8; 32]).await.unwrap();
+
+        write_frame(&mut stream, FLAG_MORE, RAW_BLOCK_TOPIC)
+            .await
+            .unwrap();
+        write_frame(&mut stream, FLAG_MORE, &serialize(&block))
+            .await
+            .unwrap();
+        write_frame(&mut stream, 0, &0u32.to_le_bytes())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn receives_published_block() {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .await
+            .unwrap();
+        let address = listener.local_addr().unwrap();
+        let block = genesis_block(Network::Regtest);
+        tokio::spawn(publish(listener, block.clone()));
+
+        let mut subscriber = RawBlockSubscriber::connect(address).await.unwrap();
+
+        assert_eq!(subscriber.next_block().await.unwrap(), block);
+    }
+}
@@ -1,16 +1,20 @@
 mod cache;
+mod scan_logs;
+mod wait_for_confirmations;
 mod watch_for_contract_creation;
 mod watch_for_event;
 mod web3_connector;
 
 pub use self::{
     cache::Cache,
+    scan_logs::scan_logs,
+    wait_for_confirmations::{confirmations_up_to, wait_for_confirmations},
     watch_for_contract_creation::{matching_transaction_and_receipt, watch_for_contract_creation},
     watch_for_event::watch_for_event,
     web3_connector::Web3Connector,
 };
 use crate::{
-    btsieve::{BlockHash, ConnectedNetwork, Predates, PreviousBlockHash},
+    btsieve::{BlockHash, ConnectedNetwork, PollingPolicy, Predates, PreviousBlockHash},
     ethereum::{Address, Block, ChainId, Hash, Log, Transaction, TransactionReceipt, U256},
 };
 use anyhow::Result;
@@ -30,7 +34,21 @@ pub trait TransactionByHash: Send + Sync + 'static {
 
 #[async_trait]
 pub trait GetLogs: Send + Sync + 'static {
-    async fn get_logs(&self, event: Event) -> Result<Vec<Log>>;
+    /// The logs emitted by `address` matching `topics` within the blocks
+    /// `from_block` to `to_block` (inclusive), filtered by the node.
+    async fn get_logs_for(
+        &self,
+        address: Address,
+        topics: Vec<Option<Hash>>,
+        from_block: U256,
+        to_block: U256,
+    ) -> Result<Vec<Log>>;
+
+    /// The most blocks the node answers `eth_getLogs` for at once, `None` if
+    /// it is only limited by the number of logs it returns.
+    fn max_block_range(&self) -> Option<u64> {
+        None
+    }
 }
 
 impl BlockHash for Block {
@@ -65,15 +83,19 @@ pub struct Event {
     pub topics: Vec<Option<Hash>>,
 }
 
-async fn poll_interval<C>(connector: &C) -> Result<Duration>
+async fn polling_policy<C>(connector: &C) -> Result<PollingPolicy>
 where
     C: ConnectedNetwork<Network = ChainId>,
 {
+    if let Some(policy) = connector.polling_policy() {
+        return Ok(policy);
+    }
+
     let network = connector.connected_network().await?;
     let seconds = match network {
         ChainId::GETH_DEV => 1,
         _ => 10,
     };
 
-    Ok(Duration::from_secs(seconds))
+    Ok(PollingPolicy::new(Duration::from_secs(seconds)))
 }
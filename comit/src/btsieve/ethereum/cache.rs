@@ -1,9 +1,9 @@
 use crate::{
     btsieve::{
-        ethereum::{self, Event, GetLogs, Hash, ReceiptByHash, TransactionByHash},
-        BlockByHash, ConnectedNetwork, LatestBlock,
+        ethereum::{self, GetLogs, Hash, ReceiptByHash, TransactionByHash},
+        BlockByHash, ConnectedNetwork, LatestBlock, MempoolTransactions, PollingPolicy,
     },
-    ethereum::{ChainId, Log, Transaction, TransactionReceipt},
+    ethereum::{Address, ChainId, Log, Transaction, TransactionReceipt},
 };
 use anyhow::Result;
 use async_trait::async_trait;
@@ -26,10 +26,13 @@ pub struct Cache<C> {
     #[derivative(Debug = "ignore")]
     pub receipt_cache: Arc<Mutex<LruCache<Hash, TransactionReceipt>>>,
     #[derivative(Debug = "ignore")]
+    pub transaction_cache: Arc<Mutex<LruCache<Hash, Transaction>>>,
+    #[derivative(Debug = "ignore")]
     pub connected_network_cache: Arc<Mutex<Option<ChainId>>>,
 }
 
 impl<C> Cache<C> {
+    /// The transaction cache has the same capacity as the receipt cache.
     pub fn new(
         connector: C,
         block_cache_capacity: usize,
@@ -37,12 +40,14 @@ impl<C> Cache<C> {
     ) -> Cache<C> {
         let block_cache = Arc::new(Mutex::new(LruCache::new(block_cache_capacity)));
         let receipt_cache = Arc::new(Mutex::new(LruCache::new(receipt_cache_capacity)));
+        let transaction_cache = Arc::new(Mutex::new(LruCache::new(receipt_cache_capacity)));
         let connected_network_cache = Arc::new(Mutex::new(None));
 
         Cache {
             connector,
             block_cache,
             receipt_cache,
+            transaction_cache,
             connected_network_cache,
         }
     }
@@ -103,6 +108,11 @@ where
 
         let receipt = self.connector.receipt_by_hash(transaction_hash).await?;
 
+        // A receipt is only final once the transaction is included in a block.
+        if receipt.block_number.is_none() {
+            return Ok(receipt);
+        }
+
         // We dropped the lock so at this stage the receipt may have been inserted by
         // another thread, no worries, inserting the same receipt twice does not hurt.
         let mut guard = self.receipt_cache.lock().await;
@@ -129,6 +139,10 @@ where
 
         Ok(network)
     }
+
+    fn polling_policy(&self) -> Option<PollingPolicy> {
+        self.connector.polling_policy()
+    }
 }
 
 #[async_trait]
@@ -136,8 +150,20 @@ impl<C> GetLogs for Cache<C>
 where
     C: GetLogs,
 {
-    async fn get_logs(&self, event: Event) -> anyhow::Result<Vec<Log>> {
-        self.connector.get_logs(event).await
+    async fn get_logs_for(
+        &self,
+        address: Address,
+        topics: Vec<Option<Hash>>,
+        from_block: U256,
+        to_block: U256,
+    ) -> anyhow::Result<Vec<Log>> {
+        self.connector
+            .get_logs_for(address, topics, from_block, to_block)
+            .await
+    }
+
+    fn max_block_range(&self) -> Option<u64> {
+        self.connector.max_block_range()
     }
 }
 
@@ -147,6 +173,242 @@ where
     C: TransactionByHash,
 {
     async fn transaction_by_hash(&self, transaction_hash: Hash) -> anyhow::Result<Transaction> {
-        self.connector.transaction_by_hash(transaction_hash).await
+        if let Some(transaction) = self.transaction_cache.lock().await.get(&transaction_hash) {
+            return Ok(transaction.clone());
+        }
+
+        // The content of a transaction is committed to by its hash, it is safe to
+        // cache it even if it is not yet included in a block.
+        let transaction = self.connector.transaction_by_hash(transaction_hash).await?;
+
+        let mut guard = self.transaction_cache.lock().await;
+        guard.put(transaction_hash, transaction.clone());
+
+        Ok(transaction)
+    }
+}
+
+#[async_trait]
+impl<C> MempoolTransactions for Cache<C>
+where
+    C: MempoolTransactions<Transaction = Transaction>,
+{
+    type Transaction = Transaction;
+
+    async fn mempool_transactions(&self) -> Result<Vec<Self::Transaction>> {
+        self.connector.mempool_transactions().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Connector that only knows the blocks it was given and counts how often
+    /// it is called.
+    #[derive(Debug, Clone, Default)]
+    struct CountingConnector {
+        blocks: Arc<Mutex<Vec<Block>>>,
+        receipts: Arc<Mutex<Vec<(Hash, TransactionReceipt)>>>,
+        block_by_hash_calls: Arc<AtomicUsize>,
+        receipt_by_hash_calls: Arc<AtomicUsize>,
+        connected_network_calls: Arc<AtomicUsize>,
+    }
+
+    impl CountingConnector {
+        async fn mine(&self, block: Block) {
+            self.blocks.lock().await.push(block);
+        }
+
+        async fn set_receipt(&self, transaction_hash: Hash, receipt: TransactionReceipt) {
+            let mut receipts = self.receipts.lock().await;
+            receipts.retain(|(hash, _)| *hash != transaction_hash);
+            receipts.push((transaction_hash, receipt));
+        }
+
+        fn receipt_by_hash_calls(&self) -> usize {
+            self.receipt_by_hash_calls.load(Ordering::SeqCst)
+        }
+
+        fn block_by_hash_calls(&self) -> usize {
+            self.block_by_hash_calls.load(Ordering::SeqCst)
+        }
+
+        fn connected_network_calls(&self) -> usize {
+            self.connected_network_calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl LatestBlock for CountingConnector {
+        type Block = Block;
+
+        async fn latest_block(&self) -> Result<Self::Block> {
+            self.blocks
+                .lock()
+                .await
+                .last()
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("no blocks"))
+        }
+    }
+
+    #[async_trait]
+    impl BlockByHash for CountingConnector {
+        type Block = Block;
+        type BlockHash = Hash;
+
+        async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block> {
+            self.block_by_hash_calls.fetch_add(1, Ordering::SeqCst);
+
+            self.blocks
+                .lock()
+                .await
+                .iter()
+                .find(|block| block.hash == block_hash)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("block {:?} not found", block_hash))
+        }
+    }
+
+    #[async_trait]
+    impl ReceiptByHash for CountingConnector {
+        async fn receipt_by_hash(&self, transaction_hash: Hash) -> Result<TransactionReceipt> {
+            self.receipt_by_hash_calls.fetch_add(1, Ordering::SeqCst);
+
+            self.receipts
+                .lock()
+                .await
+                .iter()
+                .find(|(hash, _)| *hash == transaction_hash)
+                .map(|(_, receipt)| receipt.clone())
+                .ok_or_else(|| anyhow::anyhow!("receipt {:?} not found", transaction_hash))
+        }
+    }
+
+    #[async_trait]
+    impl ConnectedNetwork for CountingConnector {
+        type Network = ChainId;
+
+        async fn connected_network(&self) -> Result<Self::Network> {
+            self.connected_network_calls.fetch_add(1, Ordering::SeqCst);
+
+            Ok(ChainId::GETH_DEV)
+        }
+    }
+
+    fn block(number: u8) -> Block {
+        Block {
+            hash: Hash::from([number; 32]),
+            parent_hash: Hash::from([number.wrapping_sub(1); 32]),
+            ..Block::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn block_is_fetched_from_connector_only_once() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10, 10);
+        let block = block(1);
+        connector.mine(block.clone()).await;
+
+        for _ in 0..5 {
+            let cached = cache.block_by_hash(block.hash).await.unwrap();
+            assert_eq!(cached, block);
+        }
+
+        assert_eq!(connector.block_by_hash_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn latest_block_is_cached() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10, 10);
+        let block = block(1);
+        connector.mine(block.clone()).await;
+
+        let latest_block = cache.latest_block().await.unwrap();
+        let cached = cache.block_by_hash(latest_block.hash).await.unwrap();
+
+        assert_eq!(cached, block);
+        assert_eq!(connector.block_by_hash_calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_block_is_evicted() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 2, 10);
+        for number in 1..=3 {
+            connector.mine(block(number)).await;
+        }
+
+        for number in 1..=3 {
+            cache.block_by_hash(block(number).hash).await.unwrap();
+        }
+        cache.block_by_hash(block(1).hash).await.unwrap();
+
+        assert_eq!(connector.block_by_hash_calls(), 4);
+    }
+
+    #[tokio::test]
+    async fn connected_network_is_fetched_from_connector_only_once() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10, 10);
+
+        for _ in 0..5 {
+            let network = cache.connected_network().await.unwrap();
+            assert_eq!(network, ChainId::GETH_DEV);
+        }
+
+        assert_eq!(connector.connected_network_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn confirmed_receipt_is_fetched_from_connector_only_once() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10, 10);
+        let transaction_hash = Hash::from([1u8; 32]);
+        let receipt = TransactionReceipt {
+            successful: true,
+            block_number: Some(U256::from(42)),
+            ..TransactionReceipt::default()
+        };
+        connector
+            .set_receipt(transaction_hash, receipt.clone())
+            .await;
+
+        for _ in 0..5 {
+            let cached = cache.receipt_by_hash(transaction_hash).await.unwrap();
+            assert_eq!(cached, receipt);
+        }
+
+        assert_eq!(connector.receipt_by_hash_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn pending_receipt_is_not_cached() {
+        let connector = CountingConnector::default();
+        let cache = Cache::new(connector.clone(), 10, 10);
+        let transaction_hash = Hash::from([1u8; 32]);
+        connector
+            .set_receipt(transaction_hash, TransactionReceipt::default())
+            .await;
+
+        let pending = cache.receipt_by_hash(transaction_hash).await.unwrap();
+        assert_eq!(pending.block_number, None);
+
+        let confirmed = TransactionReceipt {
+            successful: true,
+            block_number: Some(U256::from(42)),
+            ..TransactionReceipt::default()
+        };
+        connector
+            .set_receipt(transaction_hash, confirmed.clone())
+            .await;
+
+        let receipt = cache.receipt_by_hash(transaction_hash).await.unwrap();
+        assert_eq!(receipt, confirmed);
+        assert_eq!(connector.receipt_by_hash_calls(), 2);
     }
 }
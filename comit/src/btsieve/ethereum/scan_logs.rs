@@ -0,0 +1,206 @@
+use crate::{
+    btsieve::ethereum::GetLogs,
+    ethereum::{Address, Hash, Log, U256},
+};
+use anyhow::Result;
+
+/// Fetches the logs of the blocks `from_block` to `to_block` (inclusive) in
+/// windows of at most `window` blocks, or all at once if there is no limit.
+///
+/// If the node refuses a window because it holds too many logs, the window
+/// is halved and requested again. The narrowed window is kept for the rest of
+/// the scan.
+pub async fn scan_logs<C>(
+    connector: &C,
+    address: Address,
+    topics: &[Option<Hash>],
+    from_block: U256,
+    to_block: U256,
+    window: Option<u64>,
+) -> Result<Vec<Log>>
+where
+    C: GetLogs,
+{
+    let mut window = window.map_or_else(
+        || {
+            to_block
+                .saturating_sub(from_block)
+                .saturating_add(U256::one())
+        },
+        |window| U256::from(window.max(1)),
+    );
+    let mut logs = Vec::new();
+    let mut start = from_block;
+
+    while start <= to_block {
+        let end = std::cmp::min(start.saturating_add(window - 1), to_block);
+
+        match connector
+            .get_logs_for(address, topics.to_vec(), start, end)
+            .await
+        {
+            Ok(window_logs) => {
+                logs.extend(window_logs);
+
+                if end == to_block {
+                    break;
+                }
+                start = end + 1;
+            }
+            Err(e) if is_too_many_results(&e) && window > U256::one() => {
+                window /= 2;
+                tracing::debug!(
+                    "node refused logs of blocks {} to {}, narrowing window to {} blocks",
+                    start,
+                    end,
+                    window
+                );
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(logs)
+}
+
+/// Whether the node refused to answer because the range holds too many logs,
+/// e.g. Infura answers with "query returned more than 10000 results".
+fn is_too_many_results(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .any(|cause| cause.to_string().contains("query returned more than"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Has a log in every block but refuses to return more than `limit` logs
+    /// at once.
+    struct LimitedNode {
+        limit: u64,
+        requests: Mutex<Vec<(U256, U256)>>,
+    }
+
+    #[async_trait]
+    impl GetLogs for LimitedNode {
+        async fn get_logs_for(
+            &self,
+            address: Address,
+            _: Vec<Option<Hash>>,
+            from_block: U256,
+            to_block: U256,
+        ) -> Result<Vec<Log>> {
+            self.requests.lock().unwrap().push((from_block, to_block));
+
+            if to_block - from_block + 1 > U256::from(self.limit) {
+                anyhow::bail!("query returned more than {} results", self.limit)
+            }
+
+            let logs = (from_block.low_u64()..=to_block.low_u64())
+                .map(|number| Log {
+                    address,
+                    topics: vec![],
+                    data: Default::default(),
+                    transaction_hash: hash(number),
+                })
+                .collect();
+
+            Ok(logs)
+        }
+    }
+
+    fn hash(number: u64) -> Hash {
+        let mut bytes = [0u8; 32];
+        bytes[24..].copy_from_slice(&number.to_be_bytes());
+
+        Hash::from(bytes)
+    }
+
+    #[tokio::test]
+    async fn narrows_window_until_node_answers() {
+        let node = LimitedNode {
+            limit: 300,
+            requests: Mutex::new(Vec::new()),
+        };
+
+        let logs = scan_logs(
+            &node,
+            Address::random(),
+            &[],
+            U256::from(1),
+            U256::from(1000),
+            Some(1000),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(logs.len(), 1000);
+        assert_eq!(logs[999].transaction_hash, hash(1000));
+        assert_eq!(*node.requests.lock().unwrap(), vec![
+            (U256::from(1), U256::from(1000)),
+            (U256::from(1), U256::from(500)),
+            (U256::from(1), U256::from(250)),
+            (U256::from(251), U256::from(500)),
+            (U256::from(501), U256::from(750)),
+            (U256::from(751), U256::from(1000)),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn without_limit_the_whole_range_is_requested_at_once() {
+        let node = LimitedNode {
+            limit: 5000,
+            requests: Mutex::new(Vec::new()),
+        };
+
+        let logs = scan_logs(
+            &node,
+            Address::random(),
+            &[],
+            U256::zero(),
+            U256::from(4999),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(logs.len(), 5000);
+        assert_eq!(*node.requests.lock().unwrap(), vec![(
+            U256::zero(),
+            U256::from(4999)
+        )]);
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_not_retried() {
+        struct BrokenNode;
+
+        #[async_trait]
+        impl GetLogs for BrokenNode {
+            async fn get_logs_for(
+                &self,
+                _: Address,
+                _: Vec<Option<Hash>>,
+                _: U256,
+                _: U256,
+            ) -> Result<Vec<Log>> {
+                anyhow::bail!("connection refused")
+            }
+        }
+
+        let result = scan_logs(
+            &BrokenNode,
+            Address::random(),
+            &[],
+            U256::zero(),
+            U256::from(10),
+            None,
+        )
+        .await;
+
+        assert!(result.is_err());
+    }
+}
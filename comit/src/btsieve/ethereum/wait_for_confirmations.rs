@@ -0,0 +1,333 @@
+use crate::{
+    btsieve::{
+        ethereum::polling_policy, Backoff, BlockByHash, ConnectedNetwork, LatestBlock,
+        PollingPolicy, Predates,
+    },
+    ethereum::{Block, ChainId, Hash},
+};
+use anyhow::Result;
+use time::OffsetDateTime;
+
+/// Waits until the block including `transaction` is buried under enough
+/// blocks to count `min_confirmations`, the including block being the first
+/// confirmation.
+///
+/// Confirmations are counted on the current chain every time, hence a reorg
+/// that orphans the transaction resets the count.
+pub async fn wait_for_confirmations<C>(
+    connector: &C,
+    transaction: Hash,
+    start_of_swap: OffsetDateTime,
+    min_confirmations: u32,
+) -> Result<()>
+where
+    C: LatestBlock<Block = Block>
+        + BlockByHash<Block = Block, BlockHash = Hash>
+        + ConnectedNetwork<Network = ChainId>,
+{
+    let polling_policy = polling_policy(connector).await?;
+
+    wait(
+        connector,
+        transaction,
+        start_of_swap,
+        min_confirmations,
+        polling_policy,
+    )
+    .await
+}
+
+async fn wait<C>(
+    connector: &C,
+    transaction: Hash,
+    start_of_swap: OffsetDateTime,
+    min_confirmations: u32,
+    polling_policy: PollingPolicy,
+) -> Result<()>
+where
+    C: LatestBlock<Block = Block> + BlockByHash<Block = Block, BlockHash = Hash>,
+{
+    let mut backoff = Backoff::new(polling_policy);
+    let mut last_tip = None;
+
+    loop {
+        let (tip, confirmations) =
+            confirmations_at_tip(connector, transaction, start_of_swap).await?;
+
+        if confirmations >= min_confirmations {
+            return Ok(());
+        }
+
+        tracing::debug!(
+            "transaction {} has {} out of {} confirmations",
+            transaction,
+            confirmations,
+            min_confirmations
+        );
+
+        let new_block = last_tip != Some(tip);
+        last_tip = Some(tip);
+
+        backoff.wait(new_block).await;
+    }
+}
+
+/// Walks the current chain back from its tip until it finds the block
+/// including `transaction`, looking at no more than `max_depth` blocks.
+///
+/// Returns zero if no block since the start of the swap includes it, e.g.
+/// because a reorg orphaned it. A transaction that is not found within
+/// `max_depth` blocks is assumed to be buried at least that deep, hence
+/// `max_depth` is returned.
+pub async fn confirmations_up_to<C>(
+    connector: &C,
+    transaction: Hash,
+    start_of_swap: OffsetDateTime,
+    max_depth: u32,
+) -> Result<u32>
+where
+    C: LatestBlock<Block = Block> + BlockByHash<Block = Block, BlockHash = Hash>,
+{
+    let (_, confirmations) =
+        walk_from_tip(connector, transaction, start_of_swap, max_depth).await?;
+
+    Ok(confirmations)
+}
+
+/// The confirmations of `transaction` together with the tip they were counted
+/// from.
+async fn confirmations_at_tip<C>(
+    connector: &C,
+    transaction: Hash,
+    start_of_swap: OffsetDateTime,
+) -> Result<(Hash, u32)>
+where
+    C: LatestBlock<Block = Block> + BlockByHash<Block = Block, BlockHash = Hash>,
+{
+    walk_from_tip(connector, transaction, start_of_swap, u32::MAX).await
+}
+
+async fn walk_from_tip<C>(
+    connector: &C,
+    transaction: Hash,
+    start_of_swap: OffsetDateTime,
+    max_depth: u32,
+) -> Result<(Hash, u32)>
+where
+    C: LatestBlock<Block = Block> + BlockByHash<Block = Block, BlockHash = Hash>,
+{
+    let mut block = connector.latest_block().await?;
+    let tip = block.hash;
+    let mut depth = 1;
+
+    loop {
+        if block.transactions.iter().any(|tx| tx.hash == transaction) {
+            return Ok((tip, depth));
+        }
+
+        if block.predates(start_of_swap) {
+            return Ok((tip, 0));
+        }
+
+        if depth >= max_depth {
+            return Ok((tip, max_depth));
+        }
+
+        block = connector.block_by_hash(block.parent_hash).await?;
+        depth += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::{Transaction, U256};
+    use async_trait::async_trait;
+    use std::{
+        collections::{HashMap, VecDeque},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+        time::Duration,
+    };
+
+    /// Connector that presents the given tips one after the other as the
+    /// latest block.
+    #[derive(Debug, Default)]
+    struct ScriptedConnector {
+        blocks: Mutex<HashMap<Hash, Block>>,
+        tips: Mutex<VecDeque<Hash>>,
+        latest_block_calls: AtomicUsize,
+    }
+
+    impl ScriptedConnector {
+        fn add(&self, block: Block) {
+            self.blocks.lock().unwrap().insert(block.hash, block);
+        }
+
+        fn script(&self, tips: Vec<Hash>) {
+            self.tips.lock().unwrap().extend(tips);
+        }
+
+        fn latest_block_calls(&self) -> usize {
+            self.latest_block_calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl LatestBlock for ScriptedConnector {
+        type Block = Block;
+
+        async fn latest_block(&self) -> Result<Self::Block> {
+            self.latest_block_calls.fetch_add(1, Ordering::SeqCst);
+
+            let tip = self
+                .tips
+                .lock()
+                .unwrap()
+                .pop_front()
+                .ok_or_else(|| anyhow::anyhow!("no more tips"))?;
+
+            self.block_by_hash(tip).await
+        }
+    }
+
+    #[async_trait]
+    impl BlockByHash for ScriptedConnector {
+        type Block = Block;
+        type BlockHash = Hash;
+
+        async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block> {
+            self.blocks
+                .lock()
+                .unwrap()
+                .get(&block_hash)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("block {} not found", block_hash))
+        }
+    }
+
+    fn block(hash: u8, parent: &Block, transactions: Vec<Hash>) -> Block {
+        Block {
+            hash: Hash::from([hash; 32]),
+            parent_hash: parent.hash,
+            timestamp: U256::from(2_000),
+            transactions: transactions
+                .into_iter()
+                .map(|hash| Transaction {
+                    hash,
+                    ..Transaction::default()
+                })
+                .collect(),
+            ..Block::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn reorg_that_orphans_the_transaction_resets_the_count() {
+        let start_of_swap = OffsetDateTime::from_unix_timestamp(1_000);
+        let funding = Hash::from([0xf0; 32]);
+        let connector = ScriptedConnector::default();
+
+        let genesis = Block {
+            hash: Hash::from([0xff; 32]),
+            timestamp: U256::from(500),
+            ..Block::default()
+        };
+        let a1 = block(0xa1, &genesis, vec![funding]);
+        let a2 = block(0xa2, &a1, vec![]);
+        // the fork that replaces a1 and a2 does not include the transaction at first
+        let b1 = block(0xb1, &genesis, vec![]);
+        let b2 = block(0xb2, &b1, vec![]);
+        let b3 = block(0xb3, &b2, vec![funding]);
+        let b4 = block(0xb4, &b3, vec![]);
+        let b5 = block(0xb5, &b4, vec![]);
+
+        connector.script(vec![a1.hash, a2.hash, b2.hash, b3.hash, b4.hash, b5.hash]);
+        for block in vec![genesis, a1, a2, b1, b2, b3, b4, b5] {
+            connector.add(block);
+        }
+
+        wait(
+            &connector,
+            funding,
+            start_of_swap,
+            3,
+            PollingPolicy::new(Duration::from_secs(0)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(connector.latest_block_calls(), 6);
+    }
+
+    #[tokio::test]
+    async fn walk_stops_at_max_depth() {
+        let start_of_swap = OffsetDateTime::from_unix_timestamp(1_000);
+        let funding = Hash::from([0xf0; 32]);
+        let orphaned = Hash::from([0xf1; 32]);
+        let connector = ScriptedConnector::default();
+
+        let genesis = Block {
+            hash: Hash::from([0xff; 32]),
+            timestamp: U256::from(500),
+            ..Block::default()
+        };
+        let a1 = block(0xa1, &genesis, vec![funding]);
+        let a2 = block(0xa2, &a1, vec![]);
+        let a3 = block(0xa3, &a2, vec![]);
+
+        connector.script(vec![a3.hash, a3.hash, a3.hash]);
+        for block in vec![genesis, a1, a2, a3] {
+            connector.add(block);
+        }
+
+        let buried = confirmations_up_to(&connector, funding, start_of_swap, 2)
+            .await
+            .unwrap();
+        let found = confirmations_up_to(&connector, funding, start_of_swap, 5)
+            .await
+            .unwrap();
+        let missing = confirmations_up_to(&connector, orphaned, start_of_swap, 5)
+            .await
+            .unwrap();
+
+        assert_eq!(buried, 2);
+        assert_eq!(found, 3);
+        assert_eq!(missing, 0);
+    }
+
+    #[tokio::test]
+    async fn returns_once_buried_deep_enough() {
+        let start_of_swap = OffsetDateTime::from_unix_timestamp(1_000);
+        let funding = Hash::from([0xf0; 32]);
+        let connector = ScriptedConnector::default();
+
+        let genesis = Block {
+            hash: Hash::from([0xff; 32]),
+            timestamp: U256::from(500),
+            ..Block::default()
+        };
+        let a1 = block(0xa1, &genesis, vec![]);
+        let a2 = block(0xa2, &a1, vec![funding]);
+        let a3 = block(0xa3, &a2, vec![]);
+
+        connector.script(vec![a2.hash, a3.hash]);
+        for block in vec![genesis, a1, a2, a3] {
+            connector.add(block);
+        }
+
+        wait(
+            &connector,
+            funding,
+            start_of_swap,
+            2,
+            PollingPolicy::new(Duration::from_secs(0)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(connector.latest_block_calls(), 2);
+    }
+}
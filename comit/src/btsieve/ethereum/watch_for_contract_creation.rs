@@ -1,7 +1,7 @@
 use crate::{
     btsieve::{
-        ethereum::{poll_interval, ReceiptByHash},
-        fetch_blocks_since, BlockByHash, ConnectedNetwork, LatestBlock,
+        ethereum::{polling_policy, ReceiptByHash},
+        fetch_blocks_since, BlockByHash, Checkpoints, ConnectedNetwork, LatestBlock,
     },
     ethereum::{Address, Block, ChainId, Hash, Transaction, TransactionReceipt},
 };
@@ -10,8 +10,9 @@ use genawaiter::GeneratorState;
 use time::OffsetDateTime;
 use tracing_futures::Instrument;
 
-pub async fn watch_for_contract_creation<C>(
+pub async fn watch_for_contract_creation<C, K>(
     connector: &C,
+    checkpoints: &K,
     start_of_swap: OffsetDateTime,
     expected_bytecode: &[u8],
 ) -> Result<(Transaction, Address)>
@@ -20,9 +21,10 @@ where
         + BlockByHash<Block = Block, BlockHash = Hash>
         + ReceiptByHash
         + ConnectedNetwork<Network = ChainId>,
+    K: Checkpoints<Hash>,
 {
     let (transaction, receipt) =
-        matching_transaction_and_receipt(connector, start_of_swap, |transaction| {
+        matching_transaction_and_receipt(connector, checkpoints, start_of_swap, |transaction| {
             // transaction.to address is None if, and only if, the transaction
             // creates a contract.
 
@@ -60,8 +62,9 @@ where
     }
 }
 
-pub async fn matching_transaction_and_receipt<C, F>(
+pub async fn matching_transaction_and_receipt<C, K, F>(
     connector: &C,
+    checkpoints: &K,
     start_of_swap: OffsetDateTime,
     matcher: F,
 ) -> Result<(Transaction, TransactionReceipt)>
@@ -70,10 +73,12 @@ where
         + BlockByHash<Block = Block, BlockHash = Hash>
         + ReceiptByHash
         + ConnectedNetwork<Network = ChainId>,
+    K: Checkpoints<Hash>,
     F: Fn(&Transaction) -> bool + Clone,
 {
-    let poll_interval = poll_interval(connector).await?;
-    let mut block_generator = fetch_blocks_since(connector, start_of_swap, poll_interval);
+    let polling_policy = polling_policy(connector).await?;
+    let mut block_generator =
+        fetch_blocks_since(connector, checkpoints, start_of_swap, polling_policy);
 
     loop {
         match block_generator.async_resume().await {
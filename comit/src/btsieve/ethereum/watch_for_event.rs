@@ -1,13 +1,17 @@
 use crate::{
     btsieve::{
-        ethereum::{poll_interval, Event, GetLogs, ReceiptByHash, TransactionByHash},
-        BlockByHash, ConnectedNetwork, LatestBlock,
+        ethereum::{polling_policy, scan_logs, Event, GetLogs, ReceiptByHash, TransactionByHash},
+        Backoff, BlockByHash, ConnectedNetwork, LatestBlock,
     },
-    ethereum::{Block, ChainId, Hash, Log, Transaction},
+    ethereum::{Block, ChainId, Hash, Log, Transaction, U256},
 };
 use anyhow::Result;
 use time::OffsetDateTime;
 
+/// The logs of this many blocks below the tip are fetched again, in case a
+/// reorg replaced them.
+const REORG_MARGIN: u64 = 12;
+
 pub async fn watch_for_event<C>(
     connector: &C,
     _start_of_swap: OffsetDateTime,
@@ -21,18 +25,43 @@ where
         + ConnectedNetwork<Network = ChainId>
         + GetLogs,
 {
-    let poll_interval = poll_interval(connector).await?;
+    let mut backoff = Backoff::new(polling_policy(connector).await?);
+    let mut last_seen_block = None;
+    // Ideally, we would start at the block of start-of-swap. However, the node
+    // filters by the address of the HTLC which has no logs before the swap,
+    // hence scanning from 0 in a single request is reasonably performant (~
+    // 1sec for Infura). Subsequent calls only fetch the most recent blocks.
+    let mut from_block = U256::zero();
 
     loop {
-        let logs = connector.get_logs(expected_event.clone()).await?;
+        // the logs can only change with a new block, checking for one is much
+        // cheaper than fetching the logs
+        let latest_block = connector.latest_block().await?;
+        let new_block = last_seen_block != Some(latest_block.hash);
+
+        if new_block {
+            let to_block = latest_block.number;
+            let logs = scan_logs(
+                connector,
+                expected_event.address,
+                &expected_event.topics,
+                std::cmp::min(from_block, to_block),
+                to_block,
+                connector.max_block_range(),
+            )
+            .await?;
+
+            if let Some(log) = find_log_for_event(&expected_event, logs) {
+                let tx = connector.transaction_by_hash(log.transaction_hash).await?;
 
-        if let Some(log) = find_log_for_event(&expected_event, logs) {
-            let tx = connector.transaction_by_hash(log.transaction_hash).await?;
+                return Ok((tx, log));
+            }
 
-            return Ok((tx, log));
+            last_seen_block = Some(latest_block.hash);
+            from_block = (to_block + 1).saturating_sub(U256::from(REORG_MARGIN));
         }
 
-        tokio::time::delay_for(poll_interval).await;
+        backoff.wait(new_block).await;
     }
 }
 
@@ -55,3 +84,153 @@ fn find_log_for_event(event: &Event, logs: Vec<Log>) -> Option<Log> {
         }),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::{Address, TransactionReceipt};
+    use async_trait::async_trait;
+    use std::{collections::VecDeque, sync::Mutex};
+
+    type Filter = (Address, Vec<Option<Hash>>, U256, U256);
+
+    /// Node that presents the given tips one after the other and filters its
+    /// logs like `eth_getLogs` does.
+    #[derive(Debug)]
+    struct FilteringNode {
+        tips: Mutex<VecDeque<Block>>,
+        /// The logs together with the number of the block they are in.
+        logs: Vec<(U256, Log)>,
+        filters: Mutex<Vec<Filter>>,
+    }
+
+    #[async_trait]
+    impl LatestBlock for FilteringNode {
+        type Block = Block;
+
+        async fn latest_block(&self) -> Result<Self::Block> {
+            self.tips
+                .lock()
+                .unwrap()
+                .pop_front()
+                .ok_or_else(|| anyhow::anyhow!("no more tips"))
+        }
+    }
+
+    #[async_trait]
+    impl BlockByHash for FilteringNode {
+        type Block = Block;
+        type BlockHash = Hash;
+
+        async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block> {
+            anyhow::bail!("block {} not found", block_hash)
+        }
+    }
+
+    #[async_trait]
+    impl ReceiptByHash for FilteringNode {
+        async fn receipt_by_hash(&self, transaction_hash: Hash) -> Result<TransactionReceipt> {
+            anyhow::bail!("receipt {} not found", transaction_hash)
+        }
+    }
+
+    #[async_trait]
+    impl TransactionByHash for FilteringNode {
+        async fn transaction_by_hash(&self, transaction_hash: Hash) -> Result<Transaction> {
+            Ok(Transaction {
+                hash: transaction_hash,
+                ..Transaction::default()
+            })
+        }
+    }
+
+    #[async_trait]
+    impl ConnectedNetwork for FilteringNode {
+        type Network = ChainId;
+
+        async fn connected_network(&self) -> Result<ChainId> {
+            Ok(ChainId::GETH_DEV)
+        }
+    }
+
+    #[async_trait]
+    impl GetLogs for FilteringNode {
+        async fn get_logs_for(
+            &self,
+            address: Address,
+            topics: Vec<Option<Hash>>,
+            from_block: U256,
+            to_block: U256,
+        ) -> Result<Vec<Log>> {
+            self.filters
+                .lock()
+                .unwrap()
+                .push((address, topics.clone(), from_block, to_block));
+
+            let logs = self
+                .logs
+                .iter()
+                .filter(|(number, _)| (from_block..=to_block).contains(number))
+                .map(|(_, log)| log)
+                .filter(|log| log.address == address)
+                .filter(|log| {
+                    topics
+                        .iter()
+                        .zip(log.topics.iter())
+                        .all(|(expected, actual)| expected.map_or(true, |topic| topic == *actual))
+                })
+                .cloned()
+                .collect();
+
+            Ok(logs)
+        }
+    }
+
+    fn block(number: u8) -> Block {
+        Block {
+            hash: Hash::from([number; 32]),
+            number: U256::from(number),
+            ..Block::default()
+        }
+    }
+
+    fn log(address: Address, topic: Hash, transaction: u8) -> Log {
+        Log {
+            address,
+            topics: vec![topic],
+            data: Default::default(),
+            transaction_hash: Hash::from([transaction; 32]),
+        }
+    }
+
+    #[tokio::test]
+    async fn filter_is_forwarded_to_the_node() {
+        let htlc = Address::from([1u8; 20]);
+        let redeem = Hash::from([0xaa; 32]);
+        let refund = Hash::from([0xbb; 32]);
+        let node = FilteringNode {
+            tips: Mutex::new(vec![block(20), block(25)].into()),
+            logs: vec![
+                (U256::from(24), log(Address::from([2u8; 20]), redeem, 1)),
+                (U256::from(24), log(htlc, refund, 2)),
+                (U256::from(25), log(htlc, redeem, 3)),
+            ],
+            filters: Mutex::default(),
+        };
+        let event = Event {
+            address: htlc,
+            topics: vec![Some(redeem)],
+        };
+
+        let (transaction, log) = watch_for_event(&node, OffsetDateTime::now_utc(), event)
+            .await
+            .unwrap();
+
+        assert_eq!(transaction.hash, Hash::from([3u8; 32]));
+        assert_eq!(log.transaction_hash, Hash::from([3u8; 32]));
+        assert_eq!(*node.filters.lock().unwrap(), vec![
+            (htlc, vec![Some(redeem)], U256::from(0), U256::from(20)),
+            (htlc, vec![Some(redeem)], U256::from(9), U256::from(25)),
+        ]);
+    }
+}
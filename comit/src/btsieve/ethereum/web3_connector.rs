@@ -1,22 +1,45 @@
 use crate::{
     btsieve::{
-        ethereum::{Event, GetLogs, ReceiptByHash, TransactionByHash},
-        jsonrpc, BlockByHash, ConnectedNetwork, LatestBlock,
+        ethereum::{GetLogs, ReceiptByHash, TransactionByHash},
+        jsonrpc, BlockByHash, ConnectedNetwork, LatestBlock, MempoolTransactions, PollingPolicy,
     },
-    ethereum::{ChainId, Hash, Log, Transaction, TransactionReceipt},
+    ethereum::{Address, ChainId, Hash, Log, Transaction, TransactionReceipt, U256},
 };
 use anyhow::Result;
 use async_trait::async_trait;
+use serde::Deserialize;
 
 #[derive(Debug)]
 pub struct Web3Connector {
     client: jsonrpc::Client,
+    max_block_range: Option<u64>,
+    polling_policy: Option<PollingPolicy>,
 }
 
 impl Web3Connector {
     pub fn new(node_url: reqwest::Url) -> Self {
         Self {
             client: jsonrpc::Client::new(node_url),
+            max_block_range: None,
+            polling_policy: None,
+        }
+    }
+
+    /// Limits the number of blocks whose logs are requested at once, for nodes
+    /// that refuse large ranges regardless of how many logs they hold.
+    pub fn with_max_block_range(self, max_block_range: Option<u64>) -> Self {
+        Self {
+            max_block_range,
+            ..self
+        }
+    }
+
+    /// Poll the node according to `polling_policy` instead of the default for
+    /// the chain it is connected to.
+    pub fn with_polling_policy(self, polling_policy: Option<PollingPolicy>) -> Self {
+        Self {
+            polling_policy,
+            ..self
         }
     }
 
@@ -65,6 +88,30 @@ impl BlockByHash for Web3Connector {
     }
 }
 
+/// The block the node is assembling from its pending transactions, it has no
+/// hash or number yet.
+#[derive(Debug, Deserialize)]
+struct PendingBlock {
+    transactions: Vec<Transaction>,
+}
+
+#[async_trait]
+impl MempoolTransactions for Web3Connector {
+    type Transaction = Transaction;
+
+    async fn mempool_transactions(&self) -> Result<Vec<Self::Transaction>> {
+        let block: PendingBlock = self
+            .client
+            .send(jsonrpc::Request::new("eth_getBlockByNumber", vec![
+                jsonrpc::serialize("pending")?,
+                jsonrpc::serialize(true)?,
+            ]))
+            .await?;
+
+        Ok(block.transactions)
+    }
+}
+
 #[async_trait]
 impl ReceiptByHash for Web3Connector {
     async fn receipt_by_hash(&self, transaction_hash: Hash) -> Result<TransactionReceipt> {
@@ -102,28 +149,37 @@ impl ConnectedNetwork for Web3Connector {
 
         Ok(chain_id)
     }
+
+    fn polling_policy(&self) -> Option<PollingPolicy> {
+        self.polling_policy
+    }
 }
 
 #[async_trait]
 impl GetLogs for Web3Connector {
-    async fn get_logs(&self, event: Event) -> Result<Vec<Log>> {
-        // Ideally, we would be computing this based on start-of-swap.
-        // However, some manual testing on mainnet shows that even specifying 0 is
-        // reasonably performant (~ 1sec for Infura). Hence, we just specify 0
-        // here to be on the safe side.
-        let from_block = "0x0";
-
+    async fn get_logs_for(
+        &self,
+        address: Address,
+        topics: Vec<Option<Hash>>,
+        from_block: U256,
+        to_block: U256,
+    ) -> Result<Vec<Log>> {
         let logs = self
             .client
             .send(jsonrpc::Request::new("eth_getLogs", vec![
                 serde_json::json!({
                     "fromBlock": from_block,
-                    "address": event.address,
-                    "topics": event.topics
+                    "toBlock": to_block,
+                    "address": address,
+                    "topics": topics
                 }),
             ]))
             .await?;
 
         Ok(logs)
     }
+
+    fn max_block_range(&self) -> Option<u64> {
+        self.max_block_range
+    }
 }
@@ -0,0 +1,124 @@
+use crate::clock::{Clock, SystemClock};
+use rand::Rng;
+use std::{cmp, time::Duration};
+
+/// How often the watch functions poll the connector.
+///
+/// While no new block arrives the interval grows exponentially up to `max`,
+/// a new block resets it to `initial`. Every interval is randomized by up to
+/// `jitter` (a fraction of the interval) in either direction so that many
+/// watchers do not poll the node in lockstep.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PollingPolicy {
+    pub initial: Duration,
+    pub max: Duration,
+    pub multiplier: u32,
+    pub jitter: f64,
+}
+
+impl PollingPolicy {
+    /// Polls every `interval` while blocks keep coming and backs off to
+    /// eight times that while idle.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            initial: interval,
+            max: interval * 8,
+            multiplier: 2,
+            jitter: 0.1,
+        }
+    }
+}
+
+/// Polling according to a [`PollingPolicy`], waiting on the given clock.
+#[derive(Debug)]
+pub struct Backoff<C = SystemClock> {
+    policy: PollingPolicy,
+    current: Duration,
+    clock: C,
+}
+
+impl Backoff {
+    pub fn new(policy: PollingPolicy) -> Self {
+        Self::with_clock(policy, SystemClock)
+    }
+}
+
+impl<C> Backoff<C>
+where
+    C: Clock,
+{
+    pub fn with_clock(policy: PollingPolicy, clock: C) -> Self {
+        Self {
+            policy,
+            current: policy.initial,
+            clock,
+        }
+    }
+
+    pub fn policy(&self) -> PollingPolicy {
+        self.policy
+    }
+
+    /// Waits before polling again, `new_block` tells whether the last poll
+    /// saw a block we did not know before.
+    pub async fn wait(&mut self, new_block: bool) {
+        let interval = self.next_interval(new_block);
+
+        self.clock.sleep(interval).await
+    }
+
+    fn next_interval(&mut self, new_block: bool) -> Duration {
+        if new_block {
+            self.current = self.policy.initial;
+        }
+
+        let interval = self.current;
+        self.current = cmp::min(interval * self.policy.multiplier, self.policy.max);
+
+        jittered(interval, self.policy.jitter)
+    }
+}
+
+fn jittered(interval: Duration, jitter: f64) -> Duration {
+    if jitter <= 0.0 {
+        return interval;
+    }
+
+    let factor = rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter);
+
+    interval.mul_f64(factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interval_grows_while_idle_and_resets_on_new_block() {
+        let mut backoff = Backoff::new(PollingPolicy {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(5),
+            multiplier: 2,
+            jitter: 0.0,
+        });
+
+        let idle = (0..5)
+            .map(|_| backoff.next_interval(false).as_secs())
+            .collect::<Vec<_>>();
+        assert_eq!(idle, vec![1, 2, 4, 5, 5]);
+
+        assert_eq!(backoff.next_interval(true), Duration::from_secs(1));
+        assert_eq!(backoff.next_interval(false), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let interval = Duration::from_secs(10);
+
+        for _ in 0..100 {
+            let jittered = jittered(interval, 0.1);
+
+            assert!(jittered >= Duration::from_secs(9) && jittered <= Duration::from_secs(11));
+        }
+    }
+}
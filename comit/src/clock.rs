@@ -0,0 +1,104 @@
+use crate::Timestamp;
+use async_trait::async_trait;
+use futures::future;
+use std::{
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+    task::Poll,
+};
+use time::{Duration, OffsetDateTime};
+
+/// The source of the current time.
+///
+/// Code that acts once some time passed takes a clock instead of asking the
+/// system, so that tests can move time forward without sleeping.
+#[async_trait]
+pub trait Clock: Send + Sync {
+    fn now(&self) -> OffsetDateTime;
+
+    fn timestamp(&self) -> Timestamp {
+        Timestamp::from(self.now())
+    }
+
+    /// Waits until `duration` passed on this clock.
+    async fn sleep(&self, duration: std::time::Duration) {
+        tokio::time::delay_for(duration).await
+    }
+}
+
+/// The wall-clock time of the system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// A clock that only moves when it is told to.
+///
+/// Clones share the same time, hence a test can keep a clone to advance the
+/// clock it handed out.
+#[derive(Clone, Debug)]
+pub struct TestClock {
+    now: Arc<Mutex<OffsetDateTime>>,
+}
+
+impl TestClock {
+    pub fn new(now: OffsetDateTime) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().expect("lock poisoned");
+        *now += duration;
+    }
+}
+
+#[async_trait]
+impl Clock for TestClock {
+    fn now(&self) -> OffsetDateTime {
+        *self.now.lock().expect("lock poisoned")
+    }
+
+    /// Moves the clock forward right away instead of waiting.
+    ///
+    /// Still yields once, just like a real sleep would, so that a task that
+    /// keeps sleeping does not starve the others.
+    async fn sleep(&self, duration: std::time::Duration) {
+        self.advance(Duration::try_from(duration).expect("duration fits into time::Duration"));
+
+        let mut yielded = false;
+        future::poll_fn(|cx| {
+            if yielded {
+                return Poll::Ready(());
+            }
+
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest;
+
+    proptest::proptest! {
+        #[test]
+        fn test_clock_moves_only_when_advanced(start in proptest::time::timestamp(), seconds in 0u32..=3600) {
+            let clock = TestClock::new(OffsetDateTime::from_unix_timestamp(u32::from(start).into()));
+            assert_eq!(clock.timestamp(), start);
+
+            clock.advance(Duration::seconds(seconds.into()));
+
+            assert_eq!(clock.timestamp(), start.plus(seconds));
+        }
+    }
+}
@@ -187,6 +187,8 @@ pub struct Block {
     /// Hash of the parent
     #[serde(rename = "parentHash")]
     pub parent_hash: Hash,
+    /// Height of the block
+    pub number: U256,
     /// Logs bloom
     #[serde(rename = "logsBloom")]
     pub logs_bloom: H2048,
@@ -207,6 +209,15 @@ impl ChainId {
     pub const ROPSTEN: Self = ChainId(3);
     pub const KOVAN: Self = ChainId(42);
     pub const GETH_DEV: Self = ChainId(1337);
+
+    /// Whether the chain has activated the London hard fork and hence accepts
+    /// EIP-1559 (type 2) transactions.
+    ///
+    /// Unknown chains, including the geth dev chain we use for testing, are
+    /// assumed to only accept legacy transactions.
+    pub fn supports_eip1559(&self) -> bool {
+        matches!(self.0, 1 | 3 | 4 | 5 | 42)
+    }
 }
 
 impl fmt::Display for ChainId {
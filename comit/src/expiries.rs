@@ -5,8 +5,9 @@
 //! beta ledger expiry time must not elapse before the alpha ledger expiry time.
 
 mod config;
+mod congestion;
 
-pub use self::config::*;
+pub use self::{config::*, congestion::*};
 
 use crate::{
     timestamp::{self, Timestamp},
@@ -25,14 +26,34 @@ use time::Duration;
 /// Calculate a pair of expiries suitable for use with the herc20-hbit COMIT
 /// protocol.
 pub fn expiry_offsets_herc20_hbit(network: Network) -> (AlphaOffset, BetaOffset) {
-    let config = Config::herc20_hbit(network);
-    expiry_offsets(&config)
+    expiry_offsets_herc20_hbit_with_config(network, ExpiryConfig::from_network(network))
 }
 
 /// Calculate a pair of expiries suitable for use with the hbit-herc20 COMIT
 /// protocol.
 pub fn expiry_offsets_hbit_herc20(network: Network) -> (AlphaOffset, BetaOffset) {
-    let config = Config::hbit_herc20(network);
+    expiry_offsets_hbit_herc20_with_config(network, ExpiryConfig::from_network(network))
+}
+
+/// Calculate a pair of expiries for the herc20-hbit COMIT protocol, waiting
+/// for the confirmation targets of `expiry_config` instead of the defaults of
+/// the network.
+pub fn expiry_offsets_herc20_hbit_with_config(
+    network: Network,
+    expiry_config: ExpiryConfig,
+) -> (AlphaOffset, BetaOffset) {
+    let config = Config::herc20_hbit(network).with_confirmation_targets(expiry_config);
+    expiry_offsets(&config)
+}
+
+/// Calculate a pair of expiries for the hbit-herc20 COMIT protocol, waiting
+/// for the confirmation targets of `expiry_config` instead of the defaults of
+/// the network.
+pub fn expiry_offsets_hbit_herc20_with_config(
+    network: Network,
+    expiry_config: ExpiryConfig,
+) -> (AlphaOffset, BetaOffset) {
+    let config = Config::hbit_herc20(network).with_confirmation_targets(expiry_config);
     expiry_offsets(&config)
 }
 
@@ -904,6 +925,58 @@ mod tests {
         assert_eq!(b, 35.seconds().into());
     }
 
+    #[test]
+    fn default_expiry_config_yields_default_offsets() {
+        let expiry_config = ExpiryConfig::from_network(Network::Main);
+
+        assert_eq!(
+            expiry_offsets_herc20_hbit_with_config(Network::Main, expiry_config),
+            expiry_offsets_herc20_hbit(Network::Main)
+        );
+        assert_eq!(
+            expiry_offsets_hbit_herc20_with_config(Network::Main, expiry_config),
+            expiry_offsets_hbit_herc20(Network::Main)
+        );
+    }
+
+    #[test]
+    fn more_confirmations_yield_longer_offsets() {
+        let expiry_config = ExpiryConfig {
+            bitcoin_confirmation_target: 10,
+            ethereum_confirmation_target: 10,
+        };
+
+        let (a, b) = expiry_offsets_herc20_hbit_with_config(Network::Dev, expiry_config);
+
+        assert_that(&a).is_greater_than(&44.seconds().into());
+        assert_that(&b).is_greater_than(&35.seconds().into());
+    }
+
+    #[test]
+    fn confirmation_targets_apply_to_the_matching_ledger() {
+        let default = ExpiryConfig::from_network(Network::Main);
+        let more_bitcoin_confirmations = ExpiryConfig {
+            bitcoin_confirmation_target: 12,
+            ..default
+        };
+
+        // Bitcoin is the alpha ledger of hbit-herc20 and the beta ledger of
+        // herc20-hbit.
+        let hbit_herc20 = Config::hbit_herc20(Network::Main)
+            .with_confirmation_targets(more_bitcoin_confirmations);
+        let herc20_hbit = Config::herc20_hbit(Network::Main)
+            .with_confirmation_targets(more_bitcoin_confirmations);
+
+        assert_eq!(hbit_herc20.finality_alpha(), herc20_hbit.finality_beta());
+        assert_eq!(hbit_herc20.finality_beta(), herc20_hbit.finality_alpha());
+        assert_that(&hbit_herc20.finality_alpha())
+            .is_greater_than(&Config::hbit_herc20(Network::Main).finality_alpha());
+        assert_eq!(
+            herc20_hbit.finality_alpha(),
+            Config::herc20_hbit(Network::Main).finality_alpha()
+        );
+    }
+
     #[tokio::test]
     async fn alice_can_complete_an_hbit_herc20_swap() {
         let start_at = Timestamp::now();
@@ -77,6 +77,28 @@ mod dev {
     pub const ETHEREUM_MINE_WITHIN_N_BLOCKS: u8 = 3;
 }
 
+/// The number of confirmations to wait for on each ledger before a
+/// transaction is considered final.
+///
+/// Together with the average block times of the network these determine the
+/// expiry offsets, operators can hence tune the offsets to the chains they are
+/// running on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExpiryConfig {
+    pub bitcoin_confirmation_target: u8,
+    pub ethereum_confirmation_target: u8,
+}
+
+impl ExpiryConfig {
+    /// The confirmation targets used by default on `network`.
+    pub fn from_network(network: Network) -> Self {
+        ExpiryConfig {
+            bitcoin_confirmation_target: bitcoin_confirmations(network),
+            ethereum_confirmation_target: ethereum_confirmations(network),
+        }
+    }
+}
+
 /// Configuration values used during transition period calculations.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Config {
@@ -134,6 +156,43 @@ impl Config {
         }
     }
 
+    /// Wait for the confirmation targets of `expiry_config` instead of the
+    /// defaults of the network.
+    pub fn with_confirmation_targets(self, expiry_config: ExpiryConfig) -> Self {
+        let bitcoin = expiry_config.bitcoin_confirmation_target;
+        let ethereum = expiry_config.ethereum_confirmation_target;
+
+        let (alpha_required_confirmations, beta_required_confirmations) = match self.protocol {
+            Protocol::Herc20Hbit => (ethereum, bitcoin),
+            Protocol::HbitHerc20 => (bitcoin, ethereum),
+        };
+
+        Config {
+            alpha_required_confirmations,
+            beta_required_confirmations,
+            ..self
+        }
+    }
+
+    /// Allow for transactions to be mined within the given number of blocks
+    /// instead of the defaults of the network.
+    pub fn with_mine_within_n_blocks(self, bitcoin: u8, ethereum: u8) -> Self {
+        let (alpha, beta) = match self.protocol {
+            Protocol::Herc20Hbit => (ethereum, bitcoin),
+            Protocol::HbitHerc20 => (bitcoin, ethereum),
+        };
+
+        Config {
+            alpha_mine_deploy_within_n_blocks: alpha,
+            beta_mine_deploy_within_n_blocks: beta,
+            alpha_mine_fund_within_n_blocks: alpha,
+            beta_mine_fund_within_n_blocks: beta,
+            alpha_mine_redeem_within_n_blocks: alpha,
+            beta_mine_redeem_within_n_blocks: beta,
+            ..self
+        }
+    }
+
     /// Alpha/beta expiries are required to be separated by at least this window
     /// in order for Bobs redeem transaction to be 'safe' i.e., for Bob to be
     /// sure Alice can not redeem and refund at the same time (e.g. by trying to
@@ -0,0 +1,170 @@
+//! Recommends expiry offsets that reflect the current state of the fee
+//! markets.
+//!
+//! The default offsets assume that a transaction paying the suggested fee is
+//! mined within a fixed number of blocks. When the fee markets are hot it takes
+//! longer for such a transaction to be mined, when they are quiet it is mined
+//! sooner. We account for this by scaling the number of blocks we allow for.
+
+use super::{
+    bitcoin_mine_within_blocks, ethereum_mine_within_blocks, expiry_offsets, AlphaOffset,
+    BetaOffset, Config, Protocol,
+};
+use crate::{ethereum::U256, Network};
+use std::cmp;
+
+/// Up to this fee rate (in sat/vbyte) the Bitcoin fee market is quiet.
+const BITCOIN_QUIET_SAT_PER_VBYTE: u64 = 10;
+/// Up to this fee rate (in sat/vbyte) the Bitcoin fee market is normal.
+const BITCOIN_NORMAL_SAT_PER_VBYTE: u64 = 100;
+/// Up to this gas price (in gwei) the Ethereum fee market is quiet.
+const ETHEREUM_QUIET_GWEI: u64 = 30;
+/// Up to this gas price (in gwei) the Ethereum fee market is normal.
+const ETHEREUM_NORMAL_GWEI: u64 = 150;
+
+/// How busy the fee market of a ledger is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Congestion {
+    Quiet,
+    Normal,
+    Hot,
+}
+
+impl Congestion {
+    /// Classifies the Bitcoin fee market by the fee rate per vbyte.
+    pub fn bitcoin(fee_rate: bitcoin::Amount) -> Self {
+        match fee_rate.as_sat() {
+            rate if rate <= BITCOIN_QUIET_SAT_PER_VBYTE => Congestion::Quiet,
+            rate if rate <= BITCOIN_NORMAL_SAT_PER_VBYTE => Congestion::Normal,
+            _ => Congestion::Hot,
+        }
+    }
+
+    /// Classifies the Ethereum fee market by the gas price in wei.
+    pub fn ethereum(gas_price: U256) -> Self {
+        let gwei = gas_price / U256::exp10(9);
+
+        if gwei <= U256::from(ETHEREUM_QUIET_GWEI) {
+            Congestion::Quiet
+        } else if gwei <= U256::from(ETHEREUM_NORMAL_GWEI) {
+            Congestion::Normal
+        } else {
+            Congestion::Hot
+        }
+    }
+
+    fn mine_within_n_blocks(self, default: u8) -> u8 {
+        match self {
+            Congestion::Quiet => cmp::max(default / 2, 1),
+            Congestion::Normal => default,
+            Congestion::Hot => default.saturating_mul(2),
+        }
+    }
+}
+
+/// Recommends expiry offsets for `protocol` given the current Bitcoin fee
+/// rate (per vbyte) and Ethereum gas price (in wei).
+///
+/// The recommended offsets never shrink as fees rise, on a normal fee market
+/// they match the default offsets of the network.
+pub fn recommend_offsets(
+    network: Network,
+    protocol: Protocol,
+    btc_fee_rate: bitcoin::Amount,
+    eth_gas_price: U256,
+) -> (AlphaOffset, BetaOffset) {
+    let bitcoin_blocks =
+        Congestion::bitcoin(btc_fee_rate).mine_within_n_blocks(bitcoin_mine_within_blocks(network));
+    let ethereum_blocks = Congestion::ethereum(eth_gas_price)
+        .mine_within_n_blocks(ethereum_mine_within_blocks(network));
+
+    let config = match protocol {
+        Protocol::Herc20Hbit => Config::herc20_hbit(network),
+        Protocol::HbitHerc20 => Config::hbit_herc20(network),
+    }
+    .with_mine_within_n_blocks(bitcoin_blocks, ethereum_blocks);
+
+    expiry_offsets(&config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expiries::{expiry_offsets_hbit_herc20, expiry_offsets_herc20_hbit};
+
+    const PROTOCOLS: [Protocol; 2] = [Protocol::Herc20Hbit, Protocol::HbitHerc20];
+
+    fn gwei(gwei: u64) -> U256 {
+        U256::from(gwei) * U256::exp10(9)
+    }
+
+    #[test]
+    fn higher_bitcoin_fee_rate_yields_longer_or_equal_offsets() {
+        let fee_rates = [1, 5, 10, 11, 50, 100, 101, 500, 5_000];
+
+        for protocol in PROTOCOLS.iter() {
+            let offsets = fee_rates
+                .iter()
+                .map(|sat| {
+                    recommend_offsets(
+                        Network::Main,
+                        *protocol,
+                        bitcoin::Amount::from_sat(*sat),
+                        gwei(50),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            for pair in offsets.windows(2) {
+                assert!(pair[0].0 <= pair[1].0, "{:?}", protocol);
+                assert!(pair[0].1 <= pair[1].1, "{:?}", protocol);
+            }
+            assert!(offsets.first() < offsets.last());
+        }
+    }
+
+    #[test]
+    fn higher_gas_price_yields_longer_or_equal_offsets() {
+        let gas_prices = [1, 20, 30, 31, 100, 150, 151, 1_000, 10_000];
+
+        for protocol in PROTOCOLS.iter() {
+            let offsets = gas_prices
+                .iter()
+                .map(|price| {
+                    recommend_offsets(
+                        Network::Main,
+                        *protocol,
+                        bitcoin::Amount::from_sat(50),
+                        gwei(*price),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            for pair in offsets.windows(2) {
+                assert!(pair[0].0 <= pair[1].0, "{:?}", protocol);
+                assert!(pair[0].1 <= pair[1].1, "{:?}", protocol);
+            }
+            assert!(offsets.first() < offsets.last());
+        }
+    }
+
+    #[test]
+    fn normal_fee_markets_yield_default_offsets() {
+        let fee_rate = bitcoin::Amount::from_sat(50);
+        let gas_price = gwei(50);
+
+        assert_eq!(
+            recommend_offsets(Network::Main, Protocol::Herc20Hbit, fee_rate, gas_price),
+            expiry_offsets_herc20_hbit(Network::Main)
+        );
+        assert_eq!(
+            recommend_offsets(Network::Main, Protocol::HbitHerc20, fee_rate, gas_price),
+            expiry_offsets_hbit_herc20(Network::Main)
+        );
+    }
+
+    #[test]
+    fn quiet_markets_never_allow_less_than_one_block() {
+        assert_eq!(Congestion::Quiet.mine_within_n_blocks(1), 1);
+    }
+}
@@ -4,7 +4,7 @@ use crate::{
     asset,
     btsieve::{
         bitcoin::{watch_for_created_outpoint, watch_for_spent_outpoint},
-        BlockByHash, ConnectedNetwork, LatestBlock,
+        BlockByHash, Checkpoints, ConnectedNetwork, LatestBlock,
     },
     htlc_location, identity, ledger,
     swap::actions::{SendToAddress, SpendOutput},
@@ -65,8 +65,9 @@ pub trait WatchForRedeemed {
     ) -> Redeemed;
 }
 
-pub async fn watch_for_funded<C>(
+pub async fn watch_for_funded<C, K>(
     connector: &C,
+    checkpoints: &K,
     params: &SharedParams,
     start_of_swap: OffsetDateTime,
 ) -> Result<Result<Funded, IncorrectlyFunded>>
@@ -74,13 +75,18 @@ where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = BlockHash>
         + ConnectedNetwork<Network = ledger::Bitcoin>,
+    K: Checkpoints<BlockHash>,
 {
     let expected_asset = params.asset;
 
-    let (transaction, location) =
-        watch_for_created_outpoint(connector, start_of_swap, params.compute_address())
-            .instrument(tracing::info_span!("", action = "fund"))
-            .await?;
+    let (transaction, location) = watch_for_created_outpoint(
+        connector,
+        checkpoints,
+        start_of_swap,
+        params.compute_address(),
+    )
+    .instrument(tracing::info_span!("", action = "fund"))
+    .await?;
 
     let asset = asset::Bitcoin::from_sat(transaction.output[location.vout as usize].value);
 
@@ -93,8 +99,9 @@ where
     }
 }
 
-pub async fn watch_for_redeemed<C>(
+pub async fn watch_for_redeemed<C, K>(
     connector: &C,
+    checkpoints: &K,
     params: &SharedParams,
     location: htlc_location::Bitcoin,
     start_of_swap: OffsetDateTime,
@@ -103,11 +110,17 @@ where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = BlockHash>
         + ConnectedNetwork<Network = ledger::Bitcoin>,
+    K: Checkpoints<BlockHash>,
 {
-    let (transaction, _) =
-        watch_for_spent_outpoint(connector, start_of_swap, location, params.redeem_identity)
-            .instrument(tracing::info_span!("", action = "redeem"))
-            .await?;
+    let (transaction, _) = watch_for_spent_outpoint(
+        connector,
+        checkpoints,
+        start_of_swap,
+        location,
+        params.redeem_identity,
+    )
+    .instrument(tracing::info_span!("", action = "redeem"))
+    .await?;
 
     let secret = extract_secret(&transaction, &params.secret_hash)
         .expect("Redeem transaction must contain secret");
@@ -118,8 +131,9 @@ where
     })
 }
 
-pub async fn watch_for_refunded<C>(
+pub async fn watch_for_refunded<C, K>(
     connector: &C,
+    checkpoints: &K,
     params: &SharedParams,
     location: htlc_location::Bitcoin,
     start_of_swap: OffsetDateTime,
@@ -128,11 +142,17 @@ where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = BlockHash>
         + ConnectedNetwork<Network = ledger::Bitcoin>,
+    K: Checkpoints<BlockHash>,
 {
-    let (transaction, _) =
-        watch_for_spent_outpoint(connector, start_of_swap, location, params.refund_identity)
-            .instrument(tracing::info_span!("", action = "refund"))
-            .await?;
+    let (transaction, _) = watch_for_spent_outpoint(
+        connector,
+        checkpoints,
+        start_of_swap,
+        location,
+        params.refund_identity,
+    )
+    .instrument(tracing::info_span!("", action = "refund"))
+    .await?;
 
     Ok(Refunded {
         transaction: transaction.txid(),
@@ -317,6 +337,7 @@ mod arbitrary {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::proptest;
     use bitcoin::{consensus::encode::deserialize, OutPoint, Script, Transaction, TxIn};
     use spectral::prelude::*;
     use std::str::FromStr;
@@ -364,6 +385,19 @@ mod test {
         assert_that!(extract_secret(&transaction, &secret_hash)).is_none();
     }
 
+    proptest::proptest! {
+        #[test]
+        fn mismatched_secret_never_validates(
+            (secret, secret_hash) in proptest::secret_and_hash(),
+            other in proptest::secret(),
+        ) {
+            proptest::prop_assume!(other != secret);
+            let transaction = setup(&other);
+
+            assert_eq!(extract_secret(&transaction, &secret_hash), None);
+        }
+    }
+
     #[test]
     fn extract_correct_secret_from_mainnet_transaction() {
         let hex_tx = hex::decode("0200000000010124e06fe5594b941d06c7385dc7307ec694a41f7d307423121855ee17e47e06ad0100000000ffffffff0137aa0b000000000017a914050377baa6e8c5a07aed125d0ef262c6d5b67a038705483045022100d780139514f39ed943179e4638a519101bae875ec1220b226002bcbcb147830b0220273d1efb1514a77ee3dd4adee0e896b7e76be56c6d8e73470ae9bd91c91d700c01210344f8f459494f74ebb87464de9b74cdba3709692df4661159857988966f94262f20ec9e9fb3c669b2354ea026ab3da82968a2e7ab9398d5cbed4e78e47246f2423e01015b63a82091d6a24697ed31932537ae598d3de3131e1fcd0641b9ac4be7afcb376386d71e8876a9149f4a0cf348b478336cb1d87ea4c8313a7ca3de1967029000b27576a91465252e57f727a27f32c77098e14d88d8dbec01816888ac00000000").unwrap();
@@ -5,9 +5,10 @@ use crate::{
     asset::{ethereum::FromWei, Erc20, Erc20Quantity},
     btsieve::{
         ethereum::{
-            watch_for_contract_creation, watch_for_event, GetLogs, ReceiptByHash, TransactionByHash,
+            wait_for_confirmations, watch_for_contract_creation, watch_for_event, GetLogs,
+            ReceiptByHash, TransactionByHash,
         },
-        BlockByHash, ConnectedNetwork, LatestBlock,
+        BlockByHash, Checkpoints, ConnectedNetwork, LatestBlock,
     },
     ethereum,
     ethereum::{Block, ChainId, Hash, U256},
@@ -99,8 +100,9 @@ pub trait WatchForRedeemed {
     ) -> Redeemed;
 }
 
-pub async fn watch_for_deployed<C>(
+pub async fn watch_for_deployed<C, K>(
     connector: &C,
+    checkpoints: &K,
     params: Params,
     start_of_swap: OffsetDateTime,
 ) -> Result<Deployed>
@@ -109,11 +111,12 @@ where
         + BlockByHash<Block = Block, BlockHash = Hash>
         + ReceiptByHash
         + ConnectedNetwork<Network = ChainId>,
+    K: Checkpoints<Hash>,
 {
     let expected_bytecode = params.clone().bytecode();
 
     let (transaction, location) =
-        watch_for_contract_creation(connector, start_of_swap, &expected_bytecode)
+        watch_for_contract_creation(connector, checkpoints, start_of_swap, &expected_bytecode)
             .instrument(tracing::info_span!("", action = "deploy"))
             .await?;
 
@@ -152,22 +155,53 @@ where
         .instrument(tracing::info_span!("", action = "fund"))
         .await?;
 
-    let expected_asset = &params.asset;
-
     let quantity = Erc20Quantity::from_wei(U256::from_big_endian(&log.data.0));
     let asset = Erc20::new(log.address, quantity);
 
-    match expected_asset.cmp(&asset) {
-        Ordering::Equal => Ok(Ok(Funded {
-            transaction: transaction.hash,
-        })),
-        _ => Ok(Err(IncorrectlyFunded {
-            expected: params.asset,
-            got: asset,
-        })),
+    Ok(check_funding(params.asset, asset, transaction.hash))
+}
+
+/// Compares the asset the HTLC was funded with to the one we expected, both
+/// end up in the error if they differ.
+fn check_funding(
+    expected: asset::Erc20,
+    got: asset::Erc20,
+    transaction: ethereum::Hash,
+) -> Result<Funded, IncorrectlyFunded> {
+    match expected.cmp(&got) {
+        Ordering::Equal => Ok(Funded { transaction }),
+        _ => Err(IncorrectlyFunded { expected, got }),
     }
 }
 
+/// Like `watch_for_funded` but only returns once the funding transaction has
+/// at least `min_confirmations`.
+pub async fn watch_for_confirmed_funded<C>(
+    connector: &C,
+    params: Params,
+    start_of_swap: OffsetDateTime,
+    deployed: Deployed,
+    min_confirmations: u32,
+) -> Result<Result<Funded, IncorrectlyFunded>>
+where
+    C: LatestBlock<Block = Block>
+        + BlockByHash<Block = Block, BlockHash = Hash>
+        + ReceiptByHash
+        + TransactionByHash
+        + ConnectedNetwork<Network = ChainId>
+        + GetLogs,
+{
+    let funded = watch_for_funded(connector, params, start_of_swap, deployed).await?;
+
+    if let Ok(Funded { transaction }) = funded {
+        wait_for_confirmations(connector, transaction, start_of_swap, min_confirmations)
+            .instrument(tracing::info_span!("", action = "confirm fund"))
+            .await?;
+    }
+
+    Ok(funded)
+}
+
 pub async fn watch_for_redeemed<C>(
     connector: &C,
     start_of_swap: OffsetDateTime,
@@ -255,6 +289,8 @@ impl Params {
             amount: asset::Ether::zero(),
             gas_limit,
             chain_id,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
         }
     }
 
@@ -272,6 +308,8 @@ impl Params {
             data,
             gas_limit,
             chain_id: self.chain_id,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
         }
     }
 
@@ -284,6 +322,8 @@ impl Params {
             data,
             gas_limit,
             chain_id: self.chain_id,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
         }
     }
 
@@ -300,6 +340,8 @@ impl Params {
             data,
             gas_limit,
             chain_id: self.chain_id,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
         }
     }
 }
@@ -357,3 +399,57 @@ impl quickcheck::Arbitrary for Params {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swap;
+
+    fn dai(token_contract: ethereum::Address, atto: u64) -> asset::Erc20 {
+        Erc20::new(token_contract, Erc20Quantity::from_wei(atto))
+    }
+
+    #[test]
+    fn underfunding_keeps_expected_and_actual_amounts() {
+        let token_contract = ethereum::Address::random();
+        let expected = dai(token_contract, 1_000_000);
+        let got = dai(token_contract, 999_999);
+
+        let error = check_funding(expected.clone(), got.clone(), Hash::default()).unwrap_err();
+
+        assert_eq!(error.expected, expected);
+        assert_eq!(error.got, got);
+    }
+
+    #[test]
+    fn amounts_survive_into_the_swap_error() {
+        let token_contract = ethereum::Address::random();
+        let error = check_funding(
+            dai(token_contract, 1_000_000),
+            dai(token_contract, 1),
+            Hash::default(),
+        )
+        .unwrap_err();
+        let message = error.to_string();
+
+        let swap_error = anyhow::Error::from(
+            swap::Error::<IncorrectlyFunded, IncorrectlyFunded>::AlphaIncorrectlyFunded(error),
+        );
+
+        let incorrectly_funded = swap_error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<IncorrectlyFunded>())
+            .expect("incorrectly funded to be the source");
+        assert_eq!(incorrectly_funded.got, dai(token_contract, 1));
+        assert!(format!("{:#}", swap_error).contains(&message));
+    }
+
+    #[test]
+    fn correct_funding_is_funded() {
+        let asset = dai(ethereum::Address::random(), 1_000_000);
+
+        let result = check_funding(asset.clone(), asset, Hash::default());
+
+        assert!(result.is_ok());
+    }
+}
@@ -18,14 +18,18 @@ use std::fmt;
 pub enum Bitcoin {
     Mainnet,
     Testnet,
+    Testnet4,
+    Signet,
     Regtest,
 }
 
+/// Signet and testnet4 use the same address prefixes and key versions as
+/// testnet, hence they map to `Network::Testnet`.
 impl From<Bitcoin> for ::bitcoin::Network {
     fn from(bitcoin: Bitcoin) -> ::bitcoin::Network {
         match bitcoin {
             Bitcoin::Mainnet => ::bitcoin::Network::Bitcoin,
-            Bitcoin::Testnet => ::bitcoin::Network::Testnet,
+            Bitcoin::Testnet | Bitcoin::Testnet4 | Bitcoin::Signet => ::bitcoin::Network::Testnet,
             Bitcoin::Regtest => ::bitcoin::Network::Regtest,
         }
     }
@@ -83,6 +87,8 @@ pub mod bitcoind_jsonrpc_network {
                 match v {
                     "main" => Ok(Bitcoin::Mainnet),
                     "test" => Ok(Bitcoin::Testnet),
+                    "testnet4" => Ok(Bitcoin::Testnet4),
+                    "signet" => Ok(Bitcoin::Signet),
                     "regtest" => Ok(Bitcoin::Regtest),
                     unknown => Err(E::custom(format!("unknown bitcoin network {}", unknown))),
                 }
@@ -96,10 +102,12 @@ pub mod bitcoind_jsonrpc_network {
 #[cfg(feature = "quickcheck")]
 impl quickcheck::Arbitrary for Bitcoin {
     fn arbitrary<G: quickcheck::Gen>(g: &mut G) -> Self {
-        match u8::arbitrary(g) % 3 {
+        match u8::arbitrary(g) % 5 {
             0 => Bitcoin::Mainnet,
             1 => Bitcoin::Testnet,
-            2 => Bitcoin::Regtest,
+            2 => Bitcoin::Testnet4,
+            3 => Bitcoin::Signet,
+            4 => Bitcoin::Regtest,
             _ => unreachable!(),
         }
     }
@@ -127,4 +135,22 @@ mod tests {
 
         assert_eq!(ledger, rinsed);
     }
+
+    #[test]
+    fn signet_and_testnet4_are_parsed_from_bitcoind_chain_names() {
+        #[derive(Deserialize)]
+        struct BlockchainInfo {
+            #[serde(with = "bitcoind_jsonrpc_network")]
+            chain: Bitcoin,
+        }
+
+        for (chain, network) in &[("signet", Bitcoin::Signet), ("testnet4", Bitcoin::Testnet4)] {
+            let info =
+                serde_json::from_str::<BlockchainInfo>(&format!(r#"{{"chain":"{}"}}"#, chain))
+                    .unwrap();
+
+            assert_eq!(info.chain, *network);
+            assert_eq!(network.to_string().parse::<Bitcoin>().unwrap(), *network);
+        }
+    }
 }
@@ -18,6 +18,7 @@ pub mod arbitrary;
 pub mod asset;
 pub mod bitcoin;
 pub mod btsieve;
+pub mod clock;
 pub mod ethereum;
 pub mod expiries;
 pub mod hbit;
@@ -170,7 +171,9 @@ impl From<ledger::Bitcoin> for Network {
     fn from(network: ledger::Bitcoin) -> Self {
         match network {
             ledger::Bitcoin::Mainnet => Network::Main,
-            ledger::Bitcoin::Testnet => Network::Test,
+            ledger::Bitcoin::Testnet | ledger::Bitcoin::Testnet4 | ledger::Bitcoin::Signet => {
+                Network::Test
+            }
             ledger::Bitcoin::Regtest => Network::Dev,
         }
     }
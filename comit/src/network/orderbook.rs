@@ -2,7 +2,7 @@ mod makerbook;
 mod order_source;
 
 use crate::{
-    orderpool::{Match, OrderPool},
+    orderpool::{Match, MinimumMatch, OrderPool},
     BtcDaiOrder, OrderId,
 };
 use libp2p::{
@@ -12,6 +12,8 @@ use libp2p::{
 };
 use makerbook::Makerbook;
 use order_source::*;
+
+pub(crate) use order_source::wire;
 use std::{
     collections::VecDeque,
     task::{Context, Poll},
@@ -42,6 +44,14 @@ impl Orderbook {
         }
     }
 
+    /// Only match orders for at least `minimum`.
+    pub fn with_minimum_match(self, minimum: MinimumMatch) -> Self {
+        Orderbook {
+            orderpool: self.orderpool.with_minimum(minimum),
+            ..self
+        }
+    }
+
     /// Declare oneself to the network as a maker.
     pub fn declare_as_maker(&mut self) {
         self.makerbook.login();
@@ -103,6 +113,15 @@ pub struct OrderNotFound(OrderId);
 #[derive(Debug)]
 pub enum BehaviourOutEvent {
     OrderMatch(Match),
+    /// We retrieved the current orders of this maker.
+    RetrievedOrders {
+        maker: PeerId,
+        orders: Vec<BtcDaiOrder>,
+    },
+    /// This maker no longer offers any orders.
+    MakerIsGone {
+        maker: PeerId,
+    },
 }
 
 impl NetworkBehaviourEventProcess<makerbook::BehaviourOutEvent> for Orderbook {
@@ -110,7 +129,9 @@ impl NetworkBehaviourEventProcess<makerbook::BehaviourOutEvent> for Orderbook {
         match event {
             makerbook::BehaviourOutEvent::Logout { peer } => {
                 self.order_source.stop_getting_orders_from(&peer);
-                self.orderpool.remove_all_from(&peer)
+                self.orderpool.remove_all_from(&peer);
+                self.events
+                    .push_back(BehaviourOutEvent::MakerIsGone { maker: peer });
             }
         }
     }
@@ -124,10 +145,14 @@ impl NetworkBehaviourEventProcess<order_source::BehaviourOutEvent> for Orderbook
                     .send_orders(response_handle, self.orderpool.ours().cloned().collect());
             }
             order_source::BehaviourOutEvent::RetrievedOrders { maker, orders } => {
-                self.orderpool.receive(maker, orders);
+                self.orderpool.receive(maker.clone(), orders.clone());
+                self.events
+                    .push_back(BehaviourOutEvent::RetrievedOrders { maker, orders });
             }
             order_source::BehaviourOutEvent::MakerIsGone { maker } => {
                 self.orderpool.remove_all_from(&maker);
+                self.events
+                    .push_back(BehaviourOutEvent::MakerIsGone { maker });
             }
         }
     }
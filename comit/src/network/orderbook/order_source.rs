@@ -338,9 +338,9 @@ impl RequestResponseCodec for GetBtcDaiOrdersCodec {
             .await
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
         let mut de = serde_json::Deserializer::from_slice(&message);
-        let orders = Vec::<wire::BtcDaiOrder>::deserialize(&mut de)?;
+        let orders = Vec::<BtcDaiOrder>::deserialize(&mut de)?;
 
-        Ok(orders.into_iter().map(|wire| wire.into_model()).collect())
+        Ok(orders)
     }
 
     /// Writes a get orders request to the given I/O stream.
@@ -367,12 +367,7 @@ impl RequestResponseCodec for GetBtcDaiOrdersCodec {
     where
         T: AsyncWrite + Unpin + Send,
     {
-        let bytes = serde_json::to_vec(
-            &orders
-                .into_iter()
-                .map(wire::BtcDaiOrder::from_model)
-                .collect::<Vec<_>>(),
-        )?;
+        let bytes = serde_json::to_vec(&orders)?;
         upgrade::write_one(io, &bytes).await?;
 
         Ok(())
@@ -380,7 +375,10 @@ impl RequestResponseCodec for GetBtcDaiOrdersCodec {
 }
 
 /// A dedicated module for the types that represent our messages "on the wire".
-mod wire {
+///
+/// The model types serialize through these, hence they serialize the same
+/// wherever they are used.
+pub(crate) mod wire {
     use crate::{asset, asset::Erc20Quantity, OrderId, Position};
     use serde::{Deserialize, Serialize};
     use time::OffsetDateTime;
@@ -392,12 +390,15 @@ mod wire {
         pub swap_protocol: SwapProtocol,
         #[serde(with = "time::serde::timestamp")]
         pub created_at: OffsetDateTime,
+        /// Unix timestamp, omitted by peers that do not know about it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub valid_until: Option<i64>,
         #[serde(with = "asset::bitcoin::sats_as_string")]
         pub quantity: asset::Bitcoin,
         pub price: Erc20Quantity,
     }
 
-    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
     #[serde(rename_all = "snake_case")]
     pub enum SwapProtocol {
         HbitHerc20 {
@@ -411,33 +412,38 @@ mod wire {
     }
 }
 
-impl wire::BtcDaiOrder {
-    fn into_model(self) -> BtcDaiOrder {
+impl From<wire::BtcDaiOrder> for BtcDaiOrder {
+    fn from(wire: wire::BtcDaiOrder) -> Self {
         let wire::BtcDaiOrder {
             id,
             position,
             swap_protocol,
             created_at,
+            valid_until,
             quantity,
             price,
-        } = self;
+        } = wire;
 
         BtcDaiOrder {
             id,
             position,
-            swap_protocol: swap_protocol.into_model(),
+            swap_protocol: SwapProtocol::from(swap_protocol),
             created_at,
+            valid_until: valid_until.map(time::OffsetDateTime::from_unix_timestamp),
             quantity: Quantity::new(quantity),
             price: Price::from_wei_per_sat(price),
         }
     }
+}
 
-    fn from_model(model: BtcDaiOrder) -> Self {
+impl From<BtcDaiOrder> for wire::BtcDaiOrder {
+    fn from(model: BtcDaiOrder) -> Self {
         let BtcDaiOrder {
             id,
             position,
             swap_protocol,
             created_at,
+            valid_until,
             quantity,
             price,
         } = model;
@@ -445,8 +451,9 @@ impl wire::BtcDaiOrder {
         Self {
             id,
             position,
-            swap_protocol: wire::SwapProtocol::from_model(swap_protocol),
+            swap_protocol: wire::SwapProtocol::from(swap_protocol),
             created_at,
+            valid_until: valid_until.map(|valid_until| valid_until.timestamp()),
             quantity: quantity.to_inner(),
             price: price.wei_per_sat(), /* This is consistent with how we convert into the wire
                                          * model above. */
@@ -454,9 +461,9 @@ impl wire::BtcDaiOrder {
     }
 }
 
-impl wire::SwapProtocol {
-    fn into_model(self) -> SwapProtocol {
-        match self {
+impl From<wire::SwapProtocol> for SwapProtocol {
+    fn from(wire: wire::SwapProtocol) -> Self {
+        match wire {
             wire::SwapProtocol::Herc20Hbit {
                 herc20_expiry_offset,
                 hbit_expiry_offset,
@@ -473,8 +480,10 @@ impl wire::SwapProtocol {
             },
         }
     }
+}
 
-    fn from_model(model: SwapProtocol) -> Self {
+impl From<SwapProtocol> for wire::SwapProtocol {
+    fn from(model: SwapProtocol) -> Self {
         use time::Duration;
 
         match model {
@@ -506,7 +515,7 @@ mod tests {
         fn conversions_to_and_from_wire_model_are_consistent(
             order in proptest::order::btc_dai_order(),
         ) {
-            let round_tripped = wire::BtcDaiOrder::from_model(order.clone()).into_model();
+            let round_tripped = BtcDaiOrder::from(wire::BtcDaiOrder::from(order.clone()));
 
             assert_eq!(order, round_tripped);
         }
@@ -1,5 +1,5 @@
 use crate::{
-    asset, ethereum::ChainId, hbit, herc20, identity, ledger, Role, SecretHash, Timestamp,
+    asset, ethereum, ethereum::ChainId, hbit, herc20, identity, ledger, Role, SecretHash, Timestamp,
 };
 use anyhow::Result;
 use futures::prelude::*;
@@ -25,6 +25,16 @@ use std::{
 #[error("Already have role dependent parameters for this set of common parameters")]
 pub struct AlreadyHaveRoleParams;
 
+/// Why we refused to set up a swap proposed by another peer.
+///
+/// The reason is only reported locally, the peer is not told that its swap
+/// was declined.
+#[derive(Clone, Copy, Debug, PartialEq, thiserror::Error)]
+pub enum DeclineReason {
+    #[error("token contract {0} is not allowlisted")]
+    TokenNotAllowlisted(ethereum::Address),
+}
+
 #[derive(Clone, Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum BehaviourOutEvent<C> {
@@ -35,6 +45,12 @@ pub enum BehaviourOutEvent<C> {
         have: RoleDependentParams,
         received: RoleDependentParams,
     },
+    /// We refused the swap proposed by `peer`. The peer is not notified, on
+    /// its side the swap is simply never confirmed.
+    Declined {
+        peer: PeerId,
+        reason: DeclineReason,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -124,6 +140,9 @@ pub struct SetupSwap<C: Clone + Send + 'static> {
     swap_data: HashMap<CommonParams, RoleDependentParams>,
     #[behaviour(ignore)]
     context: HashMap<CommonParams, C>,
+    /// The token contracts we accept herc20 swaps for, any if empty.
+    #[behaviour(ignore)]
+    token_allowlist: Vec<ethereum::Address>,
 }
 
 impl<C: Clone + Send + 'static> Default for SetupSwap<C> {
@@ -142,11 +161,26 @@ impl<C: Clone + Send + 'static> Default for SetupSwap<C> {
             events: Default::default(),
             swap_data: Default::default(),
             context: Default::default(),
+            token_allowlist: Default::default(),
         }
     }
 }
 
 impl<C: Clone + Send + 'static> SetupSwap<C> {
+    /// Only accepts swaps proposed for one of the given token contracts. An
+    /// empty allowlist accepts any token.
+    ///
+    /// Declined swaps are reported with [`BehaviourOutEvent::Declined`] but
+    /// the proposer is not told about it, the setup-swap protocol has no way
+    /// to reply with an error. It never sees an executable swap and has to
+    /// give up on it after a timeout of its own.
+    pub fn with_token_allowlist(token_allowlist: Vec<ethereum::Address>) -> Self {
+        SetupSwap {
+            token_allowlist,
+            ..SetupSwap::default()
+        }
+    }
+
     pub fn send(
         &mut self,
         to: &PeerId,
@@ -215,6 +249,27 @@ impl<C: Clone + Send + 'static> SetupSwap<C> {
     fn bob_receive_herc20_hbit(&mut self, from: PeerId, common: CommonParams, alice: AliceParams) {
         self.bob_receive(from, common, alice, SwapProtocol::Herc20Hbit);
     }
+    /// Declines the swap if its parameters are not acceptable to us, returns
+    /// whether it was declined.
+    fn decline_if_unacceptable(&mut self, from: &PeerId, common: &CommonParams) -> bool {
+        let token_contract = common.erc20.token_contract;
+        if self.token_allowlist.is_empty() || self.token_allowlist.contains(&token_contract) {
+            return false;
+        }
+
+        let reason = DeclineReason::TokenNotAllowlisted(token_contract);
+        tracing::info!("declining swap proposed by {}: {}", from, reason);
+
+        self.swap_data.remove(common);
+        self.context.remove(common);
+        self.events.push_back(BehaviourOutEvent::Declined {
+            peer: from.clone(),
+            reason,
+        });
+
+        true
+    }
+
     fn alice_receive(
         &mut self,
         from: PeerId,
@@ -222,6 +277,10 @@ impl<C: Clone + Send + 'static> SetupSwap<C> {
         bob: BobParams,
         swap_protocol: SwapProtocol,
     ) {
+        if self.decline_if_unacceptable(&from, &common) {
+            return;
+        }
+
         match self.swap_data.get(&common) {
             Some(RoleDependentParams::Alice(alice)) => {
                 // todo: remove unwrap
@@ -259,6 +318,10 @@ impl<C: Clone + Send + 'static> SetupSwap<C> {
         alice: AliceParams,
         swap_protocol: SwapProtocol,
     ) {
+        if self.decline_if_unacceptable(&from, &common) {
+            return;
+        }
+
         match self.swap_data.get(&common) {
             Some(RoleDependentParams::Alice(have)) => {
                 self.events
@@ -521,7 +584,8 @@ mod tests {
         Secret,
     };
     use bitcoin::secp256k1;
-    use std::{future::Future, str::FromStr};
+    use futures::future::{self, Either};
+    use std::{future::Future, str::FromStr, time::Duration};
 
     #[tokio::test]
     async fn given_bob_sends_when_alice_sends_one_then_swap_is_confirmed() {
@@ -588,6 +652,109 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn swap_for_allowlisted_token_is_confirmed() {
+        let token = identity::Ethereum::random();
+        let (mut alice_swarm, _, alice_id) =
+            new_swarm(move |_, _| SetupSwap::with_token_allowlist(vec![token]));
+        let (mut bob_swarm, _, bob_id) = new_swarm(|_, _| SetupSwap::default());
+        connect(&mut alice_swarm, &mut bob_swarm).await;
+
+        let common = common_params(token);
+
+        bob_swarm
+            .send(
+                &alice_id,
+                bob_params(),
+                common.clone(),
+                SwapProtocol::Herc20Hbit,
+                2,
+            )
+            .expect("bob failed to send");
+        alice_swarm
+            .send(&bob_id, alice_params(), common, SwapProtocol::Herc20Hbit, 1)
+            .expect("alice failed to send");
+
+        assert_both_confirmed(alice_swarm.next(), bob_swarm.next(), 1, 2).await;
+    }
+
+    #[tokio::test]
+    async fn swap_for_token_not_on_allowlist_is_declined() {
+        let allowlisted = identity::Ethereum::random();
+        let (mut alice_swarm, _, alice_id) =
+            new_swarm(move |_, _| SetupSwap::<u32>::with_token_allowlist(vec![allowlisted]));
+        let (mut bob_swarm, _, bob_id) = new_swarm(|_, _| SetupSwap::<u32>::default());
+        connect(&mut alice_swarm, &mut bob_swarm).await;
+
+        let proposed = identity::Ethereum::random();
+        bob_swarm
+            .send(
+                &alice_id,
+                bob_params(),
+                common_params(proposed),
+                SwapProtocol::Herc20Hbit,
+                2,
+            )
+            .expect("bob failed to send");
+
+        // bob never hears back, hence we only wait for alice
+        let event = tokio::time::timeout(
+            Duration::from_secs(10),
+            future::select(Box::pin(alice_swarm.next()), Box::pin(bob_swarm.next())),
+        )
+        .await
+        .expect("timed out waiting for alice to decline");
+
+        match event {
+            Either::Left((BehaviourOutEvent::Declined { peer, reason }, _)) => {
+                assert_eq!(peer, bob_id);
+                assert_eq!(reason, DeclineReason::TokenNotAllowlisted(proposed));
+            }
+            Either::Left((event, _)) => panic!("expected alice to decline but got {:?}", event),
+            Either::Right((event, _)) => panic!("bob unexpectedly emitted {:?}", event),
+        }
+    }
+
+    fn common_params(token_contract: ethereum::Address) -> CommonParams {
+        CommonParams {
+            erc20: asset::Erc20::new(token_contract, asset::Erc20Quantity::zero()),
+            bitcoin: asset::Bitcoin::from_sat(0),
+            ethereum_absolute_expiry: 0,
+            bitcoin_absolute_expiry: 0,
+            ethereum_chain_id: ChainId::GETH_DEV,
+            bitcoin_network: ledger::Bitcoin::Regtest,
+        }
+    }
+
+    fn bob_params() -> RoleDependentParams {
+        RoleDependentParams::Bob(BobParams {
+            ethereum_identity: identity::Ethereum::random(),
+            bitcoin_identity: bitcoin_identity(),
+        })
+    }
+
+    fn alice_params() -> RoleDependentParams {
+        RoleDependentParams::Alice(AliceParams {
+            ethereum_identity: identity::Ethereum::random(),
+            bitcoin_identity: bitcoin_identity(),
+            secret_hash: SecretHash::new(
+                Secret::from_str(
+                    "68d627971643a6f97f27c58957826fcba853ec2077fd10ec6b93d8e61deb4c66",
+                )
+                .expect("could not convert string to secret"),
+            ),
+        })
+    }
+
+    fn bitcoin_identity() -> identity::Bitcoin {
+        identity::Bitcoin::from(
+            secp256k1::PublicKey::from_str(
+                "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275",
+            )
+            .unwrap(),
+        )
+    }
+
     async fn assert_both_confirmed<C: PartialEq + Debug>(
         alice_event: impl Future<Output = BehaviourOutEvent<C>>,
         bob_event: impl Future<Output = BehaviourOutEvent<C>>,
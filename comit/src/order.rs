@@ -1,7 +1,7 @@
 use crate::{
     asset::{Bitcoin, Erc20Quantity},
     expiries,
-    expiries::{AlphaOffset, BetaOffset},
+    expiries::{AlphaOffset, BetaOffset, ExpiryConfig},
     Network, Role,
 };
 use serde::{Deserialize, Serialize};
@@ -42,12 +42,20 @@ impl From<Uuid> for OrderId {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Serializes the same way as orders are sent to other nodes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(
+    into = "crate::network::orderbook::wire::BtcDaiOrder",
+    from = "crate::network::orderbook::wire::BtcDaiOrder"
+)]
 pub struct BtcDaiOrder {
     pub id: OrderId,
     pub position: Position,
     pub swap_protocol: SwapProtocol,
     pub created_at: OffsetDateTime,
+    /// The order must no longer be taken after this point in time, orders
+    /// without it stay valid until they are cancelled.
+    pub valid_until: Option<OffsetDateTime>,
     pub quantity: Quantity<Bitcoin>,
     pub price: Price<Bitcoin, Erc20Quantity>,
 }
@@ -82,12 +90,18 @@ impl BtcDaiOrder {
             price,
             swap_protocol,
             created_at: OffsetDateTime::now_utc(),
+            valid_until: None,
         }
     }
 
     pub fn quote(&self) -> Erc20Quantity {
         self.quantity * self.price.clone()
     }
+
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.valid_until
+            .map_or(false, |valid_until| now >= valid_until)
+    }
 }
 
 /// A newtype representing a quantity in a certain base currency B.
@@ -185,6 +199,7 @@ impl BtcDaiOrder {
             price,
             swap_protocol,
             created_at,
+            valid_until: None,
         }
     }
 }
@@ -246,7 +261,13 @@ pub enum Position {
 /// (waiting for some number of confirmations) before he moves forward with the
 /// lock on the [`Beta`](Side::Beta) side. Hence, the _duration_ of the lock
 /// differs from the offset specified here.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+///
+/// Serializes the same way as it is sent to other nodes as part of an order.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(
+    into = "crate::network::orderbook::wire::SwapProtocol",
+    from = "crate::network::orderbook::wire::SwapProtocol"
+)]
 pub enum SwapProtocol {
     HbitHerc20 {
         hbit_expiry_offset: AlphaOffset,
@@ -310,10 +331,21 @@ impl SwapProtocol {
     }
 
     pub fn new(role: Role, position: Position, network: Network) -> Self {
+        Self::with_expiry_config(role, position, network, ExpiryConfig::from_network(network))
+    }
+
+    /// Creates the swap protocol with expiry offsets derived from the
+    /// confirmation targets of `expiry_config`.
+    pub fn with_expiry_config(
+        role: Role,
+        position: Position,
+        network: Network,
+        expiry_config: ExpiryConfig,
+    ) -> Self {
         match (role, position) {
             (Role::Bob, Position::Buy) | (Role::Alice, Position::Sell) => {
                 let (hbit_expiry_offset, herc20_expiry_offset) =
-                    expiries::expiry_offsets_hbit_herc20(network);
+                    expiries::expiry_offsets_hbit_herc20_with_config(network, expiry_config);
 
                 SwapProtocol::HbitHerc20 {
                     hbit_expiry_offset,
@@ -322,7 +354,7 @@ impl SwapProtocol {
             }
             (Role::Alice, Position::Buy) | (Role::Bob, Position::Sell) => {
                 let (herc20_expiry_offset, hbit_expiry_offset) =
-                    expiries::expiry_offsets_herc20_hbit(network);
+                    expiries::expiry_offsets_herc20_hbit_with_config(network, expiry_config);
 
                 SwapProtocol::Herc20Hbit {
                     hbit_expiry_offset,
@@ -360,6 +392,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn order_expires_at_valid_until() {
+        let valid_until = OffsetDateTime::from_unix_timestamp(1_000);
+        let order = BtcDaiOrder {
+            valid_until: Some(valid_until),
+            ..BtcDaiOrder::buy(
+                btc(1.0),
+                dai_per_btc(9_000),
+                SwapProtocol::new(Role::Alice, Position::Buy, Network::Main),
+            )
+        };
+
+        assert!(!order.is_expired(valid_until - Duration::seconds(1)));
+        assert!(order.is_expired(valid_until));
+    }
+
+    #[test]
+    fn order_without_valid_until_never_expires() {
+        let order = BtcDaiOrder::buy(
+            btc(1.0),
+            dai_per_btc(9_000),
+            SwapProtocol::new(Role::Alice, Position::Buy, Network::Main),
+        );
+
+        assert!(!order.is_expired(OffsetDateTime::from_unix_timestamp(253_402_300_799)));
+    }
+
     #[test]
     fn dai_per_btc_turns_into_wei_per_sat() {
         // 1 BTC : 9_000 DAI = 1 BTC : 9_000_000_000_000_000_000_000 WEI = 100_000_000
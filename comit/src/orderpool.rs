@@ -17,6 +17,8 @@ use time::OffsetDateTime;
 #[derive(Debug)]
 pub struct OrderPool {
     inner: HashMap<PeerId, HashMap<OrderId, BtcDaiOrder>>,
+    /// When we last received the orders of each maker.
+    last_seen: HashMap<PeerId, OffsetDateTime>,
 
     reserved_quantities: HashMap<OrderId, asset::Bitcoin>,
     /// Our own id.
@@ -26,6 +28,34 @@ pub struct OrderPool {
 
     /// A cache for storing which orders don't match.
     no_match_cache: LruCache<NoMatch, ()>,
+
+    /// Matches smaller than this are not worth the fees of the swap.
+    minimum: MinimumMatch,
+}
+
+/// The smallest match worth swapping, in quantity and in value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MinimumMatch {
+    pub quantity: Option<asset::Bitcoin>,
+    pub value: Option<Erc20Quantity>,
+}
+
+impl MinimumMatch {
+    fn allows(
+        &self,
+        quantity: Quantity<asset::Bitcoin>,
+        price: &Price<asset::Bitcoin, Erc20Quantity>,
+    ) -> bool {
+        let big_enough = self
+            .quantity
+            .map_or(true, |min_quantity| quantity.to_inner() >= min_quantity);
+        let worth_enough = self
+            .value
+            .as_ref()
+            .map_or(true, |min_value| &(quantity * price.clone()) >= min_value);
+
+        big_enough && worth_enough
+    }
 }
 
 /// Serves as the key in our no-match cache.
@@ -45,13 +75,20 @@ impl OrderPool {
     pub fn new(me: PeerId) -> Self {
         Self {
             inner: Default::default(),
+            last_seen: Default::default(),
             reserved_quantities: Default::default(),
             me,
             no_match_cache: LruCache::new(100), /* cap this at a 100 entries to avoid unbounded
                                                  * memory growth */
+            minimum: MinimumMatch::default(),
         }
     }
 
+    /// Only match orders for at least `minimum`.
+    pub fn with_minimum(self, minimum: MinimumMatch) -> Self {
+        Self { minimum, ..self }
+    }
+
     /// Get the peer id of the maker of this order.
     pub fn maker_id(&self, id: OrderId) -> Option<PeerId> {
         for (maker, orders) in self.inner.iter() {
@@ -77,13 +114,44 @@ impl OrderPool {
     /// This replaces all current orders of this peer with the newly received
     /// ones.
     pub fn receive(&mut self, maker: PeerId, orders: Vec<BtcDaiOrder>) {
+        self.receive_seen_at(maker, orders, OffsetDateTime::now_utc());
+    }
+
+    /// Receive other people's orders which we saw at `seen_at`, e.g. orders
+    /// that were saved before a restart.
+    pub fn receive_seen_at(
+        &mut self,
+        maker: PeerId,
+        orders: Vec<BtcDaiOrder>,
+        seen_at: OffsetDateTime,
+    ) {
         let map = HashMap::from_iter(orders.into_iter().map(|o| (o.id, o)));
 
+        self.last_seen.insert(maker.clone(), seen_at);
         self.inner.insert(maker, map);
     }
 
     pub fn remove_all_from(&mut self, maker: &PeerId) {
         self.inner.remove(maker);
+        self.last_seen.remove(maker);
+    }
+
+    /// Remove the orders of all makers we did not hear from since `cutoff`.
+    ///
+    /// Returns the makers whose orders were removed.
+    pub fn remove_not_seen_since(&mut self, cutoff: OffsetDateTime) -> Vec<PeerId> {
+        let stale = self
+            .last_seen
+            .iter()
+            .filter(|(_, seen_at)| **seen_at < cutoff)
+            .map(|(maker, _)| maker.clone())
+            .collect::<Vec<_>>();
+
+        for maker in stale.iter() {
+            self.remove_all_from(maker);
+        }
+
+        stale
     }
 
     pub fn clear_own_orders(&mut self) {
@@ -220,7 +288,9 @@ impl OrderPool {
                     continue;
                 }
 
-                if let Some(r#match) = match_orders(ours, theirs, reserved_ours, reserved_theirs) {
+                if let Some(r#match) =
+                    match_orders(ours, theirs, reserved_ours, reserved_theirs, &self.minimum)
+                {
                     let quantity = r#match.quantity;
 
                     matches.push(Match {
@@ -295,6 +365,7 @@ fn match_orders(
     right: &BtcDaiOrder,
     reserved_left: &asset::Bitcoin,
     reserved_right: &asset::Bitcoin,
+    minimum: &MinimumMatch,
 ) -> Option<InternalMatch> {
     use Position::*;
 
@@ -322,27 +393,42 @@ fn match_orders(
         return None;
     }
 
-    // TODO: partial order matching
-    if left.quantity != right.quantity {
-        tracing::trace!("orders with different quantities don't match");
-        return None;
-    }
-
-    let remaining_left = left.quantity.to_inner() - *reserved_left;
-    let remaining_right = right.quantity.to_inner() - *reserved_right;
+    let remaining_left = left
+        .quantity
+        .to_inner()
+        .checked_sub(*reserved_left)
+        .unwrap_or(asset::Bitcoin::ZERO);
+    let remaining_right = right
+        .quantity
+        .to_inner()
+        .checked_sub(*reserved_right)
+        .unwrap_or(asset::Bitcoin::ZERO);
 
     if remaining_left == asset::Bitcoin::ZERO || remaining_right == asset::Bitcoin::ZERO {
         tracing::trace!("cannot fill order because of existing reserved funds");
         return None;
     }
 
-    let quantity = remaining_left;
+    // the bigger order stays in the pool with whatever is left of it
+    let quantity = Quantity::new(std::cmp::min(remaining_left, remaining_right));
 
-    tracing::info!("matched with {} at price {}", quantity, price.wei_per_sat());
+    if !minimum.allows(quantity, price) {
+        tracing::trace!(
+            "match of {} is too small to be worth a swap",
+            quantity.to_inner()
+        );
+        return None;
+    }
+
+    tracing::info!(
+        "matched with {} at price {}",
+        quantity.to_inner(),
+        price.wei_per_sat()
+    );
 
     Some(InternalMatch {
         price: price.clone(),
-        quantity: Quantity::new(quantity),
+        quantity,
     })
 }
 
@@ -369,7 +455,13 @@ mod tests {
         let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
         let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
 
-        let r#match = match_orders(&buy, &sell, &Bitcoin::ZERO, &Bitcoin::ZERO);
+        let r#match = match_orders(
+            &buy,
+            &sell,
+            &Bitcoin::ZERO,
+            &Bitcoin::ZERO,
+            &MinimumMatch::default(),
+        );
 
         assert_that(&r#match).is_some().is_equal_to(&InternalMatch {
             price: dai_per_btc(9000),
@@ -382,7 +474,13 @@ mod tests {
         let sell_1 = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
         let sell_2 = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
 
-        let r#match = match_orders(&sell_1, &sell_2, &Bitcoin::ZERO, &Bitcoin::ZERO);
+        let r#match = match_orders(
+            &sell_1,
+            &sell_2,
+            &Bitcoin::ZERO,
+            &Bitcoin::ZERO,
+            &MinimumMatch::default(),
+        );
 
         assert_that(&r#match).is_none();
     }
@@ -392,7 +490,13 @@ mod tests {
         let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
         let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(8500), herc20_hbit());
 
-        let r#match = match_orders(&sell, &buy, &Bitcoin::ZERO, &Bitcoin::ZERO);
+        let r#match = match_orders(
+            &sell,
+            &buy,
+            &Bitcoin::ZERO,
+            &Bitcoin::ZERO,
+            &MinimumMatch::default(),
+        );
 
         assert_that(&r#match).is_none();
     }
@@ -402,7 +506,13 @@ mod tests {
         let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(8500), herc20_hbit());
         let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
 
-        let r#match = match_orders(&sell, &buy, &Bitcoin::ZERO, &Bitcoin::ZERO);
+        let r#match = match_orders(
+            &sell,
+            &buy,
+            &Bitcoin::ZERO,
+            &Bitcoin::ZERO,
+            &MinimumMatch::default(),
+        );
 
         assert_that(&r#match).is_some().is_equal_to(&InternalMatch {
             price: dai_per_btc(8500),
@@ -410,15 +520,42 @@ mod tests {
         });
     }
 
-    // only temporary until we take care of partial matching properly
     #[test]
-    fn given_different_quantities_then_no_match() {
+    fn given_different_quantities_then_matches_smaller_quantity() {
         let sell = BtcDaiOrder::sell(btc(0.5), dai_per_btc(9000), herc20_hbit());
         let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
 
-        let r#match = match_orders(&sell, &buy, &Bitcoin::ZERO, &Bitcoin::ZERO);
+        let r#match = match_orders(
+            &sell,
+            &buy,
+            &Bitcoin::ZERO,
+            &Bitcoin::ZERO,
+            &MinimumMatch::default(),
+        );
 
-        assert_that(&r#match).is_none();
+        assert_that(&r#match).is_some().is_equal_to(&InternalMatch {
+            price: dai_per_btc(9000),
+            quantity: btc(0.5),
+        });
+    }
+
+    #[test]
+    fn given_reserved_quantity_on_smaller_order_then_matches_what_remains_of_it() {
+        let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let buy = BtcDaiOrder::buy(btc(0.5), dai_per_btc(9000), herc20_hbit());
+
+        let r#match = match_orders(
+            &sell,
+            &buy,
+            &Bitcoin::ZERO,
+            &btc(0.25).to_inner(),
+            &MinimumMatch::default(),
+        );
+
+        assert_that(&r#match).is_some().is_equal_to(&InternalMatch {
+            price: dai_per_btc(9000),
+            quantity: btc(0.25),
+        });
     }
 
     #[test]
@@ -426,7 +563,13 @@ mod tests {
         let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
         let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
 
-        let r#match = match_orders(&sell, &buy, &btc(0.5).to_inner(), &Bitcoin::ZERO);
+        let r#match = match_orders(
+            &sell,
+            &buy,
+            &btc(0.5).to_inner(),
+            &Bitcoin::ZERO,
+            &MinimumMatch::default(),
+        );
 
         assert_that(&r#match).is_some().is_equal_to(&InternalMatch {
             price: dai_per_btc(9000),
@@ -439,7 +582,13 @@ mod tests {
         let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
         let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
 
-        let r#match = match_orders(&sell, &buy, &Bitcoin::ONE_BTC, &Bitcoin::ZERO);
+        let r#match = match_orders(
+            &sell,
+            &buy,
+            &Bitcoin::ONE_BTC,
+            &Bitcoin::ZERO,
+            &MinimumMatch::default(),
+        );
 
         assert_that(&r#match).is_none();
     }
@@ -449,7 +598,13 @@ mod tests {
         let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
         let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), hbit_herc20());
 
-        let r#match = match_orders(&sell, &buy, &Bitcoin::ZERO, &Bitcoin::ZERO);
+        let r#match = match_orders(
+            &sell,
+            &buy,
+            &Bitcoin::ZERO,
+            &Bitcoin::ZERO,
+            &MinimumMatch::default(),
+        );
 
         assert_that(&r#match).is_none();
     }
@@ -465,7 +620,43 @@ mod tests {
             herc20_expiry_offset: 1.hours().into(),
         });
 
-        let r#match = match_orders(&sell, &buy, &Bitcoin::ZERO, &Bitcoin::ZERO);
+        let r#match = match_orders(
+            &sell,
+            &buy,
+            &Bitcoin::ZERO,
+            &Bitcoin::ZERO,
+            &MinimumMatch::default(),
+        );
+
+        assert_that(&r#match).is_none();
+    }
+
+    #[test]
+    fn given_remaining_quantity_below_minimum_then_no_match() {
+        let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let minimum = MinimumMatch {
+            quantity: Some(btc(0.5).to_inner()),
+            value: None,
+        };
+
+        let whole = match_orders(&sell, &buy, &Bitcoin::ZERO, &Bitcoin::ZERO, &minimum);
+        let rest = match_orders(&sell, &buy, &btc(0.75).to_inner(), &Bitcoin::ZERO, &minimum);
+
+        assert_that(&whole).is_some();
+        assert_that(&rest).is_none();
+    }
+
+    #[test]
+    fn given_match_worth_less_than_minimum_value_then_no_match() {
+        let sell = BtcDaiOrder::sell(btc(0.1), dai_per_btc(9000), herc20_hbit());
+        let buy = BtcDaiOrder::buy(btc(0.1), dai_per_btc(9000), herc20_hbit());
+        let minimum = MinimumMatch {
+            quantity: None,
+            value: Some(btc(1.0) * dai_per_btc(1000)),
+        };
+
+        let r#match = match_orders(&sell, &buy, &Bitcoin::ZERO, &Bitcoin::ZERO, &minimum);
 
         assert_that(&r#match).is_none();
     }
@@ -503,8 +694,8 @@ mod tests {
             reserved_right in proptest::asset::bitcoin()
         ) {
 
-            let first_match = match_orders(&left, &right, &reserved_left, &reserved_right);
-            let second_match = match_orders(&right, &left, &reserved_right, &reserved_left);
+            let first_match = match_orders(&left, &right, &reserved_left, &reserved_right, &MinimumMatch::default());
+            let second_match = match_orders(&right, &left, &reserved_right, &reserved_left, &MinimumMatch::default());
 
             assert_eq!(first_match, second_match);
         }
@@ -548,6 +739,46 @@ mod tests {
         assert_that(&pool.ours().next()).is_none();
     }
 
+    #[test]
+    fn given_maker_not_seen_since_cutoff_then_their_orders_are_removed() {
+        let mut pool = OrderPool::new(PeerId::random());
+        let now = OffsetDateTime::now_utc();
+        let stale_maker = PeerId::random();
+        let fresh_maker = PeerId::random();
+
+        pool.publish(BtcDaiOrder::buy(btc(0.5), dai_per_btc(9000), hbit_herc20()));
+        pool.receive_seen_at(
+            stale_maker.clone(),
+            vec![BtcDaiOrder::sell(
+                btc(0.5),
+                dai_per_btc(9100),
+                hbit_herc20(),
+            )],
+            now - 10.minutes(),
+        );
+        pool.receive_seen_at(
+            fresh_maker.clone(),
+            vec![BtcDaiOrder::sell(
+                btc(0.5),
+                dai_per_btc(9100),
+                hbit_herc20(),
+            )],
+            now,
+        );
+
+        let removed = pool.remove_not_seen_since(now - 5.minutes());
+
+        assert_eq!(removed, vec![stale_maker]);
+        assert_that(
+            &pool
+                .theirs()
+                .map(|(maker, _)| maker.clone())
+                .collect::<Vec<_>>(),
+        )
+        .is_equal_to(vec![fresh_maker]);
+        assert_that(&pool.ours().count()).is_equal_to(1);
+    }
+
     fn hbit_herc20() -> SwapProtocol {
         SwapProtocol::HbitHerc20 {
             hbit_expiry_offset: 0.seconds().into(),
@@ -1,4 +1,4 @@
-use crate::{order::SwapProtocol, Role};
+use crate::{order::SwapProtocol, Role, Secret, SecretHash};
 pub use proptest::prelude::*;
 
 pub mod ethereum {
@@ -93,6 +93,14 @@ pub fn role() -> impl Strategy<Value = Role> {
     prop_oneof![Just(Role::Alice), Just(Role::Bob)]
 }
 
+pub fn secret() -> impl Strategy<Value = Secret> {
+    any::<[u8; 32]>().prop_map(Secret::from)
+}
+
+pub fn secret_and_hash() -> impl Strategy<Value = (Secret, SecretHash)> {
+    secret().prop_map(|secret| (secret, SecretHash::new(secret)))
+}
+
 pub mod time {
     use super::*;
     use ::time::*;
@@ -108,6 +116,12 @@ pub mod time {
             OffsetDateTime::from_unix_timestamp(unix_timestamp as i64)
         }
     }
+
+    prop_compose! {
+        pub fn timestamp()(seconds in any::<u32>()) -> crate::Timestamp {
+            crate::Timestamp::from(seconds)
+        }
+    }
 }
 
 pub mod expiries {
@@ -153,7 +153,7 @@ impl quickcheck::Arbitrary for SecretHash {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Secret;
+    use crate::{proptest, Secret};
 
     #[test]
     fn new_secret_hash_as_hex() {
@@ -179,4 +179,12 @@ mod tests {
             "68d627971643a6f97f27c58957826fcba853ec2077fd10ec6b93d8e61deb4cec"
         )
     }
+
+    proptest::proptest! {
+        #[test]
+        fn secret_hash_is_stable((secret, secret_hash) in proptest::secret_and_hash()) {
+            assert_eq!(SecretHash::new(secret), secret_hash);
+            assert_eq!(SecretHash::new(secret), SecretHash::new(secret));
+        }
+    }
 }
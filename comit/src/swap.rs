@@ -1,8 +1,10 @@
 pub mod actions;
+pub mod hbit_hbit;
 pub mod hbit_herc20;
 pub mod herc20_hbit;
 
 pub use crate::{ethereum, *};
+pub use hbit_hbit::{hbit_hbit_alice, hbit_hbit_bob};
 pub use hbit_herc20::{hbit_herc20_alice, hbit_herc20_bob};
 pub use herc20_hbit::{herc20_hbit_alice, herc20_hbit_bob};
 
@@ -60,6 +60,20 @@ pub struct DeployContract {
     pub amount: asset::Ether,
     pub gas_limit: u64,
     pub chain_id: ChainId,
+    /// EIP-1559 fee cap, the legacy gas price is used if not set.
+    pub max_fee_per_gas: Option<asset::Ether>,
+    /// EIP-1559 tip, the legacy gas price is used if not set.
+    pub max_priority_fee_per_gas: Option<asset::Ether>,
+}
+
+impl DeployContract {
+    pub fn fee_mode(&self) -> FeeMode {
+        FeeMode::select(
+            self.chain_id,
+            self.max_fee_per_gas.clone(),
+            self.max_priority_fee_per_gas.clone(),
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -68,4 +82,110 @@ pub struct CallContract {
     pub data: Option<Vec<u8>>,
     pub gas_limit: u64,
     pub chain_id: ChainId,
+    /// EIP-1559 fee cap, the legacy gas price is used if not set.
+    pub max_fee_per_gas: Option<asset::Ether>,
+    /// EIP-1559 tip, the legacy gas price is used if not set.
+    pub max_priority_fee_per_gas: Option<asset::Ether>,
+}
+
+impl CallContract {
+    pub fn fee_mode(&self) -> FeeMode {
+        FeeMode::select(
+            self.chain_id,
+            self.max_fee_per_gas.clone(),
+            self.max_priority_fee_per_gas.clone(),
+        )
+    }
+}
+
+/// How an ethereum transaction pays for its gas.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeeMode {
+    /// A legacy transaction paying a gas price chosen by the sender.
+    Legacy,
+    /// A type 2 transaction as per EIP-1559.
+    Eip1559 {
+        max_fee_per_gas: asset::Ether,
+        max_priority_fee_per_gas: asset::Ether,
+    },
+}
+
+impl FeeMode {
+    /// Type 2 transactions are only built if both fees are set and the chain
+    /// supports them, otherwise we fall back to a legacy transaction.
+    pub fn select(
+        chain_id: ChainId,
+        max_fee_per_gas: Option<asset::Ether>,
+        max_priority_fee_per_gas: Option<asset::Ether>,
+    ) -> Self {
+        match (max_fee_per_gas, max_priority_fee_per_gas) {
+            (Some(max_fee_per_gas), Some(max_priority_fee_per_gas))
+                if chain_id.supports_eip1559() =>
+            {
+                FeeMode::Eip1559 {
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                }
+            }
+            _ => FeeMode::Legacy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::ethereum::FromWei;
+
+    fn fees() -> (Option<asset::Ether>, Option<asset::Ether>) {
+        (
+            Some(asset::Ether::from_wei(100_000_000_000u64)),
+            Some(asset::Ether::from_wei(2_000_000_000u64)),
+        )
+    }
+
+    #[test]
+    fn selects_eip1559_on_london_chains() {
+        let (max_fee, max_priority_fee) = fees();
+
+        for chain_id in &[ChainId::MAINNET, ChainId::ROPSTEN, ChainId::KOVAN] {
+            assert_eq!(
+                FeeMode::select(*chain_id, max_fee.clone(), max_priority_fee.clone()),
+                FeeMode::Eip1559 {
+                    max_fee_per_gas: max_fee.clone().unwrap(),
+                    max_priority_fee_per_gas: max_priority_fee.clone().unwrap(),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn selects_legacy_on_unknown_chains() {
+        let (max_fee, max_priority_fee) = fees();
+
+        for chain_id in &[ChainId::GETH_DEV, ChainId::from(31337)] {
+            assert_eq!(
+                FeeMode::select(*chain_id, max_fee.clone(), max_priority_fee.clone()),
+                FeeMode::Legacy
+            );
+        }
+    }
+
+    #[test]
+    fn selects_legacy_without_fees() {
+        let (max_fee, max_priority_fee) = fees();
+
+        assert_eq!(
+            FeeMode::select(ChainId::MAINNET, None, None),
+            FeeMode::Legacy
+        );
+        assert_eq!(
+            FeeMode::select(ChainId::MAINNET, max_fee, None),
+            FeeMode::Legacy
+        );
+        assert_eq!(
+            FeeMode::select(ChainId::MAINNET, None, max_priority_fee),
+            FeeMode::Legacy
+        );
+    }
 }
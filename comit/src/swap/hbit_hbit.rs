@@ -0,0 +1,299 @@
+use crate::{
+    swap::{hbit, Action, Error},
+    Secret,
+};
+use bitcoin::secp256k1::{Secp256k1, Signing};
+use futures::Stream;
+use genawaiter::sync::Gen;
+use time::OffsetDateTime;
+
+/// Execute a Hbit<->Hbit swap for Alice.
+pub fn hbit_hbit_alice<A, B, C>(
+    alpha: A,
+    beta: B,
+    secp: Secp256k1<C>,
+    alpha_params: hbit::Params,
+    beta_params: hbit::Params,
+    secret: Secret,
+    utc_start_of_swap: OffsetDateTime,
+) -> impl Stream<Item = Result<Action, Error<hbit::IncorrectlyFunded, hbit::IncorrectlyFunded>>>
+where
+    A: hbit::WatchForFunded + hbit::WatchForRedeemed,
+    B: hbit::WatchForFunded + hbit::WatchForRedeemed,
+    C: Signing,
+{
+    Gen::new(|co| async move {
+        tracing::info!("starting swap");
+
+        co.yield_(Ok(Action::HbitFund(alpha_params.build_fund_action())))
+            .await;
+        let alpha_funded = match alpha
+            .watch_for_funded(&alpha_params, utc_start_of_swap)
+            .await
+        {
+            Ok(alpha_funded) => alpha_funded,
+            Err(e) => {
+                co.yield_(Err(Error::AlphaIncorrectlyFunded(e))).await;
+                return;
+            }
+        };
+
+        tracing::info!("we funded the alpha hbit htlc");
+
+        let beta_funded = match beta.watch_for_funded(&beta_params, utc_start_of_swap).await {
+            Ok(beta_funded) => beta_funded,
+            Err(e) => {
+                co.yield_(Err(Error::BetaIncorrectlyFunded(e))).await;
+                return;
+            }
+        };
+
+        tracing::info!("bob funded the beta hbit htlc");
+
+        co.yield_(Ok(Action::HbitRedeem(
+            beta_params.build_redeem_action(&secp, beta_funded.location, secret),
+            secret,
+        )))
+        .await;
+        let _ = beta
+            .watch_for_redeemed(&beta_params, beta_funded, utc_start_of_swap)
+            .await;
+
+        tracing::info!("we redeemed the beta hbit htlc");
+
+        let _ = alpha
+            .watch_for_redeemed(&alpha_params, alpha_funded, utc_start_of_swap)
+            .await;
+
+        tracing::info!("bob redeemed the alpha hbit htlc");
+    })
+}
+
+/// Execute a Hbit<->Hbit swap for Bob.
+pub fn hbit_hbit_bob<A, B, C>(
+    alpha: A,
+    beta: B,
+    secp: Secp256k1<C>,
+    alpha_params: hbit::Params,
+    beta_params: hbit::Params,
+    utc_start_of_swap: OffsetDateTime,
+) -> impl Stream<Item = Result<Action, Error<hbit::IncorrectlyFunded, hbit::IncorrectlyFunded>>>
+where
+    A: hbit::WatchForFunded + hbit::WatchForRedeemed,
+    B: hbit::WatchForFunded + hbit::WatchForRedeemed,
+    C: Signing,
+{
+    Gen::new(|co| async move {
+        tracing::info!("starting swap");
+
+        let alpha_funded = match alpha
+            .watch_for_funded(&alpha_params, utc_start_of_swap)
+            .await
+        {
+            Ok(alpha_funded) => alpha_funded,
+            Err(e) => {
+                co.yield_(Err(Error::AlphaIncorrectlyFunded(e))).await;
+                return;
+            }
+        };
+
+        tracing::info!("alice funded the alpha hbit htlc");
+
+        co.yield_(Ok(Action::HbitFund(beta_params.build_fund_action())))
+            .await;
+        let beta_funded = match beta.watch_for_funded(&beta_params, utc_start_of_swap).await {
+            Ok(beta_funded) => beta_funded,
+            Err(e) => {
+                co.yield_(Err(Error::BetaIncorrectlyFunded(e))).await;
+                return;
+            }
+        };
+
+        tracing::info!("we funded the beta hbit htlc");
+
+        let beta_redeemed = beta
+            .watch_for_redeemed(&beta_params, beta_funded, utc_start_of_swap)
+            .await;
+
+        tracing::info!("alice redeemed the beta hbit htlc");
+
+        co.yield_(Ok(Action::HbitRedeem(
+            alpha_params.build_redeem_action(&secp, alpha_funded.location, beta_redeemed.secret),
+            beta_redeemed.secret,
+        )))
+        .await;
+        let _ = alpha
+            .watch_for_redeemed(&alpha_params, alpha_funded, utc_start_of_swap)
+            .await;
+
+        tracing::info!("we redeemed the alpha hbit htlc");
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{asset, identity, ledger, SecretHash, Timestamp};
+    use bitcoin::{hashes::Hash, secp256k1::SecretKey, Address, OutPoint, Txid};
+    use futures::{future, TryStreamExt};
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    /// A bitcoin network on which HTLCs are funded and redeemed as soon as
+    /// the corresponding action is executed.
+    #[derive(Clone, Debug, Default)]
+    struct MockLedger {
+        funded: Arc<Mutex<Option<hbit::Funded>>>,
+        redeemed: Arc<Mutex<Option<hbit::Redeemed>>>,
+    }
+
+    impl MockLedger {
+        fn execute(&self, action: Action) {
+            match action {
+                Action::HbitFund(_) => {
+                    *self.funded.lock().unwrap() = Some(hbit::Funded {
+                        location: OutPoint::new(Txid::from_inner([1u8; 32]), 0),
+                    })
+                }
+                Action::HbitRedeem(_, secret) => {
+                    *self.redeemed.lock().unwrap() = Some(hbit::Redeemed {
+                        transaction: Txid::from_inner([2u8; 32]),
+                        secret,
+                    })
+                }
+                action => panic!("unexpected action {:?}", action),
+            }
+        }
+
+        fn redeemed(&self) -> Option<hbit::Redeemed> {
+            *self.redeemed.lock().unwrap()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl hbit::WatchForFunded for MockLedger {
+        async fn watch_for_funded(
+            &self,
+            _: &hbit::Params,
+            _: OffsetDateTime,
+        ) -> Result<hbit::Funded, hbit::IncorrectlyFunded> {
+            loop {
+                if let Some(funded) = *self.funded.lock().unwrap() {
+                    return Ok(funded);
+                }
+                tokio::time::delay_for(Duration::from_millis(10)).await;
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl hbit::WatchForRedeemed for MockLedger {
+        async fn watch_for_redeemed(
+            &self,
+            _: &hbit::Params,
+            _: hbit::Funded,
+            _: OffsetDateTime,
+        ) -> hbit::Redeemed {
+            loop {
+                if let Some(redeemed) = self.redeemed() {
+                    return redeemed;
+                }
+                tokio::time::delay_for(Duration::from_millis(10)).await;
+            }
+        }
+    }
+
+    fn params(
+        network: ledger::Bitcoin,
+        secret_hash: SecretHash,
+        redeemer: SecretKey,
+        refunder: SecretKey,
+    ) -> (hbit::Params, hbit::Params) {
+        let secp = Secp256k1::signing_only();
+        let redeem_identity = identity::Bitcoin::from_secret_key(&secp, &redeemer);
+        let refund_identity = identity::Bitcoin::from_secret_key(&secp, &refunder);
+        let shared = hbit::SharedParams {
+            network,
+            asset: asset::Bitcoin::from_sat(100_000),
+            redeem_identity,
+            refund_identity,
+            expiry: Timestamp::now().plus(60 * 60),
+            secret_hash,
+        };
+
+        let redeemer = hbit::Params {
+            shared,
+            transient_sk: redeemer,
+            final_address: Address::p2wpkh(&redeem_identity.into(), network.into()).unwrap(),
+        };
+        let refunder = hbit::Params {
+            shared,
+            transient_sk: refunder,
+            final_address: Address::p2wpkh(&refund_identity.into(), network.into()).unwrap(),
+        };
+
+        (redeemer, refunder)
+    }
+
+    #[tokio::test]
+    async fn both_sides_fund_and_redeem() {
+        let secret = Secret::from(*b"hello world, you are beautiful!!");
+        let secret_hash = SecretHash::new(secret);
+        let start_of_swap = OffsetDateTime::now_utc();
+        let alice_sk = SecretKey::from_slice(&[0x01; 32]).unwrap();
+        let bob_sk = SecretKey::from_slice(&[0x02; 32]).unwrap();
+
+        let (alpha_bob, alpha_alice) =
+            params(ledger::Bitcoin::Regtest, secret_hash, bob_sk, alice_sk);
+        let (beta_alice, beta_bob) =
+            params(ledger::Bitcoin::Testnet, secret_hash, alice_sk, bob_sk);
+
+        let alpha = MockLedger::default();
+        let beta = MockLedger::default();
+
+        let alice = hbit_hbit_alice(
+            alpha.clone(),
+            beta.clone(),
+            Secp256k1::signing_only(),
+            alpha_alice,
+            beta_alice,
+            secret,
+            start_of_swap,
+        )
+        .try_for_each(|action| {
+            match &action {
+                Action::HbitFund(_) => alpha.execute(action),
+                _ => beta.execute(action),
+            }
+            future::ok(())
+        });
+        let bob = hbit_hbit_bob(
+            alpha.clone(),
+            beta.clone(),
+            Secp256k1::signing_only(),
+            alpha_bob,
+            beta_bob,
+            start_of_swap,
+        )
+        .try_for_each(|action| {
+            match &action {
+                Action::HbitFund(_) => beta.execute(action),
+                _ => alpha.execute(action),
+            }
+            future::ok(())
+        });
+
+        future::try_join(alice, bob).await.unwrap();
+
+        assert_eq!(
+            alpha.redeemed().map(|redeemed| redeemed.secret),
+            Some(secret)
+        );
+        assert_eq!(
+            beta.redeemed().map(|redeemed| redeemed.secret),
+            Some(secret)
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
-use std::time::SystemTime;
-use time::Duration;
+use std::{cmp, time::SystemTime};
+use time::{Duration, OffsetDateTime};
 use tracing::warn;
 
 /// An exact time and date used to represent absolute timelocks
@@ -95,6 +95,16 @@ impl From<Timestamp> for i64 {
     }
 }
 
+/// Times outside of what a `Timestamp` can represent are clamped.
+impl From<OffsetDateTime> for Timestamp {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from(item: OffsetDateTime) -> Self {
+        let seconds = cmp::min(cmp::max(item.timestamp(), 0), i64::from(u32::MAX));
+
+        Self(seconds as u32)
+    }
+}
+
 impl From<crate::ethereum::U256> for Timestamp {
     fn from(value: crate::ethereum::U256) -> Self {
         value.low_u32().into()
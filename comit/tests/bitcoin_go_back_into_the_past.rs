@@ -2,7 +2,7 @@ pub mod bitcoin_helper;
 
 use bitcoin::Address;
 use bitcoin_helper::BitcoinConnectorMock;
-use comit::btsieve::bitcoin::watch_for_created_outpoint;
+use comit::btsieve::{bitcoin::watch_for_created_outpoint, NoCheckpoints};
 use std::str::FromStr;
 use time::OffsetDateTime;
 
@@ -30,6 +30,7 @@ async fn find_transaction_go_back_into_the_past() {
         OffsetDateTime::from_unix_timestamp(block1_with_transaction.header.time as i64);
     let (expected_transaction, _out_point) = watch_for_created_outpoint(
         &connector,
+        &NoCheckpoints,
         start_of_swap,
         Address::from_str(
             include_str!("test_data/bitcoin/find_transaction_go_back_into_the_past/address").trim(),
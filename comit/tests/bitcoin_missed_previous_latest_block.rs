@@ -2,7 +2,7 @@ pub mod bitcoin_helper;
 
 use bitcoin::Address;
 use bitcoin_helper::BitcoinConnectorMock;
-use comit::btsieve::bitcoin::watch_for_created_outpoint;
+use comit::btsieve::{bitcoin::watch_for_created_outpoint, NoCheckpoints};
 use std::str::FromStr;
 use time::OffsetDateTime;
 
@@ -32,6 +32,7 @@ async fn find_transaction_missed_previous_latest_block() {
     let start_of_swap = OffsetDateTime::from_unix_timestamp(block1.header.time as i64 + 1);
     let (expected_transaction, _out_point) = watch_for_created_outpoint(
         &connector,
+        &NoCheckpoints,
         start_of_swap,
         Address::from_str(
             include_str!("test_data/bitcoin/find_transaction_missed_previous_latest_block/address")
@@ -78,7 +79,7 @@ async fn find_transaction_missed_previous_latest_block_with_big_gap() {
     // first one
     let start_of_swap = OffsetDateTime::from_unix_timestamp(block1.header.time as i64 + 1);
     let (expected_transaction, _out_point) = watch_for_created_outpoint(
-        &connector,
+        &connector, &NoCheckpoints,
         start_of_swap,
         Address::from_str(
             include_str!(
@@ -117,6 +118,7 @@ async fn find_transaction_if_blockchain_reorganisation() {
     let start_of_swap = OffsetDateTime::now_utc();
     let (expected_transaction, _out_point) = watch_for_created_outpoint(
         &connector,
+        &NoCheckpoints,
         start_of_swap,
         Address::from_str(
             include_str!("test_data/bitcoin/find_transaction_if_blockchain_reorganisation/address")
@@ -154,7 +156,7 @@ async fn find_transaction_if_blockchain_reorganisation_with_long_chain() {
     );
 
     let start_of_swap = OffsetDateTime::now_utc();
-    let (expected_transaction, _out_point) = watch_for_created_outpoint(&connector, start_of_swap, Address::from_str(
+    let (expected_transaction, _out_point) = watch_for_created_outpoint(&connector, &NoCheckpoints, start_of_swap, Address::from_str(
         include_str!(
             "test_data/bitcoin/find_transaction_if_blockchain_reorganisation_with_long_chain/address"
         ).trim()
@@ -1,7 +1,10 @@
 #[cfg(feature = "testcontainers")]
 mod tests {
     use bitcoincore_rpc::RpcApi;
-    use comit::btsieve::bitcoin::{watch_for_created_outpoint, BitcoindConnector};
+    use comit::btsieve::{
+        bitcoin::{watch_for_created_outpoint, BitcoindConnector},
+        NoCheckpoints,
+    };
     use images::coblox_bitcoincore::BitcoinCore;
     use reqwest::Url;
     use std::time::Duration;
@@ -70,6 +73,7 @@ mod tests {
 
         let (funding_transaction, _out_point) = watch_for_created_outpoint(
             &connector,
+            &NoCheckpoints,
             start_of_swap,
             target_address.to_string().parse().unwrap(),
         )
@@ -1,7 +1,7 @@
 pub mod ethereum_helper;
 
 use comit::{
-    btsieve::ethereum::matching_transaction_and_receipt,
+    btsieve::{ethereum::matching_transaction_and_receipt, NoCheckpoints},
     ethereum::{Block, Transaction, TransactionReceipt},
 };
 use ethereum_helper::EthereumConnectorMock;
@@ -49,7 +49,7 @@ async fn find_transaction_go_back_into_the_past() {
         OffsetDateTime::from_unix_timestamp(block1_with_transaction.timestamp.as_u32() as i64);
 
     let (got_transaction, got_receipt) =
-        matching_transaction_and_receipt(&connector, start_of_swap, {
+        matching_transaction_and_receipt(&connector, &NoCheckpoints, start_of_swap, {
             |transaction| transaction.to == want_transaction.to
         })
         .await
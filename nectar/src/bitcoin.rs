@@ -6,7 +6,10 @@ pub use ::bitcoin::{Address, Block, BlockHash, Network, Transaction, Txid};
 pub use bitcoind::*;
 pub use comit::asset::Bitcoin as Amount;
 pub use fee::*;
-pub use wallet::Wallet;
+pub use wallet::{
+    AddressNotOwned, AddressType, Chain, ChangePolicy, ConfirmationError, FeeBumpError,
+    RangedDescriptor, SweepError, Utxo, Wallet, WalletError, DEFAULT_ACCOUNT,
+};
 
 pub const SATS_IN_BITCOIN_EXP: u16 = 8;
 
@@ -7,30 +7,129 @@ use anyhow::Context;
 use bitcoin::OutPoint;
 use comit::ledger;
 use ledger::Bitcoin as Network;
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize, Serialize,
+};
+use std::{collections::HashMap, convert::TryFrom, fmt::Debug, time::Duration};
 
 pub const JSONRPC_VERSION: &str = "1.0";
 
+/// Error code returned by bitcoind when it does not know the requested
+/// transaction, address or key.
+const RPC_INVALID_ADDRESS_OR_KEY: i64 = -5;
+pub const RPC_WALLET_INSUFFICIENT_FUNDS: i64 = -6;
+pub const RPC_WALLET_NOT_FOUND: i64 = -18;
+/// Error code returned by bitcoind when no address carries the requested
+/// label.
+const RPC_WALLET_INVALID_LABEL_NAME: i64 = -11;
+
 #[derive(Debug, Clone)]
 pub struct Client {
     rpc_client: jsonrpc::Client,
+    options: ClientOptions,
+}
+
+/// How patient the client is with bitcoind.
+///
+/// Only requests that are safe to repeat are retried, anything that spends
+/// funds is sent exactly once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClientOptions {
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every subsequent one.
+    pub backoff: Duration,
+    /// How many idle connections to bitcoind are kept open for reuse.
+    pub max_idle_connections: usize,
+    /// Closes connections that were idle for this long.
+    pub idle_timeout: Duration,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            // Generous because importing descriptors triggers a rescan
+            request_timeout: Duration::from_secs(60),
+            max_retries: 3,
+            backoff: Duration::from_millis(500),
+            max_idle_connections: 4,
+            // bitcoind closes connections after 30 seconds by default
+            // (-rpcservertimeout), we close them before it does
+            idle_timeout: Duration::from_secs(20),
+        }
+    }
 }
 
 impl Client {
     pub fn new(url: url::Url) -> Self {
+        Self::with_options(url, ClientOptions::default())
+    }
+
+    pub fn with_options(url: url::Url, options: ClientOptions) -> Self {
         Client {
-            rpc_client: jsonrpc::Client::new(url),
+            rpc_client: jsonrpc::Client::with_options(url, jsonrpc::HttpOptions {
+                connect_timeout: options.connect_timeout,
+                request_timeout: options.request_timeout,
+                max_idle_connections: options.max_idle_connections,
+                idle_timeout: Some(options.idle_timeout),
+            }),
+            options,
+        }
+    }
+
+    /// Sends a request that does not change the state of bitcoind, retrying
+    /// with exponential backoff if bitcoind could not be reached, closed the
+    /// connection or did not answer in time.
+    ///
+    /// Errors returned by bitcoind itself are not retried. Requests that change
+    /// the state of bitcoind must not go through here, a connection failure
+    /// does not tell whether bitcoind already processed them.
+    async fn send_idempotent<Req, Res>(
+        &self,
+        path: &str,
+        request: jsonrpc::Request<Req>,
+    ) -> anyhow::Result<Res>
+    where
+        Req: Debug + Serialize + Clone,
+        Res: Debug + DeserializeOwned,
+    {
+        let mut backoff = self.options.backoff;
+        let mut retries = 0;
+
+        loop {
+            match self
+                .rpc_client
+                .send_with_path(path.to_owned(), request.clone())
+                .await
+            {
+                Err(e)
+                    if retries < self.options.max_retries
+                        && e.downcast_ref::<jsonrpc::JsonRpcError>().is_none() =>
+                {
+                    tracing::debug!(
+                        "request to bitcoind failed, retrying in {:?}: {:#}",
+                        backoff,
+                        e
+                    );
+                    tokio::time::delay_for(backoff).await;
+
+                    backoff = backoff.checked_mul(2).unwrap_or(backoff);
+                    retries += 1;
+                }
+                result => return result,
+            }
         }
     }
 
     pub async fn network(&self) -> anyhow::Result<Network> {
         let blockchain_info = self
-            .rpc_client
-            .send::<Vec<()>, BlockchainInfo>(jsonrpc::Request::new(
-                "getblockchaininfo",
-                vec![],
-                JSONRPC_VERSION.into(),
-            ))
+            .send_idempotent::<Vec<()>, BlockchainInfo>(
+                "",
+                jsonrpc::Request::new("getblockchaininfo", vec![], JSONRPC_VERSION.into()),
+            )
             .await?;
 
         Ok(blockchain_info.chain)
@@ -62,6 +161,56 @@ impl Client {
         Ok(response)
     }
 
+    /// Creates a blank descriptor wallet, only supported by bitcoind 0.21+.
+    pub async fn create_descriptor_wallet(
+        &self,
+        wallet_name: &str,
+        disable_private_keys: bool,
+    ) -> anyhow::Result<CreateWalletResponse> {
+        let response = self
+            .rpc_client
+            .send(jsonrpc::Request::new(
+                "createwallet",
+                serde_json::json!([
+                    wallet_name,
+                    disable_private_keys,
+                    true, // blank
+                    null, // passphrase
+                    null, // avoid_reuse
+                    true, // descriptors
+                ]),
+                JSONRPC_VERSION.into(),
+            ))
+            .await
+            .context("failed to create descriptor wallet")?;
+        Ok(response)
+    }
+
+    pub async fn import_descriptors(
+        &self,
+        wallet_name: &str,
+        requests: Vec<ImportDescriptorRequest>,
+    ) -> anyhow::Result<()> {
+        let responses: Vec<ImportDescriptorResponse> = self
+            .rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "importdescriptors",
+                    vec![jsonrpc::serialize(requests)?],
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to import descriptors")?;
+
+        if let Some(error) = responses.into_iter().find_map(|response| response.error) {
+            anyhow::bail!("failed to import descriptor: {}", error.message);
+        }
+
+        Ok(())
+    }
+
     pub async fn get_balance(
         &self,
         wallet_name: &str,
@@ -70,9 +219,8 @@ impl Client {
         avoid_reuse: Option<bool>,
     ) -> anyhow::Result<Amount> {
         let response = self
-            .rpc_client
-            .send_with_path(
-                format!("/wallet/{}", wallet_name),
+            .send_idempotent(
+                &format!("/wallet/{}", wallet_name),
                 jsonrpc::Request::new(
                     "getbalance",
                     vec![
@@ -89,6 +237,18 @@ impl Client {
         Ok(amount)
     }
 
+    pub async fn get_balances(&self, wallet_name: &str) -> anyhow::Result<GetBalancesResponse> {
+        let response = self
+            .rpc_client
+            .send_with_path::<Vec<()>, _>(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new("getbalances", vec![], JSONRPC_VERSION.into()),
+            )
+            .await
+            .context("failed to get balances")?;
+        Ok(response)
+    }
+
     pub async fn set_hd_seed(
         &self,
         wallet_name: &str,
@@ -113,6 +273,64 @@ impl Client {
         Ok(())
     }
 
+    pub async fn encrypt_wallet(&self, wallet_name: &str, passphrase: &str) -> anyhow::Result<()> {
+        let _: String = self
+            .rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "encryptwallet",
+                    vec![jsonrpc::serialize(passphrase)?],
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .map_err(without_request)
+            .context("failed to encrypt wallet")?;
+
+        Ok(())
+    }
+
+    /// Unlocks the wallet for `timeout` seconds.
+    pub async fn wallet_passphrase(
+        &self,
+        wallet_name: &str,
+        passphrase: &str,
+        timeout: u32,
+    ) -> anyhow::Result<()> {
+        let _: () = self
+            .rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "walletpassphrase",
+                    vec![
+                        jsonrpc::serialize(passphrase)?,
+                        jsonrpc::serialize(timeout)?,
+                    ],
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .map_err(without_request)
+            .context("failed to unlock wallet")?;
+
+        Ok(())
+    }
+
+    pub async fn wallet_lock(&self, wallet_name: &str) -> anyhow::Result<()> {
+        let _: () = self
+            .rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new("walletlock", Vec::<()>::new(), JSONRPC_VERSION.into()),
+            )
+            .await
+            .context("failed to lock wallet")?;
+
+        Ok(())
+    }
+
     pub async fn get_new_address(
         &self,
         wallet_name: &str,
@@ -137,17 +355,123 @@ impl Client {
         Ok(address)
     }
 
-    pub async fn get_wallet_info(&self, wallet_name: &str) -> anyhow::Result<WalletInfoResponse> {
-        let response = self
+    pub async fn set_label(
+        &self,
+        wallet_name: &str,
+        address: &Address,
+        label: &str,
+    ) -> anyhow::Result<()> {
+        let _: () = self
             .rpc_client
-            .send_with_path::<Vec<()>, _>(
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "setlabel",
+                    vec![jsonrpc::serialize(address)?, jsonrpc::serialize(label)?],
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to set label")?;
+
+        Ok(())
+    }
+
+    /// The addresses carrying `label`, none if the label is unknown.
+    pub async fn get_addresses_by_label(
+        &self,
+        wallet_name: &str,
+        label: &str,
+    ) -> anyhow::Result<Vec<Address>> {
+        let response: anyhow::Result<HashMap<Address, IgnoredAny>> = self
+            .rpc_client
+            .send_with_path(
                 format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "getaddressesbylabel",
+                    vec![jsonrpc::serialize(label)?],
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await;
+
+        match response {
+            Ok(addresses) => Ok(addresses.into_iter().map(|(address, _)| address).collect()),
+            Err(e) if is_rpc_error(&e, RPC_WALLET_INVALID_LABEL_NAME) => Ok(Vec::new()),
+            Err(e) => Err(e.context("failed to get addresses by label")),
+        }
+    }
+
+    pub async fn get_raw_change_address(
+        &self,
+        wallet_name: &str,
+        address_type: Option<String>,
+    ) -> anyhow::Result<Address> {
+        self.rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "getrawchangeaddress",
+                    vec![jsonrpc::serialize(address_type)?],
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to get raw change address")
+    }
+
+    pub async fn get_wallet_info(&self, wallet_name: &str) -> anyhow::Result<WalletInfoResponse> {
+        let response = self
+            .send_idempotent::<Vec<()>, _>(
+                &format!("/wallet/{}", wallet_name),
                 jsonrpc::Request::new("getwalletinfo", vec![], JSONRPC_VERSION.into()),
             )
             .await?;
         Ok(response)
     }
 
+    pub async fn list_unspent(
+        &self,
+        wallet_name: &str,
+        min_confirmations: u32,
+    ) -> anyhow::Result<Vec<ListUnspentResponse>> {
+        self.rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "listunspent",
+                    vec![jsonrpc::serialize(min_confirmations)?],
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to list unspent outputs")
+    }
+
+    /// The addresses of the wallet, watch-only ones included, that received
+    /// funds.
+    pub async fn list_received_by_address(
+        &self,
+        wallet_name: &str,
+    ) -> anyhow::Result<Vec<ListReceivedByAddressResponse>> {
+        self.rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "listreceivedbyaddress",
+                    vec![
+                        jsonrpc::serialize(0)?,
+                        jsonrpc::serialize(false)?,
+                        jsonrpc::serialize(true)?,
+                    ],
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to list received by address")
+    }
+
+    #[cfg(all(test, feature = "testcontainers"))]
     pub async fn send_to_address(
         &self,
         wallet_name: &str,
@@ -160,10 +484,14 @@ impl Client {
                 format!("/wallet/{}", wallet_name),
                 jsonrpc::Request::new(
                     "sendtoaddress",
-                    vec![
-                        jsonrpc::serialize(address)?,
-                        jsonrpc::serialize(amount.as_btc())?,
-                    ],
+                    serde_json::json!([
+                        address,
+                        amount.as_btc(),
+                        null, // comment
+                        null, // comment_to
+                        null, // subtractfeefromamount
+                        true, // replaceable, allows fee bumping using BIP125
+                    ]),
                     JSONRPC_VERSION.into(),
                 ),
             )
@@ -174,6 +502,113 @@ impl Client {
         Ok(txid)
     }
 
+    /// Sends `amount` to `address` with the fee being deducted from `amount`,
+    /// which allows spending the whole balance. `vbyte_fee_rate` is in sat/vB,
+    /// bitcoind estimates the fee if it is not set.
+    pub async fn send_to_address_subtract_fee(
+        &self,
+        wallet_name: &str,
+        address: Address,
+        amount: Amount,
+        vbyte_fee_rate: Option<Amount>,
+    ) -> anyhow::Result<Txid> {
+        let txid: String = self
+            .rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "sendtoaddress",
+                    serde_json::json!([
+                        address,
+                        amount.as_btc(),
+                        null,                                     // comment
+                        null,                                     // comment_to
+                        true,                                     // subtractfeefromamount
+                        true,                                     // replaceable
+                        null,                                     // conf_target
+                        null,                                     // estimate_mode
+                        null,                                     // avoid_reuse
+                        vbyte_fee_rate.map(|rate| rate.as_sat()), // fee_rate in sat/vB
+                    ]),
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to send to address subtracting the fee")?;
+        let txid = Txid::from_hex(&txid)?;
+
+        Ok(txid)
+    }
+
+    pub async fn send_many(
+        &self,
+        wallet_name: &str,
+        outputs: Vec<(Address, Amount)>,
+    ) -> anyhow::Result<Txid> {
+        let mut amounts = serde_json::Map::new();
+        for (address, amount) in outputs {
+            let address = address.to_string();
+            if amounts.contains_key(&address) {
+                anyhow::bail!("address {} appears more than once in outputs", address);
+            }
+            amounts.insert(address, serde_json::json!(amount.as_btc()));
+        }
+
+        let txid: String = self
+            .rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "sendmany",
+                    serde_json::json!(["", amounts]),
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to send many")?;
+        let txid = Txid::from_hex(&txid)?;
+
+        Ok(txid)
+    }
+
+    /// Same as `send_to_address` but lets the caller set the fee rate instead
+    /// of relying on bitcoind's fee estimation. `vbyte_fee_rate` is in
+    /// sat/vB.
+    pub async fn send_to_address_with_fee_rate(
+        &self,
+        wallet_name: &str,
+        address: Address,
+        amount: Amount,
+        vbyte_fee_rate: Amount,
+    ) -> anyhow::Result<Txid> {
+        let txid: String = self
+            .rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "sendtoaddress",
+                    serde_json::json!([
+                        address,
+                        amount.as_btc(),
+                        null,                    // comment
+                        null,                    // comment_to
+                        null,                    // subtractfeefromamount
+                        true,                    // replaceable
+                        null,                    // conf_target
+                        null,                    // estimate_mode
+                        null,                    // avoid_reuse
+                        vbyte_fee_rate.as_sat(), // fee_rate in sat/vB
+                    ]),
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to send to address with fee rate")?;
+        let txid = Txid::from_hex(&txid)?;
+
+        Ok(txid)
+    }
+
     pub async fn fund_htlc(
         &self,
         wallet_name: &str,
@@ -268,6 +703,53 @@ impl Client {
         })
     }
 
+    /// Replaces a wallet transaction by one paying `vbyte_fee_rate` (in
+    /// sat/vB) and returns the id of the replacement transaction.
+    pub async fn bump_fee(
+        &self,
+        wallet_name: &str,
+        txid: Txid,
+        vbyte_fee_rate: Amount,
+    ) -> anyhow::Result<Txid> {
+        let response: BumpFeeResponse = self
+            .rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "bumpfee",
+                    serde_json::json!([
+                        txid,
+                        {
+                            "fee_rate": vbyte_fee_rate.as_sat()
+                        }
+                    ]),
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to bump fee")?;
+
+        Ok(response.txid)
+    }
+
+    pub async fn get_transaction(
+        &self,
+        wallet_name: &str,
+        txid: Txid,
+    ) -> anyhow::Result<GetTransactionResponse> {
+        self.rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "gettransaction",
+                    vec![jsonrpc::serialize(txid)?],
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to get transaction")
+    }
+
     pub async fn send_raw_transaction(
         &self,
         wallet_name: &str,
@@ -278,15 +760,39 @@ impl Client {
             .send_with_path(
                 format!("/wallet/{}", wallet_name),
                 jsonrpc::Request::new(
-                    "sendrawtransaction",
-                    vec![serialize_hex(&transaction)],
+                    "sendrawtransaction",
+                    vec![serialize_hex(&transaction)],
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to send raw transaction")?;
+        let txid = Txid::from_hex(&txid)?;
+        Ok(txid)
+    }
+
+    /// Checks whether bitcoind would accept `transaction` into its mempool
+    /// without broadcasting it.
+    pub async fn test_mempool_accept(
+        &self,
+        transaction: &Transaction,
+    ) -> anyhow::Result<MempoolAcceptResult> {
+        let results: Vec<MempoolAcceptResult> = self
+            .send_idempotent(
+                "",
+                jsonrpc::Request::new(
+                    "testmempoolaccept",
+                    serde_json::json!([[serialize_hex(transaction)]]),
                     JSONRPC_VERSION.into(),
                 ),
             )
             .await
-            .context("failed to send raw transaction")?;
-        let txid = Txid::from_hex(&txid)?;
-        Ok(txid)
+            .context("failed to test mempool acceptance")?;
+
+        results
+            .into_iter()
+            .next()
+            .context("bitcoind did not test the transaction")
     }
 
     pub async fn get_confirmations(&self, transaction: Txid) -> anyhow::Result<i32> {
@@ -303,7 +809,42 @@ impl Client {
         Ok(response.confirmations.unwrap_or(0))
     }
 
-    #[cfg(test)]
+    /// Returns the number of confirmations of the given wallet transaction or
+    /// `None` if the wallet does not know about it.
+    ///
+    /// Unlike `getrawtransaction`, `gettransaction` does not depend on
+    /// `-txindex` and keeps finding the transaction once it left the mempool.
+    /// A transaction that conflicts with the chain (negative confirmations) is
+    /// reported as unknown.
+    pub async fn find_confirmations(
+        &self,
+        wallet_name: &str,
+        transaction: Txid,
+    ) -> anyhow::Result<Option<u32>> {
+        match self.get_transaction(wallet_name, transaction).await {
+            Ok(response) => Ok(u32::try_from(response.confirmations).ok()),
+            Err(e) if is_rpc_error(&e, RPC_INVALID_ADDRESS_OR_KEY) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg(all(test, feature = "testcontainers"))]
+    pub async fn get_raw_transaction(&self, txid: Txid) -> anyhow::Result<Transaction> {
+        let hex: String = self
+            .rpc_client
+            .send(jsonrpc::Request::new(
+                "getrawtransaction",
+                serde_json::json!([txid]),
+                JSONRPC_VERSION.into(),
+            ))
+            .await
+            .context("failed to get raw transaction")?;
+        let transaction = ::bitcoin::consensus::deserialize(&Vec::<u8>::from_hex(&hex)?)?;
+
+        Ok(transaction)
+    }
+
+    #[cfg(all(test, feature = "testcontainers"))]
     pub async fn dump_wallet(
         &self,
         wallet_name: &str,
@@ -338,7 +879,6 @@ impl Client {
         Ok(wallets)
     }
 
-    #[allow(dead_code)]
     pub async fn derive_addresses(
         &self,
         descriptor: &str,
@@ -370,7 +910,186 @@ impl Client {
             .context("failed to get descriptor info")
     }
 
-    #[cfg(test)]
+    /// Creates a PSBT paying to `outputs`, funded by coins selected by the
+    /// wallet and sending the change to `change_address`. `vbyte_fee_rate` is
+    /// in sat/vB, bitcoind estimates the fee if it is not set.
+    pub async fn wallet_create_funded_psbt(
+        &self,
+        wallet_name: &str,
+        outputs: Vec<(Address, Amount)>,
+        change_address: Address,
+        include_watching: bool,
+        vbyte_fee_rate: Option<Amount>,
+    ) -> anyhow::Result<String> {
+        let mut amounts = serde_json::Map::new();
+        for (address, amount) in outputs {
+            let address = address.to_string();
+            if amounts.contains_key(&address) {
+                anyhow::bail!("address {} appears more than once in outputs", address);
+            }
+            amounts.insert(address, serde_json::json!(amount.as_btc()));
+        }
+
+        let mut options = serde_json::json!({
+            "changeAddress": change_address,
+            "includeWatching": include_watching,
+        });
+        if let Some(vbyte_fee_rate) = vbyte_fee_rate {
+            options["fee_rate"] = serde_json::json!(vbyte_fee_rate.as_sat());
+        }
+
+        let response: CreatePsbtResponse = self
+            .rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "walletcreatefundedpsbt",
+                    serde_json::json!([
+                        [], // inputs, selected by the wallet
+                        [amounts],
+                        0, // locktime
+                        options,
+                    ]),
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to create funded psbt")?;
+
+        Ok(response.psbt)
+    }
+
+    /// Creates a PSBT spending exactly `inputs` to a single output of
+    /// `amount`, paying `vbyte_fee_rate` (in sat/vB) out of that output.
+    pub async fn wallet_create_psbt_from_inputs(
+        &self,
+        wallet_name: &str,
+        inputs: Vec<OutPoint>,
+        address: Address,
+        amount: Amount,
+        vbyte_fee_rate: Amount,
+    ) -> anyhow::Result<String> {
+        let inputs = inputs
+            .into_iter()
+            .map(|outpoint| serde_json::json!({ "txid": outpoint.txid, "vout": outpoint.vout }))
+            .collect::<Vec<_>>();
+
+        let response: CreatePsbtResponse = self
+            .rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "walletcreatefundedpsbt",
+                    serde_json::json!([
+                        inputs,
+                        [{ address.to_string(): amount.as_btc() }],
+                        0, // locktime
+                        {
+                            "add_inputs": false,
+                            "subtractFeeFromOutputs": [0],
+                            "fee_rate": vbyte_fee_rate.as_sat(),
+                        },
+                    ]),
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to create psbt from inputs")?;
+
+        Ok(response.psbt)
+    }
+
+    pub async fn finalize_psbt(&self, psbt: &str) -> anyhow::Result<FinalizePsbtResponse> {
+        self.rpc_client
+            .send(jsonrpc::Request::new(
+                "finalizepsbt",
+                serde_json::json!([psbt, true]),
+                JSONRPC_VERSION.into(),
+            ))
+            .await
+            .context("failed to finalize psbt")
+    }
+
+    /// Signs the inputs of `psbt` that belong to the wallet.
+    pub async fn wallet_process_psbt(
+        &self,
+        wallet_name: &str,
+        psbt: &str,
+    ) -> anyhow::Result<String> {
+        let response: ProcessPsbtResponse = self
+            .rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "walletprocesspsbt",
+                    serde_json::json!([psbt]),
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to process psbt")?;
+
+        Ok(response.psbt)
+    }
+
+    pub async fn get_address_info(
+        &self,
+        wallet_name: &str,
+        address: &Address,
+    ) -> anyhow::Result<GetAddressInfoResponse> {
+        self.rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "getaddressinfo",
+                    vec![jsonrpc::serialize(address)?],
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to get address info")
+    }
+
+    pub async fn sign_message(
+        &self,
+        wallet_name: &str,
+        address: &Address,
+        message: &str,
+    ) -> anyhow::Result<String> {
+        self.rpc_client
+            .send_with_path(
+                format!("/wallet/{}", wallet_name),
+                jsonrpc::Request::new(
+                    "signmessage",
+                    vec![jsonrpc::serialize(address)?, jsonrpc::serialize(message)?],
+                    JSONRPC_VERSION.into(),
+                ),
+            )
+            .await
+            .context("failed to sign message")
+    }
+
+    pub async fn verify_message(
+        &self,
+        address: &Address,
+        signature: &str,
+        message: &str,
+    ) -> anyhow::Result<bool> {
+        self.rpc_client
+            .send(jsonrpc::Request::new(
+                "verifymessage",
+                vec![
+                    jsonrpc::serialize(address)?,
+                    jsonrpc::serialize(signature)?,
+                    jsonrpc::serialize(message)?,
+                ],
+                JSONRPC_VERSION.into(),
+            ))
+            .await
+            .context("failed to verify message")
+    }
+
+    #[cfg(all(test, feature = "testcontainers"))]
     pub async fn generate_to_address(
         &self,
         nblocks: u32,
@@ -466,6 +1185,68 @@ pub struct WalletInfoResponse {
     pub scanning: ScanProgress,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct GetBalancesResponse {
+    pub mine: Balances,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct Balances {
+    /// Confirmed funds and unconfirmed funds sent by the wallet itself
+    #[serde(deserialize_with = "btc_as_float::deserialize")]
+    pub trusted: Amount,
+    /// Unconfirmed funds sent to the wallet by someone else
+    #[serde(deserialize_with = "btc_as_float::deserialize")]
+    pub untrusted_pending: Amount,
+    /// Coinbase outputs that are not mature yet
+    #[serde(deserialize_with = "btc_as_float::deserialize")]
+    pub immature: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ListUnspentResponse {
+    pub txid: Txid,
+    pub vout: u32,
+    pub address: Address,
+    #[serde(deserialize_with = "btc_as_float::deserialize")]
+    pub amount: Amount,
+    pub confirmations: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListReceivedByAddressResponse {
+    pub address: Address,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportDescriptorRequest {
+    #[serde(rename = "desc")]
+    pub descriptor: String,
+    pub active: bool,
+    /// Whether the addresses of the descriptor are used for change.
+    pub internal: bool,
+    pub range: [u64; 2],
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct ImportDescriptorResponse {
+    success: bool,
+    error: Option<ImportDescriptorError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct ImportDescriptorError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct GetAddressInfoResponse {
+    #[serde(rename = "ismine")]
+    pub is_mine: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 struct DumpWalletResponse {
     filename: String,
@@ -502,25 +1283,74 @@ struct ProcessPsbtResponse {
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq)]
-struct FinalizePsbtResponse {
-    psbt: Option<String>,
-    hex: Option<String>,
-    complete: bool,
+pub struct FinalizePsbtResponse {
+    pub psbt: Option<String>,
+    /// The network serialized transaction, only present if `complete`.
+    pub hex: Option<String>,
+    pub complete: bool,
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+/// bitcoind omits the fee rate and returns errors instead when it does not have
+/// enough data to estimate a fee, which is common on a fresh regtest chain.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
 pub struct EstimateSmartFeeResponse {
     #[serde(rename = "feerate")]
-    #[serde(with = "btc_as_float")]
-    pub kbyte_rate: Amount,
+    #[serde(default, deserialize_with = "btc_as_float::deserialize_option")]
+    pub kbyte_rate: Option<Amount>,
+    #[serde(default)]
+    pub errors: Vec<String>,
     pub blocks: u32,
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct MempoolAcceptResult {
+    pub allowed: bool,
+    /// Why bitcoind rejects the transaction, only present if not `allowed`.
+    #[serde(rename = "reject-reason")]
+    pub reject_reason: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+struct BumpFeeResponse {
+    txid: Txid,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct GetTransactionResponse {
+    pub confirmations: i32,
+    #[serde(rename = "bip125-replaceable")]
+    pub bip125_replaceable: Bip125Replaceable,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Bip125Replaceable {
+    Yes,
+    No,
+    /// The transaction is unconfirmed and not in the mempool
+    Unknown,
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
 pub struct GetRawTransactionResponse {
     pub confirmations: Option<i32>,
 }
 
+pub fn is_rpc_error(error: &anyhow::Error, code: i64) -> bool {
+    error
+        .downcast_ref::<jsonrpc::JsonRpcError>()
+        .map_or(false, |e| e.code() == code)
+}
+
+/// The JSON-RPC client attaches the whole request to its errors, which would
+/// leak the passphrase into our logs. Only keep the error returned by bitcoind.
+fn without_request(error: anyhow::Error) -> anyhow::Error {
+    match error.downcast::<jsonrpc::JsonRpcError>() {
+        Ok(error) => error.into(),
+        Err(error) => error,
+    }
+}
+
 mod btc_as_float {
     use super::*;
     use serde::{de::Error, Deserialize, Deserializer};
@@ -534,6 +1364,21 @@ mod btc_as_float {
 
         Ok(amount)
     }
+
+    pub fn deserialize_option<'de, D>(
+        deserializer: D,
+    ) -> Result<Option<Amount>, <D as Deserializer<'de>>::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Option::<f64>::deserialize(deserializer)?;
+        let amount = value
+            .map(Amount::from_btc)
+            .transpose()
+            .map_err(<D as Deserializer<'de>>::Error::custom)?;
+
+        Ok(amount)
+    }
 }
 
 #[cfg(test)]
@@ -543,6 +1388,192 @@ impl crate::StaticStub for Client {
     }
 }
 
+#[cfg(test)]
+mod mock_node_tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    /// Answers every connection with `body`, waiting `delays[n]` before
+    /// answering the `n`th one.
+    fn mock_node(delays: Vec<Duration>, body: &'static str) -> (url::Url, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        let counter = connections.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let delay = delays
+                    .get(counter.fetch_add(1, Ordering::SeqCst))
+                    .copied()
+                    .unwrap_or_default();
+
+                thread::spawn(move || {
+                    let mut request = [0u8; 4096];
+                    let _ = stream.read(&mut request);
+                    thread::sleep(delay);
+
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes());
+                });
+            }
+        });
+
+        (url, connections)
+    }
+
+    fn options(max_retries: u32) -> ClientOptions {
+        ClientOptions {
+            connect_timeout: Duration::from_secs(1),
+            request_timeout: Duration::from_millis(200),
+            max_retries,
+            backoff: Duration::from_millis(10),
+            max_idle_connections: 1,
+            idle_timeout: Duration::from_secs(60),
+        }
+    }
+
+    const BLOCKCHAIN_INFO: &str = r#"{"result":{"chain":"regtest"},"id":"1"}"#;
+
+    /// Answers the first request on every connection and closes the connection
+    /// when a second one arrives, like bitcoind does once it considers the
+    /// connection idle.
+    fn closing_mock_node(body: &'static str) -> (url::Url, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        let counter = connections.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                thread::spawn(move || {
+                    let mut request = [0u8; 4096];
+                    let _ = stream.read(&mut request);
+
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes());
+
+                    // The body of the first request may arrive separately,
+                    // only the start of the next request closes the connection
+                    while let Ok(n) = stream.read(&mut request) {
+                        if n == 0 || request.starts_with(b"POST") {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        (url, connections)
+    }
+
+    #[tokio::test]
+    async fn hung_node_times_out() {
+        let (url, _) = mock_node(vec![Duration::from_secs(10)], BLOCKCHAIN_INFO);
+        let client = Client::with_options(url, options(0));
+
+        let error = client.network().await.unwrap_err();
+
+        assert!(error.downcast_ref::<jsonrpc::Timeout>().is_some());
+    }
+
+    #[tokio::test]
+    async fn idempotent_request_is_retried_after_timeout() {
+        let (url, connections) = mock_node(
+            vec![Duration::from_secs(10), Duration::from_secs(0)],
+            BLOCKCHAIN_INFO,
+        );
+        let client = Client::with_options(url, options(1));
+
+        let network = client.network().await.unwrap();
+
+        assert_eq!(network, Network::Regtest);
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn sending_raw_transaction_is_never_retried() {
+        let (url, connections) = mock_node(
+            vec![Duration::from_secs(10), Duration::from_secs(0)],
+            r#"{"result":"0000000000000000000000000000000000000000000000000000000000000000","id":"1"}"#,
+        );
+        let client = Client::with_options(url, options(3));
+        let transaction = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![],
+        };
+
+        let error = client
+            .send_raw_transaction("nectar", transaction)
+            .await
+            .unwrap_err();
+
+        assert!(error.downcast_ref::<jsonrpc::Timeout>().is_some());
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn idempotent_request_on_connection_closed_by_node_is_retried() {
+        let (url, connections) = closing_mock_node(BLOCKCHAIN_INFO);
+        let client = Client::with_options(url, options(1));
+
+        client.network().await.unwrap();
+        let network = client.network().await.unwrap();
+
+        assert_eq!(network, Network::Regtest);
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn sending_raw_transaction_on_connection_closed_by_node_is_not_resent() {
+        let (url, connections) = closing_mock_node(
+            r#"{"result":"0000000000000000000000000000000000000000000000000000000000000000","id":"1"}"#,
+        );
+        let client = Client::with_options(url, options(3));
+        let transaction = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![],
+        };
+
+        client
+            .send_raw_transaction("nectar", transaction.clone())
+            .await
+            .unwrap();
+        let result = client.send_raw_transaction("nectar", transaction).await;
+
+        assert!(result.is_err());
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+}
+
 #[cfg(all(test, feature = "testcontainers"))]
 mod test {
     use super::*;
@@ -609,8 +1640,96 @@ mod test {
         let res: EstimateSmartFeeResponse = serde_json::from_str(&json).unwrap();
 
         assert_eq!(res, EstimateSmartFeeResponse {
-            kbyte_rate: ::bitcoin::Amount::from_btc(0.00065295).unwrap(),
+            kbyte_rate: Some(::bitcoin::Amount::from_btc(0.00065295).unwrap()),
+            errors: vec![],
             blocks: 5
         })
     }
+
+    #[test]
+    fn decode_estimate_smart_fee_without_estimate() {
+        let json = r#"{
+    "errors": [
+        "Insufficient data or no feerate found"
+    ],
+    "blocks": 0
+}"#;
+
+        let res: EstimateSmartFeeResponse = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(res, EstimateSmartFeeResponse {
+            kbyte_rate: None,
+            errors: vec!["Insufficient data or no feerate found".to_owned()],
+            blocks: 0
+        })
+    }
+
+    #[test]
+    fn decode_failed_import_descriptors() {
+        let json = r#"[
+    {
+        "success": false,
+        "error": {
+            "code": -4,
+            "message": "Cannot import private keys to a wallet with private keys disabled"
+        }
+    }
+]"#;
+
+        let res: Vec<ImportDescriptorResponse> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(res, vec![ImportDescriptorResponse {
+            success: false,
+            error: Some(ImportDescriptorError {
+                code: -4,
+                message: "Cannot import private keys to a wallet with private keys disabled"
+                    .to_owned()
+            })
+        }])
+    }
+
+    #[test]
+    fn decode_get_transaction() {
+        let json = r#"{
+    "amount": 0.00000000,
+    "fee": -0.00000141,
+    "confirmations": 0,
+    "trusted": true,
+    "txid": "f3a4b4ac0a6b6a2f4b5c4b0f5d5d3b1c0b5a4b3c2d1e0f9a8b7c6d5e4f3a2b1c",
+    "walletconflicts": [],
+    "time": 1603264592,
+    "timereceived": 1603264592,
+    "bip125-replaceable": "yes",
+    "details": [],
+    "hex": "00"
+}"#;
+
+        let res: GetTransactionResponse = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(res, GetTransactionResponse {
+            confirmations: 0,
+            bip125_replaceable: Bip125Replaceable::Yes
+        })
+    }
+
+    #[test]
+    fn decode_get_balances() {
+        let json = r#"{
+    "mine": {
+        "trusted": 1.50000000,
+        "untrusted_pending": 0.00100000,
+        "immature": 0.00000000
+    }
+}"#;
+
+        let res: GetBalancesResponse = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(res, GetBalancesResponse {
+            mine: Balances {
+                trusted: Amount::from_sat(150_000_000),
+                untrusted_pending: Amount::from_sat(100_000),
+                immature: Amount::ZERO,
+            }
+        })
+    }
 }
@@ -1,69 +1,255 @@
 use crate::{
-    bitcoin::{Address, Amount, Client, WalletInfoResponse},
+    bitcoin::{
+        is_rpc_error, Address, Amount, Bip125Replaceable, Client, EstimateMode,
+        FinalizePsbtResponse, ImportDescriptorRequest, MempoolAcceptResult, WalletInfoResponse,
+        RPC_WALLET_INSUFFICIENT_FUNDS, RPC_WALLET_NOT_FOUND,
+    },
+    config,
     seed::Seed,
 };
 use ::bitcoin::{
     hashes::{sha256, Hash, HashEngine},
     secp256k1::SecretKey,
-    util::bip32::{ChainCode, ChildNumber, ExtendedPrivKey},
+    util::{
+        bip32::{ChainCode, ChildNumber, ExtendedPrivKey},
+        psbt::PartiallySignedTransaction,
+    },
     PrivateKey, Transaction, Txid,
 };
+use anyhow::Context;
 use bitcoin::{util::bip32::DerivationPath, OutPoint};
-use comit::ledger;
-use std::str::FromStr;
+use comit::{expiries::bitcoin_mine_within_blocks, ledger};
+use futures::lock::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashSet, VecDeque},
+    convert::TryFrom,
+    future::Future,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 use url::Url;
 
-const BITCOIND_DEFAULT_EXTERNAL_DERIVATION_PATH: &str = "/0h/0h/*h";
-const BITCOIND_DEFAULT_INTERNAL_DERIVATION_PATH: &str = "/0h/1h/*h";
+/// The account bitcoind derives the keys of its HD seed from.
+pub const DEFAULT_ACCOUNT: u32 = 0;
 const TRANSIENT_DERIVATION_PATH: &str = "m/0'/9939'";
+/// Fee rate, in sat/vB, used if bitcoind is not able to estimate one.
+const DEFAULT_VBYTE_FEE_RATE_FLOOR: u64 = 1;
+/// Number of seconds an encrypted wallet stays unlocked if we fail to lock it
+/// again after signing.
+const UNLOCK_TIMEOUT_SECS: u32 = 10;
+/// Number of addresses per descriptor watched by a watch-only wallet.
+const WATCH_ONLY_RANGE: u64 = 1000;
+/// Number of addresses per descriptor scanned when looking up the derivation
+/// index of an address.
+const DEFAULT_GAP_LIMIT: u32 = 100;
+/// Number of addresses the address buffer fetches from bitcoind per refill.
+const ADDRESS_BATCH_SIZE: usize = 20;
+/// The address buffer is refilled in the background once fewer addresses than
+/// this are left.
+const ADDRESS_BUFFER_LOW_WATER: usize = 5;
+/// Label of the addresses in the address buffer.
+const BUFFERED_ADDRESS_LABEL: &str = "nectar_buffered";
 
 #[derive(derivative::Derivative)]
 #[derivative(Debug)]
 pub struct Wallet {
     /// The wallet is named `nectar_x` with `x` being the first 4 bytes of the
-    /// hash of the seed
+    /// hash of the seed, or `nectar_watch_only_x` with `x` being the first 4
+    /// bytes of the hash of the descriptors
     name: String,
     bitcoind_client: Client,
-    root_key: ExtendedPrivKey,
+    keys: Keys,
+    vbyte_fee_rate_floor: Amount,
+    estimate_mode: Option<EstimateMode>,
+    gap_limit: u32,
+    /// The BIP32 account of the seed the keys are derived from.
+    account: u32,
+    unlocker: Arc<Unlocker>,
+    address_buffer: Arc<AddressBuffer>,
+    change_policy: ChangePolicy,
     pub ledger: ledger::Bitcoin,
 }
 
 impl Wallet {
-    pub async fn new(seed: Seed, url: Url, ledger: ledger::Bitcoin) -> anyhow::Result<Wallet> {
-        let name = Wallet::gen_name(seed);
+    /// If a `passphrase` is given, a newly created wallet is encrypted with it
+    /// and it is used to unlock the wallet before signing transactions.
+    pub async fn new(
+        seed: Seed,
+        url: Url,
+        ledger: ledger::Bitcoin,
+        passphrase: Option<String>,
+    ) -> Result<Wallet, WalletError> {
+        Wallet::new_with_account(seed, url, ledger, DEFAULT_ACCOUNT, passphrase).await
+    }
+
+    /// Derives the keys of the given BIP32 `account` instead of the default
+    /// one, e.g. to restore the addresses of another wallet software.
+    pub async fn new_with_account(
+        seed: Seed,
+        url: Url,
+        ledger: ledger::Bitcoin,
+        account: u32,
+        passphrase: Option<String>,
+    ) -> Result<Wallet, WalletError> {
+        let name = Wallet::gen_name(seed, account);
         let bitcoind_client = Client::new(url);
 
         let root_key = Self::root_extended_private_key_from_seed(&seed, ledger);
 
+        let unlocker = Arc::new(Unlocker::new(
+            bitcoind_client.clone(),
+            name.clone(),
+            passphrase,
+        ));
+
         let wallet = Wallet {
+            address_buffer: AddressBuffer::new(
+                bitcoind_client.clone(),
+                name.clone(),
+                Arc::clone(&unlocker),
+            ),
             name,
             bitcoind_client,
-            root_key,
+            keys: Keys::Seed(root_key),
+            vbyte_fee_rate_floor: Amount::from_sat(DEFAULT_VBYTE_FEE_RATE_FLOOR),
+            estimate_mode: None,
+            gap_limit: DEFAULT_GAP_LIMIT,
+            account,
+            unlocker,
+            change_policy: ChangePolicy::default(),
             ledger,
         };
 
         wallet.init(seed).await?;
+        wallet.address_buffer.restore().await?;
+
+        Ok(wallet)
+    }
+
+    /// Creates a wallet that only watches the addresses of the given
+    /// descriptors. The keys are expected to be held by an external signer,
+    /// hence all operations that require signing fail with
+    /// `WalletError::WatchOnly`.
+    pub async fn new_watch_only(
+        descriptors: Vec<String>,
+        url: Url,
+        ledger: ledger::Bitcoin,
+    ) -> Result<Wallet, WalletError> {
+        // Checksums are added back when talking to bitcoind
+        let descriptors = descriptors
+            .iter()
+            .map(|descriptor| match descriptor.find('#') {
+                Some(index) => descriptor[..index].to_owned(),
+                None => descriptor.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        let name = Wallet::gen_watch_only_name(&descriptors);
+        let bitcoind_client = Client::new(url);
+
+        // Watch-only wallets never sign, hence are never unlocked
+        let unlocker = Arc::new(Unlocker::new(bitcoind_client.clone(), name.clone(), None));
+
+        let wallet = Wallet {
+            address_buffer: AddressBuffer::new(
+                bitcoind_client.clone(),
+                name.clone(),
+                Arc::clone(&unlocker),
+            ),
+            name,
+            bitcoind_client,
+            keys: Keys::WatchOnly {
+                descriptors,
+                next_index: AtomicU64::new(0),
+                next_change_index: AtomicU64::new(0),
+            },
+            vbyte_fee_rate_floor: Amount::from_sat(DEFAULT_VBYTE_FEE_RATE_FLOOR),
+            estimate_mode: None,
+            gap_limit: DEFAULT_GAP_LIMIT,
+            account: DEFAULT_ACCOUNT,
+            unlocker,
+            change_policy: ChangePolicy::default(),
+            ledger,
+        };
+
+        wallet.init_watch_only().await?;
+        wallet.restore_watched_indices().await?;
 
         Ok(wallet)
     }
 
+    /// Set the minimum fee rate, in sat/vB, returned by `estimate_fee_rate`.
+    pub fn with_fee_rate_floor(self, vbyte_fee_rate_floor: Amount) -> Self {
+        Wallet {
+            vbyte_fee_rate_floor,
+            ..self
+        }
+    }
+
+    /// Estimate fee rates in the mode and with the floor of `fees` if they are
+    /// estimated by bitcoind, static fees leave the wallet as it is.
+    pub fn with_fees(self, fees: config::BitcoinFees) -> Self {
+        match fees {
+            config::BitcoinFees::BitcoindEstimateSmartfee {
+                mode,
+                min_sat_per_vbyte,
+                ..
+            } => Wallet {
+                estimate_mode: Some(mode.into()),
+                ..self.with_fee_rate_floor(min_sat_per_vbyte)
+            },
+            config::BitcoinFees::SatsPerByte(_) => self,
+        }
+    }
+
+    /// Set the number of addresses per chain scanned by `address_index`.
+    pub fn with_gap_limit(self, gap_limit: u32) -> Self {
+        Wallet { gap_limit, ..self }
+    }
+
+    /// Set how the change of transactions built by the wallet is handled.
+    pub fn with_change_policy(self, change_policy: ChangePolicy) -> Self {
+        Wallet {
+            change_policy,
+            ..self
+        }
+    }
+
     async fn init(&self, seed: Seed) -> anyhow::Result<()> {
+        if self.account != DEFAULT_ACCOUNT {
+            return self.init_account().await;
+        }
+
         let info = self.info().await;
 
         // We assume the wallet present with the same name has the
         // same seed, which is fair but could be safer.
         match info {
             Err(_) => {
-                // TODO: Probably need to protect the wallet with a passphrase
                 self.bitcoind_client
                     .create_wallet(&self.name, None, Some(true), None, None)
                     .await?;
 
+                // The wallet is encrypted while still blank, otherwise bitcoind
+                // would replace the HD seed with a freshly generated one.
+                if let Some(passphrase) = &self.unlocker.passphrase {
+                    self.bitcoind_client
+                        .encrypt_wallet(&self.name, passphrase)
+                        .await?;
+                }
+
                 let wif = self.seed_as_wif(seed);
 
-                self.bitcoind_client
-                    .set_hd_seed(&self.name, Some(true), Some(wif))
-                    .await?;
+                self.unlocked(
+                    self.bitcoind_client
+                        .set_hd_seed(&self.name, Some(true), Some(wif)),
+                )
+                .await?;
 
                 Ok(())
             }
@@ -74,9 +260,11 @@ impl Wallet {
                 // have failed
                 let wif = self.seed_as_wif(seed);
 
-                self.bitcoind_client
-                    .set_hd_seed(&self.name, Some(true), Some(wif))
-                    .await?;
+                self.unlocked(
+                    self.bitcoind_client
+                        .set_hd_seed(&self.name, Some(true), Some(wif)),
+                )
+                .await?;
 
                 Ok(())
             }
@@ -84,36 +272,399 @@ impl Wallet {
         }
     }
 
+    /// bitcoind only derives the keys of the default account from an HD seed,
+    /// the descriptors of any other account are imported into a descriptor
+    /// wallet instead.
+    async fn init_account(&self) -> anyhow::Result<()> {
+        if self.info().await.is_ok() {
+            return Ok(());
+        }
+
+        self.bitcoind_client
+            .create_descriptor_wallet(&self.name, false)
+            .await?;
+
+        if let Some(passphrase) = &self.unlocker.passphrase {
+            self.bitcoind_client
+                .encrypt_wallet(&self.name, passphrase)
+                .await?;
+        }
+
+        let requests = self
+            .descriptors_with_checksums()
+            .await?
+            .into_iter()
+            .zip([false, true].iter())
+            .map(|(descriptor, internal)| ImportDescriptorRequest {
+                descriptor,
+                active: true,
+                internal: *internal,
+                range: [0, u64::from(DEFAULT_GAP_LIMIT) - 1],
+                // Rescan the whole chain as the addresses may already have been used
+                timestamp: 0,
+            })
+            .collect();
+
+        self.unlocked(
+            self.bitcoind_client
+                .import_descriptors(&self.name, requests),
+        )
+        .await
+    }
+
+    async fn init_watch_only(&self) -> anyhow::Result<()> {
+        if self.info().await.is_ok() {
+            return Ok(());
+        }
+
+        let mut requests = Vec::new();
+        let mut has_private_keys = false;
+        for descriptor in self.descriptors_with_checksums().await? {
+            let info = self
+                .bitcoind_client
+                .get_descriptor_info(&descriptor)
+                .await?;
+            has_private_keys |= info.has_private_keys;
+
+            requests.push(ImportDescriptorRequest {
+                descriptor,
+                active: false,
+                internal: false,
+                range: [0, WATCH_ONLY_RANGE - 1],
+                // Rescan the whole chain as the addresses may already have been used
+                timestamp: 0,
+            });
+        }
+
+        // bitcoind refuses to import descriptors with private keys in a wallet
+        // with private keys disabled. We never sign with this wallet either way.
+        self.bitcoind_client
+            .create_descriptor_wallet(&self.name, !has_private_keys)
+            .await?;
+
+        self.bitcoind_client
+            .import_descriptors(&self.name, requests)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The derivation indices of watch-only wallets are not persisted, derive
+    /// them from the addresses that received funds instead so that addresses
+    /// are not reused across restarts. Addresses that were handed out but
+    /// never paid to may be handed out again.
+    async fn restore_watched_indices(&self) -> anyhow::Result<()> {
+        let next_indices = match &self.keys {
+            Keys::WatchOnly {
+                next_index,
+                next_change_index,
+                ..
+            } => [next_index, next_change_index],
+            Keys::Seed(_) => return Ok(()),
+        };
+
+        let received = self
+            .bitcoind_client
+            .list_received_by_address(&self.name)
+            .await?
+            .into_iter()
+            .map(|received| received.address)
+            .collect::<HashSet<_>>();
+        if received.is_empty() {
+            return Ok(());
+        }
+
+        let descriptors = self.descriptors_with_checksums().await?;
+        for (descriptor, next_index) in descriptors.iter().zip(next_indices.iter()) {
+            let derived = self
+                .bitcoind_client
+                .derive_addresses(descriptor, Some([0, WATCH_ONLY_RANGE - 1]))
+                .await?;
+
+            if let Some(last_used) = derived
+                .iter()
+                .rposition(|address| received.contains(address))
+            {
+                next_index.store(last_used as u64 + 1, Ordering::SeqCst);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn root_key(&self) -> Result<&ExtendedPrivKey, WalletError> {
+        match &self.keys {
+            Keys::Seed(root_key) => Ok(root_key),
+            Keys::WatchOnly { .. } => Err(WalletError::WatchOnly),
+        }
+    }
+
+    fn ensure_can_sign(&self) -> Result<(), WalletError> {
+        self.root_key().map(|_| ())
+    }
+
     /// Derive a new key under transient derivation path
-    pub fn derive_transient_sk(&self, index: u32) -> anyhow::Result<SecretKey> {
-        let index = ChildNumber::from_hardened_idx(index)?;
+    pub fn derive_transient_sk(&self, index: u32) -> Result<SecretKey, WalletError> {
+        let index = ChildNumber::from_hardened_idx(index).context("invalid transient key index")?;
         let path = DerivationPath::from_str(TRANSIENT_DERIVATION_PATH)
             .expect("Valid derivation path in cost")
             .child(index);
-        let ext_key = self.root_key.derive_priv(&crate::SECP, &path)?;
+        let ext_key = self
+            .root_key()?
+            .derive_priv(&crate::SECP, &path)
+            .context("failed to derive transient key")?;
         Ok(ext_key.private_key.key)
     }
 
-    pub async fn info(&self) -> anyhow::Result<WalletInfoResponse> {
+    pub async fn info(&self) -> Result<WalletInfoResponse, WalletError> {
         self.assert_network(self.ledger).await?;
 
-        self.bitcoind_client.get_wallet_info(&self.name).await
+        Ok(self.bitcoind_client.get_wallet_info(&self.name).await?)
     }
 
-    pub async fn new_address(&self) -> anyhow::Result<Address> {
+    /// Addresses of seed wallets are taken from a buffer filled by bitcoind
+    /// ahead of time, hence concurrent calls are fast and never return the
+    /// same address twice.
+    pub async fn new_address(&self) -> Result<Address, WalletError> {
         self.assert_network(self.ledger).await?;
 
+        let address = match &self.keys {
+            Keys::Seed(_) => AddressBuffer::next(&self.address_buffer).await?,
+            // The imported descriptors are not active so bitcoind cannot hand
+            // out addresses for them, derive them from the external descriptor
+            Keys::WatchOnly { next_index, .. } => {
+                self.derive_watched_address(Chain::External, next_index)
+                    .await?
+            }
+        };
+
+        Ok(address)
+    }
+
+    /// An address of the internal chain to send change to.
+    ///
+    /// Watch-only wallets derive it from their internal descriptor, hence the
+    /// address type of the change policy does not apply to them.
+    async fn change_address(&self) -> anyhow::Result<Address> {
+        match &self.keys {
+            Keys::Seed(_) => {
+                self.bitcoind_client
+                    .get_raw_change_address(
+                        &self.name,
+                        Some(self.change_policy.address_type.to_string()),
+                    )
+                    .await
+            }
+            Keys::WatchOnly {
+                next_change_index, ..
+            } => {
+                self.derive_watched_address(Chain::Internal, next_change_index)
+                    .await
+            }
+        }
+    }
+
+    async fn derive_watched_address(
+        &self,
+        chain: Chain,
+        next_index: &AtomicU64,
+    ) -> anyhow::Result<Address> {
+        let index = next_index.fetch_add(1, Ordering::SeqCst);
+        if index >= WATCH_ONLY_RANGE {
+            anyhow::bail!(
+                "all {} watched addresses of the {:?} chain were used",
+                WATCH_ONLY_RANGE,
+                chain
+            );
+        }
+
+        let position = match chain {
+            Chain::External => 0,
+            Chain::Internal => 1,
+        };
+        let descriptor = self
+            .descriptors_with_checksums()
+            .await?
+            .into_iter()
+            .nth(position)
+            .with_context(|| format!("watch-only wallet has no {:?} descriptor", chain))?;
+
         self.bitcoind_client
-            .get_new_address(&self.name, None, Some("bech32".into()))
-            .await
+            .derive_addresses(&descriptor, Some([index, index]))
+            .await?
+            .pop()
+            .context("bitcoind did not derive any address")
     }
 
-    pub async fn balance(&self) -> anyhow::Result<Amount> {
+    /// Looks up the chain and derivation index of an address of this wallet.
+    ///
+    /// Only the first `gap_limit` addresses of each chain are scanned, `None`
+    /// is returned if the address is not amongst them.
+    pub async fn address_index(
+        &self,
+        address: &Address,
+    ) -> Result<Option<(Chain, u32)>, WalletError> {
         self.assert_network(self.ledger).await?;
 
-        self.bitcoind_client
+        if self.gap_limit == 0 {
+            return Ok(None);
+        }
+        let range = [0, u64::from(self.gap_limit) - 1];
+
+        let descriptors = self.descriptors_with_checksums().await?;
+        for (chain, descriptor) in [Chain::External, Chain::Internal]
+            .iter()
+            .zip(descriptors.iter())
+        {
+            let addresses = self
+                .bitcoind_client
+                .derive_addresses(descriptor, Some(range))
+                .await?;
+
+            if let Some(index) = addresses.iter().position(|derived| derived == address) {
+                let index = u32::try_from(index).context("derivation index exceeds u32")?;
+                return Ok(Some((*chain, index)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Creates an unsigned PSBT paying to `outputs`, to be signed elsewhere.
+    ///
+    /// The inputs are selected from the coins of this wallet and the change is
+    /// sent to an address of the internal chain, unless the change policy
+    /// discards it.
+    pub async fn create_psbt(
+        &self,
+        outputs: Vec<(Address, Amount)>,
+    ) -> Result<String, WalletError> {
+        self.assert_network(self.ledger).await?;
+
+        Ok(self.fund_psbt(outputs, None).await?)
+    }
+
+    async fn fund_psbt(
+        &self,
+        outputs: Vec<(Address, Amount)>,
+        vbyte_fee_rate: Option<Amount>,
+    ) -> anyhow::Result<String> {
+        if outputs.is_empty() {
+            anyhow::bail!("cannot create a psbt without outputs");
+        }
+
+        let change_address = self.change_address().await?;
+        let include_watching = matches!(self.keys, Keys::WatchOnly { .. });
+
+        let psbt = self
+            .bitcoind_client
+            .wallet_create_funded_psbt(
+                &self.name,
+                outputs,
+                change_address.clone(),
+                include_watching,
+                vbyte_fee_rate,
+            )
+            .await?;
+
+        discard_small_change(&psbt, &change_address, self.change_policy.discard_below)
+    }
+
+    /// Finalizes a fully signed PSBT and extracts the transaction, ready to be
+    /// broadcast.
+    pub async fn finalize_and_extract(&self, psbt: &str) -> Result<Transaction, WalletError> {
+        let response = self.bitcoind_client.finalize_psbt(psbt).await?;
+
+        let hex = match response {
+            FinalizePsbtResponse {
+                complete: true,
+                hex: Some(hex),
+                ..
+            } => hex,
+            _ => return Err(anyhow::anyhow!("psbt is not fully signed").into()),
+        };
+        let bytes = hex::decode(hex).context("finalized transaction is not hex")?;
+        let transaction = ::bitcoin::consensus::deserialize(&bytes)
+            .context("failed to deserialize finalized transaction")?;
+
+        Ok(transaction)
+    }
+
+    /// Signs `message` with the key of `address`, proving control over the
+    /// funds of the address without moving them.
+    ///
+    /// bitcoind only signs messages for legacy (P2PKH) addresses.
+    pub async fn sign_message(
+        &self,
+        address: &Address,
+        message: &str,
+    ) -> Result<String, WalletError> {
+        self.assert_network(self.ledger).await?;
+        self.ensure_can_sign()?;
+
+        let info = self
+            .bitcoind_client
+            .get_address_info(&self.name, address)
+            .await?;
+        if !info.is_mine {
+            return Err(AddressNotOwned(address.clone()).into());
+        }
+
+        let signature = self
+            .unlocked(
+                self.bitcoind_client
+                    .sign_message(&self.name, address, message),
+            )
+            .await?;
+        Ok(signature)
+    }
+
+    /// Verifies that `signature` was produced by the key of `address` over
+    /// `message`, the address does not need to belong to this wallet.
+    pub async fn verify_message(
+        &self,
+        address: &Address,
+        message: &str,
+        signature: &str,
+    ) -> Result<bool, WalletError> {
+        self.assert_network(self.ledger).await?;
+
+        let valid = self
+            .bitcoind_client
+            .verify_message(address, signature, message)
+            .await?;
+        Ok(valid)
+    }
+
+    /// Lists the unspent outputs of the wallet with at least
+    /// `min_confirmations`.
+    pub async fn list_unspent(&self, min_confirmations: u32) -> Result<Vec<Utxo>, WalletError> {
+        self.assert_network(self.ledger).await?;
+
+        let unspent = self
+            .bitcoind_client
+            .list_unspent(&self.name, min_confirmations)
+            .await?;
+
+        Ok(unspent
+            .into_iter()
+            .map(|unspent| Utxo {
+                outpoint: OutPoint::new(unspent.txid, unspent.vout),
+                amount: unspent.amount,
+                address: unspent.address,
+                confirmations: unspent.confirmations,
+            })
+            .collect())
+    }
+
+    pub async fn balance(&self) -> Result<Amount, WalletError> {
+        self.assert_network(self.ledger).await?;
+
+        let balance = self
+            .bitcoind_client
             .get_balance(&self.name, None, None, None)
-            .await
+            .await?;
+        Ok(balance)
     }
 
     /// Returns the seed in wif format, this allows the user to import the
@@ -162,15 +713,22 @@ impl Wallet {
 
     /// Wallet descriptors as specified in https://github.com/bitcoin/bitcoin/blob/master/doc/descriptors.md
     pub fn descriptors(&self) -> Vec<String> {
-        Self::hd_paths()
-            .iter()
-            .map(|path| format!("wpkh({}{})", self.root_key, path))
-            .collect()
+        match &self.keys {
+            Keys::Seed(root_key) => Self::hd_paths(self.account)
+                .iter()
+                .map(|path| format!("wpkh({}{})", root_key, path))
+                .collect(),
+            Keys::WatchOnly { descriptors, .. } => descriptors.clone(),
+        }
     }
 
-    pub fn descriptors_from_seed(seed: &Seed, ledger: ledger::Bitcoin) -> Vec<String> {
+    pub fn descriptors_from_seed(
+        seed: &Seed,
+        ledger: ledger::Bitcoin,
+        account: u32,
+    ) -> Vec<String> {
         let ext_priv_key = Self::root_extended_private_key_from_seed(seed, ledger);
-        Self::hd_paths()
+        Self::hd_paths(account)
             .iter()
             .map(|path| format!("wpkh({}{})", ext_priv_key, path))
             .collect()
@@ -179,7 +737,7 @@ impl Wallet {
     /// Some bitcoind rpc command requires the descriptor to be
     /// suffixed with a checksum. For now we are going to ask bitcoind
     /// to calculate the checksum for us.
-    pub async fn descriptors_with_checksums(&self) -> anyhow::Result<Vec<String>> {
+    pub async fn descriptors_with_checksums(&self) -> Result<Vec<String>, WalletError> {
         let mut descriptors = Vec::new();
         for descriptor in self.descriptors() {
             let response = self
@@ -193,6 +751,33 @@ impl Wallet {
         Ok(descriptors)
     }
 
+    /// The descriptors of the external and internal chain, bound to the
+    /// derivation indices `start` to `end` (inclusive) so that a wallet
+    /// importing them derives a known window of addresses.
+    pub async fn descriptors_ranged(
+        &self,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<RangedDescriptor>, WalletError> {
+        if start > end {
+            return Err(
+                anyhow::anyhow!("range start {} is greater than its end {}", start, end).into(),
+            );
+        }
+
+        let descriptors = self
+            .descriptors_with_checksums()
+            .await?
+            .into_iter()
+            .map(|descriptor| RangedDescriptor {
+                descriptor,
+                range: [u64::from(start), u64::from(end)],
+            })
+            .collect();
+
+        Ok(descriptors)
+    }
+
     /// In accordance with [BIP32](https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki),
     /// bitcoind uses 2 derivations paths to generate new keys and addresses,
     /// "m/iH/0/k corresponds to the k'th keypair of the external chain of
@@ -201,212 +786,2173 @@ impl Wallet {
     /// "m/iH/1/k corresponds to the k'th keypair of the internal chain of
     /// account number i of the HDW derived from master m." ie, the
     /// addresses to send change.
-    fn hd_paths() -> Vec<&'static str> {
+    /// Unlike BIP32, bitcoind derives all levels hardened.
+    fn hd_paths(account: u32) -> Vec<String> {
         vec![
-            BITCOIND_DEFAULT_EXTERNAL_DERIVATION_PATH,
-            BITCOIND_DEFAULT_INTERNAL_DERIVATION_PATH,
+            format!("/{}h/0h/*h", account),
+            format!("/{}h/1h/*h", account),
         ]
     }
 
+    /// Pays the fee rate returned by `estimate_fee_rate` for the transaction
+    /// to be mined as fast as our HTLCs expect.
+    ///
+    /// The wallet builds the transaction itself if a change policy is set,
+    /// otherwise bitcoind does.
     pub async fn send_to_address(
         &self,
         address: Address,
         amount: Amount,
         ledger: ledger::Bitcoin,
-    ) -> anyhow::Result<Txid> {
-        self.assert_network(ledger).await?;
+    ) -> Result<Txid, WalletError> {
+        let target_blocks = bitcoin_mine_within_blocks(ledger.into());
+        let vbyte_fee_rate = self.estimate_fee_rate(u16::from(target_blocks)).await?;
+
+        if self.change_policy != ChangePolicy::default() {
+            return self
+                .send_to_address_with_change_policy(address, amount, ledger, vbyte_fee_rate)
+                .await;
+        }
 
-        let txid = self
-            .bitcoind_client
-            .send_to_address(&self.name, address, amount)
-            .await?;
-        Ok(txid)
+        self.send_to_address_with_fee_rate(address, amount, ledger, vbyte_fee_rate)
+            .await
     }
 
-    pub async fn fund_htlc(
+    /// Pays all `outputs` in a single transaction, which is cheaper than
+    /// calling `send_to_address` for each of them.
+    pub async fn send_to_many(
         &self,
-        address: Address,
-        amount: Amount,
+        outputs: Vec<(Address, Amount)>,
         ledger: ledger::Bitcoin,
-        kbyte_fee_rate: Amount,
-    ) -> anyhow::Result<OutPoint> {
+    ) -> Result<Txid, WalletError> {
+        self.ensure_can_sign()?;
         self.assert_network(ledger).await?;
 
-        let outpoint = self
+        if outputs.is_empty() {
+            return Err(anyhow::anyhow!("cannot send a transaction without outputs").into());
+        }
+
+        let total = outputs
+            .iter()
+            .try_fold(Amount::ZERO, |total, (_, amount)| {
+                total.checked_add(*amount)
+            })
+            .context("total amount of outputs overflows")?;
+        let confirmed_balance = self
             .bitcoind_client
-            .fund_htlc(&self.name, address, amount, kbyte_fee_rate)
+            .get_balance(&self.name, Some(1), None, None)
             .await?;
-        Ok(outpoint)
-    }
 
-    pub async fn send_raw_transaction(
-        &self,
-        transaction: Transaction,
-        ledger: ledger::Bitcoin,
-    ) -> anyhow::Result<Txid> {
-        self.assert_network(ledger).await?;
+        if total > confirmed_balance {
+            return Err(anyhow::anyhow!(
+                "total amount of outputs {} exceeds confirmed balance {}",
+                total,
+                confirmed_balance
+            )
+            .into());
+        }
 
         let txid = self
-            .bitcoind_client
-            .send_raw_transaction(&self.name, transaction)
+            .unlocked(self.bitcoind_client.send_many(&self.name, outputs))
             .await?;
         Ok(txid)
     }
 
-    pub async fn wait_until_confirmed(
+    /// Moves the whole balance of the wallet to `address` in a single
+    /// transaction, the fee is deducted from the swept amount. `fee_rate` is
+    /// in sat/vB, bitcoind estimates the fee if it is not set.
+    ///
+    /// Refuses to sweep while there are unconfirmed funds unless
+    /// `include_unconfirmed` is set, in which case unconfirmed change of the
+    /// wallet is swept as well. Unconfirmed funds received from someone else
+    /// cannot be spent yet and are left behind.
+    pub async fn sweep_to(
         &self,
-        transaction_hash: Txid,
-        ledger: ledger::Bitcoin,
-    ) -> anyhow::Result<()> {
-        let poll_interval = match ledger {
-            ledger::Bitcoin::Mainnet | ledger::Bitcoin::Testnet => 30,
-            ledger::Bitcoin::Regtest => 1,
-        };
-
-        loop {
-            let confirmations = self
-                .bitcoind_client
-                .get_confirmations(transaction_hash)
-                .await?;
+        address: Address,
+        fee_rate: Option<Amount>,
+        include_unconfirmed: bool,
+    ) -> Result<Txid, WalletError> {
+        self.ensure_can_sign()?;
+        self.assert_network(self.ledger).await?;
 
-            if confirmations > 0 {
-                tracing::debug!(
-                    "found tx {} in chain with {} confirmations",
-                    transaction_hash,
-                    confirmations
+        let confirmed = self
+            .bitcoind_client
+            .get_balance(&self.name, Some(1), None, None)
+            .await?;
+        let balances = self.bitcoind_client.get_balances(&self.name).await?.mine;
+        let unconfirmed = balances
+            .trusted
+            .checked_sub(confirmed)
+            .and_then(|change| change.checked_add(balances.untrusted_pending))
+            .context("failed to compute unconfirmed balance")?;
+
+        if unconfirmed != Amount::ZERO {
+            if !include_unconfirmed {
+                return Err(SweepError::UnconfirmedFunds(unconfirmed).into());
+            }
+            if balances.untrusted_pending != Amount::ZERO {
+                tracing::warn!(
+                    "leaving {} of unconfirmed incoming funds behind",
+                    balances.untrusted_pending
                 );
-
-                return Ok(());
             }
+        }
 
-            tokio::time::delay_for(std::time::Duration::from_secs(poll_interval)).await;
+        if balances.trusted == Amount::ZERO {
+            return Err(SweepError::NothingToSweep.into());
         }
-    }
 
-    #[cfg(all(test, feature = "testcontainers"))]
-    pub async fn dump(&self, filename: &std::path::Path) -> anyhow::Result<()> {
-        self.bitcoind_client.dump_wallet(&self.name, filename).await
+        let txid = self
+            .unlocked(self.bitcoind_client.send_to_address_subtract_fee(
+                &self.name,
+                address,
+                balances.trusted,
+                fee_rate,
+            ))
+            .await?;
+        Ok(txid)
     }
 
-    async fn assert_network(&self, expected: ledger::Bitcoin) -> anyhow::Result<()> {
-        let actual = self.bitcoind_client.network().await?;
+    /// Spends up to `max_inputs` of the smallest confirmed outputs of the
+    /// wallet to a fresh change address, paying `vbyte_fee_rate` (in sat/vB).
+    ///
+    /// Returns `None` if there are fewer than two outputs to consolidate.
+    /// Outputs locked in bitcoind are not listed as unspent and hence left
+    /// alone.
+    pub async fn consolidate(
+        &self,
+        max_inputs: usize,
+        vbyte_fee_rate: Amount,
+    ) -> Result<Option<Txid>, WalletError> {
+        self.ensure_can_sign()?;
 
-        if expected != actual {
-            anyhow::bail!("Wrong network: expected {}, got {}", expected, actual);
+        let mut utxos = self.list_unspent(1).await?;
+        utxos.sort_by_key(|utxo| utxo.amount.as_sat());
+        utxos.truncate(max_inputs);
+
+        if utxos.len() < 2 {
+            return Ok(None);
         }
 
-        Ok(())
-    }
+        let amount = utxos
+            .iter()
+            .try_fold(Amount::ZERO, |total, utxo| total.checked_add(utxo.amount))
+            .context("total amount of outputs overflowed")?;
+        let inputs = utxos.into_iter().map(|utxo| utxo.outpoint).collect();
+        let address = self.change_address().await?;
 
-    fn gen_name(seed: Seed) -> String {
-        let mut engine = sha256::HashEngine::default();
+        let psbt = self
+            .bitcoind_client
+            .wallet_create_psbt_from_inputs(&self.name, inputs, address, amount, vbyte_fee_rate)
+            .await?;
+        let psbt = self
+            .unlocked(self.bitcoind_client.wallet_process_psbt(&self.name, &psbt))
+            .await?;
+        let transaction = self.finalize_and_extract(&psbt).await?;
 
-        engine.input(&seed.bytes());
+        let txid = self.send_raw_transaction(transaction, self.ledger).await?;
+        Ok(Some(txid))
+    }
 
-        let hash = sha256::Hash::from_engine(engine);
-        let hash = hash.into_inner();
+    /// Send `amount` to `address` paying `vbyte_fee_rate` (in sat/vB)
+    /// instead of letting bitcoind pick the fee.
+    pub async fn send_to_address_with_fee_rate(
+        &self,
+        address: Address,
+        amount: Amount,
+        ledger: ledger::Bitcoin,
+        vbyte_fee_rate: Amount,
+    ) -> Result<Txid, WalletError> {
+        self.ensure_can_sign()?;
+        self.assert_network(ledger).await?;
+
+        let txid = self
+            .unlocked(self.bitcoind_client.send_to_address_with_fee_rate(
+                &self.name,
+                address,
+                amount,
+                vbyte_fee_rate,
+            ))
+            .await?;
+        Ok(txid)
+    }
+
+    /// Send `amount` to `address` paying at least `vbyte_fee_rate` (in sat/vB).
+    ///
+    /// Unlike `send_to_address_with_fee_rate`, the transaction is built and
+    /// signed by the wallet itself so that the change policy applies to it.
+    pub async fn send_to_address_with_change_policy(
+        &self,
+        address: Address,
+        amount: Amount,
+        ledger: ledger::Bitcoin,
+        vbyte_fee_rate: Amount,
+    ) -> Result<Txid, WalletError> {
+        self.ensure_can_sign()?;
+        self.assert_network(ledger).await?;
+
+        let psbt = self
+            .fund_psbt(vec![(address, amount)], Some(vbyte_fee_rate))
+            .await?;
+        let psbt = self
+            .unlocked(self.bitcoind_client.wallet_process_psbt(&self.name, &psbt))
+            .await?;
+        let transaction = self.finalize_and_extract(&psbt).await?;
+
+        self.send_raw_transaction(transaction, ledger).await
+    }
+
+    /// Returns the fee rate, in sat/vB, that bitcoind estimates is needed for
+    /// a transaction to be confirmed within `target_blocks`.
+    ///
+    /// The configured floor is returned if bitcoind does not have enough data
+    /// to provide an estimate, which is common on a fresh regtest chain.
+    pub async fn estimate_fee_rate(&self, target_blocks: u16) -> Result<Amount, WalletError> {
+        self.assert_network(self.ledger).await?;
+
+        let response = self
+            .bitcoind_client
+            .estimate_smart_fee(u32::from(target_blocks), self.estimate_mode)
+            .await?;
+
+        let vbyte_rate = match response.kbyte_rate {
+            Some(kbyte_rate) => kbyte_rate
+                .checked_div(1000)
+                .context("could not div kbyte rate")?,
+            None => {
+                tracing::debug!(
+                    "bitcoind could not estimate fee rate, using floor of {} sat/vB: {:?}",
+                    self.vbyte_fee_rate_floor.as_sat(),
+                    response.errors
+                );
+                return Ok(self.vbyte_fee_rate_floor);
+            }
+        };
+
+        Ok(std::cmp::max(vbyte_rate, self.vbyte_fee_rate_floor))
+    }
+
+    /// Replaces an unconfirmed transaction of this wallet by one paying
+    /// `new_fee_rate` (in sat/vB), returns the id of the replacement
+    /// transaction.
+    pub async fn bump_fee(&self, txid: Txid, new_fee_rate: Amount) -> Result<Txid, WalletError> {
+        self.ensure_can_sign()?;
+        self.assert_network(self.ledger).await?;
+
+        let transaction = self
+            .bitcoind_client
+            .get_transaction(&self.name, txid)
+            .await?;
+
+        if transaction.confirmations > 0 {
+            return Err(FeeBumpError::AlreadyConfirmed(txid).into());
+        }
+        if transaction.bip125_replaceable != Bip125Replaceable::Yes {
+            return Err(FeeBumpError::NotReplaceable(txid).into());
+        }
+
+        let txid = self
+            .unlocked(
+                self.bitcoind_client
+                    .bump_fee(&self.name, txid, new_fee_rate),
+            )
+            .await?;
+        Ok(txid)
+    }
+
+    pub async fn fund_htlc(
+        &self,
+        address: Address,
+        amount: Amount,
+        ledger: ledger::Bitcoin,
+        kbyte_fee_rate: Amount,
+    ) -> Result<OutPoint, WalletError> {
+        self.ensure_can_sign()?;
+        self.assert_network(ledger).await?;
+
+        let outpoint = self
+            .unlocked(
+                self.bitcoind_client
+                    .fund_htlc(&self.name, address, amount, kbyte_fee_rate),
+            )
+            .await?;
+        Ok(outpoint)
+    }
+
+    pub async fn send_raw_transaction(
+        &self,
+        transaction: Transaction,
+        ledger: ledger::Bitcoin,
+    ) -> Result<Txid, WalletError> {
+        self.ensure_can_sign()?;
+        self.assert_network(ledger).await?;
+
+        let txid = self
+            .unlocked(
+                self.bitcoind_client
+                    .send_raw_transaction(&self.name, transaction),
+            )
+            .await?;
+        Ok(txid)
+    }
+
+    /// Checks whether `transaction` would be accepted into the mempool, e.g.
+    /// to catch a fee that is too low before broadcasting.
+    pub async fn test_mempool_accept(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<MempoolAcceptResult, WalletError> {
+        self.assert_network(self.ledger).await?;
+
+        Ok(self
+            .bitcoind_client
+            .test_mempool_accept(transaction)
+            .await?)
+    }
+
+    pub async fn wait_until_confirmed(
+        &self,
+        transaction_hash: Txid,
+        ledger: ledger::Bitcoin,
+    ) -> Result<(), WalletError> {
+        let poll_interval = match ledger {
+            ledger::Bitcoin::Mainnet
+            | ledger::Bitcoin::Testnet
+            | ledger::Bitcoin::Testnet4
+            | ledger::Bitcoin::Signet => 30,
+            ledger::Bitcoin::Regtest => 1,
+        };
+
+        loop {
+            let confirmations = self
+                .bitcoind_client
+                .get_confirmations(transaction_hash)
+                .await?;
+
+            if confirmations > 0 {
+                tracing::debug!(
+                    "found tx {} in chain with {} confirmations",
+                    transaction_hash,
+                    confirmations
+                );
+
+                return Ok(());
+            }
+
+            tokio::time::delay_for(std::time::Duration::from_secs(poll_interval)).await;
+        }
+    }
+
+    /// Returns the number of confirmations of the given transaction.
+    ///
+    /// A transaction that the wallet does not know about yet is reported as
+    /// having 0 confirmations.
+    pub async fn transaction_confirmations(&self, txid: Txid) -> Result<u32, WalletError> {
+        let confirmations = self
+            .bitcoind_client
+            .find_confirmations(&self.name, txid)
+            .await?;
+
+        Ok(confirmations.unwrap_or(0))
+    }
+
+    /// Polls bitcoind every `poll_interval` until the given transaction has at
+    /// least `target` confirmations and returns the number of confirmations.
+    ///
+    /// Fails if `timeout` elapses first or if bitcoind forgets about the
+    /// transaction after having seen it.
+    pub async fn wait_for_confirmations(
+        &self,
+        txid: Txid,
+        target: u32,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<u32, WalletError> {
+        let deadline = Instant::now() + timeout;
+        let mut seen = false;
+
+        loop {
+            match self
+                .bitcoind_client
+                .find_confirmations(&self.name, txid)
+                .await?
+            {
+                Some(confirmations) if confirmations >= target => return Ok(confirmations),
+                Some(_) => seen = true,
+                None if seen => return Err(ConfirmationError::DroppedFromMempool(txid).into()),
+                None => {}
+            }
+
+            if Instant::now() >= deadline {
+                return Err(ConfirmationError::Timeout { txid, target }.into());
+            }
+
+            tokio::time::delay_for(poll_interval).await;
+        }
+    }
+
+    /// Polls the wallet until outputs paying at least `min_amount` in total to
+    /// `address` arrived and returns the txid of the payment.
+    ///
+    /// Unconfirmed outputs count. If the payment is split across several
+    /// outputs, the txid of the output that completed it is returned.
+    pub async fn wait_for_payment(
+        &self,
+        address: &Address,
+        min_amount: Amount,
+        timeout: Duration,
+    ) -> Result<Txid, WalletError> {
+        let poll_interval = match self.ledger {
+            ledger::Bitcoin::Mainnet
+            | ledger::Bitcoin::Testnet
+            | ledger::Bitcoin::Testnet4
+            | ledger::Bitcoin::Signet => Duration::from_secs(30),
+            ledger::Bitcoin::Regtest => Duration::from_secs(1),
+        };
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let mut payments = self
+                .list_unspent(0)
+                .await?
+                .into_iter()
+                .filter(|utxo| &utxo.address == address)
+                .collect::<Vec<_>>();
+            // Oldest first
+            payments.sort_by_key(|utxo| std::cmp::Reverse(utxo.confirmations));
+
+            let mut received = Amount::ZERO;
+            for payment in payments {
+                received += payment.amount;
+
+                if received >= min_amount {
+                    return Ok(payment.outpoint.txid);
+                }
+            }
+
+            if Instant::now() >= deadline {
+                return Err(PaymentError::Timeout {
+                    address: address.clone(),
+                    min_amount,
+                    received,
+                }
+                .into());
+            }
+
+            tokio::time::delay_for(poll_interval).await;
+        }
+    }
+
+    /// Runs `operation` with the wallet unlocked if it is protected by a
+    /// passphrase and locks it again afterwards.
+    async fn unlocked<T>(
+        &self,
+        operation: impl Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        self.unlocker.unlocked(operation).await
+    }
+
+    #[cfg(all(test, feature = "testcontainers"))]
+    pub async fn dump(&self, filename: &std::path::Path) -> Result<(), WalletError> {
+        Ok(self
+            .bitcoind_client
+            .dump_wallet(&self.name, filename)
+            .await?)
+    }
+
+    async fn assert_network(&self, expected: ledger::Bitcoin) -> Result<(), WalletError> {
+        let actual = self.bitcoind_client.network().await?;
+
+        if expected != actual {
+            return Err(WalletError::WrongNetwork { expected, actual });
+        }
+
+        Ok(())
+    }
+
+    fn gen_watch_only_name(descriptors: &[String]) -> String {
+        let mut engine = sha256::HashEngine::default();
+
+        for descriptor in descriptors {
+            engine.input(descriptor.as_bytes());
+        }
+
+        let hash = sha256::Hash::from_engine(engine);
+        let hash = hash.into_inner();
+
+        format!(
+            "nectar_watch_only_{:x}{:x}{:x}{:x}",
+            hash[0], hash[1], hash[2], hash[3]
+        )
+    }
+
+    /// Wallets of other accounts than the default one are suffixed with the
+    /// account, as they cannot share the bitcoind wallet.
+    fn gen_name(seed: Seed, account: u32) -> String {
+        let mut engine = sha256::HashEngine::default();
+
+        engine.input(&seed.bytes());
+
+        let hash = sha256::Hash::from_engine(engine);
+        let hash = hash.into_inner();
+
+        let name = format!(
+            "nectar_{:x}{:x}{:x}{:x}",
+            hash[0], hash[1], hash[2], hash[3]
+        );
+
+        if account == DEFAULT_ACCOUNT {
+            name
+        } else {
+            format!("{}_account_{}", name, account)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Utxo {
+    pub outpoint: OutPoint,
+    pub amount: Amount,
+    pub address: Address,
+    pub confirmations: u32,
+}
+
+/// The chains of a BIP32 wallet, addresses of the external chain are handed
+/// out to receive funds and the internal chain is used for change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chain {
+    External,
+    Internal,
+}
+
+#[derive(Debug)]
+enum Keys {
+    /// The wallet is derived from our seed and can sign transactions.
+    Seed(ExtendedPrivKey),
+    /// The keys are held by an external signer.
+    WatchOnly {
+        descriptors: Vec<String>,
+        next_index: AtomicU64,
+        next_change_index: AtomicU64,
+    },
+}
+
+/// Addresses of the external chain that bitcoind generated but we did not hand
+/// out yet, oldest first.
+///
+/// Buffered addresses are labelled in the bitcoind wallet until they are handed
+/// out, hence a restart buffers them again instead of skipping them. The
+/// addresses restored this way come first, in no particular order.
+#[derive(derivative::Derivative)]
+#[derivative(Debug)]
+struct AddressBuffer {
+    bitcoind_client: Client,
+    wallet_name: String,
+    /// bitcoind needs to unlock an encrypted wallet to refill its keypool
+    /// when generating an address.
+    unlocker: Arc<Unlocker>,
+    #[derivative(Debug = "ignore")]
+    addresses: Mutex<VecDeque<Address>>,
+    /// Held while fetching an address, so that addresses are buffered in the
+    /// order bitcoind generated them.
+    #[derivative(Debug = "ignore")]
+    fetch: Mutex<()>,
+    /// Whether a batch is being fetched in the background.
+    refilling: AtomicBool,
+}
+
+impl AddressBuffer {
+    fn new(bitcoind_client: Client, wallet_name: String, unlocker: Arc<Unlocker>) -> Arc<Self> {
+        Arc::new(AddressBuffer {
+            bitcoind_client,
+            wallet_name,
+            unlocker,
+            addresses: Mutex::new(VecDeque::new()),
+            fetch: Mutex::new(()),
+            refilling: AtomicBool::new(false),
+        })
+    }
+
+    /// Buffers the addresses a previous run fetched but did not hand out.
+    async fn restore(&self) -> anyhow::Result<()> {
+        let addresses = self
+            .bitcoind_client
+            .get_addresses_by_label(&self.wallet_name, BUFFERED_ADDRESS_LABEL)
+            .await?;
+        self.addresses.lock().await.extend(addresses);
+
+        Ok(())
+    }
+
+    /// Takes the oldest address out of the buffer. If the buffer is empty we
+    /// only wait for one address, the rest of the batch is fetched in the
+    /// background.
+    async fn next(buffer: &Arc<Self>) -> anyhow::Result<Address> {
+        let address = loop {
+            if let Some(address) = buffer.addresses.lock().await.pop_front() {
+                break address;
+            }
+
+            buffer.fetch_one().await?;
+        };
+
+        if buffer.addresses.lock().await.len() < ADDRESS_BUFFER_LOW_WATER {
+            AddressBuffer::refill_in_background(buffer);
+        }
+
+        // The address is not buffered anymore once it is handed out, it must
+        // not be restored after a restart
+        buffer
+            .bitcoind_client
+            .set_label(&buffer.wallet_name, &address, "")
+            .await?;
+
+        Ok(address)
+    }
+
+    /// Fetches a batch of addresses, unless a batch is being fetched already.
+    fn refill_in_background(buffer: &Arc<Self>) {
+        if buffer.refilling.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let buffer = Arc::clone(buffer);
+        tokio::spawn(async move {
+            for _ in 0..ADDRESS_BATCH_SIZE {
+                if let Err(e) = buffer.fetch_one().await {
+                    tracing::warn!("failed to refill the address buffer: {:#}", e);
+                    break;
+                }
+            }
+
+            buffer.refilling.store(false, Ordering::SeqCst);
+        });
+    }
+
+    async fn fetch_one(&self) -> anyhow::Result<()> {
+        let _fetch = self.fetch.lock().await;
+
+        let address = self
+            .unlocker
+            .unlocked(self.bitcoind_client.get_new_address(
+                &self.wallet_name,
+                Some(BUFFERED_ADDRESS_LABEL.into()),
+                Some("bech32".into()),
+            ))
+            .await?;
+        self.addresses.lock().await.push_back(address);
+
+        Ok(())
+    }
+}
+
+/// Unlocks an encrypted wallet for the operations that need its keys.
+///
+/// Operations may run concurrently, the wallet is only locked again once the
+/// last of them finished so that no operation finds it locked midway.
+#[derive(derivative::Derivative)]
+#[derivative(Debug)]
+struct Unlocker {
+    bitcoind_client: Client,
+    wallet_name: String,
+    #[derivative(Debug = "ignore")]
+    passphrase: Option<String>,
+    /// Number of operations running with the wallet unlocked. Held while
+    /// unlocking and locking the wallet so that both are applied in the order
+    /// the count changes.
+    #[derivative(Debug = "ignore")]
+    users: Mutex<usize>,
+}
+
+impl Unlocker {
+    fn new(bitcoind_client: Client, wallet_name: String, passphrase: Option<String>) -> Self {
+        Unlocker {
+            bitcoind_client,
+            wallet_name,
+            passphrase,
+            users: Mutex::new(0),
+        }
+    }
+
+    async fn unlocked<T>(
+        &self,
+        operation: impl Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        let passphrase = match &self.passphrase {
+            Some(passphrase) => passphrase,
+            None => return operation.await,
+        };
+
+        {
+            let mut users = self.users.lock().await;
+
+            // Unlocking an unlocked wallet only extends the unlock timeout
+            self.bitcoind_client
+                .wallet_passphrase(&self.wallet_name, passphrase, UNLOCK_TIMEOUT_SECS)
+                .await
+                .context("failed to unlock bitcoin wallet, is the passphrase correct?")?;
+            *users += 1;
+        }
+
+        let result = operation.await;
+
+        let mut users = self.users.lock().await;
+        *users -= 1;
+        if *users == 0 {
+            if let Err(e) = self.bitcoind_client.wallet_lock(&self.wallet_name).await {
+                tracing::warn!(
+                    "failed to lock bitcoin wallet, it will be locked automatically in {}s: {:#}",
+                    UNLOCK_TIMEOUT_SECS,
+                    e
+                );
+            }
+        }
+
+        result
+    }
+}
+
+/// How the wallet deals with the change of the transactions it builds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChangePolicy {
+    /// Change below this amount is not worth an output of its own, it is
+    /// added to the fee instead.
+    pub discard_below: Amount,
+    pub address_type: AddressType,
+}
+
+impl Default for ChangePolicy {
+    fn default() -> Self {
+        ChangePolicy {
+            discard_below: Amount::ZERO,
+            address_type: AddressType::Bech32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, strum_macros::Display)]
+#[serde(rename_all = "kebab-case")]
+pub enum AddressType {
+    #[strum(serialize = "legacy")]
+    Legacy,
+    #[strum(serialize = "p2sh-segwit")]
+    P2shSegwit,
+    #[strum(serialize = "bech32")]
+    Bech32,
+}
+
+/// Removes the output paying to `change_address` from `psbt` if it is below
+/// `discard_below`, its amount goes to the fee instead. Must be applied before
+/// the PSBT is signed.
+fn discard_small_change(
+    psbt: &str,
+    change_address: &Address,
+    discard_below: Amount,
+) -> anyhow::Result<String> {
+    let bytes = base64::decode(psbt).context("psbt is not base64")?;
+    let mut psbt: PartiallySignedTransaction =
+        ::bitcoin::consensus::deserialize(&bytes).context("failed to decode psbt")?;
+
+    let change_script = change_address.script_pubkey();
+    let small_change = psbt.global.unsigned_tx.output.iter().position(|output| {
+        output.script_pubkey == change_script && output.value < discard_below.as_sat()
+    });
+
+    if let Some(index) = small_change {
+        psbt.global.unsigned_tx.output.remove(index);
+        psbt.outputs.remove(index);
+    }
+
+    Ok(base64::encode(::bitcoin::consensus::serialize(&psbt)))
+}
+
+/// Failures of the wallet, errors of bitcoind that callers may want to handle
+/// are given their own variant.
+#[derive(Debug, thiserror::Error)]
+pub enum WalletError {
+    #[error("cannot sign with a watch-only wallet")]
+    WatchOnly,
+    #[error("wrong network: expected {expected}, got {actual}")]
+    WrongNetwork {
+        expected: ledger::Bitcoin,
+        actual: ledger::Bitcoin,
+    },
+    #[error("insufficient funds")]
+    InsufficientFunds,
+    #[error("wallet not found in bitcoind")]
+    NotFound,
+    #[error(transparent)]
+    AddressNotOwned(#[from] AddressNotOwned),
+    #[error(transparent)]
+    FeeBump(#[from] FeeBumpError),
+    #[error(transparent)]
+    Sweep(#[from] SweepError),
+    #[error(transparent)]
+    Confirmation(#[from] ConfirmationError),
+    #[error(transparent)]
+    Payment(#[from] PaymentError),
+    /// Any other failure, mostly of the calls to bitcoind.
+    #[error(transparent)]
+    Rpc(anyhow::Error),
+}
+
+impl From<anyhow::Error> for WalletError {
+    fn from(error: anyhow::Error) -> Self {
+        if is_rpc_error(&error, RPC_WALLET_INSUFFICIENT_FUNDS) {
+            return WalletError::InsufficientFunds;
+        }
+        if is_rpc_error(&error, RPC_WALLET_NOT_FOUND) {
+            return WalletError::NotFound;
+        }
+
+        // Our own errors may have been passed through an `anyhow::Error`
+        match error.downcast::<WalletError>() {
+            Ok(error) => error,
+            Err(error) => WalletError::Rpc(error),
+        }
+    }
+}
+
+/// A descriptor together with the range of indices to derive, in the form
+/// bitcoind accepts for `importdescriptors` and `scantxoutset`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RangedDescriptor {
+    #[serde(rename = "desc")]
+    pub descriptor: String,
+    pub range: [u64; 2],
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("address {0} is not owned by this wallet")]
+pub struct AddressNotOwned(pub Address);
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+pub enum FeeBumpError {
+    #[error("transaction {0} is already confirmed")]
+    AlreadyConfirmed(Txid),
+    #[error("transaction {0} does not signal replaceability")]
+    NotReplaceable(Txid),
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+pub enum SweepError {
+    #[error("wallet has {0} of unconfirmed funds")]
+    UnconfirmedFunds(Amount),
+    #[error("wallet has no funds to sweep")]
+    NothingToSweep,
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+pub enum ConfirmationError {
+    #[error("transaction {0} was dropped from the mempool")]
+    DroppedFromMempool(Txid),
+    #[error("timed out waiting for transaction {txid} to reach {target} confirmations")]
+    Timeout { txid: Txid, target: u32 },
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum PaymentError {
+    #[error("timed out waiting for {min_amount} at {address}, received {received}")]
+    Timeout {
+        address: Address,
+        min_amount: Amount,
+        received: Amount,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accounts_have_distinct_descriptors() {
+        let seed = Seed::random().unwrap();
+
+        let account_0 = Wallet::descriptors_from_seed(&seed, ledger::Bitcoin::Regtest, 0);
+        let account_1 = Wallet::descriptors_from_seed(&seed, ledger::Bitcoin::Regtest, 1);
+
+        assert_eq!(account_0.len(), 2);
+        assert_eq!(account_1.len(), 2);
+        assert!(account_0
+            .iter()
+            .all(|descriptor| !account_1.contains(descriptor)));
+        assert!(account_1[0].ends_with("/1h/0h/*h)"));
+    }
+
+    #[test]
+    fn change_below_threshold_is_discarded() {
+        let recipient = Address::p2wsh(&::bitcoin::Script::new(), ::bitcoin::Network::Regtest);
+        let change = Address::p2wsh(
+            &::bitcoin::Script::from(vec![0x51]),
+            ::bitcoin::Network::Regtest,
+        );
+        let psbt = |change_sats: u64| {
+            let transaction = Transaction {
+                version: 2,
+                lock_time: 0,
+                input: vec![::bitcoin::TxIn {
+                    previous_output: OutPoint::default(),
+                    script_sig: Default::default(),
+                    sequence: 0xFFFF_FFFF,
+                    witness: vec![],
+                }],
+                output: vec![
+                    ::bitcoin::TxOut {
+                        value: 100_000,
+                        script_pubkey: recipient.script_pubkey(),
+                    },
+                    ::bitcoin::TxOut {
+                        value: change_sats,
+                        script_pubkey: change.script_pubkey(),
+                    },
+                ],
+            };
+            let psbt = PartiallySignedTransaction::from_unsigned_tx(transaction).unwrap();
+            base64::encode(::bitcoin::consensus::serialize(&psbt))
+        };
+        let outputs = |psbt: &str| {
+            let bytes = base64::decode(psbt).unwrap();
+            let psbt: PartiallySignedTransaction =
+                ::bitcoin::consensus::deserialize(&bytes).unwrap();
+            assert_eq!(psbt.outputs.len(), psbt.global.unsigned_tx.output.len());
+            psbt.global
+                .unsigned_tx
+                .output
+                .into_iter()
+                .map(|output| output.value)
+                .collect::<Vec<_>>()
+        };
+        let discard_below = Amount::from_sat(1_000);
+
+        let dust = discard_small_change(&psbt(999), &change, discard_below).unwrap();
+        let kept = discard_small_change(&psbt(1_000), &change, discard_below).unwrap();
+
+        assert_eq!(outputs(&dust), vec![100_000]);
+        assert_eq!(outputs(&kept), vec![100_000, 1_000]);
+    }
+}
+
+#[cfg(all(test, feature = "testcontainers"))]
+mod docker_tests {
+    use super::*;
+    use crate::test_harness::bitcoin;
+    use std::{
+        fs::File,
+        io::{BufRead, BufReader},
+        path::Path,
+        process::{Command, Stdio},
+    };
+    use tempfile::TempDir;
+    use testcontainers::clients;
+
+    #[tokio::test]
+    async fn create_bitcoin_wallet_from_seed_and_get_address() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let _address = wallet.new_address().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn wallet_on_signet_hands_out_testnet_addresses() {
+        let tc_client = clients::Cli::default();
+        let signet = bitcoin::Signet::new(&tc_client).unwrap();
+
+        let wallet = Wallet::new(
+            Seed::random().unwrap(),
+            signet.node_url.clone(),
+            ledger::Bitcoin::Signet,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let address = wallet.new_address().await.unwrap();
+
+        assert_eq!(address.network, ::bitcoin::Network::Testnet);
+        assert!(address.to_string().starts_with("tb1"));
+    }
+
+    #[tokio::test]
+    async fn concurrent_new_address_calls_never_return_the_same_address() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let addresses = futures::future::try_join_all((0..50).map(|_| wallet.new_address()))
+            .await
+            .unwrap();
+
+        let unique = addresses.iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(unique.len(), addresses.len());
+
+        // the buffer hands out addresses in the order bitcoind derived them
+        let mut indices = Vec::new();
+        for _ in 0..5 {
+            let address = wallet.new_address().await.unwrap();
+            indices.push(wallet.address_index(&address).await.unwrap().unwrap().1);
+        }
+        assert!(indices.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[tokio::test]
+    async fn buffered_addresses_are_handed_out_after_a_restart() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let handed_out = wallet.new_address().await.unwrap();
+        while wallet.address_buffer.refilling.load(Ordering::SeqCst) {
+            tokio::time::delay_for(Duration::from_millis(10)).await;
+        }
+        let buffered = wallet.address_buffer.addresses.lock().await.clone();
+        assert!(!buffered.is_empty());
+        drop(wallet);
+
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let restored = wallet.address_buffer.addresses.lock().await.clone();
+        assert!(!restored.contains(&handed_out));
+
+        let next = wallet.new_address().await.unwrap();
+        assert!(buffered.contains(&next));
+    }
+
+    #[tokio::test]
+    async fn root_key_calculated_from_seed_is_the_same_than_bitcoind_s() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let wif_path_docker = Path::new("/wallet.wif");
+
+        let _ = wallet.dump(wif_path_docker).await.unwrap();
+
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("wallet.wif");
+
+        Command::new("docker")
+            .arg("cp")
+            .arg(format!(
+                "{}:{}",
+                blockchain.container_id(),
+                wif_path_docker.display()
+            ))
+            .arg(path.clone())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Failed to execute docker cp")
+            .wait()
+            .expect("Failed to run docker cp");
+
+        let wif = File::open(path).unwrap();
+        let wif = BufReader::new(wif);
+
+        // The line we are looking for looks like that:
+        // # extended private masterkey: tprv...
+
+        let line = wif
+            .lines()
+            .find(|line| {
+                line.as_ref()
+                    .map(|line| line.starts_with("# extended private masterkey: "))
+                    .unwrap_or(false)
+            })
+            .unwrap()
+            .unwrap();
+
+        let key = line.split_ascii_whitespace().last().unwrap();
+        assert_eq!(key, &wallet.root_key().unwrap().to_string());
+    }
+
+    #[tokio::test]
+    async fn create_bitcoin_wallet_from_seed_and_get_balance() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let _balance = wallet.balance().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn estimate_fee_rate_falls_back_to_the_floor_on_a_fresh_regtest_chain() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let floor = Amount::from_sat(7);
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap()
+        .with_fee_rate_floor(floor);
+
+        blockchain
+            .mint(
+                wallet.new_address().await.unwrap(),
+                Amount::from_btc(1.0).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // a handful of blocks with a single transaction is not enough data for
+        // bitcoind to estimate a fee rate
+        let fee_rate = wallet.estimate_fee_rate(6).await.unwrap();
+
+        assert_eq!(fee_rate, floor);
+    }
+
+    #[tokio::test]
+    async fn wait_for_confirmations_of_sent_transaction() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        blockchain
+            .mint(
+                wallet.new_address().await.unwrap(),
+                Amount::from_btc(1.0).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // Wait for the minted funds to be confirmed so that the wallet can spend them
+        tokio::time::delay_for(Duration::from_secs(3)).await;
+
+        let txid = wallet
+            .send_to_address(
+                wallet.new_address().await.unwrap(),
+                Amount::from_sat(100_000),
+                ledger::Bitcoin::Regtest,
+            )
+            .await
+            .unwrap();
+
+        let confirmations = wallet
+            .wait_for_confirmations(txid, 2, Duration::from_millis(200), Duration::from_secs(30))
+            .await
+            .unwrap();
+
+        assert!(confirmations >= 2);
+        assert!(wallet.transaction_confirmations(txid).await.unwrap() >= 2);
+    }
+
+    #[tokio::test]
+    async fn unknown_transaction_has_zero_confirmations() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let txid = Txid::default();
+
+        let confirmations = wallet.transaction_confirmations(txid).await.unwrap();
+        assert_eq!(confirmations, 0);
+
+        let error = wallet
+            .wait_for_confirmations(txid, 1, Duration::from_millis(100), Duration::from_secs(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            WalletError::Confirmation(ConfirmationError::Timeout { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn send_to_address_with_wrong_passphrase_fails() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            Some("correct horse battery staple".to_owned()),
+        )
+        .await
+        .unwrap();
+
+        blockchain
+            .mint(
+                wallet.new_address().await.unwrap(),
+                Amount::from_btc(1.0).unwrap(),
+            )
+            .await
+            .unwrap();
+        // Generating an address may need to unlock the wallet as well
+        let to_address = wallet.new_address().await.unwrap();
+
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            Some("wrong passphrase".to_owned()),
+        )
+        .await
+        .unwrap();
+
+        // Reading from the wallet does not require unlocking it
+        let _info = wallet.info().await.unwrap();
+        let _balance = wallet.balance().await.unwrap();
+
+        let error = wallet
+            .send_to_address(
+                to_address,
+                Amount::from_sat(100_000),
+                ledger::Bitcoin::Regtest,
+            )
+            .await
+            .unwrap_err();
+
+        let message = format!("{:#}", error);
+        assert!(message.contains("is the passphrase correct?"));
+        assert!(!message.contains("wrong passphrase"));
+    }
+
+    #[tokio::test]
+    async fn concurrent_sends_from_an_encrypted_wallet_succeed() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            Some("correct horse battery staple".to_owned()),
+        )
+        .await
+        .unwrap();
+
+        for _ in 0..2 {
+            blockchain
+                .mint(
+                    wallet.new_address().await.unwrap(),
+                    Amount::from_btc(1.0).unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+
+        // Wait for the minted funds to be confirmed so that the wallet can spend them
+        tokio::time::delay_for(Duration::from_secs(3)).await;
+
+        let first_address = wallet.new_address().await.unwrap();
+        let second_address = wallet.new_address().await.unwrap();
+
+        // Neither send may find the wallet locked by the other one finishing
+        let (first, second) = futures::future::join(
+            wallet.send_to_address(
+                first_address,
+                Amount::from_sat(100_000),
+                ledger::Bitcoin::Regtest,
+            ),
+            wallet.send_to_address(
+                second_address,
+                Amount::from_sat(100_000),
+                ledger::Bitcoin::Regtest,
+            ),
+        )
+        .await;
+
+        assert_ne!(first.unwrap(), second.unwrap());
+    }
+
+    #[tokio::test]
+    async fn watch_only_wallet_derives_addresses_but_cannot_send() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let descriptors =
+            Wallet::descriptors_from_seed(&seed, ledger::Bitcoin::Regtest, DEFAULT_ACCOUNT);
+        let wallet = Wallet::new_watch_only(
+            descriptors,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+        )
+        .await
+        .unwrap();
+
+        let first = wallet.new_address().await.unwrap();
+        let second = wallet.new_address().await.unwrap();
+        assert_ne!(first, second);
+
+        let _info = wallet.info().await.unwrap();
+        let _balance = wallet.balance().await.unwrap();
+
+        let error = wallet
+            .send_to_address(second, Amount::from_sat(100_000), ledger::Bitcoin::Regtest)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WalletError::WatchOnly));
+    }
+
+    #[tokio::test]
+    async fn watch_only_wallet_does_not_reuse_paid_addresses_after_restart() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let descriptors =
+            Wallet::descriptors_from_seed(&seed, ledger::Bitcoin::Regtest, DEFAULT_ACCOUNT);
+        let wallet = Wallet::new_watch_only(
+            descriptors.clone(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+        )
+        .await
+        .unwrap();
+
+        let first = wallet.new_address().await.unwrap();
+        let paid = wallet.new_address().await.unwrap();
+        blockchain
+            .mint(paid.clone(), Amount::from_btc(0.1).unwrap())
+            .await
+            .unwrap();
+        drop(wallet);
+
+        let restarted = Wallet::new_watch_only(
+            descriptors,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+        )
+        .await
+        .unwrap();
+        let next = restarted.new_address().await.unwrap();
+
+        assert_ne!(next, first);
+        assert_ne!(next, paid);
+    }
+
+    #[tokio::test]
+    async fn address_index_finds_the_derivation_index_of_an_address() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap()
+        .with_gap_limit(10);
+        let bitcoind_client = Client::new(blockchain.node_url.clone());
+        let descriptors = wallet.descriptors_with_checksums().await.unwrap();
+
+        let external = bitcoind_client
+            .derive_addresses(&descriptors[0], Some([5, 5]))
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+        let internal = bitcoind_client
+            .derive_addresses(&descriptors[1], Some([2, 2]))
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+        let beyond_gap_limit = bitcoind_client
+            .derive_addresses(&descriptors[0], Some([10, 10]))
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+
+        assert_eq!(
+            wallet.address_index(&external).await.unwrap(),
+            Some((Chain::External, 5))
+        );
+        assert_eq!(
+            wallet.address_index(&internal).await.unwrap(),
+            Some((Chain::Internal, 2))
+        );
+        assert_eq!(wallet.address_index(&beyond_gap_limit).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn sending_from_a_depleted_wallet_fails_with_insufficient_funds() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+        blockchain.init().await.unwrap();
+
+        let wallet = Wallet::new(
+            Seed::random().unwrap(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let error = wallet
+            .send_to_address(
+                wallet.new_address().await.unwrap(),
+                Amount::from_sat(100_000),
+                ledger::Bitcoin::Regtest,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, WalletError::InsufficientFunds));
+    }
+
+    #[tokio::test]
+    async fn sending_on_the_wrong_network_fails_with_wrong_network() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+        blockchain.init().await.unwrap();
+
+        let wallet = Wallet::new(
+            Seed::random().unwrap(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let error = wallet
+            .send_to_address(
+                wallet.new_address().await.unwrap(),
+                Amount::from_sat(100_000),
+                ledger::Bitcoin::Mainnet,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, WalletError::WrongNetwork {
+            expected: ledger::Bitcoin::Mainnet,
+            actual: ledger::Bitcoin::Regtest,
+        }));
+    }
+
+    #[tokio::test]
+    async fn psbt_signed_elsewhere_can_be_broadcast() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+        blockchain.init().await.unwrap();
+
+        let wallet = Wallet::new(
+            Seed::random().unwrap(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+        blockchain
+            .mint(
+                wallet.new_address().await.unwrap(),
+                Amount::from_btc(1.0).unwrap(),
+            )
+            .await
+            .unwrap();
+        // Wait for the minted funds to be confirmed so that the wallet can spend them
+        tokio::time::delay_for(Duration::from_secs(3)).await;
+        let recipient = Client::new(blockchain.node_url.clone())
+            .get_new_address(&blockchain.wallet_name, None, None)
+            .await
+            .unwrap();
+
+        let psbt = wallet
+            .create_psbt(vec![(recipient.clone(), Amount::from_btc(0.1).unwrap())])
+            .await
+            .unwrap();
+        assert!(wallet.finalize_and_extract(&psbt).await.is_err());
+
+        // stands in for the air-gapped signer
+        let signed = wallet
+            .bitcoind_client
+            .wallet_process_psbt(&wallet.name, &psbt)
+            .await
+            .unwrap();
+        let transaction = wallet.finalize_and_extract(&signed).await.unwrap();
+        let txid = wallet
+            .send_raw_transaction(transaction.clone(), ledger::Bitcoin::Regtest)
+            .await
+            .unwrap();
 
-        format!(
-            "nectar_{:x}{:x}{:x}{:x}",
-            hash[0], hash[1], hash[2], hash[3]
+        assert_eq!(txid, transaction.txid());
+        assert_eq!(transaction.output.len(), 2, "expected a change output");
+        assert!(transaction
+            .output
+            .iter()
+            .any(|output| output.script_pubkey == recipient.script_pubkey()
+                && output.value == Amount::from_btc(0.1).unwrap().as_sat()));
+    }
+
+    #[tokio::test]
+    async fn ranged_descriptor_derives_the_addresses_of_the_wallet() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+        blockchain.init().await.unwrap();
+
+        let wallet = Wallet::new(
+            Seed::random().unwrap(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
         )
+        .await
+        .unwrap();
+        let mut own_addresses = Vec::new();
+        for _ in 0..=15 {
+            own_addresses.push(wallet.new_address().await.unwrap());
+        }
+
+        let external = wallet.descriptors_ranged(10, 15).await.unwrap().remove(0);
+        let derived = Client::new(blockchain.node_url.clone())
+            .derive_addresses(&external.descriptor, Some(external.range))
+            .await
+            .unwrap();
+
+        assert_eq!(external.range, [10, 15]);
+        assert_eq!(derived, own_addresses[10..=15].to_vec());
+        assert!(wallet.descriptors_ranged(15, 10).await.is_err());
     }
-}
 
-#[cfg(all(test, feature = "testcontainers"))]
-mod docker_tests {
-    use super::*;
-    use crate::test_harness::bitcoin;
-    use std::{
-        fs::File,
-        io::{BufRead, BufReader},
-        path::Path,
-        process::{Command, Stdio},
-    };
-    use tempfile::TempDir;
-    use testcontainers::clients;
+    #[tokio::test]
+    async fn low_fee_transaction_is_not_accepted_into_mempool() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+        blockchain.init().await.unwrap();
+
+        let wallet = Wallet::new(
+            Seed::random().unwrap(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+        blockchain
+            .mint(
+                wallet.new_address().await.unwrap(),
+                Amount::from_btc(1.0).unwrap(),
+            )
+            .await
+            .unwrap();
+        // Wait for the minted funds to be confirmed so that the wallet can spend them
+        tokio::time::delay_for(Duration::from_secs(3)).await;
+        let recipient = Client::new(blockchain.node_url.clone())
+            .get_new_address(&blockchain.wallet_name, None, None)
+            .await
+            .unwrap();
+
+        let psbt = wallet
+            .create_psbt(vec![(recipient.clone(), Amount::from_btc(0.1).unwrap())])
+            .await
+            .unwrap();
+        let signed = wallet
+            .bitcoind_client
+            .wallet_process_psbt(&wallet.name, &psbt)
+            .await
+            .unwrap();
+        let valid = wallet.finalize_and_extract(&signed).await.unwrap();
+
+        let result = wallet.test_mempool_accept(&valid).await.unwrap();
+        assert!(result.allowed);
+        assert_eq!(result.reject_reason, None);
+
+        // bitcoind refuses to create transactions below the minimum fee, hence
+        // we spend the whole output ourselves, paying no fee at all
+        let utxo = wallet.list_unspent(1).await.unwrap().remove(0);
+        let zero_fee = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![::bitcoin::TxIn {
+                previous_output: utxo.outpoint,
+                script_sig: Default::default(),
+                sequence: 0xFFFF_FFFF,
+                witness: vec![],
+            }],
+            output: vec![::bitcoin::TxOut {
+                value: utxo.amount.as_sat(),
+                script_pubkey: recipient.script_pubkey(),
+            }],
+        };
+        let psbt =
+            ::bitcoin::util::psbt::PartiallySignedTransaction::from_unsigned_tx(zero_fee).unwrap();
+        let psbt = base64::encode(::bitcoin::consensus::serialize(&psbt));
+        let signed = wallet
+            .bitcoind_client
+            .wallet_process_psbt(&wallet.name, &psbt)
+            .await
+            .unwrap();
+        let low_fee = wallet.finalize_and_extract(&signed).await.unwrap();
+
+        let result = wallet.test_mempool_accept(&low_fee).await.unwrap();
+        assert!(!result.allowed);
+        assert!(result.reject_reason.is_some());
+    }
+
+    async fn wallet_with_legacy_address(blockchain: &bitcoin::Blockchain<'_>) -> (Wallet, Address) {
+        let wallet = Wallet::new(
+            Seed::random().unwrap(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+        // bitcoind only signs messages with the keys of legacy addresses
+        let address = wallet
+            .bitcoind_client
+            .get_new_address(&wallet.name, None, Some("legacy".into()))
+            .await
+            .unwrap();
+
+        (wallet, address)
+    }
 
     #[tokio::test]
-    async fn create_bitcoin_wallet_from_seed_and_get_address() {
+    async fn signed_message_can_be_verified() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+        blockchain.init().await.unwrap();
+        let (wallet, address) = wallet_with_legacy_address(&blockchain).await;
+        let nonce = "proof of reserves 2c9f1e";
+
+        let signature = wallet.sign_message(&address, nonce).await.unwrap();
+
+        assert!(wallet
+            .verify_message(&address, nonce, &signature)
+            .await
+            .unwrap());
+        assert!(!wallet
+            .verify_message(&address, "proof of reserves 2c9f1f", &signature)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn cannot_sign_message_for_foreign_address() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+        blockchain.init().await.unwrap();
+        let (wallet, _) = wallet_with_legacy_address(&blockchain).await;
+        let (_, foreign) = wallet_with_legacy_address(&blockchain).await;
+
+        let error = wallet.sign_message(&foreign, "nonce").await.unwrap_err();
+
+        assert!(matches!(error, WalletError::AddressNotOwned(_)));
+    }
+
+    #[tokio::test]
+    async fn send_to_many_pays_all_outputs_in_one_transaction() {
         let tc_client = clients::Cli::default();
         let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
 
         blockchain.init().await.unwrap();
 
         let seed = Seed::random().unwrap();
-        let wallet = Wallet::new(seed, blockchain.node_url.clone(), ledger::Bitcoin::Regtest)
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        blockchain
+            .mint(
+                wallet.new_address().await.unwrap(),
+                Amount::from_btc(1.0).unwrap(),
+            )
             .await
             .unwrap();
 
-        let _address = wallet.new_address().await.unwrap();
+        // Wait for the minted funds to be confirmed so that the wallet can spend them
+        tokio::time::delay_for(Duration::from_secs(3)).await;
+
+        let mut outputs = Vec::new();
+        for sats in &[100_000, 200_000, 300_000] {
+            outputs.push((wallet.new_address().await.unwrap(), Amount::from_sat(*sats)));
+        }
+
+        let txid = wallet
+            .send_to_many(outputs.clone(), ledger::Bitcoin::Regtest)
+            .await
+            .unwrap();
+
+        let transaction = wallet
+            .bitcoind_client
+            .get_raw_transaction(txid)
+            .await
+            .unwrap();
+
+        for (address, amount) in outputs {
+            assert!(transaction.output.iter().any(|output| {
+                output.script_pubkey == address.script_pubkey() && output.value == amount.as_sat()
+            }));
+        }
     }
 
     #[tokio::test]
-    async fn root_key_calculated_from_seed_is_the_same_than_bitcoind_s() {
+    async fn sweep_spends_all_utxos() {
         let tc_client = clients::Cli::default();
         let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
 
         blockchain.init().await.unwrap();
 
-        let seed = Seed::random().unwrap();
-        let wallet = Wallet::new(seed, blockchain.node_url.clone(), ledger::Bitcoin::Regtest)
+        let wallet = Wallet::new(
+            Seed::random().unwrap(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+        let cold_wallet = Wallet::new(
+            Seed::random().unwrap(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        for btc in &[0.1, 0.2, 0.3] {
+            blockchain
+                .mint(
+                    wallet.new_address().await.unwrap(),
+                    Amount::from_btc(*btc).unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+
+        // Wait for the minted funds to be confirmed so that the wallet can spend them
+        tokio::time::delay_for(Duration::from_secs(3)).await;
+
+        wallet
+            .sweep_to(cold_wallet.new_address().await.unwrap(), None, false)
             .await
             .unwrap();
 
-        let wif_path_docker = Path::new("/wallet.wif");
+        assert_eq!(wallet.balance().await.unwrap(), Amount::ZERO);
+    }
 
-        let _ = wallet.dump(wif_path_docker).await.unwrap();
+    #[tokio::test]
+    async fn list_unspent_lists_every_funded_output() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
 
-        let tmp_dir = TempDir::new().unwrap();
-        let path = tmp_dir.path().join("wallet.wif");
+        blockchain.init().await.unwrap();
 
-        Command::new("docker")
-            .arg("cp")
-            .arg(format!(
-                "{}:{}",
-                blockchain.container_id(),
-                wif_path_docker.display()
-            ))
-            .arg(path.clone())
-            .stdout(Stdio::piped())
-            .spawn()
-            .expect("Failed to execute docker cp")
-            .wait()
-            .expect("Failed to run docker cp");
+        let wallet = Wallet::new(
+            Seed::random().unwrap(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
 
-        let wif = File::open(path).unwrap();
-        let wif = BufReader::new(wif);
+        let first = wallet.new_address().await.unwrap();
+        let second = wallet.new_address().await.unwrap();
+        blockchain
+            .mint(first.clone(), Amount::from_btc(0.1).unwrap())
+            .await
+            .unwrap();
+        blockchain
+            .mint(second.clone(), Amount::from_btc(0.2).unwrap())
+            .await
+            .unwrap();
 
-        // The line we are looking for looks like that:
-        // # extended private masterkey: tprv...
+        tokio::time::delay_for(Duration::from_secs(3)).await;
 
-        let line = wif
-            .lines()
-            .find(|line| {
-                line.as_ref()
-                    .map(|line| line.starts_with("# extended private masterkey: "))
-                    .unwrap_or(false)
-            })
+        let mut utxos = wallet.list_unspent(1).await.unwrap();
+        utxos.sort_by_key(|utxo| utxo.amount.as_sat());
+
+        assert_eq!(utxos.len(), 2);
+        assert_eq!(utxos[0].address, first);
+        assert_eq!(utxos[0].amount, Amount::from_btc(0.1).unwrap());
+        assert_eq!(utxos[1].address, second);
+        assert_eq!(utxos[1].amount, Amount::from_btc(0.2).unwrap());
+        assert!(utxos.iter().all(|utxo| utxo.confirmations >= 1));
+    }
+
+    #[tokio::test]
+    async fn wait_for_payment_sums_outputs_to_the_address() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let wallet = Wallet::new(
+            Seed::random().unwrap(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let address = wallet.new_address().await.unwrap();
+        let other = wallet.new_address().await.unwrap();
+        blockchain
+            .mint(address.clone(), Amount::from_btc(0.1).unwrap())
+            .await
+            .unwrap();
+        blockchain
+            .mint(other, Amount::from_btc(0.5).unwrap())
+            .await
+            .unwrap();
+
+        let error = wallet
+            .wait_for_payment(
+                &address,
+                Amount::from_btc(0.3).unwrap(),
+                Duration::from_secs(2),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            WalletError::Payment(PaymentError::Timeout { .. })
+        ));
+
+        blockchain
+            .mint(address.clone(), Amount::from_btc(0.2).unwrap())
+            .await
+            .unwrap();
+
+        let txid = wallet
+            .wait_for_payment(
+                &address,
+                Amount::from_btc(0.3).unwrap(),
+                Duration::from_secs(30),
+            )
+            .await
+            .unwrap();
+
+        let utxos = wallet.list_unspent(0).await.unwrap();
+        assert!(utxos
+            .iter()
+            .any(|utxo| utxo.outpoint.txid == txid && utxo.address == address));
+    }
+
+    #[tokio::test]
+    async fn consolidate_collapses_outputs_into_one() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let wallet = Wallet::new(
+            Seed::random().unwrap(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        for btc in &[0.1, 0.2, 0.3, 0.4] {
+            blockchain
+                .mint(
+                    wallet.new_address().await.unwrap(),
+                    Amount::from_btc(*btc).unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+
+        tokio::time::delay_for(Duration::from_secs(3)).await;
+
+        let txid = wallet
+            .consolidate(10, Amount::from_sat(2))
+            .await
             .unwrap()
+            .expect("four outputs to consolidate");
+
+        let utxos = wallet.list_unspent(0).await.unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].outpoint.txid, txid);
+        assert!(utxos[0].amount < Amount::from_btc(1.0).unwrap());
+    }
+
+    #[tokio::test]
+    async fn consolidate_single_output_does_nothing() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let wallet = Wallet::new(
+            Seed::random().unwrap(),
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        blockchain
+            .mint(
+                wallet.new_address().await.unwrap(),
+                Amount::from_btc(0.1).unwrap(),
+            )
+            .await
             .unwrap();
 
-        let key = line.split_ascii_whitespace().last().unwrap();
-        assert_eq!(key, &wallet.root_key.to_string());
+        tokio::time::delay_for(Duration::from_secs(3)).await;
+
+        let txid = wallet.consolidate(10, Amount::from_sat(2)).await.unwrap();
+
+        assert_eq!(txid, None);
     }
 
     #[tokio::test]
-    async fn create_bitcoin_wallet_from_seed_and_get_balance() {
+    async fn dust_change_is_dropped_into_fees() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap()
+        .with_change_policy(ChangePolicy {
+            discard_below: Amount::from_sat(50_000),
+            address_type: AddressType::Bech32,
+        });
+
+        blockchain
+            .mint(
+                wallet.new_address().await.unwrap(),
+                Amount::from_btc(1.0).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // Wait for the minted funds to be confirmed so that the wallet can spend them
+        tokio::time::delay_for(Duration::from_secs(3)).await;
+
+        // at 1 sat/vB this leaves less than 10_000 sats of change
+        let recipient = wallet.new_address().await.unwrap();
+        let amount = Amount::from_sat(99_990_000);
+        let txid = wallet
+            .send_to_address_with_change_policy(
+                recipient.clone(),
+                amount,
+                ledger::Bitcoin::Regtest,
+                Amount::from_sat(1),
+            )
+            .await
+            .unwrap();
+
+        let transaction = wallet
+            .bitcoind_client
+            .get_raw_transaction(txid)
+            .await
+            .unwrap();
+
+        assert_eq!(transaction.output.len(), 1);
+        assert_eq!(
+            transaction.output[0].script_pubkey,
+            recipient.script_pubkey()
+        );
+        assert_eq!(transaction.output[0].value, amount.as_sat());
+    }
+
+    #[tokio::test]
+    async fn send_to_many_without_outputs_fails() {
+        let tc_client = clients::Cli::default();
+        let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
+
+        blockchain.init().await.unwrap();
+
+        let seed = Seed::random().unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let result = wallet
+            .send_to_many(Vec::new(), ledger::Bitcoin::Regtest)
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn bump_fee_of_unconfirmed_transaction() {
         let tc_client = clients::Cli::default();
         let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
 
         blockchain.init().await.unwrap();
 
         let seed = Seed::random().unwrap();
-        let wallet = Wallet::new(seed, blockchain.node_url.clone(), ledger::Bitcoin::Regtest)
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
+
+        blockchain
+            .mint(
+                wallet.new_address().await.unwrap(),
+                Amount::from_btc(1.0).unwrap(),
+            )
             .await
             .unwrap();
 
-        let _balance = wallet.balance().await.unwrap();
+        // Wait for the minted funds to be confirmed so that the wallet can spend them
+        tokio::time::delay_for(Duration::from_secs(3)).await;
+
+        let txid = wallet
+            .send_to_address_with_fee_rate(
+                wallet.new_address().await.unwrap(),
+                Amount::from_sat(100_000),
+                ledger::Bitcoin::Regtest,
+                Amount::from_sat(1),
+            )
+            .await
+            .unwrap();
+
+        let replacement = wallet.bump_fee(txid, Amount::from_sat(10)).await.unwrap();
+        assert_ne!(replacement, txid);
+
+        wallet
+            .wait_for_confirmations(
+                replacement,
+                1,
+                Duration::from_millis(200),
+                Duration::from_secs(30),
+            )
+            .await
+            .unwrap();
+
+        let error = wallet
+            .bump_fee(replacement, Amount::from_sat(20))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            WalletError::FeeBump(FeeBumpError::AlreadyConfirmed(_))
+        ));
     }
 
     #[tokio::test]
@@ -418,17 +2964,27 @@ mod docker_tests {
 
         let seed = Seed::random().unwrap();
         {
-            let wallet = Wallet::new(seed, blockchain.node_url.clone(), ledger::Bitcoin::Regtest)
-                .await
-                .unwrap();
+            let wallet = Wallet::new(
+                seed,
+                blockchain.node_url.clone(),
+                ledger::Bitcoin::Regtest,
+                None,
+            )
+            .await
+            .unwrap();
 
             let _address = wallet.new_address().await.unwrap();
         }
 
         {
-            let wallet = Wallet::new(seed, blockchain.node_url.clone(), ledger::Bitcoin::Regtest)
-                .await
-                .unwrap();
+            let wallet = Wallet::new(
+                seed,
+                blockchain.node_url.clone(),
+                ledger::Bitcoin::Regtest,
+                None,
+            )
+            .await
+            .unwrap();
 
             let _address = wallet.new_address().await.unwrap();
         }
@@ -446,9 +3002,14 @@ mod docker_tests {
         let wallet_name = {
             let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
             blockchain.init().await.unwrap();
-            let wallet = Wallet::new(seed, blockchain.node_url.clone(), ledger::Bitcoin::Regtest)
-                .await
-                .unwrap();
+            let wallet = Wallet::new(
+                seed,
+                blockchain.node_url.clone(),
+                ledger::Bitcoin::Regtest,
+                None,
+            )
+            .await
+            .unwrap();
             wallet.name
         };
 
@@ -457,8 +3018,13 @@ mod docker_tests {
         // to reproduce this behaviour)
         let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
         {
-            let res =
-                Wallet::new(seed, blockchain.node_url.clone(), ledger::Bitcoin::Regtest).await;
+            let res = Wallet::new(
+                seed,
+                blockchain.node_url.clone(),
+                ledger::Bitcoin::Regtest,
+                None,
+            )
+            .await;
             // If this did not fail then the test is moot
             assert!(res.is_err());
 
@@ -471,9 +3037,14 @@ mod docker_tests {
         }
         // Generate 100+ blocks, now it should work
         blockchain.init().await.unwrap();
-        let wallet = Wallet::new(seed, blockchain.node_url.clone(), ledger::Bitcoin::Regtest)
-            .await
-            .unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
         let _address = wallet.new_address().await.unwrap();
         // If we did not panic, we succeeded.
     }
@@ -487,9 +3058,14 @@ mod docker_tests {
             let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
             blockchain.init().await.unwrap();
 
-            let wallet = Wallet::new(seed, blockchain.node_url.clone(), ledger::Bitcoin::Regtest)
-                .await
-                .unwrap();
+            let wallet = Wallet::new(
+                seed,
+                blockchain.node_url.clone(),
+                ledger::Bitcoin::Regtest,
+                None,
+            )
+            .await
+            .unwrap();
 
             let mut addresses = Vec::new();
 
@@ -507,9 +3083,14 @@ mod docker_tests {
         let blockchain = bitcoin::Blockchain::new(&tc_client).unwrap();
         blockchain.init().await.unwrap();
         let bitcoind_client = Client::new(blockchain.node_url.clone());
-        let wallet = Wallet::new(seed, blockchain.node_url.clone(), ledger::Bitcoin::Regtest)
-            .await
-            .unwrap();
+        let wallet = Wallet::new(
+            seed,
+            blockchain.node_url.clone(),
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
 
         let descriptors = wallet.descriptors_with_checksums().await.unwrap();
 
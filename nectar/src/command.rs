@@ -2,9 +2,11 @@ use std::path::PathBuf;
 use structopt::StructOpt;
 
 mod balance;
+mod btc;
 mod create_transaction;
 mod deposit;
 mod migrate_db;
+mod restore_seed;
 mod resume_only;
 mod trade;
 mod wallet_info;
@@ -23,17 +25,20 @@ use num::BigUint;
 use std::str::FromStr;
 
 pub use balance::balance;
+pub use btc::btc;
 use comit::Secret;
 pub use create_transaction::create_transaction;
 pub use deposit::deposit;
 pub use migrate_db::migrate_db;
+pub use restore_seed::restore_seed;
 pub use resume_only::resume_only;
 use time::OffsetDateTime;
 pub use trade::trade;
 pub use wallet_info::wallet_info;
 pub use withdraw::withdraw;
 
-#[derive(StructOpt, Debug)]
+#[derive(StructOpt, derivative::Derivative)]
+#[derivative(Debug)]
 pub struct Options {
     /// Path to configuration file
     #[structopt(short = "c", long = "config", parse(from_os_str))]
@@ -43,6 +48,16 @@ pub struct Options {
     #[structopt(short = "n", long = "network")]
     pub network: Option<comit::Network>,
 
+    /// Passphrase of the Bitcoin wallet, takes precedence over the one in the
+    /// configuration file
+    #[structopt(
+        long = "bitcoin-wallet-passphrase",
+        env = "NECTAR_BITCOIN_WALLET_PASSPHRASE",
+        hide_env_values = true
+    )]
+    #[derivative(Debug = "ignore")]
+    pub bitcoin_wallet_passphrase: Option<String>,
+
     /// Commands available
     #[structopt(subcommand)]
     pub cmd: Command,
@@ -76,6 +91,31 @@ pub enum Command {
     ArchiveSwap { id: SwapId },
     /// Migrate the database to the current format.
     MigrateDb(MigrateDb),
+    /// Export all swaps of the database to a file, e.g. to move them to
+    /// another host.
+    ExportDb {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+    /// Import the swaps of a file written by `export-db` into the database.
+    ImportDb {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+    /// Replace a pending Ethereum transaction of the wallet with one paying a
+    /// higher gas price.
+    BumpGasPrice {
+        /// The hash of the pending transaction.
+        tx_hash: ethereum::Hash,
+        /// The new gas price in gwei, at least 10% above the original one.
+        #[structopt(parse(try_from_str = parse_gwei))]
+        gas_price: ether::Amount,
+    },
+    /// Inspect and manage the Bitcoin wallet.
+    Btc(Btc),
+    /// Restore the seed from its mnemonic, as printed by `wallet-info`. The
+    /// mnemonic is read from the standard input.
+    RestoreSeed,
 }
 
 pub fn dump_config(settings: Settings) -> anyhow::Result<()> {
@@ -94,6 +134,22 @@ pub enum Withdraw {
         amount: bitcoin::Amount,
         to_address: bitcoin::Address,
     },
+    /// Pay several addresses in a single transaction
+    BtcMany {
+        /// The outputs to pay, as `<address>=<amount>`
+        #[structopt(required = true, parse(try_from_str = parse_bitcoin_output))]
+        outputs: Vec<(bitcoin::Address, bitcoin::Amount)>,
+    },
+    /// Move the whole balance to an address, the fee is deducted from it
+    BtcAll {
+        to_address: bitcoin::Address,
+        /// The fee rate to pay, estimated by bitcoind if not set
+        #[structopt(long)]
+        sat_per_vbyte: Option<u64>,
+        /// Also sweep the unconfirmed change of the wallet
+        #[structopt(long)]
+        include_unconfirmed: bool,
+    },
     Dai {
         #[structopt(parse(try_from_str = parse_dai))]
         amount: dai::Amount,
@@ -158,6 +214,87 @@ impl CreateTransaction {
     }
 }
 
+#[derive(StructOpt, Debug, Clone)]
+pub enum Btc {
+    /// Print the number of confirmations of a transaction of the wallet.
+    Confirmations {
+        txid: bitcoin::Txid,
+        /// Wait until the transaction has at least this many confirmations.
+        #[structopt(long)]
+        wait_for: Option<u32>,
+        /// How long to wait for the confirmations, in seconds.
+        #[structopt(long, default_value = "3600")]
+        timeout_secs: u64,
+    },
+    /// Replace an unconfirmed transaction of the wallet by one paying a
+    /// higher fee rate.
+    BumpFee {
+        txid: bitcoin::Txid,
+        /// The fee rate of the replacement transaction.
+        sat_per_vbyte: u64,
+    },
+    /// Print the chain and derivation index of an address of the wallet.
+    AddressIndex {
+        address: bitcoin::Address,
+        /// Number of addresses per chain to scan.
+        #[structopt(long, default_value = "100")]
+        gap_limit: u32,
+    },
+    /// Sign a message with the key of an address of the wallet, e.g. to
+    /// prove control over its funds.
+    SignMessage {
+        address: bitcoin::Address,
+        message: String,
+    },
+    /// Verify that a message was signed with the key of an address.
+    VerifyMessage {
+        address: bitcoin::Address,
+        message: String,
+        signature: String,
+    },
+    /// Create an unsigned PSBT funded by the wallet, to be signed elsewhere.
+    CreatePsbt {
+        /// The outputs to pay, as `<address>=<amount>`
+        #[structopt(required = true, parse(try_from_str = parse_bitcoin_output))]
+        outputs: Vec<(bitcoin::Address, bitcoin::Amount)>,
+    },
+    /// Finalize a fully signed PSBT and print the transaction.
+    FinalizePsbt {
+        psbt: String,
+        /// Broadcast the transaction instead of printing it.
+        #[structopt(long)]
+        broadcast: bool,
+    },
+    /// List the unspent outputs of the wallet.
+    ListUnspent {
+        /// Only list outputs with at least this many confirmations.
+        #[structopt(long, default_value = "1")]
+        min_confirmations: u32,
+    },
+    /// Spend the smallest confirmed outputs of the wallet to a single output.
+    Consolidate {
+        /// Maximum number of outputs to spend.
+        #[structopt(long, default_value = "20")]
+        max_inputs: usize,
+        /// The fee rate of the consolidating transaction.
+        #[structopt(long)]
+        sat_per_vbyte: u64,
+    },
+    /// Wait until an address of the wallet received at least the given
+    /// amount.
+    WaitForPayment {
+        address: bitcoin::Address,
+        #[structopt(parse(try_from_str = parse_bitcoin))]
+        amount: bitcoin::Amount,
+        /// How long to wait for the payment, in seconds.
+        #[structopt(long, default_value = "3600")]
+        timeout_secs: u64,
+    },
+    /// Print the descriptors of the wallet bound to the derivation indices
+    /// `start` to `end`, as JSON that `importdescriptors` accepts.
+    Descriptors { start: u32, end: u32 },
+}
+
 #[derive(StructOpt, Debug, Clone)]
 pub enum MigrateDb {
     /// Print whether the database needs a migration.
@@ -175,6 +312,16 @@ fn parse_bitcoin(str: &str) -> anyhow::Result<bitcoin::Amount> {
     Ok(amount)
 }
 
+fn parse_bitcoin_output(str: &str) -> anyhow::Result<(bitcoin::Address, bitcoin::Amount)> {
+    let mut parts = str.splitn(2, '=');
+    let address = parts.next().unwrap_or_default();
+    let amount = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("expected <address>=<amount> but got {}", str))?;
+
+    Ok((bitcoin::Address::from_str(address)?, parse_bitcoin(amount)?))
+}
+
 fn parse_dai(str: &str) -> anyhow::Result<dai::Amount> {
     // TODO: In addition to providing an interface to withdraw attodai, we could use
     // string instead of float here
@@ -186,6 +333,15 @@ fn parse_ether(str: &str) -> anyhow::Result<ether::Amount> {
     ether::Amount::from_ether_str(str)
 }
 
+fn parse_gwei(str: &str) -> anyhow::Result<ether::Amount> {
+    let gwei = u64::from_str(str)?;
+    let wei = gwei
+        .checked_mul(1_000_000_000)
+        .ok_or_else(|| anyhow::anyhow!("gas price of {} gwei is too high", gwei))?;
+
+    Ok(ether::Amount::from(wei))
+}
+
 fn parse_secret(str: &str) -> anyhow::Result<Secret> {
     let mut secret = [0u8; 32];
     hex::decode_to_slice(str, &mut secret)?;
@@ -193,23 +349,26 @@ fn parse_secret(str: &str) -> anyhow::Result<Secret> {
     Ok(Secret::from(secret))
 }
 
+/// The history only records BTC/DAI trades, hence hbit<->hbit swaps have no
+/// entry.
 pub fn into_history_trade(
     peer_id: libp2p::PeerId,
     swap: SwapKind,
     #[cfg(not(test))] final_timestamp: OffsetDateTime,
-) -> history::Trade {
+) -> Option<history::Trade> {
     use crate::history::*;
 
     let (swap, position) = match swap {
         SwapKind::HbitHerc20(swap) => (swap, history::Position::Sell),
         SwapKind::Herc20Hbit(swap) => (swap, history::Position::Buy),
+        SwapKind::HbitHbit(_) => return None,
     };
 
     #[cfg(test)]
     let final_timestamp =
         OffsetDateTime::parse("2020-07-10T17:48:26.123+10:00", time::Format::Rfc3339).unwrap();
 
-    Trade {
+    Some(Trade {
         utc_start_timestamp: swap.start_of_swap,
         utc_final_timestamp: final_timestamp,
         base_symbol: Symbol::Btc,
@@ -219,7 +378,7 @@ pub fn into_history_trade(
         quote_precise_amount: BigUint::from_str(&swap.herc20_params.asset.quantity.to_wei_dec())
             .expect("number to number conversion"),
         peer: peer_id,
-    }
+    })
 }
 
 #[derive(Debug, Clone)]
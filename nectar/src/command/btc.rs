@@ -0,0 +1,142 @@
+use crate::{bitcoin, command::Btc};
+use ::bitcoin::consensus::encode::serialize_hex;
+use comit::ledger;
+use std::time::Duration;
+
+pub async fn btc(bitcoin_wallet: bitcoin::Wallet, arguments: Btc) -> anyhow::Result<String> {
+    match arguments {
+        Btc::Confirmations {
+            txid,
+            wait_for,
+            timeout_secs,
+        } => {
+            let confirmations = match wait_for {
+                Some(target) => {
+                    bitcoin_wallet
+                        .wait_for_confirmations(
+                            txid,
+                            target,
+                            poll_interval(bitcoin_wallet.ledger),
+                            Duration::from_secs(timeout_secs),
+                        )
+                        .await?
+                }
+                None => bitcoin_wallet.transaction_confirmations(txid).await?,
+            };
+            Ok(format!(
+                "Transaction {} has {} confirmations",
+                txid, confirmations
+            ))
+        }
+        Btc::BumpFee {
+            txid,
+            sat_per_vbyte,
+        } => {
+            let replacement = bitcoin_wallet
+                .bump_fee(txid, bitcoin::Amount::from_sat(sat_per_vbyte))
+                .await?;
+            Ok(format!("Transaction {} replaced by {}", txid, replacement))
+        }
+        Btc::AddressIndex { address, gap_limit } => {
+            let index = bitcoin_wallet
+                .with_gap_limit(gap_limit)
+                .address_index(&address)
+                .await?;
+            match index {
+                Some((chain, index)) => Ok(format!(
+                    "Address {} is at index {} of the {:?} chain",
+                    address, index, chain
+                )),
+                None => Ok(format!(
+                    "Address {} is not amongst the first {} addresses of the wallet",
+                    address, gap_limit
+                )),
+            }
+        }
+        Btc::SignMessage { address, message } => bitcoin_wallet
+            .sign_message(&address, &message)
+            .await
+            .map_err(Into::into),
+        Btc::VerifyMessage {
+            address,
+            message,
+            signature,
+        } => {
+            let valid = bitcoin_wallet
+                .verify_message(&address, &message, &signature)
+                .await?;
+            if valid {
+                Ok("Signature is valid".to_owned())
+            } else {
+                Ok("Signature is NOT valid".to_owned())
+            }
+        }
+        Btc::CreatePsbt { outputs } => bitcoin_wallet
+            .create_psbt(outputs)
+            .await
+            .map_err(Into::into),
+        Btc::FinalizePsbt { psbt, broadcast } => {
+            let transaction = bitcoin_wallet.finalize_and_extract(&psbt).await?;
+            if !broadcast {
+                return Ok(serialize_hex(&transaction));
+            }
+
+            let txid = bitcoin_wallet
+                .send_raw_transaction(transaction, bitcoin_wallet.ledger)
+                .await?;
+            Ok(format!("Transaction id: {}", txid))
+        }
+        Btc::ListUnspent { min_confirmations } => {
+            let utxos = bitcoin_wallet.list_unspent(min_confirmations).await?;
+            Ok(utxos
+                .iter()
+                .map(|utxo| {
+                    format!(
+                        "{} {} to {} ({} confirmations)",
+                        utxo.outpoint, utxo.amount, utxo.address, utxo.confirmations
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        Btc::Consolidate {
+            max_inputs,
+            sat_per_vbyte,
+        } => {
+            let txid = bitcoin_wallet
+                .consolidate(max_inputs, bitcoin::Amount::from_sat(sat_per_vbyte))
+                .await?;
+            match txid {
+                Some(txid) => Ok(format!("Transaction id: {}", txid)),
+                None => Ok("Fewer than two outputs to consolidate".to_owned()),
+            }
+        }
+        Btc::WaitForPayment {
+            address,
+            amount,
+            timeout_secs,
+        } => {
+            let txid = bitcoin_wallet
+                .wait_for_payment(&address, amount, Duration::from_secs(timeout_secs))
+                .await?;
+            Ok(format!(
+                "{} received by {}\nTransaction id: {}",
+                amount, address, txid
+            ))
+        }
+        Btc::Descriptors { start, end } => {
+            let descriptors = bitcoin_wallet.descriptors_ranged(start, end).await?;
+            Ok(serde_json::to_string_pretty(&descriptors)?)
+        }
+    }
+}
+
+fn poll_interval(ledger: ledger::Bitcoin) -> Duration {
+    match ledger {
+        ledger::Bitcoin::Mainnet
+        | ledger::Bitcoin::Testnet
+        | ledger::Bitcoin::Testnet4
+        | ledger::Bitcoin::Signet => Duration::from_secs(30),
+        ledger::Bitcoin::Regtest => Duration::from_secs(1),
+    }
+}
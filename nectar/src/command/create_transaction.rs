@@ -3,7 +3,7 @@ use crate::{
     command::CreateTransaction,
     database::{Database, Load},
     ethereum,
-    ethereum::to_clarity_address,
+    ethereum::{ether, to_clarity_address, GasPriceOracle, Urgency},
     swap::SwapKind,
 };
 use anyhow::{Context, Result};
@@ -33,7 +33,7 @@ pub async fn create_transaction(
                 },
             ) => {
                 let redeem_address = bitcoin_wallet.new_address().await?;
-                let vbyte_rate = bitcoin_fee.vbyte_rate().await?;
+                let vbyte_rate = bitcoin_fee.vbyte_rate(&bitcoin_wallet).await?;
 
                 let transaction = params.hbit_params.build_spend_action(
                 fund_amount.unwrap_or(params.hbit_params.shared.asset),
@@ -53,7 +53,7 @@ pub async fn create_transaction(
                 let action = params.herc20_params.build_refund_action(address.context(
                     "HTLC address required but not provided, please provide with --address",
                 )?);
-                let gas_price = gas_price.gas_price().await?;
+                let gas_price = ether::Amount::from(gas_price.gas_price(Urgency::Normal).await?);
                 let to = to_clarity_address(action.to)?;
                 let chain_id = action.chain_id;
 
@@ -95,7 +95,7 @@ pub async fn create_transaction(
                     secret,
                 );
 
-                let gas_price = gas_price.gas_price().await?;
+                let gas_price = ether::Amount::from(gas_price.gas_price(Urgency::Normal).await?);
                 let to = to_clarity_address(action.to)?;
                 let chain_id = action.chain_id;
 
@@ -132,7 +132,7 @@ pub async fn create_transaction(
                 },
             ) => {
                 let refund_address = bitcoin_wallet.new_address().await?;
-                let vbyte_rate = bitcoin_fee.vbyte_rate().await?;
+                let vbyte_rate = bitcoin_fee.vbyte_rate(&bitcoin_wallet).await?;
 
                 let transaction = params.hbit_params.build_spend_action(
                     fund_amount.unwrap_or(params.hbit_params.shared.asset),
@@ -147,6 +147,9 @@ pub async fn create_transaction(
 
                 ::bitcoin::consensus::encode::serialize_hex(&transaction)
             }
+            (SwapKind::HbitHbit(_), _) => {
+                anyhow::bail!("creating transactions for hbit<->hbit swaps is not supported yet")
+            }
         };
 
     Ok(hex)
@@ -33,10 +33,14 @@ mod tests {
         let bitcoin_blockchain = test_harness::bitcoin::Blockchain::new(&client).unwrap();
         bitcoin_blockchain.init().await.unwrap();
 
-        let bitcoin_wallet =
-            bitcoin::Wallet::new(seed, bitcoin_blockchain.node_url, ledger::Bitcoin::Regtest)
-                .await
-                .unwrap();
+        let bitcoin_wallet = bitcoin::Wallet::new(
+            seed,
+            bitcoin_blockchain.node_url,
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await
+        .unwrap();
 
         let mut ethereum_blockchain = test_harness::ethereum::Blockchain::new(&client).unwrap();
         ethereum_blockchain.init().await.unwrap();
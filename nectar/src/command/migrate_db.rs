@@ -1,12 +1,13 @@
-use crate::{command::MigrateDb, database, database::Database};
+use crate::{command::MigrateDb, config::Data, database, database::Database, Seed};
 use anyhow::{bail, Result};
-use std::path::PathBuf;
 use time::OffsetDateTime;
 
-pub async fn migrate_db(action: MigrateDb, db_dir: &PathBuf) -> Result<()> {
+pub async fn migrate_db(action: MigrateDb, data: &Data, seed: &Seed) -> Result<()> {
+    let db_dir = data.dir.join("database");
+
     match action {
         MigrateDb::Status => {
-            match status(db_dir).await? {
+            match status(data, seed).await? {
                 MigrationStatus::NotNeeded => {
                     println!("Database migration is not needed.");
                 }
@@ -21,12 +22,12 @@ pub async fn migrate_db(action: MigrateDb, db_dir: &PathBuf) -> Result<()> {
             }
             Ok(())
         }
-        MigrateDb::Run => run(db_dir).await,
+        MigrateDb::Run => run(data, seed).await,
     }
 }
 
-async fn status(db_dir: &PathBuf) -> Result<MigrationStatus> {
-    let db = match Database::new(db_dir.as_path()) {
+async fn status(data: &Data, seed: &Seed) -> Result<MigrationStatus> {
+    let db = match Database::open(data, seed) {
         Err(database::Error::Sled(sled::Error::Unsupported(_))) => {
             println!("Database needs migration due to an upgrade of sled data type");
             return Ok(MigrationStatus::NeededSledFormat);
@@ -49,8 +50,10 @@ pub enum MigrationStatus {
     NeededSerializationFormat,
 }
 
-async fn run(db_dir: &PathBuf) -> Result<()> {
-    match status(db_dir).await? {
+async fn run(data: &Data, seed: &Seed) -> Result<()> {
+    let db_dir = data.dir.join("database");
+
+    match status(data, seed).await? {
         MigrationStatus::NotNeeded => {
             bail!("Database migration is not necessary");
         }
@@ -65,7 +68,7 @@ async fn run(db_dir: &PathBuf) -> Result<()> {
                 })?
                 .join(backup_dir_name);
 
-            std::fs::rename(db_dir, backup_dir.clone())?;
+            std::fs::rename(&db_dir, backup_dir.clone())?;
 
             let old_db = sled::open(backup_dir.as_path())?;
             let new_db = sled::open(db_dir.as_path())?;
@@ -77,7 +80,7 @@ async fn run(db_dir: &PathBuf) -> Result<()> {
             Ok(())
         }
         MigrationStatus::NeededSerializationFormat => {
-            let db = Database::new(db_dir.as_path())?;
+            let db = Database::open(data, seed)?;
             db.reserialize().await
         }
     }
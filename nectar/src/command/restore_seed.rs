@@ -0,0 +1,17 @@
+use crate::{config, Seed};
+use std::{io::BufRead, path::PathBuf};
+
+/// Reads the mnemonic of the seed from `input`, it is not taken as argument
+/// so that it does not end up in the shell history.
+pub fn restore_seed(data_dir: &PathBuf, mut input: impl BufRead) -> anyhow::Result<String> {
+    let mut phrase = String::new();
+    input.read_line(&mut phrase)?;
+
+    let seed = Seed::from_mnemonic(phrase.trim(), "")?;
+    config::Seed::restore(data_dir, seed)?;
+
+    Ok(format!(
+        "Seed restored to {}",
+        data_dir.join("seed.pem").display()
+    ))
+}
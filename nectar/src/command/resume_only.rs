@@ -4,7 +4,8 @@ use crate::{
     config::Settings,
     ethereum,
     history::History,
-    swap::{Database, SwapExecutor},
+    swap::{self, Database, SwapExecutor},
+    Seed,
 };
 use comit::btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector};
 use futures::{future::TryFutureExt, StreamExt};
@@ -12,31 +13,40 @@ use std::sync::Arc;
 
 pub async fn resume_only(
     settings: Settings,
+    seed: Seed,
+    db: Database,
     bitcoin_wallet: bitcoin::Wallet,
     bitcoin_fee: bitcoin::Fee,
     ethereum_wallet: ethereum::Wallet,
     ethereum_gas_price: ethereum::GasPrice,
 ) -> anyhow::Result<()> {
-    #[cfg(not(test))]
-    let db = Database::new(&settings.data.dir.join("database"))?;
-    #[cfg(test)]
-    let db = Database::new_test()?;
     let db = Arc::new(db);
     let mut history = History::new(settings.data.dir.join("history.csv").as_path())?;
 
+    let bitcoin_wallets = swap::bitcoin::Wallets::new(
+        swap::bitcoin::Wallet {
+            inner: Arc::new(bitcoin_wallet),
+            fee: bitcoin_fee,
+            connector: Arc::new(BitcoindConnector::new(
+                settings.bitcoin.bitcoind.node_url.clone(),
+            )?),
+        },
+        seed,
+        &settings.bitcoin,
+    )
+    .await?;
+
     let (executor, mut finished_swap_receiver) = SwapExecutor::new(
         db.clone(),
-        Arc::new(bitcoin_wallet),
-        bitcoin_fee,
+        bitcoin_wallets,
         Arc::new(ethereum_wallet),
         ethereum_gas_price,
-        Arc::new(BitcoindConnector::new(settings.bitcoin.bitcoind.node_url)?),
         Arc::new(Web3Connector::new(settings.ethereum.node_url)),
     );
 
-    for swap in db.all_active_swaps()? {
-        executor.execute(swap);
-    }
+    executor.resume(db.all_active_swaps()?)?;
+    executor.spawn_refund_watcher();
+    executor.spawn_reorg_watcher();
 
     while let Some(finished_swap) = finished_swap_receiver.next().await {
         handle_finished_swap(finished_swap, db.as_ref(), &mut history)
@@ -46,20 +56,20 @@ pub async fn resume_only(
 }
 
 fn handle_finished_swap(finished_swap: FinishedSwap, db: &Database, history: &mut History) {
-    let trade = into_history_trade(
+    if let Some(trade) = into_history_trade(
         finished_swap.peer.peer_id(),
         finished_swap.swap.clone(),
         #[cfg(not(test))]
         finished_swap.final_timestamp,
-    );
-
-    let _ = history.write(trade).map_err(|error| {
-        tracing::error!(
-            "Unable to register history entry: {:#}; {:?}",
-            error,
-            finished_swap
-        )
-    });
+    ) {
+        let _ = history.write(trade).map_err(|error| {
+            tracing::error!(
+                "Unable to register history entry: {:#}; {:?}",
+                error,
+                finished_swap
+            )
+        });
+    }
 
     let swap_id = finished_swap.swap.swap_id();
 
@@ -9,7 +9,7 @@ use crate::{
     maker::strategy,
     mid_market_rate::get_btc_dai_mid_market_rate,
     network::{self, new_swarm},
-    swap::{Database, SwapExecutor, SwapKind, SwapParams},
+    swap::{self, shutdown, Database, HbitHbitParams, SwapExecutor, SwapKind, SwapParams},
     Maker, MidMarketRate, Seed, Spread,
 };
 use anyhow::Context;
@@ -17,10 +17,17 @@ use comit::{
     btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector},
     Role,
 };
-use futures::{channel::mpsc, Future, SinkExt};
+use futures::{
+    channel::mpsc,
+    future::{self, Either},
+    Future, SinkExt,
+};
 use futures_timer::Delay;
 use std::{sync::Arc, time::Duration};
 
+/// How long we wait for in-flight swap actions when shutting down.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(60);
+
 pub async fn trade(
     seed: &Seed,
     settings: Settings,
@@ -31,11 +38,8 @@ pub async fn trade(
     let bitcoin_wallet = Arc::new(bitcoin_wallet);
     let ethereum_wallet = Arc::new(ethereum_wallet);
 
-    let bitcoind_client = bitcoin::Client::new(settings.bitcoin.bitcoind.node_url.clone());
-
     let mut maker = init_maker(
         Arc::clone(&bitcoin_wallet),
-        bitcoind_client.clone(),
         Arc::clone(&ethereum_wallet),
         settings.clone(),
         network,
@@ -44,7 +48,7 @@ pub async fn trade(
     .context("Could not initialise Maker")?;
 
     #[cfg(not(test))]
-    let db = Arc::new(Database::new(&settings.data.dir.join("database"))?);
+    let db = Arc::new(Database::open(&settings.data, seed)?);
     #[cfg(test)]
     let db = Arc::new(Database::new_test()?);
 
@@ -79,25 +83,37 @@ pub async fn trade(
     )?);
     let ethereum_connector = Arc::new(Web3Connector::new(settings.ethereum.node_url.clone()));
 
-    let bitcoin_fee = bitcoin::Fee::new(settings.bitcoin.clone(), bitcoind_client);
+    let bitcoin_fee = bitcoin::Fee::new(settings.bitcoin.clone());
+    let bitcoin_wallets = swap::bitcoin::Wallets::new(
+        swap::bitcoin::Wallet {
+            inner: Arc::clone(&bitcoin_wallet),
+            fee: bitcoin_fee,
+            connector: bitcoin_connector,
+        },
+        *seed,
+        &settings.bitcoin,
+    )
+    .await?;
 
     let ethereum_gas_price = ethereum::GasPrice::new(settings.ethereum.gas_price);
 
     let (swap_executor, swap_execution_finished_receiver) = SwapExecutor::new(
         Arc::clone(&db),
-        Arc::clone(&bitcoin_wallet),
-        bitcoin_fee,
+        bitcoin_wallets,
         Arc::clone(&ethereum_wallet),
         ethereum_gas_price,
-        bitcoin_connector,
         ethereum_connector,
     );
 
     respawn_swaps(Arc::clone(&db), &mut maker, swap_executor.clone())
         .context("Could not respawn swaps")?;
+    swap_executor.spawn_refund_watcher();
+    swap_executor.spawn_reorg_watcher();
 
     let history = History::new(settings.data.dir.join("history.csv").as_path())?;
 
+    let shutdown = swap_executor.shutdown();
+
     let event_loop = EventLoop::new(
         maker,
         swarm,
@@ -108,19 +124,34 @@ pub async fn trade(
         swap_executor,
     );
 
-    event_loop
-        .run(
-            swap_execution_finished_receiver,
-            rate_update_receiver,
-            btc_balance_update_receiver,
-            dai_balance_update_receiver,
-        )
-        .await
+    let event_loop = Box::pin(event_loop.run(
+        swap_execution_finished_receiver,
+        rate_update_receiver,
+        btc_balance_update_receiver,
+        dai_balance_update_receiver,
+    ));
+
+    // Dropping the event loop stops taking new orders and swaps
+    match future::select(event_loop, Box::pin(shutdown::signal())).await {
+        Either::Left((result, _)) => result,
+        Either::Right((signal, _)) => {
+            signal.context("Could not listen for the shutdown signal")?;
+
+            tracing::info!("shutting down, waiting for in-flight swap actions");
+            if !shutdown.initiate(SHUTDOWN_TIMEOUT).await {
+                tracing::warn!(
+                    "swap actions still in flight after {}s, they will be resumed on restart",
+                    SHUTDOWN_TIMEOUT.as_secs()
+                );
+            }
+
+            Ok(())
+        }
+    }
 }
 
 async fn init_maker(
     bitcoin_wallet: Arc<bitcoin::Wallet>,
-    bitcoind_client: bitcoin::Client,
     ethereum_wallet: Arc<ethereum::Wallet>,
     settings: Settings,
     network: comit::Network,
@@ -148,7 +179,6 @@ async fn init_maker(
         btc_dai.max_buy_quantity,
         btc_dai.max_sell_quantity,
         spread,
-        bitcoind_client,
     );
 
     Ok(Maker::new(
@@ -210,7 +240,7 @@ fn init_bitcoin_balance_updates(
 
     let future = async move {
         loop {
-            let balance = wallet.balance().await;
+            let balance = wallet.balance().await.map_err(anyhow::Error::from);
 
             let _ = sender.send(balance).await.map_err(|e| {
                 tracing::trace!(
@@ -258,7 +288,9 @@ fn respawn_swaps(
     maker: &mut Maker,
     swap_executor: SwapExecutor,
 ) -> anyhow::Result<()> {
-    for swap in db.all_active_swaps()?.into_iter() {
+    let swaps = db.all_active_swaps()?;
+
+    for swap in swaps.iter() {
         // Reserve funds
         match swap {
             SwapKind::HbitHerc20(SwapParams {
@@ -273,12 +305,17 @@ fn respawn_swaps(
                 let fund_amount = hbit_params.shared.asset;
                 maker.strategy.herc20_hbit_swap_resumed(fund_amount)?;
             }
+            // we fund the beta HTLC, reserving the same way as for herc20_hbit swaps
+            SwapKind::HbitHbit(HbitHbitParams {
+                ref beta_params, ..
+            }) => {
+                let fund_amount = beta_params.shared.asset;
+                maker.strategy.herc20_hbit_swap_resumed(fund_amount)?;
+            }
         };
-
-        swap_executor.execute(swap);
     }
 
-    Ok(())
+    swap_executor.resume(swaps)
 }
 
 #[cfg(all(test, feature = "testcontainers"))]
@@ -315,6 +352,7 @@ mod tests {
                 btc_dai: Default::default(),
                 spread: StaticStub::static_stub(),
                 kraken_api_host: Default::default(),
+                token_allowlist: vec![],
             },
             network: Network {
                 listen: vec!["/ip4/98.97.96.95/tcp/20500"
@@ -323,9 +361,11 @@ mod tests {
             },
             data: Data {
                 dir: Default::default(),
+                encrypt: false,
             },
             logging: Logging {
                 level: LevelFilter::Trace,
+                file: None,
             },
             bitcoin: settings::Bitcoin::default_from_network(ledger::Bitcoin::Regtest),
             ethereum: settings::Ethereum {
@@ -343,6 +383,7 @@ mod tests {
             seed,
             bitcoin_blockchain.node_url.clone(),
             ledger::Bitcoin::Regtest,
+            None,
         )
         .await
         .unwrap();
@@ -166,16 +166,16 @@ impl EventLoop {
             .await
             .context("Unable to remove from active takers");
 
-        let trade = into_history_trade(
+        if let Some(trade) = into_history_trade(
             finished_swap.peer.peer_id(),
             finished_swap.swap.clone(),
             #[cfg(not(test))]
             finished_swap.final_timestamp,
-        );
-
-        self.history
-            .write(trade)
-            .with_context(|| format!("Unable to register history entry: {:?}", finished_swap))?;
+        ) {
+            self.history.write(trade).with_context(|| {
+                format!("Unable to register history entry: {:?}", finished_swap)
+            })?;
+        }
 
         self.database
             .remove_swap(&finished_swap.swap.swap_id())
@@ -251,6 +251,9 @@ impl EventLoop {
             setup_swap::BehaviourOutEvent::AlreadyHaveRoleParams { peer, .. } => {
                 bail!("already received role params from {}", peer)
             }
+            setup_swap::BehaviourOutEvent::Declined { peer, reason } => {
+                tracing::info!("declined swap proposed by {}: {}", peer, reason)
+            }
         }
 
         Ok(())
@@ -417,6 +420,8 @@ impl EventLoop {
                     TakeRequestDecision::RateNotProfitable => bail!("Rate not profitable"),
                 };
             }
+            orderbook::BehaviourOutEvent::RetrievedOrders { .. }
+            | orderbook::BehaviourOutEvent::MakerIsGone { .. } => {}
         }
 
         Ok(())
@@ -11,8 +11,10 @@ pub async fn wallet_info(
     let ethereum_info = ethereum_info(ethereum_wallet, &seed);
 
     Ok(format!(
-        "Bitcoin wallet descriptors:\n{}\nEthereum private key:\n{}",
-        bitcoin_info, ethereum_info
+        "Seed mnemonic:\n{}\nBitcoin wallet descriptors:\n{}\nEthereum private key:\n{}",
+        seed.to_mnemonic(),
+        bitcoin_info,
+        ethereum_info
     ))
 }
 
@@ -29,7 +31,8 @@ async fn bitcoin_info(
     match descriptors {
         Some(descriptors) => descriptors.join("\n"),
         None => {
-            let descriptors = bitcoin::Wallet::descriptors_from_seed(&seed, ledger);
+            let descriptors =
+                bitcoin::Wallet::descriptors_from_seed(&seed, ledger, bitcoin::DEFAULT_ACCOUNT);
             format!("(could not reach bitcoind)\n{}", descriptors.join("\n"))
         }
     }
@@ -60,9 +63,13 @@ mod tests {
         let bitcoin_blockchain = test_harness::bitcoin::Blockchain::new(&client)?;
         bitcoin_blockchain.init().await?;
 
-        let bitcoin_wallet =
-            bitcoin::Wallet::new(seed, bitcoin_blockchain.node_url, ledger::Bitcoin::Regtest)
-                .await?;
+        let bitcoin_wallet = bitcoin::Wallet::new(
+            seed,
+            bitcoin_blockchain.node_url,
+            ledger::Bitcoin::Regtest,
+            None,
+        )
+        .await?;
 
         let mut ethereum_blockchain = test_harness::ethereum::Blockchain::new(&client)?;
         ethereum_blockchain.init().await?;
@@ -1,4 +1,9 @@
-use crate::{bitcoin, command::Withdraw, ethereum, ethereum::STANDARD_ETH_TRANSFER_GAS_LIMIT};
+use crate::{
+    bitcoin,
+    command::Withdraw,
+    ethereum,
+    ethereum::{ether, GasPriceOracle, Urgency, STANDARD_ETH_TRANSFER_GAS_LIMIT},
+};
 use std::borrow::Borrow;
 
 pub async fn withdraw(
@@ -18,8 +23,38 @@ pub async fn withdraw(
                 amount, to_address, tx_id
             ))
         }
+        Withdraw::BtcMany { outputs } => {
+            let bitcoin_wallet = bitcoin_wallet.borrow();
+            let tx_id = bitcoin_wallet
+                .send_to_many(outputs.clone(), bitcoin_wallet.ledger)
+                .await?;
+            let transfers = outputs
+                .iter()
+                .map(|(to_address, amount)| format!("{} transferred to {}", amount, to_address))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(format!("{}\nTransaction id: {}", transfers, tx_id))
+        }
+        Withdraw::BtcAll {
+            to_address,
+            sat_per_vbyte,
+            include_unconfirmed,
+        } => {
+            let tx_id = bitcoin_wallet
+                .borrow()
+                .sweep_to(
+                    to_address.clone(),
+                    sat_per_vbyte.map(bitcoin::Amount::from_sat),
+                    include_unconfirmed,
+                )
+                .await?;
+            Ok(format!(
+                "Balance transferred to {}\nTransaction id: {}",
+                to_address, tx_id
+            ))
+        }
         Withdraw::Dai { amount, to_address } => {
-            let gas_price = ethereum_gas_price.gas_price().await?;
+            let gas_price = ether::Amount::from(ethereum_gas_price.gas_price(Urgency::Slow).await?);
             let tx_id = ethereum_wallet
                 .transfer_dai(
                     to_address,
@@ -34,7 +69,7 @@ pub async fn withdraw(
             ))
         }
         Withdraw::Eth { amount, to_address } => {
-            let gas_price = ethereum_gas_price.gas_price().await?;
+            let gas_price = ether::Amount::from(ethereum_gas_price.gas_price(Urgency::Slow).await?);
             let tx_id = ethereum_wallet
                 .send_transaction(
                     to_address,
@@ -81,6 +116,7 @@ mod tests {
                 seed,
                 bitcoin_blockchain.node_url.clone(),
                 ledger::Bitcoin::Regtest,
+                None,
             )
             .await
             .unwrap(),
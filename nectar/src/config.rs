@@ -6,6 +6,7 @@ pub mod settings;
 use crate::bitcoin;
 use ::serde::{Deserialize, Serialize};
 use anyhow::anyhow;
+use comit::ledger;
 use libp2p::Multiaddr;
 use std::path::PathBuf;
 use url::Url;
@@ -17,6 +18,11 @@ use anyhow::Context;
 #[serde(deny_unknown_fields)]
 pub struct Data {
     pub dir: PathBuf,
+    /// Encrypt the swaps stored in the database with a key derived from the
+    /// seed. Swaps stored in plaintext before enabling it are encrypted when
+    /// the database is opened.
+    #[serde(default)]
+    pub encrypt: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -31,6 +37,15 @@ pub struct Bitcoind {
     pub node_url: Url,
 }
 
+/// A bitcoind on another network than the main one, the other leg of
+/// hbit<->hbit swaps may be on it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdditionalBitcoinNetwork {
+    pub network: ledger::Bitcoin,
+    pub node_url: Url,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct BtcDai {
@@ -153,6 +168,7 @@ mod tests {
                 }),
                 spread: Some(Spread::new(500).unwrap()),
                 kraken_api_host: Some("https://api.kraken.com".parse().unwrap()),
+                token_allowlist: None,
             }),
             network: Some(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
@@ -161,9 +177,11 @@ mod tests {
                 dir: "/Users/froyer/Library/Application Support/nectar"
                     .parse()
                     .unwrap(),
+                encrypt: false,
             }),
             logging: Some(file::Logging {
                 level: Some(Level::Info),
+                file: None,
             }),
             bitcoin: Some(file::Bitcoin {
                 network: ledger::Bitcoin::Regtest,
@@ -175,7 +193,12 @@ mod tests {
                     sat_per_vbyte: Some(bitcoin::Amount::from_sat(12)),
                     estimate_mode: None,
                     max_sat_per_vbyte: None,
+                    min_sat_per_vbyte: None,
                 }),
+                additional_networks: vec![],
+                watch_only_descriptors: vec![],
+                change_policy: None,
+                wallet_passphrase: None,
             }),
             ethereum: Some(file::Ethereum {
                 chain_id: ChainId::MAINNET,
@@ -1,12 +1,16 @@
 use crate::{
-    config::{Bitcoind, BtcDai, Data, EstimateMode, Network},
+    bitcoin::AddressType,
+    config::{AdditionalBitcoinNetwork, Bitcoind, BtcDai, Data, EstimateMode, Network},
     Spread,
 };
 use comit::{ethereum::ChainId, ledger};
 use config as config_rs;
 use log::LevelFilter;
 use serde::{Deserialize, Serialize};
-use std::{ffi::OsStr, path::Path};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
 use url::Url;
 
 /// This struct aims to represent the configuration file as it appears on disk.
@@ -31,6 +35,8 @@ pub struct File {
 pub struct Maker {
     pub spread: Option<Spread>,
     pub kraken_api_host: Option<Url>,
+    #[serde(default)]
+    pub token_allowlist: Option<Vec<comit::ethereum::Address>>,
     pub btc_dai: Option<BtcDai>,
 }
 
@@ -41,6 +47,16 @@ pub struct Bitcoin {
     pub bitcoind: Option<Bitcoind>,
     #[serde(default)]
     pub fees: Option<BitcoinFees>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_networks: Vec<AdditionalBitcoinNetwork>,
+    /// Descriptors of a wallet whose keys are held by an external signer.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watch_only_descriptors: Vec<String>,
+    #[serde(default)]
+    pub change_policy: Option<ChangePolicy>,
+    /// Never written out, dumping the config must not reveal it.
+    #[serde(default, skip_serializing)]
+    pub wallet_passphrase: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -73,6 +89,23 @@ pub struct BitcoinFees {
     #[serde(default)]
     #[serde(with = "::bitcoin::util::amount::serde::as_sat::opt")]
     pub max_sat_per_vbyte: Option<bitcoin::Amount>,
+    /// The rate to use if bitcoind estimatesmartfee returns a lower one or
+    /// cannot estimate one at all, which is common on a fresh regtest chain.
+    #[serde(default)]
+    #[serde(with = "::bitcoin::util::amount::serde::as_sat::opt")]
+    pub min_sat_per_vbyte: Option<bitcoin::Amount>,
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChangePolicy {
+    /// Change below this amount is added to the fee instead of being paid to
+    /// an output of its own
+    #[serde(default)]
+    #[serde(with = "::bitcoin::util::amount::serde::as_sat::opt")]
+    pub discard_below_sat: Option<bitcoin::Amount>,
+    /// The type of the change addresses
+    pub address_type: Option<AddressType>,
 }
 
 #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -124,10 +157,18 @@ impl Default for File {
     }
 }
 
-#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Logging {
     pub level: Option<Level>,
+    pub file: Option<LogFile>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LogFile {
+    pub dir: PathBuf,
+    pub prefix: Option<String>,
 }
 
 #[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
@@ -214,6 +255,7 @@ mod tests {
 # 1000 is 10.00% spread
 spread = 1000
 kraken_api_host = "https://api.kraken.com"
+token_allowlist = ["0x6A9865aDE2B6207dAAC49f8bCba9705dEB0B0e6D"]
 
 [maker.btc_dai]
 max_buy_quantity = 1.23456
@@ -238,6 +280,10 @@ node_url = "http://localhost:18443/"
 strategy = "bitcoind"
 max_sat_per_vbyte = 25
 
+[[bitcoin.additional_networks]]
+network = "signet"
+node_url = "http://localhost:38332"
+
 [ethereum]
 chain_id = 1337
 node_url = "http://localhost:8545/"
@@ -258,15 +304,20 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
                 }),
                 spread: Some(Spread::new(1000).unwrap()),
                 kraken_api_host: Some("https://api.kraken.com".parse().unwrap()),
+                token_allowlist: Some(vec!["0x6A9865aDE2B6207dAAC49f8bCba9705dEB0B0e6D"
+                    .parse()
+                    .unwrap()]),
             }),
             network: Some(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
             }),
             data: Some(Data {
                 dir: PathBuf::from("/tmp/nectar/"),
+                encrypt: false,
             }),
             logging: Some(Logging {
                 level: Some(Level::Debug),
+                file: None,
             }),
             bitcoin: Some(Bitcoin {
                 network: ledger::Bitcoin::Regtest,
@@ -278,7 +329,15 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
                     sat_per_vbyte: None,
                     estimate_mode: None,
                     max_sat_per_vbyte: Some(bitcoin::Amount::from_sat(25)),
+                    min_sat_per_vbyte: None,
                 }),
+                additional_networks: vec![AdditionalBitcoinNetwork {
+                    network: ledger::Bitcoin::Signet,
+                    node_url: "http://localhost:38332".parse().unwrap(),
+                }],
+                watch_only_descriptors: vec![],
+                change_policy: None,
+                wallet_passphrase: None,
             }),
             ethereum: Some(Ethereum {
                 chain_id: ChainId::GETH_DEV,
@@ -323,15 +382,21 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
                 }),
                 spread: Some(Spread::new(1000).unwrap()),
                 kraken_api_host: Some("https://api.kraken.com".parse().unwrap()),
+                token_allowlist: None,
             }),
             network: Some(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
             }),
             data: Some(Data {
                 dir: PathBuf::from("/tmp/nectar/"),
+                encrypt: true,
             }),
             logging: Some(Logging {
                 level: Some(Level::Debug),
+                file: Some(LogFile {
+                    dir: PathBuf::from("/var/log/nectar/"),
+                    prefix: None,
+                }),
             }),
             bitcoin: Some(Bitcoin {
                 network: ledger::Bitcoin::Regtest,
@@ -343,7 +408,12 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
                     sat_per_vbyte: None,
                     estimate_mode: Some(EstimateMode::Conservative),
                     max_sat_per_vbyte: Some(bitcoin::Amount::from_sat(34)),
+                    min_sat_per_vbyte: None,
                 }),
+                additional_networks: vec![],
+                watch_only_descriptors: vec![],
+                change_policy: None,
+                wallet_passphrase: None,
             }),
             ethereum: Some(Ethereum {
                 chain_id: ChainId::GETH_DEV,
@@ -380,10 +450,14 @@ listen = ["/ip4/0.0.0.0/tcp/9939"]
 
 [data]
 dir = "/tmp/nectar/"
+encrypt = true
 
 [logging]
 level = "debug"
 
+[logging.file]
+dir = "/var/log/nectar/"
+
 [bitcoin]
 network = "regtest"
 
@@ -464,6 +538,10 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
                     node_url: Url::parse("http://example.com:8332").unwrap(),
                 }),
                 fees: None,
+                additional_networks: vec![],
+                watch_only_descriptors: vec![],
+                change_policy: None,
+                wallet_passphrase: None,
             },
             Bitcoin {
                 network: ledger::Bitcoin::Testnet,
@@ -471,6 +549,10 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
                     node_url: Url::parse("http://example.com:18332").unwrap(),
                 }),
                 fees: None,
+                additional_networks: vec![],
+                watch_only_descriptors: vec![],
+                change_policy: None,
+                wallet_passphrase: None,
             },
             Bitcoin {
                 network: ledger::Bitcoin::Regtest,
@@ -482,7 +564,12 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
                     sat_per_vbyte: Some(bitcoin::Amount::from_sat(12)),
                     estimate_mode: Some(EstimateMode::Unset),
                     max_sat_per_vbyte: Some(bitcoin::Amount::from_sat(34)),
+                    min_sat_per_vbyte: None,
                 }),
+                additional_networks: vec![],
+                watch_only_descriptors: vec![],
+                change_policy: None,
+                wallet_passphrase: None,
             },
         ];
 
@@ -495,6 +582,43 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn change_policy_deserializes_correctly() {
+        let file_contents = r#"
+            network = "regtest"
+            [change_policy]
+            discard_below_sat = 1000
+            address_type = "p2sh-segwit"
+            "#;
+
+        let bitcoin = toml::from_str::<Bitcoin>(file_contents).unwrap();
+
+        assert_eq!(
+            bitcoin.change_policy,
+            Some(ChangePolicy {
+                discard_below_sat: Some(bitcoin::Amount::from_sat(1000)),
+                address_type: Some(AddressType::P2shSegwit),
+            })
+        );
+    }
+
+    #[test]
+    fn wallet_passphrase_is_read_but_never_written() {
+        let file_contents = r#"
+            network = "regtest"
+            wallet_passphrase = "correct horse battery staple"
+            "#;
+
+        let bitcoin = toml::from_str::<Bitcoin>(file_contents).unwrap();
+        assert_eq!(
+            bitcoin.wallet_passphrase,
+            Some("correct horse battery staple".to_string())
+        );
+
+        let serialized = toml::to_string(&bitcoin).unwrap();
+        assert!(!serialized.contains("wallet_passphrase"));
+    }
+
     #[test]
     fn ethereum_deserializes_correctly() {
         let file_contents = vec![
@@ -640,6 +764,10 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
             strategy = "bitcoind"
             estimate_mode = "economical"
             "#,
+            r#"
+            strategy = "bitcoind"
+            min_sat_per_vbyte = 2
+            "#,
         ];
 
         let expected = vec![
@@ -648,24 +776,35 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
                 sat_per_vbyte: Some(bitcoin::Amount::from_sat(10)),
                 estimate_mode: None,
                 max_sat_per_vbyte: None,
+                min_sat_per_vbyte: None,
             },
             BitcoinFees {
                 strategy: Some(BitcoinFeeStrategy::Bitcoind),
                 sat_per_vbyte: None,
                 estimate_mode: Some(EstimateMode::Unset),
                 max_sat_per_vbyte: Some(bitcoin::Amount::from_sat(34)),
+                min_sat_per_vbyte: None,
             },
             BitcoinFees {
                 strategy: Some(BitcoinFeeStrategy::Bitcoind),
                 sat_per_vbyte: None,
                 estimate_mode: None,
                 max_sat_per_vbyte: Some(bitcoin::Amount::from_sat(50)),
+                min_sat_per_vbyte: None,
             },
             BitcoinFees {
                 strategy: Some(BitcoinFeeStrategy::Bitcoind),
                 sat_per_vbyte: None,
                 estimate_mode: Some(EstimateMode::Economical),
                 max_sat_per_vbyte: None,
+                min_sat_per_vbyte: None,
+            },
+            BitcoinFees {
+                strategy: Some(BitcoinFeeStrategy::Bitcoind),
+                sat_per_vbyte: None,
+                estimate_mode: None,
+                max_sat_per_vbyte: None,
+                min_sat_per_vbyte: Some(bitcoin::Amount::from_sat(2)),
             },
         ];
 
@@ -33,6 +33,21 @@ impl Seed {
         Ok(random_seed)
     }
 
+    /// Writes a seed restored from a backup to the data directory, refuses
+    /// to overwrite an existing seed.
+    pub fn restore(data_dir: &PathBuf, seed: seed::Seed) -> Result<Self, Error> {
+        let file_path = data_dir.join("seed.pem");
+
+        if file_path.exists() {
+            return Err(Error::AlreadyExists(file_path));
+        }
+
+        let seed = Seed(seed);
+        seed.write_to(file_path)?;
+
+        Ok(seed)
+    }
+
     fn from_file<D>(seed_file: D) -> Result<Self, Error>
     where
         D: AsRef<OsStr>,
@@ -115,6 +130,8 @@ pub enum Error {
     Rand(#[from] rand::Error),
     #[error("no default path")]
     NoDefaultPath,
+    #[error("seed file {0} already exists")]
+    AlreadyExists(PathBuf),
 }
 
 #[cfg(test)]
@@ -182,6 +199,20 @@ mbKANv2qKGmNVg1qtquj6Hx1pFPelpqOfE2JaJJAMEg1FlFhNRNlFlE=
         }
     }
 
+    #[test]
+    fn restore_does_not_overwrite_existing_seed() {
+        let data_dir = tempfile::tempdir().unwrap().into_path();
+        let seed = seed::Seed::random().unwrap();
+
+        let restored = Seed::restore(&data_dir, seed).unwrap();
+        let error = Seed::restore(&data_dir, seed::Seed::random().unwrap()).unwrap_err();
+
+        assert_eq!(restored.0, seed);
+        assert!(matches!(error, Error::AlreadyExists(_)));
+        let read = Seed::from_file_or_generate(&data_dir).unwrap();
+        assert_eq!(read.0, seed);
+    }
+
     #[test]
     fn round_trip_through_file_write_read() {
         let tmpfile = temp_dir().join("seed.pem");
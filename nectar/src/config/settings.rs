@@ -1,7 +1,8 @@
 use crate::{
     bitcoin,
     config::{
-        file, file::EthereumGasPriceService, Bitcoind, BtcDai, Data, EstimateMode, File, Network,
+        file, file::EthereumGasPriceService, AdditionalBitcoinNetwork, Bitcoind, BtcDai, Data,
+        EstimateMode, File, Network,
     },
     ethereum, Spread,
 };
@@ -9,6 +10,7 @@ use anyhow::{Context, Result};
 use comit::ledger;
 use conquer_once::Lazy;
 use log::LevelFilter;
+use std::path::PathBuf;
 use url::Url;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -22,11 +24,22 @@ pub struct Settings {
     pub sentry: Option<Sentry>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, derivative::Derivative, PartialEq)]
+#[derivative(Debug)]
 pub struct Bitcoin {
     pub network: ledger::Bitcoin,
     pub bitcoind: Bitcoind,
     pub fees: BitcoinFees,
+    /// Never contains `network`, nor the same network twice.
+    pub additional_networks: Vec<AdditionalBitcoinNetwork>,
+    /// If not empty, the wallet only watches the addresses of these
+    /// descriptors and cannot sign transactions.
+    pub watch_only_descriptors: Vec<String>,
+    pub change_policy: bitcoin::ChangePolicy,
+    /// Encrypts the wallets we create and unlocks them to sign transactions,
+    /// on all configured networks.
+    #[derivative(Debug = "ignore")]
+    pub wallet_passphrase: Option<String>,
 }
 
 impl Bitcoin {
@@ -35,13 +48,17 @@ impl Bitcoin {
             network,
             bitcoind: Bitcoind::new(network),
             fees: Default::default(),
+            additional_networks: Vec::new(),
+            watch_only_descriptors: Vec::new(),
+            change_policy: bitcoin::ChangePolicy::default(),
+            wallet_passphrase: None,
         }
     }
 
     fn from_file(bitcoin: file::Bitcoin, comit_network: Option<comit::Network>) -> Result<Self> {
         if let Some(comit_network) = comit_network {
             let inferred = ledger::Bitcoin::from(comit_network);
-            if inferred != bitcoin.network {
+            if comit::Network::from(bitcoin.network) != comit_network {
                 anyhow::bail!(
                     "inferred Bitcoin network {} from CLI argument {} but config file says {}",
                     inferred,
@@ -57,10 +74,27 @@ impl Bitcoin {
             .fees
             .map_or_else(BitcoinFees::default, BitcoinFees::from);
 
+        let mut networks = vec![network];
+        for additional in &bitcoin.additional_networks {
+            if networks.contains(&additional.network) {
+                anyhow::bail!(
+                    "Bitcoin network {} is configured more than once",
+                    additional.network
+                );
+            }
+            networks.push(additional.network);
+        }
+
         Ok(Bitcoin {
             network,
             bitcoind,
             fees,
+            additional_networks: bitcoin.additional_networks,
+            watch_only_descriptors: bitcoin.watch_only_descriptors,
+            change_policy: bitcoin
+                .change_policy
+                .map_or_else(bitcoin::ChangePolicy::default, bitcoin::ChangePolicy::from),
+            wallet_passphrase: bitcoin.wallet_passphrase,
         })
     }
 }
@@ -72,6 +106,10 @@ impl crate::StaticStub for Bitcoin {
             network: ledger::Bitcoin::Regtest,
             bitcoind: Bitcoind::new(ledger::Bitcoin::Regtest),
             fees: BitcoinFees::static_stub(),
+            additional_networks: Vec::new(),
+            watch_only_descriptors: Vec::new(),
+            change_policy: bitcoin::ChangePolicy::default(),
+            wallet_passphrase: None,
         }
     }
 }
@@ -85,6 +123,12 @@ impl Bitcoind {
             ledger::Bitcoin::Testnet => {
                 Url::parse("http://localhost:18332").expect("static string to be a valid url")
             }
+            ledger::Bitcoin::Testnet4 => {
+                Url::parse("http://localhost:48332").expect("static string to be a valid url")
+            }
+            ledger::Bitcoin::Signet => {
+                Url::parse("http://localhost:38332").expect("static string to be a valid url")
+            }
             ledger::Bitcoin::Regtest => {
                 Url::parse("http://localhost:18443").expect("static string to be a valid url")
             }
@@ -100,6 +144,10 @@ impl From<Bitcoin> for file::Bitcoin {
             network: bitcoin.network,
             bitcoind: Some(bitcoin.bitcoind),
             fees: Some(bitcoin.fees.into()),
+            additional_networks: bitcoin.additional_networks,
+            watch_only_descriptors: bitcoin.watch_only_descriptors,
+            change_policy: Some(bitcoin.change_policy.into()),
+            wallet_passphrase: bitcoin.wallet_passphrase,
         }
     }
 }
@@ -203,6 +251,9 @@ pub struct Maker {
     /// is 5.2% spread
     pub spread: Spread,
     pub kraken_api_host: KrakenApiHost,
+    /// Token contracts takers may propose herc20 swaps for, any token is
+    /// accepted if empty
+    pub token_allowlist: Vec<ethereum::Address>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -234,6 +285,8 @@ pub enum BitcoinFees {
     BitcoindEstimateSmartfee {
         mode: EstimateMode,
         max_sat_per_vbyte: bitcoin::Amount,
+        /// Used if bitcoind estimates a lower rate or cannot estimate one.
+        min_sat_per_vbyte: bitcoin::Amount,
     },
 }
 
@@ -260,6 +313,10 @@ static DEFAULT_MAX_BITCOIN_FEE_SAT_PER_VBYTE: Lazy<bitcoin::Amount> =
 // https://txstats.com/dashboard/db/fee-estimation?orgId=1&panelId=5&fullscreen&from=now-1y&to=now
     Lazy::new(|| bitcoin::Amount::from_sat(200));
 
+static DEFAULT_MIN_BITCOIN_FEE_SAT_PER_VBYTE: Lazy<bitcoin::Amount> =
+    // Minimum relay fee rate of bitcoind
+    Lazy::new(|| bitcoin::Amount::from_sat(1));
+
 #[cfg(test)]
 impl crate::StaticStub for BitcoinFees {
     fn static_stub() -> Self {
@@ -280,6 +337,9 @@ impl From<file::BitcoinFees> for BitcoinFees {
                 file::BitcoinFeeStrategy::Bitcoind => Self::BitcoindEstimateSmartfee {
                     mode: file.estimate_mode.unwrap_or_else(EstimateMode::default),
                     max_sat_per_vbyte: Default::default(),
+                    min_sat_per_vbyte: file
+                        .min_sat_per_vbyte
+                        .unwrap_or(*DEFAULT_MIN_BITCOIN_FEE_SAT_PER_VBYTE),
                 },
             })
     }
@@ -290,6 +350,7 @@ impl Default for BitcoinFees {
         Self::BitcoindEstimateSmartfee {
             mode: EstimateMode::Economical,
             max_sat_per_vbyte: *DEFAULT_MAX_BITCOIN_FEE_SAT_PER_VBYTE,
+            min_sat_per_vbyte: *DEFAULT_MIN_BITCOIN_FEE_SAT_PER_VBYTE,
         }
     }
 }
@@ -302,20 +363,43 @@ impl From<BitcoinFees> for file::BitcoinFees {
                 sat_per_vbyte: Some(fee),
                 estimate_mode: None,
                 max_sat_per_vbyte: None,
+                min_sat_per_vbyte: None,
             },
             BitcoinFees::BitcoindEstimateSmartfee {
                 mode,
                 max_sat_per_vbyte,
+                min_sat_per_vbyte,
             } => Self {
                 strategy: Some(file::BitcoinFeeStrategy::Bitcoind),
                 sat_per_vbyte: None,
                 estimate_mode: Some(mode),
                 max_sat_per_vbyte: Some(max_sat_per_vbyte),
+                min_sat_per_vbyte: Some(min_sat_per_vbyte),
             },
         }
     }
 }
 
+impl From<file::ChangePolicy> for bitcoin::ChangePolicy {
+    fn from(file: file::ChangePolicy) -> Self {
+        let default = bitcoin::ChangePolicy::default();
+
+        bitcoin::ChangePolicy {
+            discard_below: file.discard_below_sat.unwrap_or(default.discard_below),
+            address_type: file.address_type.unwrap_or(default.address_type),
+        }
+    }
+}
+
+impl From<bitcoin::ChangePolicy> for file::ChangePolicy {
+    fn from(settings: bitcoin::ChangePolicy) -> Self {
+        Self {
+            discard_below_sat: Some(settings.discard_below),
+            address_type: Some(settings.address_type),
+        }
+    }
+}
+
 static DEFAULT_ETH_GAS_STATION_URL: Lazy<url::Url> = Lazy::new(|| {
     "https://ethgasstation.info/api/ethgasAPI.json"
         .parse()
@@ -368,6 +452,7 @@ impl Maker {
             kraken_api_host: file
                 .kraken_api_host
                 .map_or_else(KrakenApiHost::default, KrakenApiHost),
+            token_allowlist: file.token_allowlist.unwrap_or_default(),
         }
     }
 }
@@ -378,6 +463,7 @@ impl Default for Maker {
             btc_dai: BtcDai::default(),
             spread: Spread::new(500).expect("500 is a valid spread value"),
             kraken_api_host: KrakenApiHost::default(),
+            token_allowlist: vec![],
         }
     }
 }
@@ -398,11 +484,46 @@ impl From<Sentry> for file::Sentry {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, derivative::Derivative)]
+#[derive(Clone, Debug, PartialEq, derivative::Derivative)]
 #[derivative(Default)]
 pub struct Logging {
     #[derivative(Default(value = "LevelFilter::Info"))]
     pub level: LevelFilter,
+    pub file: Option<LogFile>,
+}
+
+impl From<file::Logging> for Logging {
+    fn from(logging: file::Logging) -> Self {
+        Logging {
+            level: logging.level.map_or(LevelFilter::Info, LevelFilter::from),
+            file: logging.file.map(LogFile::from),
+        }
+    }
+}
+
+/// Logs are additionally written to files in `dir`, rotated daily.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogFile {
+    pub dir: PathBuf,
+    pub prefix: String,
+}
+
+impl From<file::LogFile> for LogFile {
+    fn from(file: file::LogFile) -> Self {
+        LogFile {
+            dir: file.dir,
+            prefix: file.prefix.unwrap_or_else(|| String::from("nectar.log")),
+        }
+    }
+}
+
+impl From<LogFile> for file::LogFile {
+    fn from(log_file: LogFile) -> Self {
+        file::LogFile {
+            dir: log_file.dir,
+            prefix: Some(log_file.prefix),
+        }
+    }
 }
 
 impl From<Settings> for File {
@@ -411,7 +532,10 @@ impl From<Settings> for File {
             maker,
             network,
             data,
-            logging: Logging { level },
+            logging: Logging {
+                level,
+                file: log_file,
+            },
             bitcoin,
             ethereum,
             sentry,
@@ -423,6 +547,7 @@ impl From<Settings> for File {
             data: Some(data),
             logging: Some(file::Logging {
                 level: Some(level.into()),
+                file: log_file.map(file::LogFile::from),
             }),
             bitcoin: Some(bitcoin.into()),
             ethereum: Some(ethereum.into()),
@@ -443,6 +568,11 @@ impl From<Maker> for file::Maker {
             },
             spread: Some(maker.spread),
             kraken_api_host: Some(maker.kraken_api_host.0),
+            token_allowlist: if maker.token_allowlist.is_empty() {
+                None
+            } else {
+                Some(maker.token_allowlist)
+            },
         }
     }
 }
@@ -478,19 +608,10 @@ impl Settings {
                     crate::fs::data_dir().context("unable to determine default data path")?;
                 data.unwrap_or(Data {
                     dir: default_data_dir,
+                    encrypt: false,
                 })
             },
-            logging: {
-                match logging {
-                    None => Logging::default(),
-                    Some(inner) => match inner {
-                        file::Logging { level: None } => Logging::default(),
-                        file::Logging { level: Some(level) } => Logging {
-                            level: level.into(),
-                        },
-                    },
-                }
-            },
+            logging: logging.map_or_else(Logging::default, Logging::from),
             bitcoin: bitcoin.map_or_else(
                 || {
                     Ok(Bitcoin::default_from_network(
@@ -529,6 +650,34 @@ mod tests {
             .map(|settings| &settings.logging)
             .is_equal_to(Logging {
                 level: LevelFilter::Info,
+                file: None,
+            })
+    }
+
+    #[test]
+    fn log_file_prefix_defaults_to_nectar() {
+        let config_file = File {
+            logging: Some(file::Logging {
+                level: None,
+                file: Some(file::LogFile {
+                    dir: PathBuf::from("/var/log/nectar"),
+                    prefix: None,
+                }),
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file, None);
+
+        assert_that(&settings)
+            .is_ok()
+            .map(|settings| &settings.logging)
+            .is_equal_to(Logging {
+                level: LevelFilter::Info,
+                file: Some(LogFile {
+                    dir: PathBuf::from("/var/log/nectar"),
+                    prefix: String::from("nectar.log"),
+                }),
             })
     }
 
@@ -566,7 +715,12 @@ mod tests {
                 fees: BitcoinFees::BitcoindEstimateSmartfee {
                     mode: EstimateMode::Economical,
                     max_sat_per_vbyte: bitcoin::Amount::from_sat(200),
+                    min_sat_per_vbyte: bitcoin::Amount::from_sat(1),
                 },
+                additional_networks: Vec::new(),
+                watch_only_descriptors: Vec::new(),
+                change_policy: bitcoin::ChangePolicy::default(),
+                wallet_passphrase: None,
             })
     }
 
@@ -575,6 +729,8 @@ mod tests {
         let defaults = vec![
             (ledger::Bitcoin::Mainnet, "http://localhost:8332"),
             (ledger::Bitcoin::Testnet, "http://localhost:18332"),
+            (ledger::Bitcoin::Testnet4, "http://localhost:48332"),
+            (ledger::Bitcoin::Signet, "http://localhost:38332"),
             (ledger::Bitcoin::Regtest, "http://localhost:18443"),
         ];
 
@@ -584,6 +740,10 @@ mod tests {
                     network,
                     bitcoind: None,
                     fees: None,
+                    additional_networks: vec![],
+                    watch_only_descriptors: vec![],
+                    change_policy: None,
+                    wallet_passphrase: None,
                 }),
                 ..File::default()
             };
@@ -599,10 +759,45 @@ mod tests {
                         node_url: url.parse().unwrap(),
                     },
                     fees: Default::default(),
+                    additional_networks: Vec::new(),
+                    watch_only_descriptors: Vec::new(),
+                    change_policy: bitcoin::ChangePolicy::default(),
+                    wallet_passphrase: None,
                 })
         }
     }
 
+    #[test]
+    fn bitcoin_network_configured_twice_is_rejected() {
+        let additional = |network| AdditionalBitcoinNetwork {
+            network,
+            node_url: "http://localhost:38332".parse().unwrap(),
+        };
+        let config_with = |additional_networks| File {
+            bitcoin: Some(file::Bitcoin {
+                network: ledger::Bitcoin::Mainnet,
+                bitcoind: None,
+                fees: None,
+                additional_networks,
+                watch_only_descriptors: vec![],
+                change_policy: None,
+                wallet_passphrase: None,
+            }),
+            ..File::default()
+        };
+
+        let main_network_again = config_with(vec![additional(ledger::Bitcoin::Mainnet)]);
+        let signet_twice = config_with(vec![
+            additional(ledger::Bitcoin::Signet),
+            additional(ledger::Bitcoin::Signet),
+        ]);
+        let signet_once = config_with(vec![additional(ledger::Bitcoin::Signet)]);
+
+        assert!(Settings::from_config_file_and_defaults(main_network_again, None).is_err());
+        assert!(Settings::from_config_file_and_defaults(signet_twice, None).is_err());
+        assert!(Settings::from_config_file_and_defaults(signet_once, None).is_ok());
+    }
+
     #[test]
     fn ethereum_defaults() {
         let config_file = File { ..File::default() };
@@ -4,20 +4,44 @@ use self::{
 };
 #[cfg(test)]
 use crate::StaticStub;
-use crate::{network, network::ActivePeer, swap, swap::SwapKind, SwapId};
+use crate::{config, network, network::ActivePeer, swap, swap::SwapKind, Seed, SwapId};
 use anyhow::{anyhow, Context};
 use conquer_once::Lazy;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashSet, iter::FromIterator};
+use sled::{
+    transaction::{ConflictableTransactionError, TransactionError},
+    Transactional,
+};
+use std::{
+    collections::HashSet,
+    convert::TryFrom,
+    io::{Read, Write},
+    iter::FromIterator,
+};
 use time::OffsetDateTime;
 
+mod checkpoint;
+mod encryption;
 mod hbit;
 mod herc20;
 
+pub use checkpoint::SwapCheckpoints;
+pub use encryption::DecryptionFailed;
+pub use hbit::Beta;
+pub use herc20::Herc20EventKind;
+
 static ACTIVE_PEER_KEY: Lazy<Vec<u8>> =
     Lazy::new(|| serialize(&"active_peer").expect("this slice can be serialized"));
 static BITCOIN_TRANSIENT_KEYS_INDEX_KEY: Lazy<Vec<u8>> =
     Lazy::new(|| serialize(&"bitcoin_transient_key_index").expect("this slice can be serialized"));
+/// Present once the secrets revealed before the tree of revealed secrets
+/// existed were added to it.
+static REVEALED_SECRETS_INDEXED_KEY: Lazy<Vec<u8>> =
+    Lazy::new(|| serialize(&"revealed_secrets_indexed").expect("this slice can be serialized"));
+
+/// The secret of every active swap for which a redeem event was saved, keyed
+/// by swap id.
+const REVEALED_SECRETS_TREE: &str = "revealed_secrets";
 
 pub trait Load<T>: Send + Sync + 'static {
     fn load(&self, swap_id: SwapId) -> anyhow::Result<Option<T>>;
@@ -31,6 +55,9 @@ pub trait Save<T>: Send + Sync + 'static {
 #[derive(Debug)]
 pub struct Database {
     db: sled::Db,
+    revealed_secrets: sled::Tree,
+    /// Swaps, and their revealed secrets, are stored in plaintext if `None`.
+    cipher: Option<encryption::Cipher>,
     #[cfg(test)]
     tmp_dir: Option<tempfile::TempDir>,
 }
@@ -50,17 +77,126 @@ pub enum Error {
 // We should just try to flush on critical saves.
 impl Database {
     pub fn new(path: &std::path::Path) -> Result<Self, Error> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Opens the database in the data directory, swaps are encrypted if the
+    /// configuration asks for it.
+    pub fn open(data: &config::Data, seed: &Seed) -> Result<Self, Error> {
+        let path = data.dir.join("database");
+
+        if data.encrypt {
+            Self::new_encrypted(&path, seed)
+        } else {
+            Self::new(&path)
+        }
+    }
+
+    /// Opens a database in which swaps are encrypted with a key derived from
+    /// `seed`. Swaps stored in plaintext, before encryption was enabled, are
+    /// encrypted on opening.
+    ///
+    /// Reading swaps that were stored with another seed fails with
+    /// `DecryptionFailed`.
+    pub fn new_encrypted(path: &std::path::Path, seed: &Seed) -> Result<Self, Error> {
+        Self::open_with_cipher(path, Some(encryption::Cipher::new(seed)))
+    }
+
+    fn open_with_cipher(
+        path: &std::path::Path,
+        cipher: Option<encryption::Cipher>,
+    ) -> Result<Self, Error> {
         let path = path
             .to_str()
             .ok_or_else(|| Error::Path(format!("{:?}", path)))?;
 
         let db = Self::new_sled(path)?;
+        let revealed_secrets = db.open_tree(REVEALED_SECRETS_TREE)?;
 
-        Ok(Database {
+        let database = Database {
             db,
+            revealed_secrets,
+            cipher,
             #[cfg(test)]
             tmp_dir: None,
-        })
+        };
+
+        if let Some(cipher) = &database.cipher {
+            database.encrypt_plaintext_values(cipher)?;
+        }
+
+        // Tried again on the next start if it fails, e.g. because some swaps
+        // cannot be decrypted with this cipher.
+        if let Err(e) = database.index_revealed_secrets() {
+            tracing::warn!("failed to index revealed secrets: {:#}", e);
+        }
+
+        Ok(database)
+    }
+
+    /// Adds the secrets of swaps that were redeemed before the tree of
+    /// revealed secrets existed, this only scans all swaps once.
+    fn index_revealed_secrets(&self) -> anyhow::Result<()> {
+        if self.db.contains_key(REVEALED_SECRETS_INDEXED_KEY.clone())? {
+            return Ok(());
+        }
+
+        for (swap_id, swap) in self.all_swaps()? {
+            if let Some(secret) = revealed_secret(&swap) {
+                let key = serialize(&swap_id)?;
+                let value = self.encode_secret(&key, &secret)?;
+                let _ = self.revealed_secrets.insert(key, value)?;
+            }
+        }
+        let _ = self
+            .db
+            .insert(REVEALED_SECRETS_INDEXED_KEY.clone(), serialize(&true)?)?;
+
+        Ok(())
+    }
+
+    /// Encrypts the swaps, and their revealed secrets, that were stored in
+    /// plaintext.
+    ///
+    /// Values that neither decrypt nor deserialize are left as they are,
+    /// reading them reports the error.
+    fn encrypt_plaintext_values(&self, cipher: &encryption::Cipher) -> Result<(), Error> {
+        let mut encrypted = 0;
+
+        for item in self.db.iter() {
+            let (key, value) = item?;
+            // Only swaps are stored under a swap id
+            if deserialize::<SwapId>(&key).is_err()
+                || cipher.decrypt(&value, &key).is_ok()
+                || deserialize::<Swap>(&value).is_err()
+            {
+                continue;
+            }
+
+            let _ = self.db.insert(&key, cipher.encrypt(&value, &key))?;
+            encrypted += 1;
+        }
+
+        for item in self.revealed_secrets.iter() {
+            let (key, value) = item?;
+            if cipher.decrypt(&value, &key).is_ok()
+                || deserialize::<comit::Secret>(&value).is_err()
+            {
+                continue;
+            }
+
+            let _ = self
+                .revealed_secrets
+                .insert(&key, cipher.encrypt(&value, &key))?;
+        }
+
+        if encrypted > 0 {
+            tracing::info!("encrypted {} swaps stored in plaintext", encrypted);
+        }
+
+        let _ = self.db.flush()?;
+
+        Ok(())
     }
 
     fn new_sled(path: &str) -> Result<sled::Db, Error> {
@@ -92,9 +228,13 @@ impl Database {
 
         let index = serialize(&0u32)?;
         let _ = db.insert(BITCOIN_TRANSIENT_KEYS_INDEX_KEY.clone(), index)?;
+        let _ = db.insert(REVEALED_SECRETS_INDEXED_KEY.clone(), serialize(&true)?)?;
+        let revealed_secrets = db.open_tree(REVEALED_SECRETS_TREE)?;
 
         Ok(Database {
             db,
+            revealed_secrets,
+            cipher: None,
             tmp_dir: Some(tmp_dir),
         })
     }
@@ -133,7 +273,9 @@ impl Database {
 
     /// Mark a swap as archived and remove its peer from the "active peers"
     pub async fn archive_swap(&self, swap_id: &SwapId) -> anyhow::Result<()> {
-        let stored_swap = self.get_swap_or_bail(&swap_id)?;
+        let (old_value, stored_swap) = self
+            .get_stored_swap(&swap_id)?
+            .ok_or_else(|| anyhow!("swap does not exists {}", swap_id))?;
 
         if let Some(true) = stored_swap.archived {
             anyhow::bail!("swap is already archived");
@@ -143,15 +285,19 @@ impl Database {
         new_swap.archived = Some(true);
 
         let key = serialize(&swap_id).context("failed to serialize swap id for db storage")?;
-        let old_value = serialize(&stored_swap)
-            .context("failed to serialize stored swap value for db update")?;
-        let new_value =
-            serialize(&new_swap).context("failed to serialize new swap value for db storage")?;
+        let new_value = self
+            .encode_swap(&key, &new_swap)
+            .context("failed to serialize new swap value for db storage")?;
 
         self.db
-            .compare_and_swap(key, Some(old_value), Some(new_value))
+            .compare_and_swap(key.clone(), Some(old_value), Some(new_value))
             .context("failed to write in the DB")?
             .context("failed to save in the DB, stored swap somehow changed")?;
+        let _ = self
+            .revealed_secrets
+            .remove(key)
+            .context("failed to remove revealed secret of archived swap")?;
+        self.remove_checkpoints(*swap_id)?;
 
         let peer = stored_swap.active_peer;
 
@@ -170,12 +316,18 @@ impl Database {
             .filter_map(|item| match item {
                 Ok((key, value)) => {
                     let swap_id = deserialize::<SwapId>(&key);
-                    let swap = deserialize::<Swap>(&value).context("failed to deserialize swap");
+                    let value = self.decrypt(&key, &value);
+                    let swap = value
+                        .as_ref()
+                        .map_err(|e| anyhow!("{}", e))
+                        .and_then(|value| {
+                            deserialize::<Swap>(value).context("failed to deserialize swap")
+                        });
                     match (swap_id, swap) {
                         (Ok(_), Ok(swap)) => match serialize(&swap) {
                             Ok(value_current_format) => {
                                 let value_current_format: &[u8] = value_current_format.as_ref();
-                                if value != value_current_format {
+                                if value.as_deref().ok() != Some(value_current_format) {
                                     Some(Ok(()))
                                 } else {
                                     None
@@ -203,11 +355,10 @@ impl Database {
             .map(|item| match item {
                 Ok((key, old_value)) => {
                     let swap_id = deserialize::<SwapId>(&key);
-                    let swap =
-                        deserialize::<Swap>(&old_value).context("failed to deserialize swap");
+                    let swap = self.decode_swap(&key, &old_value);
 
                     match (swap_id, swap) {
-                        (Ok(_), Ok(swap)) => match serialize(&swap) {
+                        (Ok(_), Ok(swap)) => match self.encode_swap(&key, &swap) {
                             Ok(new_value) => self
                                 .db
                                 .compare_and_swap(key, Some(old_value), Some(new_value))
@@ -241,7 +392,9 @@ impl Database {
                 let key = serialize(&swap_id)?;
 
                 let swap: Swap = swap.into();
-                let new_value = serialize(&swap).context("failed to serialize new swap value")?;
+                let new_value = self
+                    .encode_swap(&key, &swap)
+                    .context("failed to serialize new swap value")?;
 
                 self.db
                     .compare_and_swap(key, Option::<Vec<u8>>::None, Some(new_value))
@@ -263,7 +416,7 @@ impl Database {
             .filter_map(|item| match item {
                 Ok((key, value)) => {
                     let swap_id = deserialize::<SwapId>(&key);
-                    let swap = deserialize::<Swap>(&value).context("failed to deserialize swap");
+                    let swap = self.decode_swap(&key, &value);
 
                     match (swap_id, swap) {
                         (Ok(swap_id), Ok(swap)) => Some(Ok((swap, swap_id))),
@@ -283,7 +436,25 @@ impl Database {
                     _,
                 )))
             })
-            .map(|res| res.map(SwapKind::from))
+            .map(|res| res.and_then(SwapKind::try_from))
+            .collect()
+    }
+
+    /// Returns the secret of every active swap for which a redeem event was
+    /// saved, on either ledger.
+    ///
+    /// The secret is recorded in the same transaction as the redeem event,
+    /// hence every secret returned here has been persisted.
+    pub fn swaps_with_revealed_secret(&self) -> anyhow::Result<Vec<(SwapId, comit::Secret)>> {
+        self.revealed_secrets
+            .iter()
+            .map(|item| {
+                let (key, value) = item.context("failed to retrieve revealed secrets from DB")?;
+                let swap_id = deserialize::<SwapId>(&key)?;
+                let secret = self.decode_secret(&key, &value)?;
+
+                Ok((swap_id, secret))
+            })
             .collect()
     }
 
@@ -291,9 +462,14 @@ impl Database {
         let key = serialize(swap_id)?;
 
         self.db
-            .remove(key)
+            .remove(key.clone())
             .with_context(|| format!("failed to delete swap {}", swap_id))
             .map(|_| ())?;
+        let _ = self
+            .revealed_secrets
+            .remove(key)
+            .with_context(|| format!("failed to delete revealed secret of swap {}", swap_id))?;
+        self.remove_checkpoints(*swap_id)?;
 
         self.db
             .flush_async()
@@ -306,13 +482,16 @@ impl Database {
     where
         U: FnOnce(Swap) -> anyhow::Result<Swap>,
     {
-        let stored_swap = self.get_swap_or_bail(&swap_id)?;
-        let old_value = serialize(&stored_swap).context("Could not serialize old swap value")?;
+        let (old_value, stored_swap) = self
+            .get_stored_swap(&swap_id)?
+            .ok_or_else(|| anyhow!("swap does not exists {}", swap_id))?;
 
         let new_swap = update_fn(stored_swap)?;
 
         let key = serialize(&swap_id)?;
-        let new_value = serialize(&new_swap).context("Could not serialize new swap value")?;
+        let new_value = self
+            .encode_swap(&key, &new_swap)
+            .context("Could not serialize new swap value")?;
 
         self.db
             .compare_and_swap(key, Some(old_value), Some(new_value))
@@ -326,6 +505,55 @@ impl Database {
             .context("Could not flush db")
     }
 
+    /// Like `update_swap`, the redeem event saved by `update_fn` reveals
+    /// `secret` which is recorded in the same transaction.
+    async fn update_swap_revealing_secret<U>(
+        &self,
+        swap_id: &SwapId,
+        secret: comit::Secret,
+        update_fn: U,
+    ) -> anyhow::Result<()>
+    where
+        U: FnOnce(Swap) -> anyhow::Result<Swap>,
+    {
+        let (old_value, stored_swap) = self
+            .get_stored_swap(&swap_id)?
+            .ok_or_else(|| anyhow!("swap does not exists {}", swap_id))?;
+
+        let new_swap = update_fn(stored_swap)?;
+
+        let key = serialize(&swap_id)?;
+        let new_value = self
+            .encode_swap(&key, &new_swap)
+            .context("Could not serialize new swap value")?;
+        let secret_value = self
+            .encode_secret(&key, &secret)
+            .context("Could not serialize revealed secret")?;
+
+        (&*self.db, &self.revealed_secrets)
+            .transaction(|(swaps, revealed_secrets)| {
+                if swaps.get(&key)?.as_ref() != Some(&old_value) {
+                    return Err(ConflictableTransactionError::Abort(StoredSwapChanged));
+                }
+                let _ = swaps.insert(key.as_slice(), new_value.as_slice())?;
+                let _ = revealed_secrets.insert(key.as_slice(), secret_value.as_slice())?;
+
+                Ok(())
+            })
+            .map_err(|e| match e {
+                TransactionError::Abort(e) => anyhow::Error::from(e),
+                TransactionError::Storage(e) => {
+                    anyhow::Error::from(e).context("Could not write in the DB")
+                }
+            })?;
+
+        self.db
+            .flush_async()
+            .await
+            .map(|_| ())
+            .context("Could not flush db")
+    }
+
     fn get_swap_or_bail(&self, swap_id: &SwapId) -> anyhow::Result<Swap> {
         let swap = self
             .get_swap(swap_id)?
@@ -335,21 +563,71 @@ impl Database {
     }
 
     fn get_swap(&self, swap_id: &SwapId) -> anyhow::Result<Option<Swap>> {
+        let swap = self.get_stored_swap(swap_id)?.map(|(_, swap)| swap);
+
+        Ok(swap)
+    }
+
+    /// Returns the swap together with its value as stored in the database.
+    fn get_stored_swap(&self, swap_id: &SwapId) -> anyhow::Result<Option<(sled::IVec, Swap)>> {
         let key = serialize(swap_id)?;
 
-        let swap = match self.db.get(&key)? {
-            Some(data) => deserialize(&data).context("failed to deserialize swap")?,
+        let value = match self.db.get(&key)? {
+            Some(value) => value,
             None => return Ok(None),
         };
+        let swap = self.decode_swap(&key, &value)?;
+
+        Ok(Some((value, swap)))
+    }
+
+    /// Encrypted swaps are bound to the `key` they are stored under, a value
+    /// copied to another swap id does not decrypt.
+    fn encode_swap(&self, key: &[u8], swap: &Swap) -> anyhow::Result<Vec<u8>> {
+        let plaintext = serialize(swap)?;
+
+        match &self.cipher {
+            Some(cipher) => Ok(cipher.encrypt(&plaintext, key)),
+            None => Ok(plaintext),
+        }
+    }
 
-        Ok(Some(swap))
+    fn decode_swap(&self, key: &[u8], value: &[u8]) -> anyhow::Result<Swap> {
+        let plaintext = self.decrypt(key, value)?;
+
+        deserialize(&plaintext).context("failed to deserialize swap")
+    }
+
+    /// Revealed secrets are encrypted like the swap they belong to.
+    fn encode_secret(&self, key: &[u8], secret: &comit::Secret) -> anyhow::Result<Vec<u8>> {
+        let plaintext = serialize(secret)?;
+
+        match &self.cipher {
+            Some(cipher) => Ok(cipher.encrypt(&plaintext, key)),
+            None => Ok(plaintext),
+        }
+    }
+
+    fn decode_secret(&self, key: &[u8], value: &[u8]) -> anyhow::Result<comit::Secret> {
+        let plaintext = self.decrypt(key, value)?;
+
+        deserialize(&plaintext).context("failed to deserialize revealed secret")
+    }
+
+    fn decrypt(&self, key: &[u8], value: &[u8]) -> Result<Vec<u8>, DecryptionFailed> {
+        match &self.cipher {
+            Some(cipher) => cipher.decrypt(value, key),
+            None => Ok(value.to_vec()),
+        }
     }
 }
 
 impl Load<SwapKind> for Database {
     fn load(&self, swap_id: SwapId) -> anyhow::Result<Option<SwapKind>> {
         let swap = self.get_swap(&swap_id)?;
-        let swap_kind = swap.map(|swap| SwapKind::from((swap, swap_id)));
+        let swap_kind = swap
+            .map(|swap| SwapKind::try_from((swap, swap_id)))
+            .transpose()?;
 
         Ok(swap_kind)
     }
@@ -412,6 +690,203 @@ impl Database {
     }
 }
 
+/// The version of the document written by `Database::export`.
+const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("unsupported export version {0}, expected version {}", EXPORT_VERSION)]
+pub struct UnsupportedExportVersion(pub u32);
+
+#[derive(Debug, Deserialize)]
+struct ExportVersion {
+    version: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Export {
+    version: u32,
+    bitcoin_transient_key_index: u32,
+    active_peers: Vec<ActivePeer>,
+    swaps: Vec<ExportedSwap>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportedSwap {
+    id: SwapId,
+    swap: Swap,
+}
+
+/// These methods are used to move the database to another host.
+impl Database {
+    /// Writes all swaps, including archived ones and their events, as JSON
+    /// document.
+    pub fn export(&self, writer: impl Write) -> anyhow::Result<()> {
+        let index = self
+            .db
+            .get(BITCOIN_TRANSIENT_KEYS_INDEX_KEY.clone())?
+            .context("no bitcoin transient keys index in db")?;
+
+        let export = Export {
+            version: EXPORT_VERSION,
+            bitcoin_transient_key_index: deserialize(&index)?,
+            active_peers: self.peers()?.into_iter().collect(),
+            swaps: self
+                .all_swaps()?
+                .into_iter()
+                .map(|(id, swap)| ExportedSwap { id, swap })
+                .collect(),
+        };
+
+        serde_json::to_writer_pretty(writer, &export).context("failed to write export")
+    }
+
+    /// Loads a document written by `export` into this database, which must
+    /// not contain any of the exported swaps yet.
+    ///
+    /// The document is written in a single transaction, either all of it is
+    /// imported or nothing. The bitcoin transient key index is only ever
+    /// raised, keys derived by this database are never handed out again.
+    pub async fn import(&self, mut reader: impl Read) -> anyhow::Result<()> {
+        let mut document = String::new();
+        reader
+            .read_to_string(&mut document)
+            .context("failed to read export")?;
+
+        let ExportVersion { version } =
+            serde_json::from_str(&document).context("failed to read export version")?;
+        if version != EXPORT_VERSION {
+            return Err(UnsupportedExportVersion(version).into());
+        }
+
+        let export: Export = serde_json::from_str(&document).context("failed to parse export")?;
+
+        let swaps = export
+            .swaps
+            .iter()
+            .map(|ExportedSwap { id, swap }| {
+                let key = serialize(id)?;
+                let value = self
+                    .encode_swap(&key, swap)
+                    .context("failed to serialize swap")?;
+                let secret = revealed_secret(swap)
+                    .map(|secret| self.encode_secret(&key, &secret))
+                    .transpose()?;
+
+                Ok((id, key, value, secret))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        (&*self.db, &self.revealed_secrets)
+            .transaction(|(db, revealed_secrets)| {
+                for (id, key, value, secret) in &swaps {
+                    if db.get(key.as_slice())?.is_some() {
+                        return Err(ConflictableTransactionError::Abort(anyhow!(
+                            "swap {} is already stored",
+                            id
+                        )));
+                    }
+                    let _ = db.insert(key.as_slice(), value.as_slice())?;
+
+                    if let Some(secret) = secret {
+                        let _ = revealed_secrets.insert(key.as_slice(), secret.as_slice())?;
+                    }
+                }
+
+                let peers = db
+                    .get(ACTIVE_PEER_KEY.as_slice())?
+                    .ok_or_else(|| anyhow!("no key \"active_peer\" in db"))
+                    .and_then(|peers| {
+                        deserialize::<Vec<ActivePeer>>(&peers).map_err(anyhow::Error::from)
+                    })
+                    .map_err(ConflictableTransactionError::Abort)?;
+                let mut peers = HashSet::<ActivePeer>::from_iter(peers);
+                peers.extend(export.active_peers.iter().cloned());
+                let peers = serialize(&Vec::from_iter(peers))
+                    .map_err(|e| ConflictableTransactionError::Abort(e.into()))?;
+                let _ = db.insert(ACTIVE_PEER_KEY.as_slice(), peers)?;
+
+                let index = db
+                    .get(BITCOIN_TRANSIENT_KEYS_INDEX_KEY.as_slice())?
+                    .ok_or_else(|| anyhow!("no bitcoin transient keys index in db"))
+                    .and_then(|index| deserialize::<u32>(&index).map_err(anyhow::Error::from))
+                    .map_err(ConflictableTransactionError::Abort)?;
+                let index = std::cmp::max(index, export.bitcoin_transient_key_index);
+                let index =
+                    serialize(&index).map_err(|e| ConflictableTransactionError::Abort(e.into()))?;
+                let _ = db.insert(BITCOIN_TRANSIENT_KEYS_INDEX_KEY.as_slice(), index)?;
+
+                Ok(())
+            })
+            .map_err(|e| match e {
+                TransactionError::Abort(e) => e,
+                TransactionError::Storage(e) => {
+                    anyhow::Error::from(e).context("failed to write in the DB")
+                }
+            })?;
+
+        self.db
+            .flush_async()
+            .await
+            .map(|_| ())
+            .context("failed to flush db")
+    }
+
+    fn all_swaps(&self) -> anyhow::Result<Vec<(SwapId, Swap)>> {
+        self.db
+            .iter()
+            .filter_map(|item| match item {
+                Ok((key, value)) => {
+                    let swap_id = deserialize::<SwapId>(&key);
+                    let swap = self.decode_swap(&key, &value);
+
+                    match (swap_id, swap) {
+                        (Ok(swap_id), Ok(swap)) => Some(Ok((swap_id, swap))),
+                        (Ok(_), Err(err)) => Some(Err(err)), // If the swap id deserialize, then
+                        // it should be a swap
+                        (..) => None, // This is not a swap item
+                    }
+                }
+                Err(err) => Some(Err(err).context("failed to retrieve swaps from DB")),
+            })
+            .collect()
+    }
+}
+
+/// Stores `event` unless an event is already stored, in which case saving
+/// only succeeds if it is the same event.
+///
+/// Resuming a swap may observe an event a second time, this must not fail.
+fn save_event<T>(stored: &mut Option<T>, event: T, name: &str) -> anyhow::Result<()>
+where
+    T: PartialEq,
+{
+    match stored {
+        Some(stored) if *stored == event => Ok(()),
+        Some(_) => anyhow::bail!("{} event is already stored with a different value", name),
+        None => {
+            *stored = Some(event);
+            Ok(())
+        }
+    }
+}
+
+/// The secret revealed by a redeem event of an active swap, on either ledger.
+fn revealed_secret(swap: &Swap) -> Option<comit::Secret> {
+    if swap.archived == Some(true) {
+        return None;
+    }
+
+    let herc20_secret = swap.herc20_redeemed.map(|redeemed| redeemed.secret);
+    let hbit_secret = swap.hbit_redeemed.map(|redeemed| redeemed.secret);
+    let hbit_beta_secret = swap.hbit_beta_redeemed.map(|redeemed| redeemed.secret);
+
+    herc20_secret.or(hbit_secret).or(hbit_beta_secret)
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("stored swap somehow changed, aborting saving")]
+struct StoredSwapChanged;
+
 pub fn serialize<T>(t: &T) -> Result<Vec<u8>, serde_cbor::Error>
 where
     T: Serialize,
@@ -429,8 +904,13 @@ where
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct Swap {
     pub kind: Kind,
+    /// The alpha params of a hbit<->hbit swap.
     pub hbit_params: hbit::Params,
-    pub herc20_params: herc20::Params,
+    /// Only `None` for hbit<->hbit swaps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub herc20_params: Option<herc20::Params>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hbit_beta_params: Option<hbit::Params>,
     pub secret_hash: comit::SecretHash,
     pub utc_start_of_swap: OffsetDateTime,
     pub active_peer: network::ActivePeer,
@@ -441,6 +921,12 @@ struct Swap {
     pub herc20_funded: Option<Herc20Funded>,
     pub herc20_redeemed: Option<Herc20Redeemed>,
     pub herc20_refunded: Option<Herc20Refunded>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hbit_beta_funded: Option<HbitFunded>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hbit_beta_redeemed: Option<HbitRedeemed>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hbit_beta_refunded: Option<HbitRefunded>,
     pub archived: Option<bool>,
 }
 
@@ -448,6 +934,7 @@ struct Swap {
 enum Kind {
     HbitHerc20,
     Herc20Hbit,
+    HbitHbit,
 }
 
 #[cfg(test)]
@@ -458,7 +945,8 @@ impl StaticStub for Swap {
         Swap {
             kind: Kind::HbitHerc20,
             hbit_params: StaticStub::static_stub(),
-            herc20_params: StaticStub::static_stub(),
+            herc20_params: Some(StaticStub::static_stub()),
+            hbit_beta_params: None,
             secret_hash: comit::SecretHash::new(
                 comit::Secret::from_str(
                     "aa68d627971643a6f97f27c58957826fcba853ec2077fd10ec6b93d8e61deb4c",
@@ -474,55 +962,99 @@ impl StaticStub for Swap {
             herc20_funded: None,
             herc20_redeemed: None,
             herc20_refunded: None,
+            hbit_beta_funded: None,
+            hbit_beta_redeemed: None,
+            hbit_beta_refunded: None,
             archived: None,
         }
     }
 }
 
-impl From<(Swap, SwapId)> for SwapKind {
-    fn from(swap_data: (Swap, SwapId)) -> Self {
+impl TryFrom<(Swap, SwapId)> for SwapKind {
+    type Error = anyhow::Error;
+
+    fn try_from(swap_data: (Swap, SwapId)) -> anyhow::Result<Self> {
         let (swap, swap_id) = swap_data;
 
         let Swap {
             kind,
             hbit_params,
             herc20_params,
+            hbit_beta_params,
             secret_hash,
             utc_start_of_swap: start_of_swap,
             active_peer: taker,
             ..
         } = swap;
 
-        let swap = swap::SwapParams {
-            hbit_params: hbit_params.into(),
-            herc20_params: herc20_params.into(),
-            secret_hash,
-            start_of_swap,
-            swap_id,
-            taker,
-        };
-
         match kind {
-            Kind::HbitHerc20 => SwapKind::HbitHerc20(swap),
-            Kind::Herc20Hbit => SwapKind::Herc20Hbit(swap),
+            Kind::HbitHerc20 | Kind::Herc20Hbit => {
+                let herc20_params = herc20_params
+                    .with_context(|| format!("missing herc20 params of swap {}", swap_id))?;
+                let swap = swap::SwapParams {
+                    hbit_params: hbit_params.into(),
+                    herc20_params: herc20_params.into(),
+                    secret_hash,
+                    start_of_swap,
+                    swap_id,
+                    taker,
+                };
+
+                if let Kind::HbitHerc20 = kind {
+                    Ok(SwapKind::HbitHerc20(swap))
+                } else {
+                    Ok(SwapKind::Herc20Hbit(swap))
+                }
+            }
+            Kind::HbitHbit => {
+                let beta_params = hbit_beta_params.with_context(|| {
+                    format!("missing beta params of hbit<->hbit swap {}", swap_id)
+                })?;
+
+                Ok(SwapKind::HbitHbit(swap::HbitHbitParams {
+                    alpha_params: hbit_params.into(),
+                    beta_params: beta_params.into(),
+                    secret_hash,
+                    start_of_swap,
+                    swap_id,
+                    taker,
+                }))
+            }
         }
     }
 }
 
 impl From<SwapKind> for Swap {
     fn from(swap_kind: SwapKind) -> Self {
-        let (kind, swap) = match swap_kind {
-            SwapKind::HbitHerc20(swap) => (Kind::HbitHerc20, swap),
-            SwapKind::Herc20Hbit(swap) => (Kind::Herc20Hbit, swap),
+        let (kind, hbit_params, herc20_params, hbit_beta_params) = match &swap_kind {
+            SwapKind::HbitHerc20(swap) => (
+                Kind::HbitHerc20,
+                swap.hbit_params.clone(),
+                Some(swap.herc20_params.clone()),
+                None,
+            ),
+            SwapKind::Herc20Hbit(swap) => (
+                Kind::Herc20Hbit,
+                swap.hbit_params.clone(),
+                Some(swap.herc20_params.clone()),
+                None,
+            ),
+            SwapKind::HbitHbit(swap) => (
+                Kind::HbitHbit,
+                swap.alpha_params.clone(),
+                None,
+                Some(swap.beta_params.clone()),
+            ),
         };
 
         Swap {
             kind,
-            hbit_params: swap.hbit_params.into(),
-            herc20_params: swap.herc20_params.into(),
-            secret_hash: swap.secret_hash,
-            utc_start_of_swap: swap.start_of_swap,
-            active_peer: swap.taker,
+            hbit_params: hbit_params.into(),
+            herc20_params: herc20_params.map(Into::into),
+            hbit_beta_params: hbit_beta_params.map(Into::into),
+            secret_hash: swap_kind.secret_hash(),
+            utc_start_of_swap: swap_kind.start_of_swap(),
+            active_peer: swap_kind.taker(),
             hbit_funded: None,
             hbit_redeemed: None,
             hbit_refunded: None,
@@ -530,6 +1062,9 @@ impl From<SwapKind> for Swap {
             herc20_funded: None,
             herc20_redeemed: None,
             herc20_refunded: None,
+            hbit_beta_funded: None,
+            hbit_beta_redeemed: None,
+            hbit_beta_refunded: None,
             archived: None,
         }
     }
@@ -594,6 +1129,136 @@ mod tests {
         matches!(res, Ok(false))
     }
 
+    #[tokio::test]
+    async fn export_and_import_round_trip() {
+        let db = Database::new_test().unwrap();
+        let mut gen = StdThreadGen::new(10);
+        let mut swap_ids = Vec::new();
+
+        for i in 0..3u8 {
+            let swap = SwapKind::arbitrary(&mut gen);
+            let swap_id = swap.swap_id();
+            db.insert_swap(swap).await.unwrap();
+
+            let transaction = comit::ethereum::Hash::from([i; 32]);
+            db.save(
+                swap::herc20::Deployed {
+                    transaction,
+                    location: comit::htlc_location::Ethereum::random(),
+                },
+                swap_id,
+            )
+            .await
+            .unwrap();
+            db.save(swap::herc20::Funded { transaction }, swap_id)
+                .await
+                .unwrap();
+            db.save(
+                swap::herc20::Redeemed {
+                    transaction,
+                    secret: comit::Secret::from([i; 32]),
+                },
+                swap_id,
+            )
+            .await
+            .unwrap();
+
+            swap_ids.push(swap_id);
+        }
+        db.archive_swap(&swap_ids[0]).await.unwrap();
+
+        let mut export = Vec::new();
+        db.export(&mut export).unwrap();
+
+        let imported = Database::new_test().unwrap();
+        imported.import(export.as_slice()).await.unwrap();
+
+        let mut reexport = Vec::new();
+        imported.export(&mut reexport).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&export).unwrap(),
+            serde_json::from_slice::<serde_json::Value>(&reexport).unwrap()
+        );
+        for swap_id in swap_ids {
+            let deployed: Option<swap::herc20::Deployed> = db.load(swap_id).unwrap();
+            let funded: Option<swap::herc20::Funded> = db.load(swap_id).unwrap();
+            let redeemed: Option<swap::herc20::Redeemed> = db.load(swap_id).unwrap();
+
+            assert_eq!(
+                Load::<swap::herc20::Deployed>::load(&imported, swap_id).unwrap(),
+                deployed
+            );
+            assert_eq!(
+                Load::<swap::herc20::Funded>::load(&imported, swap_id)
+                    .unwrap()
+                    .map(|funded| funded.transaction),
+                funded.map(|funded| funded.transaction)
+            );
+            assert_eq!(
+                Load::<swap::herc20::Redeemed>::load(&imported, swap_id).unwrap(),
+                redeemed
+            );
+        }
+        assert_eq!(
+            imported.all_active_swaps().unwrap().len(),
+            db.all_active_swaps().unwrap().len()
+        );
+    }
+
+    #[tokio::test]
+    async fn import_keeps_the_higher_bitcoin_transient_key_index() {
+        let exporting = Database::new_test().unwrap();
+        let _ = exporting
+            .fetch_inc_bitcoin_transient_key_index()
+            .await
+            .unwrap();
+        let mut export = Vec::new();
+        exporting.export(&mut export).unwrap();
+
+        let db = Database::new_test().unwrap();
+        for _ in 0..5 {
+            let _ = db.fetch_inc_bitcoin_transient_key_index().await.unwrap();
+        }
+        db.import(export.as_slice()).await.unwrap();
+
+        assert_eq!(db.fetch_inc_bitcoin_transient_key_index().await.unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn failed_import_leaves_the_database_untouched() {
+        let db = Database::new_test().unwrap();
+        let swap = SwapKind::arbitrary(&mut StdThreadGen::new(10));
+        db.insert_swap(swap.clone()).await.unwrap();
+
+        let exporting = Database::new_test().unwrap();
+        for _ in 0..5 {
+            let _ = exporting
+                .fetch_inc_bitcoin_transient_key_index()
+                .await
+                .unwrap();
+        }
+        exporting.insert_swap(swap).await.unwrap();
+        let mut export = Vec::new();
+        exporting.export(&mut export).unwrap();
+
+        let _ = db.import(export.as_slice()).await.unwrap_err();
+
+        assert_eq!(db.fetch_inc_bitcoin_transient_key_index().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn import_rejects_unknown_version() {
+        let db = Database::new_test().unwrap();
+        let export = serde_json::json!({
+            "version": EXPORT_VERSION + 1,
+            "swaps": []
+        });
+
+        let error = db.import(export.to_string().as_bytes()).await.unwrap_err();
+
+        assert!(error.is::<UnsupportedExportVersion>());
+    }
+
     #[tokio::test]
     async fn save_and_retrieve_hundred_swaps() {
         let size = 100;
@@ -669,7 +1334,7 @@ mod tests {
     async fn peer_is_not_active_for_archived_swap(swap: SwapKind) -> bool {
         let db = Database::new_test().unwrap();
         let swap_id = swap.swap_id();
-        let peer = swap.params().taker;
+        let peer = swap.taker();
 
         db.insert_swap(swap.clone()).await.unwrap();
         db.insert_active_peer(peer.clone()).await.unwrap();
@@ -734,4 +1399,174 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn only_swaps_with_redeem_event_have_revealed_secret() {
+        let db = Database::new_test().unwrap();
+        let mut gen = StdThreadGen::new(10);
+        let redeemed = SwapKind::arbitrary(&mut gen);
+        let not_redeemed = SwapKind::arbitrary(&mut gen);
+        let secret = comit::Secret::from([3u8; 32]);
+
+        db.insert_swap(redeemed.clone()).await.unwrap();
+        db.insert_swap(not_redeemed).await.unwrap();
+        db.save(
+            swap::herc20::Redeemed {
+                transaction: comit::ethereum::Hash::from([1u8; 32]),
+                secret,
+            },
+            redeemed.swap_id(),
+        )
+        .await
+        .unwrap();
+
+        let secrets = db.swaps_with_revealed_secret().unwrap();
+
+        assert_eq!(secrets, vec![(redeemed.swap_id(), secret)]);
+    }
+
+    #[tokio::test]
+    async fn archived_swap_has_no_revealed_secret() {
+        let db = Database::new_test().unwrap();
+        let swap = SwapKind::arbitrary(&mut StdThreadGen::new(10));
+        let swap_id = swap.swap_id();
+
+        db.insert_swap(swap).await.unwrap();
+        db.save(
+            swap::herc20::Redeemed {
+                transaction: comit::ethereum::Hash::from([1u8; 32]),
+                secret: comit::Secret::from([3u8; 32]),
+            },
+            swap_id,
+        )
+        .await
+        .unwrap();
+        db.archive_swap(&swap_id).await.unwrap();
+
+        assert!(db.swaps_with_revealed_secret().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn secrets_revealed_before_the_index_existed_are_indexed_on_open() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path();
+        let seed = Seed::random().unwrap();
+        let swap = SwapKind::arbitrary(&mut StdThreadGen::new(10));
+        let swap_id = swap.swap_id();
+        let secret = comit::Secret::from([3u8; 32]);
+
+        {
+            let db = Database::new_encrypted(path, &seed).unwrap();
+            db.insert_swap(swap).await.unwrap();
+            db.save(
+                swap::herc20::Redeemed {
+                    transaction: comit::ethereum::Hash::from([1u8; 32]),
+                    secret,
+                },
+                swap_id,
+            )
+            .await
+            .unwrap();
+
+            // what a database written by an older version looks like
+            db.revealed_secrets.clear().unwrap();
+            let _ = db.db.remove(REVEALED_SECRETS_INDEXED_KEY.clone()).unwrap();
+            db.db.flush_async().await.unwrap();
+        }
+
+        let db = Database::new_encrypted(path, &seed).unwrap();
+
+        assert_eq!(db.swaps_with_revealed_secret().unwrap(), vec![(
+            swap_id, secret
+        )]);
+    }
+
+    #[tokio::test]
+    async fn encrypted_swap_cannot_be_read_with_another_seed() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path();
+        let seed = Seed::random().unwrap();
+        let swap = SwapKind::arbitrary(&mut StdThreadGen::new(10));
+        let swap_id = swap.swap_id();
+        let redeemed = swap::herc20::Redeemed {
+            transaction: comit::ethereum::Hash::from([1u8; 32]),
+            secret: comit::Secret::from([2u8; 32]),
+        };
+
+        {
+            let db = Database::new_encrypted(path, &seed).unwrap();
+            db.insert_swap(swap.clone()).await.unwrap();
+            db.save(redeemed, swap_id).await.unwrap();
+        }
+
+        {
+            let db = Database::new_encrypted(path, &Seed::random().unwrap()).unwrap();
+
+            let error = Load::<swap::herc20::Redeemed>::load(&db, swap_id).unwrap_err();
+            assert!(error.is::<DecryptionFailed>());
+            assert!(db.all_active_swaps().is_err());
+        }
+
+        {
+            let db = Database::new_encrypted(path, &seed).unwrap();
+
+            let loaded = Load::<swap::herc20::Redeemed>::load(&db, swap_id).unwrap();
+            assert_eq!(loaded, Some(redeemed));
+            assert_eq!(db.all_active_swaps().unwrap(), vec![swap]);
+        }
+    }
+
+    #[tokio::test]
+    async fn plaintext_swaps_are_encrypted_when_enabling_encryption() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path();
+        let seed = Seed::random().unwrap();
+        let swap = SwapKind::arbitrary(&mut StdThreadGen::new(10));
+        let swap_id = swap.swap_id();
+        let secret = comit::Secret::from([2u8; 32]);
+
+        {
+            let db = Database::new(path).unwrap();
+            db.insert_swap(swap.clone()).await.unwrap();
+            db.save(
+                swap::herc20::Redeemed {
+                    transaction: comit::ethereum::Hash::from([1u8; 32]),
+                    secret,
+                },
+                swap_id,
+            )
+            .await
+            .unwrap();
+        }
+
+        {
+            let db = Database::new_encrypted(path, &seed).unwrap();
+
+            assert_eq!(db.all_active_swaps().unwrap(), vec![swap]);
+            assert_eq!(db.swaps_with_revealed_secret().unwrap(), vec![(
+                swap_id, secret
+            )]);
+        }
+
+        let db = Database::new(path).unwrap();
+
+        assert!(db.all_active_swaps().is_err());
+    }
+
+    #[tokio::test]
+    async fn encrypted_swap_moved_to_another_id_cannot_be_read() {
+        let tmp_dir = TempDir::new().unwrap();
+        let db = Database::new_encrypted(tmp_dir.path(), &Seed::random().unwrap()).unwrap();
+        let swap = SwapKind::arbitrary(&mut StdThreadGen::new(10));
+        let other_id = SwapId::default();
+        db.insert_swap(swap.clone()).await.unwrap();
+
+        let value = db.db.get(serialize(&swap.swap_id()).unwrap()).unwrap();
+        db.db
+            .insert(serialize(&other_id).unwrap(), value.unwrap())
+            .unwrap();
+
+        let error = db.get_swap(&other_id).unwrap_err();
+        assert!(error.is::<DecryptionFailed>());
+    }
 }
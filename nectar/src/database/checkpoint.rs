@@ -0,0 +1,238 @@
+use crate::{
+    database::{deserialize, serialize, Beta, Database, Load, Save},
+    swap::{hbit, herc20},
+    SwapId,
+};
+use anyhow::Context;
+use comit::btsieve::Checkpoints;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{marker::PhantomData, sync::Arc};
+
+/// An event we scan the blockchain for, every one of them has its own
+/// checkpoint.
+pub trait Scan: Send + Sync + 'static {
+    const NAME: &'static str;
+
+    type BlockHash: Serialize + DeserializeOwned + Copy + Send + Sync + 'static;
+}
+
+impl Scan for hbit::Funded {
+    const NAME: &'static str = "hbit_funded";
+
+    type BlockHash = ::bitcoin::BlockHash;
+}
+
+impl Scan for hbit::Redeemed {
+    const NAME: &'static str = "hbit_redeemed";
+
+    type BlockHash = ::bitcoin::BlockHash;
+}
+
+impl Scan for Beta<hbit::Funded> {
+    const NAME: &'static str = "hbit_beta_funded";
+
+    type BlockHash = ::bitcoin::BlockHash;
+}
+
+impl Scan for Beta<hbit::Redeemed> {
+    const NAME: &'static str = "hbit_beta_redeemed";
+
+    type BlockHash = ::bitcoin::BlockHash;
+}
+
+impl Scan for herc20::Deployed {
+    const NAME: &'static str = "herc20_deployed";
+
+    type BlockHash = comit::ethereum::Hash;
+}
+
+/// The last block up to which the blockchain was scanned for `E`.
+#[derive(Debug)]
+pub struct Checkpoint<E: Scan>(pub E::BlockHash);
+
+fn key<E: Scan>(swap_id: SwapId) -> anyhow::Result<Vec<u8>> {
+    let key = serialize(&("checkpoint", E::NAME, swap_id))?;
+
+    Ok(key)
+}
+
+#[async_trait::async_trait]
+impl<E: Scan> Save<Checkpoint<E>> for Database {
+    async fn save(&self, checkpoint: Checkpoint<E>, swap_id: SwapId) -> anyhow::Result<()> {
+        let value = serialize(&checkpoint.0).context("failed to serialize checkpoint")?;
+
+        self.db
+            .insert(key::<E>(swap_id)?, value)
+            .context("failed to write in the DB")?;
+
+        self.db
+            .flush_async()
+            .await
+            .map(|_| ())
+            .context("failed to flush db")
+    }
+}
+
+impl<E: Scan> Load<Checkpoint<E>> for Database {
+    fn load(&self, swap_id: SwapId) -> anyhow::Result<Option<Checkpoint<E>>> {
+        let block_hash = match self.db.get(key::<E>(swap_id)?)? {
+            Some(data) => deserialize(&data).context("failed to deserialize checkpoint")?,
+            None => return Ok(None),
+        };
+
+        Ok(Some(Checkpoint(block_hash)))
+    }
+}
+
+impl Database {
+    /// Deletes the checkpoints of every scan of a swap, they are of no use
+    /// once the swap is not executed anymore.
+    pub(super) fn remove_checkpoints(&self, swap_id: SwapId) -> anyhow::Result<()> {
+        let keys = [
+            key::<hbit::Funded>(swap_id)?,
+            key::<hbit::Redeemed>(swap_id)?,
+            key::<Beta<hbit::Funded>>(swap_id)?,
+            key::<Beta<hbit::Redeemed>>(swap_id)?,
+            key::<herc20::Deployed>(swap_id)?,
+        ];
+
+        for key in keys.iter() {
+            let _ = self
+                .db
+                .remove(key)
+                .with_context(|| format!("failed to delete checkpoint of swap {}", swap_id))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Persists how far the blockchain was scanned for `E` in the context of a
+/// swap, so that scanning resumes from there after a restart.
+#[derive(Debug)]
+pub struct SwapCheckpoints<E> {
+    db: Arc<Database>,
+    swap_id: SwapId,
+    scan: PhantomData<E>,
+}
+
+impl<E> SwapCheckpoints<E> {
+    pub fn new(db: Arc<Database>, swap_id: SwapId) -> Self {
+        Self {
+            db,
+            swap_id,
+            scan: PhantomData,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<E: Scan> Checkpoints<E::BlockHash> for SwapCheckpoints<E> {
+    async fn load_checkpoint(&self) -> anyhow::Result<Option<E::BlockHash>> {
+        let checkpoint: Option<Checkpoint<E>> = self.db.load(self.swap_id)?;
+
+        Ok(checkpoint.map(|checkpoint| checkpoint.0))
+    }
+
+    async fn save_checkpoint(&self, block_hash: E::BlockHash) -> anyhow::Result<()> {
+        self.db
+            .save(Checkpoint::<E>(block_hash), self.swap_id)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swap::SwapKind;
+    use ::bitcoin::hashes::Hash;
+    use quickcheck::{Arbitrary, StdThreadGen};
+
+    #[tokio::test]
+    async fn checkpoint_survives_restart() {
+        let db = Arc::new(Database::new_test().unwrap());
+        let swap_id = SwapId::default();
+        let block_hash = ::bitcoin::BlockHash::from_inner([1u8; 32]);
+
+        let checkpoints = SwapCheckpoints::<hbit::Funded>::new(db.clone(), swap_id);
+        checkpoints.save_checkpoint(block_hash).await.unwrap();
+        drop(checkpoints);
+
+        let checkpoints = SwapCheckpoints::<hbit::Funded>::new(db, swap_id);
+        let loaded = checkpoints.load_checkpoint().await.unwrap();
+
+        assert_eq!(loaded, Some(block_hash));
+    }
+
+    #[tokio::test]
+    async fn checkpoints_are_kept_per_scan_and_swap() {
+        let db = Arc::new(Database::new_test().unwrap());
+        let swap_id = SwapId::default();
+        let block_hash = ::bitcoin::BlockHash::from_inner([1u8; 32]);
+
+        SwapCheckpoints::<hbit::Funded>::new(db.clone(), swap_id)
+            .save_checkpoint(block_hash)
+            .await
+            .unwrap();
+
+        let other_scan = SwapCheckpoints::<hbit::Redeemed>::new(db.clone(), swap_id);
+        let other_swap = SwapCheckpoints::<hbit::Funded>::new(db.clone(), SwapId::default());
+
+        assert_eq!(other_scan.load_checkpoint().await.unwrap(), None);
+        assert_eq!(other_swap.load_checkpoint().await.unwrap(), None);
+        assert!(db.all_active_swaps().unwrap().is_empty());
+    }
+
+    async fn save_all_checkpoints(db: &Arc<Database>, swap_id: SwapId) {
+        let block_hash = ::bitcoin::BlockHash::from_inner([1u8; 32]);
+
+        SwapCheckpoints::<hbit::Funded>::new(db.clone(), swap_id)
+            .save_checkpoint(block_hash)
+            .await
+            .unwrap();
+        SwapCheckpoints::<Beta<hbit::Redeemed>>::new(db.clone(), swap_id)
+            .save_checkpoint(block_hash)
+            .await
+            .unwrap();
+        SwapCheckpoints::<herc20::Deployed>::new(db.clone(), swap_id)
+            .save_checkpoint(comit::ethereum::Hash::from([2u8; 32]))
+            .await
+            .unwrap();
+    }
+
+    async fn assert_no_checkpoints(db: &Arc<Database>, swap_id: SwapId) {
+        let funded = SwapCheckpoints::<hbit::Funded>::new(db.clone(), swap_id);
+        let beta_redeemed = SwapCheckpoints::<Beta<hbit::Redeemed>>::new(db.clone(), swap_id);
+        let deployed = SwapCheckpoints::<herc20::Deployed>::new(db.clone(), swap_id);
+
+        assert_eq!(funded.load_checkpoint().await.unwrap(), None);
+        assert_eq!(beta_redeemed.load_checkpoint().await.unwrap(), None);
+        assert_eq!(deployed.load_checkpoint().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn removed_swap_has_no_checkpoints() {
+        let db = Arc::new(Database::new_test().unwrap());
+        let swap = SwapKind::arbitrary(&mut StdThreadGen::new(10));
+        let swap_id = swap.swap_id();
+
+        db.insert_swap(swap).await.unwrap();
+        save_all_checkpoints(&db, swap_id).await;
+        db.remove_swap(&swap_id).await.unwrap();
+
+        assert_no_checkpoints(&db, swap_id).await;
+    }
+
+    #[tokio::test]
+    async fn archived_swap_has_no_checkpoints() {
+        let db = Arc::new(Database::new_test().unwrap());
+        let swap = SwapKind::arbitrary(&mut StdThreadGen::new(10));
+        let swap_id = swap.swap_id();
+
+        db.insert_swap(swap).await.unwrap();
+        save_all_checkpoints(&db, swap_id).await;
+        db.archive_swap(&swap_id).await.unwrap();
+
+        assert_no_checkpoints(&db, swap_id).await;
+    }
+}
@@ -0,0 +1,105 @@
+use crate::Seed;
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use rand::RngCore;
+use std::fmt;
+
+const NONCE_LENGTH: usize = 24;
+
+/// Encrypts the values stored in the database with a key derived from the
+/// seed.
+///
+/// Values are authenticated together with the database key they are stored
+/// under, hence decrypting with a key derived from another seed, or a value
+/// moved to another database key, fails instead of returning garbage.
+pub struct Cipher(XChaCha20Poly1305);
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("failed to decrypt database value, was the database encrypted with another seed?")]
+pub struct DecryptionFailed;
+
+impl Cipher {
+    pub fn new(seed: &Seed) -> Self {
+        let key = seed.derive_database_key();
+
+        Self(XChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+
+    /// Returns the random nonce followed by the ciphertext, `aad` has to be
+    /// passed again to decrypt it.
+    pub fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> Vec<u8> {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .0
+            .encrypt(XNonce::from_slice(&nonce), Payload {
+                msg: plaintext,
+                aad,
+            })
+            .expect("encryption does not fail for values of this size");
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+
+        sealed
+    }
+
+    pub fn decrypt(&self, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, DecryptionFailed> {
+        if sealed.len() < NONCE_LENGTH {
+            return Err(DecryptionFailed);
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+
+        self.0
+            .decrypt(XNonce::from_slice(nonce), Payload {
+                msg: ciphertext,
+                aad,
+            })
+            .map_err(|_| DecryptionFailed)
+    }
+}
+
+impl fmt::Debug for Cipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cipher([*****])")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let cipher = Cipher::new(&Seed::random().unwrap());
+
+        let sealed = cipher.encrypt(b"are those thirty-two bytes? Hum.", b"key");
+
+        assert_eq!(
+            cipher.decrypt(&sealed, b"key").unwrap(),
+            b"are those thirty-two bytes? Hum.".to_vec()
+        );
+    }
+
+    #[test]
+    fn tampered_value_is_rejected() {
+        let cipher = Cipher::new(&Seed::random().unwrap());
+        let mut sealed = cipher.encrypt(b"are those thirty-two bytes? Hum.", b"key");
+
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+
+        assert!(cipher.decrypt(&sealed, b"key").is_err());
+    }
+
+    #[test]
+    fn value_under_another_key_is_rejected() {
+        let cipher = Cipher::new(&Seed::random().unwrap());
+        let sealed = cipher.encrypt(b"are those thirty-two bytes? Hum.", b"key");
+
+        assert!(cipher.decrypt(&sealed, b"another key").is_err());
+    }
+}
@@ -1,14 +1,14 @@
 use crate::{
-    database::{Database, Load, Save},
+    database::{save_event, Database, Load, Save},
     swap::hbit,
     SwapId,
 };
 use ::bitcoin::secp256k1;
-use comit::{identity, Secret, SecretHash, Timestamp};
-use serde::{Deserialize, Serialize};
+use comit::{identity, ledger, Secret, SecretHash, Timestamp};
+use serde::{Deserialize, Deserializer, Serialize};
 
 // TODO: control the serialisation
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct HbitFunded {
     pub location: ::bitcoin::OutPoint,
 }
@@ -47,12 +47,9 @@ impl From<hbit::Funded> for HbitFunded {
 #[async_trait::async_trait]
 impl Save<hbit::Funded> for Database {
     async fn save(&self, event: hbit::Funded, swap_id: SwapId) -> anyhow::Result<()> {
-        self.update_swap(&swap_id, |mut old_swap| match &old_swap.hbit_funded {
-            Some(_) => anyhow::bail!("Hbit Funded event is already stored"),
-            None => {
-                old_swap.hbit_funded = Some(event.into());
-                Ok(old_swap)
-            }
+        self.update_swap(&swap_id, |mut old_swap| {
+            save_event(&mut old_swap.hbit_funded, event.into(), "Hbit Funded")?;
+            Ok(old_swap)
         })
         .await
     }
@@ -66,7 +63,7 @@ impl Load<hbit::Funded> for Database {
     }
 }
 
-#[derive(Clone, Debug, Copy, Serialize, Deserialize)]
+#[derive(Clone, Debug, Copy, Serialize, Deserialize, PartialEq)]
 pub struct HbitRedeemed {
     pub transaction: bitcoin::Txid,
     pub secret: Secret,
@@ -93,12 +90,9 @@ impl From<hbit::Redeemed> for HbitRedeemed {
 #[async_trait::async_trait]
 impl Save<hbit::Redeemed> for Database {
     async fn save(&self, event: hbit::Redeemed, swap_id: SwapId) -> anyhow::Result<()> {
-        self.update_swap(&swap_id, |mut old_swap| match &old_swap.hbit_redeemed {
-            Some(_) => anyhow::bail!("Hbit Redeemed event is already stored"),
-            None => {
-                old_swap.hbit_redeemed = Some(event.into());
-                Ok(old_swap)
-            }
+        self.update_swap_revealing_secret(&swap_id, event.secret, |mut old_swap| {
+            save_event(&mut old_swap.hbit_redeemed, event.into(), "Hbit Redeemed")?;
+            Ok(old_swap)
         })
         .await
     }
@@ -112,7 +106,7 @@ impl Load<hbit::Redeemed> for Database {
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub struct HbitRefunded {
     pub transaction: bitcoin::Txid,
 }
@@ -136,12 +130,9 @@ impl From<hbit::Refunded> for HbitRefunded {
 #[async_trait::async_trait]
 impl Save<hbit::Refunded> for Database {
     async fn save(&self, event: hbit::Refunded, swap_id: SwapId) -> anyhow::Result<()> {
-        self.update_swap(&swap_id, |mut old_swap| match &old_swap.hbit_refunded {
-            Some(_) => anyhow::bail!("Hbit Refunded event is already stored"),
-            None => {
-                old_swap.hbit_refunded = Some(event.into());
-                Ok(old_swap)
-            }
+        self.update_swap(&swap_id, |mut old_swap| {
+            save_event(&mut old_swap.hbit_refunded, event.into(), "Hbit Refunded")?;
+            Ok(old_swap)
         })
         .await
     }
@@ -155,9 +146,86 @@ impl Load<hbit::Refunded> for Database {
     }
 }
 
+/// An event of the beta HTLC of a hbit<->hbit swap.
+///
+/// Every other hbit HTLC, including the alpha one of a hbit<->hbit swap, has
+/// its events stored as is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Beta<E>(pub E);
+
+#[async_trait::async_trait]
+impl Save<Beta<hbit::Funded>> for Database {
+    async fn save(&self, event: Beta<hbit::Funded>, swap_id: SwapId) -> anyhow::Result<()> {
+        self.update_swap(&swap_id, |mut old_swap| {
+            save_event(
+                &mut old_swap.hbit_beta_funded,
+                event.0.into(),
+                "Beta Hbit Funded",
+            )?;
+            Ok(old_swap)
+        })
+        .await
+    }
+}
+
+impl Load<Beta<hbit::Funded>> for Database {
+    fn load(&self, swap_id: SwapId) -> anyhow::Result<Option<Beta<hbit::Funded>>> {
+        let swap = self.get_swap_or_bail(&swap_id)?;
+
+        Ok(swap.hbit_beta_funded.map(|event| Beta(event.into())))
+    }
+}
+
+#[async_trait::async_trait]
+impl Save<Beta<hbit::Redeemed>> for Database {
+    async fn save(&self, event: Beta<hbit::Redeemed>, swap_id: SwapId) -> anyhow::Result<()> {
+        self.update_swap_revealing_secret(&swap_id, event.0.secret, |mut old_swap| {
+            save_event(
+                &mut old_swap.hbit_beta_redeemed,
+                event.0.into(),
+                "Beta Hbit Redeemed",
+            )?;
+            Ok(old_swap)
+        })
+        .await
+    }
+}
+
+impl Load<Beta<hbit::Redeemed>> for Database {
+    fn load(&self, swap_id: SwapId) -> anyhow::Result<Option<Beta<hbit::Redeemed>>> {
+        let swap = self.get_swap_or_bail(&swap_id)?;
+
+        Ok(swap.hbit_beta_redeemed.map(|event| Beta(event.into())))
+    }
+}
+
+#[async_trait::async_trait]
+impl Save<Beta<hbit::Refunded>> for Database {
+    async fn save(&self, event: Beta<hbit::Refunded>, swap_id: SwapId) -> anyhow::Result<()> {
+        self.update_swap(&swap_id, |mut old_swap| {
+            save_event(
+                &mut old_swap.hbit_beta_refunded,
+                event.0.into(),
+                "Beta Hbit Refunded",
+            )?;
+            Ok(old_swap)
+        })
+        .await
+    }
+}
+
+impl Load<Beta<hbit::Refunded>> for Database {
+    fn load(&self, swap_id: SwapId) -> anyhow::Result<Option<Beta<hbit::Refunded>>> {
+        let swap = self.get_swap_or_bail(&swap_id)?;
+
+        Ok(swap.hbit_beta_refunded.map(|event| Beta(event.into())))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Params {
-    pub network: ::bitcoin::Network,
+    #[serde(deserialize_with = "network")]
+    pub network: ledger::Bitcoin,
     pub asset: Amount,
     pub redeem_identity: identity::Bitcoin,
     pub refund_identity: identity::Bitcoin,
@@ -167,6 +235,25 @@ pub struct Params {
     pub final_address: bitcoin::Address,
 }
 
+/// Swaps used to be saved with the network of rust-bitcoin which cannot tell
+/// signet and testnet4 apart from testnet, those still load.
+fn network<'de, D>(deserializer: D) -> Result<ledger::Bitcoin, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Network {
+        Ledger(ledger::Bitcoin),
+        Legacy(::bitcoin::Network),
+    }
+
+    Ok(match Network::deserialize(deserializer)? {
+        Network::Ledger(ledger) => ledger,
+        Network::Legacy(network) => network.into(),
+    })
+}
+
 impl From<Params> for hbit::Params {
     fn from(params: Params) -> Self {
         let Params {
@@ -182,7 +269,7 @@ impl From<Params> for hbit::Params {
 
         hbit::Params {
             shared: comit::hbit::SharedParams {
-                network: network.into(),
+                network,
                 asset: asset.into(),
                 redeem_identity,
                 refund_identity,
@@ -198,7 +285,7 @@ impl From<Params> for hbit::Params {
 impl From<hbit::Params> for Params {
     fn from(params: hbit::Params) -> Self {
         Params {
-            network: params.shared.network.into(),
+            network: params.shared.network,
             asset: params.shared.asset.into(),
             redeem_identity: params.shared.redeem_identity,
             refund_identity: params.shared.refund_identity,
@@ -216,7 +303,7 @@ impl crate::StaticStub for Params {
         use std::str::FromStr;
 
         Params {
-            network: ::bitcoin::Network::Regtest,
+            network: ledger::Bitcoin::Regtest,
             asset: Amount::from(comit::asset::Bitcoin::from_sat(123456789)),
             redeem_identity: comit::bitcoin::PublicKey::from_str(
                 "039b6347398505f5ec93826dc61c19f47c66c0283ee9be980e29ce325a0f4679ef",
@@ -245,10 +332,11 @@ impl crate::StaticStub for Params {
 mod tests {
     use super::*;
     use crate::{
-        database::{Database, Swap},
+        database::{deserialize, serialize, Database, Kind, Swap},
         swap::SwapKind,
         StaticStub,
     };
+    use std::convert::TryFrom;
 
     fn bitcoin_transaction() -> ::bitcoin::Transaction {
         ::bitcoin::Transaction {
@@ -267,6 +355,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn params_on_signet_keep_their_network() {
+        let params = Params {
+            network: ledger::Bitcoin::Signet,
+            ..Params::static_stub()
+        };
+
+        let rinsed: Params = deserialize(&serialize(&params).unwrap()).unwrap();
+
+        assert_eq!(rinsed.network, ledger::Bitcoin::Signet);
+    }
+
+    #[test]
+    fn network_saved_as_rust_bitcoin_network_still_loads() {
+        #[derive(Serialize)]
+        struct Saved {
+            network: ::bitcoin::Network,
+        }
+        #[derive(Deserialize)]
+        struct Loaded {
+            #[serde(deserialize_with = "network")]
+            network: ledger::Bitcoin,
+        }
+
+        for (saved, loaded) in &[
+            (::bitcoin::Network::Bitcoin, ledger::Bitcoin::Mainnet),
+            (::bitcoin::Network::Testnet, ledger::Bitcoin::Testnet),
+            (::bitcoin::Network::Regtest, ledger::Bitcoin::Regtest),
+        ] {
+            let bytes = serialize(&Saved { network: *saved }).unwrap();
+            let rinsed: Loaded = deserialize(&bytes).unwrap();
+
+            assert_eq!(rinsed.network, *loaded);
+        }
+    }
+
     #[tokio::test]
     async fn save_and_load_hbit_funded() {
         let db = Database::new_test().unwrap();
@@ -274,7 +398,7 @@ mod tests {
         let swap = Swap::static_stub();
         let swap_id = SwapId::default();
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
@@ -297,7 +421,7 @@ mod tests {
         let swap = Swap::static_stub();
         let swap_id = SwapId::default();
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
@@ -323,7 +447,7 @@ mod tests {
         let swap = Swap::static_stub();
         let swap_id = SwapId::default();
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
@@ -337,4 +461,48 @@ mod tests {
 
         assert_eq!(stored_event.transaction, transaction);
     }
+
+    #[tokio::test]
+    async fn beta_events_of_hbit_hbit_swap_are_stored_separately() {
+        let db = Database::new_test().unwrap();
+        let swap_id = SwapId::default();
+        let alpha_location = ::bitcoin::OutPoint::new(Default::default(), 0);
+        let beta_location = ::bitcoin::OutPoint::new(Default::default(), 1);
+
+        let mut swap = Swap::static_stub();
+        swap.kind = Kind::HbitHbit;
+        swap.herc20_params = None;
+        swap.hbit_beta_params = Some(Params::static_stub());
+        db.insert_swap(SwapKind::try_from((swap, swap_id)).unwrap())
+            .await
+            .unwrap();
+
+        db.save(
+            hbit::Funded {
+                location: alpha_location,
+            },
+            swap_id,
+        )
+        .await
+        .unwrap();
+        db.save(
+            Beta(hbit::Funded {
+                location: beta_location,
+            }),
+            swap_id,
+        )
+        .await
+        .unwrap();
+
+        let alpha: hbit::Funded = db.load(swap_id).unwrap().unwrap();
+        let Beta(beta) = Load::<Beta<hbit::Funded>>::load(&db, swap_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(alpha.location, alpha_location);
+        assert_eq!(beta.location, beta_location);
+        assert!(matches!(
+            Load::<SwapKind>::load(&db, swap_id).unwrap(),
+            Some(SwapKind::HbitHbit(_))
+        ));
+    }
 }
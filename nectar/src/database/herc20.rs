@@ -1,12 +1,19 @@
 use crate::{
-    database::{Database, Load, Save},
+    database::{save_event, Database, Load, Save, Swap},
     swap::herc20,
     SwapId,
 };
-use comit::{asset::Erc20, ethereum, identity, Secret, SecretHash, Timestamp};
+use comit::{
+    asset::Erc20,
+    btsieve::{ethereum::confirmations_up_to, BlockByHash, LatestBlock},
+    ethereum,
+    ethereum::Block,
+    identity, Secret, SecretHash, Timestamp,
+};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Herc20Deployed {
     pub transaction: ethereum::Hash,
     pub location: comit::htlc_location::Ethereum,
@@ -33,12 +40,13 @@ impl From<herc20::Deployed> for Herc20Deployed {
 #[async_trait::async_trait]
 impl Save<herc20::Deployed> for Database {
     async fn save(&self, event: herc20::Deployed, swap_id: SwapId) -> anyhow::Result<()> {
-        self.update_swap(&swap_id, |mut old_swap| match &old_swap.herc20_deployed {
-            Some(_) => anyhow::bail!("Herc20 Deployed event is already stored"),
-            None => {
-                old_swap.herc20_deployed = Some(event.into());
-                Ok(old_swap)
-            }
+        self.update_swap(&swap_id, |mut old_swap| {
+            save_event(
+                &mut old_swap.herc20_deployed,
+                event.into(),
+                "Herc20 Deployed",
+            )?;
+            Ok(old_swap)
         })
         .await
     }
@@ -52,7 +60,7 @@ impl Load<herc20::Deployed> for Database {
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Herc20Funded {
     pub transaction: ethereum::Hash,
 }
@@ -76,12 +84,9 @@ impl From<herc20::Funded> for Herc20Funded {
 #[async_trait::async_trait]
 impl Save<herc20::Funded> for Database {
     async fn save(&self, event: herc20::Funded, swap_id: SwapId) -> anyhow::Result<()> {
-        self.update_swap(&swap_id, |mut old_swap| match &old_swap.herc20_funded {
-            Some(_) => anyhow::bail!("Herc20 Funded event is already stored"),
-            None => {
-                old_swap.herc20_funded = Some(event.into());
-                Ok(old_swap)
-            }
+        self.update_swap(&swap_id, |mut old_swap| {
+            save_event(&mut old_swap.herc20_funded, event.into(), "Herc20 Funded")?;
+            Ok(old_swap)
         })
         .await
     }
@@ -95,7 +100,7 @@ impl Load<herc20::Funded> for Database {
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Herc20Redeemed {
     pub transaction: ethereum::Hash,
     pub secret: Secret,
@@ -122,12 +127,13 @@ impl From<herc20::Redeemed> for Herc20Redeemed {
 #[async_trait::async_trait]
 impl Save<herc20::Redeemed> for Database {
     async fn save(&self, event: herc20::Redeemed, swap_id: SwapId) -> anyhow::Result<()> {
-        self.update_swap(&swap_id, |mut old_swap| match &old_swap.herc20_redeemed {
-            Some(_) => anyhow::bail!("Herc20 Redeem event is already stored"),
-            None => {
-                old_swap.herc20_redeemed = Some(event.into());
-                Ok(old_swap)
-            }
+        self.update_swap_revealing_secret(&swap_id, event.secret, |mut old_swap| {
+            save_event(
+                &mut old_swap.herc20_redeemed,
+                event.into(),
+                "Herc20 Redeemed",
+            )?;
+            Ok(old_swap)
         })
         .await
     }
@@ -141,7 +147,7 @@ impl Load<herc20::Redeemed> for Database {
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Herc20Refunded {
     pub transaction: ethereum::Hash,
 }
@@ -165,12 +171,13 @@ impl From<herc20::Refunded> for Herc20Refunded {
 #[async_trait::async_trait]
 impl Save<herc20::Refunded> for Database {
     async fn save(&self, event: herc20::Refunded, swap_id: SwapId) -> anyhow::Result<()> {
-        self.update_swap(&swap_id, |mut old_swap| match &old_swap.herc20_refunded {
-            Some(_) => anyhow::bail!("Herc20 Refunded event is already stored"),
-            None => {
-                old_swap.herc20_refunded = Some(event.into());
-                Ok(old_swap)
-            }
+        self.update_swap(&swap_id, |mut old_swap| {
+            save_event(
+                &mut old_swap.herc20_refunded,
+                event.into(),
+                "Herc20 Refunded",
+            )?;
+            Ok(old_swap)
         })
         .await
     }
@@ -184,6 +191,76 @@ impl Load<herc20::Refunded> for Database {
     }
 }
 
+/// We don't expect reorgs deeper than this, a transaction buried under as
+/// many blocks is final and the chain is not searched any further.
+const FINAL_CONFIRMATIONS: u32 = 12;
+
+/// Block timestamps are set by miners and our clock may be off, hence we also
+/// search the blocks mined shortly before the swap started.
+const CLOCK_SKEW_MARGIN: Duration = Duration::from_secs(15 * 60);
+
+/// The herc20 events whose transaction a reorg can orphan before the swap
+/// completes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Herc20EventKind {
+    Deployed,
+    Funded,
+}
+
+impl Database {
+    /// Clears the stored event of the given kind if its transaction is no
+    /// longer part of the chain, so that the executor observes it again.
+    ///
+    /// Returns whether the event was cleared.
+    pub async fn invalidate_event_if_orphaned<C>(
+        &self,
+        connector: &C,
+        swap_id: SwapId,
+        kind: Herc20EventKind,
+    ) -> anyhow::Result<bool>
+    where
+        C: LatestBlock<Block = Block> + BlockByHash<Block = Block, BlockHash = ethereum::Hash>,
+    {
+        let swap = self.get_swap_or_bail(&swap_id)?;
+        let transaction = match stored_transaction(&swap, kind) {
+            Some(transaction) => transaction,
+            None => return Ok(false),
+        };
+
+        let confirmations = confirmations_up_to(
+            connector,
+            transaction,
+            swap.utc_start_of_swap - CLOCK_SKEW_MARGIN,
+            FINAL_CONFIRMATIONS,
+        )
+        .await?;
+        if confirmations > 0 {
+            return Ok(false);
+        }
+
+        self.update_swap(&swap_id, |mut swap| {
+            // the event may have been replaced in the meantime
+            if stored_transaction(&swap, kind) == Some(transaction) {
+                match kind {
+                    Herc20EventKind::Deployed => swap.herc20_deployed = None,
+                    Herc20EventKind::Funded => swap.herc20_funded = None,
+                }
+            }
+            Ok(swap)
+        })
+        .await?;
+
+        Ok(true)
+    }
+}
+
+fn stored_transaction(swap: &Swap, kind: Herc20EventKind) -> Option<ethereum::Hash> {
+    match kind {
+        Herc20EventKind::Deployed => swap.herc20_deployed.map(|event| event.transaction),
+        Herc20EventKind::Funded => swap.herc20_funded.map(|event| event.transaction),
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Erc20Asset {
     pub token_contract: ethereum::Address,
@@ -276,7 +353,76 @@ impl crate::StaticStub for Params {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{database::Swap, swap::SwapKind, StaticStub};
+    use crate::{swap::SwapKind, StaticStub};
+    use comit::ethereum::{Transaction, U256};
+    use std::{collections::HashMap, convert::TryFrom, sync::Mutex};
+    use time::OffsetDateTime;
+
+    /// A chain whose tip is the block added last, adding a sibling of the tip
+    /// simulates a reorg.
+    #[derive(Default)]
+    struct Chain {
+        blocks: Mutex<HashMap<ethereum::Hash, Block>>,
+        tip: Mutex<ethereum::Hash>,
+    }
+
+    impl Chain {
+        fn extend(&self, block: Block) {
+            *self.tip.lock().unwrap() = block.hash;
+            self.blocks.lock().unwrap().insert(block.hash, block);
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LatestBlock for Chain {
+        type Block = Block;
+
+        async fn latest_block(&self) -> anyhow::Result<Block> {
+            let tip = *self.tip.lock().unwrap();
+            self.block_by_hash(tip).await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl BlockByHash for Chain {
+        type Block = Block;
+        type BlockHash = ethereum::Hash;
+
+        async fn block_by_hash(&self, block_hash: ethereum::Hash) -> anyhow::Result<Block> {
+            self.blocks
+                .lock()
+                .unwrap()
+                .get(&block_hash)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("block {} not found", block_hash))
+        }
+    }
+
+    fn block(hash: u8, parent: ethereum::Hash, transactions: Vec<ethereum::Hash>) -> Block {
+        mined_at(hash, parent, transactions, 60)
+    }
+
+    /// A block mined `seconds` from now.
+    fn mined_at(
+        hash: u8,
+        parent: ethereum::Hash,
+        transactions: Vec<ethereum::Hash>,
+        seconds: i64,
+    ) -> Block {
+        Block {
+            hash: ethereum::Hash::from([hash; 32]),
+            parent_hash: parent,
+            timestamp: U256::from(OffsetDateTime::now_utc().timestamp() + seconds),
+            transactions: transactions
+                .into_iter()
+                .map(|hash| Transaction {
+                    hash,
+                    ..Transaction::default()
+                })
+                .collect(),
+            ..Block::default()
+        }
+    }
 
     #[tokio::test]
     async fn save_and_load_herc20_deployed() {
@@ -286,7 +432,7 @@ mod tests {
         let transaction = comit::transaction::Ethereum::default().hash;
         let location = comit::htlc_location::Ethereum::random();
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
@@ -312,7 +458,7 @@ mod tests {
         let swap_id = SwapId::default();
         let transaction = comit::transaction::Ethereum::default().hash;
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
@@ -335,7 +481,7 @@ mod tests {
         let transaction = comit::transaction::Ethereum::default().hash;
         let secret = Secret::from_vec(b"are those thirty-two bytes? Hum.").unwrap();
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
@@ -361,7 +507,7 @@ mod tests {
         let swap_id = SwapId::default();
         let transaction = comit::transaction::Ethereum::default().hash;
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
@@ -375,4 +521,189 @@ mod tests {
 
         assert_eq!(stored_event.transaction, transaction);
     }
+
+    #[tokio::test]
+    async fn funded_event_orphaned_by_reorg_is_cleared() {
+        let db = Database::new_test().unwrap();
+        let swap_id = SwapId::default();
+        db.insert_swap(SwapKind::try_from((Swap::static_stub(), swap_id)).unwrap())
+            .await
+            .unwrap();
+        let transaction = ethereum::Hash::from([0xf0; 32]);
+        db.save(herc20::Funded { transaction }, swap_id)
+            .await
+            .unwrap();
+
+        let chain = Chain::default();
+        let genesis = Block {
+            hash: ethereum::Hash::from([0xff; 32]),
+            ..Block::default()
+        };
+        let genesis_hash = genesis.hash;
+        chain.extend(genesis);
+        chain.extend(block(0xa1, genesis_hash, vec![transaction]));
+
+        let cleared = db
+            .invalidate_event_if_orphaned(&chain, swap_id, Herc20EventKind::Funded)
+            .await
+            .unwrap();
+        assert!(!cleared);
+        let stored: Option<herc20::Funded> = db.load(swap_id).unwrap();
+        assert!(stored.is_some());
+
+        // the fork replacing a1 does not include the transaction
+        chain.extend(block(0xb1, genesis_hash, vec![]));
+
+        let cleared = db
+            .invalidate_event_if_orphaned(&chain, swap_id, Herc20EventKind::Funded)
+            .await
+            .unwrap();
+        assert!(cleared);
+        let stored: Option<herc20::Funded> = db.load(swap_id).unwrap();
+        assert!(stored.is_none());
+    }
+
+    #[tokio::test]
+    async fn transaction_in_block_timestamped_before_the_swap_is_not_orphaned() {
+        let db = Database::new_test().unwrap();
+        let swap_id = SwapId::default();
+        db.insert_swap(SwapKind::try_from((Swap::static_stub(), swap_id)).unwrap())
+            .await
+            .unwrap();
+        let transaction = ethereum::Hash::from([0xf0; 32]);
+        db.save(herc20::Funded { transaction }, swap_id)
+            .await
+            .unwrap();
+
+        let chain = Chain::default();
+        let genesis = mined_at(0xff, ethereum::Hash::default(), vec![], -3600);
+        let genesis_hash = genesis.hash;
+        chain.extend(genesis);
+        // the miner's clock is a bit behind ours
+        let a1 = mined_at(0xa1, genesis_hash, vec![transaction], -60);
+        let a1_hash = a1.hash;
+        chain.extend(a1);
+        chain.extend(mined_at(0xa2, a1_hash, vec![], -30));
+
+        let cleared = db
+            .invalidate_event_if_orphaned(&chain, swap_id, Herc20EventKind::Funded)
+            .await
+            .unwrap();
+
+        assert!(!cleared);
+    }
+
+    #[tokio::test]
+    async fn chain_is_not_searched_beyond_final_confirmations() {
+        let db = Database::new_test().unwrap();
+        let swap_id = SwapId::default();
+        db.insert_swap(SwapKind::try_from((Swap::static_stub(), swap_id)).unwrap())
+            .await
+            .unwrap();
+        let transaction = ethereum::Hash::from([0xf0; 32]);
+        db.save(herc20::Funded { transaction }, swap_id)
+            .await
+            .unwrap();
+
+        // the block including the transaction and its ancestors are unknown,
+        // looking them up fails
+        let chain = Chain::default();
+        let mut parent = ethereum::Hash::from([0xa0; 32]);
+        for hash in 1..=u8::try_from(FINAL_CONFIRMATIONS).unwrap() {
+            let block = block(hash, parent, vec![]);
+            parent = block.hash;
+            chain.extend(block);
+        }
+
+        let cleared = db
+            .invalidate_event_if_orphaned(&chain, swap_id, Herc20EventKind::Funded)
+            .await
+            .unwrap();
+
+        assert!(!cleared);
+    }
+
+    #[tokio::test]
+    async fn saving_the_same_event_twice_succeeds() {
+        let db = Database::new_test().unwrap();
+        let swap_id = SwapId::default();
+        db.insert_swap(SwapKind::try_from((Swap::static_stub(), swap_id)).unwrap())
+            .await
+            .unwrap();
+
+        let deployed = herc20::Deployed {
+            transaction: comit::transaction::Ethereum::default().hash,
+            location: comit::htlc_location::Ethereum::random(),
+        };
+        let redeemed = herc20::Redeemed {
+            transaction: comit::transaction::Ethereum::default().hash,
+            secret: Secret::from_vec(b"are those thirty-two bytes? Hum.").unwrap(),
+        };
+        db.save(deployed, swap_id).await.unwrap();
+        db.save(redeemed, swap_id).await.unwrap();
+
+        db.save(deployed, swap_id).await.unwrap();
+        db.save(redeemed, swap_id).await.unwrap();
+
+        let stored_event: herc20::Redeemed = db.load(swap_id).unwrap().unwrap();
+        assert_eq!(stored_event.secret, redeemed.secret);
+    }
+
+    #[tokio::test]
+    async fn saving_a_conflicting_event_fails() {
+        let db = Database::new_test().unwrap();
+        let swap_id = SwapId::default();
+        db.insert_swap(SwapKind::try_from((Swap::static_stub(), swap_id)).unwrap())
+            .await
+            .unwrap();
+
+        let location = comit::htlc_location::Ethereum::random();
+        let transaction = comit::transaction::Ethereum::default().hash;
+        let other_transaction = ethereum::Hash::from([1u8; 32]);
+        let secret = Secret::from_vec(b"are those thirty-two bytes? Hum.").unwrap();
+        let other_secret = Secret::from_vec(b"these are thirty-two bytes, yes!").unwrap();
+
+        db.save(
+            herc20::Deployed {
+                transaction,
+                location,
+            },
+            swap_id,
+        )
+        .await
+        .unwrap();
+        db.save(
+            herc20::Redeemed {
+                transaction,
+                secret,
+            },
+            swap_id,
+        )
+        .await
+        .unwrap();
+
+        let different_transaction = db
+            .save(
+                herc20::Deployed {
+                    transaction: other_transaction,
+                    location,
+                },
+                swap_id,
+            )
+            .await;
+        let different_secret = db
+            .save(
+                herc20::Redeemed {
+                    transaction,
+                    secret: other_secret,
+                },
+                swap_id,
+            )
+            .await;
+
+        assert!(different_transaction.is_err());
+        assert!(different_secret.is_err());
+        let stored_event: herc20::Redeemed = db.load(swap_id).unwrap().unwrap();
+        assert_eq!(stored_event.secret, secret);
+    }
 }
@@ -1,9 +1,13 @@
 pub mod dai;
+mod eip1559;
+mod fee_bump;
 mod gas_price;
 mod geth;
+mod nonce;
 mod wallet;
 
 pub use comit::ethereum::{Address, ChainId, Hash};
+pub use eip1559::Eip1559Transaction;
 pub use gas_price::*;
 pub use geth::Client;
 pub use wallet::Wallet;
@@ -155,6 +159,12 @@ pub mod ether {
         }
     }
 
+    impl From<U256> for Amount {
+        fn from(wei: U256) -> Self {
+            Amount(comit::asset::ethereum::Ether::from_wei(wei))
+        }
+    }
+
     /// Integer is wei
     impl From<u64> for Amount {
         fn from(int: u64) -> Self {
@@ -0,0 +1,207 @@
+//! Type 2 transactions as introduced by EIP-1559.
+//!
+//! clarity only knows about legacy transactions, hence we encode and sign
+//! these ourselves.
+
+use crate::ethereum::{Address, ChainId};
+use comit::ethereum::U256;
+use std::convert::TryFrom;
+use tiny_keccak::{Hasher, Keccak};
+
+const TRANSACTION_TYPE: u8 = 0x02;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Eip1559Transaction {
+    pub chain_id: ChainId,
+    pub nonce: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: U256,
+    /// `None` deploys a contract.
+    pub to: Option<Address>,
+    pub value: U256,
+    pub data: Vec<u8>,
+}
+
+impl Eip1559Transaction {
+    /// The hash that is signed by the sender.
+    pub fn signing_hash(&self) -> [u8; 32] {
+        keccak256(&typed(rlp::list(&self.fields())))
+    }
+
+    /// Signs the transaction and returns its encoding, ready to be sent with
+    /// `eth_sendRawTransaction`.
+    pub fn sign(&self, private_key: &clarity::PrivateKey) -> Vec<u8> {
+        let signature = private_key.sign_hash(&self.signing_hash());
+
+        // clarity adds 27 to the recovery id, type 2 transactions only carry
+        // its parity
+        let y_parity = if signature.v == clarity::Uint256::from(28u64) {
+            U256::one()
+        } else {
+            U256::zero()
+        };
+
+        let mut fields = self.fields();
+        fields.push(rlp::uint(y_parity));
+        fields.push(rlp::uint(U256::from_big_endian(
+            &signature.r.0.to_bytes_be(),
+        )));
+        fields.push(rlp::uint(U256::from_big_endian(
+            &signature.s.0.to_bytes_be(),
+        )));
+
+        typed(rlp::list(&fields))
+    }
+
+    fn fields(&self) -> Vec<Vec<u8>> {
+        let to = self.to.as_ref().map_or(&[][..], |to| &to.as_bytes()[..]);
+
+        vec![
+            rlp::uint(U256::from(u32::from(self.chain_id))),
+            rlp::uint(self.nonce),
+            rlp::uint(self.max_priority_fee_per_gas),
+            rlp::uint(self.max_fee_per_gas),
+            rlp::uint(self.gas_limit),
+            rlp::bytes(to),
+            rlp::uint(self.value),
+            rlp::bytes(&self.data),
+            rlp::list(&[]), // access list
+        ]
+    }
+}
+
+fn typed(payload: Vec<u8>) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(payload.len() + 1);
+    encoded.push(TRANSACTION_TYPE);
+    encoded.extend(payload);
+
+    encoded
+}
+
+fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    hasher.update(bytes);
+
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+
+    hash
+}
+
+/// The subset of RLP needed to encode transactions.
+mod rlp {
+    use super::*;
+
+    pub fn uint(value: U256) -> Vec<u8> {
+        let mut buffer = [0u8; 32];
+        value.to_big_endian(&mut buffer);
+
+        let leading_zeros = buffer.iter().take_while(|byte| **byte == 0).count();
+
+        bytes(&buffer[leading_zeros..])
+    }
+
+    pub fn bytes(bytes: &[u8]) -> Vec<u8> {
+        match bytes {
+            [byte] if *byte < 0x80 => vec![*byte],
+            bytes => with_prefix(0x80, bytes),
+        }
+    }
+
+    pub fn list(items: &[Vec<u8>]) -> Vec<u8> {
+        with_prefix(0xc0, &items.concat())
+    }
+
+    fn with_prefix(offset: u8, payload: &[u8]) -> Vec<u8> {
+        let mut encoded = if payload.len() <= 55 {
+            vec![offset + u8::try_from(payload.len()).expect("at most 55")]
+        } else {
+            let length = payload.len().to_be_bytes();
+            let leading_zeros = length.iter().take_while(|byte| **byte == 0).count();
+            let length = &length[leading_zeros..];
+
+            let length_of_length = u8::try_from(length.len()).expect("at most 8");
+
+            let mut prefix = vec![offset + 55 + length_of_length];
+            prefix.extend_from_slice(length);
+            prefix
+        };
+        encoded.extend_from_slice(payload);
+
+        encoded
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn encodes_strings() {
+            assert_eq!(bytes(b"dog"), vec![0x83, b'd', b'o', b'g']);
+            assert_eq!(bytes(b""), vec![0x80]);
+            assert_eq!(bytes(&[0x0f]), vec![0x0f]);
+            assert_eq!(bytes(&[0x80]), vec![0x81, 0x80]);
+        }
+
+        #[test]
+        fn encodes_long_strings() {
+            let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+
+            let encoded = bytes(lorem);
+
+            assert_eq!(encoded[..2], [0xb8, 0x38]);
+            assert_eq!(&encoded[2..], &lorem[..]);
+        }
+
+        #[test]
+        fn encodes_integers() {
+            assert_eq!(uint(U256::zero()), vec![0x80]);
+            assert_eq!(uint(U256::from(15)), vec![0x0f]);
+            assert_eq!(uint(U256::from(1024)), vec![0x82, 0x04, 0x00]);
+        }
+
+        #[test]
+        fn encodes_lists() {
+            assert_eq!(list(&[]), vec![0xc0]);
+            assert_eq!(list(&[bytes(b"cat"), bytes(b"dog")]), vec![
+                0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g'
+            ]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction() -> Eip1559Transaction {
+        Eip1559Transaction {
+            chain_id: ChainId::MAINNET,
+            nonce: U256::from(1),
+            max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            max_fee_per_gas: U256::from(100_000_000_000u64),
+            gas_limit: U256::from(21_000),
+            to: Some(Address::from([0x11; 20])),
+            value: U256::zero(),
+            data: vec![],
+        }
+    }
+
+    #[test]
+    fn signed_transaction_is_typed() {
+        let private_key = clarity::PrivateKey::from_slice(&[0x42; 32]).unwrap();
+
+        let signed = transaction().sign(&private_key);
+
+        assert_eq!(signed[0], TRANSACTION_TYPE);
+    }
+
+    #[test]
+    fn signing_hash_commits_to_the_fees() {
+        let mut other = transaction();
+        other.max_fee_per_gas = U256::from(200_000_000_000u64);
+
+        assert_ne!(transaction().signing_hash(), other.signing_hash());
+    }
+}
@@ -0,0 +1,175 @@
+use crate::ethereum::{ether, geth, to_clarity_address, Address, Hash};
+use comit::ethereum::{UnformattedData, U256};
+
+/// A transaction as returned by `eth_getTransactionByHash`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SentTransaction {
+    pub nonce: U256,
+    pub gas_price: U256,
+    pub gas: U256,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub input: UnformattedData,
+    /// `None` while the transaction is pending.
+    pub block_number: Option<U256>,
+}
+
+#[async_trait::async_trait]
+pub trait TransactionByHash {
+    async fn transaction_by_hash(&self, hash: Hash) -> anyhow::Result<Option<SentTransaction>>;
+}
+
+#[async_trait::async_trait]
+impl TransactionByHash for geth::Client {
+    async fn transaction_by_hash(&self, hash: Hash) -> anyhow::Result<Option<SentTransaction>> {
+        self.get_transaction_by_hash(hash).await
+    }
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("transaction {0} was already mined")]
+pub struct AlreadyMined(pub Hash);
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("gas price {new} is below the minimum replacement gas price {minimum}")]
+pub struct InsufficientBump {
+    pub new: U256,
+    pub minimum: U256,
+}
+
+/// Nodes only accept a transaction with the nonce of a pending one if it pays
+/// at least 10% more gas.
+pub fn minimum_replacement_gas_price(original: U256) -> U256 {
+    original.saturating_add(original.saturating_add(U256::from(9)) / 10)
+}
+
+/// Builds the unsigned replacement of the pending transaction `pending_tx`.
+///
+/// The replacement is identical to the original apart from paying
+/// `new_price`, in particular it reuses the original nonce. It is the caller's
+/// job to sign and send it without going through the nonce manager.
+pub async fn replacement<C>(
+    client: &C,
+    pending_tx: Hash,
+    new_price: ether::Amount,
+) -> anyhow::Result<clarity::Transaction>
+where
+    C: TransactionByHash,
+{
+    let original = client
+        .transaction_by_hash(pending_tx)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("transaction {} is unknown", pending_tx))?;
+
+    if original.block_number.is_some() {
+        anyhow::bail!(AlreadyMined(pending_tx))
+    }
+
+    let new = U256::from(new_price.clone());
+    let minimum = minimum_replacement_gas_price(original.gas_price);
+    if new < minimum {
+        anyhow::bail!(InsufficientBump { new, minimum })
+    }
+
+    let to = match original.to {
+        Some(to) => to_clarity_address(to)?,
+        None => clarity::Address::default(),
+    };
+
+    Ok(clarity::Transaction {
+        nonce: ether::Amount::from(original.nonce).into(),
+        gas_price: new_price.into(),
+        gas_limit: ether::Amount::from(original.gas).into(),
+        to,
+        value: ether::Amount::from(original.value).into(),
+        data: original.input.0,
+        signature: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity::Uint256;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Default)]
+    struct MockClient {
+        transactions: Mutex<HashMap<Hash, SentTransaction>>,
+    }
+
+    impl MockClient {
+        fn add(&self, hash: Hash, transaction: SentTransaction) {
+            self.transactions.lock().unwrap().insert(hash, transaction);
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TransactionByHash for Arc<MockClient> {
+        async fn transaction_by_hash(&self, hash: Hash) -> anyhow::Result<Option<SentTransaction>> {
+            Ok(self.transactions.lock().unwrap().get(&hash).cloned())
+        }
+    }
+
+    fn pending_transaction() -> SentTransaction {
+        SentTransaction {
+            nonce: U256::from(7),
+            gas_price: U256::from(20_000_000_000u64),
+            gas: U256::from(100_000),
+            to: Some(Address::random()),
+            value: U256::zero(),
+            input: UnformattedData(vec![0xab, 0xcd]),
+            block_number: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn replacement_reuses_nonce_and_raises_gas_price() {
+        let client = Arc::new(MockClient::default());
+        let pending_tx = Hash::from([1u8; 32]);
+        client.add(pending_tx, pending_transaction());
+
+        let new_price = ether::Amount::from(22_000_000_000u64);
+        let replacement = replacement(&client, pending_tx, new_price).await.unwrap();
+
+        assert_eq!(replacement.nonce, Uint256::from(7u64));
+        assert_eq!(replacement.gas_price, Uint256::from(22_000_000_000u64));
+        assert_eq!(replacement.gas_limit, Uint256::from(100_000u64));
+        assert_eq!(replacement.data, vec![0xab, 0xcd]);
+    }
+
+    #[tokio::test]
+    async fn refuses_bump_below_ten_percent() {
+        let client = Arc::new(MockClient::default());
+        let pending_tx = Hash::from([1u8; 32]);
+        client.add(pending_tx, pending_transaction());
+
+        let new_price = ether::Amount::from(21_999_999_999u64);
+        let error = replacement(&client, pending_tx, new_price)
+            .await
+            .unwrap_err();
+
+        assert!(error.is::<InsufficientBump>());
+    }
+
+    #[tokio::test]
+    async fn refuses_to_bump_mined_transaction() {
+        let client = Arc::new(MockClient::default());
+        let pending_tx = Hash::from([1u8; 32]);
+        client.add(pending_tx, SentTransaction {
+            block_number: Some(U256::from(100)),
+            ..pending_transaction()
+        });
+
+        let new_price = ether::Amount::from(40_000_000_000u64);
+        let error = replacement(&client, pending_tx, new_price)
+            .await
+            .unwrap_err();
+
+        assert!(error.is::<AlreadyMined>());
+    }
+}
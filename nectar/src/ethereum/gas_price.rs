@@ -3,9 +3,47 @@ use crate::{
     ethereum::{ether, geth},
     Result,
 };
+use comit::ethereum::U256;
 
 mod eth_gas_station;
 
+/// How quickly a transaction needs to be mined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Urgency {
+    Slow,
+    Normal,
+    Fast,
+}
+
+impl Urgency {
+    /// The percentage of the base gas price to pay.
+    fn multiplier_percent(self) -> u64 {
+        match self {
+            Urgency::Slow => 80,
+            Urgency::Normal => 100,
+            Urgency::Fast => 130,
+        }
+    }
+
+    fn apply(self, base_gas_price: U256) -> U256 {
+        base_gas_price.saturating_mul(U256::from(self.multiplier_percent())) / 100
+    }
+}
+
+/// Tells which gas price to pay for a transaction.
+#[async_trait::async_trait]
+pub trait GasPriceOracle {
+    /// The gas price currently suggested by the network.
+    async fn base_gas_price(&self) -> Result<U256>;
+
+    /// The gas price to pay for a transaction of the given urgency, in wei.
+    async fn gas_price(&self, urgency: Urgency) -> Result<U256> {
+        let base_gas_price = self.base_gas_price().await?;
+
+        Ok(urgency.apply(base_gas_price))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GasPrice {
     service: Service,
@@ -42,22 +80,60 @@ impl GasPrice {
             service: Service::Geth(client),
         }
     }
+}
 
-    pub async fn gas_price(&self) -> Result<ether::Amount> {
-        match &self.service {
-            Service::Geth(client) => client.gas_price().await,
-            Service::EthGasStation(client) => client.gas_price().await,
-        }
+#[async_trait::async_trait]
+impl GasPriceOracle for GasPrice {
+    async fn base_gas_price(&self) -> Result<U256> {
+        let amount: ether::Amount = match &self.service {
+            Service::Geth(client) => client.gas_price().await?,
+            Service::EthGasStation(client) => client.gas_price().await?,
+        };
+
+        Ok(amount.into())
     }
 }
 
-#[cfg(all(test, feature = "testcontainers"))]
+#[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_harness::ethereum::Blockchain;
+
+    struct MockOracle(U256);
+
+    #[async_trait::async_trait]
+    impl GasPriceOracle for MockOracle {
+        async fn base_gas_price(&self) -> Result<U256> {
+            Ok(self.0)
+        }
+    }
 
+    #[tokio::test]
+    async fn applies_multiplier_of_urgency() {
+        let oracle = MockOracle(U256::from(20_000_000_000u64));
+
+        let slow = oracle.gas_price(Urgency::Slow).await.unwrap();
+        let normal = oracle.gas_price(Urgency::Normal).await.unwrap();
+        let fast = oracle.gas_price(Urgency::Fast).await.unwrap();
+
+        assert_eq!(slow, U256::from(16_000_000_000u64));
+        assert_eq!(normal, U256::from(20_000_000_000u64));
+        assert_eq!(fast, U256::from(26_000_000_000u64));
+    }
+
+    #[tokio::test]
+    async fn saturates_instead_of_overflowing() {
+        let oracle = MockOracle(U256::max_value());
+
+        let fast = oracle.gas_price(Urgency::Fast).await.unwrap();
+
+        assert_eq!(fast, U256::max_value() / 100);
+    }
+
+    #[cfg(feature = "testcontainers")]
     #[tokio::test]
     async fn gas_price() {
+        use crate::test_harness::ethereum::Blockchain;
+
         let client = testcontainers::clients::Cli::default();
 
         let mut blockchain = Blockchain::new(&client).unwrap();
@@ -65,7 +141,7 @@ mod tests {
 
         let gas_price = GasPrice::geth_url(blockchain.node_url.clone());
 
-        let gas_price = gas_price.gas_price().await.unwrap();
+        let gas_price = gas_price.gas_price(Urgency::Normal).await.unwrap();
 
         println!("Gas price: {}", gas_price)
     }
@@ -1,5 +1,5 @@
 use crate::{
-    ethereum::{ether, Address},
+    ethereum::{ether, fee_bump::SentTransaction, Address},
     jsonrpc,
 };
 use anyhow::Context;
@@ -73,6 +73,23 @@ impl Client {
         Ok(receipt)
     }
 
+    pub async fn get_transaction_by_hash(
+        &self,
+        transaction_hash: Hash,
+    ) -> anyhow::Result<Option<SentTransaction>> {
+        let transaction = self
+            .rpc_client
+            .send(jsonrpc::Request::new(
+                "eth_getTransactionByHash",
+                vec![jsonrpc::serialize(transaction_hash)?],
+                JSONRPC_VERSION.into(),
+            ))
+            .await
+            .context("failed to get transaction by hash")?;
+
+        Ok(transaction)
+    }
+
     pub async fn get_transaction_count(&self, account: Address) -> anyhow::Result<u32> {
         let count: String = self
             .rpc_client
@@ -88,6 +105,23 @@ impl Client {
         Ok(count)
     }
 
+    /// The transaction count of `account` including the transactions that are
+    /// still in the mempool, i.e. the next nonce to use.
+    pub async fn get_pending_transaction_count(&self, account: Address) -> anyhow::Result<u32> {
+        let count: String = self
+            .rpc_client
+            .send(jsonrpc::Request::new(
+                "eth_getTransactionCount",
+                vec![jsonrpc::serialize(account)?, jsonrpc::serialize("pending")?],
+                JSONRPC_VERSION.into(),
+            ))
+            .await
+            .context("failed to get pending transaction count")?;
+
+        let count = u32::from_str_radix(&count[2..], 16)?;
+        Ok(count)
+    }
+
     pub async fn erc20_balance(
         &self,
         account: Address,
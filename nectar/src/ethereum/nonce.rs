@@ -0,0 +1,186 @@
+use crate::ethereum::{geth, Address};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+#[async_trait::async_trait]
+pub trait PendingTransactionCount {
+    async fn pending_transaction_count(&self, account: Address) -> anyhow::Result<u32>;
+}
+
+#[async_trait::async_trait]
+impl PendingTransactionCount for geth::Client {
+    async fn pending_transaction_count(&self, account: Address) -> anyhow::Result<u32> {
+        self.get_pending_transaction_count(account).await
+    }
+}
+
+/// Hands out nonces per sender so that transactions sent back-to-back do not
+/// end up with the same nonce.
+///
+/// Every issued nonce must be released once the transaction was sent, or
+/// failed to be sent. If the node's pending transaction count falls behind
+/// the nonces that were issued and released (e.g. because a transaction was
+/// dropped), the manager starts over from the pending transaction count.
+#[derive(Debug, Clone)]
+pub struct NonceManager<C> {
+    client: C,
+    accounts: Arc<Mutex<HashMap<Address, Account>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Account {
+    next_nonce: u32,
+    /// Nonces that were issued but not yet released.
+    in_flight: u32,
+}
+
+impl<C> NonceManager<C>
+where
+    C: PendingTransactionCount,
+{
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            accounts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub async fn next_nonce(&self, account: Address) -> anyhow::Result<u32> {
+        let pending = self.client.pending_transaction_count(account).await?;
+
+        let mut accounts = self.accounts.lock().expect("mutex is not poisoned");
+        let state = accounts.entry(account).or_insert(Account {
+            next_nonce: pending,
+            in_flight: 0,
+        });
+
+        let sent_elsewhere = pending > state.next_nonce;
+        let gap = pending.saturating_add(state.in_flight) < state.next_nonce;
+
+        if sent_elsewhere || gap {
+            tracing::debug!(
+                "resetting nonce of {} from {} to pending transaction count {}",
+                account,
+                state.next_nonce,
+                pending
+            );
+            state.next_nonce = pending;
+        }
+
+        let nonce = state.next_nonce;
+        state.next_nonce = nonce
+            .checked_add(1)
+            .ok_or_else(|| anyhow::anyhow!("nonce of {} overflowed", account))?;
+        state.in_flight = state.in_flight.saturating_add(1);
+
+        Ok(nonce)
+    }
+
+    /// Marks a nonce of `account` as no longer in flight, regardless of whether
+    /// the transaction was sent successfully.
+    pub fn release(&self, account: Address) {
+        let mut accounts = self.accounts.lock().expect("mutex is not poisoned");
+
+        if let Some(state) = accounts.get_mut(&account) {
+            state.in_flight = state.in_flight.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Mimics a node that learns about every transaction we send.
+    #[derive(Default)]
+    struct MockClient {
+        pending: AtomicU32,
+    }
+
+    impl MockClient {
+        fn send(&self) {
+            self.pending.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl PendingTransactionCount for Arc<MockClient> {
+        async fn pending_transaction_count(&self, _: Address) -> anyhow::Result<u32> {
+            Ok(self.pending.load(Ordering::SeqCst))
+        }
+    }
+
+    #[tokio::test]
+    async fn issues_sequential_nonces() {
+        let client = Arc::new(MockClient::default());
+        client.pending.store(3, Ordering::SeqCst);
+        let nonces = NonceManager::new(client.clone());
+        let account = Address::random();
+
+        // both transactions are built before either of them was sent
+        let deploy = nonces.next_nonce(account).await.unwrap();
+        let fund = nonces.next_nonce(account).await.unwrap();
+        client.send();
+        nonces.release(account);
+        client.send();
+        nonces.release(account);
+
+        let redeem = nonces.next_nonce(account).await.unwrap();
+
+        assert_eq!((deploy, fund, redeem), (3, 4, 5));
+    }
+
+    #[tokio::test]
+    async fn recovers_from_dropped_transaction() {
+        let client = Arc::new(MockClient::default());
+        let nonces = NonceManager::new(client.clone());
+        let account = Address::random();
+
+        let first = nonces.next_nonce(account).await.unwrap();
+        client.send();
+        nonces.release(account);
+
+        // the node never learns about this one
+        let dropped = nonces.next_nonce(account).await.unwrap();
+        nonces.release(account);
+
+        let retry = nonces.next_nonce(account).await.unwrap();
+
+        assert_eq!(first, 0);
+        assert_eq!(dropped, 1);
+        assert_eq!(retry, 1);
+    }
+
+    #[tokio::test]
+    async fn catches_up_with_transactions_sent_elsewhere() {
+        let client = Arc::new(MockClient::default());
+        let nonces = NonceManager::new(client.clone());
+        let account = Address::random();
+
+        let _ = nonces.next_nonce(account).await.unwrap();
+        client.send();
+        nonces.release(account);
+        client.send();
+
+        let nonce = nonces.next_nonce(account).await.unwrap();
+
+        assert_eq!(nonce, 2);
+    }
+
+    #[tokio::test]
+    async fn nonces_are_kept_per_account() {
+        let client = Arc::new(MockClient::default());
+        let nonces = NonceManager::new(client);
+        let alice = Address::random();
+        let bob = Address::random();
+
+        let alice_nonce = nonces.next_nonce(alice).await.unwrap();
+        let bob_nonce = nonces.next_nonce(bob).await.unwrap();
+
+        assert_eq!(alice_nonce, 0);
+        assert_eq!(bob_nonce, 0);
+    }
+}
@@ -1,8 +1,9 @@
 use crate::{
     ethereum::{
-        self, dai, ether,
+        self, dai, ether, fee_bump,
         geth::{Client, EstimateGasRequest},
-        to_clarity_address, Address, ChainId, Hash, DAI_TRANSFER_GAS_LIMIT,
+        nonce::NonceManager,
+        to_clarity_address, Address, ChainId, Eip1559Transaction, Hash, DAI_TRANSFER_GAS_LIMIT,
     },
     Seed,
 };
@@ -10,8 +11,9 @@ use anyhow::Context;
 use bitcoin::util::bip32::{DerivationPath, ExtendedPrivKey};
 use clarity::Uint256;
 use comit::{
-    asset::Erc20,
-    swap::actions::{CallContract, DeployContract},
+    asset::{Erc20, Ether},
+    ethereum::U256,
+    swap::actions::{CallContract, DeployContract, FeeMode},
 };
 use conquer_once::Lazy;
 use num::BigUint;
@@ -29,6 +31,7 @@ static DERIVATION_PATH: Lazy<DerivationPath> = Lazy::new(|| {
 pub struct Wallet {
     private_key: clarity::PrivateKey,
     geth_client: Client,
+    nonces: NonceManager<Client>,
     chain: ethereum::Chain,
 }
 
@@ -38,6 +41,7 @@ impl Wallet {
 
         let private_key = Self::private_key_from_seed(&seed)?;
         let wallet = Self {
+            nonces: NonceManager::new(geth_client.clone()),
             geth_client,
             private_key,
             chain,
@@ -62,6 +66,7 @@ impl Wallet {
         let chain = ethereum::Chain::new(chain_id, placeholder_dai_contract_address);
         Self {
             private_key,
+            nonces: NonceManager::new(geth_client.clone()),
             geth_client,
             chain,
         }
@@ -116,44 +121,52 @@ impl Wallet {
         self.chain.dai_contract_address()
     }
 
+    /// Deploys the contract paying `gas_price` unless the action asks for an
+    /// EIP-1559 transaction, see `DeployContract::fee_mode`.
     pub async fn deploy_contract(
         &self,
-        DeployContract {
+        action: DeployContract,
+        gas_price: ether::Amount,
+    ) -> anyhow::Result<(Hash, ethereum::Address)> {
+        let fee_mode = action.fee_mode();
+        let DeployContract {
             data,
             gas_limit,
             chain_id,
             ..
-        }: DeployContract,
-        gas_price: ether::Amount,
-    ) -> anyhow::Result<(Hash, ethereum::Address)> {
-        let (signed_transaction, nonce) = self
-            .sign(
-                |nonce| clarity::Transaction {
-                    nonce,
-                    gas_price: gas_price.into(),
-                    gas_limit: gas_limit.into(),
-                    to: clarity::Address::default(),
-                    value: 0u64.into(),
+        } = action;
+
+        let (hash, nonce) = match fee_mode {
+            FeeMode::Legacy => {
+                self.sign_and_send_legacy(
+                    |nonce| clarity::Transaction {
+                        nonce,
+                        gas_price: gas_price.into(),
+                        gas_limit: gas_limit.into(),
+                        to: clarity::Address::default(),
+                        value: 0u64.into(),
+                        data,
+                        signature: None,
+                    },
+                    chain_id,
+                )
+                .await?
+            }
+            FeeMode::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => {
+                self.sign_and_send_eip1559(
+                    None,
                     data,
-                    signature: None,
-                },
-                chain_id,
-            )
-            .await?;
-
-        let transaction_hex = format!(
-            "0x{}",
-            hex::encode(
-                signed_transaction
-                    .to_bytes()
-                    .context("failed to serialize signed transaction to bytes")?
-            )
-        );
-
-        let hash = self
-            .geth_client
-            .send_raw_transaction(transaction_hex)
-            .await?;
+                    gas_limit,
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                    chain_id,
+                )
+                .await?
+            }
+        };
 
         // TODO: upstream this functionality to clarity using clarity types
         let address = contract_address::ContractAddress::from_sender_and_nonce(
@@ -229,29 +242,137 @@ impl Wallet {
         Ok(hash)
     }
 
+    /// Calls the contract paying `gas_price` unless the action asks for an
+    /// EIP-1559 transaction, see `CallContract::fee_mode`.
     pub async fn call_contract(
         &self,
-        CallContract {
+        action: CallContract,
+        gas_price: ether::Amount,
+    ) -> anyhow::Result<Hash> {
+        let fee_mode = action.fee_mode();
+        let CallContract {
             to,
             data,
             gas_limit,
             chain_id,
             ..
-        }: CallContract,
-        gas_price: ether::Amount,
+        } = action;
+
+        let hash = match fee_mode {
+            FeeMode::Legacy => {
+                self.sign_and_send(
+                    data.unwrap_or_default(),
+                    0u64.into(),
+                    to_clarity_address(to)?,
+                    gas_limit.into(),
+                    gas_price.into(),
+                    chain_id,
+                )
+                .await?
+            }
+            FeeMode::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => {
+                let (hash, _) = self
+                    .sign_and_send_eip1559(
+                        Some(to),
+                        data.unwrap_or_default(),
+                        gas_limit,
+                        max_fee_per_gas,
+                        max_priority_fee_per_gas,
+                        chain_id,
+                    )
+                    .await?;
+
+                hash
+            }
+        };
+
+        Ok(hash)
+    }
+
+    /// Signs and sends a type 2 transaction, returns its hash and the nonce it
+    /// was sent with.
+    async fn sign_and_send_eip1559(
+        &self,
+        to: Option<Address>,
+        data: Vec<u8>,
+        gas_limit: u64,
+        max_fee_per_gas: Ether,
+        max_priority_fee_per_gas: Ether,
+        chain_id: ChainId,
+    ) -> anyhow::Result<(Hash, Uint256)> {
+        self.assert_chain(chain_id).await?;
+
+        let account = self.account();
+        let nonce = self.nonces.next_nonce(account).await?;
+        let transaction = Eip1559Transaction {
+            chain_id,
+            nonce: U256::from(nonce),
+            max_priority_fee_per_gas: max_priority_fee_per_gas.to_u256(),
+            max_fee_per_gas: max_fee_per_gas.to_u256(),
+            gas_limit: U256::from(gas_limit),
+            to,
+            value: U256::zero(),
+            data,
+        };
+
+        let transaction_hex = format!("0x{}", hex::encode(transaction.sign(&self.private_key)));
+        let result = self.geth_client.send_raw_transaction(transaction_hex).await;
+        self.nonces.release(account);
+
+        Ok((result?, u64::from(nonce).into()))
+    }
+
+    /// Signs and sends a legacy transaction, returns its hash and the nonce it
+    /// was sent with.
+    async fn sign_and_send_legacy(
+        &self,
+        transaction_fn: impl FnOnce(Uint256) -> clarity::Transaction,
+        chain_id: ChainId,
+    ) -> anyhow::Result<(Hash, Uint256)> {
+        self.assert_chain(chain_id).await?;
+
+        let account = self.account();
+        let nonce: Uint256 = self.nonces.next_nonce(account).await?.into();
+        let result = self.send_legacy(transaction_fn(nonce.clone())).await;
+        self.nonces.release(account);
+
+        Ok((result?, nonce))
+    }
+
+    /// Replaces the pending legacy transaction `pending_tx` with one paying
+    /// `new_price`, which must be at least 10% above the original gas price.
+    ///
+    /// The replacement reuses the nonce of the original transaction, hence it
+    /// deliberately does not ask the nonce manager for a new one.
+    pub async fn bump_gas_price(
+        &self,
+        pending_tx: Hash,
+        new_price: ether::Amount,
     ) -> anyhow::Result<Hash> {
-        let hash = self
-            .sign_and_send(
-                data.unwrap_or_default(),
-                0u64.into(),
-                to_clarity_address(to)?,
-                gas_limit.into(),
-                gas_price.into(),
-                chain_id,
+        let transaction = fee_bump::replacement(&self.geth_client, pending_tx, new_price).await?;
+
+        self.send_legacy(transaction).await
+    }
+
+    async fn send_legacy(&self, transaction: clarity::Transaction) -> anyhow::Result<Hash> {
+        let signed_transaction = transaction.sign(
+            &self.private_key,
+            Some(u32::from(self.chain.chain_id()) as u64),
+        );
+
+        let transaction_hex = format!(
+            "0x{}",
+            hex::encode(
+                signed_transaction
+                    .to_bytes()
+                    .context("failed to serialize signed transaction to bytes")?
             )
-            .await?;
+        );
 
-        Ok(hash)
+        self.geth_client.send_raw_transaction(transaction_hex).await
     }
 
     pub async fn sign_and_send(
@@ -263,8 +384,8 @@ impl Wallet {
         gas_price: Uint256,
         chain_id: ChainId,
     ) -> anyhow::Result<Hash> {
-        let (signed_transaction, _) = self
-            .sign(
+        let (hash, _) = self
+            .sign_and_send_legacy(
                 |nonce| clarity::Transaction {
                     nonce,
                     gas_price,
@@ -278,20 +399,6 @@ impl Wallet {
             )
             .await?;
 
-        let transaction_hex = format!(
-            "0x{}",
-            hex::encode(
-                signed_transaction
-                    .to_bytes()
-                    .context("failed to serialize signed transaction to bytes")?
-            )
-        );
-
-        let hash = self
-            .geth_client
-            .send_raw_transaction(transaction_hex)
-            .await?;
-
         Ok(hash)
     }
 
@@ -418,7 +525,7 @@ impl From<DeployedContract> for comit::herc20::Deployed {
 mod tests {
     use super::*;
     use crate::{
-        ethereum::{ether, GasPrice},
+        ethereum::{ether, GasPrice, GasPriceOracle, Urgency},
         test_harness::ethereum::Blockchain,
     };
     use comit::asset::{self, ethereum::FromWei, Erc20Quantity};
@@ -515,9 +622,10 @@ mod tests {
         assert_eq!(balance, dai::Amount::from_atto(initial_deposit.into()));
 
         let gas_price = GasPrice::geth_url(blockchain.node_url.clone())
-            .gas_price()
+            .gas_price(Urgency::Normal)
             .await
-            .unwrap();
+            .unwrap()
+            .into();
 
         let hash = wallet
             .transfer_dai(
@@ -585,9 +693,10 @@ mod tests {
         };
 
         let gas_price = GasPrice::geth_url(blockchain.node_url.clone())
-            .gas_price()
+            .gas_price(Urgency::Normal)
             .await
-            .unwrap();
+            .unwrap()
+            .into();
 
         let (hash, _) = wallet
             .deploy_contract(
@@ -596,6 +705,8 @@ mod tests {
                     amount: asset::Ether::zero(),
                     gas_limit: 160_000,
                     chain_id,
+                    max_fee_per_gas: None,
+                    max_priority_fee_per_gas: None,
                 },
                 gas_price,
             )
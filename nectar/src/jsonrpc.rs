@@ -1,6 +1,6 @@
 use anyhow::Context;
 use serde::{de::DeserializeOwned, Serialize};
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
 #[derive(Clone, Debug)]
 pub struct Client {
@@ -16,6 +16,21 @@ impl Client {
         }
     }
 
+    pub fn with_options(base_url: url::Url, options: HttpOptions) -> Self {
+        let inner = reqwest::Client::builder()
+            .connect_timeout(options.connect_timeout)
+            .timeout(options.request_timeout)
+            .pool_max_idle_per_host(options.max_idle_connections)
+            .pool_idle_timeout(options.idle_timeout)
+            .build()
+            .expect("TLS backend can be initialized");
+
+        Self {
+            inner,
+            url: base_url,
+        }
+    }
+
     pub async fn send<Req, Res>(&self, request: Request<Req>) -> anyhow::Result<Res>
     where
         Req: Debug + Serialize,
@@ -37,13 +52,15 @@ impl Client {
 
         let response = self
             .inner
-            .post(url.clone())
+            .post(url)
             .json(&request)
             .send()
             .await
+            .map_err(with_timeout)
             .context("failed to send request")?
             .json::<Response<Res>>()
             .await
+            .map_err(with_timeout)
             .context("failed to deserialize JSON response as JSON-RPC response")?
             .payload
             .into_result()
@@ -58,6 +75,33 @@ impl Client {
     }
 }
 
+/// How the client manages its HTTP connections to the node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HttpOptions {
+    /// Gives up on connections that take longer than this to establish.
+    pub connect_timeout: Duration,
+    /// Gives up on requests that take longer than this.
+    pub request_timeout: Duration,
+    /// How many idle connections are kept open for reuse.
+    pub max_idle_connections: usize,
+    /// Closes connections that were idle for this long. Should be shorter than
+    /// the time after which the node closes idle connections itself.
+    pub idle_timeout: Option<Duration>,
+}
+
+/// The node did not answer within the configured timeout.
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("request timed out")]
+pub struct Timeout;
+
+fn with_timeout(error: reqwest::Error) -> anyhow::Error {
+    if error.is_timeout() {
+        return anyhow::Error::new(error).context(Timeout);
+    }
+
+    error.into()
+}
+
 #[derive(serde::Serialize, Debug, Clone)]
 pub struct Request<T> {
     id: String,
@@ -106,6 +150,12 @@ pub struct JsonRpcError {
     message: String,
 }
 
+impl JsonRpcError {
+    pub fn code(&self) -> i64 {
+        self.code
+    }
+}
+
 pub fn serialize<T>(t: T) -> anyhow::Result<serde_json::Value>
 where
     T: Serialize,
@@ -38,8 +38,8 @@ mod test_harness;
 
 use crate::{
     command::{
-        balance, create_transaction, deposit, dump_config, migrate_db, resume_only, trade,
-        wallet_info, withdraw, Command, Options,
+        balance, btc, create_transaction, deposit, dump_config, migrate_db, restore_seed,
+        resume_only, trade, wallet_info, withdraw, Command, Options,
     },
     config::{read_config, Settings},
     fs::default_config_path,
@@ -66,31 +66,70 @@ async fn main() -> Result<()> {
     let options = Options::from_args();
 
     let file = read_config(&options.config_file, default_config_path)?;
-    let settings = Settings::from_config_file_and_defaults(file, options.network)
+    let mut settings = Settings::from_config_file_and_defaults(file, options.network)
         .context("could not initialize configuration")?;
+    if let Some(passphrase) = options.bitcoin_wallet_passphrase {
+        settings.bitcoin.wallet_passphrase = Some(passphrase);
+    }
 
     if let Command::DumpConfig = options.cmd {
         dump_config(settings).expect("dump config");
         std::process::exit(0);
     }
 
-    trace::init_tracing(settings.logging.level).expect("initialize tracing");
+    // Dropping the guard stops writing to the log file, keep it until we exit.
+    let _log_file_guard =
+        trace::init_tracing(settings.logging.level, settings.logging.file.as_ref())
+            .expect("initialize tracing");
 
     let _guard = settings.sentry.as_ref().map(|sentry| {
         tracing::info!("Initializing sentry with URL {}", sentry.url.as_str());
         sentry::init(sentry.url.as_str())
     });
 
+    // Must run before a seed is generated
+    if let Command::RestoreSeed = options.cmd {
+        let output = restore_seed(&settings.data.dir, std::io::stdin().lock())
+            .context("failed to restore seed")?;
+        println!("{}", output);
+        std::process::exit(0);
+    }
+
     let seed = config::Seed::from_file_or_generate(&settings.data.dir)
         .expect("Could not retrieve/initialize seed")
         .into();
 
-    let bitcoin_wallet = bitcoin::Wallet::new(
-        seed,
-        settings.bitcoin.bitcoind.node_url.clone(),
-        settings.bitcoin.network,
-    )
-    .await;
+    let watch_only = !settings.bitcoin.watch_only_descriptors.is_empty();
+    if watch_only
+        && matches!(
+            options.cmd,
+            Command::Trade | Command::ResumeOnly | Command::CreateTransaction(_)
+        )
+    {
+        anyhow::bail!("the Bitcoin wallet is watch-only and cannot sign transactions");
+    }
+
+    let bitcoin_wallet = if watch_only {
+        bitcoin::Wallet::new_watch_only(
+            settings.bitcoin.watch_only_descriptors.clone(),
+            settings.bitcoin.bitcoind.node_url.clone(),
+            settings.bitcoin.network,
+        )
+        .await
+    } else {
+        bitcoin::Wallet::new(
+            seed,
+            settings.bitcoin.bitcoind.node_url.clone(),
+            settings.bitcoin.network,
+            settings.bitcoin.wallet_passphrase.clone(),
+        )
+        .await
+    }
+    .map(|wallet| {
+        wallet
+            .with_fees(settings.bitcoin.fees)
+            .with_change_policy(settings.bitcoin.change_policy)
+    });
 
     let ethereum_wallet = ethereum::Wallet::new(
         seed,
@@ -150,15 +189,20 @@ async fn main() -> Result<()> {
             .expect("Withdraw assets");
             println!("Withdraw successful. Transaction Id: {}", tx_id);
         }
-        Command::DumpConfig => unreachable!(),
+        Command::DumpConfig | Command::RestoreSeed => unreachable!(),
         Command::ResumeOnly => {
-            let bitcoind_client = bitcoin::Client::new(settings.bitcoin.bitcoind.node_url.clone());
-            let bitcoin_fee = bitcoin::Fee::new(settings.bitcoin.clone(), bitcoind_client);
+            let bitcoin_fee = bitcoin::Fee::new(settings.bitcoin.clone());
 
             let ethereum_gas_price = ethereum::GasPrice::new(settings.ethereum.gas_price.clone());
+            #[cfg(not(test))]
+            let db = Database::open(&settings.data, &seed)?;
+            #[cfg(test)]
+            let db = Database::new_test()?;
 
             resume_only(
                 settings,
+                seed,
+                db,
                 bitcoin_wallet.expect("could not initialise bitcoin wallet"),
                 bitcoin_fee,
                 ethereum_wallet.expect("could not initialise ethereum wallet"),
@@ -168,11 +212,10 @@ async fn main() -> Result<()> {
             .expect("Wrapping up")
         }
         Command::CreateTransaction(input) => {
-            let bitcoind_client = bitcoin::Client::new(settings.bitcoin.bitcoind.node_url.clone());
-            let bitcoin_fee = bitcoin::Fee::new(settings.bitcoin.clone(), bitcoind_client);
+            let bitcoin_fee = bitcoin::Fee::new(settings.bitcoin.clone());
             let ethereum_gas_price = ethereum::GasPrice::new(settings.ethereum.gas_price.clone());
             #[cfg(not(test))]
-            let db = Database::new(&settings.data.dir.join("database"))?;
+            let db = Database::open(&settings.data, &seed)?;
             #[cfg(test)]
             let db = Database::new_test()?;
 
@@ -191,7 +234,7 @@ async fn main() -> Result<()> {
         }
         Command::ArchiveSwap { id } => {
             #[cfg(not(test))]
-            let db = Database::new(&settings.data.dir.join("database"))?;
+            let db = Database::open(&settings.data, &seed)?;
             #[cfg(test)]
             let db = Database::new_test()?;
 
@@ -199,8 +242,45 @@ async fn main() -> Result<()> {
                 .await
                 .context("failed to archive swap")?;
         }
-        Command::MigrateDb(action) => {
-            migrate_db(action, &settings.data.dir.join("database")).await?
+        Command::MigrateDb(action) => migrate_db(action, &settings.data, &seed).await?,
+        Command::ExportDb { file } => {
+            #[cfg(not(test))]
+            let db = Database::open(&settings.data, &seed)?;
+            #[cfg(test)]
+            let db = Database::new_test()?;
+
+            let writer = std::fs::File::create(&file)
+                .with_context(|| format!("failed to create {}", file.display()))?;
+            db.export(std::io::BufWriter::new(writer))
+                .context("failed to export database")?;
+        }
+        Command::ImportDb { file } => {
+            #[cfg(not(test))]
+            let db = Database::open(&settings.data, &seed)?;
+            #[cfg(test)]
+            let db = Database::new_test()?;
+
+            let reader = std::fs::File::open(&file)
+                .with_context(|| format!("failed to open {}", file.display()))?;
+            db.import(reader)
+                .await
+                .context("failed to import database")?;
+        }
+        Command::BumpGasPrice { tx_hash, gas_price } => {
+            let replacement = ethereum_wallet
+                .context("could not initialize ethereum wallet")?
+                .bump_gas_price(tx_hash, gas_price)
+                .await
+                .context("failed to bump gas price")?;
+            println!("Replacement transaction id: {}", replacement);
+        }
+        Command::Btc(arguments) => {
+            let output = btc(
+                bitcoin_wallet.context("could not initialize bitcoin wallet")?,
+                arguments,
+            )
+            .await?;
+            println!("{}", output);
         }
     };
 
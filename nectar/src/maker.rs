@@ -373,7 +373,6 @@ mod tests {
             None,
             Some(btc(1.0)),
             Spread::static_stub(),
-            StaticStub::static_stub(),
         );
 
         let mut maker = Maker {
@@ -398,7 +397,6 @@ mod tests {
             Some(btc(1.0)),
             None,
             Spread::static_stub(),
-            StaticStub::static_stub(),
         );
 
         let mut maker = Maker {
@@ -423,7 +421,6 @@ mod tests {
             Some(btc(0.002)),
             None,
             Spread::static_stub(),
-            StaticStub::static_stub(),
         );
 
         let maker = Maker {
@@ -441,13 +438,8 @@ mod tests {
 
     #[test]
     fn new_buy_order() {
-        let strategy = strategy::AllIn::new(
-            StaticStub::static_stub(),
-            None,
-            None,
-            Spread::static_stub(),
-            StaticStub::static_stub(),
-        );
+        let strategy =
+            strategy::AllIn::new(StaticStub::static_stub(), None, None, Spread::static_stub());
 
         let maker = Maker {
             dai_balance: some_dai(20.0),
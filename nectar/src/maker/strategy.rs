@@ -30,9 +30,8 @@ impl AllIn {
         max_buy_quantity: Option<bitcoin::Amount>,
         max_sell_quantity: Option<bitcoin::Amount>,
         spread: Spread,
-        bitcoind_client: bitcoin::Client,
     ) -> Self {
-        let bitcoin_fee = Fee::new(config, bitcoind_client);
+        let bitcoin_fee = Fee::new(config);
         Self {
             bitcoin_fee,
             btc_reserved_funds: Default::default(),
@@ -202,6 +201,10 @@ impl AllIn {
             SwapKind::HbitHerc20(swap) => {
                 self.dai_reserved_funds -= swap.herc20_params.asset.into();
             }
+            SwapKind::HbitHbit(swap) => {
+                self.btc_reserved_funds -=
+                    swap.beta_params.shared.asset + self.bitcoin_fee.max_tx_fee();
+            }
         }
     }
 }
@@ -261,7 +264,6 @@ mod test {
                 None,
                 None,
                 StaticStub::static_stub(),
-                StaticStub::static_stub(),
             )
         }
     }
@@ -276,7 +278,7 @@ mod test {
             ..StaticStub::static_stub()
         };
 
-        let strategy = AllIn::new(config, None, None, spread, StaticStub::static_stub());
+        let strategy = AllIn::new(config, None, None, spread);
 
         let result = strategy.new_sell(btc(0.07), rate);
         assert!(result.unwrap_err().downcast::<InsufficientFunds>().is_ok());
@@ -340,7 +342,6 @@ mod test {
             Some(btc(2.0)),
             Some(btc(2.0)),
             Spread::static_stub(),
-            StaticStub::static_stub(),
         );
 
         let order = strategy.new_sell(btc(10.0), rate).unwrap();
@@ -355,13 +356,7 @@ mod test {
     #[test]
     fn given_an_available_balance_and_fees_sell_balance() {
         let rate = Rate::try_from(1.0).unwrap();
-        let strategy = AllIn::new(
-            StaticStub::static_stub(),
-            None,
-            None,
-            Spread::static_stub(),
-            StaticStub::static_stub(),
-        );
+        let strategy = AllIn::new(StaticStub::static_stub(), None, None, Spread::static_stub());
 
         let order = strategy.new_sell(btc(10.0), rate).unwrap();
 
@@ -376,13 +371,7 @@ mod test {
             ..StaticStub::static_stub()
         };
 
-        let strategy = AllIn::new(
-            config,
-            None,
-            None,
-            Spread::static_stub(),
-            StaticStub::static_stub(),
-        );
+        let strategy = AllIn::new(config, None, None, Spread::static_stub());
 
         let result = strategy.new_sell(btc(1.0), rate);
 
@@ -397,13 +386,7 @@ mod test {
             ..StaticStub::static_stub()
         };
 
-        let strategy = AllIn::new(
-            config,
-            None,
-            None,
-            Spread::static_stub(),
-            StaticStub::static_stub(),
-        );
+        let strategy = AllIn::new(config, None, None, Spread::static_stub());
 
         let result = strategy.new_sell(btc(0.07), rate);
 
@@ -413,13 +396,7 @@ mod test {
     #[test]
     fn given_a_rate_return_order_with_both_amounts() {
         let spread = Spread::new(0).unwrap();
-        let mut strategy = AllIn::new(
-            StaticStub::static_stub(),
-            None,
-            None,
-            spread,
-            StaticStub::static_stub(),
-        );
+        let mut strategy = AllIn::new(StaticStub::static_stub(), None, None, spread);
 
         // Resuming a swap should take some reserve for the swap amount and fee.
         strategy.herc20_hbit_swap_resumed(btc(50.0)).unwrap();
@@ -454,13 +431,7 @@ mod test {
     fn given_a_rate_and_spread_return_order_with_both_amounts_correct_1() {
         let rate = Rate::try_from(10_000.0).unwrap();
         let spread = Spread::new(300).unwrap();
-        let mut strategy = AllIn::new(
-            StaticStub::static_stub(),
-            None,
-            None,
-            spread,
-            StaticStub::static_stub(),
-        );
+        let mut strategy = AllIn::new(StaticStub::static_stub(), None, None, spread);
 
         assert_eq!(
             spread.apply(rate, Position::Sell).unwrap().integer(),
@@ -489,13 +460,7 @@ mod test {
 
     #[test]
     fn btc_funds_reserved_upon_taking_sell_order() {
-        let mut strategy = AllIn::new(
-            StaticStub::static_stub(),
-            None,
-            None,
-            Spread::static_stub(),
-            StaticStub::static_stub(),
-        );
+        let mut strategy = AllIn::new(StaticStub::static_stub(), None, None, Spread::static_stub());
 
         let taken_order = btc_dai_order(Position::Sell, btc(1.5), rate(0.0));
 
@@ -519,7 +484,7 @@ mod test {
             if let (Ok(dai_balance), Ok(rate), Ok(spread)) = (dai_balance, rate, spread) {
                 let dai_balance = dai::Amount::from_atto(dai_balance);
 
-                let strategy = AllIn::new(StaticStub::static_stub(), None, Some(max_buy_quantity), spread, StaticStub::static_stub(),);
+                let strategy = AllIn::new(StaticStub::static_stub(), None, Some(max_buy_quantity), spread,);
                 let _: anyhow::Result<BtcDaiOrderForm> = strategy.new_buy(dai_balance, rate);
             }
         }
@@ -534,7 +499,7 @@ mod test {
             let spread = Spread::new(spread);
 
             if let (Ok(dai_balance), Ok(rate), Ok(spread)) = (dai_balance, rate, spread) {
-                let strategy = AllIn::new(StaticStub::static_stub(), None, None, spread, StaticStub::static_stub(),);
+                let strategy = AllIn::new(StaticStub::static_stub(), None, None, spread,);
 
                 let dai_balance = dai::Amount::from_atto(dai_balance);
 
@@ -553,7 +518,7 @@ mod test {
             let spread = Spread::new(spread);
 
             if let (Ok(rate), Ok(spread)) = (rate, spread) {
-                let strategy = AllIn::new(StaticStub::static_stub(), Some(max_sell_quantity), None, spread, StaticStub::static_stub());
+                let strategy = AllIn::new(StaticStub::static_stub(), Some(max_sell_quantity), None, spread);
 
                 let _: anyhow::Result<BtcDaiOrderForm> = strategy.new_sell(btc_balance, rate);
             }
@@ -569,7 +534,7 @@ mod test {
             let spread = Spread::new(spread);
 
             if let (Ok(rate), Ok(spread)) = (rate, spread) {
-                let strategy = AllIn::new(StaticStub::static_stub(), None, None, spread, StaticStub::static_stub());
+                let strategy = AllIn::new(StaticStub::static_stub(), None, None, spread);
 
                 let _: anyhow::Result<BtcDaiOrderForm> = strategy.new_sell(btc_balance, rate);
             }
@@ -583,13 +548,7 @@ mod test {
             ..StaticStub::static_stub()
         };
 
-        let mut strategy = AllIn::new(
-            config,
-            None,
-            None,
-            Spread::static_stub(),
-            StaticStub::static_stub(),
-        );
+        let mut strategy = AllIn::new(config, None, None, Spread::static_stub());
 
         let taken_order = btc_dai_order(Position::Sell, btc(1.5), rate(0.0));
 
@@ -603,13 +562,7 @@ mod test {
 
     #[test]
     fn dai_funds_reserved_upon_taking_buy_order() {
-        let mut strategy = AllIn::new(
-            StaticStub::static_stub(),
-            None,
-            None,
-            Spread::static_stub(),
-            StaticStub::static_stub(),
-        );
+        let mut strategy = AllIn::new(StaticStub::static_stub(), None, None, Spread::static_stub());
 
         let taken_order = btc_dai_order(Position::Buy, btc(1.0), rate(1.5));
 
@@ -623,13 +576,7 @@ mod test {
 
     #[test]
     fn dai_funds_reserved_upon_taking_buy_order_with_fee() {
-        let mut strategy = AllIn::new(
-            StaticStub::static_stub(),
-            None,
-            None,
-            Spread::static_stub(),
-            StaticStub::static_stub(),
-        );
+        let mut strategy = AllIn::new(StaticStub::static_stub(), None, None, Spread::static_stub());
 
         let taken_order = btc_dai_order(Position::Buy, btc(1.0), rate(1.5));
 
@@ -643,13 +590,7 @@ mod test {
 
     #[test]
     fn not_enough_btc_funds_to_reserve_for_a_sell_order() {
-        let mut strategy = AllIn::new(
-            StaticStub::static_stub(),
-            None,
-            None,
-            Spread::static_stub(),
-            StaticStub::static_stub(),
-        );
+        let mut strategy = AllIn::new(StaticStub::static_stub(), None, None, Spread::static_stub());
 
         let taken_order = btc_dai_order(Position::Sell, btc(1.5), rate(0.0));
 
@@ -662,13 +603,7 @@ mod test {
 
     #[test]
     fn not_enough_btc_funds_to_reserve_for_a_buy_order() {
-        let mut strategy = AllIn::new(
-            StaticStub::static_stub(),
-            None,
-            None,
-            Spread::static_stub(),
-            StaticStub::static_stub(),
-        );
+        let mut strategy = AllIn::new(StaticStub::static_stub(), None, None, Spread::static_stub());
 
         let taken_order = btc_dai_order(Position::Buy, btc(1.0), rate(1.5));
 
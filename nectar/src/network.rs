@@ -17,7 +17,7 @@ pub const SEED_LENGTH: usize = 32;
 pub fn new_swarm(seed: Seed, settings: &crate::config::Settings) -> anyhow::Result<Swarm> {
     use anyhow::Context as _;
 
-    let behaviour = Nectar::new(seed);
+    let behaviour = Nectar::new(seed, settings.maker.token_allowlist.clone());
 
     let local_key_pair = behaviour.identity();
     let local_peer_id = behaviour.peer_id();
@@ -77,14 +77,14 @@ pub struct Nectar {
 }
 
 impl Nectar {
-    fn new(seed: Seed) -> Self {
+    fn new(seed: Seed, token_allowlist: Vec<comit::ethereum::Address>) -> Self {
         let identity = seed.derive_libp2p_identity();
         let peer_id = PeerId::from(identity.public());
 
         Self {
             orderbook: comit::network::Orderbook::new(peer_id, identity.clone()),
             identity,
-            setup_swap: Default::default(),
+            setup_swap: setup_swap::SetupSwap::with_token_allowlist(token_allowlist),
         }
     }
 
@@ -73,6 +73,7 @@ impl crate::StaticStub for comit::BtcDaiOrder {
                 comit::Network::Dev,
             ),
             created_at: time::OffsetDateTime::from_unix_timestamp(0),
+            valid_until: None,
             quantity: Quantity::new(Bitcoin::from_sat(1)),
             price: crate::Rate::try_from(1.0).unwrap().into(),
         }
@@ -90,6 +91,7 @@ pub fn btc_dai_order(
         position,
         swap_protocol: SwapProtocol::new(comit::Role::Alice, Position::Buy, comit::Network::Dev),
         created_at: time::OffsetDateTime::from_unix_timestamp(0),
+        valid_until: None,
         quantity: Quantity::new(btc_quantity),
         price: btc_dai_rate.into(),
     }
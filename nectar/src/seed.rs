@@ -1,11 +1,15 @@
 use ::bitcoin::{
-    hashes::{sha512, Hash, HashEngine, Hmac, HmacEngine},
+    hashes::{sha256, sha512, Hash, HashEngine, Hmac, HmacEngine},
     secp256k1::{self, constants::SECRET_KEY_SIZE, SecretKey},
 };
+use anyhow::Context;
+use bip39::Mnemonic;
 use rand::prelude::*;
-use std::fmt;
+use std::{convert::TryFrom, fmt};
 
 pub const SEED_LENGTH: usize = 32;
+/// Number of words of the BIP39 mnemonic encoding `SEED_LENGTH` bytes.
+const MNEMONIC_WORD_COUNT: usize = 24;
 
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub struct Seed([u8; SEED_LENGTH]);
@@ -21,6 +25,37 @@ impl Seed {
         Ok(Seed(bytes))
     }
 
+    /// Restores a seed from its BIP39 mnemonic, see `to_mnemonic`.
+    ///
+    /// The seed is the entropy encoded by the mnemonic, which is what makes
+    /// the round trip lossless. BIP39 passphrases only apply when stretching
+    /// the mnemonic into a 64 bytes seed and therefore have to be empty.
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> anyhow::Result<Seed> {
+        if !passphrase.is_empty() {
+            anyhow::bail!("mnemonic passphrases are not supported");
+        }
+
+        let mnemonic = Mnemonic::parse(phrase).context("invalid mnemonic")?;
+        let bytes =
+            <[u8; SEED_LENGTH]>::try_from(mnemonic.to_entropy().as_slice()).with_context(|| {
+                format!(
+                    "mnemonic has {} words, expected {}",
+                    mnemonic.word_count(),
+                    MNEMONIC_WORD_COUNT
+                )
+            })?;
+
+        Ok(Seed(bytes))
+    }
+
+    /// Encodes the seed as a 24 words BIP39 mnemonic, a human friendly backup
+    /// of the seed.
+    pub fn to_mnemonic(&self) -> String {
+        Mnemonic::from_entropy(&self.0)
+            .expect("32 bytes are valid BIP39 entropy")
+            .to_string()
+    }
+
     pub fn bytes(&self) -> [u8; SEED_LENGTH] {
         self.0
     }
@@ -45,6 +80,15 @@ impl Seed {
         (secret_key, chain_code.to_vec())
     }
 
+    /// The key used to encrypt the database at rest.
+    pub fn derive_database_key(&self) -> [u8; 32] {
+        let mut engine = sha256::HashEngine::default();
+        engine.input(&self.0);
+        engine.input(b"DATABASE");
+
+        sha256::Hash::from_engine(engine).into_inner()
+    }
+
     /// Do note that the secret key returned only contains the seed bytes.
     /// This helper function provides a different format but does not
     /// manipulate the seed. Further computation may be needed to match
@@ -81,9 +125,65 @@ pub enum Error {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn generate_random_seed() {
         let _ = Seed::random().unwrap();
     }
+
+    #[test]
+    fn bip39_test_vectors() {
+        let vectors = [
+            (
+                [0x00; SEED_LENGTH],
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+            ),
+            (
+                [0x7f; SEED_LENGTH],
+                "legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth title",
+            ),
+            (
+                [0x80; SEED_LENGTH],
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic bless",
+            ),
+            (
+                [0xff; SEED_LENGTH],
+                "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote",
+            ),
+        ];
+
+        for (bytes, phrase) in vectors.iter() {
+            let seed = Seed::from(*bytes);
+
+            assert_eq!(seed.to_mnemonic(), *phrase);
+            assert_eq!(Seed::from_mnemonic(phrase, "").unwrap(), seed);
+        }
+    }
+
+    #[test]
+    fn mnemonic_with_bad_checksum_is_rejected() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+
+        assert!(Seed::from_mnemonic(phrase, "").is_err());
+    }
+
+    #[test]
+    fn mnemonic_of_wrong_length_is_rejected() {
+        let phrase =
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+        assert!(Seed::from_mnemonic(phrase, "").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn mnemonic_round_trip(bytes in any::<[u8; SEED_LENGTH]>()) {
+            let seed = Seed::from(bytes);
+
+            let restored = Seed::from_mnemonic(&seed.to_mnemonic(), "").unwrap();
+
+            prop_assert_eq!(restored, seed);
+        }
+    }
 }
@@ -4,18 +4,22 @@ pub mod bitcoin;
 pub mod ethereum;
 pub mod hbit;
 pub mod herc20;
+pub mod refund;
+pub mod reorg;
+pub mod shutdown;
 
 use crate::{
     command::FinishedSwap,
-    database::{Load, Save},
+    database::{Beta, Load, Save},
     network::ActivePeer,
+    swap::shutdown::{Shutdown, ShuttingDown},
     SwapId,
 };
-use ::comit::btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector};
+use ::comit::btsieve::ethereum::Web3Connector;
 use anyhow::Result;
-use comit::swap::Action;
-use futures::{channel::mpsc, SinkExt, Stream, TryStreamExt};
-use std::{future::Future, sync::Arc};
+use comit::{clock::SystemClock, swap::Action};
+use futures::{channel::mpsc, SinkExt, Stream, TryFutureExt, TryStreamExt};
+use std::{collections::HashSet, future::Future, sync::Arc};
 use time::OffsetDateTime;
 use tracing_futures::Instrument;
 
@@ -25,17 +29,36 @@ pub use crate::database::Database;
 pub enum SwapKind {
     HbitHerc20(SwapParams),
     Herc20Hbit(SwapParams),
+    HbitHbit(HbitHbitParams),
 }
 
 impl SwapKind {
-    pub fn params(&self) -> SwapParams {
+    pub fn swap_id(&self) -> SwapId {
         match self {
-            SwapKind::HbitHerc20(params) | SwapKind::Herc20Hbit(params) => params.clone(),
+            SwapKind::HbitHerc20(params) | SwapKind::Herc20Hbit(params) => params.swap_id,
+            SwapKind::HbitHbit(params) => params.swap_id,
         }
     }
 
-    pub fn swap_id(&self) -> SwapId {
-        self.params().swap_id
+    pub fn taker(&self) -> ActivePeer {
+        match self {
+            SwapKind::HbitHerc20(params) | SwapKind::Herc20Hbit(params) => params.taker.clone(),
+            SwapKind::HbitHbit(params) => params.taker.clone(),
+        }
+    }
+
+    pub fn secret_hash(&self) -> comit::SecretHash {
+        match self {
+            SwapKind::HbitHerc20(params) | SwapKind::Herc20Hbit(params) => params.secret_hash,
+            SwapKind::HbitHbit(params) => params.secret_hash,
+        }
+    }
+
+    pub fn start_of_swap(&self) -> OffsetDateTime {
+        match self {
+            SwapKind::HbitHerc20(params) | SwapKind::Herc20Hbit(params) => params.start_of_swap,
+            SwapKind::HbitHbit(params) => params.start_of_swap,
+        }
     }
 }
 
@@ -49,6 +72,18 @@ pub struct SwapParams {
     pub taker: ActivePeer,
 }
 
+/// A swap of bitcoin on one network against bitcoin on another, e.g. mainnet
+/// against signet.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HbitHbitParams {
+    pub alpha_params: hbit::Params,
+    pub beta_params: hbit::Params,
+    pub secret_hash: comit::SecretHash,
+    pub start_of_swap: OffsetDateTime,
+    pub swap_id: SwapId,
+    pub taker: ActivePeer,
+}
+
 #[cfg(test)]
 impl crate::StaticStub for SwapParams {
     fn static_stub() -> Self {
@@ -113,10 +148,23 @@ mod arbitrary {
 
     impl Arbitrary for SwapKind {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
-            if bool::arbitrary(g) {
-                SwapKind::HbitHerc20(SwapParams::arbitrary(g))
-            } else {
-                SwapKind::Herc20Hbit(SwapParams::arbitrary(g))
+            match u8::arbitrary(g) % 3 {
+                0 => SwapKind::HbitHerc20(SwapParams::arbitrary(g)),
+                1 => SwapKind::Herc20Hbit(SwapParams::arbitrary(g)),
+                _ => SwapKind::HbitHbit(HbitHbitParams::arbitrary(g)),
+            }
+        }
+    }
+
+    impl Arbitrary for HbitHbitParams {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            HbitHbitParams {
+                alpha_params: hbit::Params::arbitrary(g),
+                beta_params: hbit::Params::arbitrary(g),
+                secret_hash: SecretHash::arbitrary(g),
+                start_of_swap: OffsetDateTime::from_unix_timestamp(u32::arbitrary(g) as i64),
+                swap_id: SwapId::arbitrary(g),
+                taker: ActivePeer::arbitrary(g),
             }
         }
     }
@@ -242,7 +290,7 @@ mod tests {
             let seed = Seed::random().unwrap();
             let bitcoin_wallet = {
                 let wallet =
-                    crate::bitcoin::Wallet::new(seed, bitcoind_url.clone(), bitcoin_network)
+                    crate::bitcoin::Wallet::new(seed, bitcoind_url.clone(), bitcoin_network, None)
                         .await?;
 
                 bitcoin_blockchain
@@ -273,16 +321,17 @@ mod tests {
             let ethereum_gas_price =
                 crate::ethereum::GasPrice::geth_url(ethereum_blockchain.node_url.clone());
 
-            let bitcoin_fee = crate::bitcoin::Fee::new(
-                crate::config::Bitcoin {
-                    network: ledger::Bitcoin::Regtest,
-                    bitcoind: crate::config::Bitcoind {
-                        node_url: bitcoind_url.clone(),
-                    },
-                    fees: crate::config::BitcoinFees::SatsPerByte(bitcoin::Amount::from_sat(50)),
+            let bitcoin_fee = crate::bitcoin::Fee::new(crate::config::Bitcoin {
+                network: ledger::Bitcoin::Regtest,
+                bitcoind: crate::config::Bitcoind {
+                    node_url: bitcoind_url.clone(),
                 },
-                crate::bitcoin::Client::new(bitcoind_url.clone()),
-            );
+                fees: crate::config::BitcoinFees::SatsPerByte(bitcoin::Amount::from_sat(50)),
+                additional_networks: Vec::new(),
+                watch_only_descriptors: Vec::new(),
+                change_policy: Default::default(),
+                wallet_passphrase: None,
+            });
 
             (
                 bitcoin::Wallet {
@@ -301,7 +350,8 @@ mod tests {
         let (bob_bitcoin_wallet, bob_ethereum_wallet) = {
             let seed = Seed::random().unwrap();
             let bitcoin_wallet =
-                crate::bitcoin::Wallet::new(seed, bitcoind_url.clone(), bitcoin_network).await?;
+                crate::bitcoin::Wallet::new(seed, bitcoind_url.clone(), bitcoin_network, None)
+                    .await?;
             let ethereum_wallet = crate::ethereum::Wallet::new(
                 seed,
                 ethereum_node_url,
@@ -329,16 +379,17 @@ mod tests {
             let ethereum_gas_price =
                 crate::ethereum::GasPrice::geth_url(ethereum_blockchain.node_url.clone());
 
-            let bitcoin_fee = crate::bitcoin::Fee::new(
-                crate::config::Bitcoin {
-                    network: ledger::Bitcoin::Regtest,
-                    bitcoind: crate::config::Bitcoind {
-                        node_url: bitcoind_url.clone(),
-                    },
-                    fees: crate::config::BitcoinFees::SatsPerByte(bitcoin::Amount::from_sat(50)),
+            let bitcoin_fee = crate::bitcoin::Fee::new(crate::config::Bitcoin {
+                network: ledger::Bitcoin::Regtest,
+                bitcoind: crate::config::Bitcoind {
+                    node_url: bitcoind_url.clone(),
                 },
-                crate::bitcoin::Client::new(bitcoind_url.clone()),
-            );
+                fees: crate::config::BitcoinFees::SatsPerByte(bitcoin::Amount::from_sat(50)),
+                additional_networks: Vec::new(),
+                watch_only_descriptors: Vec::new(),
+                change_policy: Default::default(),
+                wallet_passphrase: None,
+            });
 
             (
                 bitcoin::Wallet {
@@ -397,6 +448,7 @@ mod tests {
                         swap_id,
                         db: alice_db.clone(),
                         wallet: alice_bitcoin_wallet.clone(),
+                        htlc: hbit::Htlc::Primary,
                     },
                     herc20::Facade {
                         swap_id,
@@ -408,10 +460,13 @@ mod tests {
                     secret,
                     start_of_swap,
                 ),
-                alice_bitcoin_wallet.clone(),
+                alice_bitcoin_wallet.clone().into(),
                 alice_ethereum_wallet.clone(),
                 alice_db.clone(),
                 swap_id,
+                hbit::Htlc::Primary,
+                Shutdown::default(),
+                reorg::Orphans::default().watch(swap_id),
             )
         };
 
@@ -445,6 +500,7 @@ mod tests {
                         swap_id,
                         db: bob_db.clone(),
                         wallet: bob_bitcoin_wallet.clone(),
+                        htlc: hbit::Htlc::Primary,
                     },
                     herc20::Facade {
                         swap_id,
@@ -456,10 +512,13 @@ mod tests {
                     herc20_params.clone(),
                     start_of_swap,
                 ),
-                bob_bitcoin_wallet.clone(),
+                bob_bitcoin_wallet.clone().into(),
                 bob_ethereum_wallet.clone(),
                 bob_db.clone(),
                 swap_id,
+                hbit::Htlc::Primary,
+                Shutdown::default(),
+                reorg::Orphans::default().watch(swap_id),
             )
         };
 
@@ -522,23 +581,22 @@ mod tests {
 #[derive(Debug, Clone)]
 pub struct SwapExecutor {
     db: Arc<Database>,
-    bitcoin_wallet: Arc<crate::bitcoin::Wallet>,
-    bitcoin_fee: crate::bitcoin::Fee,
+    bitcoin_wallets: bitcoin::Wallets,
     ethereum_wallet: Arc<crate::ethereum::Wallet>,
     ethereum_gas_price: crate::ethereum::GasPrice,
     finished_swap_sender: mpsc::Sender<FinishedSwap>,
-    bitcoin_connector: Arc<BitcoindConnector>,
     ethereum_connector: Arc<Web3Connector>,
+    shutdown: Shutdown,
+    orphans: reorg::Orphans,
+    running: refund::Running,
 }
 
 impl SwapExecutor {
     pub fn new(
         db: Arc<Database>,
-        bitcoin_wallet: Arc<crate::bitcoin::Wallet>,
-        bitcoin_fee: crate::bitcoin::Fee,
+        bitcoin_wallets: bitcoin::Wallets,
         ethereum_wallet: Arc<crate::ethereum::Wallet>,
         ethereum_gas_price: crate::ethereum::GasPrice,
-        bitcoin_connector: Arc<BitcoindConnector>,
         ethereum_connector: Arc<Web3Connector>,
     ) -> (Self, mpsc::Receiver<FinishedSwap>) {
         // buffer increases by 1 for every clone of `Sender` and we use every sender
@@ -548,28 +606,98 @@ impl SwapExecutor {
 
         let executor = Self {
             db,
-            bitcoin_wallet,
-            bitcoin_fee,
+            bitcoin_wallets,
             ethereum_wallet,
             ethereum_gas_price,
             finished_swap_sender,
-            bitcoin_connector,
             ethereum_connector,
+            shutdown: Shutdown::default(),
+            orphans: reorg::Orphans::default(),
+            running: refund::Running::default(),
         };
 
         (executor, finished_swap_receiver)
     }
+
+    /// The handle to shut down the execution of all swaps.
+    pub fn shutdown(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
+    /// Spawns a task that refunds the HTLCs we funded once they expired, for
+    /// the swaps that are not executed anymore.
+    pub fn spawn_refund_watcher(&self) {
+        let refunder = refund::Refunder {
+            db: self.db.clone(),
+            bitcoin_wallets: self.bitcoin_wallets.clone(),
+            ethereum_wallet: ethereum::Wallet {
+                inner: self.ethereum_wallet.clone(),
+                connector: self.ethereum_connector.clone(),
+                gas_price: self.ethereum_gas_price.clone(),
+            },
+        };
+
+        tokio::spawn(refund::watch(
+            self.db.clone(),
+            refunder,
+            self.running.clone(),
+            SystemClock,
+            refund::POLL_INTERVAL,
+        ));
+    }
+
+    /// Spawns a task that forgets herc20 events orphaned by a reorg.
+    pub fn spawn_reorg_watcher(&self) {
+        tokio::spawn(reorg::watch(
+            self.db.clone(),
+            self.ethereum_connector.clone(),
+            self.orphans.clone(),
+            reorg::POLL_INTERVAL,
+        ));
+    }
 }
 
 impl SwapExecutor {
+    /// Resumes the execution of swaps loaded from the database.
+    ///
+    /// Swaps whose secret was already revealed are resumed first because the
+    /// counterparty can use that secret at any time, so the dependent redeem
+    /// must not queue behind the other swaps.
+    pub fn resume(&self, mut swaps: Vec<SwapKind>) -> Result<()> {
+        let revealed = self
+            .db
+            .swaps_with_revealed_secret()?
+            .into_iter()
+            .map(|(swap_id, _)| swap_id)
+            .collect::<HashSet<_>>();
+
+        swaps.sort_by_key(|swap| !revealed.contains(&swap.swap_id()));
+
+        for swap in swaps {
+            if revealed.contains(&swap.swap_id()) {
+                tracing::info!(
+                    "resuming swap {} whose secret was already revealed",
+                    swap.swap_id()
+                );
+            }
+            self.execute(swap);
+        }
+
+        Ok(())
+    }
+
     pub fn execute(&self, swap: SwapKind) {
+        if self.shutdown.is_initiated() {
+            tracing::info!(
+                "not executing swap {} because we are shutting down",
+                swap.swap_id()
+            );
+            return;
+        }
+
         let execution = execute(
             swap.clone(),
-            bitcoin::Wallet {
-                inner: self.bitcoin_wallet.clone(),
-                connector: self.bitcoin_connector.clone(),
-                fee: self.bitcoin_fee.clone(),
-            },
+            self.bitcoin_wallets.clone(),
             ethereum::Wallet {
                 inner: self.ethereum_wallet.clone(),
                 connector: self.ethereum_connector.clone(),
@@ -577,10 +705,29 @@ impl SwapExecutor {
             },
             self.db.clone(),
             self.finished_swap_sender.clone(),
+            self.shutdown.clone(),
+            self.orphans.watch(swap.swap_id()),
         );
+        let executor = self.clone();
+        let swap_id = swap.swap_id();
+        self.running.insert(swap_id);
 
         tokio::spawn(async move {
-            if let Err(e) = execution.await {
+            let result = execution.await;
+            executor.running.remove(&swap_id);
+
+            if let Err(e) = result {
+                if e.is::<ShuttingDown>() {
+                    tracing::info!("suspended execution for swap {}", swap.swap_id());
+                    return;
+                }
+
+                if e.is::<reorg::EventsOrphaned>() {
+                    tracing::info!("executing swap {} again after a reorg", swap.swap_id());
+                    executor.execute(swap);
+                    return;
+                }
+
                 let err = e.context(format!("failed execution for swap {}", swap.swap_id()));
 
                 sentry::integrations::anyhow::capture_anyhow(&err);
@@ -592,10 +739,12 @@ impl SwapExecutor {
 
 async fn execute(
     swap: SwapKind,
-    bitcoin_wallet: bitcoin::Wallet,
+    bitcoin_wallets: bitcoin::Wallets,
     ethereum_wallet: ethereum::Wallet,
     db: Arc<Database>,
     mut sender: mpsc::Sender<FinishedSwap>,
+    shutdown: Shutdown,
+    orphaned: reorg::Orphaned,
 ) -> Result<()> {
     match swap.clone() {
         SwapKind::HbitHerc20(SwapParams {
@@ -605,11 +754,13 @@ async fn execute(
             swap_id,
             ..
         }) => {
+            let bitcoin_wallet = bitcoin_wallets.on(hbit_params.shared.network)?;
             let swap = comit::swap::hbit_herc20_bob(
                 hbit::Facade {
                     swap_id,
                     db: db.clone(),
                     wallet: bitcoin_wallet.clone(),
+                    htlc: hbit::Htlc::Primary,
                 },
                 herc20::Facade {
                     swap_id,
@@ -623,7 +774,17 @@ async fn execute(
             )
             .instrument(tracing::error_span!("hbit_herc20_bob", %swap_id));
 
-            drive(swap, bitcoin_wallet, ethereum_wallet, db, swap_id).await?;
+            drive(
+                swap,
+                bitcoin_wallets,
+                ethereum_wallet,
+                db,
+                swap_id,
+                hbit::Htlc::Primary,
+                shutdown,
+                orphaned,
+            )
+            .await?;
         }
         SwapKind::Herc20Hbit(SwapParams {
             hbit_params,
@@ -632,6 +793,7 @@ async fn execute(
             swap_id,
             ..
         }) => {
+            let bitcoin_wallet = bitcoin_wallets.on(hbit_params.shared.network)?;
             let swap = comit::swap::herc20_hbit_bob(
                 herc20::Facade {
                     swap_id,
@@ -642,6 +804,7 @@ async fn execute(
                     swap_id,
                     db: db.clone(),
                     wallet: bitcoin_wallet.clone(),
+                    htlc: hbit::Htlc::Primary,
                 },
                 herc20_params,
                 hbit_params,
@@ -649,11 +812,61 @@ async fn execute(
             )
             .instrument(tracing::error_span!("herc20_hbit_bob", %swap_id));
 
-            drive(swap, bitcoin_wallet, ethereum_wallet, db, swap_id).await?;
+            drive(
+                swap,
+                bitcoin_wallets,
+                ethereum_wallet,
+                db,
+                swap_id,
+                hbit::Htlc::Primary,
+                shutdown,
+                orphaned,
+            )
+            .await?;
+        }
+        SwapKind::HbitHbit(HbitHbitParams {
+            alpha_params,
+            beta_params,
+            start_of_swap,
+            swap_id,
+            ..
+        }) => {
+            let swap = comit::swap::hbit_hbit_bob(
+                hbit::Facade {
+                    swap_id,
+                    db: db.clone(),
+                    wallet: bitcoin_wallets.on(alpha_params.shared.network)?,
+                    htlc: hbit::Htlc::Primary,
+                },
+                hbit::Facade {
+                    swap_id,
+                    db: db.clone(),
+                    wallet: bitcoin_wallets.on(beta_params.shared.network)?,
+                    htlc: hbit::Htlc::Beta,
+                },
+                crate::SECP.clone(),
+                alpha_params,
+                beta_params,
+                start_of_swap,
+            )
+            .instrument(tracing::error_span!("hbit_hbit_bob", %swap_id));
+
+            // as Bob we fund the beta HTLC and redeem the alpha one
+            drive(
+                swap,
+                bitcoin_wallets,
+                ethereum_wallet,
+                db,
+                swap_id,
+                hbit::Htlc::Beta,
+                shutdown,
+                orphaned,
+            )
+            .await?;
         }
     };
 
-    let active_peer = swap.params().taker;
+    let active_peer = swap.taker();
     let swap_id = swap.swap_id();
     if let Err(e) = sender
         .send(FinishedSwap::new(
@@ -676,42 +889,84 @@ async fn execute(
     Ok(())
 }
 
+/// Executes the actions yielded by `swap`, the hbit HTLC we fund is
+/// `hbit_funding`. Hbit actions are executed with the wallet of their network.
+///
+/// Fails with [`reorg::EventsOrphaned`] if a reorg orphaned one of the events
+/// of the swap while it waits for its next action. An action that is being
+/// executed is never interrupted.
+#[allow(clippy::too_many_arguments)]
 async fn drive<E>(
     mut swap: impl Stream<Item = Result<Action, E>> + Unpin,
-    bitcoin_wallet: bitcoin::Wallet,
+    bitcoin_wallets: bitcoin::Wallets,
     ethereum_wallet: ethereum::Wallet,
     db: Arc<Database>,
     swap_id: SwapId,
+    hbit_funding: hbit::Htlc,
+    shutdown: Shutdown,
+    mut orphaned: reorg::Orphaned,
 ) -> Result<()>
 where
     E: std::error::Error + Send + Sync + 'static,
 {
-    while let Some(action) = swap.try_next().await? {
+    loop {
+        let action = tokio::select! {
+            action = swap.try_next() => action?,
+            _ = orphaned.recv() => return Err(reorg::EventsOrphaned(swap_id).into()),
+        };
+        let action = match action {
+            Some(action) => action,
+            None => break,
+        };
+
         match action {
             Action::Herc20Deploy(inner) => {
                 let action = ethereum_wallet.execute_deploy(inner);
 
-                execute_idempotently(db.as_ref(), swap_id, action).await?;
+                shutdown
+                    .guard(execute_idempotently(db.as_ref(), swap_id, action))
+                    .await?;
             }
             Action::Herc20Fund(inner) => {
                 let action = ethereum_wallet.execute_fund(inner);
 
-                execute_idempotently(db.as_ref(), swap_id, action).await?;
+                shutdown
+                    .guard(execute_idempotently(db.as_ref(), swap_id, action))
+                    .await?;
             }
             Action::Herc20Redeem(inner, secret) => {
                 let action = ethereum_wallet.execute_redeem(inner, secret);
 
-                execute_idempotently(db.as_ref(), swap_id, action).await?;
+                shutdown
+                    .guard(execute_idempotently(db.as_ref(), swap_id, action))
+                    .await?;
             }
             Action::HbitFund(inner) => {
-                let action = bitcoin_wallet.execute_fund(inner);
-
-                execute_idempotently(db.as_ref(), swap_id, action).await?;
+                let wallet = bitcoin_wallets.on(inner.network)?;
+                let action = wallet.execute_fund(inner);
+
+                match hbit_funding {
+                    hbit::Htlc::Primary => {
+                        shutdown
+                            .guard(execute_idempotently(db.as_ref(), swap_id, action))
+                            .await?
+                    }
+                    hbit::Htlc::Beta => {
+                        let action = action.map_ok(Beta);
+
+                        shutdown
+                            .guard(execute_idempotently(db.as_ref(), swap_id, action))
+                            .await?
+                    }
+                }
             }
             Action::HbitRedeem(inner, secret) => {
-                let action = bitcoin_wallet.execute_redeem(inner, secret);
+                let wallet = bitcoin_wallets.on(inner.network)?;
+                let action = wallet.execute_redeem(inner, secret);
 
-                execute_idempotently(db.as_ref(), swap_id, action).await?;
+                shutdown
+                    .guard(execute_idempotently(db.as_ref(), swap_id, action))
+                    .await?;
             }
         }
     }
@@ -1,7 +1,8 @@
-use crate::{bitcoin, swap::hbit};
-use anyhow::Result;
+use crate::{bitcoin, config, swap::hbit, Seed};
+use anyhow::{Context, Result};
 use comit::{
-    btsieve::{BlockByHash, LatestBlock},
+    btsieve::{bitcoin::BitcoindConnector, BlockByHash, LatestBlock},
+    ledger,
     swap::actions::{SendToAddress, SpendOutput},
     Secret,
 };
@@ -19,7 +20,7 @@ pub struct Wallet {
 
 impl Wallet {
     pub async fn execute_fund(&self, action: SendToAddress) -> Result<hbit::Funded> {
-        let kbyte_fee_rate = self.fee.kvbyte_rate().await?;
+        let kbyte_fee_rate = self.fee.kvbyte_rate(&self.inner).await?;
 
         let location = self
             .inner
@@ -39,14 +40,11 @@ impl Wallet {
         secret: Secret, /* Receiving the secret here is a bit of a hack but otherwise, we have
                          * to get it out of the action again which is even more cumbersome. */
     ) -> Result<hbit::Redeemed> {
-        let vbyte_rate = self.fee.vbyte_rate().await?;
+        let vbyte_rate = self.fee.vbyte_rate(&self.inner).await?;
         let network = action.network;
         let transaction = action.sign(&crate::SECP, vbyte_rate)?;
 
-        let txid = self
-            .inner
-            .send_raw_transaction(transaction, network)
-            .await?;
+        let txid = self.broadcast(transaction, network).await?;
 
         tracing::info!("signed hbit redeem transaction {}", txid);
 
@@ -55,6 +53,103 @@ impl Wallet {
             secret,
         })
     }
+
+    pub async fn execute_refund(&self, action: SpendOutput) -> Result<hbit::Refunded> {
+        let vbyte_rate = self.fee.vbyte_rate(&self.inner).await?;
+        let network = action.network;
+        let transaction = action.sign(&crate::SECP, vbyte_rate)?;
+
+        let txid = self.broadcast(transaction, network).await?;
+
+        tracing::info!("signed hbit refund transaction {}", txid);
+
+        Ok(hbit::Refunded { transaction: txid })
+    }
+
+    /// bitcoind only gives an opaque error if it refuses a broadcast
+    /// transaction, hence we first test whether it would accept it to report
+    /// why it does not.
+    async fn broadcast(
+        &self,
+        transaction: Transaction,
+        network: ledger::Bitcoin,
+    ) -> Result<bitcoin::Txid> {
+        let result = self.inner.test_mempool_accept(&transaction).await?;
+        if !result.allowed {
+            anyhow::bail!(
+                "bitcoind would not accept transaction {} into its mempool: {}",
+                transaction.txid(),
+                result.reject_reason.unwrap_or_default()
+            );
+        }
+
+        Ok(self
+            .inner
+            .send_raw_transaction(transaction, network)
+            .await?)
+    }
+}
+
+/// The wallets we act with on each Bitcoin network, the legs of hbit<->hbit
+/// swaps may be on different networks.
+#[derive(Debug, Clone)]
+pub struct Wallets(Vec<Wallet>);
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("no Bitcoin wallet is configured for network {0}")]
+pub struct NoWalletForNetwork(pub ledger::Bitcoin);
+
+impl Wallets {
+    /// `main` is the wallet of the configured network, the others are opened
+    /// on the additional networks.
+    pub async fn new(main: Wallet, seed: Seed, settings: &config::Bitcoin) -> Result<Self> {
+        let mut wallets = vec![main];
+
+        for additional in &settings.additional_networks {
+            let network = additional.network;
+            let node_url = additional.node_url.clone();
+            let inner = bitcoin::Wallet::new(
+                seed,
+                node_url.clone(),
+                network,
+                settings.wallet_passphrase.clone(),
+            )
+            .await
+            .with_context(|| format!("failed to initialise Bitcoin wallet on {}", network))?
+            .with_fees(settings.fees)
+            .with_change_policy(settings.change_policy);
+            let fee = bitcoin::Fee::new(config::Bitcoin {
+                network,
+                bitcoind: config::Bitcoind {
+                    node_url: node_url.clone(),
+                },
+                ..settings.clone()
+            });
+
+            wallets.push(Wallet {
+                inner: Arc::new(inner),
+                fee,
+                connector: Arc::new(BitcoindConnector::new(node_url)?),
+            });
+        }
+
+        Ok(Self(wallets))
+    }
+
+    /// The wallet to fund, redeem and refund HTLCs on `network` with.
+    pub fn on(&self, network: ledger::Bitcoin) -> Result<Wallet, NoWalletForNetwork> {
+        self.0
+            .iter()
+            .find(|wallet| wallet.inner.ledger == network)
+            .cloned()
+            .ok_or(NoWalletForNetwork(network))
+    }
+}
+
+impl From<Wallet> for Wallets {
+    fn from(wallet: Wallet) -> Self {
+        Self(vec![wallet])
+    }
 }
 
 #[async_trait::async_trait]
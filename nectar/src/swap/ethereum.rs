@@ -1,4 +1,7 @@
-use crate::swap::herc20;
+use crate::{
+    ethereum::{GasPriceOracle, Urgency},
+    swap::herc20,
+};
 use comit::btsieve::LatestBlock;
 use std::sync::Arc;
 
@@ -17,8 +20,9 @@ pub struct Wallet {
 
 impl Wallet {
     pub async fn execute_deploy(&self, action: DeployContract) -> anyhow::Result<herc20::Deployed> {
-        let gas_price = self.gas_price.gas_price().await?;
-        let (tx_hash, contract_address) = self.inner.deploy_contract(action, gas_price).await?;
+        let gas_price = self.gas_price.gas_price(Urgency::Normal).await?;
+        let (tx_hash, contract_address) =
+            self.inner.deploy_contract(action, gas_price.into()).await?;
 
         tracing::info!("signed herc20 deploy transaction {}", tx_hash);
 
@@ -29,8 +33,8 @@ impl Wallet {
     }
 
     pub async fn execute_fund(&self, action: CallContract) -> anyhow::Result<herc20::Funded> {
-        let gas_price = self.gas_price.gas_price().await?;
-        let tx_hash = self.inner.call_contract(action, gas_price).await?;
+        let gas_price = self.gas_price.gas_price(Urgency::Normal).await?;
+        let tx_hash = self.inner.call_contract(action, gas_price.into()).await?;
 
         tracing::info!("signed herc20 fund transaction {}", tx_hash);
 
@@ -45,8 +49,9 @@ impl Wallet {
         secret: Secret, /* Receiving the secret here is a bit of a hack but otherwise, we have
                          * to get it out of the action again which is even more cumbersome. */
     ) -> anyhow::Result<herc20::Redeemed> {
-        let gas_price = self.gas_price.gas_price().await?;
-        let tx_hash = self.inner.call_contract(action, gas_price).await?;
+        // We have to redeem before the counterparty can refund
+        let gas_price = self.gas_price.gas_price(Urgency::Fast).await?;
+        let tx_hash = self.inner.call_contract(action, gas_price.into()).await?;
 
         tracing::info!("signed herc20 redeem transaction {}", tx_hash);
 
@@ -55,6 +60,17 @@ impl Wallet {
             secret,
         })
     }
+
+    pub async fn execute_refund(&self, action: CallContract) -> anyhow::Result<herc20::Refunded> {
+        let gas_price = self.gas_price.gas_price(Urgency::Normal).await?;
+        let tx_hash = self.inner.call_contract(action, gas_price.into()).await?;
+
+        tracing::info!("signed herc20 refund transaction {}", tx_hash);
+
+        Ok(herc20::Refunded {
+            transaction: tx_hash,
+        })
+    }
 }
 
 #[async_trait::async_trait]
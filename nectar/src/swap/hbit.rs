@@ -4,12 +4,13 @@ pub use comit::{
 };
 
 use crate::{
-    database::{Load, Save},
+    database::{Beta, Load, Save, SwapCheckpoints},
     swap::{bitcoin::Wallet, Database},
     SwapId,
 };
 use backoff::{backoff::Constant, future::FutureOperation};
 use comit::{
+    btsieve::Checkpoints,
     ledger,
     swap::hbit::{WatchForFunded, WatchForRedeemed},
 };
@@ -17,10 +18,47 @@ use futures::TryFutureExt;
 use std::{sync::Arc, time::Duration};
 use time::OffsetDateTime;
 
+/// Which hbit HTLC of a swap a facade watches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Htlc {
+    /// The only hbit HTLC of a swap, or the alpha one of a hbit<->hbit swap.
+    Primary,
+    /// The beta HTLC of a hbit<->hbit swap.
+    Beta,
+}
+
+/// Loads the event `E` of the given HTLC of a swap.
+pub fn load<E>(db: &Database, swap_id: SwapId, htlc: Htlc) -> anyhow::Result<Option<E>>
+where
+    Database: Load<E> + Load<Beta<E>>,
+{
+    match htlc {
+        Htlc::Primary => Load::<E>::load(db, swap_id),
+        Htlc::Beta => {
+            let event = Load::<Beta<E>>::load(db, swap_id)?;
+
+            Ok(event.map(|Beta(event)| event))
+        }
+    }
+}
+
+/// Saves the event `E` of the given HTLC of a swap.
+pub async fn save<E>(db: &Database, event: E, swap_id: SwapId, htlc: Htlc) -> anyhow::Result<()>
+where
+    Database: Save<E> + Save<Beta<E>>,
+    E: Send + 'static,
+{
+    match htlc {
+        Htlc::Primary => Save::<E>::save(db, event, swap_id).await,
+        Htlc::Beta => Save::<Beta<E>>::save(db, Beta(event), swap_id).await,
+    }
+}
+
 pub struct Facade {
     pub wallet: Wallet,
     pub db: Arc<Database>,
     pub swap_id: SwapId,
+    pub htlc: Htlc,
 }
 
 impl Facade {
@@ -42,39 +80,93 @@ impl Facade {
             })
             .await;
     }
-}
 
-#[async_trait::async_trait]
-impl WatchForFunded for Facade {
-    async fn watch_for_funded(
+    async fn watch_for_funded_since<C>(
         &self,
+        checkpoints: &C,
         params: &Params,
         start_of_swap: OffsetDateTime,
-    ) -> Result<Funded, IncorrectlyFunded> {
-        if let Ok(Some(Funded { location })) = self.db.load(self.swap_id) {
-            self.wait_until_confirmed(location.txid, params.shared.network)
-                .await;
-
-            return Ok(Funded { location });
-        }
-
+    ) -> Result<Funded, IncorrectlyFunded>
+    where
+        C: Checkpoints<::bitcoin::BlockHash>,
+    {
         let operation = || {
             comit::hbit::watch_for_funded(
                 self.wallet.connector.as_ref(),
+                checkpoints,
                 &params.shared,
                 start_of_swap,
             )
             .map_err(backoff::Error::Transient)
         };
 
-        let funded = operation
+        operation
             .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
                 tracing::warn!("failed to watch for hbit funding, retrying ...: {:#}", e)
             })
             .await
-            .expect("transient error is never returned")?;
+            .expect("transient error is never returned")
+    }
+
+    async fn watch_for_redeemed_since<C>(
+        &self,
+        checkpoints: &C,
+        params: &Params,
+        fund_event: Funded,
+        start_of_swap: OffsetDateTime,
+    ) -> Redeemed
+    where
+        C: Checkpoints<::bitcoin::BlockHash>,
+    {
+        let operation = || {
+            watch_for_redeemed(
+                self.wallet.connector.as_ref(),
+                checkpoints,
+                &params.shared,
+                fund_event.location,
+                start_of_swap,
+            )
+            .map_err(backoff::Error::Transient)
+        };
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!("failed to watch for hbit redeem, retrying ...: {:#}", e)
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
 
-        let _ = self.db.save(funded, self.swap_id).await;
+#[async_trait::async_trait]
+impl WatchForFunded for Facade {
+    async fn watch_for_funded(
+        &self,
+        params: &Params,
+        start_of_swap: OffsetDateTime,
+    ) -> Result<Funded, IncorrectlyFunded> {
+        if let Ok(Some(Funded { location })) = load(&self.db, self.swap_id, self.htlc) {
+            self.wait_until_confirmed(location.txid, params.shared.network)
+                .await;
+
+            return Ok(Funded { location });
+        }
+
+        let funded = match self.htlc {
+            Htlc::Primary => {
+                let checkpoints = SwapCheckpoints::<Funded>::new(self.db.clone(), self.swap_id);
+                self.watch_for_funded_since(&checkpoints, params, start_of_swap)
+                    .await?
+            }
+            Htlc::Beta => {
+                let checkpoints =
+                    SwapCheckpoints::<Beta<Funded>>::new(self.db.clone(), self.swap_id);
+                self.watch_for_funded_since(&checkpoints, params, start_of_swap)
+                    .await?
+            }
+        };
+
+        let _ = save(&self.db, funded, self.swap_id, self.htlc).await;
 
         Ok(funded)
     }
@@ -91,7 +183,7 @@ impl WatchForRedeemed for Facade {
         if let Ok(Some(Redeemed {
             transaction,
             secret,
-        })) = self.db.load(self.swap_id)
+        })) = load(&self.db, self.swap_id, self.htlc)
         {
             self.wait_until_confirmed(transaction, params.shared.network)
                 .await;
@@ -102,24 +194,21 @@ impl WatchForRedeemed for Facade {
             };
         }
 
-        let operation = || {
-            watch_for_redeemed(
-                self.wallet.connector.as_ref(),
-                &params.shared,
-                fund_event.location,
-                start_of_swap,
-            )
-            .map_err(backoff::Error::Transient)
+        let redeemed = match self.htlc {
+            Htlc::Primary => {
+                let checkpoints = SwapCheckpoints::<Redeemed>::new(self.db.clone(), self.swap_id);
+                self.watch_for_redeemed_since(&checkpoints, params, fund_event, start_of_swap)
+                    .await
+            }
+            Htlc::Beta => {
+                let checkpoints =
+                    SwapCheckpoints::<Beta<Redeemed>>::new(self.db.clone(), self.swap_id);
+                self.watch_for_redeemed_since(&checkpoints, params, fund_event, start_of_swap)
+                    .await
+            }
         };
 
-        let redeemed = operation
-            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
-                tracing::warn!("failed to watch for hbit redeem, retrying ...: {:#}", e)
-            })
-            .await
-            .expect("transient error is never returned");
-
-        let _ = self.db.save(redeemed, self.swap_id).await;
+        let _ = save(&self.db, redeemed, self.swap_id, self.htlc).await;
 
         redeemed
     }
@@ -4,7 +4,7 @@ pub use comit::{
 };
 
 use crate::{
-    database::{Load, Save},
+    database::{Load, Save, SwapCheckpoints},
     swap::{ethereum::Wallet, Database},
     SwapId,
 };
@@ -12,7 +12,7 @@ use backoff::{backoff::Constant, future::FutureOperation};
 use comit::{
     ethereum,
     ethereum::ChainId,
-    herc20::{watch_for_deployed, watch_for_funded, watch_for_redeemed},
+    herc20::{watch_for_confirmed_funded, watch_for_deployed, watch_for_redeemed},
     swap::herc20::{WatchForDeployed, WatchForFunded, WatchForRedeemed},
 };
 use futures::TryFutureExt;
@@ -46,6 +46,15 @@ impl Facade {
     }
 }
 
+/// On mainnet, we don't consider the HTLC funded before the funding
+/// transaction is buried under a few blocks.
+fn min_funding_confirmations(chain_id: ChainId) -> u32 {
+    match chain_id {
+        ChainId::MAINNET => 3,
+        _ => 1,
+    }
+}
+
 #[async_trait::async_trait]
 impl WatchForDeployed for Facade {
     async fn watch_for_deployed(
@@ -67,9 +76,11 @@ impl WatchForDeployed for Facade {
             };
         }
 
+        let checkpoints = SwapCheckpoints::<Deployed>::new(self.db.clone(), self.swap_id);
         let operation = || {
             watch_for_deployed(
                 self.wallet.connector.as_ref(),
+                &checkpoints,
                 params.clone(),
                 utc_start_of_swap,
             )
@@ -107,12 +118,14 @@ impl WatchForFunded for Facade {
             return Ok(Funded { transaction });
         }
 
+        let min_confirmations = min_funding_confirmations(params.chain_id);
         let operation = || {
-            watch_for_funded(
+            watch_for_confirmed_funded(
                 self.wallet.connector.as_ref(),
                 params.clone(),
                 utc_start_of_swap,
                 deploy_event,
+                min_confirmations,
             )
             .map_err(backoff::Error::Transient)
         };
@@ -0,0 +1,414 @@
+//! Refund the HTLCs we funded once their expiry passed.
+//!
+//! If the taker vanishes after we funded, nothing else would get our funds
+//! back. Swaps that are still executed are left to their execution, which
+//! refunds them itself.
+
+use crate::{
+    database::{Load, Save},
+    swap::{bitcoin, ethereum, hbit, herc20, Database, SwapKind},
+    SwapId,
+};
+use anyhow::Result;
+use comit::{clock::Clock, ledger, Timestamp};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// How often we check for swaps that are due for a refund.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The swaps whose execution is in progress.
+#[derive(Debug, Clone, Default)]
+pub struct Running(Arc<Mutex<HashSet<SwapId>>>);
+
+impl Running {
+    pub fn insert(&self, swap_id: SwapId) {
+        let _ = self
+            .0
+            .lock()
+            .expect("no other thread panicked while holding the lock")
+            .insert(swap_id);
+    }
+
+    pub fn remove(&self, swap_id: &SwapId) {
+        let _ = self
+            .0
+            .lock()
+            .expect("no other thread panicked while holding the lock")
+            .remove(swap_id);
+    }
+
+    fn contains(&self, swap_id: &SwapId) -> bool {
+        self.0
+            .lock()
+            .expect("no other thread panicked while holding the lock")
+            .contains(swap_id)
+    }
+}
+
+/// An HTLC we funded that can be refunded.
+#[derive(Clone, Debug)]
+pub enum Refund {
+    Hbit {
+        params: hbit::Params,
+        funded: hbit::Funded,
+        htlc: hbit::Htlc,
+    },
+    Herc20 {
+        params: herc20::Params,
+        deployed: herc20::Deployed,
+    },
+}
+
+#[async_trait::async_trait]
+pub trait ExecuteRefund: Send + Sync {
+    /// Refunds the HTLC and saves the refunded event of the swap.
+    async fn execute_refund(&self, swap_id: SwapId, refund: Refund) -> Result<()>;
+
+    /// The median time past of the given Bitcoin network, which is what the
+    /// expiry of `hbit` HTLCs is checked against.
+    async fn median_time_past(&self, network: ledger::Bitcoin) -> Result<Timestamp>;
+}
+
+#[derive(Debug, Clone)]
+pub struct Refunder {
+    pub db: Arc<Database>,
+    pub bitcoin_wallets: bitcoin::Wallets,
+    pub ethereum_wallet: ethereum::Wallet,
+}
+
+#[async_trait::async_trait]
+impl ExecuteRefund for Refunder {
+    async fn execute_refund(&self, swap_id: SwapId, refund: Refund) -> Result<()> {
+        match refund {
+            Refund::Hbit {
+                params,
+                funded,
+                htlc,
+            } => {
+                let action = params.build_refund_action(&*crate::SECP, funded.location);
+                let refunded = self
+                    .bitcoin_wallets
+                    .on(params.shared.network)?
+                    .execute_refund(action)
+                    .await?;
+
+                hbit::save(&self.db, refunded, swap_id, htlc).await
+            }
+            Refund::Herc20 { params, deployed } => {
+                let action = params.build_refund_action(deployed.location);
+                let refunded = self.ethereum_wallet.execute_refund(action).await?;
+
+                self.db.save(refunded, swap_id).await
+            }
+        }
+    }
+
+    async fn median_time_past(&self, network: ledger::Bitcoin) -> Result<Timestamp> {
+        let wallet = self.bitcoin_wallets.on(network)?;
+
+        comit::bitcoin::median_time_past(wallet.connector.as_ref()).await
+    }
+}
+
+/// Refunds every HTLC that is due according to `clock`, checking again every
+/// `poll_interval`. The `running` swaps are skipped.
+pub async fn watch<R, C>(
+    db: Arc<Database>,
+    refunder: R,
+    running: Running,
+    clock: C,
+    poll_interval: Duration,
+) where
+    R: ExecuteRefund,
+    C: Clock,
+{
+    loop {
+        if let Err(e) = refund_expired(&db, &refunder, &running, clock.timestamp()).await {
+            tracing::warn!("failed to refund expired swaps: {:#}", e);
+        }
+
+        tokio::time::delay_for(poll_interval).await;
+    }
+}
+
+/// Refunds the HTLCs we funded whose expiry passed at `now`. `hbit` HTLCs
+/// are only refunded once the median time past of their chain passed their
+/// expiry too, as the refund transaction is rejected before.
+///
+/// HTLCs that were already redeemed or refunded are left alone, so are the
+/// `running` swaps.
+pub async fn refund_expired<R>(
+    db: &Database,
+    refunder: &R,
+    running: &Running,
+    now: Timestamp,
+) -> Result<()>
+where
+    R: ExecuteRefund,
+{
+    for (swap_id, refund) in due_refunds(db, running, now)? {
+        if let Refund::Hbit { params, .. } = &refund {
+            match refunder.median_time_past(params.shared.network).await {
+                Ok(median_time_past) if median_time_past < params.shared.expiry => continue,
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        "failed to get the median time past for swap {}: {:#}",
+                        swap_id,
+                        e
+                    );
+                    continue;
+                }
+            }
+        }
+
+        tracing::info!("refunding expired swap {}", swap_id);
+
+        if let Err(e) = refunder.execute_refund(swap_id, refund).await {
+            tracing::warn!("failed to refund swap {}: {:#}", swap_id, e);
+        }
+    }
+
+    Ok(())
+}
+
+fn due_refunds(db: &Database, running: &Running, now: Timestamp) -> Result<Vec<(SwapId, Refund)>> {
+    let mut refunds = Vec::new();
+
+    for swap in db.all_active_swaps()? {
+        let swap_id = swap.swap_id();
+        if running.contains(&swap_id) {
+            continue;
+        }
+
+        // As Bob we fund the beta ledger
+        let refund = match swap {
+            SwapKind::HbitHerc20(params) => herc20_refund(db, swap_id, params.herc20_params, now)?,
+            SwapKind::Herc20Hbit(params) => {
+                hbit_refund(db, swap_id, params.hbit_params, hbit::Htlc::Primary, now)?
+            }
+            SwapKind::HbitHbit(params) => {
+                hbit_refund(db, swap_id, params.beta_params, hbit::Htlc::Beta, now)?
+            }
+        };
+
+        if let Some(refund) = refund {
+            refunds.push((swap_id, refund));
+        }
+    }
+
+    Ok(refunds)
+}
+
+fn hbit_refund(
+    db: &Database,
+    swap_id: SwapId,
+    params: hbit::Params,
+    htlc: hbit::Htlc,
+    now: Timestamp,
+) -> Result<Option<Refund>> {
+    if now < params.shared.expiry {
+        return Ok(None);
+    }
+
+    let funded = match hbit::load::<hbit::Funded>(db, swap_id, htlc)? {
+        Some(funded) => funded,
+        None => return Ok(None),
+    };
+    let redeemed = hbit::load::<hbit::Redeemed>(db, swap_id, htlc)?;
+    let refunded = hbit::load::<hbit::Refunded>(db, swap_id, htlc)?;
+
+    if redeemed.is_some() || refunded.is_some() {
+        return Ok(None);
+    }
+
+    Ok(Some(Refund::Hbit {
+        params,
+        funded,
+        htlc,
+    }))
+}
+
+fn herc20_refund(
+    db: &Database,
+    swap_id: SwapId,
+    params: herc20::Params,
+    now: Timestamp,
+) -> Result<Option<Refund>> {
+    if now < params.expiry {
+        return Ok(None);
+    }
+
+    let deployed = match Load::<herc20::Deployed>::load(db, swap_id)? {
+        Some(deployed) => deployed,
+        None => return Ok(None),
+    };
+    let funded = Load::<herc20::Funded>::load(db, swap_id)?;
+    let redeemed = Load::<herc20::Redeemed>::load(db, swap_id)?;
+    let refunded = Load::<herc20::Refunded>::load(db, swap_id)?;
+
+    if funded.is_none() || redeemed.is_some() || refunded.is_some() {
+        return Ok(None);
+    }
+
+    Ok(Some(Refund::Herc20 { params, deployed }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{swap::SwapParams, StaticStub};
+    use ::bitcoin::hashes::Hash;
+    use comit::clock::TestClock;
+    use time::OffsetDateTime;
+
+    /// Records the refunds instead of executing them.
+    struct MockRefunder {
+        db: Arc<Database>,
+        refunds: Arc<Mutex<Vec<SwapId>>>,
+        median_time_past: Arc<Mutex<Timestamp>>,
+    }
+
+    impl MockRefunder {
+        fn new(db: Arc<Database>, median_time_past: Timestamp) -> Self {
+            Self {
+                db,
+                refunds: Arc::new(Mutex::new(Vec::new())),
+                median_time_past: Arc::new(Mutex::new(median_time_past)),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ExecuteRefund for MockRefunder {
+        async fn execute_refund(&self, swap_id: SwapId, refund: Refund) -> Result<()> {
+            self.refunds.lock().unwrap().push(swap_id);
+
+            match refund {
+                Refund::Hbit { htlc, .. } => {
+                    let refunded = hbit::Refunded {
+                        transaction: ::bitcoin::Txid::from_inner([1u8; 32]),
+                    };
+                    hbit::save(&self.db, refunded, swap_id, htlc).await
+                }
+                Refund::Herc20 { .. } => panic!("we funded the hbit HTLC"),
+            }
+        }
+
+        async fn median_time_past(&self, _: ledger::Bitcoin) -> Result<Timestamp> {
+            Ok(*self.median_time_past.lock().unwrap())
+        }
+    }
+
+    /// A swap in which we funded the hbit HTLC that expires at the returned
+    /// timestamp.
+    async fn funded_swap(db: &Database) -> (SwapId, Timestamp) {
+        let params = SwapParams::static_stub();
+        let swap_id = params.swap_id;
+        let expiry = params.hbit_params.shared.expiry;
+        db.insert_swap(SwapKind::Herc20Hbit(params)).await.unwrap();
+        db.save(
+            hbit::Funded {
+                location: ::bitcoin::OutPoint::default(),
+            },
+            swap_id,
+        )
+        .await
+        .unwrap();
+
+        (swap_id, expiry)
+    }
+
+    #[tokio::test]
+    async fn refunds_exactly_once_after_expiry() {
+        let db = Arc::new(Database::new_test().unwrap());
+        let (swap_id, expiry) = funded_swap(&db).await;
+        let refunder = MockRefunder::new(db.clone(), expiry);
+        let running = Running::default();
+
+        refund_expired(&db, &refunder, &running, expiry.minus(1))
+            .await
+            .unwrap();
+        assert!(refunder.refunds.lock().unwrap().is_empty());
+
+        refund_expired(&db, &refunder, &running, expiry.plus(1))
+            .await
+            .unwrap();
+        refund_expired(&db, &refunder, &running, expiry.plus(2))
+            .await
+            .unwrap();
+
+        assert_eq!(*refunder.refunds.lock().unwrap(), vec![swap_id]);
+    }
+
+    #[tokio::test]
+    async fn does_not_refund_before_median_time_past_passes_expiry() {
+        let db = Arc::new(Database::new_test().unwrap());
+        let (swap_id, expiry) = funded_swap(&db).await;
+        let refunder = MockRefunder::new(db.clone(), expiry.minus(1));
+        let running = Running::default();
+
+        refund_expired(&db, &refunder, &running, expiry.plus(1))
+            .await
+            .unwrap();
+        assert!(refunder.refunds.lock().unwrap().is_empty());
+
+        *refunder.median_time_past.lock().unwrap() = expiry;
+        refund_expired(&db, &refunder, &running, expiry.plus(1))
+            .await
+            .unwrap();
+
+        assert_eq!(*refunder.refunds.lock().unwrap(), vec![swap_id]);
+    }
+
+    #[tokio::test]
+    async fn leaves_running_swaps_to_their_execution() {
+        let db = Arc::new(Database::new_test().unwrap());
+        let (swap_id, expiry) = funded_swap(&db).await;
+        let refunder = MockRefunder::new(db.clone(), expiry);
+        let running = Running::default();
+
+        running.insert(swap_id);
+        refund_expired(&db, &refunder, &running, expiry.plus(1))
+            .await
+            .unwrap();
+        assert!(refunder.refunds.lock().unwrap().is_empty());
+
+        running.remove(&swap_id);
+        refund_expired(&db, &refunder, &running, expiry.plus(1))
+            .await
+            .unwrap();
+
+        assert_eq!(*refunder.refunds.lock().unwrap(), vec![swap_id]);
+    }
+
+    #[tokio::test]
+    async fn watcher_refunds_once_clock_passes_expiry() {
+        tokio::time::pause();
+        let db = Arc::new(Database::new_test().unwrap());
+        let (swap_id, expiry) = funded_swap(&db).await;
+        let refunder = MockRefunder::new(db.clone(), expiry);
+        let refunds = refunder.refunds.clone();
+        let clock = TestClock::new(OffsetDateTime::from_unix_timestamp(expiry.minus(10).into()));
+
+        tokio::spawn(watch(
+            db,
+            refunder,
+            Running::default(),
+            clock.clone(),
+            POLL_INTERVAL,
+        ));
+        tokio::task::yield_now().await;
+        assert!(refunds.lock().unwrap().is_empty());
+
+        clock.advance(time::Duration::seconds(10));
+        tokio::time::advance(POLL_INTERVAL).await;
+        tokio::task::yield_now().await;
+
+        assert_eq!(*refunds.lock().unwrap(), vec![swap_id]);
+    }
+}
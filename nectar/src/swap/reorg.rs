@@ -0,0 +1,151 @@
+//! Forget the herc20 events whose transaction a reorg orphaned.
+//!
+//! Otherwise the database keeps reporting the HTLC as deployed or funded
+//! although it no longer is on the chain. The execution of the swap is told
+//! about it so that it observes the ledger again.
+
+use crate::{
+    database::Herc20EventKind,
+    swap::{Database, SwapKind},
+    SwapId,
+};
+use anyhow::Result;
+use comit::{
+    btsieve::{BlockByHash, LatestBlock},
+    ethereum::{Block, Hash},
+};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::broadcast::{self, RecvError};
+
+/// How often we check the stored events against the chain.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Swaps whose events were orphaned, broadcast to their executions.
+#[derive(Debug, Clone)]
+pub struct Orphans(broadcast::Sender<SwapId>);
+
+impl Default for Orphans {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(16);
+
+        Self(sender)
+    }
+}
+
+impl Orphans {
+    /// Follows the orphaned events of the given swap.
+    pub fn watch(&self, swap_id: SwapId) -> Orphaned {
+        Orphaned {
+            receiver: self.0.subscribe(),
+            swap_id,
+        }
+    }
+
+    fn notify(&self, swap_id: SwapId) {
+        // no execution may be listening, e.g. while resuming swaps
+        let _ = self.0.send(swap_id);
+    }
+}
+
+#[derive(Debug)]
+pub struct Orphaned {
+    receiver: broadcast::Receiver<SwapId>,
+    swap_id: SwapId,
+}
+
+impl Orphaned {
+    /// Resolves once a reorg orphaned one of the events of the swap.
+    pub async fn recv(&mut self) {
+        loop {
+            match self.receiver.recv().await {
+                Ok(swap_id) if swap_id == self.swap_id => return,
+                Ok(_) => {}
+                // we may have missed our swap, executing it again is harmless
+                Err(RecvError::Lagged(_)) => return,
+                Err(RecvError::Closed) => futures::future::pending().await,
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("a reorg orphaned events of swap {0}")]
+pub struct EventsOrphaned(pub SwapId);
+
+/// Clears orphaned events, checking again every `poll_interval`.
+pub async fn watch<C>(
+    db: Arc<Database>,
+    connector: Arc<C>,
+    orphans: Orphans,
+    poll_interval: Duration,
+) where
+    C: LatestBlock<Block = Block> + BlockByHash<Block = Block, BlockHash = Hash>,
+{
+    loop {
+        if let Err(e) = invalidate_orphaned_events(&db, connector.as_ref(), &orphans).await {
+            tracing::warn!("failed to check swaps for orphaned events: {:#}", e);
+        }
+
+        tokio::time::delay_for(poll_interval).await;
+    }
+}
+
+/// Clears the deployed and funded events of all active herc20 swaps whose
+/// transaction is no longer part of the chain and notifies their executions.
+pub async fn invalidate_orphaned_events<C>(
+    db: &Database,
+    connector: &C,
+    orphans: &Orphans,
+) -> Result<()>
+where
+    C: LatestBlock<Block = Block> + BlockByHash<Block = Block, BlockHash = Hash>,
+{
+    for swap in db.all_active_swaps()? {
+        let swap_id = swap.swap_id();
+
+        if let SwapKind::HbitHbit(_) = swap {
+            continue;
+        }
+
+        let mut orphaned = false;
+        for kind in &[Herc20EventKind::Funded, Herc20EventKind::Deployed] {
+            if db
+                .invalidate_event_if_orphaned(connector, swap_id, *kind)
+                .await?
+            {
+                tracing::warn!(
+                    "herc20 {:?} event of swap {} was orphaned by a reorg",
+                    kind,
+                    swap_id
+                );
+                orphaned = true;
+            }
+        }
+
+        if orphaned {
+            orphans.notify(swap_id);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn execution_is_only_told_about_its_own_swap() {
+        let orphans = Orphans::default();
+        let swap_id = SwapId::default();
+        let mut orphaned = orphans.watch(swap_id);
+
+        orphans.notify(SwapId::default());
+        let other_swap = tokio::time::timeout(Duration::from_millis(100), orphaned.recv()).await;
+        orphans.notify(swap_id);
+        let own_swap = tokio::time::timeout(Duration::from_millis(100), orphaned.recv()).await;
+
+        assert!(other_swap.is_err());
+        assert!(own_swap.is_ok());
+    }
+}
@@ -0,0 +1,173 @@
+//! Coordinate shutting down while swap actions are in flight.
+
+use anyhow::Result;
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Lets in-flight swap actions complete before shutting down.
+///
+/// Once a shutdown was initiated no new action is started. Actions that are
+/// already in flight are awaited, including persisting their outcome, so
+/// that a swap resumed on restart does not execute them a second time.
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown {
+    initiated: Arc<AtomicBool>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("shutting down, not starting any new actions")]
+pub struct ShuttingDown;
+
+impl Shutdown {
+    pub fn is_initiated(&self) -> bool {
+        self.initiated.load(Ordering::SeqCst)
+    }
+
+    /// Executes `action` unless a shutdown was initiated.
+    pub async fn guard<T>(&self, action: impl Future<Output = Result<T>>) -> Result<T> {
+        // Register before checking the flag, otherwise `initiate` could miss
+        // an action that starts right after the flag was set.
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let _in_flight = InFlight(&self.in_flight);
+
+        if self.is_initiated() {
+            return Err(ShuttingDown.into());
+        }
+
+        action.await
+    }
+
+    /// Stops new actions from being started and waits up to `timeout` for
+    /// the ones in flight to complete.
+    ///
+    /// Returns `false` if actions were still in flight when `timeout`
+    /// elapsed.
+    pub async fn initiate(&self, timeout: Duration) -> bool {
+        self.initiated.store(true, Ordering::SeqCst);
+
+        let in_flight_completed = async {
+            while self.in_flight.load(Ordering::SeqCst) > 0 {
+                tokio::time::delay_for(POLL_INTERVAL).await;
+            }
+        };
+
+        tokio::time::timeout(timeout, in_flight_completed)
+            .await
+            .is_ok()
+    }
+}
+
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Resolves once the process is asked to terminate.
+#[cfg(unix)]
+pub async fn signal() -> Result<()> {
+    use futures::future::{self, Either};
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    let terminate = Box::pin(terminate.recv());
+    let interrupt = Box::pin(tokio::signal::ctrl_c());
+
+    if let Either::Right((interrupt, _)) = future::select(terminate, interrupt).await {
+        interrupt?;
+    }
+
+    Ok(())
+}
+
+/// Resolves once the process is asked to terminate.
+#[cfg(not(unix))]
+pub async fn signal() -> Result<()> {
+    tokio::signal::ctrl_c().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn shutdown_waits_for_fund_in_flight() {
+        let shutdown = Shutdown::default();
+        let funded = Arc::new(AtomicBool::new(false));
+
+        let fund = {
+            let shutdown = shutdown.clone();
+            let funded = funded.clone();
+
+            tokio::spawn(async move {
+                shutdown
+                    .guard(async {
+                        tokio::time::delay_for(Duration::from_millis(500)).await;
+                        funded.store(true, Ordering::SeqCst);
+
+                        Ok(())
+                    })
+                    .await
+            })
+        };
+        // let the fund action start
+        tokio::time::delay_for(Duration::from_millis(50)).await;
+
+        let completed = shutdown.initiate(Duration::from_secs(5)).await;
+
+        assert!(completed);
+        assert!(funded.load(Ordering::SeqCst));
+        assert!(fund.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn no_action_is_started_after_shutdown() {
+        let shutdown = Shutdown::default();
+        let executed = AtomicBool::new(false);
+        let _ = shutdown.initiate(Duration::from_secs(1)).await;
+
+        let result = shutdown
+            .guard(async {
+                executed.store(true, Ordering::SeqCst);
+
+                Ok(())
+            })
+            .await;
+
+        assert!(result.unwrap_err().is::<ShuttingDown>());
+        assert!(!executed.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn shutdown_gives_up_after_timeout() {
+        let shutdown = Shutdown::default();
+
+        let _stuck = {
+            let shutdown = shutdown.clone();
+
+            tokio::spawn(async move {
+                shutdown
+                    .guard(futures::future::pending::<Result<()>>())
+                    .await
+            })
+        };
+        tokio::time::delay_for(Duration::from_millis(50)).await;
+
+        let completed = shutdown.initiate(Duration::from_millis(200)).await;
+
+        assert!(!completed);
+    }
+}
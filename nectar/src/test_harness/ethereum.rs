@@ -1,4 +1,4 @@
-use crate::ethereum::{self, ether, Address, ChainId, GasPrice};
+use crate::ethereum::{self, ether, Address, ChainId, GasPrice, GasPriceOracle, Urgency};
 use anyhow::Context;
 use clarity::{PrivateKey, Uint256};
 use comit::{
@@ -133,7 +133,7 @@ impl<'c> Blockchain<'c> {
         ether: ether::Amount,
         chain_id: ChainId,
     ) -> anyhow::Result<()> {
-        let gas_price = self.gas_price.gas_price().await?;
+        let gas_price = ether::Amount::from(self.gas_price.gas_price(Urgency::Normal).await?);
 
         let hash = self
             .dev_account_wallet
@@ -154,7 +154,7 @@ impl<'c> Blockchain<'c> {
         chain_id: ChainId,
     ) -> anyhow::Result<()> {
         let transfer = self.transfer_fn(to, asset.quantity)?;
-        let gas_price = self.gas_price.gas_price().await?;
+        let gas_price = ether::Amount::from(self.gas_price.gas_price(Urgency::Normal).await?);
 
         let hash = self
             .dev_account_wallet
@@ -190,7 +190,7 @@ impl<'c> Blockchain<'c> {
     async fn deploy_token_contract(&mut self) -> anyhow::Result<()> {
         let contract = TOKEN_CONTRACT[2..].trim(); // remove the 0x in the front and any whitespace
         let contract = hex::decode(contract).context("token contract should be valid hex")?;
-        let gas_price = self.gas_price.gas_price().await?;
+        let gas_price = ether::Amount::from(self.gas_price.gas_price(Urgency::Normal).await?);
 
         let hash = self
             .dev_account_wallet
@@ -200,6 +200,8 @@ impl<'c> Blockchain<'c> {
                     amount: Ether::zero(),
                     gas_limit: 1_000_000,
                     chain_id: ChainId::GETH_DEV,
+                    max_fee_per_gas: None,
+                    max_priority_fee_per_gas: None,
                 },
                 gas_price,
             )
@@ -1,28 +1,100 @@
+use crate::config::LogFile;
 use log::LevelFilter;
-use tracing::{info, subscriber};
+use tracing::{info, subscriber, Subscriber};
+use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
 use tracing_log::LogTracer;
-use tracing_subscriber::FmtSubscriber;
+use tracing_subscriber::{fmt, fmt::MakeWriter, layer::SubscriberExt, EnvFilter};
 
-pub fn init_tracing(level: log::LevelFilter) -> anyhow::Result<()> {
+/// Logs to stderr and, if `log_file` is given, to a log file that is rotated
+/// daily.
+///
+/// Log lines are written to the file in the background. The returned guard
+/// flushes them when dropped, hence the caller must keep it alive for as long
+/// as it logs. Otherwise log lines are silently dropped.
+pub fn init_tracing(
+    level: log::LevelFilter,
+    log_file: Option<&LogFile>,
+) -> anyhow::Result<Option<WorkerGuard>> {
     if level == LevelFilter::Off {
-        return Ok(());
+        return Ok(None);
     }
 
     // We want upstream library log messages, just only at Info level.
     LogTracer::init_with_filter(LevelFilter::Info)?;
 
+    let (file_writer, guard) = match log_file {
+        Some(log_file) => {
+            let (writer, guard) = rolling_file(log_file);
+            (Some(writer), Some(guard))
+        }
+        None => (None, None),
+    };
+
     let is_terminal = atty::is(atty::Stream::Stderr);
-    let subscriber = FmtSubscriber::builder()
-        .with_env_filter(format!(
-            "nectar={},comit={},http=info,warp=info",
-            level, level
-        ))
-        .with_writer(std::io::stderr)
-        .with_ansi(is_terminal)
-        .finish();
-
-    subscriber::set_global_default(subscriber)?;
+    subscriber::set_global_default(layered(level, is_terminal, file_writer))?;
     info!("Initialized tracing with level: {}", level);
 
-    Ok(())
+    Ok(guard)
+}
+
+fn rolling_file(log_file: &LogFile) -> (NonBlocking, WorkerGuard) {
+    let appender = tracing_appender::rolling::daily(&log_file.dir, &log_file.prefix);
+
+    tracing_appender::non_blocking(appender)
+}
+
+fn layered<W>(
+    level: LevelFilter,
+    ansi: bool,
+    file_writer: Option<W>,
+) -> impl Subscriber + Send + Sync
+where
+    W: MakeWriter + Send + Sync + 'static,
+{
+    let filter = EnvFilter::new(format!(
+        "nectar={},comit={},http=info,warp=info",
+        level, level
+    ));
+    let stderr = fmt::layer().with_writer(std::io::stderr).with_ansi(ansi);
+    let file = file_writer.map(|writer| fmt::layer().with_writer(writer).with_ansi(false));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(stderr)
+        .with(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn log_lines_are_written_to_the_log_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_file = LogFile {
+            dir: dir.path().to_path_buf(),
+            prefix: "nectar.log".to_owned(),
+        };
+        let (writer, guard) = rolling_file(&log_file);
+
+        subscriber::with_default(layered(LevelFilter::Info, false, Some(writer)), || {
+            info!("first");
+            info!("second");
+            info!("third");
+        });
+        // flushes the pending log lines
+        drop(guard);
+
+        let files = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(files.len(), 1);
+        let name = files[0].file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("nectar.log"), "{}", name);
+        let content = fs::read_to_string(&files[0]).unwrap();
+        assert_eq!(content.lines().count(), 3);
+        assert!(content.contains("second"));
+    }
 }